@@ -1,6 +1,6 @@
 use buildscale::{
     load_config,
-    models::users::{LoginUser, RegisterUser, UpdateUser},
+    models::users::{LoginOutcome, LoginUser, RegisterUser, UpdateUser},
     queries::users::{
         create_user, delete_user, get_user_by_email, get_user_by_id, list_users, update_user,
     },
@@ -108,7 +108,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         password: "TestSecurePass123!".to_string(),
     };
 
-    let login_result = login_user(&mut conn, login_user_data).await?;
+    let login_result = login_user(&mut conn, login_user_data)
+        .await?
+        .into_login_result()
+        .expect("example users have no two-factor authentication");
     println!("✓ User login successful:");
     println!("  User ID: {}", login_result.user.id);
     println!("  User Email: {}", login_result.user.email);
@@ -617,7 +620,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let login_result = login_user(&mut conn, LoginUser {
             email: alice_user.email.clone(),
             password: "AliceSecurePass456!".to_string(),
-        }).await?;
+        }).await?
+            .into_login_result()
+            .expect("example users have no two-factor authentication");
 
         // Get session info without validation
         match get_session_info(&mut conn, &login_result.refresh_token).await? {
@@ -651,10 +656,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 email: david_user.email.clone(),
                 password: "UPPERCASE123!".to_string(),
             }).await {
-                Ok(login_result) => {
+                Ok(LoginOutcome::Authenticated(login_result)) => {
                     session_tokens.push(login_result.refresh_token);
                     println!("✓ Created session {} for {}", i + 1, david_user.email);
                 }
+                Ok(LoginOutcome::TwoFactorRequired(_)) => {
+                    println!("⚠️  Session {} for {} requires a two-factor code", i + 1, david_user.email);
+                }
                 Err(e) => {
                     println!("⚠️  Session {} creation failed for {}: {}", i + 1, david_user.email, e);
                     // Continue with other sessions instead of crashing
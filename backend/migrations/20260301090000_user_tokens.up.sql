@@ -0,0 +1,26 @@
+-- Track when a user proved ownership of their email address.
+-- NULL means the address has never been verified.
+ALTER TABLE users ADD COLUMN email_verified_at TIMESTAMPTZ;
+
+-- Single-use, expiring tokens for account recovery and email verification.
+-- Only the SHA-256 hash of each token is stored; the raw token is delivered to the user once.
+CREATE TABLE user_tokens (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    purpose TEXT NOT NULL CHECK (purpose IN ('password_reset', 'email_verification')),
+    token_hash TEXT UNIQUE NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Index for fast token lookup on consumption
+CREATE INDEX idx_user_tokens_token_hash ON user_tokens(token_hash);
+
+-- Index for invalidating a user's outstanding tokens of a given purpose
+CREATE INDEX idx_user_tokens_user_purpose ON user_tokens(user_id, purpose);
+
+-- Index for cleanup of expired tokens
+CREATE INDEX idx_user_tokens_expires_at ON user_tokens(expires_at);
+
+COMMENT ON TABLE user_tokens IS 'Hashed single-use tokens for password reset and email verification.';
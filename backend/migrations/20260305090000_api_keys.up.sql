@@ -0,0 +1,47 @@
+-- Non-human principals that belong to a single workspace (CI pipelines, bots, integrations).
+-- Each service account is backed by a password-less user row so that it can hold a
+-- workspace membership and role like any other member.
+CREATE TABLE service_accounts (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    workspace_id UUID NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+    user_id UUID UNIQUE NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(workspace_id, name)
+);
+
+-- Index for listing the service accounts of a workspace
+CREATE INDEX idx_service_accounts_workspace_id ON service_accounts(workspace_id);
+
+-- Long-lived API keys for automation, accepted as an alternative to JWT access tokens.
+-- A key acts as `user_id` (a person or a service account), is limited to one workspace
+-- and to a subset of the principal's permissions there.
+-- Only the SHA-256 hash of the key is stored; `key_prefix` is the public, non-secret
+-- part shown in listings so users can tell their keys apart.
+CREATE TABLE api_keys (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    workspace_id UUID NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    name TEXT NOT NULL,
+    key_prefix TEXT UNIQUE NOT NULL,
+    key_hash TEXT UNIQUE NOT NULL,
+    permissions TEXT[] NOT NULL DEFAULT '{}',
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Index for fast key lookup during authentication
+CREATE INDEX idx_api_keys_key_hash ON api_keys(key_hash);
+
+-- Index for listing a principal's keys within a workspace
+CREATE INDEX idx_api_keys_workspace_user ON api_keys(workspace_id, user_id);
+
+COMMENT ON TABLE service_accounts IS 'Workspace-owned non-human principals backed by password-less users.';
+COMMENT ON TABLE api_keys IS 'Hashed, workspace-scoped API keys for personal and service account automation.';
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS workspace_sso_domains;
+DROP TABLE IF EXISTS user_identities;
+DROP TABLE IF EXISTS oidc_login_states;
@@ -0,0 +1,48 @@
+-- In-flight OpenID Connect logins, keyed by the hash of the `state` parameter.
+-- Rows are consumed by the callback and hold the PKCE verifier and nonce for that attempt.
+CREATE TABLE oidc_login_states (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    state_hash TEXT UNIQUE NOT NULL,
+    code_verifier TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    redirect_to TEXT,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Index for cleanup of abandoned login attempts
+CREATE INDEX idx_oidc_login_states_expires_at ON oidc_login_states(expires_at);
+
+-- External identities linked to local users. A user can have several identities,
+-- but an (issuer, subject) pair always belongs to exactly one user.
+CREATE TABLE user_identities (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    issuer TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    email TEXT,
+    last_login_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (issuer, subject)
+);
+
+CREATE INDEX idx_user_identities_user_id ON user_identities(user_id);
+
+-- Email domains whose users automatically join a workspace with the given role
+-- the first time they sign in with single sign-on.
+CREATE TABLE workspace_sso_domains (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    workspace_id UUID NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+    domain TEXT NOT NULL,
+    role_id UUID NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (workspace_id, domain)
+);
+
+-- Index for matching a signing-in user's email domain
+CREATE INDEX idx_workspace_sso_domains_domain ON workspace_sso_domains(domain);
+
+COMMENT ON TABLE oidc_login_states IS 'Pending OpenID Connect authorization requests (state, PKCE verifier, nonce).';
+COMMENT ON TABLE user_identities IS 'External OpenID Connect identities linked to users.';
+COMMENT ON TABLE workspace_sso_domains IS 'Email domains that auto-join a workspace on single sign-on.';
@@ -0,0 +1,46 @@
+-- TOTP (RFC 6238) authenticator of a user. A row with `enabled_at` NULL is an enrollment
+-- that has not been confirmed with a first valid code yet and is not enforced at login.
+-- `last_used_step` is the time step of the last accepted code so a code cannot be replayed.
+CREATE TABLE user_totp (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    secret TEXT NOT NULL,
+    enabled_at TIMESTAMPTZ,
+    last_used_step BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Single-use recovery codes for users who lost their authenticator.
+-- Only the SHA-256 hash of each code is stored; the codes are shown to the user once.
+CREATE TABLE user_recovery_codes (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash TEXT NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(user_id, code_hash)
+);
+
+-- Short-lived tokens issued after the password step of a login for users with 2FA enabled.
+-- The token is exchanged, together with a TOTP or recovery code, for a session.
+CREATE TABLE two_factor_challenges (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash TEXT UNIQUE NOT NULL,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Index for revoking a user's pending challenges
+CREATE INDEX idx_two_factor_challenges_user_id ON two_factor_challenges(user_id);
+
+-- Index for cleanup of expired challenges
+CREATE INDEX idx_two_factor_challenges_expires_at ON two_factor_challenges(expires_at);
+
+-- Workspace policy: members without 2FA cannot access the workspace
+ALTER TABLE workspaces ADD COLUMN require_two_factor BOOLEAN NOT NULL DEFAULT FALSE;
+
+COMMENT ON TABLE user_totp IS 'TOTP authenticator secrets for two-factor authentication.';
+COMMENT ON TABLE user_recovery_codes IS 'Hashed single-use two-factor recovery codes.';
+COMMENT ON TABLE two_factor_challenges IS 'Hashed short-lived tokens for the second step of a login.';
@@ -0,0 +1,6 @@
+ALTER TABLE revoked_refresh_tokens DROP COLUMN IF EXISTS session_id;
+
+ALTER TABLE user_sessions
+    DROP COLUMN IF EXISTS last_used_at,
+    DROP COLUMN IF EXISTS ip_address,
+    DROP COLUMN IF EXISTS user_agent;
@@ -0,0 +1,14 @@
+-- Device details of login sessions, shown in the session management endpoints
+ALTER TABLE user_sessions
+    ADD COLUMN user_agent TEXT,
+    ADD COLUMN ip_address TEXT,
+    ADD COLUMN last_used_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+-- Session a rotated refresh token belonged to, so access tokens issued during the
+-- grace period stay bound to the same session
+ALTER TABLE revoked_refresh_tokens
+    ADD COLUMN session_id UUID;
+
+COMMENT ON COLUMN user_sessions.user_agent IS 'User-Agent header of the last login or refresh';
+COMMENT ON COLUMN user_sessions.ip_address IS 'Client IP address of the last login or refresh';
+COMMENT ON COLUMN user_sessions.last_used_at IS 'Time of the last login or refresh with this session';
@@ -0,0 +1,25 @@
+-- External tool servers speaking the Model Context Protocol (MCP), registered per workspace.
+-- Their tools are offered to the workspace's chat agents as `mcp__{name}__{tool}`.
+-- `transport` is 'stdio' (a command started by the backend, JSON-RPC over stdin/stdout)
+-- or 'http' (the streamable HTTP transport at `url`).
+-- `env` and `headers` usually hold credentials and are never returned by the API.
+CREATE TABLE mcp_servers (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    workspace_id UUID NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    transport TEXT NOT NULL CHECK (transport IN ('stdio', 'http')),
+    command TEXT,
+    args TEXT[] NOT NULL DEFAULT '{}',
+    env JSONB NOT NULL DEFAULT '{}',
+    url TEXT,
+    headers JSONB NOT NULL DEFAULT '{}',
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    timeout_seconds INTEGER NOT NULL DEFAULT 30 CHECK (timeout_seconds > 0),
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(workspace_id, name),
+    CHECK ((transport = 'stdio' AND command IS NOT NULL) OR (transport = 'http' AND url IS NOT NULL))
+);
+
+COMMENT ON TABLE mcp_servers IS 'Workspace MCP servers whose tools are exposed to chat agents.';
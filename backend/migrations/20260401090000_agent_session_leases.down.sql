@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_agent_sessions_owner_node_id;
+ALTER TABLE agent_sessions DROP COLUMN IF EXISTS owner_node_id;
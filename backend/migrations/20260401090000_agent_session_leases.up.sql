@@ -0,0 +1,10 @@
+-- Chat ownership leases for multi-instance deployments
+--
+-- The backend instance running a chat's actor owns the chat while it keeps
+-- last_heartbeat fresh. Other instances route commands for the chat to the owner
+-- and take the chat over once the heartbeat goes stale.
+ALTER TABLE agent_sessions ADD COLUMN owner_node_id UUID;
+
+CREATE INDEX idx_agent_sessions_owner_node_id ON agent_sessions(owner_node_id);
+
+COMMENT ON COLUMN agent_sessions.owner_node_id IS 'Backend instance running the chat actor; its lease lasts while last_heartbeat is fresh (null when released)';
@@ -0,0 +1,16 @@
+-- Log of the SSE events emitted on each chat's event bus.
+-- The id is the SSE event id: clients reconnecting with `Last-Event-ID` get the
+-- events they missed replayed from here before the live stream resumes.
+-- Rows are pruned after the configured retention; the chat messages remain the
+-- durable record of a conversation.
+CREATE TABLE chat_stream_events (
+    id BIGSERIAL PRIMARY KEY,
+    chat_id UUID NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+    event JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_chat_stream_events_chat_id ON chat_stream_events(chat_id, id);
+CREATE INDEX idx_chat_stream_events_created_at ON chat_stream_events(created_at);
+
+COMMENT ON TABLE chat_stream_events IS 'Recent chat SSE events, replayed to clients resuming a stream with Last-Event-ID.';
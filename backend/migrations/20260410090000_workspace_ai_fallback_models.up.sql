@@ -0,0 +1,5 @@
+-- Ordered models a chat of the workspace falls back to when its model's provider keeps failing,
+-- as "provider:model" identifiers. Empty: no fallback.
+ALTER TABLE workspaces ADD COLUMN ai_fallback_models TEXT[] NOT NULL DEFAULT '{}';
+
+COMMENT ON COLUMN workspaces.ai_fallback_models IS 'Ordered fallback chain of chat models, used when the chat model''s provider fails.';
@@ -0,0 +1,12 @@
+-- Tool approval policy of a workspace: ordered rules matched against each tool call a chat
+-- agent makes, before it runs. The first rule matching the tool name and argument patterns
+-- decides whether the call runs ('allow'), waits for the user's approval ('ask') or is
+-- refused ('deny'). Calls no rule matches run.
+CREATE TABLE workspace_tool_policies (
+    workspace_id UUID PRIMARY KEY REFERENCES workspaces(id) ON DELETE CASCADE,
+    rules JSONB NOT NULL DEFAULT '[]',
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE workspace_tool_policies IS 'Per-workspace rules requiring approval for, or refusing, tool calls of chat agents.';
@@ -0,0 +1 @@
+ALTER TABLE ai_models DROP COLUMN IF EXISTS supports_vision;
@@ -0,0 +1,21 @@
+-- Whether a model accepts images. Images attached to chats are only sent to these models.
+ALTER TABLE ai_models ADD COLUMN supports_vision BOOLEAN NOT NULL DEFAULT false;
+
+COMMENT ON COLUMN ai_models.supports_vision IS 'Whether the model accepts image input.';
+
+UPDATE ai_models SET supports_vision = true
+WHERE (provider, model_name) IN (
+    ('openai', 'gpt-4o'),
+    ('openai', 'gpt-4o-mini'),
+    ('openai', 'gpt-5-mini'),
+    ('openrouter', 'google/gemini-2.5-flash'),
+    ('openrouter', 'google/gemini-2.5-flash-lite'),
+    ('openrouter', 'google/gemini-3-flash-preview'),
+    ('openrouter', 'google/gemma-3-27b-it:free'),
+    ('openrouter', 'mistralai/ministral-14b-2512'),
+    ('openrouter', 'moonshotai/kimi-k2.5'),
+    ('openrouter', 'qwen/qwen3-vl-30b-a3b-instruct'),
+    ('openrouter', 'x-ai/grok-4-fast'),
+    ('openrouter', 'x-ai/grok-4.1-fast'),
+    ('openrouter', 'z-ai/glm-4.6v')
+);
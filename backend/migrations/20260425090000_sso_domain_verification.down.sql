@@ -0,0 +1,2 @@
+ALTER TABLE workspace_sso_domains DROP COLUMN IF EXISTS verified_at;
+ALTER TABLE workspace_sso_domains DROP COLUMN IF EXISTS verification_token;
@@ -0,0 +1,11 @@
+-- SSO domains only take effect once the workspace proved it controls the domain, by
+-- publishing the verification token in a DNS TXT record. Domains registered before
+-- this migration have to be verified again.
+ALTER TABLE workspace_sso_domains ADD COLUMN verification_token TEXT;
+ALTER TABLE workspace_sso_domains ADD COLUMN verified_at TIMESTAMPTZ;
+
+UPDATE workspace_sso_domains SET verification_token = encode(sha256(gen_random_uuid()::text::bytea), 'hex');
+ALTER TABLE workspace_sso_domains ALTER COLUMN verification_token SET NOT NULL;
+
+COMMENT ON COLUMN workspace_sso_domains.verification_token IS 'Token the domain owner publishes in a DNS TXT record.';
+COMMENT ON COLUMN workspace_sso_domains.verified_at IS 'When the TXT record was found; unverified domains are ignored.';
@@ -233,7 +233,7 @@ You have access to web tools for fetching and searching web content.
 
 **Available Tools:**
 - `web_fetch` - Fetch content from URLs, converts to markdown by default
-- `web_search` - Search the web using DuckDuckGo
+- `web_search` - Search the web
 
 **When to use:**
 - **Research tasks** → Use `web_search` to find relevant sources, then `web_fetch` for detailed content
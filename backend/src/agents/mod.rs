@@ -2,6 +2,7 @@ pub mod common;
 pub mod assistant;
 pub mod planner;
 pub mod builder;
+pub mod delegate;
 
 /// Central registry for agent personas.
 ///
@@ -9,7 +10,7 @@ pub mod builder;
 /// default Assistant persona if the role is not recognized or provided.
 ///
 /// # Arguments
-/// * `role` - Optional role identifier (e.g., "planner", "builder")
+/// * `role` - Optional role identifier (e.g., "planner", "builder", "delegate")
 /// * `mode` - Optional chat mode ("plan" or "build") to auto-select persona
 /// * `plan_content` - Optional plan content for Build Mode (required when role="builder")
 ///
@@ -41,6 +42,7 @@ pub fn get_persona(role: Option<&str>, mode: Option<&str>, plan_content: Option<
             builder::get_system_prompt(plan)
         }
         Some("planner") => planner::get_system_prompt(),
+        Some("delegate") => delegate::get_system_prompt(),
         None => {
             // Mode-based selection
             match mode {
@@ -92,7 +92,7 @@ This is HOW you know the user clicked a button. When you see "[Answered: "Accept
 - `memory_delete` - Delete a memory (soft delete, recoverable from trash)
 - `memory_list` - List categories/tags efficiently (use for overviews, NOT for finding content)
 - `web_fetch` - Fetch content from URLs, converts to markdown by default. Use for reading docs, API responses.
-- `web_search` - Search the web (default: DuckDuckGo instant answers). Use for research, finding information.
+- `web_search` - Search the web. Use for research, finding information.
 
 ### PLAN FILE CREATION WITH plan_write (RECOMMENDED)
 Use the `plan_write` tool for creating plan files. It automatically:
@@ -132,8 +132,8 @@ Implementation Plan: [Title]
 [Proposed solution with technical rationale]
 
 ## Step-by-Step Plan
-1. [First step with specific file changes]
-2. [Second step]
+- [ ] [First step with specific file changes]
+- [ ] [Second step]
 ...
 
 ## Risk Assessment
@@ -142,6 +142,8 @@ Implementation Plan: [Title]
 ## Success Criteria
 [How to verify the implementation is complete]
 
+Write the steps as markdown task items (`- [ ] step`). Build Mode tracks progress by checking them off, so keep each step a single, verifiable unit of work.
+
 ### IMPORTANT NOTES
 - **Stay in Plan Mode** until the user explicitly approves your plan
 - **Be Thorough**: Explore all relevant code before writing your plan
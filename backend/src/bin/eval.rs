@@ -0,0 +1,102 @@
+//! Runs agent eval scenarios and prints a scored report
+//!
+//! ```text
+//! cargo run --bin eval -- <scenario file or directory>... [options]
+//!
+//!   --model <model>        Model to run, repeatable (default: each scenario's model);
+//!                          `mock` replays the fixture named after each scenario
+//!   --label <name>         Name of the run in the report, e.g. the prompt revision
+//!   --output <file>        Writes the report as JSON
+//!   --baseline <file>      Compares the scores with an earlier JSON report
+//!   --keep-workspaces      Keeps the scenario workspaces for inspection
+//! ```
+//!
+//! Uses the same configuration as the server. Exits with status 1 when a scenario fails.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use buildscale::services::chat::rig_engine::RigService;
+use buildscale::services::eval::{EvalReport, EvalRunner, Scenario};
+use buildscale::services::storage::FileStorageService;
+use buildscale::{init_tracing, load_config, DbPool};
+use secrecy::ExposeSecret;
+
+const USAGE: &str = "Usage: eval <scenario file or directory>... [--model <model>]... [--label <name>] [--output <file>] [--baseline <file>] [--keep-workspaces]";
+
+#[derive(Default)]
+struct Args {
+    paths: Vec<PathBuf>,
+    models: Vec<String>,
+    label: Option<String>,
+    output: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+    keep_workspaces: bool,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut argv = std::env::args().skip(1);
+
+    while let Some(arg) = argv.next() {
+        let mut value = || argv.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--model" => args.models.push(value()?),
+            "--label" => args.label = Some(value()?),
+            "--output" => args.output = Some(value()?.into()),
+            "--baseline" => args.baseline = Some(value()?.into()),
+            "--keep-workspaces" => args.keep_workspaces = true,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}\n{}", flag, USAGE)),
+            path => args.paths.push(path.into()),
+        }
+    }
+
+    if args.paths.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    Ok(args)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+    init_tracing();
+
+    let mut scenarios = Vec::new();
+    for path in &args.paths {
+        scenarios.extend(Scenario::load_all(path)?);
+    }
+    let baseline: Option<EvalReport> = match &args.baseline {
+        Some(path) => Some(serde_json::from_str(&std::fs::read_to_string(path)?)?),
+        None => None,
+    };
+
+    let config = load_config()?;
+    let pool = DbPool::connect(config.database.connection_string().expose_secret()).await?;
+    let storage = Arc::new(FileStorageService::new(&config.storage.base_path));
+    let rig_service = Arc::new(RigService::from_config(&config.ai)?);
+
+    let runner = EvalRunner::new(pool, storage, rig_service, config.ai.default_context_token_limit)
+        .keep_workspaces(args.keep_workspaces);
+    let report = runner.run(&scenarios, &args.models, args.label).await;
+
+    if let Some(output) = &args.output {
+        std::fs::write(output, serde_json::to_string_pretty(&report)?)?;
+    }
+    println!("{}", report.to_markdown());
+    if let Some(baseline) = &baseline {
+        println!("{}", report.compare_markdown(baseline));
+    }
+
+    if !report.all_passed() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -80,6 +80,10 @@ pub enum Error {
     #[error("Token theft detected: {0}")]
     TokenTheftDetected(String),
 
+    /// The workspace requires two-factor authentication, which the user has not enabled.
+    #[error("Two-factor authentication required: {0}")]
+    TwoFactorRequired(String),
+
     /// An internal server error.
     #[error("Internal error: {0}")]
     Internal(String),
@@ -127,6 +131,10 @@ pub enum Error {
     /// Model disabled.
     #[error("Model '{0}' is disabled")]
     ModelDisabled(String),
+
+    /// An MCP server could not be reached or failed.
+    #[error("MCP server error: {0}")]
+    Mcp(String),
 }
 
 /// A type alias for `Result<T, Error>` to simplify function signatures.
@@ -206,6 +214,10 @@ impl IntoResponse for Error {
             Error::TokenTheftDetected(msg) => {
                 (create_error_body(msg, "TOKEN_THEFT"), StatusCode::FORBIDDEN)
             }
+            Error::TwoFactorRequired(msg) => (
+                create_error_body(msg, "TWO_FACTOR_REQUIRED"),
+                StatusCode::FORBIDDEN,
+            ),
             Error::Sqlx(_) => (
                 create_error_body("Database error".to_string(), "INTERNAL_ERROR"),
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -262,6 +274,10 @@ impl IntoResponse for Error {
                 create_error_body(format!("Model '{}' is disabled", model), "MODEL_DISABLED"),
                 StatusCode::FORBIDDEN,
             ),
+            Error::Mcp(msg) => (
+                create_error_body(msg, "MCP_ERROR"),
+                StatusCode::BAD_GATEWAY,
+            ),
         };
 
         (status, Json(body)).into_response()
@@ -278,10 +294,12 @@ impl Error {
             Error::Conflict(_) => 409,
             Error::Authentication(_) | Error::InvalidToken(_) | Error::SessionExpired(_) => 401,
             Error::TokenTheftDetected(_) => 403,
+            Error::TwoFactorRequired(_) => 403,
             Error::Json(_) => 400,
             Error::InvalidModelFormat(_) => 400,
             Error::ModelNotSupported(_, _) => 400,
             Error::ModelDisabled(_) => 403,
+            Error::Mcp(_) => 502,
             _ => 500,
         }
     }
@@ -297,6 +315,7 @@ impl Error {
             Error::InvalidToken(_) => "INVALID_TOKEN",
             Error::SessionExpired(_) => "SESSION_EXPIRED",
             Error::TokenTheftDetected(_) => "TOKEN_THEFT",
+            Error::TwoFactorRequired(_) => "TWO_FACTOR_REQUIRED",
             Error::Sqlx(_) => "INTERNAL_ERROR",
             Error::Internal(_) => "INTERNAL_ERROR",
             Error::Config(_) => "CONFIG_ERROR",
@@ -311,6 +330,7 @@ impl Error {
             Error::ModelNotSupported(_, _) => "MODEL_NOT_SUPPORTED",
             Error::ApiKeyMissing(_) => "API_KEY_MISSING",
             Error::ModelDisabled(_) => "MODEL_DISABLED",
+            Error::Mcp(_) => "MCP_ERROR",
         }
     }
 }
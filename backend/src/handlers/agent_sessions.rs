@@ -15,6 +15,7 @@ use crate::{
     middleware::workspace_access::WorkspaceAccess,
     models::agent_session::{PauseSessionRequest, SessionActionResponse},
     services::agent_sessions,
+    services::chat::cluster::RemoteCommand,
     state::AppState,
 };
 
@@ -205,6 +206,16 @@ pub async fn pause_session(
                 responder_rx
             ).await;
         }
+    } else if let Some(owner) = state.agents.remote_owner(&mut conn, session.chat_id).await? {
+        tracing::info!(
+            operation = "pause_session",
+            chat_id = %session.chat_id,
+            owner = %owner,
+            "ChatActor runs on another instance, forwarding Pause command"
+        );
+        state.agents.send_remote_command(owner, session.chat_id, RemoteCommand::Pause {
+            reason: request.reason.clone(),
+        }).await?;
     } else {
         tracing::debug!(
             operation = "pause_session",
@@ -363,6 +374,16 @@ pub async fn cancel_session(
             // Remove the actor from the registry
             state.agents.remove(&session.chat_id).await;
         }
+    } else if let Some(owner) = state.agents.remote_owner(&mut conn, session.chat_id).await? {
+        tracing::info!(
+            operation = "cancel_session",
+            chat_id = %session.chat_id,
+            owner = %owner,
+            "ChatActor runs on another instance, forwarding Cancel command"
+        );
+        state.agents.send_remote_command(owner, session.chat_id, RemoteCommand::Cancel {
+            reason: "Session cancelled by user".to_string(),
+        }).await?;
     } else {
         tracing::debug!(
             operation = "cancel_session",
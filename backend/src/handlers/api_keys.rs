@@ -0,0 +1,310 @@
+//! API key and service account handlers
+//!
+//! This module provides HTTP handlers for managing workspace-scoped API keys and
+//! service accounts. Handlers follow the thin-layer pattern: they validate inputs,
+//! delegate to services, and return responses.
+//!
+//! Keys can only be managed with a regular (JWT) session; a request authenticated
+//! with an API key cannot mint or revoke keys.
+
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use uuid::Uuid;
+use crate::{
+    error::{Error, Result},
+    middleware::auth::AuthenticatedUser,
+    middleware::workspace_access::WorkspaceAccess,
+    models::api_keys::{CreateApiKeyRequest, CreateServiceAccountRequest},
+    services::api_keys,
+    state::AppState,
+};
+
+// ============================================================================
+// PERSONAL API KEYS
+// ============================================================================
+
+/// GET /api/v1/workspaces/:id/api-keys
+///
+/// Lists the caller's personal API keys in a workspace, including revoked ones.
+/// Secrets are never returned; keys are identified by their `key_prefix`.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Keys retrieved successfully
+/// - `403 FORBIDDEN`: Not a workspace member, or called with an API key
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    Extension(workspace_access): Extension<WorkspaceAccess>,
+    Path(workspace_id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key_auth(&workspace_access)?;
+
+    let mut conn = acquire_db_connection(&state, "list_api_keys").await?;
+
+    let keys = api_keys::list_api_keys(&mut conn, workspace_id, auth_user.id)
+        .await
+        .inspect_err(|e| log_handler_error("list_api_keys", e))?;
+
+    Ok(Json(serde_json::json!({
+        "api_keys": keys,
+        "count": keys.len(),
+    })))
+}
+
+/// POST /api/v1/workspaces/:id/api-keys
+///
+/// Creates a personal API key acting as the caller in this workspace.
+///
+/// # Request Body
+/// ```json
+/// {
+///   "name": "ci-deploy",
+///   "permissions": ["workspace:read", "workspace:write"],
+///   "expires_at": "2026-12-31T00:00:00Z"
+/// }
+/// ```
+///
+/// # Returns
+/// The key metadata plus the secret `key`, which is shown only once.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Key created successfully
+/// - `400 BAD_REQUEST`: Invalid name, permission or expiry
+/// - `403 FORBIDDEN`: Permission not held by the caller, or called with an API key
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    Extension(workspace_access): Extension<WorkspaceAccess>,
+    Path(workspace_id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key_auth(&workspace_access)?;
+
+    let mut conn = acquire_db_connection(&state, "create_api_key").await?;
+
+    let created = api_keys::create_api_key(&mut conn, workspace_id, auth_user.id, request)
+        .await
+        .inspect_err(|e| log_handler_error("create_api_key", e))?;
+
+    Ok(Json(serde_json::json!({
+        "api_key": created.api_key,
+        "key": created.key,
+    })))
+}
+
+/// DELETE /api/v1/workspaces/:id/api-keys/:key_id
+///
+/// Revokes an API key. Callers can revoke their own keys; members with
+/// `workspace:manage_members` can revoke any key in the workspace.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Key revoked
+/// - `403 FORBIDDEN`: Insufficient permissions, or called with an API key
+/// - `404 NOT_FOUND`: Key not found in this workspace
+/// - `409 CONFLICT`: Key already revoked
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Extension(workspace_access): Extension<WorkspaceAccess>,
+    Path((workspace_id, key_id)): Path<(Uuid, Uuid)>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key_auth(&workspace_access)?;
+
+    let mut conn = acquire_db_connection(&state, "revoke_api_key").await?;
+
+    let revoked = api_keys::revoke_api_key(&mut conn, workspace_id, key_id, auth_user.id)
+        .await
+        .inspect_err(|e| log_handler_error("revoke_api_key", e))?;
+
+    Ok(Json(serde_json::json!({
+        "api_key": revoked,
+    })))
+}
+
+// ============================================================================
+// SERVICE ACCOUNTS
+// ============================================================================
+
+/// GET /api/v1/workspaces/:id/service-accounts
+///
+/// Lists the workspace's service accounts. Requires `workspace:manage_members`.
+pub async fn list_service_accounts(
+    State(state): State<AppState>,
+    Extension(workspace_access): Extension<WorkspaceAccess>,
+    Path(workspace_id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key_auth(&workspace_access)?;
+
+    let mut conn = acquire_db_connection(&state, "list_service_accounts").await?;
+
+    let accounts = api_keys::list_service_accounts(&mut conn, workspace_id, auth_user.id)
+        .await
+        .inspect_err(|e| log_handler_error("list_service_accounts", e))?;
+
+    Ok(Json(serde_json::json!({
+        "service_accounts": accounts,
+        "count": accounts.len(),
+    })))
+}
+
+/// POST /api/v1/workspaces/:id/service-accounts
+///
+/// Creates a service account with the given workspace role.
+/// Requires `workspace:manage_members`.
+///
+/// # Request Body
+/// ```json
+/// { "name": "github-actions", "description": "Deploy pipeline", "role_name": "editor" }
+/// ```
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Service account created
+/// - `403 FORBIDDEN`: Insufficient permissions, or called with an API key
+/// - `404 NOT_FOUND`: Role not found
+/// - `409 CONFLICT`: Name already used in this workspace
+pub async fn create_service_account(
+    State(state): State<AppState>,
+    Extension(workspace_access): Extension<WorkspaceAccess>,
+    Path(workspace_id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    Json(request): Json<CreateServiceAccountRequest>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key_auth(&workspace_access)?;
+
+    let mut conn = acquire_db_connection(&state, "create_service_account").await?;
+
+    let account = api_keys::create_service_account(&mut conn, workspace_id, auth_user.id, request)
+        .await
+        .inspect_err(|e| log_handler_error("create_service_account", e))?;
+
+    tracing::info!(
+        operation = "create_service_account",
+        workspace_id = %workspace_id,
+        service_account_id = %account.id,
+        role = %account.role_name,
+        "Service account created",
+    );
+
+    Ok(Json(serde_json::json!({
+        "service_account": account,
+    })))
+}
+
+/// DELETE /api/v1/workspaces/:id/service-accounts/:service_account_id
+///
+/// Deletes a service account and every key it owns.
+/// Requires `workspace:manage_members`.
+pub async fn delete_service_account(
+    State(state): State<AppState>,
+    Extension(workspace_access): Extension<WorkspaceAccess>,
+    Path((workspace_id, service_account_id)): Path<(Uuid, Uuid)>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key_auth(&workspace_access)?;
+
+    let mut conn = acquire_db_connection(&state, "delete_service_account").await?;
+
+    api_keys::delete_service_account(&mut conn, workspace_id, service_account_id, auth_user.id)
+        .await
+        .inspect_err(|e| log_handler_error("delete_service_account", e))?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Service account deleted successfully"
+    })))
+}
+
+/// GET /api/v1/workspaces/:id/service-accounts/:service_account_id/api-keys
+///
+/// Lists a service account's API keys. Requires `workspace:manage_members`.
+pub async fn list_service_account_api_keys(
+    State(state): State<AppState>,
+    Extension(workspace_access): Extension<WorkspaceAccess>,
+    Path((workspace_id, service_account_id)): Path<(Uuid, Uuid)>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key_auth(&workspace_access)?;
+
+    let mut conn = acquire_db_connection(&state, "list_service_account_api_keys").await?;
+
+    let keys = api_keys::list_service_account_api_keys(&mut conn, workspace_id, service_account_id, auth_user.id)
+        .await
+        .inspect_err(|e| log_handler_error("list_service_account_api_keys", e))?;
+
+    Ok(Json(serde_json::json!({
+        "api_keys": keys,
+        "count": keys.len(),
+    })))
+}
+
+/// POST /api/v1/workspaces/:id/service-accounts/:service_account_id/api-keys
+///
+/// Creates an API key acting as the service account. The permissions must be a
+/// subset of the service account's role. Requires `workspace:manage_members`.
+pub async fn create_service_account_api_key(
+    State(state): State<AppState>,
+    Extension(workspace_access): Extension<WorkspaceAccess>,
+    Path((workspace_id, service_account_id)): Path<(Uuid, Uuid)>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key_auth(&workspace_access)?;
+
+    let mut conn = acquire_db_connection(&state, "create_service_account_api_key").await?;
+
+    let created = api_keys::create_service_account_api_key(
+        &mut conn,
+        workspace_id,
+        service_account_id,
+        auth_user.id,
+        request,
+    )
+    .await
+    .inspect_err(|e| log_handler_error("create_service_account_api_key", e))?;
+
+    Ok(Json(serde_json::json!({
+        "api_key": created.api_key,
+        "key": created.key,
+    })))
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// Key management requires an interactive session so a leaked key cannot mint new ones
+fn reject_api_key_auth(workspace_access: &WorkspaceAccess) -> Result<()> {
+    if workspace_access.api_key.is_some() {
+        return Err(Error::Forbidden(
+            "API keys cannot be used to manage API keys or service accounts".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Helper to log handler errors with appropriate severity
+fn log_handler_error(operation: &str, e: &Error) {
+    match e {
+        Error::Validation(_) | Error::NotFound(_) | Error::Forbidden(_) | Error::Conflict(_) => {
+            tracing::warn!(operation = operation, error = %e, "Handler operation failed");
+        }
+        _ => {
+            tracing::error!(operation = operation, error = %e, "Handler operation failed");
+        }
+    }
+}
+
+/// Helper to acquire database connection with consistent error logging
+async fn acquire_db_connection(state: &AppState, operation: &'static str) -> Result<sqlx::pool::PoolConnection<sqlx::Postgres>> {
+    state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = operation,
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })
+}
@@ -1,16 +1,27 @@
 use axum::{
-    extract::State,
+    extract::{ConnectInfo, Path, Query, State},
     http::{
-        header::{AUTHORIZATION, COOKIE, SET_COOKIE},
-        HeaderMap, HeaderValue,
+        header::{AUTHORIZATION, COOKIE, LOCATION, SET_COOKIE, USER_AGENT},
+        HeaderMap, HeaderValue, StatusCode,
     },
     response::{IntoResponse, Json, Response},
     Extension,
 };
+use std::net::{IpAddr, SocketAddr};
+use uuid::Uuid;
 use crate::{
+    DbConn,
     error::{Error, Result},
     middleware::auth::AuthenticatedUser,
-    models::users::{LoginUser, RegisterUser},
+    models::{
+        api_keys::ApiKeyScope,
+        oidc::{OidcAuthorizeQuery, OidcCallbackQuery},
+        two_factor::{IssuedTwoFactorChallenge, TwoFactorCodeRequest, VerifyTwoFactorLoginRequest},
+        users::{
+            ForgotPasswordRequest, LoginOutcome, LoginResult, LoginUser, RegisterUser,
+            ResetPasswordRequest, SessionClient, UserTokenPurpose, VerifyEmailRequest,
+        },
+    },
     services::{
         cookies::{
             build_access_token_cookie,
@@ -18,6 +29,9 @@ use crate::{
             build_refresh_token_cookie,
             CookieConfig,
         },
+        oidc,
+        sessions,
+        two_factor,
         users,
     },
     state::AppState,
@@ -42,6 +56,13 @@ macro_rules! handle_auth_error {
                     concat!("User ", $operation, " failed: conflict"),
                 );
             }
+            Error::Forbidden(_) => {
+                tracing::warn!(
+                    operation = $operation,
+                    error = %$e,
+                    concat!("User ", $operation, " failed: forbidden"),
+                );
+            }
             Error::Authentication(_) => {
                 tracing::warn!(
                     operation = $operation,
@@ -70,8 +91,49 @@ macro_rules! handle_auth_error {
 /// Custom response type for login that sets multiple Set-Cookie headers
 pub struct LoginResponse {
     json_body: serde_json::Value,
-    access_cookie: String,
-    refresh_cookie: String,
+    access_cookie: Option<String>,
+    refresh_cookie: Option<String>,
+}
+
+impl LoginResponse {
+    /// Completed login: tokens in the body and as cookies
+    fn authenticated(login_result: LoginResult) -> Self {
+        // Build cookie configuration with security settings
+        let config = CookieConfig::default();
+
+        // Build Set-Cookie headers for both tokens
+        let access_cookie = build_access_token_cookie(&login_result.access_token, &config);
+        let refresh_cookie = build_refresh_token_cookie(&login_result.refresh_token, &config);
+
+        // Build JSON response body - ALWAYS include tokens
+        // (Login is the initial token grant, so clients need the tokens)
+        let json_body = serde_json::json!({
+            "user": login_result.user,
+            "access_token": login_result.access_token,
+            "refresh_token": login_result.refresh_token,
+            "access_token_expires_at": login_result.access_token_expires_at,
+            "refresh_token_expires_at": login_result.refresh_token_expires_at
+        });
+
+        Self {
+            json_body,
+            access_cookie: Some(access_cookie),
+            refresh_cookie: Some(refresh_cookie),
+        }
+    }
+
+    /// Password accepted but a second factor is required: challenge only, no cookies
+    fn two_factor_required(challenge: &IssuedTwoFactorChallenge) -> Self {
+        Self {
+            json_body: serde_json::json!({
+                "two_factor_required": true,
+                "challenge_token": challenge.challenge_token,
+                "challenge_expires_at": challenge.expires_at
+            }),
+            access_cookie: None,
+            refresh_cookie: None,
+        }
+    }
 }
 
 impl IntoResponse for LoginResponse {
@@ -83,10 +145,10 @@ impl IntoResponse for LoginResponse {
         let (mut parts, body) = json_response.into_parts();
 
         // Set both Set-Cookie headers using append to allow multiple values
-        if let Ok(access_cookie) = HeaderValue::from_str(&self.access_cookie) {
+        if let Some(Ok(access_cookie)) = self.access_cookie.as_deref().map(HeaderValue::from_str) {
             parts.headers.append(SET_COOKIE, access_cookie);
         }
-        if let Ok(refresh_cookie) = HeaderValue::from_str(&self.refresh_cookie) {
+        if let Some(Ok(refresh_cookie)) = self.refresh_cookie.as_deref().map(HeaderValue::from_str) {
             parts.headers.append(SET_COOKIE, refresh_cookie);
         }
 
@@ -151,6 +213,29 @@ impl IntoResponse for LogoutResponse {
     }
 }
 
+/// Custom response type for browser redirects that may also set cookies
+pub struct RedirectResponse {
+    location: String,
+    cookies: Vec<String>,
+}
+
+impl IntoResponse for RedirectResponse {
+    fn into_response(self) -> Response {
+        let mut response = StatusCode::SEE_OTHER.into_response();
+
+        if let Ok(location) = HeaderValue::from_str(&self.location) {
+            response.headers_mut().insert(LOCATION, location);
+        }
+        for cookie in self.cookies {
+            if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+                response.headers_mut().append(SET_COOKIE, cookie);
+            }
+        }
+
+        response
+    }
+}
+
 
 /// POST /api/v1/auth/register
 ///
@@ -210,6 +295,17 @@ pub async fn register(
         "User registered successfully",
     );
 
+    // Send the initial verification link. Registration already succeeded,
+    // so a failure here is logged and the user can request a new link later.
+    match users::request_email_verification(&mut conn, user.id).await {
+        Ok(issued) => {
+            users::deliver_user_token(state.mailer.as_ref(), &state.config, UserTokenPurpose::EmailVerification, &issued).await;
+        }
+        Err(e) => {
+            handle_auth_error!("register", &e);
+        }
+    }
+
     // Return user data as JSON
     Ok(Json(serde_json::json!({
         "user": user
@@ -249,6 +345,8 @@ pub async fn register(
 /// - **API/Mobile clients**: Use tokens from JSON response in Authorization header
 pub async fn login(
     State(state): State<AppState>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
     Json(request): Json<LoginUser>,
 ) -> Result<LoginResponse> {
     tracing::info!(operation = "login", "User login initiated");
@@ -271,45 +369,94 @@ pub async fn login(
     })?;
 
     // Call service layer to authenticate user
-    let login_result = match users::login_user(&mut conn, request).await {
-        Ok(result) => result,
+    let login_outcome = match users::login_user(&mut conn, request).await {
+        Ok(outcome) => outcome,
         Err(e) => {
             handle_auth_error!("login", &e);
             return Err(e);
         }
     };
 
+    match login_outcome {
+        LoginOutcome::Authenticated(login_result) => {
+            let client = session_client(&state, &headers, connect_info);
+            record_session_client(&mut conn, "login", login_result.session_id, &client).await;
+
+            tracing::info!(
+                operation = "login",
+                user_id = %login_result.user.id,
+                access_expires_at = %login_result.access_token_expires_at,
+                refresh_expires_at = %login_result.refresh_token_expires_at,
+                "User login successful",
+            );
+
+            // Return custom response with cookies
+            Ok(LoginResponse::authenticated(login_result))
+        }
+        LoginOutcome::TwoFactorRequired(challenge) => {
+            tracing::info!(
+                operation = "login",
+                user_id = %challenge.user_id,
+                challenge_expires_at = %challenge.expires_at,
+                "Password accepted, two-factor code required",
+            );
+
+            Ok(LoginResponse::two_factor_required(&challenge))
+        }
+    }
+}
+
+/// POST /api/v1/auth/login/two-factor
+///
+/// Second login step for users with two-factor authentication enabled.
+///
+/// # Request Body
+/// - `challenge_token`: Token returned by `POST /auth/login`
+/// - `code`: 6-digit code from the authenticator app, or
+/// - `recovery_code`: One of the user's unused recovery codes
+///
+/// # Returns
+/// The same body and cookies as a successful `POST /auth/login`.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Authentication successful
+/// - `400 BAD_REQUEST`: Neither or both of `code` and `recovery_code` given
+/// - `401 UNAUTHORIZED`: Wrong code, or invalid, expired or exhausted challenge token
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn login_two_factor(
+    State(state): State<AppState>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    Json(request): Json<VerifyTwoFactorLoginRequest>,
+) -> Result<LoginResponse> {
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "login_two_factor",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    let login_result = match two_factor::complete_login(&mut conn, request).await {
+        Ok(result) => result,
+        Err(e) => {
+            handle_auth_error!("login_two_factor", &e);
+            return Err(e);
+        }
+    };
+
+    let client = session_client(&state, &headers, connect_info);
+    record_session_client(&mut conn, "login_two_factor", login_result.session_id, &client).await;
+
     tracing::info!(
-        operation = "login",
+        operation = "login_two_factor",
         user_id = %login_result.user.id,
-        access_expires_at = %login_result.access_token_expires_at,
-        refresh_expires_at = %login_result.refresh_token_expires_at,
         "User login successful",
     );
 
-    // Build cookie configuration with security settings
-    let config = CookieConfig::default();
-
-    // Build Set-Cookie headers for both tokens
-    let access_cookie = build_access_token_cookie(&login_result.access_token, &config);
-    let refresh_cookie = build_refresh_token_cookie(&login_result.refresh_token, &config);
-
-    // Build JSON response body - ALWAYS include tokens
-    // (Login is the initial token grant, so clients need the tokens)
-    let json_body = serde_json::json!({
-        "user": login_result.user,
-        "access_token": login_result.access_token,
-        "refresh_token": login_result.refresh_token,
-        "access_token_expires_at": login_result.access_token_expires_at,
-        "refresh_token_expires_at": login_result.refresh_token_expires_at
-    });
-
-    // Return custom response with cookies
-    Ok(LoginResponse {
-        json_body,
-        access_cookie,
-        refresh_cookie,
-    })
+    Ok(LoginResponse::authenticated(login_result))
 }
 
 /// POST /api/v1/auth/refresh
@@ -345,6 +492,7 @@ pub async fn login(
 /// The response now includes `refresh_token` field. API clients must update to store the new token.
 pub async fn refresh(
     State(state): State<AppState>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
     headers: HeaderMap,
 ) -> Result<RefreshResponse> {
     // Extract refresh token from Authorization header or cookie
@@ -377,6 +525,11 @@ pub async fn refresh(
         }
     };
 
+    if let Some(session_id) = refresh_result.session_id {
+        let client = session_client(&state, &headers, connect_info);
+        record_session_client(&mut conn, "refresh", session_id, &client).await;
+    }
+
     let token_rotated = refresh_result.refresh_token.is_some();
     tracing::info!(
         operation = "refresh",
@@ -491,6 +644,48 @@ pub async fn logout(
     })
 }
 
+/// Device details of a request that logs in or refreshes a session
+///
+/// The client IP comes from the connection, or from `X-Forwarded-For` when the server
+/// is configured to trust proxy headers.
+fn session_client(
+    state: &AppState,
+    headers: &HeaderMap,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+) -> SessionClient {
+    let user_agent = headers
+        .get(USER_AGENT)
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string);
+
+    let forwarded_ip = if state.config.server.trust_proxy_headers {
+        headers
+            .get("x-forwarded-for")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.split(',').next())
+            .and_then(|ip| ip.trim().parse::<IpAddr>().ok())
+    } else {
+        None
+    };
+    let ip_address = forwarded_ip
+        .or_else(|| connect_info.map(|Extension(ConnectInfo(addr))| addr.ip()))
+        .map(|ip| ip.to_string());
+
+    SessionClient { user_agent, ip_address }
+}
+
+/// Records the device of a session; failing to do so does not fail the login
+async fn record_session_client(conn: &mut DbConn, operation: &str, session_id: Uuid, client: &SessionClient) {
+    if let Err(e) = sessions::record_session_client(conn, session_id, client).await {
+        tracing::warn!(
+            operation = operation,
+            session_id = %session_id,
+            error = %e,
+            "Failed to record session device",
+        );
+    }
+}
+
 /// Extract refresh token from Authorization header or cookie
 fn extract_refresh_token(headers: &HeaderMap) -> Result<(String, bool)> {
     let config = CookieConfig::default();
@@ -526,6 +721,712 @@ fn extract_refresh_token(headers: &HeaderMap) -> Result<(String, bool)> {
     ))
 }
 
+/// GET /api/v1/auth/oidc/authorize
+///
+/// Starts single sign-on by redirecting the browser to the configured OpenID Connect
+/// provider (authorization code flow with PKCE).
+///
+/// # Query Parameters
+/// - `redirect_to`: Optional frontend path to return to after login (e.g. `/workspaces`)
+///
+/// # HTTP Status Codes
+/// - `303 SEE_OTHER`: Redirect to the identity provider
+/// - `404 NOT_FOUND`: Single sign-on is not configured
+/// - `500 INTERNAL_SERVER_ERROR`: Provider discovery or database error
+pub async fn oidc_authorize(
+    State(state): State<AppState>,
+    Query(query): Query<OidcAuthorizeQuery>,
+) -> Result<RedirectResponse> {
+    tracing::info!(operation = "oidc_authorize", "Single sign-on initiated");
+
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "oidc_authorize",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    let authorization_url = match oidc::begin_login(&mut conn, &state.config.oidc, query.redirect_to.as_deref()).await {
+        Ok(url) => url,
+        Err(e) => {
+            handle_auth_error!("oidc_authorize", &e);
+            return Err(e);
+        }
+    };
+
+    Ok(RedirectResponse {
+        location: authorization_url,
+        cookies: Vec::new(),
+    })
+}
+
+/// GET /api/v1/auth/oidc/callback
+///
+/// Completes single sign-on. The identity provider redirects the browser here with
+/// `code` and `state`; the code is exchanged, the ID token verified and the user
+/// linked by identity or verified email, or provisioned on first login.
+///
+/// On success the `access_token` and `refresh_token` cookies are set and the browser is
+/// redirected to the frontend path requested when the login started. Users with
+/// two-factor authentication enabled are instead sent to `/login/two-factor` on the
+/// frontend with `challenge_token` and `redirect_to` query parameters. On failure the
+/// browser is redirected to `/login?error=sso_failed` on the frontend.
+///
+/// # HTTP Status Codes
+/// - `303 SEE_OTHER`: Redirect to the frontend
+pub async fn oidc_callback(
+    State(state): State<AppState>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    Query(query): Query<OidcCallbackQuery>,
+) -> RedirectResponse {
+    let base_url = state.config.auth.public_base_url.trim_end_matches('/');
+    let failure_redirect = || RedirectResponse {
+        location: format!("{}/login?error=sso_failed", base_url),
+        cookies: Vec::new(),
+    };
+
+    let mut conn = match state.pool.acquire().await {
+        Ok(conn) => conn,
+        Err(e) => {
+            tracing::error!(
+                operation = "oidc_callback",
+                error_code = "DATABASE_ACQUISITION_FAILED",
+                error = %e,
+                "Failed to acquire database connection",
+            );
+            return failure_redirect();
+        }
+    };
+
+    let oidc_result = match oidc::complete_login(&mut conn, &state.config.oidc, query).await {
+        Ok(result) => result,
+        Err(e) => {
+            handle_auth_error!("oidc_callback", &e);
+            return failure_redirect();
+        }
+    };
+
+    let redirect_to = oidc_result.redirect_to.as_deref().unwrap_or("/");
+    let login_result = match oidc_result.login {
+        LoginOutcome::Authenticated(login_result) => login_result,
+        LoginOutcome::TwoFactorRequired(challenge) => {
+            tracing::info!(
+                operation = "oidc_callback",
+                user_id = %challenge.user_id,
+                provisioned = oidc_result.provisioned,
+                "Single sign-on accepted, two-factor code required",
+            );
+
+            // The frontend finishes the login with POST /auth/login/two-factor
+            let query = url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("challenge_token", &challenge.challenge_token)
+                .append_pair("redirect_to", redirect_to)
+                .finish();
+            return RedirectResponse {
+                location: format!("{}/login/two-factor?{}", base_url, query),
+                cookies: Vec::new(),
+            };
+        }
+    };
+
+    let client = session_client(&state, &headers, connect_info);
+    record_session_client(&mut conn, "oidc_callback", login_result.session_id, &client).await;
+
+    tracing::info!(
+        operation = "oidc_callback",
+        user_id = %login_result.user.id,
+        provisioned = oidc_result.provisioned,
+        joined_workspaces = oidc_result.joined_workspace_ids.len(),
+        "Single sign-on successful",
+    );
+
+    let config = CookieConfig::default();
+    RedirectResponse {
+        location: format!("{}{}", base_url, redirect_to),
+        cookies: vec![
+            build_access_token_cookie(&login_result.access_token, &config),
+            build_refresh_token_cookie(&login_result.refresh_token, &config),
+        ],
+    }
+}
+
+/// POST /api/v1/auth/password/forgot
+///
+/// Starts the password reset flow by sending a single-use reset link to the email address.
+///
+/// # Request Body
+/// - `email`: Email address of the account
+///
+/// # Returns
+/// The same generic message whether or not an account exists, so the endpoint
+/// cannot be used to discover registered email addresses. The mail is sent in the
+/// background, and at most `auth.password_reset_mails_per_hour` per address.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Request accepted
+/// - `400 BAD_REQUEST`: Invalid email format
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    Json(request): Json<ForgotPasswordRequest>,
+) -> Result<Json<serde_json::Value>> {
+    tracing::info!(operation = "forgot_password", "Password reset requested");
+
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "forgot_password",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    match users::request_password_reset(&mut conn, &request.email).await {
+        Ok(Some(issued)) => {
+            // Sending waits for the mail server, answering first keeps the response time
+            // the same whether or not the account exists
+            let mailer = state.mailer.clone();
+            let config = state.config.clone();
+            tokio::spawn(async move {
+                users::deliver_user_token(mailer.as_ref(), &config, UserTokenPurpose::PasswordReset, &issued).await;
+            });
+        }
+        Ok(None) => {
+            tracing::info!(
+                operation = "forgot_password",
+                "No password reset mail sent, unknown email or too many requests",
+            );
+        }
+        Err(e) => {
+            handle_auth_error!("forgot_password", &e);
+            return Err(e);
+        }
+    }
+
+    Ok(Json(serde_json::json!({
+        "message": "If an account exists for this email, a password reset link has been sent"
+    })))
+}
+
+/// POST /api/v1/auth/password/reset
+///
+/// Completes the password reset flow with a token from a reset link.
+///
+/// # Request Body
+/// - `token`: Reset token from the emailed link
+/// - `password`: New password (minimum 12 characters)
+/// - `confirm_password`: Password confirmation (must match password)
+///
+/// All existing sessions of the user are revoked, so every device must log in again.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Password reset successfully
+/// - `400 BAD_REQUEST`: Validation error (weak password, passwords don't match)
+/// - `401 UNAUTHORIZED`: Invalid, expired or already used token
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Json(request): Json<ResetPasswordRequest>,
+) -> Result<Json<serde_json::Value>> {
+    tracing::info!(operation = "reset_password", "Password reset initiated");
+
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "reset_password",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    let user = match users::reset_password(&mut conn, request).await {
+        Ok(user) => user,
+        Err(e) => {
+            handle_auth_error!("reset_password", &e);
+            return Err(e);
+        }
+    };
+
+    // Drop cached user data so the next request sees the updated record
+    let _ = state.user_cache.delete(&format!("user:{}", user.id)).await;
+
+    tracing::info!(
+        operation = "reset_password",
+        user_id = %user.id,
+        "Password reset successful",
+    );
+
+    Ok(Json(serde_json::json!({
+        "message": "Password has been reset. Please log in with your new password."
+    })))
+}
+
+/// POST /api/v1/auth/email/verify
+///
+/// Verifies the user's email address with a token from a verification link.
+///
+/// # Request Body
+/// - `token`: Verification token from the emailed link
+///
+/// # Returns
+/// JSON response containing the updated user object.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Email verified
+/// - `401 UNAUTHORIZED`: Invalid, expired or already used token
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(request): Json<VerifyEmailRequest>,
+) -> Result<Json<serde_json::Value>> {
+    tracing::info!(operation = "verify_email", "Email verification initiated");
+
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "verify_email",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    let user = match users::verify_email(&mut conn, &request.token).await {
+        Ok(user) => user,
+        Err(e) => {
+            handle_auth_error!("verify_email", &e);
+            return Err(e);
+        }
+    };
+
+    let _ = state.user_cache.delete(&format!("user:{}", user.id)).await;
+
+    tracing::info!(
+        operation = "verify_email",
+        user_id = %user.id,
+        "Email verified successfully",
+    );
+
+    Ok(Json(serde_json::json!({
+        "user": user
+    })))
+}
+
+/// POST /api/v1/auth/email/verification
+///
+/// Sends a new email verification link to the authenticated user.
+/// Any previously issued verification link stops working.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Verification link sent
+/// - `401 UNAUTHORIZED`: Invalid or expired JWT token
+/// - `409 CONFLICT`: Email address is already verified
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn resend_email_verification(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "resend_email_verification",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    let issued = match users::request_email_verification(&mut conn, auth_user.id).await {
+        Ok(issued) => issued,
+        Err(e) => {
+            handle_auth_error!("resend_email_verification", &e);
+            return Err(e);
+        }
+    };
+
+    users::deliver_user_token(state.mailer.as_ref(), &state.config, UserTokenPurpose::EmailVerification, &issued).await;
+
+    Ok(Json(serde_json::json!({
+        "message": "Verification link sent",
+        "expires_at": issued.expires_at
+    })))
+}
+
+// ============================================================================
+// TWO-FACTOR AUTHENTICATION
+// ============================================================================
+
+/// API keys act on behalf of a user but must not change how that user signs in
+fn reject_api_key(api_key: Option<Extension<ApiKeyScope>>, action: &str) -> Result<()> {
+    if api_key.is_some() {
+        return Err(Error::Forbidden(format!("API keys cannot {}", action)));
+    }
+    Ok(())
+}
+
+/// GET /api/v1/auth/two-factor
+///
+/// Returns whether two-factor authentication is enabled for the authenticated user.
+///
+/// # Returns
+/// JSON response containing `two_factor` with `enabled`, `enabled_at` and
+/// `recovery_codes_remaining`.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Status returned
+/// - `401 UNAUTHORIZED`: Invalid or expired JWT token
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn two_factor_status(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "two_factor_status",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    let status = match two_factor::get_status(&mut conn, auth_user.id).await {
+        Ok(status) => status,
+        Err(e) => {
+            handle_auth_error!("two_factor_status", &e);
+            return Err(e);
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "two_factor": status
+    })))
+}
+
+/// POST /api/v1/auth/two-factor/enroll
+///
+/// Starts TOTP enrollment. Two-factor authentication is not enforced until the
+/// enrollment is confirmed with a code from the authenticator app.
+///
+/// # Returns
+/// JSON response containing:
+/// - `secret`: Base32 secret for manual entry
+/// - `otpauth_url`: `otpauth://` URI to render as a QR code
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Enrollment started
+/// - `401 UNAUTHORIZED`: Invalid or expired JWT token
+/// - `403 FORBIDDEN`: Called with an API key
+/// - `409 CONFLICT`: Two-factor authentication is already enabled
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn begin_two_factor_enrollment(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    api_key: Option<Extension<ApiKeyScope>>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key(api_key, "manage two-factor authentication")?;
+
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "begin_two_factor_enrollment",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    let enrollment = match two_factor::begin_enrollment(&mut conn, auth_user.id).await {
+        Ok(enrollment) => enrollment,
+        Err(e) => {
+            handle_auth_error!("begin_two_factor_enrollment", &e);
+            return Err(e);
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "secret": enrollment.secret,
+        "otpauth_url": enrollment.otpauth_url
+    })))
+}
+
+/// POST /api/v1/auth/two-factor/confirm
+///
+/// Enables two-factor authentication with a code for the pending secret.
+///
+/// # Request Body
+/// - `code`: 6-digit code from the authenticator app
+///
+/// # Returns
+/// JSON response containing `recovery_codes`. They are shown only once.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Two-factor authentication enabled
+/// - `400 BAD_REQUEST`: Invalid code
+/// - `401 UNAUTHORIZED`: Invalid or expired JWT token
+/// - `403 FORBIDDEN`: Called with an API key
+/// - `404 NOT_FOUND`: No pending enrollment
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn confirm_two_factor_enrollment(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    api_key: Option<Extension<ApiKeyScope>>,
+    Json(request): Json<TwoFactorCodeRequest>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key(api_key, "manage two-factor authentication")?;
+
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "confirm_two_factor_enrollment",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    let code = request.code.unwrap_or_default();
+    let recovery_codes = match two_factor::confirm_enrollment(&mut conn, auth_user.id, &code).await {
+        Ok(codes) => codes,
+        Err(e) => {
+            handle_auth_error!("confirm_two_factor_enrollment", &e);
+            return Err(e);
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "recovery_codes": recovery_codes
+    })))
+}
+
+/// POST /api/v1/auth/two-factor/recovery-codes
+///
+/// Replaces all recovery codes. Earlier codes stop working.
+///
+/// # Request Body
+/// - `code` or `recovery_code`: Proof of the current second factor
+///
+/// # Returns
+/// JSON response containing the new `recovery_codes`.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Recovery codes replaced
+/// - `400 BAD_REQUEST`: Invalid code
+/// - `401 UNAUTHORIZED`: Invalid or expired JWT token
+/// - `403 FORBIDDEN`: Called with an API key
+/// - `404 NOT_FOUND`: Two-factor authentication is not enabled
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    api_key: Option<Extension<ApiKeyScope>>,
+    Json(request): Json<TwoFactorCodeRequest>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key(api_key, "manage two-factor authentication")?;
+
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "regenerate_recovery_codes",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    let recovery_codes = match two_factor::regenerate_recovery_codes(&mut conn, auth_user.id, &request).await {
+        Ok(codes) => codes,
+        Err(e) => {
+            handle_auth_error!("regenerate_recovery_codes", &e);
+            return Err(e);
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "recovery_codes": recovery_codes
+    })))
+}
+
+/// POST /api/v1/auth/two-factor/disable
+///
+/// Disables two-factor authentication and deletes the recovery codes.
+///
+/// # Request Body
+/// - `code` or `recovery_code`: Proof of the current second factor
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Two-factor authentication disabled
+/// - `400 BAD_REQUEST`: Invalid code
+/// - `401 UNAUTHORIZED`: Invalid or expired JWT token
+/// - `403 FORBIDDEN`: Called with an API key
+/// - `404 NOT_FOUND`: Two-factor authentication is not enabled
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn disable_two_factor(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    api_key: Option<Extension<ApiKeyScope>>,
+    Json(request): Json<TwoFactorCodeRequest>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key(api_key, "manage two-factor authentication")?;
+
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "disable_two_factor",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    if let Err(e) = two_factor::disable(&mut conn, auth_user.id, &request).await {
+        handle_auth_error!("disable_two_factor", &e);
+        return Err(e);
+    }
+
+    Ok(Json(serde_json::json!({
+        "message": "Two-factor authentication disabled"
+    })))
+}
+
+// ============================================================================
+// SESSION MANAGEMENT
+// ============================================================================
+
+/// GET /api/v1/auth/sessions
+///
+/// Lists the authenticated user's active login sessions (devices).
+///
+/// # Returns
+/// JSON response containing `sessions`, most recently used first. Each session has
+/// `id`, `user_agent`, `ip_address`, `created_at`, `last_used_at`, `expires_at` and
+/// `current`, which is true for the session of the access token used for the request.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Sessions returned
+/// - `401 UNAUTHORIZED`: Invalid or expired JWT token
+/// - `403 FORBIDDEN`: Called with an API key
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn list_login_sessions(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    api_key: Option<Extension<ApiKeyScope>>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key(api_key, "manage login sessions")?;
+
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "list_login_sessions",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    let user_sessions = match sessions::list_user_sessions(&mut conn, auth_user.id, auth_user.session_id).await {
+        Ok(user_sessions) => user_sessions,
+        Err(e) => {
+            handle_auth_error!("list_login_sessions", &e);
+            return Err(e);
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "sessions": user_sessions
+    })))
+}
+
+/// DELETE /api/v1/auth/sessions/{session_id}
+///
+/// Revokes one of the authenticated user's sessions, e.g. a lost device. Its refresh
+/// token stops working immediately; access tokens already issued to it remain valid
+/// until they expire.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Session revoked
+/// - `401 UNAUTHORIZED`: Invalid or expired JWT token
+/// - `403 FORBIDDEN`: Called with an API key
+/// - `404 NOT_FOUND`: No such session for this user
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn revoke_login_session(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    api_key: Option<Extension<ApiKeyScope>>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key(api_key, "manage login sessions")?;
+
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "revoke_login_session",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    if let Err(e) = sessions::revoke_user_session(&mut conn, auth_user.id, session_id).await {
+        handle_auth_error!("revoke_login_session", &e);
+        return Err(e);
+    }
+
+    Ok(Json(serde_json::json!({
+        "message": "Session revoked successfully"
+    })))
+}
+
+/// POST /api/v1/auth/sessions/revoke-others
+///
+/// Logs out everywhere else: revokes every session of the authenticated user except
+/// the one of the access token used for the request.
+///
+/// # Returns
+/// JSON response containing `revoked_sessions`, the number of revoked sessions.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Other sessions revoked
+/// - `401 UNAUTHORIZED`: Invalid or expired JWT token, or a token issued without a session
+/// - `403 FORBIDDEN`: Called with an API key
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn revoke_other_login_sessions(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    api_key: Option<Extension<ApiKeyScope>>,
+) -> Result<Json<serde_json::Value>> {
+    reject_api_key(api_key, "manage login sessions")?;
+
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "revoke_other_login_sessions",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        crate::error::Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })?;
+
+    let revoked_sessions = match sessions::revoke_other_sessions(&mut conn, auth_user.id, auth_user.session_id).await {
+        Ok(count) => count,
+        Err(e) => {
+            handle_auth_error!("revoke_other_login_sessions", &e);
+            return Err(e);
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "message": "Logged out of all other sessions",
+        "revoked_sessions": revoked_sessions
+    })))
+}
+
 /// GET /api/v1/auth/me
 ///
 /// Returns the currently authenticated user's profile.
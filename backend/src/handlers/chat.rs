@@ -1,15 +1,17 @@
 use crate::error::{Error, Result};
-use crate::models::chat::{ChatAttachment, ChatMessageMetadata, ChatMessageRole, NewChatMessage, DEFAULT_CHAT_MODEL};
-use crate::models::files::{FileType, FileStatus, NewFile};
+use crate::models::chat::{ChatAttachment, ChatMessageMetadata, ChatMessageRole, NewChatMessage, QuestionAnswerMetadata, DEFAULT_CHAT_MODEL};
 use crate::models::requests::{CreateChatRequest, PostChatMessageRequest, UpdateChatRequest};
-use crate::models::sse::SseEvent;
+use crate::models::sse::{SseEvent, StreamEvent};
 use crate::queries;
-use crate::services::chat::actor::ChatActor;
-use crate::services::chat::registry::AgentCommand;
+use crate::services::chat::ChatService;
+use crate::services::chat::approvals;
+use crate::services::chat::cluster::RemoteCommand;
+use crate::services::chat::registry::{AgentCommand, AgentHandle};
 use crate::state::AppState;
+use crate::DbConn;
 use crate::middleware::auth::AuthenticatedUser;
 use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::{Extension, Json};
 use futures::stream::{self, Stream};
@@ -18,38 +20,49 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{Mutex, oneshot};
 use tokio_stream::wrappers::{BroadcastStream, IntervalStream};
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
 use uuid::Uuid;
 use futures::StreamExt;
 
 /// Maximum length of goal text to include in chat file name.
 const CHAT_NAME_GOAL_SNIPPET_LENGTH: usize = 80;
 
-/// Helper function to get the persona for a chat based on its mode.
-///
-/// This extracts the mode from the chat's app_data and determines the appropriate
-/// persona (role) for the agent.
-async fn get_chat_persona(
-    conn: &mut sqlx::PgConnection,
+/// Largest request body of the routes posting chat messages, which can carry
+/// pasted images
+pub const MAX_CHAT_REQUEST_BYTES: usize = 64 * 1024 * 1024;
+
+/// Where the actor of a chat runs
+enum ChatActorLocation {
+    Local(AgentHandle),
+    /// On another instance of the cluster
+    Remote(Uuid),
+}
+
+/// Finds the actor of a chat, spawning one on this instance when no instance runs it.
+async fn locate_chat_actor(
+    state: &AppState,
+    conn: &mut DbConn,
     chat_id: Uuid,
-) -> Result<String> {
-    // Get the latest version to determine the mode from app_data
-    let latest_version = queries::files::get_latest_version(conn, chat_id).await?;
+    workspace_id: Uuid,
+    user_id: Uuid,
+) -> Result<ChatActorLocation> {
+    if let Some(handle) = state.agents.get_handle(&chat_id).await {
+        return Ok(ChatActorLocation::Local(handle));
+    }
 
-    // Extract mode from app_data, default to "plan" if not set
-    let mode = latest_version
-        .app_data
-        .get("mode")
-        .and_then(|v| v.as_str())
-        .unwrap_or("plan");
-
-    // Determine role from mode
-    let role = match mode {
-        "build" => Some("builder"),
-        "plan" => Some("planner"),
-        _ => None,
-    };
+    if let Some(owner) = state.agents.remote_owner(conn, chat_id).await? {
+        tracing::debug!(
+            chat_id = %chat_id,
+            owner = %owner,
+            "[ChatHandler] Chat actor runs on another instance"
+        );
+        return Ok(ChatActorLocation::Remote(owner));
+    }
 
-    Ok(crate::agents::get_persona(role, Some(mode), None))
+    tracing::info!("[ChatHandler] Rehydrating ChatActor for chat {}", chat_id);
+    let default_persona = ChatService::get_chat_persona(conn, chat_id).await?;
+    let handle = state.spawn_chat_actor(chat_id, workspace_id, user_id, default_persona).await;
+    Ok(ChatActorLocation::Local(handle))
 }
 
 pub async fn create_chat(
@@ -61,43 +74,32 @@ pub async fn create_chat(
     tracing::info!("[ChatHandler] Creating chat in workspace {} for user {}", workspace_id, user.id);
     let mut conn = state.pool.acquire().await.map_err(Error::Sqlx)?;
 
-    // 1. Ensure the /chats folder exists and get its ID
-    let chats_folder_id = crate::services::files::ensure_path_exists(
+    // Resolve the agent file and pinned skills first so an unknown one fails before
+    // anything is created
+    let agent = match req.agent_id {
+        Some(agent_id) => Some(
+            crate::services::agent_files::get_agent(&mut conn, &state.storage, workspace_id, agent_id).await?,
+        ),
+        None => None,
+    };
+    let skill_attachments = crate::services::skills::resolve_skill_attachments(
         &mut conn,
+        &state.storage,
         workspace_id,
-        "chats",
-        user.id,
+        req.skills.as_deref().unwrap_or_default(),
     ).await?;
-
-    // 2. Create the .chat file identity with TEMPORARY path/slug
-    // We need to create the file first to get its ID, then update the path
-    let chat_file = queries::files::create_file_identity(&mut conn, NewFile {
+    let image_attachments = crate::services::images::resolve_image_attachments(
+        req.images.as_deref().unwrap_or_default(),
+    ).await?;
+    let url_attachments = crate::services::web_pages::resolve_url_attachments(
+        &mut conn,
+        &state.storage,
         workspace_id,
-        parent_id: chats_folder_id,
-        author_id: user.id,
-        file_type: FileType::Chat,
-        status: FileStatus::Ready,
-        name: {
-            let snippet_end = req.goal.char_indices()
-                .nth(CHAT_NAME_GOAL_SNIPPET_LENGTH)
-                .map_or(req.goal.len(), |(idx, _)| idx);
-            format!("Chat: {}", &req.goal[..snippet_end])
-        },
-        slug: "chat-temp".to_string(),  // Temporary slug
-        path: "/chats/chat-temp".to_string(),  // Temporary path
-        is_virtual: true,
-        is_remote: false,
-        permission: 600,
-    }).await?;
-
-    // 3. Update the file with its actual ID in the path/slug
-    let correct_path = format!("/chats/chat-{}.chat", chat_file.id);
-    let correct_slug = format!("chat-{}.chat", chat_file.id);
-    let chat_file = queries::files::update_file_path_and_slug(&mut conn, chat_file.id, correct_path, correct_slug).await?;
-
-    tracing::info!("[ChatHandler] Chat file created: {} (ID: {})", chat_file.path, chat_file.id);
+        user.id,
+        req.urls.as_deref().unwrap_or_default(),
+    ).await?;
 
-    // 4. Determine the mode from the request role (defaults to "plan" if not specified)
+    // 1. Determine the mode from the request role (defaults to "plan" if not specified)
     // This must be done BEFORE creating app_data so the mode is saved correctly
     let mode = match req.role.as_deref() {
         Some("builder") => "build",
@@ -105,35 +107,50 @@ pub async fn create_chat(
         _ => "chat",
     };
 
-    // 5. Create initial version with config in app_data
+    // 2. Build the initial config for app_data
+    // An explicit model wins over the agent file's default
+    let model = req.model.clone()
+        .or_else(|| agent.as_ref().and_then(|a| a.metadata.model.clone()))
+        .unwrap_or_else(|| DEFAULT_CHAT_MODEL.to_string());
+    let persona = match agent {
+        Some(ref agent) => agent.system_prompt.clone(),
+        None => crate::agents::get_persona(req.role.as_deref(), Some(mode), None),
+    };
     let app_data = serde_json::json!({
         "goal": req.goal,
         "agents": req.agents,
-        "model": req.model.clone().unwrap_or_else(|| DEFAULT_CHAT_MODEL.to_string()),
-        "persona": crate::agents::get_persona(req.role.as_deref(), Some(mode), None),
-        "temperature": 0.7,
+        "agent_id": agent.as_ref().map(|a| a.id),
+        "model": model,
+        "persona": persona,
+        "temperature": agent.as_ref().and_then(|a| a.metadata.temperature).unwrap_or(0.7),
         "mode": mode,
         "plan_file": null
     });
 
-    let version = queries::files::create_version(&mut conn, crate::models::files::NewFileVersion {
-        id: None,
-        file_id: chat_file.id,
+    // 3. Create the .chat file with its initial version
+    let name = {
+        let snippet_end = req.goal.char_indices()
+            .nth(CHAT_NAME_GOAL_SNIPPET_LENGTH)
+            .map_or(req.goal.len(), |(idx, _)| idx);
+        format!("Chat: {}", &req.goal[..snippet_end])
+    };
+    let chat_file = crate::services::chat::ChatService::create_chat_file(
+        &mut conn,
         workspace_id,
-        branch: "main".to_string(),
+        user.id,
+        name,
         app_data,
-        hash: "initial".to_string(),
-        author_id: Some(user.id),
-    }).await?;
-
-    queries::files::update_latest_version_id(&mut conn, chat_file.id, version.id).await?;
+    ).await?;
 
-    // 5. Persist initial goal message via Service (triggers write-through snapshot)
-    use crate::services::chat::ChatService;
+    tracing::info!("[ChatHandler] Chat file created: {} (ID: {})", chat_file.path, chat_file.id);
 
-    // Get model for metadata (from request or default)
-    let model_for_metadata = req.model.clone()
-        .unwrap_or_else(|| DEFAULT_CHAT_MODEL.to_string());
+    // 4. Persist initial goal message via Service (triggers write-through snapshot)
+    // Get model for metadata (from request, agent file or default)
+    let model_for_metadata = model;
+    let agent_attachment = agent.map(|agent| ChatAttachment::Agent {
+        agent_id: agent.id,
+        name: agent.metadata.name,
+    });
 
     ChatService::save_message(&mut conn, &state.storage, workspace_id, NewChatMessage {
         file_id: chat_file.id,
@@ -144,29 +161,19 @@ pub async fn create_chat(
             attachments: req.files.unwrap_or_default().into_iter().map(|f| ChatAttachment::File {
                 file_id: f,
                 version_id: None,
-            }).collect(),
+            }).chain(agent_attachment).chain(skill_attachments).chain(image_attachments).chain(url_attachments).collect(),
             model: Some(model_for_metadata),
             ..Default::default()
         }),
     }).await?;
 
-    // 6. Trigger Actor immediately for the initial goal
-    let event_tx = state.agents.get_or_create_bus(chat_file.id).await;
-
-    let handle = ChatActor::spawn(crate::services::chat::actor::ChatActorArgs {
-        chat_id: chat_file.id,
+    // 5. Trigger Actor immediately for the initial goal
+    let handle = state.spawn_chat_actor(
+        chat_file.id,
         workspace_id,
-        user_id: user.id,
-        pool: state.pool.clone(),
-        rig_service: state.rig_service.clone(),
-        storage: state.storage.clone(),
-        registry: state.agents.clone(),
-        default_persona: crate::agents::get_persona(req.role.as_deref(), Some(mode), None),
-        default_context_token_limit: state.config.ai.default_context_token_limit,
-        event_tx,
-        inactivity_timeout: std::time::Duration::from_secs(state.config.ai.actor_inactivity_timeout_seconds),
-    });
-    state.agents.register(chat_file.id, handle.clone()).await;
+        user.id,
+        crate::agents::get_persona(req.role.as_deref(), Some(mode), None),
+    ).await;
 
     let _ = handle.command_tx.send(AgentCommand::ProcessInteraction {
         user_id: user.id,
@@ -187,7 +194,14 @@ pub async fn get_chat_events(
     State(state): State<AppState>,
     Extension(_user): Extension<AuthenticatedUser>,
     Path((workspace_id, chat_id)): Path<(Uuid, Uuid)>,
+    headers: HeaderMap,
 ) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    // Resuming clients send the id of the last event they received
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<i64>().ok());
+
     tracing::info!(
         chat_id = %chat_id,
         workspace_id = %workspace_id,
@@ -204,27 +218,11 @@ pub async fn get_chat_events(
         "[SSE] SENDING SessionInit event"
     );
 
-    // 2. Ensure actor is alive (rehydrate if needed)
-    if state.agents.get_handle(&chat_id).await.is_none() {
-        tracing::info!("[ChatHandler] Rehydrating ChatActor for chat {}", chat_id);
-        let handle = ChatActor::spawn(crate::services::chat::actor::ChatActorArgs {
-            chat_id,
-            workspace_id,
-            user_id: _user.id,
-            pool: state.pool.clone(),
-            rig_service: state.rig_service.clone(),
-            storage: state.storage.clone(),
-            registry: state.agents.clone(),
-            default_persona: {
-                let mut conn = state.pool.acquire().await.map_err(Error::Sqlx)?;
-                get_chat_persona(&mut conn, chat_id).await?
-            },
-            default_context_token_limit: state.config.ai.default_context_token_limit,
-            event_tx: event_tx.clone(),
-            inactivity_timeout: std::time::Duration::from_secs(state.config.ai.actor_inactivity_timeout_seconds),
-        });
-        state.agents.register(chat_id, handle).await;
-    };
+    // 2. Ensure actor is alive on some instance (rehydrate if needed)
+    {
+        let mut conn = state.pool.acquire().await.map_err(Error::Sqlx)?;
+        locate_chat_actor(&state, &mut conn, chat_id, workspace_id, _user.id).await?;
+    }
 
     // 3. Send initial session_init event
     let init_event = SseEvent::SessionInit {
@@ -232,39 +230,71 @@ pub async fn get_chat_events(
         plan_id: None,
     };
     let init_data = serde_json::to_string(&init_event).map_err(Error::Json)?;
-    let init_stream = stream::once(async move { Ok(Event::default().data(init_data)) });
 
     // 4. Stream from persistent broadcast channel
+    // Subscribe before replaying, so no event falls between the replay and the live stream
     let current_receiver_count = event_tx.receiver_count();
     tracing::info!(
         chat_id = %chat_id,
         receiver_count = current_receiver_count + 1, // +1 for this new connection
+        resume_after = ?last_event_id,
         "[SSE] SUBSCRIBED - Client now receiving events (total receivers: {})",
         current_receiver_count + 1
     );
-    let broadcast_stream = BroadcastStream::new(event_tx.subscribe())
-        .filter_map(move |msg| async move {
+    // Events of actors running on other instances of a cluster
+    let remote_stream = stream::iter(state.agents.subscribe_remote(chat_id).await.map(BroadcastStream::new)).flatten();
+    let live_stream = stream::select(BroadcastStream::new(state.agents.subscribe_stream(chat_id).await), remote_stream);
+
+    let pool = state.pool.clone();
+    let broadcast_stream = async_stream::stream! {
+        yield Ok(Event::default().data(init_data));
+
+        // Events up to this id were sent, by the previous connection or a replay
+        let mut sent_up_to = last_event_id;
+        if let Some(after) = last_event_id {
+            for event in replay_stream_events(&pool, chat_id, after).await {
+                sent_up_to = event.id;
+                if let Some(sse_event) = to_sse_event(&event) {
+                    yield Ok(sse_event);
+                }
+            }
+        }
+
+        futures::pin_mut!(live_stream);
+        while let Some(msg) = live_stream.next().await {
             match msg {
                 Ok(event) => {
+                    if let (Some(id), Some(sent)) = (event.id, sent_up_to)
+                        && id <= sent
+                    {
+                        continue;
+                    }
+                    sent_up_to = event.id.or(sent_up_to);
                     tracing::debug!(
                         chat_id = %chat_id,
-                        event_type = ?std::mem::discriminant(&event),
+                        event_id = ?event.id,
+                        event_type = ?std::mem::discriminant(&event.event),
                         "[SSE] Broadcasting event to client"
                     );
-                    match serde_json::to_string(&event) {
-                        Ok(data) => Some(Ok(Event::default().data(data))),
-                        Err(e) => {
-                            tracing::error!("[SSE] Failed to serialize SSE event: {:?}", e);
-                            None
+                    if let Some(sse_event) = to_sse_event(&event) {
+                        yield Ok(sse_event);
+                    }
+                }
+                Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                    // Catch up from the event log
+                    tracing::warn!(chat_id = %chat_id, skipped, "[SSE] broadcast receiver lag - replaying missed events");
+                    if let Some(after) = sent_up_to {
+                        for event in replay_stream_events(&pool, chat_id, after).await {
+                            sent_up_to = event.id;
+                            if let Some(sse_event) = to_sse_event(&event) {
+                                yield Ok(sse_event);
+                            }
                         }
                     }
-                },
-                Err(_) => {
-                    tracing::warn!("[SSE] broadcast receiver lag - client disconnected");
-                    None
                 }
             }
-        });
+        }
+    };
 
     // 3. Heartbeat stream (Ping every 15 seconds)
     let heartbeat_stream = IntervalStream::new(tokio::time::interval(Duration::from_secs(15)))
@@ -274,13 +304,47 @@ pub async fn get_chat_events(
             Ok(Event::default().data(data))
         });
 
-    // Chain them together and select with heartbeat
-    let main_stream = init_stream.chain(broadcast_stream);
-    let combined_stream = stream::select(main_stream, heartbeat_stream);
+    // Select with heartbeat
+    let combined_stream = stream::select(broadcast_stream, heartbeat_stream);
 
     Ok(Sse::new(combined_stream).keep_alive(KeepAlive::default()))
 }
 
+/// Converts a chat stream event to an SSE event, with its log id as the event id
+fn to_sse_event(event: &StreamEvent) -> Option<Event> {
+    match serde_json::to_string(&event.event) {
+        Ok(data) => {
+            let sse_event = Event::default().data(data);
+            Some(match event.id {
+                Some(id) => sse_event.id(id.to_string()),
+                None => sse_event,
+            })
+        }
+        Err(e) => {
+            tracing::error!("[SSE] Failed to serialize SSE event: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Loads the logged events of a chat emitted after the event `after_id`
+async fn replay_stream_events(pool: &crate::database::DbPool, chat_id: Uuid, after_id: i64) -> Vec<StreamEvent> {
+    let events = match pool.acquire().await {
+        Ok(mut conn) => ChatService::get_stream_events_after(&mut conn, chat_id, after_id).await,
+        Err(e) => Err(Error::Sqlx(e)),
+    };
+    match events {
+        Ok(events) => {
+            tracing::info!(chat_id = %chat_id, after_id, count = events.len(), "[SSE] Replaying missed events");
+            events
+        }
+        Err(e) => {
+            tracing::warn!(chat_id = %chat_id, after_id, error = %e, "[SSE] Failed to replay missed events");
+            Vec::new()
+        }
+    }
+}
+
 pub async fn post_chat_message(
     State(state): State<AppState>,
     Extension(user): Extension<AuthenticatedUser>,
@@ -290,8 +354,41 @@ pub async fn post_chat_message(
     tracing::info!("[ChatHandler] Received message for chat {} from user {}", chat_id, user.id);
     let mut conn = state.pool.acquire().await.map_err(Error::Sqlx)?;
 
+    // Answers to approval requests resume the waiting tool call instead of starting a turn
+    if let Some(answer) = req.metadata.as_ref().and_then(|m| m.get("question_answer"))
+        && let Ok(answer) = serde_json::from_value::<QuestionAnswerMetadata>(answer.clone())
+        && let Some(approved) = approvals::approval_answer(&answer.answers)
+    {
+        answer_tool_approval(&state, &mut conn, chat_id, answer.question_id, approved).await?;
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({ "status": "accepted" })),
+        ));
+    }
+
     // 1. Append message to DB (Persistence first!) via Service for Write-Through
-    use crate::services::chat::ChatService;
+    let skill_attachments = crate::services::skills::resolve_skill_attachments(
+        &mut conn,
+        &state.storage,
+        workspace_id,
+        req.skills.as_deref().unwrap_or_default(),
+    ).await?;
+    let image_attachments = crate::services::images::resolve_image_attachments(
+        req.images.as_deref().unwrap_or_default(),
+    ).await?;
+    let url_attachments = crate::services::web_pages::resolve_url_attachments(
+        &mut conn,
+        &state.storage,
+        workspace_id,
+        user.id,
+        req.urls.as_deref().unwrap_or_default(),
+    ).await?;
+    let attachments = req.files.clone().unwrap_or_default().into_iter()
+        .map(|file_id| ChatAttachment::File { file_id, version_id: None })
+        .chain(skill_attachments)
+        .chain(image_attachments)
+        .chain(url_attachments)
+        .collect();
 
     // Get model for metadata (from request or current chat config)
     let model_for_metadata = if let Some(ref model) = req.model {
@@ -308,6 +405,8 @@ pub async fn post_chat_message(
                 previous_response_id: None,
                 mode: "plan".to_string(),
                 plan_file: None,
+                parent_chat_id: None,
+                allowed_tools: None,
             });
         agent_config.model
     };
@@ -318,6 +417,7 @@ pub async fn post_chat_message(
         role: ChatMessageRole::User,
         content: req.content.clone(),
         metadata: sqlx::types::Json(ChatMessageMetadata {
+            attachments,
             model: Some(model_for_metadata),
             question_answer: req.metadata.as_ref()
                 .and_then(|m| m.get("question_answer"))
@@ -347,33 +447,20 @@ pub async fn post_chat_message(
         }
     }
 
-    // 3. Signal Actor
-    let handle = if let Some(handle) = state.agents.get_handle(&chat_id).await {
-        handle
-    } else {
-        // Rehydrate actor
-        let event_tx = state.agents.get_or_create_bus(chat_id).await;
-        let default_persona = get_chat_persona(&mut conn, chat_id).await?;
-        let handle = ChatActor::spawn(crate::services::chat::actor::ChatActorArgs {
-            chat_id,
-            workspace_id,
-            user_id: user.id,
-            pool: state.pool.clone(),
-            rig_service: state.rig_service.clone(),
-            storage: state.storage.clone(),
-            registry: state.agents.clone(),
-            default_persona,
-            default_context_token_limit: state.config.ai.default_context_token_limit,
-            event_tx,
-            inactivity_timeout: std::time::Duration::from_secs(state.config.ai.actor_inactivity_timeout_seconds),
-        });
-        state.agents.register(chat_id, handle.clone()).await;
-        handle
-    };
-
-    let _ = handle.command_tx.send(AgentCommand::ProcessInteraction {
-        user_id: user.id,
-    }).await;
+    // 3. Signal Actor (rehydrate if needed)
+    match locate_chat_actor(&state, &mut conn, chat_id, workspace_id, user.id).await? {
+        ChatActorLocation::Local(handle) => {
+            let _ = handle.command_tx.send(AgentCommand::ProcessInteraction {
+                user_id: user.id,
+            }).await;
+        }
+        ChatActorLocation::Remote(owner) => {
+            state.agents.send_remote_command(owner, chat_id, RemoteCommand::ProcessInteraction {
+                workspace_id,
+                user_id: user.id,
+            }).await?;
+        }
+    }
 
     tracing::info!("[ChatHandler] Interaction command sent to actor for chat {}", chat_id);
 
@@ -383,6 +470,38 @@ pub async fn post_chat_message(
     ))
 }
 
+/// Resumes a tool call of a chat waiting for the user's approval, on the
+/// instance running the chat's actor
+async fn answer_tool_approval(
+    state: &AppState,
+    conn: &mut DbConn,
+    chat_id: Uuid,
+    question_id: Uuid,
+    approved: bool,
+) -> Result<()> {
+    tracing::info!(
+        chat_id = %chat_id,
+        question_id = %question_id,
+        approved,
+        "[ChatHandler] Answering tool call approval"
+    );
+    if state.agents.approvals.answer(chat_id, question_id, approved).await {
+        return Ok(());
+    }
+    match state.agents.remote_owner(conn, chat_id).await? {
+        Some(owner) => {
+            state.agents.send_remote_command(owner, chat_id, RemoteCommand::AnswerApproval {
+                question_id,
+                approved,
+            }).await
+        }
+        None => Err(Error::NotFound(format!(
+            "No tool call of chat {} is waiting for approval {}",
+            chat_id, question_id
+        ))),
+    }
+}
+
 pub async fn get_chat(
     State(state): State<AppState>,
     Extension(_user): Extension<AuthenticatedUser>,
@@ -450,6 +569,8 @@ pub async fn update_chat(
                 previous_response_id: None,
                 mode: "plan".to_string(),
                 plan_file: None,
+                parent_chat_id: None,
+                allowed_tools: None,
             });
         Some(agent_config.mode)
     } else {
@@ -457,7 +578,6 @@ pub async fn update_chat(
     };
 
     // Update chat metadata
-    use crate::services::chat::ChatService;
     ChatService::update_chat_metadata(
         &mut conn,
         &state.storage,
@@ -469,11 +589,10 @@ pub async fn update_chat(
 
     // Emit SSE event if mode changed
     if old_mode.as_deref() != Some(mode.as_str()) {
-        let event_tx = state.agents.get_or_create_bus(chat_id).await;
-        let _ = event_tx.send(SseEvent::ModeChanged {
+        state.agents.send_event(chat_id, SseEvent::ModeChanged {
             mode: mode.clone(),
             plan_file: plan_file.clone(),
-        });
+        }).await;
 
         tracing::info!(
             "[ChatHandler] Mode changed from {:?} to {} for chat {}",
@@ -524,12 +643,24 @@ pub async fn stop_chat_generation(
         chat_id
     );
 
-    // Get the actor handle
-    let handle = state
-        .agents
-        .get_handle(&chat_id)
-        .await
-        .ok_or_else(|| Error::NotFound(format!("Chat actor not found and no active stream for chat {}", chat_id)))?;
+    // Get the actor handle, or forward the stop to the instance running the actor
+    let Some(handle) = state.agents.get_handle(&chat_id).await else {
+        let mut conn = state.pool.acquire().await.map_err(Error::Sqlx)?;
+        let owner = state
+            .agents
+            .remote_owner(&mut conn, chat_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Chat actor not found and no active stream for chat {}", chat_id)))?;
+
+        state.agents.send_remote_command(owner, chat_id, RemoteCommand::Cancel {
+            reason: "user_cancelled".to_string(),
+        }).await?;
+
+        return Ok(Json(serde_json::json!({
+            "status": "cancelled",
+            "chat_id": chat_id
+        })));
+    };
 
     // Create a one-shot channel for response
     let (responder, response) = oneshot::channel();
@@ -558,6 +689,26 @@ pub async fn stop_chat_generation(
     })))
 }
 
+/// GET /workspaces/{id}/chats/{chat_id}/plan
+///
+/// Returns the step checklist and progress of the chat's active plan.
+pub async fn get_chat_plan(
+    State(state): State<AppState>,
+    Extension(_user): Extension<AuthenticatedUser>,
+    Path((workspace_id, chat_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<crate::models::chat::PlanProgressResponse>> {
+    let mut conn = state.pool.acquire().await.map_err(Error::Sqlx)?;
+
+    let plan_progress = crate::services::chat::ChatService::get_plan_progress(
+        &mut conn,
+        &state.storage,
+        workspace_id,
+        chat_id,
+    ).await?;
+
+    Ok(Json(plan_progress))
+}
+
 /// GET /workspaces/{id}/chats/{chat_id}/context
 ///
 /// Returns detailed information about everything sent to the AI for debugging
@@ -585,3 +736,34 @@ pub async fn get_chat_context(
 
     Ok(Json(context_response))
 }
+
+/// POST /workspaces/{id}/chats/{chat_id}/compact
+///
+/// Summarizes the older history of the chat now, regardless of its size. The
+/// summary replaces the covered messages in the AI context, which stay in the
+/// chat's history.
+pub async fn compact_chat(
+    State(state): State<AppState>,
+    Extension(_user): Extension<AuthenticatedUser>,
+    Path((workspace_id, chat_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<crate::models::chat::CompactChatResponse>> {
+    tracing::info!(
+        "[ChatHandler] Compaction requested for chat {} in workspace {}",
+        chat_id, workspace_id
+    );
+
+    let mut conn = state.pool.acquire().await.map_err(Error::Sqlx)?;
+
+    let response = crate::services::chat::compaction::compact_chat(
+        &mut conn,
+        &state.storage,
+        &state.rig_service,
+        &state.config.ai.compaction,
+        workspace_id,
+        chat_id,
+        state.config.ai.default_context_token_limit,
+        true,
+    ).await?;
+
+    Ok(Json(response))
+}
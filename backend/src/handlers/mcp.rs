@@ -0,0 +1,94 @@
+//! Workspace MCP endpoint
+//!
+//! Publishes the workspace tools to external MCP clients (agents, IDEs) over the
+//! streamable HTTP transport. The protocol itself lives in
+//! [`crate::services::mcp::server`].
+
+use axum::{
+    body::Bytes,
+    extract::{Extension, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use crate::{
+    error::{Error, Result},
+    middleware::workspace_access::WorkspaceAccess,
+    models::{mcp::McpEndpointQuery, permissions::workspace_permissions},
+    services::mcp::server::{self, McpServerContext},
+    state::AppState,
+};
+
+/// POST /api/v1/workspaces/:id/mcp
+///
+/// Handles one JSON-RPC message of the Model Context Protocol. Requests are
+/// answered with a JSON body, notifications with `202 Accepted`.
+///
+/// # Authentication & Authorization
+/// - Requires valid JWT token or API key (via workspace_access_middleware)
+/// - User must be a member of the workspace
+/// - API keys without `workspace:write` only get the read-only tools
+///
+/// # Query Parameters
+/// - `plan_mode=true`: restrict modifying tools to plan files, like Plan Mode chats
+///
+/// # HTTP Status Codes
+/// - `200 OK`: JSON-RPC response, including tool errors and protocol errors
+/// - `202 ACCEPTED`: Notification received
+/// - `400 BAD_REQUEST`: Body is not JSON, or unsupported `MCP-Protocol-Version`
+/// - `403 FORBIDDEN`: Not a member of the workspace
+pub async fn handle_mcp_request(
+    State(state): State<AppState>,
+    Extension(workspace_access): Extension<WorkspaceAccess>,
+    Query(query): Query<McpEndpointQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response> {
+    if let Some(version) = headers.get("mcp-protocol-version").and_then(|v| v.to_str().ok())
+        && !server::is_supported_protocol_version(version)
+    {
+        return Err(Error::Validation(crate::error::ValidationErrors::Single {
+            field: "MCP-Protocol-Version".to_string(),
+            message: format!("Unsupported MCP protocol version: {}", version),
+        }));
+    }
+
+    let message: serde_json::Value = match serde_json::from_slice(&body) {
+        Ok(message) => message,
+        Err(e) => {
+            return Ok((StatusCode::BAD_REQUEST, Json(server::parse_error(&e.to_string()))).into_response());
+        }
+    };
+
+    let context = McpServerContext {
+        workspace_id: workspace_access.workspace_id,
+        user_id: workspace_access.user_id,
+        plan_mode: query.plan_mode,
+        can_write: workspace_access
+            .require_api_key_permission(workspace_permissions::WRITE)
+            .is_ok(),
+    };
+
+    let mut conn = state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = "handle_mcp_request",
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        Error::Sqlx(e)
+    })?;
+
+    match server::handle_message(&mut conn, &state.storage, &context, message).await {
+        Some(response) => Ok(Json(response).into_response()),
+        None => Ok(StatusCode::ACCEPTED.into_response()),
+    }
+}
+
+/// GET and DELETE /api/v1/workspaces/:id/mcp
+///
+/// The server is stateless: it neither streams server-initiated messages nor
+/// keeps sessions to terminate.
+pub async fn mcp_method_not_allowed() -> Response {
+    (StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, "POST")]).into_response()
+}
@@ -0,0 +1,201 @@
+//! Workspace MCP server handlers
+//!
+//! An MCP server is an external tool server speaking the Model Context Protocol.
+//! Its tools are offered to the workspace's chat agents as `mcp__{name}__{tool}`.
+
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use uuid::Uuid;
+use crate::{
+    error::{Error, Result},
+    middleware::auth::AuthenticatedUser,
+    models::mcp::{CreateMcpServerRequest, UpdateMcpServerRequest},
+    services::mcp,
+    state::AppState,
+};
+
+/// GET /api/v1/workspaces/:id/mcp-servers
+///
+/// Lists the workspace's MCP servers. Environment variable and header values are
+/// not returned. Requires `workspace:manage_settings`.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Servers retrieved successfully
+/// - `403 FORBIDDEN`: Insufficient permissions
+pub async fn list_mcp_servers(
+    State(state): State<AppState>,
+    Path(workspace_id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = acquire_db_connection(&state, "list_mcp_servers").await?;
+
+    let servers = mcp::list_mcp_servers(&mut conn, workspace_id, auth_user.id)
+        .await
+        .inspect_err(|e| log_handler_error("list_mcp_servers", e))?;
+
+    Ok(Json(serde_json::json!({
+        "mcp_servers": servers,
+        "count": servers.len(),
+    })))
+}
+
+/// POST /api/v1/workspaces/:id/mcp-servers
+///
+/// Registers an MCP server. Requires `workspace:manage_settings`.
+///
+/// # Request Body
+/// ```json
+/// { "name": "github", "transport": "http", "url": "https://mcp.example.com/mcp",
+///   "headers": { "Authorization": "Bearer ..." }, "timeout_seconds": 30 }
+/// ```
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Server registered
+/// - `400 BAD_REQUEST`: Invalid name, command, URL, headers or timeout
+/// - `403 FORBIDDEN`: Insufficient permissions, or stdio servers are disabled
+/// - `409 CONFLICT`: A server with this name already exists
+pub async fn create_mcp_server(
+    State(state): State<AppState>,
+    Path(workspace_id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    Json(request): Json<CreateMcpServerRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = acquire_db_connection(&state, "create_mcp_server").await?;
+
+    let server = mcp::create_mcp_server(
+        &mut conn,
+        state.rig_service.mcp_clients(),
+        &state.config.ai.mcp,
+        workspace_id,
+        auth_user.id,
+        request,
+    )
+    .await
+    .inspect_err(|e| log_handler_error("create_mcp_server", e))?;
+
+    Ok(Json(serde_json::json!({
+        "mcp_server": server,
+    })))
+}
+
+/// PATCH /api/v1/workspaces/:id/mcp-servers/:server_id
+///
+/// Changes an MCP server, e.g. `{ "enabled": false }`. Omitted fields keep their
+/// value. Running chats pick up the change with their next message.
+/// Requires `workspace:manage_settings`.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Server updated
+/// - `400 BAD_REQUEST`: Invalid command, URL, headers or timeout
+/// - `403 FORBIDDEN`: Insufficient permissions
+/// - `404 NOT_FOUND`: Server not found in this workspace
+pub async fn update_mcp_server(
+    State(state): State<AppState>,
+    Path((workspace_id, server_id)): Path<(Uuid, Uuid)>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    Json(request): Json<UpdateMcpServerRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = acquire_db_connection(&state, "update_mcp_server").await?;
+
+    let server = mcp::update_mcp_server(
+        &mut conn,
+        state.rig_service.mcp_clients(),
+        &state.config.ai.mcp,
+        workspace_id,
+        server_id,
+        auth_user.id,
+        request,
+    )
+    .await
+    .inspect_err(|e| log_handler_error("update_mcp_server", e))?;
+
+    Ok(Json(serde_json::json!({
+        "mcp_server": server,
+    })))
+}
+
+/// DELETE /api/v1/workspaces/:id/mcp-servers/:server_id
+///
+/// Removes an MCP server and closes its connection.
+/// Requires `workspace:manage_settings`.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Server removed
+/// - `403 FORBIDDEN`: Insufficient permissions
+/// - `404 NOT_FOUND`: Server not found in this workspace
+pub async fn delete_mcp_server(
+    State(state): State<AppState>,
+    Path((workspace_id, server_id)): Path<(Uuid, Uuid)>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = acquire_db_connection(&state, "delete_mcp_server").await?;
+
+    mcp::delete_mcp_server(&mut conn, state.rig_service.mcp_clients(), workspace_id, server_id, auth_user.id)
+        .await
+        .inspect_err(|e| log_handler_error("delete_mcp_server", e))?;
+
+    Ok(Json(serde_json::json!({
+        "message": "MCP server removed successfully"
+    })))
+}
+
+/// GET /api/v1/workspaces/:id/mcp-servers/:server_id/tools
+///
+/// Connects to the server and lists the tools agents get from it.
+/// Requires `workspace:manage_settings`.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Tools retrieved successfully
+/// - `403 FORBIDDEN`: Insufficient permissions
+/// - `404 NOT_FOUND`: Server not found in this workspace
+/// - `502 BAD_GATEWAY`: The server could not be reached or failed
+pub async fn list_mcp_server_tools(
+    State(state): State<AppState>,
+    Path((workspace_id, server_id)): Path<(Uuid, Uuid)>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = acquire_db_connection(&state, "list_mcp_server_tools").await?;
+
+    let tools = mcp::list_mcp_server_tools(
+        &mut conn,
+        state.rig_service.mcp_clients(),
+        &state.config.ai.mcp,
+        workspace_id,
+        server_id,
+        auth_user.id,
+    )
+    .await
+    .inspect_err(|e| log_handler_error("list_mcp_server_tools", e))?;
+
+    Ok(Json(serde_json::json!({
+        "tools": tools,
+        "count": tools.len(),
+    })))
+}
+
+/// Helper to log handler errors with appropriate severity
+fn log_handler_error(operation: &str, e: &Error) {
+    match e {
+        Error::Validation(_) | Error::NotFound(_) | Error::Forbidden(_) | Error::Conflict(_) => {
+            tracing::warn!(operation = operation, error = %e, "Handler operation failed");
+        }
+        _ => {
+            tracing::error!(operation = operation, error = %e, "Handler operation failed");
+        }
+    }
+}
+
+/// Helper to acquire database connection with consistent error logging
+async fn acquire_db_connection(state: &AppState, operation: &'static str) -> Result<sqlx::pool::PoolConnection<sqlx::Postgres>> {
+    state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = operation,
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })
+}
@@ -14,7 +14,7 @@ use crate::{
     middleware::auth::AuthenticatedUser,
     middleware::workspace_access::WorkspaceAccess,
     models::workspace_members::{AddMemberRequest, UpdateMemberRoleRequest},
-    services::workspace_members,
+    services::{two_factor, workspace_members},
     state::AppState,
 };
 
@@ -317,6 +317,71 @@ pub async fn remove_member(
     })))
 }
 
+// ============================================================================
+// RESET MEMBER TWO-FACTOR
+// ============================================================================
+
+/// DELETE /api/v1/workspaces/:id/members/:user_id/two-factor
+///
+/// Removes two-factor authentication from a member who lost their authenticator and
+/// recovery codes, and signs them out everywhere.
+/// - Requires members:write permission.
+/// - Cannot reset the workspace owner or yourself.
+/// - Not available to API keys.
+///
+/// # Parameters
+/// - `id`: Workspace UUID
+/// - `user_id`: UUID of the member
+///
+/// # Headers
+/// - Authorization: Bearer <access_token>
+///
+/// # Returns
+/// JSON response confirming the reset.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Two-factor authentication removed
+/// - `400 BAD_REQUEST`: Attempting to reset yourself
+/// - `403 FORBIDDEN`: Insufficient permissions, API key, or attempting to reset the owner
+/// - `404 NOT_FOUND`: Not a member, or two-factor authentication is not enabled
+pub async fn reset_member_two_factor(
+    State(state): State<AppState>,
+    Extension(workspace_access): Extension<WorkspaceAccess>,
+    Path((workspace_id, target_user_id)): Path<(Uuid, Uuid)>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    tracing::info!(
+        operation = "reset_member_two_factor",
+        workspace_id = %workspace_id,
+        requester_id = %auth_user.id,
+        target_user_id = %target_user_id,
+        "Resetting member two-factor authentication",
+    );
+
+    if workspace_access.api_key.is_some() {
+        let e = Error::Forbidden("API keys cannot reset two-factor authentication".to_string());
+        log_handler_error("reset_member_two_factor", &e);
+        return Err(e);
+    }
+
+    let mut conn = acquire_db_connection(&state, "reset_member_two_factor").await?;
+
+    two_factor::reset_member_two_factor(&mut conn, workspace_id, auth_user.id, target_user_id)
+        .await
+        .inspect_err(|e| log_handler_error("reset_member_two_factor", e))?;
+
+    tracing::info!(
+        operation = "reset_member_two_factor",
+        workspace_id = %workspace_id,
+        user_id = %target_user_id,
+        "Member two-factor authentication reset successfully",
+    );
+
+    Ok(Json(serde_json::json!({
+        "message": "Two-factor authentication reset successfully",
+    })))
+}
+
 // ============================================================================
 // HELPER FUNCTIONS
 // ============================================================================
@@ -1,4 +1,5 @@
 pub mod agent_sessions;
+pub mod api_keys;
 pub mod auth;
 pub mod chat;
 pub mod chats;
@@ -8,6 +9,9 @@ pub mod members;
 pub mod files;
 pub mod tools;
 pub mod providers;
+pub mod sso_domains;
+pub mod mcp;
+pub mod mcp_servers;
 
 pub use agent_sessions::*;
 pub use auth::*;
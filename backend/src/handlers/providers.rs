@@ -51,6 +51,9 @@ pub struct ModelInfo {
     /// Whether this model is free to use
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_free: Option<bool>,
+    /// Whether this model accepts images
+    #[serde(default)]
+    pub supports_vision: bool,
 }
 
 /// Provider configuration response
@@ -99,6 +102,7 @@ async fn build_providers_response(
                     context_window: m.context_window,
                     is_default,
                     is_free: Some(m.is_free),
+                    supports_vision: m.supports_vision,
                 }
             })
             .collect();
@@ -0,0 +1,160 @@
+//! Workspace SSO domain handlers
+//!
+//! An SSO domain lets users with an email address at that domain join the workspace
+//! automatically, with a chosen role, the first time they sign in with single sign-on.
+//! A domain takes effect once it is verified through a DNS TXT record.
+
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use uuid::Uuid;
+use crate::{
+    error::{Error, Result},
+    middleware::auth::AuthenticatedUser,
+    models::oidc::{CreateSsoDomainRequest, WorkspaceSsoDomainDetailed},
+    services::oidc,
+    state::AppState,
+};
+
+/// GET /api/v1/workspaces/:id/sso-domains
+///
+/// Lists the workspace's SSO domains. Requires `workspace:manage_members`.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Domains retrieved successfully
+/// - `403 FORBIDDEN`: Insufficient permissions
+pub async fn list_sso_domains(
+    State(state): State<AppState>,
+    Path(workspace_id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = acquire_db_connection(&state, "list_sso_domains").await?;
+
+    let domains = oidc::list_sso_domains(&mut conn, workspace_id, auth_user.id)
+        .await
+        .inspect_err(|e| log_handler_error("list_sso_domains", e))?;
+
+    Ok(Json(serde_json::json!({
+        "sso_domains": domains,
+        "count": domains.len(),
+    })))
+}
+
+/// POST /api/v1/workspaces/:id/sso-domains
+///
+/// Registers an email domain for auto-join. Requires `workspace:manage_members`.
+/// The response names the DNS TXT record to publish before verifying the domain.
+///
+/// # Request Body
+/// ```json
+/// { "domain": "example.com", "role_name": "member" }
+/// ```
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Domain registered, not yet verified
+/// - `400 BAD_REQUEST`: Invalid domain, or a public email provider
+/// - `403 FORBIDDEN`: Insufficient permissions
+/// - `404 NOT_FOUND`: Role not found
+/// - `409 CONFLICT`: Domain already registered for this workspace
+pub async fn create_sso_domain(
+    State(state): State<AppState>,
+    Path(workspace_id): Path<Uuid>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    Json(request): Json<CreateSsoDomainRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = acquire_db_connection(&state, "create_sso_domain").await?;
+
+    let domain = oidc::create_sso_domain(&mut conn, workspace_id, auth_user.id, request)
+        .await
+        .inspect_err(|e| log_handler_error("create_sso_domain", e))?;
+
+    Ok(Json(serde_json::json!({
+        "verification_record": verification_record(&domain),
+        "sso_domain": domain,
+    })))
+}
+
+/// POST /api/v1/workspaces/:id/sso-domains/:domain_id/verify
+///
+/// Verifies an SSO domain by looking up its DNS TXT record. Requires
+/// `workspace:manage_members`.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Domain verified
+/// - `403 FORBIDDEN`: Insufficient permissions, or the TXT record is missing
+/// - `404 NOT_FOUND`: Domain not found in this workspace
+pub async fn verify_sso_domain(
+    State(state): State<AppState>,
+    Path((workspace_id, domain_id)): Path<(Uuid, Uuid)>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = acquire_db_connection(&state, "verify_sso_domain").await?;
+
+    let domain = oidc::verify_sso_domain(&mut conn, &state.config.oidc, workspace_id, domain_id, auth_user.id)
+        .await
+        .inspect_err(|e| log_handler_error("verify_sso_domain", e))?;
+
+    Ok(Json(serde_json::json!({
+        "sso_domain": domain,
+    })))
+}
+
+/// DELETE /api/v1/workspaces/:id/sso-domains/:domain_id
+///
+/// Removes an SSO domain. Members who already joined keep their membership.
+/// Requires `workspace:manage_members`.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Domain removed
+/// - `403 FORBIDDEN`: Insufficient permissions
+/// - `404 NOT_FOUND`: Domain not found in this workspace
+pub async fn delete_sso_domain(
+    State(state): State<AppState>,
+    Path((workspace_id, domain_id)): Path<(Uuid, Uuid)>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = acquire_db_connection(&state, "delete_sso_domain").await?;
+
+    oidc::delete_sso_domain(&mut conn, workspace_id, domain_id, auth_user.id)
+        .await
+        .inspect_err(|e| log_handler_error("delete_sso_domain", e))?;
+
+    Ok(Json(serde_json::json!({
+        "message": "SSO domain removed successfully"
+    })))
+}
+
+/// The DNS TXT record that verifies a domain
+fn verification_record(domain: &WorkspaceSsoDomainDetailed) -> serde_json::Value {
+    serde_json::json!({
+        "type": "TXT",
+        "name": oidc::verification_record_name(&domain.domain),
+        "value": oidc::verification_record_value(&domain.verification_token),
+    })
+}
+
+/// Helper to log handler errors with appropriate severity
+fn log_handler_error(operation: &str, e: &Error) {
+    match e {
+        Error::Validation(_) | Error::NotFound(_) | Error::Forbidden(_) | Error::Conflict(_) => {
+            tracing::warn!(operation = operation, error = %e, "Handler operation failed");
+        }
+        _ => {
+            tracing::error!(operation = operation, error = %e, "Handler operation failed");
+        }
+    }
+}
+
+/// Helper to acquire database connection with consistent error logging
+async fn acquire_db_connection(state: &AppState, operation: &'static str) -> Result<sqlx::pool::PoolConnection<sqlx::Postgres>> {
+    state.pool.acquire().await.map_err(|e| {
+        tracing::error!(
+            operation = operation,
+            error_code = "DATABASE_ACQUISITION_FAILED",
+            error = %e,
+            "Failed to acquire database connection",
+        );
+        Error::Internal(format!("Failed to acquire database connection: {}", e))
+    })
+}
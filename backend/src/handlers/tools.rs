@@ -6,7 +6,8 @@ use axum::{extract::{Extension, State}, Json};
 use crate::{
     error::{Error, Result},
     middleware::workspace_access::WorkspaceAccess,
-    models::requests::ToolRequest,
+    models::{permissions::workspace_permissions, requests::ToolRequest},
+    services::workspaces,
     tools,
     state::AppState,
 };
@@ -16,8 +17,11 @@ use crate::{
 /// Executes a tool with given arguments.
 ///
 /// # Authentication & Authorization
-/// - Requires valid JWT token (via workspace_access_middleware)
+/// - Requires valid JWT token or API key (via workspace_access_middleware)
 /// - User must be a member of the workspace
+/// - API keys need `workspace:read` for read-only tools and `workspace:write` for the rest
+/// - Calls the workspace's tool policy denies or asks about are refused with 403, there
+///   is no chat to ask for approval in
 ///
 /// # Request Body
 /// ```json
@@ -59,6 +63,9 @@ use crate::{
 ///   - args: { "path": "/folder" }
 /// - `touch`: Create empty file
 ///   - args: { "path": "/file.txt" }
+/// - `run`: Run a shell command in the sandbox (when enabled)
+///   - args: { "command": "make test", "cwd": "/project"?, "timeout": 60? }
+///   - Plan mode: Not allowed
 ///
 /// # Response
 /// ```json
@@ -84,11 +91,20 @@ pub async fn execute_tool(
     let mut conn = acquire_db_connection(&state, "execute_tool").await?;
 
     let executor = tools::get_tool_executor(&request.tool)?;
+    if !executor.is_read_only() {
+        workspace_access.require_api_key_permission(workspace_permissions::WRITE)?;
+    }
+    workspaces::authorize_tool_call(&mut conn, workspace_access.workspace_id, &request.tool, &request.args)
+        .await
+        .inspect_err(|e| log_handler_error("execute_tool", e))?;
 
     // Build ToolConfig from request (explicit mode selection)
     let config = tools::ToolConfig {
         plan_mode: request.plan_mode,
         active_plan_path: None, // Public API has no active plan context
+        sandbox: state.config.ai.sandbox.clone(),
+        web_search: state.config.ai.web_search.clone(),
+        ..Default::default() // Public API has no agent file restrictions
     };
 
     let response = executor
@@ -13,8 +13,13 @@ use crate::{
     error::{Error, Result},
     middleware::auth::AuthenticatedUser,
     middleware::workspace_access::WorkspaceAccess,
-    models::requests::{CreateWorkspaceHttp, CreateWorkspaceRequest, UpdateWorkspaceRequest},
-    services::workspaces,
+    models::{
+        requests::{CreateWorkspaceHttp, CreateWorkspaceRequest, UpdateWorkspaceRequest},
+        tool_policy::UpdateToolPolicyRequest,
+        two_factor::UpdateTwoFactorPolicyRequest,
+        workspaces::UpdateAiFallbackModelsRequest,
+    },
+    services::{two_factor, workspaces},
     state::AppState,
 };
 
@@ -308,6 +313,192 @@ pub async fn update_workspace(
     })))
 }
 
+// ============================================================================
+// UPDATE TWO-FACTOR POLICY
+// ============================================================================
+
+/// PATCH /api/v1/workspaces/:id/two-factor-policy
+///
+/// Requires (or stops requiring) two-factor authentication for every member.
+/// Members without 2FA are rejected with `TWO_FACTOR_REQUIRED` until they enroll.
+/// Requires workspace ownership, and the owner must have 2FA enabled to turn it on.
+///
+/// # Parameters
+/// - `id`: Workspace UUID
+///
+/// # Request Body
+/// - `require_two_factor`: Whether members need two-factor authentication
+///
+/// # Returns
+/// JSON response containing the updated workspace.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Policy updated successfully
+/// - `403 FORBIDDEN`: User is not the workspace owner, or has no 2FA themselves
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn update_two_factor_policy(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    Path(workspace_id): Path<Uuid>,
+    Json(request): Json<UpdateTwoFactorPolicyRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = acquire_db_connection(&state, "update_two_factor_policy").await?;
+
+    let workspace = match two_factor::set_workspace_policy(
+        &mut conn,
+        workspace_id,
+        auth_user.id,
+        request.require_two_factor,
+    ).await {
+        Ok(workspace) => workspace,
+        Err(e) => {
+            handle_workspace_error!("update_two_factor_policy", &e);
+            return Err(e);
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "workspace": workspace,
+    })))
+}
+
+// ============================================================================
+// UPDATE AI FALLBACK MODELS
+// ============================================================================
+
+/// PATCH /api/v1/workspaces/:id/ai-fallback-models
+///
+/// Sets the models the workspace's chats switch to, in order, when their model's
+/// provider keeps failing after retries. An empty list turns fallback off.
+/// Requires `workspace:manage_settings`.
+///
+/// # Parameters
+/// - `id`: Workspace UUID
+///
+/// # Request Body
+/// - `fallback_models`: `provider:model` identifiers, e.g. `["openrouter:anthropic/claude-3.5-sonnet"]`
+///
+/// # Returns
+/// JSON response containing the updated workspace.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Fallback models updated successfully
+/// - `400 BAD_REQUEST`: Unknown provider, invalid format, duplicate or too many models
+/// - `403 FORBIDDEN`: Insufficient permissions
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn update_ai_fallback_models(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    Path(workspace_id): Path<Uuid>,
+    Json(request): Json<UpdateAiFallbackModelsRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = acquire_db_connection(&state, "update_ai_fallback_models").await?;
+
+    let workspace = match workspaces::set_ai_fallback_models(
+        &mut conn,
+        workspace_id,
+        auth_user.id,
+        request,
+    ).await {
+        Ok(workspace) => workspace,
+        Err(e) => {
+            handle_workspace_error!("update_ai_fallback_models", &e);
+            return Err(e);
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "workspace": workspace,
+    })))
+}
+
+// ============================================================================
+// TOOL POLICY
+// ============================================================================
+
+/// GET /api/v1/workspaces/:id/tool-policy
+///
+/// Gets the rules deciding which tool calls of the workspace's chats run, wait
+/// for the user's approval or are refused.
+/// Requires `workspace:read`.
+///
+/// # Parameters
+/// - `id`: Workspace UUID
+///
+/// # Returns
+/// JSON response containing the tool policy, with no rules if none was set.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Tool policy returned
+/// - `403 FORBIDDEN`: Insufficient permissions
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn get_tool_policy(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    Path(workspace_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = acquire_db_connection(&state, "get_tool_policy").await?;
+
+    let policy = match workspaces::get_tool_policy(&mut conn, workspace_id, auth_user.id).await {
+        Ok(policy) => policy,
+        Err(e) => {
+            handle_workspace_error!("get_tool_policy", &e);
+            return Err(e);
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "tool_policy": policy,
+    })))
+}
+
+/// PUT /api/v1/workspaces/:id/tool-policy
+///
+/// Replaces the workspace's tool policy. Each call is matched against the rules
+/// in order and the first matching rule decides; calls no rule matches run.
+/// Requires `workspace:manage_settings`.
+///
+/// # Parameters
+/// - `id`: Workspace UUID
+///
+/// # Request Body
+/// - `rules`: Rules with a `tool` pattern, optional `arguments` patterns by
+///   argument name, an `action` (`allow`, `ask` or `deny`) and an optional `reason`
+///
+/// # Returns
+/// JSON response containing the updated tool policy.
+///
+/// # HTTP Status Codes
+/// - `200 OK`: Tool policy updated successfully
+/// - `400 BAD_REQUEST`: Invalid pattern, reason or too many rules
+/// - `403 FORBIDDEN`: Insufficient permissions
+/// - `500 INTERNAL_SERVER_ERROR`: Database error
+pub async fn update_tool_policy(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthenticatedUser>,
+    Path(workspace_id): Path<Uuid>,
+    Json(request): Json<UpdateToolPolicyRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = acquire_db_connection(&state, "update_tool_policy").await?;
+
+    let policy = match workspaces::set_tool_policy(
+        &mut conn,
+        workspace_id,
+        auth_user.id,
+        request,
+    ).await {
+        Ok(policy) => policy,
+        Err(e) => {
+            handle_workspace_error!("update_tool_policy", &e);
+            return Err(e);
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "tool_policy": policy,
+    })))
+}
+
 // ============================================================================
 // DELETE WORKSPACE
 // ============================================================================
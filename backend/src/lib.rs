@@ -22,6 +22,11 @@ pub use error::{Error, Result, ValidationErrors};
 pub use handlers::{
     agent_sessions::{list_workspace_sessions, get_session, pause_session, resume_session, cancel_session},
     auth::login, auth::logout, auth::me, auth::register, auth::refresh,
+    auth::forgot_password, auth::reset_password, auth::verify_email, auth::resend_email_verification,
+    auth::oidc_authorize, auth::oidc_callback,
+    auth::login_two_factor, auth::two_factor_status, auth::begin_two_factor_enrollment,
+    auth::confirm_two_factor_enrollment, auth::regenerate_recovery_codes, auth::disable_two_factor,
+    auth::list_login_sessions, auth::revoke_login_session, auth::revoke_other_login_sessions,
     health::health_check, health::health_cache,
     members::list_members, members::get_my_membership, members::add_member, members::update_member_role, members::remove_member,
     workspaces::create_workspace, workspaces::list_workspaces, workspaces::get_workspace, workspaces::update_workspace, workspaces::delete_workspace,
@@ -35,7 +40,7 @@ pub use handlers::{
 };
 pub use middleware::auth::AuthenticatedUser;
 pub use state::AppState;
-pub use workers::{revoked_token_cleanup_worker, archive_cleanup_worker};
+pub use workers::{revoked_token_cleanup_worker, archive_cleanup_worker, agent_cluster_worker, stream_event_cleanup_worker};
 
 /// Load configuration from environment variables
 pub fn load_config() -> Result<Config> {
@@ -124,7 +129,7 @@ fn get_build_date() -> String {
     "unknown".to_string()
 }
 
-use axum::{Router, routing::{get, post, patch, delete}, middleware as axum_middleware, response::Response, extract::Request, http::HeaderName};
+use axum::{Router, routing::{get, post, patch, delete}, middleware as axum_middleware, response::Response, extract::{DefaultBodyLimit, Request}, http::HeaderName};
 use tokio::net::TcpListener;
 use tower::ServiceBuilder;
 use tower_http::cors::{Any, CorsLayer};
@@ -180,12 +185,27 @@ pub fn create_api_router(state: AppState) -> Router<AppState> {
         .route("/health", get(health_check))
         .route("/auth/register", post(register))
         .route("/auth/login", post(login))
+        .route("/auth/login/two-factor", post(login_two_factor))
         .route("/auth/logout", post(logout))
         .route("/auth/refresh", post(refresh))
+        .route("/auth/password/forgot", post(forgot_password))
+        .route("/auth/password/reset", post(reset_password))
+        .route("/auth/email/verify", post(verify_email))
+        .route("/auth/oidc/authorize", get(oidc_authorize))
+        .route("/auth/oidc/callback", get(oidc_callback))
         .merge(
             Router::new()
                 .route("/health/cache", get(health_cache))
                 .route("/auth/me", get(me))
+                .route("/auth/email/verification", post(resend_email_verification))
+                .route("/auth/two-factor", get(two_factor_status))
+                .route("/auth/two-factor/enroll", post(begin_two_factor_enrollment))
+                .route("/auth/two-factor/confirm", post(confirm_two_factor_enrollment))
+                .route("/auth/two-factor/recovery-codes", post(regenerate_recovery_codes))
+                .route("/auth/two-factor/disable", post(disable_two_factor))
+                .route("/auth/sessions", get(list_login_sessions))
+                .route("/auth/sessions/revoke-others", post(revoke_other_login_sessions))
+                .route("/auth/sessions/{session_id}", delete(revoke_login_session))
                 .route("/providers", get(get_providers))
                 // Agent session routes - global (scoped by session ownership)
                 .route("/agent-sessions/{id}", get(crate::handlers::get_session))
@@ -222,6 +242,10 @@ fn create_workspace_router(state: AppState) -> Router<AppState> {
     use crate::handlers::chat as chat_handlers;
     use crate::handlers::tools as tool_handlers;
     use crate::handlers::agent_sessions as agent_session_handlers;
+    use crate::handlers::api_keys as api_key_handlers;
+    use crate::handlers::sso_domains as sso_domain_handlers;
+    use crate::handlers::mcp_servers as mcp_server_handlers;
+    use crate::handlers::mcp as mcp_handlers;
     use crate::middleware::workspace_access::workspace_access_middleware;
 
     Router::new()
@@ -263,6 +287,136 @@ fn create_workspace_router(state: AppState) -> Router<AppState> {
                     workspace_access_middleware,
                 )),
         )
+        .route(
+            "/{id}/members/{user_id}/two-factor",
+            delete(member_handlers::reset_member_two_factor)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        .route(
+            "/{id}/two-factor-policy",
+            patch(workspace_handlers::update_two_factor_policy)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        .route(
+            "/{id}/ai-fallback-models",
+            patch(workspace_handlers::update_ai_fallback_models)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        .route(
+            "/{id}/tool-policy",
+            get(workspace_handlers::get_tool_policy)
+                .put(workspace_handlers::update_tool_policy)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        // API key & service account routes
+        .route(
+            "/{id}/api-keys",
+            get(api_key_handlers::list_api_keys)
+                .post(api_key_handlers::create_api_key)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        .route(
+            "/{id}/api-keys/{key_id}",
+            delete(api_key_handlers::revoke_api_key)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        .route(
+            "/{id}/service-accounts",
+            get(api_key_handlers::list_service_accounts)
+                .post(api_key_handlers::create_service_account)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        .route(
+            "/{id}/service-accounts/{service_account_id}",
+            delete(api_key_handlers::delete_service_account)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        .route(
+            "/{id}/service-accounts/{service_account_id}/api-keys",
+            get(api_key_handlers::list_service_account_api_keys)
+                .post(api_key_handlers::create_service_account_api_key)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        // SSO domain routes
+        .route(
+            "/{id}/sso-domains",
+            get(sso_domain_handlers::list_sso_domains)
+                .post(sso_domain_handlers::create_sso_domain)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        .route(
+            "/{id}/sso-domains/{domain_id}",
+            delete(sso_domain_handlers::delete_sso_domain)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        .route(
+            "/{id}/sso-domains/{domain_id}/verify",
+            post(sso_domain_handlers::verify_sso_domain)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        // MCP server routes
+        .route(
+            "/{id}/mcp-servers",
+            get(mcp_server_handlers::list_mcp_servers)
+                .post(mcp_server_handlers::create_mcp_server)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        .route(
+            "/{id}/mcp-servers/{server_id}",
+            patch(mcp_server_handlers::update_mcp_server)
+                .delete(mcp_server_handlers::delete_mcp_server)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
+        .route(
+            "/{id}/mcp-servers/{server_id}/tools",
+            get(mcp_server_handlers::list_mcp_server_tools)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
         // File routes
         .route(
             "/{id}/files",
@@ -378,6 +532,17 @@ fn create_workspace_router(state: AppState) -> Router<AppState> {
                     workspace_access_middleware,
                 )),
         )
+        // MCP endpoint publishing the workspace tools
+        .route(
+            "/{id}/mcp",
+            post(mcp_handlers::handle_mcp_request)
+                .get(mcp_handlers::mcp_method_not_allowed)
+                .delete(mcp_handlers::mcp_method_not_allowed)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
         // Provider routes
         .route(
             "/{id}/providers",
@@ -392,6 +557,7 @@ fn create_workspace_router(state: AppState) -> Router<AppState> {
             "/{id}/chats",
             get(crate::handlers::chats::list_chats)
                 .post(chat_handlers::create_chat)
+                .layer(DefaultBodyLimit::max(chat_handlers::MAX_CHAT_REQUEST_BYTES))
                 .route_layer(axum_middleware::from_fn_with_state(
                     state.clone(),
                     workspace_access_middleware,
@@ -410,6 +576,7 @@ fn create_workspace_router(state: AppState) -> Router<AppState> {
             post(chat_handlers::post_chat_message)
                 .get(chat_handlers::get_chat)
                 .patch(chat_handlers::update_chat)
+                .layer(DefaultBodyLimit::max(chat_handlers::MAX_CHAT_REQUEST_BYTES))
                 .route_layer(axum_middleware::from_fn_with_state(
                     state.clone(),
                     workspace_access_middleware,
@@ -423,6 +590,14 @@ fn create_workspace_router(state: AppState) -> Router<AppState> {
                     workspace_access_middleware,
                 )),
         )
+        .route(
+            "/{id}/chats/{chat_id}/plan",
+            get(chat_handlers::get_chat_plan)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
         .route(
             "/{id}/chats/{chat_id}/context",
             get(chat_handlers::get_chat_context)
@@ -431,6 +606,14 @@ fn create_workspace_router(state: AppState) -> Router<AppState> {
                     workspace_access_middleware,
                 )),
         )
+        .route(
+            "/{id}/chats/{chat_id}/compact",
+            post(chat_handlers::compact_chat)
+                .route_layer(axum_middleware::from_fn_with_state(
+                    state.clone(),
+                    workspace_access_middleware,
+                )),
+        )
         // Agent session routes - workspace scoped
         .route(
             "/{id}/agent-sessions",
@@ -476,6 +659,8 @@ pub async fn run_api_server(
 ) -> Result<()> {
     use secrecy::ExposeSecret;
 
+    utils::net::allow_private_hosts(config.server.allowed_private_hosts.split(','));
+
     // Create database connection pool
     let pool = DbPool::connect(config.database.connection_string().expose_secret())
         .await
@@ -503,12 +688,44 @@ pub async fn run_api_server(
         archive_cleanup_worker(pool_storage, shutdown_storage, archive_cleanup_rx, worker_config, storage_config).await;
     });
 
+    // Chat Stream Event Worker
+    let pool_stream_events = pool.clone();
+    let shutdown_stream_events = cleanup_shutdown_tx.subscribe();
+    let stream_event_retention_hours = config.ai.stream_event_retention_hours;
+    tokio::spawn(async move {
+        stream_event_cleanup_worker(pool_stream_events, shutdown_stream_events, stream_event_retention_hours).await;
+    });
+
+    // Web page cache cleanup (pages attached to chat messages)
+    tokio::spawn(async move {
+        run_cache_cleanup(crate::services::web_pages::page_cache().clone()).await;
+    });
+
+    // Password reset request counts cleanup
+    tokio::spawn(async move {
+        run_cache_cleanup(crate::services::users::password_reset_requests().clone()).await;
+    });
+
+    // Web search results cache cleanup
+    tokio::spawn(async move {
+        run_cache_cleanup(crate::services::web_search::search_cache().clone()).await;
+    });
+
     // Create user cache with configured TTL
     let user_cache = Cache::new_local(CacheConfig::default());
 
     // Build the application state with cache, user_cache, database pool, and config
     let app_state = AppState::new(cache, user_cache, pool, rig_service, config.clone(), archive_cleanup_tx);
 
+    // Cluster Worker (multi-instance deployments only)
+    if config.ai.cluster.enabled {
+        let cluster_state = app_state.clone();
+        let shutdown_cluster = cleanup_shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            agent_cluster_worker(cluster_state, shutdown_cluster).await;
+        });
+    }
+
     let api_routes = create_api_router(app_state.clone());
 
     // Start with API routes
@@ -632,7 +849,8 @@ pub async fn run_api_server(
     };
 
     // Start server with shutdown signal
-    axum::serve(listener, app)
+    // Connect info provides the client IP recorded with login sessions
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
         .with_graceful_shutdown(shutdown_signal)
         .await?;
 
@@ -1,10 +1,10 @@
 //! JWT authentication middleware with user caching
 //!
-//! This module provides middleware for validating JWT tokens and caching
-//! authenticated user data to reduce database queries.
+//! This module provides middleware for validating JWT tokens (or workspace-scoped
+//! API keys) and caching authenticated user data to reduce database queries.
 
 use axum::{
-    extract::{Request, State},
+    extract::{MatchedPath, Request, State},
     http::HeaderMap,
     middleware::Next,
     response::Response,
@@ -15,14 +15,20 @@ use uuid::Uuid;
 use crate::{
     config::Config,
     error::{Error, Result},
-    models::users::User,
+    models::{api_keys::ApiKeyScope, users::User},
     queries,
-    services::jwt::authenticate_jwt_token_from_anywhere,
+    services::{api_keys, jwt::authenticate_jwt_session_from_anywhere},
     state::AppState,
 };
 
 use secrecy::ExposeSecret;
 
+/// Routes outside of a workspace that accept API keys, relative to `/api/v1`
+///
+/// Every other route outside of `/workspaces/{id}/...` rejects API keys, since
+/// only the workspace routes check a key's scope.
+const API_KEY_ROUTES: &[&str] = &["/providers"];
+
 /// Authenticated user extracted from JWT token
 ///
 /// This struct is added to request extensions by the JWT middleware
@@ -35,6 +41,9 @@ pub struct AuthenticatedUser {
     pub email: String,
     /// User's full name (optional)
     pub full_name: Option<String>,
+    /// Login session of the access token (`None` for API keys and older tokens)
+    #[serde(skip)]
+    pub session_id: Option<Uuid>,
 }
 
 impl From<User> for AuthenticatedUser {
@@ -43,6 +52,7 @@ impl From<User> for AuthenticatedUser {
             id: user.id,
             email: user.email,
             full_name: user.full_name,
+            session_id: None,
         }
     }
 }
@@ -55,15 +65,18 @@ impl From<User> for AuthenticatedUser {
 ///
 /// # Token Sources
 /// - **Authorization header** (API/Mobile clients): `Bearer <token>`
+/// - **Authorization header** (automation): `Bearer bsk_...` API key
 /// - **Cookie** (Browser clients): `access_token=<token>`
 ///
 /// # Behavior
 /// 1. Extracts JWT token from header or cookie (header takes priority)
-/// 2. Validates JWT signature and expiration
+/// 2. Validates JWT signature and expiration, or looks up the API key and adds
+///    its `ApiKeyScope` to request extensions
 /// 3. Checks cache for user details (cache key: `user:{user_id}`)
 /// 4. On cache miss: queries database and caches user with configured TTL
-/// 5. Adds `AuthenticatedUser` to request extensions
-/// 6. Returns 401 if token is invalid, expired, or missing
+/// 5. Adds `AuthenticatedUser` (with the token's session ID) to request extensions
+/// 6. Returns 401 if token is invalid, expired, or missing, and 403 for an API key
+///    on a route that does not accept them (see [`route_accepts_api_keys`])
 ///
 /// # Usage
 /// Apply this middleware to protected routes using `route_layer()`:
@@ -90,11 +103,31 @@ pub async fn jwt_auth_middleware(
     let cookie_header = headers.get("cookie").and_then(|h| h.to_str().ok());
     let access_token = cookie_header.and_then(|h| extract_cookie_value(h, "access_token"));
 
-    let user_id = authenticate_jwt_token_from_anywhere(
-        auth_header,
-        access_token.as_deref(),
-        config.jwt.secret.expose_secret(),
-    )?;
+    // API keys are only accepted from the Authorization header, never from cookies
+    let api_key = auth_header
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| api_keys::is_api_key(token));
+
+    let (user_id, session_id) = match api_key {
+        Some(key) => {
+            let matched_path = request.extensions().get::<MatchedPath>().map(MatchedPath::as_str);
+            if !matched_path.is_some_and(route_accepts_api_keys) {
+                return Err(Error::Forbidden(
+                    "API keys can only be used on workspace routes".to_string(),
+                ));
+            }
+            let mut conn = state.pool.acquire().await?;
+            let api_key = api_keys::authenticate_api_key(&mut conn, key).await?;
+            request.extensions_mut().insert(ApiKeyScope::from(&api_key));
+            (api_key.user_id, None)
+        }
+        None => authenticate_jwt_session_from_anywhere(
+            auth_header,
+            access_token.as_deref(),
+            config.jwt.secret.expose_secret(),
+        )?,
+    };
 
     // 2. Check cache for user details
     let cache_key = format!("user:{}", user_id);
@@ -102,7 +135,8 @@ pub async fn jwt_auth_middleware(
     match state.user_cache.get(&cache_key).await {
         Ok(Some(cached_user)) => {
             // Cache hit - convert to AuthenticatedUser and add to extensions
-            let authenticated_user: AuthenticatedUser = cached_user.into();
+            let mut authenticated_user: AuthenticatedUser = cached_user.into();
+            authenticated_user.session_id = session_id;
             request.extensions_mut().insert(authenticated_user);
             return Ok(next.run(request).await);
         }
@@ -126,7 +160,8 @@ pub async fn jwt_auth_middleware(
         .ok_or_else(|| Error::Authentication("User not found".to_string()))?;
 
     // 4. Cache user details with configurable TTL from config (cache the User object)
-    let authenticated_user: AuthenticatedUser = user.clone().into();
+    let mut authenticated_user: AuthenticatedUser = user.clone().into();
+    authenticated_user.session_id = session_id;
     if let Err(e) = state
         .user_cache
         .set_ex(
@@ -149,6 +184,22 @@ pub async fn jwt_auth_middleware(
     Ok(next.run(request).await)
 }
 
+/// Whether a route accepts API keys, by its matched path
+///
+/// API keys are scoped to a workspace, so they are accepted on the routes of a
+/// single workspace (`/workspaces/{id}/...`), where `workspace_access_middleware`
+/// checks the key's workspace and permissions, and on the routes listed in
+/// [`API_KEY_ROUTES`]. Creating and listing workspaces, the account routes and
+/// the agent session routes reject them.
+fn route_accepts_api_keys(matched_path: &str) -> bool {
+    let path = matched_path.strip_prefix("/api/v1").unwrap_or(matched_path);
+    // Nested routers may see the path without their `/workspaces` prefix
+    let workspace_route = path.strip_prefix("/workspaces").unwrap_or(path);
+    workspace_route == "/{id}"
+        || workspace_route.starts_with("/{id}/")
+        || API_KEY_ROUTES.contains(&path)
+}
+
 /// Extract specific cookie value from Cookie header
 ///
 /// # Arguments
@@ -170,6 +221,19 @@ fn extract_cookie_value(cookie_str: &str, cookie_name: &str) -> Option<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_route_accepts_api_keys() {
+        assert!(route_accepts_api_keys("/api/v1/workspaces/{id}"));
+        assert!(route_accepts_api_keys("/api/v1/workspaces/{id}/files/{file_id}"));
+        assert!(route_accepts_api_keys("/api/v1/providers"));
+
+        assert!(!route_accepts_api_keys("/api/v1/workspaces"));
+        assert!(!route_accepts_api_keys("/api/v1/workspaces/"));
+        assert!(!route_accepts_api_keys("/api/v1/auth/me"));
+        assert!(!route_accepts_api_keys("/api/v1/auth/email/verification"));
+        assert!(!route_accepts_api_keys("/api/v1/agent-sessions/{id}/pause"));
+    }
+
     #[test]
     fn test_extract_cookie_value() {
         let cookie_str = "access_token=abc123; refresh_token=def456";
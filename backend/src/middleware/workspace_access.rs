@@ -4,16 +4,17 @@
 //! and ownership for protected workspace routes.
 
 use axum::{
-    extract::{Request, State},
+    extract::{MatchedPath, Request, State},
     middleware::Next,
     response::Response,
 };
 use uuid::Uuid;
 use crate::{
     middleware::auth::AuthenticatedUser,
+    models::api_keys::ApiKeyScope,
     state::AppState,
     error::{Error, Result},
-    services::workspaces,
+    services::{api_keys, two_factor, workspaces},
 };
 
 /// Workspace access context added to request extensions
@@ -30,6 +31,24 @@ pub struct WorkspaceAccess {
     pub is_owner: bool,
     /// Whether the user is a workspace member (owners are always members)
     pub is_member: bool,
+    /// Scope of the API key used to authenticate, `None` for JWT-authenticated requests
+    pub api_key: Option<ApiKeyScope>,
+}
+
+impl WorkspaceAccess {
+    /// Ensures an API key (if one was used) was granted the given permission
+    ///
+    /// JWT-authenticated requests always pass; their permissions are enforced by the
+    /// service layer as before.
+    pub fn require_api_key_permission(&self, permission: &str) -> Result<()> {
+        match &self.api_key {
+            Some(scope) if !scope.has_permission(permission) => Err(Error::Forbidden(format!(
+                "API key lacks required permission: {}",
+                permission
+            ))),
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Middleware to validate workspace access control
@@ -37,8 +56,9 @@ pub struct WorkspaceAccess {
 /// This middleware:
 /// 1. Extracts workspace_id from request path parameters
 /// 2. Validates user is owner or member of the workspace
-/// 3. Adds WorkspaceAccess to request extensions for handler use
-/// 4. Returns 403 if user cannot access the workspace
+/// 3. For API key requests, checks the key's workspace and permission scope
+/// 4. Adds WorkspaceAccess to request extensions for handler use
+/// 5. Returns 403 if user cannot access the workspace
 ///
 /// # Token Sources
 /// - Requires JWT authentication to be already validated (runs after jwt_auth_middleware)
@@ -46,8 +66,10 @@ pub struct WorkspaceAccess {
 /// # Behavior
 /// 1. Extracts workspace_id from URL path (e.g., /api/v1/workspaces/{workspace_id})
 /// 2. Validates authenticated user has access (owner OR member)
-/// 3. Adds WorkspaceAccess with ownership/membership flags to extensions
-/// 4. Returns 403 Forbidden if user lacks access
+/// 3. If authenticated with an API key, requires the key to belong to this workspace
+///    and to hold the route's permission (see `api_keys::required_permission_for_request`)
+/// 4. Adds WorkspaceAccess with ownership/membership flags to extensions
+/// 5. Returns 403 Forbidden if user lacks access
 ///
 /// # Usage
 /// Apply this middleware to protected workspace routes using `route_layer()`:
@@ -86,12 +108,28 @@ pub async fn workspace_access_middleware(
         auth_user.id,
     ).await?;
 
+    // API keys are additionally limited to their own workspace and permission subset.
+    // Interactive users must satisfy the workspace's two-factor policy; API keys cannot
+    // log in interactively and are exempt.
+    let api_key = request.extensions().get::<ApiKeyScope>().cloned();
+    if let Some(scope) = &api_key {
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .ok_or_else(|| Error::Internal("Workspace route has no matched path".to_string()))?;
+        let permission = api_keys::required_permission_for_request(request.method(), route.as_str());
+        api_keys::authorize_api_key(&mut conn, scope, workspace_id, auth_user.id, permission).await?;
+    } else {
+        two_factor::ensure_workspace_policy(&mut conn, workspace_id, auth_user.id).await?;
+    }
+
     // Add workspace access context to extensions
     let access = WorkspaceAccess {
         workspace_id,
         user_id: auth_user.id,
         is_owner,
         is_member,
+        api_key,
     };
     request.extensions_mut().insert(access);
 
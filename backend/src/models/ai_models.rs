@@ -15,6 +15,7 @@ pub struct AiModel {
     pub context_window: Option<i32>,
     pub is_enabled: bool,           // Global enable/disable flag
     pub is_free: bool,              // Whether model is available for free
+    pub supports_vision: bool,      // Whether model accepts image input
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -29,6 +30,7 @@ pub struct NewAiModel {
     pub context_window: Option<i32>,
     pub is_enabled: bool,
     pub is_free: bool,
+    pub supports_vision: bool,
 }
 
 /// Update an existing AI model
@@ -39,6 +41,7 @@ pub struct UpdateAiModel {
     pub context_window: Option<i32>,
     pub is_enabled: Option<bool>,
     pub is_free: Option<bool>,
+    pub supports_vision: Option<bool>,
 }
 
 /// Workspace-model mapping with access control
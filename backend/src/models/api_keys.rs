@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Public prefix shared by every API key, used to tell keys apart from JWTs
+pub const API_KEY_PREFIX: &str = "bsk_";
+
+/// Workspace-scoped API key (only the hash of the secret is persisted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub workspace_id: Uuid,
+    pub created_by: Option<Uuid>,
+    pub name: String,
+    pub key_prefix: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub permissions: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ApiKey {
+    /// Returns true if the key is neither revoked nor past its expiry time
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| expires_at > Utc::now())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewApiKey {
+    pub user_id: Uuid,
+    pub workspace_id: Uuid,
+    pub created_by: Option<Uuid>,
+    pub name: String,
+    pub key_prefix: String,
+    pub key_hash: String,
+    pub permissions: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    /// Subset of the principal's workspace permissions granted to the key
+    pub permissions: Vec<String>,
+    /// Optional expiry; keys without one stay valid until revoked
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A freshly created key together with its secret, which is only returned once
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedApiKey {
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
+/// Non-human workspace principal backed by a password-less user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceAccount {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewServiceAccount {
+    pub workspace_id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_by: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateServiceAccountRequest {
+    pub name: String,
+    pub description: Option<String>,
+    /// Workspace role assigned to the service account (e.g. "editor")
+    pub role_name: String,
+}
+
+/// Service account with its workspace role, as returned by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceAccountDetailed {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub role_name: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Restrictions attached to a request authenticated with an API key
+///
+/// Added to request extensions by the auth middleware next to `AuthenticatedUser`.
+/// Its absence means the request was authenticated with a regular JWT.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKeyScope {
+    pub key_id: Uuid,
+    pub workspace_id: Uuid,
+    pub permissions: Vec<String>,
+}
+
+impl From<&ApiKey> for ApiKeyScope {
+    fn from(api_key: &ApiKey) -> Self {
+        Self {
+            key_id: api_key.id,
+            workspace_id: api_key.workspace_id,
+            permissions: api_key.permissions.clone(),
+        }
+    }
+}
+
+impl ApiKeyScope {
+    /// Returns true if the key was granted the given permission
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|p| p == permission)
+    }
+}
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use strum_macros::{Display, EnumString};
 use uuid::Uuid;
 
+use crate::models::files::ImageContent;
+
 /// Default AI model for new chat sessions
 pub const DEFAULT_CHAT_MODEL: &str = "gpt-5-mini";
 
@@ -26,9 +28,14 @@ pub enum ChatAttachment {
         file_id: Uuid,
         version_id: Option<Uuid>,
     },
+    /// Web page fetched into the context of the message's turn
     Url {
         url: String,
         title: Option<String>,
+        /// Workspace document the page was saved to when the message was posted,
+        /// read instead of fetching the page again
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        snapshot_file_id: Option<Uuid>,
     },
     Agent {
         agent_id: Uuid,
@@ -38,6 +45,11 @@ pub enum ChatAttachment {
         skill_id: Uuid,
         name: String,
     },
+    /// Image pasted into the message, stored already resized
+    Image {
+        name: Option<String>,
+        image: ImageContent,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -48,6 +60,9 @@ pub struct ChatMessageMetadata {
     pub usage: Option<serde_json::Value>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub model: Option<String>, // Model that generated this message (e.g., "gpt-5", "gpt-5-mini", "gpt-4o")
+    /// Chat model that failed, when `model` is a fallback taken from the workspace's chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_from: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub response_id: Option<String>, // OpenAI Responses API response_id for conversation continuity
     /// Question answer metadata (from ask_user tool responses)
@@ -110,6 +125,12 @@ pub struct AgentConfig {
     /// Absolute path to associated .plan file (only in build mode)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub plan_file: Option<String>,
+    /// Chat that delegated this chat's task (only set for sub-agent chats)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_chat_id: Option<Uuid>,
+    /// Tools the chat may call, on top of any agent file allowlist (all tools when absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
 }
 
 fn default_mode() -> String {
@@ -217,6 +238,8 @@ pub struct ContextSummary {
     pub utilization_percent: f64,
     pub model: String,
     pub token_limit: usize,
+    /// Tokenizer the counts come from, e.g. `o200k_base`
+    pub tokenizer: String,
     pub breakdown: TokenBreakdown,
 }
 
@@ -227,3 +250,37 @@ pub struct TokenBreakdown {
     pub tools_tokens: usize,
     pub attachments_tokens: usize,
 }
+
+// ============================================================================
+// Plan Progress API Response Models
+// ============================================================================
+
+/// Response for GET /chats/{id}/plan - step progress of the chat's active plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanProgressResponse {
+    pub chat_id: Uuid,
+    pub plan_file: String,
+    pub metadata: Option<crate::utils::PlanMetadata>,
+    pub steps: Vec<crate::utils::PlanStep>,
+    pub progress: crate::utils::PlanProgress,
+}
+
+// ============================================================================
+// Compaction API Response Models
+// ============================================================================
+
+/// Response for POST /chats/{id}/compact - result of compacting the history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactChatResponse {
+    pub chat_id: Uuid,
+    /// Whether a summary was written; false when there was too little history
+    pub compacted: bool,
+    /// Messages replaced by the summary in the AI context
+    pub compacted_message_count: usize,
+    /// Estimated history tokens before and after compaction
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+    /// The persisted summary message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<ChatMessage>,
+}
@@ -127,6 +127,17 @@ pub struct FileTag {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Image sent to vision-capable models, resized to fit what providers accept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageContent {
+    /// MIME type of the encoded image, e.g. `image/png`
+    pub media_type: String,
+    pub width: u32,
+    pub height: u32,
+    /// Base64-encoded image data
+    pub data: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
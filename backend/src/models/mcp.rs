@@ -0,0 +1,168 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use std::collections::BTreeMap;
+use strum_macros::{Display, EnumString};
+use uuid::Uuid;
+
+/// How the backend talks to an MCP server
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display, EnumString, sqlx::Type,
+)]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum McpTransport {
+    /// A local command started by the backend, JSON-RPC over stdin/stdout
+    Stdio,
+    /// A remote server speaking the streamable HTTP transport
+    Http,
+}
+
+/// A Model Context Protocol server registered in a workspace
+#[derive(Debug, Clone)]
+pub struct McpServer {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    /// Namespace of the server's tools (`mcp__{name}__{tool}`)
+    pub name: String,
+    pub transport: McpTransport,
+    /// Command to start (stdio)
+    pub command: Option<String>,
+    /// Command arguments (stdio)
+    pub args: Vec<String>,
+    /// Environment variables of the command (stdio), may hold secrets
+    pub env: Json<BTreeMap<String, String>>,
+    /// Endpoint URL (http)
+    pub url: Option<String>,
+    /// Request headers (http), may hold secrets
+    pub headers: Json<BTreeMap<String, String>>,
+    pub enabled: bool,
+    /// Timeout of connecting and of every request, in seconds
+    pub timeout_seconds: i32,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewMcpServer {
+    pub workspace_id: Uuid,
+    pub name: String,
+    pub transport: McpTransport,
+    pub command: Option<String>,
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub url: Option<String>,
+    pub headers: BTreeMap<String, String>,
+    pub enabled: bool,
+    pub timeout_seconds: i32,
+    pub created_by: Option<Uuid>,
+}
+
+/// Changes to an MCP server, `None` keeps the current value
+#[derive(Debug, Clone, Default)]
+pub struct UpdateMcpServer {
+    pub command: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub env: Option<BTreeMap<String, String>>,
+    pub url: Option<String>,
+    pub headers: Option<BTreeMap<String, String>>,
+    pub enabled: Option<bool>,
+    pub timeout_seconds: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateMcpServerRequest {
+    /// Lowercase letters, digits and hyphens, e.g. "github"
+    pub name: String,
+    pub transport: McpTransport,
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    pub url: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Default: true
+    pub enabled: Option<bool>,
+    /// Default: 30 seconds
+    pub timeout_seconds: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateMcpServerRequest {
+    pub command: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub env: Option<BTreeMap<String, String>>,
+    pub url: Option<String>,
+    pub headers: Option<BTreeMap<String, String>>,
+    pub enabled: Option<bool>,
+    pub timeout_seconds: Option<i32>,
+}
+
+/// MCP server as returned by the API
+///
+/// Environment variables and headers usually carry credentials, only their
+/// names are returned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerResponse {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub name: String,
+    pub transport: McpTransport,
+    pub command: Option<String>,
+    pub args: Vec<String>,
+    pub env_keys: Vec<String>,
+    pub url: Option<String>,
+    pub header_names: Vec<String>,
+    pub enabled: bool,
+    pub timeout_seconds: i32,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<McpServer> for McpServerResponse {
+    fn from(server: McpServer) -> Self {
+        Self {
+            id: server.id,
+            workspace_id: server.workspace_id,
+            name: server.name,
+            transport: server.transport,
+            command: server.command,
+            args: server.args,
+            env_keys: server.env.0.into_keys().collect(),
+            url: server.url,
+            header_names: server.headers.0.into_keys().collect(),
+            enabled: server.enabled,
+            timeout_seconds: server.timeout_seconds,
+            created_by: server.created_by,
+            created_at: server.created_at,
+            updated_at: server.updated_at,
+        }
+    }
+}
+
+/// Query parameters of the workspace MCP endpoint
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct McpEndpointQuery {
+    /// Restrict modifying tools to plan files
+    #[serde(default, deserialize_with = "crate::models::requests::deserialize_flexible_bool")]
+    pub plan_mode: bool,
+}
+
+/// A tool discovered on an MCP server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpTool {
+    /// Name the agent calls the tool by (`mcp__{server}__{tool}`)
+    pub name: String,
+    /// Name of the tool on the MCP server
+    pub remote_name: String,
+    pub description: Option<String>,
+    /// JSON Schema of the tool arguments
+    pub input_schema: serde_json::Value,
+    /// The server declares that the tool does not modify its environment
+    pub read_only: bool,
+}
@@ -1,12 +1,17 @@
 pub mod agent_session;
 pub mod ai_models;
+pub mod api_keys;
 pub mod chat;
 pub mod files;
 pub mod invitations;
+pub mod mcp;
+pub mod oidc;
 pub mod permissions;
 pub mod requests;
 pub mod roles;
 pub mod sse;
+pub mod tool_policy;
+pub mod two_factor;
 pub mod users;
 pub mod workspace_members;
 pub mod workspaces;
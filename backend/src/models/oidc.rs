@@ -0,0 +1,168 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
+
+use crate::models::users::LoginOutcome;
+
+/// A pending authorization request, created when the user is sent to the provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcLoginState {
+    pub id: Uuid,
+    pub state_hash: String,
+    pub code_verifier: String,
+    pub nonce: String,
+    pub redirect_to: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewOidcLoginState {
+    pub state_hash: String,
+    pub code_verifier: String,
+    pub nonce: String,
+    pub redirect_to: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// An external identity (issuer + subject) linked to a local user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewUserIdentity {
+    pub user_id: Uuid,
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+}
+
+/// Email domain whose users auto-join a workspace on single sign-on
+///
+/// Takes effect once verified, i.e. once `verification_token` was found in the
+/// domain's DNS TXT record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceSsoDomain {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub domain: String,
+    pub role_id: Uuid,
+    pub verification_token: String,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewWorkspaceSsoDomain {
+    pub workspace_id: Uuid,
+    pub domain: String,
+    pub role_id: Uuid,
+    pub verification_token: String,
+    pub created_by: Option<Uuid>,
+}
+
+/// SSO domain together with the name of the role it grants
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceSsoDomainDetailed {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub domain: String,
+    pub role_id: Uuid,
+    pub role_name: String,
+    pub verification_token: String,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateSsoDomainRequest {
+    /// Email domain, e.g. "example.com"
+    pub domain: String,
+    /// Role granted to users who join through this domain
+    pub role_name: String,
+}
+
+/// Query parameters of `GET /auth/oidc/authorize`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OidcAuthorizeQuery {
+    /// Frontend path to return to after a successful login, e.g. "/workspaces"
+    pub redirect_to: Option<String>,
+}
+
+/// Query parameters the provider sends to `GET /auth/oidc/callback`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// Subset of the provider's discovery document used by the login flow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// Token endpoint response (only the ID token is used)
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcTokenResponse {
+    pub id_token: String,
+}
+
+/// Verified ID token claims
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcIdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub email: Option<String>,
+    /// Some providers send this as the string "true"
+    #[serde(default, deserialize_with = "deserialize_flexible_bool")]
+    pub email_verified: bool,
+    pub name: Option<String>,
+    pub nonce: Option<String>,
+}
+
+/// Outcome of a completed OIDC login
+#[derive(Debug, Clone)]
+pub struct OidcLoginResult {
+    /// Session, or a two-factor challenge for users with 2FA enabled
+    pub login: LoginOutcome,
+    /// Frontend path requested when the login started
+    pub redirect_to: Option<String>,
+    /// True if the account was created by this login
+    pub provisioned: bool,
+    /// Workspaces joined automatically through their SSO domains
+    pub joined_workspace_ids: Vec<Uuid>,
+}
+
+fn deserialize_flexible_bool<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum FlexibleBool {
+        Bool(bool),
+        String(String),
+    }
+
+    Ok(match Option::<FlexibleBool>::deserialize(deserializer)? {
+        Some(FlexibleBool::Bool(value)) => value,
+        Some(FlexibleBool::String(value)) => value.eq_ignore_ascii_case("true"),
+        None => false,
+    })
+}
@@ -1,5 +1,5 @@
 use crate::models::{
-    files::{File, FileType, FileVersion},
+    files::{File, FileType, FileVersion, ImageContent},
     roles::Role,
     workspace_members::WorkspaceMember,
     workspaces::Workspace,
@@ -549,6 +549,14 @@ pub struct CreateChatRequest {
     pub goal: String,
     pub files: Option<Vec<Uuid>>,
     pub agents: Option<Vec<Uuid>>,
+    /// Agent file whose persona, model and tool restrictions the chat uses
+    pub agent_id: Option<Uuid>,
+    /// Skill files pinned for the whole conversation
+    pub skills: Option<Vec<Uuid>>,
+    /// Images pasted into the goal
+    pub images: Option<Vec<ImageUpload>>,
+    /// Web pages to fetch into the context of the goal
+    pub urls: Option<Vec<UrlAttachmentRequest>>,
     pub model: Option<String>,
     pub role: Option<String>,
 }
@@ -557,11 +565,38 @@ pub struct CreateChatRequest {
 pub struct PostChatMessageRequest {
     pub content: String,
     pub model: Option<String>,
+    /// Skill files to pin from this message on
+    pub skills: Option<Vec<Uuid>>,
+    /// Workspace files attached to this message
+    pub files: Option<Vec<Uuid>>,
+    /// Images pasted into this message
+    pub images: Option<Vec<ImageUpload>>,
+    /// Web pages to fetch into the context of this message
+    pub urls: Option<Vec<UrlAttachmentRequest>>,
     /// Optional metadata for the message (e.g., question answers)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<serde_json::Value>,
 }
 
+/// Image pasted into a chat message
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImageUpload {
+    /// Base64-encoded image, or a `data:` URL
+    pub data: String,
+    /// File name the image was pasted or dropped with
+    pub name: Option<String>,
+}
+
+/// Web page attached to a chat message
+#[derive(Debug, Clone, Deserialize)]
+pub struct UrlAttachmentRequest {
+    /// http or https URL of the page
+    pub url: String,
+    /// Save the page as a document in the workspace's /snapshots folder
+    #[serde(default)]
+    pub snapshot: bool,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct UpdateChatRequest {
     /// Application data to update (mode, plan_file, etc.)
@@ -985,6 +1020,11 @@ pub struct ReadResult {
     /// Used for scroll mode to track position in large files
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cursor: Option<usize>,
+
+    /// The file's image, resized for models, when the file is a PNG, JPEG,
+    /// GIF or WebP image. `content` is null then.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageContent>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1115,6 +1155,9 @@ pub struct PlanReadResult {
     pub hash: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total_lines: Option<usize>,
+    /// Step checklist of the whole plan, regardless of offset and limit
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<crate::utils::PlanStep>,
 }
 
 /// Single plan item in plan_list result
@@ -1144,6 +1187,31 @@ impl TruncatableList for PlanListResult {
     }
 }
 
+/// Arguments for plan_update_step tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanUpdateStepArgs {
+    /// Path to the plan file (defaults to the chat's active plan)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// 1-based step number
+    #[serde(deserialize_with = "deserialize_flexible_usize")]
+    pub step: usize,
+    pub status: crate::utils::PlanStepStatus,
+}
+
+/// Result for plan_update_step tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanUpdateStepResult {
+    pub path: String,
+    pub file_id: Uuid,
+    pub version_id: Uuid,
+    pub hash: String,
+    /// The step with its new status
+    pub step: crate::utils::PlanStep,
+    pub previous_status: crate::utils::PlanStepStatus,
+    pub progress: crate::utils::PlanProgress,
+}
+
 // ============================================================================
 // MEMORY TOOLS: memory_set, memory_get, memory_search, memory_delete
 // ============================================================================
@@ -1507,3 +1575,181 @@ pub struct WebSearchResult {
     /// Instant answer (if available)
     pub answer: Option<String>,
 }
+
+// ============================================================================
+// SKILL TOOLS: skill_list, skill_activate
+// ============================================================================
+
+/// Arguments for skill_list tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillListArgs {
+    /// Case-insensitive filter on name, description and triggers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// Maximum number of results
+    #[serde(default, deserialize_with = "deserialize_flexible_usize_option", skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// Single skill in skill_list result (metadata only, no instructions)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillListItem {
+    pub id: Uuid,
+    pub path: String,
+    pub name: String,
+    pub description: String,
+    pub triggers: Vec<String>,
+    pub allowed_tools: Vec<String>,
+}
+
+/// Result for skill_list tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillListResult {
+    pub skills: Vec<SkillListItem>,
+    pub total: usize,
+}
+
+impl TruncatableList for SkillListResult {
+    fn list_len(&self) -> usize {
+        self.skills.len()
+    }
+
+    fn truncate_list(mut self, limit: usize) -> Self {
+        self.skills = self.skills.into_iter().take(limit).collect();
+        self.total = self.skills.len();
+        self
+    }
+}
+
+/// Arguments for skill_activate tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillActivateArgs {
+    /// Name of the skill to load
+    pub name: String,
+}
+
+/// Result for skill_activate tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillActivateResult {
+    pub id: Uuid,
+    pub path: String,
+    pub name: String,
+    pub description: String,
+    pub allowed_tools: Vec<String>,
+    /// Skill body (everything after the frontmatter)
+    pub instructions: String,
+}
+
+// ============================================================================
+// DELEGATE TOOL: delegate
+// ============================================================================
+
+/// Single sub-agent task in a delegate call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegateTask {
+    /// Self-contained instructions for the sub-agent
+    pub task: String,
+    /// Tools the sub-agent may call (the parent's tools when omitted)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<String>>,
+    /// Maximum estimated tokens the sub-agent may produce before it is stopped
+    #[serde(default, deserialize_with = "deserialize_flexible_usize_option", skip_serializing_if = "Option::is_none")]
+    pub token_budget: Option<usize>,
+}
+
+/// Arguments for delegate tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegateArgs {
+    /// Tasks to run, concurrently
+    pub tasks: Vec<DelegateTask>,
+}
+
+/// Outcome of a single delegated task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegateTaskResult {
+    /// Sub-agent chat holding the full transcript
+    pub chat_id: Uuid,
+    pub task: String,
+    /// "completed", "failed", "budget_exceeded", "timed_out" or "cancelled"
+    pub status: String,
+    /// Final answer of the sub-agent (partial answer if it was stopped)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Tokens of the sub-agent's model calls, prompts included: as reported by the
+    /// provider, or estimated with the model's tokenizer if it reported none
+    pub tokens_used: usize,
+}
+
+/// Result for delegate tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegateResult {
+    /// One result per task, in the order of the request
+    pub results: Vec<DelegateTaskResult>,
+}
+
+// ============================================================================
+// RUN TOOL: run
+// ============================================================================
+
+/// Arguments for run tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunArgs {
+    /// Shell command to run, with `/bin/sh -c`
+    pub command: String,
+    /// Workspace folder to run the command in (default: "/")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Wall time limit in seconds (default and maximum set by the server)
+    #[serde(default, deserialize_with = "deserialize_flexible_usize_option", skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<usize>,
+}
+
+/// How a run changed a workspace file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+/// Workspace file a run changed, recorded as a new version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunFileChange {
+    pub path: String,
+    pub change: RunChangeKind,
+    /// Version holding the new content (`None` for deleted files)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<Uuid>,
+}
+
+/// Change a run made on disk that was not recorded in the workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunUnrecordedChange {
+    pub path: String,
+    pub change: RunChangeKind,
+    pub reason: String,
+}
+
+/// Result for run tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunResult {
+    pub command: String,
+    pub cwd: String,
+    /// Exit code, `None` if the command was killed
+    pub exit_code: Option<i32>,
+    /// True if the command was killed at its wall time limit
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    /// True if stdout or stderr was cut at the server's output limit
+    pub output_truncated: bool,
+    pub duration_ms: u64,
+    /// Files the command created, modified or deleted
+    pub changes: Vec<RunFileChange>,
+    /// Changes left on disk only (binary or oversized files, links, unsupported names)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unrecorded: Vec<RunUnrecordedChange>,
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use crate::utils::{PlanProgress, PlanStep, PlanStepStatus};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data", rename_all = "snake_case")]
@@ -22,6 +23,11 @@ pub enum SseEvent {
         output: String,
         success: bool,
     },
+    /// Output a tool streamed while running, before its observation
+    ObservationChunk {
+        tool: String,
+        output: String,
+    },
     FileUpdated {
         path: String,
         version: i32,
@@ -51,6 +57,44 @@ pub enum SseEvent {
         mode: String,
         plan_file: Option<String>,
     },
+    /// The chat model's provider kept failing, the turn continues with the next fallback model
+    ModelFallback {
+        from_model: String,
+        to_model: String,
+        reason: String,
+    },
+    /// Tokens the provider reported for the turn's model calls, sent before `Done`
+    Usage {
+        input_tokens: u64,
+        output_tokens: u64,
+        total_tokens: u64,
+    },
+    /// Plan step status changed by the plan_update_step tool
+    PlanStepUpdated {
+        path: String,
+        step: PlanStep,
+        previous_status: PlanStepStatus,
+        progress: PlanProgress,
+    },
+    /// Event from a sub-agent started by the delegate tool, nested in the parent's stream
+    Delegate {
+        /// Sub-agent chat the event comes from
+        chat_id: Uuid,
+        /// Position of the task in the delegate call
+        task_index: usize,
+        event: Box<SseEvent>,
+    },
+}
+
+/// Event of a chat's stream with its position in the chat's event log
+///
+/// The id is sent as the SSE event id, so clients can resume the stream with
+/// `Last-Event-ID`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamEvent {
+    /// Position in the log, `None` for events that are not logged (e.g. pings)
+    pub id: Option<i64>,
+    pub event: SseEvent,
 }
 
 /// Question definition for ask_user tool
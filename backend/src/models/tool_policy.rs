@@ -0,0 +1,263 @@
+//! Tool approval policy of a workspace
+//!
+//! Ordered rules matched against each tool call a chat agent makes, before it
+//! runs. The first rule whose tool and argument patterns match the call decides
+//! whether it runs, waits for the user's approval, or is refused. Calls no rule
+//! matches run.
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Error of a call a `deny` rule refuses
+pub const TOOL_POLICY_DENIED: &str = "The workspace's tool policy does not allow this call";
+
+/// Error of a call an `ask` rule matches outside of a chat, where nobody can approve it
+pub const TOOL_POLICY_NEEDS_APPROVAL: &str =
+    "The workspace's tool policy requires approval for this call, which can only be given in a chat";
+
+/// What happens to a tool call a rule matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolPolicyAction {
+    /// The call runs
+    Allow,
+    /// The call waits until the user approves or denies it
+    Ask,
+    /// The call is refused, the model gets an error
+    Deny,
+}
+
+/// A rule of a workspace's tool policy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolPolicyRule {
+    /// Pattern of the tool names the rule applies to, e.g. `rm`, `mcp__github__*` or `*`
+    pub tool: String,
+    /// Patterns the call's arguments must all match, by argument name
+    ///
+    /// A missing argument matches no pattern. Arguments other than strings match
+    /// by their JSON text (`true`, `42`), arrays when any of their items matches.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub arguments: BTreeMap<String, String>,
+    pub action: ToolPolicyAction,
+    /// Why the rule exists, shown to the user asked for approval or the model refused
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl ToolPolicyRule {
+    /// Error a call refused under this rule reports, with the rule's reason
+    pub fn refusal(&self, refusal: &str) -> String {
+        match &self.reason {
+            Some(reason) => format!("{}: {}", refusal, reason),
+            None => refusal.to_string(),
+        }
+    }
+
+    /// Returns true if the rule applies to a call of `tool` with `arguments`
+    ///
+    /// When the arguments are not an object, e.g. because the model's could not
+    /// be parsed, they can't be checked: `ask` and `deny` rules for the tool
+    /// apply whatever their argument patterns, `allow` rules only without any.
+    pub fn matches(&self, tool: &str, arguments: &Value) -> bool {
+        if !glob_matches(&self.tool, tool) {
+            return false;
+        }
+        if !arguments.is_object() {
+            return self.arguments.is_empty() || self.action != ToolPolicyAction::Allow;
+        }
+        self.arguments.iter().all(|(name, pattern)| {
+            arguments
+                .get(name)
+                .is_some_and(|value| argument_matches(pattern, value))
+        })
+    }
+}
+
+/// The tool policy of a workspace
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolPolicy {
+    pub rules: Vec<ToolPolicyRule>,
+    /// When the policy was last set, `None` if it never was
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl ToolPolicy {
+    /// First rule applying to a call of `tool` with `arguments`
+    pub fn rule_for(&self, tool: &str, arguments: &Value) -> Option<&ToolPolicyRule> {
+        self.rules.iter().find(|rule| rule.matches(tool, arguments))
+    }
+
+    /// Rule requiring approval for, or refusing, a call of `tool` with `arguments`
+    ///
+    /// Every place that runs tools decides through this: `None` means the call runs.
+    pub fn gate(&self, tool: &str, arguments: &Value) -> Option<&ToolPolicyRule> {
+        self.rule_for(tool, arguments)
+            .filter(|rule| rule.action != ToolPolicyAction::Allow)
+    }
+}
+
+/// Request to replace a workspace's tool policy
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateToolPolicyRequest {
+    pub rules: Vec<ToolPolicyRule>,
+}
+
+fn argument_matches(pattern: &str, value: &Value) -> bool {
+    match value {
+        Value::String(value) => glob_matches(pattern, value),
+        Value::Array(items) => items.iter().any(|item| argument_matches(pattern, item)),
+        value => glob_matches(pattern, &value.to_string()),
+    }
+}
+
+/// Returns true if `value` matches the glob `pattern`
+///
+/// `**` matches any text, `*` any text without a `/` and `?` any character
+/// other than `/`, so path and URL patterns like `/src/**` or
+/// `https://*.example.com/**` work as expected.
+pub fn glob_matches(pattern: &str, value: &str) -> bool {
+    glob_regex(pattern).is_some_and(|regex| regex.is_match(value))
+}
+
+/// Compiles a glob pattern, `None` if it is too large to compile
+pub fn glob_regex(pattern: &str) -> Option<Regex> {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(tool: &str, arguments: &[(&str, &str)], action: ToolPolicyAction) -> ToolPolicyRule {
+        ToolPolicyRule {
+            tool: tool.to_string(),
+            arguments: arguments.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            action,
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("rm", "rm"));
+        assert!(!glob_matches("rm", "rmdir"));
+        assert!(glob_matches("mcp__github__*", "mcp__github__create_issue"));
+        assert!(glob_matches("/src/*", "/src/main.rs"));
+        assert!(!glob_matches("/src/*", "/src/bin/eval.rs"));
+        assert!(glob_matches("/src/**", "/src/bin/eval.rs"));
+        assert!(glob_matches("https://*.rust-lang.org/**", "https://doc.rust-lang.org/std/index.html"));
+        assert!(!glob_matches("https://*.rust-lang.org/**", "https://evil.example/doc.rust-lang.org/"));
+        assert!(glob_matches("file?.txt", "file1.txt"));
+        assert!(glob_matches("a+b (1).md", "a+b (1).md"));
+    }
+
+    #[test]
+    fn test_first_matching_rule_decides() {
+        let policy = ToolPolicy {
+            rules: vec![
+                rule("web_fetch", &[("url", "https://docs.rs/**")], ToolPolicyAction::Allow),
+                rule("web_fetch", &[], ToolPolicyAction::Ask),
+                rule("rm", &[("path", "/projects/**")], ToolPolicyAction::Deny),
+                rule("mv", &[("source", "/*"), ("destination", "/archive/**")], ToolPolicyAction::Ask),
+            ],
+            updated_at: None,
+        };
+        let action = |tool: &str, args: Value| policy.rule_for(tool, &args).map(|rule| rule.action);
+
+        assert_eq!(action("web_fetch", json!({"url": "https://docs.rs/tokio"})), Some(ToolPolicyAction::Allow));
+        assert_eq!(action("web_fetch", json!({"url": "https://example.com"})), Some(ToolPolicyAction::Ask));
+        assert_eq!(action("rm", json!({"path": "/projects/site/index.md"})), Some(ToolPolicyAction::Deny));
+        assert_eq!(action("rm", json!({"path": "/notes/todo.md"})), None);
+        assert_eq!(action("mv", json!({"source": "/drafts", "destination": "/archive/2026"})), Some(ToolPolicyAction::Ask));
+        assert_eq!(action("mv", json!({"source": "/drafts"})), None);
+        assert_eq!(action("read", json!({"path": "/projects/site/index.md"})), None);
+    }
+
+    #[test]
+    fn test_gate_skips_allowed_calls() {
+        let policy = ToolPolicy {
+            rules: vec![
+                rule("rm", &[("path", "/tmp/**")], ToolPolicyAction::Allow),
+                rule("rm", &[], ToolPolicyAction::Deny),
+            ],
+            updated_at: None,
+        };
+
+        assert!(policy.gate("rm", &json!({"path": "/tmp/scratch.md"})).is_none());
+        assert!(policy.gate("read", &json!({"path": "/notes.md"})).is_none());
+        let rule = policy.gate("rm", &json!({"path": "/notes.md"})).unwrap();
+        assert_eq!(rule.refusal(TOOL_POLICY_DENIED), TOOL_POLICY_DENIED);
+
+        let rule = ToolPolicyRule {
+            reason: Some("Projects are shared".to_string()),
+            ..rule.clone()
+        };
+        assert_eq!(
+            rule.refusal(TOOL_POLICY_DENIED),
+            "The workspace's tool policy does not allow this call: Projects are shared"
+        );
+    }
+
+    #[test]
+    fn test_non_string_arguments() {
+        let tags = rule("memory_set", &[("tags", "secret")], ToolPolicyAction::Deny);
+        assert!(tags.matches("memory_set", &json!({"tags": ["work", "secret"]})));
+        assert!(!tags.matches("memory_set", &json!({"tags": ["work"]})));
+
+        let flag = rule("grep", &[("case_sensitive", "true")], ToolPolicyAction::Ask);
+        assert!(flag.matches("grep", &json!({"case_sensitive": true})));
+        assert!(!flag.matches("grep", &json!({"case_sensitive": false})));
+    }
+
+    #[test]
+    fn test_paths_match_normalized() {
+        let policy = ToolPolicy {
+            rules: vec![
+                rule("rm", &[("path", "/projects/**")], ToolPolicyAction::Deny),
+                rule("read_multiple_files", &[("paths", "/secrets/**")], ToolPolicyAction::Ask),
+            ],
+            updated_at: None,
+        };
+        let gated = |tool: &str, args: Value| policy.gate(tool, &crate::tools::policy_arguments(tool, &args)).is_some();
+
+        assert!(gated("rm", json!({"path": "projects/site"})));
+        assert!(gated("rm", json!({"path": "//projects/site"})));
+        assert!(gated("rm", json!({"path": "/notes/../projects/site"})));
+        assert!(gated("rm", json!({"path": " /projects/./site/ "})));
+        assert!(!gated("rm", json!({"path": "/projects/../notes/todo.md"})));
+        assert!(gated("read_multiple_files", json!({"paths": ["/notes.md", "secrets/../secrets/key"]})));
+    }
+
+    #[test]
+    fn test_unparsed_arguments_match_restricting_rules() {
+        let policy = ToolPolicy {
+            rules: vec![
+                rule("rm", &[("path", "/tmp/**")], ToolPolicyAction::Allow),
+                rule("rm", &[("path", "/projects/**")], ToolPolicyAction::Deny),
+            ],
+            updated_at: None,
+        };
+
+        let rule = policy.gate("rm", &Value::Null).unwrap();
+        assert_eq!(rule.action, ToolPolicyAction::Deny);
+        assert!(policy.gate("read", &Value::Null).is_none());
+    }
+}
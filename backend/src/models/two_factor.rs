@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// TOTP authenticator of a user (pending until `enabled_at` is set)
+#[derive(Debug, Clone)]
+pub struct UserTotp {
+    pub user_id: Uuid,
+    /// Base32-encoded shared secret
+    pub secret: String,
+    pub enabled_at: Option<DateTime<Utc>>,
+    /// Time step of the last accepted code, used to reject replays
+    pub last_used_step: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Single-use recovery code (only the hash is persisted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryCode {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub code_hash: String,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Pending second login step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorChallenge {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub failed_attempts: i32,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewTwoFactorChallenge {
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A freshly issued challenge together with its unhashed token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedTwoFactorChallenge {
+    pub user_id: Uuid,
+    pub challenge_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Secret to add to an authenticator app, returned when enrollment starts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotpEnrollment {
+    /// Base32-encoded secret for manual entry
+    pub secret: String,
+    /// `otpauth://` URI, usually rendered as a QR code
+    pub otpauth_url: String,
+}
+
+/// Two-factor state of the current user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub recovery_codes_remaining: i64,
+}
+
+/// Proof of a second factor: either a TOTP code or a recovery code
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TwoFactorCodeRequest {
+    /// 6-digit code from the authenticator app
+    pub code: Option<String>,
+    /// One of the recovery codes shown at enrollment
+    pub recovery_code: Option<String>,
+}
+
+/// Second step of a login
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyTwoFactorLoginRequest {
+    pub challenge_token: String,
+    #[serde(flatten)]
+    pub proof: TwoFactorCodeRequest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateTwoFactorPolicyRequest {
+    pub require_two_factor: bool,
+}
@@ -1,13 +1,17 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
 use uuid::Uuid;
 
+use crate::models::two_factor::IssuedTwoFactorChallenge;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub id: Uuid,
     pub email: String,
     pub password_hash: Option<String>,
     pub full_name: Option<String>,
+    pub email_verified_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -46,6 +50,27 @@ pub struct LoginResult {
     pub refresh_token: String, // Session token (long-lived, e.g., 30 days)
     pub access_token_expires_at: DateTime<Utc>, // When the access token expires
     pub refresh_token_expires_at: DateTime<Utc>, // When the refresh token expires
+    pub session_id: Uuid, // Session backing the refresh token
+}
+
+/// Outcome of the first login step
+#[derive(Debug, Clone)]
+pub enum LoginOutcome {
+    /// No second factor is needed and a session has been issued
+    Authenticated(LoginResult),
+    /// The primary credentials were accepted; a TOTP or recovery code must be
+    /// presented together with the challenge token to receive a session
+    TwoFactorRequired(IssuedTwoFactorChallenge),
+}
+
+impl LoginOutcome {
+    /// Returns the issued session, or `None` if a second factor is still required
+    pub fn into_login_result(self) -> Option<LoginResult> {
+        match self {
+            LoginOutcome::Authenticated(result) => Some(result),
+            LoginOutcome::TwoFactorRequired(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +78,7 @@ pub struct RefreshTokenResult {
     pub access_token: String,          // New JWT access token
     pub refresh_token: Option<String>, // New refresh token (rotated), None if within grace period
     pub expires_at: DateTime<Utc>,     // When the new access token expires
+    pub session_id: Option<Uuid>,      // Refreshed session, None for tokens rotated before it was recorded
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +87,9 @@ pub struct UserSession {
     pub user_id: Uuid,
     pub token_hash: String,
     pub expires_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub last_used_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -77,6 +106,26 @@ pub struct UpdateUserSession {
     pub expires_at: Option<DateTime<Utc>>,
 }
 
+/// Device that logged in or refreshed a session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionClient {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+/// A login session as shown to its owner (never includes the token hash)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Whether this is the session of the requesting access token
+    pub current: bool,
+}
+
 /// Revoked refresh token for theft detection
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RevokedRefreshToken {
@@ -85,4 +134,64 @@ pub struct RevokedRefreshToken {
     pub token_hash: String,
     pub revoked_at: DateTime<Utc>,
     pub reason: String,
+    pub session_id: Option<Uuid>,
+}
+
+/// What a single-use user token can be exchanged for
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display, EnumString, sqlx::Type,
+)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum UserTokenPurpose {
+    /// Allows setting a new password without knowing the current one
+    PasswordReset,
+    /// Proves ownership of the account's email address
+    EmailVerification,
+}
+
+/// Single-use, expiring token issued to a user (only the hash is persisted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub purpose: UserTokenPurpose,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewUserToken {
+    pub user_id: Uuid,
+    pub purpose: UserTokenPurpose,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A freshly issued token together with its unhashed value, ready for delivery
+#[derive(Debug, Clone)]
+pub struct IssuedUserToken {
+    pub user: User,
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub password: String,
+    pub confirm_password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
 }
@@ -12,6 +12,10 @@ pub struct Workspace {
     /// If None, uses the global default provider
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ai_provider_override: Option<String>,
+    /// Members must have two-factor authentication enabled to access the workspace
+    pub require_two_factor: bool,
+    /// Models chats fall back to, in order, when their model's provider fails
+    pub ai_fallback_models: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -33,3 +37,10 @@ pub struct UpdateWorkspace {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ai_provider_override: Option<Option<String>>,
 }
+
+/// Request body of `PATCH /workspaces/:id/ai-fallback-models`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateAiFallbackModelsRequest {
+    /// Models in the order they are tried, e.g. `["openrouter:anthropic/claude-3.5-sonnet"]`
+    pub fallback_models: Vec<String>,
+}
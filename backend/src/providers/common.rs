@@ -9,6 +9,8 @@ use std::str::FromStr;
 pub enum AiProvider {
     OpenAi,
     OpenRouter,
+    /// Replays recorded completions, for tests
+    Mock,
 }
 
 impl AiProvider {
@@ -17,6 +19,7 @@ impl AiProvider {
         match self {
             AiProvider::OpenAi => "openai",
             AiProvider::OpenRouter => "openrouter",
+            AiProvider::Mock => "mock",
         }
     }
 }
@@ -28,6 +31,7 @@ impl FromStr for AiProvider {
         match s.to_lowercase().as_str() {
             "openai" => Ok(AiProvider::OpenAi),
             "openrouter" => Ok(AiProvider::OpenRouter),
+            "mock" => Ok(AiProvider::Mock),
             _ => Err(format!("Unknown provider: {}", s)),
         }
     }
@@ -133,6 +137,7 @@ mod tests {
         assert_eq!(AiProvider::from_str("OpenAI").unwrap(), AiProvider::OpenAi);
         assert_eq!(AiProvider::from_str("OPENAI").unwrap(), AiProvider::OpenAi);
         assert_eq!(AiProvider::from_str("openrouter").unwrap(), AiProvider::OpenRouter);
+        assert_eq!(AiProvider::from_str("mock").unwrap(), AiProvider::Mock);
         assert!(AiProvider::from_str("unknown").is_err());
     }
 
@@ -0,0 +1,176 @@
+//! HTTP client of the OpenAI and OpenRouter providers
+//!
+//! rig's reqwest client turns an unsuccessful response into an error holding only
+//! its status and body. This client does the same, but appends the response's
+//! `Retry-After` to the message, so the chat's retry policy can wait as long as
+//! the provider asked (see [`super::retry`]).
+
+use bytes::Bytes;
+use chrono::Utc;
+use futures::StreamExt;
+use http::{header::RETRY_AFTER, HeaderMap, Request, Response, StatusCode};
+use rig::http_client::sse::BoxedStream;
+use rig::http_client::{
+    Error, HttpClientExt, LazyBody, MultipartForm, ReqwestClient, Result, StreamingResponse,
+};
+use rig::wasm_compat::WasmCompatSend;
+
+/// reqwest client keeping the `Retry-After` of failed responses
+#[derive(Debug, Clone, Default)]
+pub struct ProviderHttpClient {
+    inner: ReqwestClient,
+}
+
+fn instance_error<E: std::error::Error + Send + Sync + 'static>(error: E) -> Error {
+    Error::Instance(error.into())
+}
+
+/// Error of an unsuccessful response, noting how long the provider asked to wait
+fn status_error(status: StatusCode, headers: &HeaderMap, body: String) -> Error {
+    let retry_after = headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(retry_after_seconds);
+    let message = match retry_after {
+        Some(seconds) => format!("{} (Retry-After: {}s)", body, seconds),
+        None => body,
+    };
+    Error::InvalidStatusCodeWithMessage(status, message)
+}
+
+/// Seconds to wait from a `Retry-After` value, given in seconds or as an HTTP date
+fn retry_after_seconds(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(seconds);
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((date.with_timezone(&Utc) - Utc::now()).num_seconds().max(0) as u64)
+}
+
+impl HttpClientExt for ProviderHttpClient {
+    fn send<T, U>(
+        &self,
+        req: Request<T>,
+    ) -> impl Future<Output = Result<Response<LazyBody<U>>>> + WasmCompatSend + 'static
+    where
+        T: Into<Bytes>,
+        T: WasmCompatSend,
+        U: From<Bytes>,
+        U: WasmCompatSend + 'static,
+    {
+        let (parts, body) = req.into_parts();
+        let request = self
+            .inner
+            .request(parts.method, parts.uri.to_string())
+            .headers(parts.headers)
+            .body(body.into());
+
+        async move {
+            let response = request.send().await.map_err(instance_error)?;
+            let (status, headers) = (response.status(), response.headers().clone());
+            if !status.is_success() {
+                return Err(status_error(status, &headers, response.text().await.unwrap_or_default()));
+            }
+
+            let mut res = Response::builder().status(status);
+            if let Some(hs) = res.headers_mut() {
+                *hs = headers;
+            }
+            let body: LazyBody<U> = Box::pin(async move {
+                let bytes = response.bytes().await.map_err(instance_error)?;
+                Ok(U::from(bytes))
+            });
+            res.body(body).map_err(Error::Protocol)
+        }
+    }
+
+    fn send_multipart<U>(
+        &self,
+        req: Request<MultipartForm>,
+    ) -> impl Future<Output = Result<Response<LazyBody<U>>>> + WasmCompatSend + 'static
+    where
+        U: From<Bytes>,
+        U: WasmCompatSend + 'static,
+    {
+        let (parts, body) = req.into_parts();
+        let request = self
+            .inner
+            .request(parts.method, parts.uri.to_string())
+            .headers(parts.headers)
+            .multipart(body.into());
+
+        async move {
+            let response = request.send().await.map_err(instance_error)?;
+            let (status, headers) = (response.status(), response.headers().clone());
+            if !status.is_success() {
+                return Err(status_error(status, &headers, response.text().await.unwrap_or_default()));
+            }
+
+            let mut res = Response::builder().status(status);
+            if let Some(hs) = res.headers_mut() {
+                *hs = headers;
+            }
+            let body: LazyBody<U> = Box::pin(async move {
+                let bytes = response.bytes().await.map_err(instance_error)?;
+                Ok(U::from(bytes))
+            });
+            res.body(body).map_err(Error::Protocol)
+        }
+    }
+
+    fn send_streaming<T>(
+        &self,
+        req: Request<T>,
+    ) -> impl Future<Output = Result<StreamingResponse>> + WasmCompatSend
+    where
+        T: Into<Bytes>,
+    {
+        let (parts, body) = req.into_parts();
+        let request = self
+            .inner
+            .request(parts.method, parts.uri.to_string())
+            .headers(parts.headers)
+            .body(body.into());
+
+        async move {
+            let response = request.send().await.map_err(instance_error)?;
+            let (status, headers) = (response.status(), response.headers().clone());
+            if !status.is_success() {
+                return Err(status_error(status, &headers, response.text().await.unwrap_or_default()));
+            }
+
+            let mut res = Response::builder().status(status).version(response.version());
+            if let Some(hs) = res.headers_mut() {
+                *hs = headers;
+            }
+            let stream: BoxedStream = Box::pin(response.bytes_stream().map(|chunk| chunk.map_err(instance_error)));
+            res.body(stream).map_err(Error::Protocol)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_error_keeps_retry_after() {
+        let mut headers = HeaderMap::new();
+        let error = status_error(StatusCode::TOO_MANY_REQUESTS, &headers, "Rate limited".to_string());
+        assert_eq!(error.to_string(), "Invalid status code 429 Too Many Requests with message: Rate limited");
+
+        headers.insert(RETRY_AFTER, "7".parse().unwrap());
+        let error = status_error(StatusCode::TOO_MANY_REQUESTS, &headers, "Rate limited".to_string());
+        assert!(error.to_string().ends_with("Rate limited (Retry-After: 7s)"));
+    }
+
+    #[test]
+    fn test_retry_after_seconds() {
+        assert_eq!(retry_after_seconds(" 30 "), Some(30));
+        assert_eq!(retry_after_seconds("Wed, 21 Oct 2015 07:28:00 GMT"), Some(0));
+        let later = (Utc::now() + chrono::Duration::seconds(120)).to_rfc2822();
+        assert!(matches!(retry_after_seconds(&later), Some(118..=120)));
+        assert_eq!(retry_after_seconds("soon"), None);
+    }
+}
@@ -0,0 +1,631 @@
+//! Mock provider replaying recorded completions, for testing agents offline
+//!
+//! A fixture is a JSON file holding the completions of a conversation in order:
+//! text, reasoning, tool calls and token usage. The model `mock:<name>` replays
+//! `<fixtures_dir>/<name>.json`, answering each completion request with the
+//! fixture's next completion. Tools run for real, so the whole agent loop of a
+//! chat can be exercised without an API key.
+//!
+//! In recording mode, agents of the real providers write the completions they
+//! receive into `<fixtures_dir>/<chat_id>.json`, ready to be replayed as
+//! `mock:<chat_id>`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures::StreamExt;
+use rig::OneOrMany;
+use rig::completion::{
+    AssistantContent, CompletionError, CompletionModel, CompletionRequest, CompletionResponse,
+    GetTokenUsage, Usage,
+};
+use rig::message::{Reasoning, ToolCall, ToolFunction};
+use rig::streaming::{
+    RawStreamingChoice, RawStreamingToolCall, StreamedAssistantContent, StreamingCompletionResponse,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+/// Recorded completions of a conversation, in order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub completions: Vec<FixtureCompletion>,
+}
+
+impl Fixture {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| Error::NotFound(format!("Mock fixture {} not readable: {}", path.display(), e)))?;
+        serde_json::from_str(&data).map_err(Error::Json)
+    }
+}
+
+/// One model response
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FixtureCompletion {
+    #[serde(default)]
+    pub content: Vec<FixtureContent>,
+    #[serde(default)]
+    pub usage: FixtureUsage,
+    /// Provider error returned instead of the response, to replay failures such as
+    /// "Invalid status code 503 Service Unavailable with message: ..."
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl FixtureCompletion {
+    /// Appends text, merging it with text right before
+    fn push_text(&mut self, text: &str) {
+        match self.content.last_mut() {
+            Some(FixtureContent::Text { text: last }) => last.push_str(text),
+            _ => self.content.push(FixtureContent::Text { text: text.to_string() }),
+        }
+    }
+
+    fn from_choice(choice: &OneOrMany<AssistantContent>, usage: Usage) -> Self {
+        let mut completion = FixtureCompletion {
+            content: Vec::new(),
+            usage: usage.into(),
+            error: None,
+        };
+        for content in choice.iter() {
+            match content {
+                AssistantContent::Text(text) => completion.push_text(&text.text),
+                AssistantContent::Reasoning(reasoning) => completion.content.push(FixtureContent::Reasoning {
+                    text: reasoning.reasoning.join(""),
+                }),
+                AssistantContent::ToolCall(tool_call) => completion.content.push(tool_call.into()),
+                AssistantContent::Image(_) => {}
+            }
+        }
+        completion
+    }
+}
+
+/// Part of a model response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FixtureContent {
+    Text { text: String },
+    Reasoning { text: String },
+    ToolCall {
+        id: String,
+        name: String,
+        arguments: serde_json::Value,
+    },
+}
+
+impl FixtureContent {
+    fn to_assistant_content(&self) -> AssistantContent {
+        match self {
+            FixtureContent::Text { text } => AssistantContent::text(text),
+            FixtureContent::Reasoning { text } => AssistantContent::Reasoning(Reasoning::new(text)),
+            FixtureContent::ToolCall { id, name, arguments } => AssistantContent::ToolCall(ToolCall {
+                id: id.clone(),
+                call_id: None,
+                function: ToolFunction {
+                    name: name.clone(),
+                    arguments: arguments.clone(),
+                },
+                signature: None,
+                additional_params: None,
+            }),
+        }
+    }
+
+    fn into_streaming_choice(self) -> RawStreamingChoice<MockResponse> {
+        match self {
+            FixtureContent::Text { text } => RawStreamingChoice::Message(text),
+            FixtureContent::Reasoning { text } => RawStreamingChoice::Reasoning {
+                id: None,
+                reasoning: text,
+                signature: None,
+            },
+            FixtureContent::ToolCall { id, name, arguments } => {
+                RawStreamingChoice::ToolCall(RawStreamingToolCall::new(id, name, arguments))
+            }
+        }
+    }
+}
+
+impl From<&ToolCall> for FixtureContent {
+    fn from(tool_call: &ToolCall) -> Self {
+        FixtureContent::ToolCall {
+            id: tool_call.id.clone(),
+            name: tool_call.function.name.clone(),
+            arguments: tool_call.function.arguments.clone(),
+        }
+    }
+}
+
+/// Token usage of a model response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl From<Usage> for FixtureUsage {
+    fn from(usage: Usage) -> Self {
+        Self {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+        }
+    }
+}
+
+impl From<FixtureUsage> for Usage {
+    fn from(usage: FixtureUsage) -> Self {
+        let mut result = Usage::new();
+        result.input_tokens = usage.input_tokens;
+        result.output_tokens = usage.output_tokens;
+        result.total_tokens = usage.input_tokens + usage.output_tokens;
+        result
+    }
+}
+
+/// Raw response of the mock model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockResponse {
+    pub usage: FixtureUsage,
+}
+
+impl GetTokenUsage for MockResponse {
+    fn token_usage(&self) -> Option<Usage> {
+        Some(self.usage.into())
+    }
+}
+
+/// Mock provider serving fixtures from a directory
+#[derive(Debug)]
+pub struct MockProvider {
+    fixtures_dir: PathBuf,
+    record: bool,
+    /// Position of each chat in each fixture, kept when agents are rebuilt
+    cursors: Mutex<HashMap<(String, Uuid), Arc<AtomicUsize>>>,
+    /// Open recordings, by chat
+    recorders: Mutex<HashMap<Uuid, Arc<FixtureRecorder>>>,
+}
+
+impl MockProvider {
+    /// Create a mock provider replaying the fixtures of `fixtures_dir`
+    pub fn new(fixtures_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            fixtures_dir: fixtures_dir.into(),
+            record: false,
+            cursors: Mutex::new(HashMap::new()),
+            recorders: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record the completions of the real providers into the fixtures directory
+    pub fn with_recording(mut self, record: bool) -> Self {
+        self.record = record;
+        self
+    }
+
+    /// Check if recording is enabled
+    pub fn is_recording(&self) -> bool {
+        self.record
+    }
+
+    pub fn fixtures_dir(&self) -> &Path {
+        &self.fixtures_dir
+    }
+
+    /// Path of the fixture `name`
+    pub fn fixture_path(&self, name: &str) -> Result<PathBuf> {
+        let valid = !name.is_empty()
+            && Path::new(name)
+                .components()
+                .all(|component| matches!(component, std::path::Component::Normal(_)));
+        if !valid {
+            return Err(Error::Internal(format!("Invalid mock fixture name: {}", name)));
+        }
+        Ok(self.fixtures_dir.join(format!("{}.json", name)))
+    }
+
+    /// Model replaying the fixture `name` for a chat
+    ///
+    /// Each chat replays the fixture from its start, continuing where it left
+    /// off when the chat's agent is rebuilt.
+    pub fn completion_model(&self, name: &str, chat_id: Uuid) -> Result<MockCompletionModel> {
+        let fixture = Fixture::load(&self.fixture_path(name)?)?;
+        let cursor = self
+            .cursors
+            .lock()
+            .unwrap()
+            .entry((name.to_string(), chat_id))
+            .or_default()
+            .clone();
+
+        Ok(MockCompletionModel {
+            name: name.to_string(),
+            fixture: Arc::new(fixture),
+            cursor,
+        })
+    }
+
+    /// Recording of a chat's completions, continuing an existing one
+    pub fn recorder(&self, chat_id: Uuid) -> Result<Arc<FixtureRecorder>> {
+        let mut recorders = self.recorders.lock().unwrap();
+        if let Some(recorder) = recorders.get(&chat_id) {
+            return Ok(recorder.clone());
+        }
+
+        let path = self.fixture_path(&chat_id.to_string())?;
+        let fixture = if path.exists() { Fixture::load(&path)? } else { Fixture::default() };
+        let recorder = Arc::new(FixtureRecorder {
+            path,
+            fixture: Mutex::new(fixture),
+        });
+        recorders.insert(chat_id, recorder.clone());
+        Ok(recorder)
+    }
+}
+
+/// Completion model answering with the completions of a fixture, in order
+#[derive(Debug, Clone)]
+pub struct MockCompletionModel {
+    name: String,
+    fixture: Arc<Fixture>,
+    cursor: Arc<AtomicUsize>,
+}
+
+impl MockCompletionModel {
+    fn next_completion(&self) -> std::result::Result<FixtureCompletion, CompletionError> {
+        let position = self.cursor.fetch_add(1, Ordering::SeqCst);
+        let completion = self.fixture.completions.get(position).cloned().ok_or_else(|| {
+            CompletionError::ProviderError(format!(
+                "Mock fixture '{}' has no completion left ({} recorded)",
+                self.name,
+                self.fixture.completions.len()
+            ))
+        })?;
+        tracing::debug!(fixture = %self.name, position, "[MockProvider] Replaying completion");
+        match completion.error {
+            Some(error) => Err(CompletionError::ProviderError(error)),
+            None => Ok(completion),
+        }
+    }
+}
+
+impl CompletionModel for MockCompletionModel {
+    type Response = MockResponse;
+    type StreamingResponse = MockResponse;
+    type Client = MockProvider;
+
+    fn make(client: &Self::Client, model: impl Into<String>) -> Self {
+        let name = model.into();
+        client.completion_model(&name, Uuid::nil()).unwrap_or_else(|e| {
+            tracing::error!(fixture = %name, error = %e, "[MockProvider] Failed to load fixture");
+            MockCompletionModel {
+                name,
+                fixture: Arc::new(Fixture::default()),
+                cursor: Arc::new(AtomicUsize::new(0)),
+            }
+        })
+    }
+
+    async fn completion(
+        &self,
+        _request: CompletionRequest,
+    ) -> std::result::Result<CompletionResponse<MockResponse>, CompletionError> {
+        let completion = self.next_completion()?;
+        let choice = OneOrMany::many(completion.content.iter().map(FixtureContent::to_assistant_content))
+            .map_err(|_| {
+                CompletionError::ResponseError(format!("Mock fixture '{}' has an empty completion", self.name))
+            })?;
+
+        Ok(CompletionResponse {
+            choice,
+            usage: completion.usage.into(),
+            raw_response: MockResponse { usage: completion.usage },
+        })
+    }
+
+    async fn stream(
+        &self,
+        _request: CompletionRequest,
+    ) -> std::result::Result<StreamingCompletionResponse<MockResponse>, CompletionError> {
+        let completion = self.next_completion()?;
+        let usage = completion.usage;
+        let choices: Vec<_> = completion
+            .content
+            .into_iter()
+            .map(FixtureContent::into_streaming_choice)
+            .chain(std::iter::once(RawStreamingChoice::FinalResponse(MockResponse { usage })))
+            .map(Ok)
+            .collect();
+
+        Ok(StreamingCompletionResponse::stream(Box::pin(futures::stream::iter(choices))))
+    }
+}
+
+/// Writes the completions of a chat into its fixture as they come in
+#[derive(Debug)]
+pub struct FixtureRecorder {
+    path: PathBuf,
+    fixture: Mutex<Fixture>,
+}
+
+impl FixtureRecorder {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    async fn record(&self, completion: FixtureCompletion) {
+        let json = {
+            let mut fixture = self.fixture.lock().unwrap();
+            fixture.completions.push(completion);
+            serde_json::to_string_pretty(&*fixture)
+        };
+
+        let result = match json {
+            Ok(json) => match self.path.parent() {
+                Some(dir) => match tokio::fs::create_dir_all(dir).await {
+                    Ok(()) => tokio::fs::write(&self.path, json).await,
+                    Err(e) => Err(e),
+                },
+                None => tokio::fs::write(&self.path, json).await,
+            },
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            tracing::warn!(path = %self.path.display(), error = %e, "[MockProvider] Failed to write recording");
+        }
+    }
+}
+
+/// Completion model recording the completions of another model into a fixture
+#[derive(Debug, Clone)]
+pub struct RecordingCompletionModel<M> {
+    inner: M,
+    recorder: Option<Arc<FixtureRecorder>>,
+}
+
+impl<M> RecordingCompletionModel<M> {
+    pub fn new(inner: M, recorder: Arc<FixtureRecorder>) -> Self {
+        Self {
+            inner,
+            recorder: Some(recorder),
+        }
+    }
+}
+
+impl<M> CompletionModel for RecordingCompletionModel<M>
+where
+    M: CompletionModel + 'static,
+    M::StreamingResponse: 'static,
+{
+    type Response = M::Response;
+    type StreamingResponse = M::StreamingResponse;
+    type Client = M::Client;
+
+    /// Model that records nothing, as recordings are per chat
+    fn make(client: &Self::Client, model: impl Into<String>) -> Self {
+        Self {
+            inner: M::make(client, model),
+            recorder: None,
+        }
+    }
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> std::result::Result<CompletionResponse<M::Response>, CompletionError> {
+        let response = self.inner.completion(request).await?;
+        if let Some(recorder) = &self.recorder {
+            recorder.record(FixtureCompletion::from_choice(&response.choice, response.usage)).await;
+        }
+        Ok(response)
+    }
+
+    async fn stream(
+        &self,
+        request: CompletionRequest,
+    ) -> std::result::Result<StreamingCompletionResponse<M::StreamingResponse>, CompletionError> {
+        let mut inner = self.inner.stream(request).await?;
+        let recorder = self.recorder.clone();
+
+        let stream = async_stream::stream! {
+            let mut recorded = FixtureCompletion::default();
+            // Reasoning streamed in deltas, until complete
+            let mut reasoning = String::new();
+
+            while let Some(item) = inner.next().await {
+                let choice = match item {
+                    Ok(StreamedAssistantContent::Text(text)) => {
+                        flush_reasoning(&mut recorded, &mut reasoning);
+                        recorded.push_text(&text.text);
+                        RawStreamingChoice::Message(text.text)
+                    }
+                    Ok(StreamedAssistantContent::ToolCall(tool_call)) => {
+                        flush_reasoning(&mut recorded, &mut reasoning);
+                        recorded.content.push((&tool_call).into());
+                        RawStreamingChoice::ToolCall(RawStreamingToolCall {
+                            id: tool_call.id,
+                            call_id: tool_call.call_id,
+                            name: tool_call.function.name,
+                            arguments: tool_call.function.arguments,
+                            signature: tool_call.signature,
+                            additional_params: tool_call.additional_params,
+                        })
+                    }
+                    Ok(StreamedAssistantContent::ToolCallDelta { id, content }) => {
+                        RawStreamingChoice::ToolCallDelta { id, content }
+                    }
+                    Ok(StreamedAssistantContent::Reasoning(complete)) => {
+                        // Supersedes its deltas
+                        reasoning.clear();
+                        let text = complete.reasoning.join("");
+                        recorded.content.push(FixtureContent::Reasoning { text: text.clone() });
+                        RawStreamingChoice::Reasoning {
+                            id: complete.id,
+                            reasoning: text,
+                            signature: complete.signature,
+                        }
+                    }
+                    Ok(StreamedAssistantContent::ReasoningDelta { id, reasoning: delta }) => {
+                        reasoning.push_str(&delta);
+                        RawStreamingChoice::ReasoningDelta { id, reasoning: delta }
+                    }
+                    Ok(StreamedAssistantContent::Final(response)) => {
+                        if let Some(usage) = response.token_usage() {
+                            recorded.usage = usage.into();
+                        }
+                        RawStreamingChoice::FinalResponse(response)
+                    }
+                    // Failed completions are not recorded
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    }
+                };
+                yield Ok(choice);
+            }
+
+            flush_reasoning(&mut recorded, &mut reasoning);
+            if let Some(recorder) = recorder {
+                recorder.record(recorded).await;
+            }
+        };
+
+        Ok(StreamingCompletionResponse::stream(Box::pin(stream)))
+    }
+}
+
+/// Records reasoning received in deltas
+fn flush_reasoning(recorded: &mut FixtureCompletion, reasoning: &mut String) {
+    if !reasoning.is_empty() {
+        recorded.content.push(FixtureContent::Reasoning {
+            text: std::mem::take(reasoning),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Fixture {
+        Fixture {
+            completions: vec![
+                FixtureCompletion {
+                    content: vec![
+                        FixtureContent::Reasoning { text: "Look first".to_string() },
+                        FixtureContent::ToolCall {
+                            id: "call_1".to_string(),
+                            name: "ls".to_string(),
+                            arguments: serde_json::json!({ "path": "/" }),
+                        },
+                    ],
+                    usage: FixtureUsage { input_tokens: 10, output_tokens: 5 },
+                    error: None,
+                },
+                FixtureCompletion {
+                    content: vec![FixtureContent::Text { text: "Done".to_string() }],
+                    usage: FixtureUsage::default(),
+                    error: None,
+                },
+            ],
+        }
+    }
+
+    fn model(fixture: Fixture) -> MockCompletionModel {
+        MockCompletionModel {
+            name: "test".to_string(),
+            fixture: Arc::new(fixture),
+            cursor: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            preamble: None,
+            chat_history: OneOrMany::one(rig::message::Message::user("hello")),
+            documents: Vec::new(),
+            tools: Vec::new(),
+            temperature: None,
+            max_tokens: None,
+            tool_choice: None,
+            additional_params: None,
+        }
+    }
+
+    #[test]
+    fn test_fixture_format() {
+        let json = r#"{"completions": [{"content": [
+            {"type": "text", "text": "Hi"},
+            {"type": "tool_call", "id": "call_1", "name": "read", "arguments": {"path": "/a.md"}}
+        ]}]}"#;
+        let fixture: Fixture = serde_json::from_str(json).unwrap();
+        assert_eq!(fixture.completions[0].content.len(), 2);
+        assert_eq!(fixture.completions[0].usage, FixtureUsage::default());
+    }
+
+    #[tokio::test]
+    async fn test_stream_replays_completions_in_order() {
+        let model = model(fixture());
+
+        let mut stream = model.stream(request()).await.unwrap();
+        let mut items = Vec::new();
+        while let Some(item) = stream.next().await {
+            items.push(item.unwrap());
+        }
+        assert!(matches!(items[0], StreamedAssistantContent::Reasoning(_)));
+        match &items[1] {
+            StreamedAssistantContent::ToolCall(tool_call) => {
+                assert_eq!(tool_call.function.name, "ls");
+                assert_eq!(tool_call.function.arguments["path"], "/");
+            }
+            other => panic!("Unexpected item: {:?}", other),
+        }
+        match &items[2] {
+            StreamedAssistantContent::Final(response) => assert_eq!(response.usage.input_tokens, 10),
+            other => panic!("Unexpected item: {:?}", other),
+        }
+
+        let response = model.completion(request()).await.unwrap();
+        assert!(matches!(response.choice.first(), AssistantContent::Text(ref text) if text.text == "Done"));
+
+        // The fixture is used up
+        assert!(model.stream(request()).await.is_err());
+    }
+
+    #[test]
+    fn test_fixture_names_stay_in_fixtures_dir() {
+        let provider = MockProvider::new("/fixtures");
+        assert_eq!(provider.fixture_path("chat/plan").unwrap(), PathBuf::from("/fixtures/chat/plan.json"));
+        assert!(provider.fixture_path("../secrets").is_err());
+        assert!(provider.fixture_path("/etc/passwd").is_err());
+        assert!(provider.fixture_path("").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_recording_round_trip() {
+        let dir = std::env::temp_dir().join(format!("buildscale_fixtures_{}", Uuid::now_v7()));
+        let provider = MockProvider::new(&dir).with_recording(true);
+        let chat_id = Uuid::now_v7();
+
+        // Record the completions of another model
+        let recording = RecordingCompletionModel::new(model(fixture()), provider.recorder(chat_id).unwrap());
+        let mut stream = recording.stream(request()).await.unwrap();
+        while stream.next().await.is_some() {}
+        recording.completion(request()).await.unwrap();
+
+        let recorded = Fixture::load(&provider.fixture_path(&chat_id.to_string()).unwrap()).unwrap();
+        assert_eq!(recorded, fixture());
+
+        // and replay them
+        let replay = provider.completion_model(&chat_id.to_string(), chat_id).unwrap();
+        let response = replay.completion(request()).await.unwrap();
+        assert!(matches!(response.choice.first(), AssistantContent::Reasoning(_)));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,16 +1,22 @@
 //! Multi-provider AI support
 //!
 //! This module provides abstraction for multiple AI providers (OpenAI, OpenRouter)
-//! with a common interface for OpenAI-compatible providers.
+//! with a common interface for OpenAI-compatible providers, plus a mock provider
+//! replaying recorded completions for tests.
 
 pub mod common;
+pub mod http;
+pub mod mock;
 pub mod openai;
 pub mod openrouter;
+pub mod retry;
 
 // Re-export common types
 pub use common::{AiProvider, ModelIdentifier};
+pub use http::ProviderHttpClient;
 
 // Re-export providers
+pub use mock::MockProvider;
 pub use openai::OpenAiProvider;
 pub use openrouter::OpenRouterProvider;
 
@@ -21,8 +27,16 @@ use std::fmt;
 /// This allows the rest of the system to work with a single Agent type
 /// while each provider handles its own agent building internally.
 pub enum Agent {
-    OpenAI(rig::agent::Agent<rig::providers::openai::responses_api::ResponsesCompletionModel>),
-    OpenRouter(rig::agent::Agent<rig::providers::openrouter::CompletionModel>),
+    OpenAI(rig::agent::Agent<rig::providers::openai::responses_api::ResponsesCompletionModel<ProviderHttpClient>>),
+    OpenRouter(rig::agent::Agent<rig::providers::openrouter::CompletionModel<ProviderHttpClient>>),
+    /// Replays a fixture, see [`mock`]
+    Mock(rig::agent::Agent<mock::MockCompletionModel>),
+    /// OpenAI agent recording its completions into a fixture
+    RecordingOpenAI(
+        rig::agent::Agent<mock::RecordingCompletionModel<rig::providers::openai::responses_api::ResponsesCompletionModel<ProviderHttpClient>>>,
+    ),
+    /// OpenRouter agent recording its completions into a fixture
+    RecordingOpenRouter(rig::agent::Agent<mock::RecordingCompletionModel<rig::providers::openrouter::CompletionModel<ProviderHttpClient>>>),
 }
 
 // Manually implement Debug since rig::agent::Agent doesn't implement it
@@ -31,6 +45,11 @@ impl fmt::Debug for Agent {
         match self {
             Agent::OpenAI(_) => f.debug_tuple("Agent::OpenAI").field(&"<OpenAI Agent>").finish(),
             Agent::OpenRouter(_) => f.debug_tuple("Agent::OpenRouter").field(&"<OpenRouter Agent>").finish(),
+            Agent::Mock(_) => f.debug_tuple("Agent::Mock").field(&"<Mock Agent>").finish(),
+            Agent::RecordingOpenAI(_) => f.debug_tuple("Agent::RecordingOpenAI").field(&"<OpenAI Agent>").finish(),
+            Agent::RecordingOpenRouter(_) => {
+                f.debug_tuple("Agent::RecordingOpenRouter").field(&"<OpenRouter Agent>").finish()
+            }
         }
     }
 }
@@ -41,6 +60,9 @@ impl Clone for Agent {
         match self {
             Agent::OpenAI(agent) => Agent::OpenAI(agent.clone()),
             Agent::OpenRouter(agent) => Agent::OpenRouter(agent.clone()),
+            Agent::Mock(agent) => Agent::Mock(agent.clone()),
+            Agent::RecordingOpenAI(agent) => Agent::RecordingOpenAI(agent.clone()),
+            Agent::RecordingOpenRouter(agent) => Agent::RecordingOpenRouter(agent.clone()),
         }
     }
 }
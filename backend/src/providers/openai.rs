@@ -1,9 +1,12 @@
 //! OpenAI provider implementation with reasoning support
 
-use rig::providers::openai::Client;
+use super::ProviderHttpClient;
 use secrecy::{ExposeSecret, SecretString};
 use std::fmt;
 
+/// Client sending requests through [`ProviderHttpClient`], which keeps `Retry-After`
+pub type Client = rig::providers::openai::Client<ProviderHttpClient>;
+
 /// OpenAI provider with reasoning support
 pub struct OpenAiProvider {
     client: Client,
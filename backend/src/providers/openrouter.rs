@@ -1,9 +1,12 @@
 //! OpenRouter provider implementation (OpenAI-compatible)
 
-use rig::providers::openrouter::Client;
+use super::ProviderHttpClient;
 use secrecy::{ExposeSecret, SecretString};
 use std::fmt;
 
+/// Client sending requests through [`ProviderHttpClient`], which keeps `Retry-After`
+pub type Client = rig::providers::openrouter::Client<ProviderHttpClient>;
+
 /// OpenRouter provider (OpenAI-compatible)
 ///
 /// OpenRouter provides access to multiple models through a unified API.
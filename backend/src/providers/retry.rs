@@ -0,0 +1,164 @@
+//! Retry policy of failed provider calls
+//!
+//! A failed call is classified from its error message: rate limits (429), server
+//! errors (5xx), timeouts and network failures are transient and retried with
+//! exponential backoff, or after the delay the provider asked for. Other failures,
+//! and transient ones once the retries are spent, move the chat to its workspace's
+//! next fallback model.
+
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use regex::Regex;
+
+use crate::config::RetryConfig;
+
+/// Status code in rig's HTTP errors ("Invalid status code 429 Too Many Requests ...")
+/// or in error payloads streamed by the provider (`"code": 429`)
+static STATUS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)(?:status code:? |"code":\s*)(\d{3})\b"#).unwrap());
+
+/// `Retry-After` noted by [`super::http::ProviderHttpClient`]
+static RETRY_AFTER_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"Retry-After: (\d+)s").unwrap());
+
+/// Delay suggested in the message itself, as OpenAI does ("Please try again in 1.5s")
+static TRY_AGAIN_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)try again in (\d+(?:\.\d+)?)\s*(ms|s)\b").unwrap());
+
+/// Failures without a status that are worth retrying
+const TRANSIENT_MESSAGES: &[&str] = &[
+    "rate limit",
+    "overloaded",
+    "timeout",
+    "timed out",
+    "connection",
+    "error sending request",
+    "temporarily unavailable",
+    "failed to get tool definitions",
+];
+
+/// A failed provider call, classified from its error message
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderFailure {
+    /// HTTP status of the provider's response, when there was one
+    pub status: Option<u16>,
+    /// Whether the same call may succeed when retried
+    pub transient: bool,
+    /// How long the provider asked to wait before retrying
+    pub retry_after: Option<Duration>,
+}
+
+impl ProviderFailure {
+    pub fn classify(message: &str) -> Self {
+        let status = STATUS_RE
+            .captures(message)
+            .and_then(|captures| captures[1].parse::<u16>().ok());
+
+        let transient = match status {
+            Some(status) => matches!(status, 408 | 425 | 429 | 500..=599),
+            None => {
+                let message = message.to_lowercase();
+                TRANSIENT_MESSAGES.iter().any(|pattern| message.contains(pattern))
+            }
+        };
+
+        let retry_after = RETRY_AFTER_RE
+            .captures(message)
+            .and_then(|captures| captures[1].parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .or_else(|| {
+                let captures = TRY_AGAIN_RE.captures(message)?;
+                let value: f64 = captures[1].parse().ok()?;
+                let seconds = if &captures[2] == "ms" { value / 1000.0 } else { value };
+                Some(Duration::from_secs_f64(seconds))
+            });
+
+        Self { status, transient, retry_after }
+    }
+
+    /// Short description for logs and the fallback event
+    pub fn reason(&self) -> String {
+        match (self.status, self.transient) {
+            (Some(429), _) => "rate limited (429)".to_string(),
+            (Some(status), true) => format!("provider unavailable ({})", status),
+            (Some(status), false) => format!("request rejected ({})", status),
+            (None, true) => "provider unreachable".to_string(),
+            (None, false) => "provider error".to_string(),
+        }
+    }
+}
+
+/// Delay before retry `attempt` (from 1) of a failed call, `None` when the call
+/// should not be retried: the failure is not transient, the retries are spent, or
+/// the provider asked to wait longer than `max_retry_after_seconds`.
+pub fn retry_delay(config: &RetryConfig, attempt: u32, failure: &ProviderFailure) -> Option<Duration> {
+    if !failure.transient || attempt > config.max_retries {
+        return None;
+    }
+
+    match failure.retry_after {
+        Some(delay) if delay > Duration::from_secs(config.max_retry_after_seconds) => None,
+        Some(delay) => Some(delay),
+        None => {
+            let backoff = config
+                .initial_backoff_ms
+                .saturating_mul(1u64 << (attempt - 1).min(32))
+                .min(config.max_backoff_ms);
+            Some(Duration::from_millis(backoff))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_provider_failures() {
+        let rate_limited = ProviderFailure::classify(
+            "CompletionError: HttpError: Invalid status code 429 Too Many Requests with message: slow down (Retry-After: 12s)",
+        );
+        assert_eq!(rate_limited.status, Some(429));
+        assert!(rate_limited.transient);
+        assert_eq!(rate_limited.retry_after, Some(Duration::from_secs(12)));
+
+        let unavailable = ProviderFailure::classify("Invalid status code: 503 Service Unavailable");
+        assert_eq!((unavailable.status, unavailable.transient), (Some(503), true));
+
+        let hinted = ProviderFailure::classify(r#"{"error":{"code":429,"message":"Please try again in 250ms."}}"#);
+        assert_eq!(hinted.status, Some(429));
+        assert_eq!(hinted.retry_after, Some(Duration::from_millis(250)));
+
+        let unauthorized = ProviderFailure::classify("Invalid status code 401 Unauthorized with message: bad key");
+        assert_eq!((unauthorized.status, unauthorized.transient), (Some(401), false));
+
+        assert!(ProviderFailure::classify("error sending request for url").transient);
+        assert!(!ProviderFailure::classify("Mock fixture 'x' has no completion left (0 recorded)").transient);
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let config = RetryConfig {
+            max_retries: 3,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 3000,
+            max_retry_after_seconds: 60,
+        };
+        let transient = ProviderFailure { status: Some(503), transient: true, retry_after: None };
+
+        // Exponential backoff, capped
+        assert_eq!(retry_delay(&config, 1, &transient), Some(Duration::from_millis(1000)));
+        assert_eq!(retry_delay(&config, 2, &transient), Some(Duration::from_millis(2000)));
+        assert_eq!(retry_delay(&config, 3, &transient), Some(Duration::from_millis(3000)));
+        assert_eq!(retry_delay(&config, 4, &transient), None);
+
+        // Retry-After replaces the backoff, unless it is too long to wait for
+        let asked = ProviderFailure { retry_after: Some(Duration::from_secs(20)), ..transient.clone() };
+        assert_eq!(retry_delay(&config, 1, &asked), Some(Duration::from_secs(20)));
+        let too_long = ProviderFailure { retry_after: Some(Duration::from_secs(600)), ..transient };
+        assert_eq!(retry_delay(&config, 1, &too_long), None);
+
+        let permanent = ProviderFailure { status: Some(400), transient: false, retry_after: None };
+        assert_eq!(retry_delay(&config, 1, &permanent), None);
+    }
+}
@@ -611,6 +611,206 @@ pub async fn update_heartbeat(conn: &mut DbConn, session_id: Uuid) -> Result<()>
     Ok(())
 }
 
+// ============================================================================
+// CHAT LEASES
+// ============================================================================
+
+/// Claims the lease on a chat for a backend instance, creating its session if needed.
+///
+/// The lease is free when nobody holds it, when `node_id` already holds it, when
+/// the session has ended, or when the holder's heartbeat is older than
+/// `STALE_SESSION_THRESHOLD_SECONDS`. A claimed session starts over as idle.
+///
+/// Returns `None` when another live instance holds the lease.
+pub async fn acquire_chat_lease(
+    conn: &mut DbConn,
+    new_session: NewAgentSession,
+    node_id: Uuid,
+) -> Result<Option<AgentSession>> {
+    let threshold = Utc::now() - Duration::seconds(STALE_SESSION_THRESHOLD_SECONDS);
+
+    let session_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO agent_sessions (
+            workspace_id, chat_id, user_id, agent_type, status, model, mode, owner_node_id
+        )
+        VALUES ($1, $2, $3, $4, 'idle', $5, $6, $7)
+        ON CONFLICT (chat_id) DO UPDATE SET
+            user_id = EXCLUDED.user_id,
+            agent_type = EXCLUDED.agent_type,
+            model = EXCLUDED.model,
+            mode = EXCLUDED.mode,
+            owner_node_id = EXCLUDED.owner_node_id,
+            status = 'idle',
+            current_task = NULL,
+            error_message = NULL,
+            completed_at = NULL,
+            last_heartbeat = NOW(),
+            updated_at = NOW()
+        WHERE agent_sessions.owner_node_id IS NULL
+            OR agent_sessions.owner_node_id = EXCLUDED.owner_node_id
+            OR agent_sessions.last_heartbeat < $8
+            OR agent_sessions.status IN ('completed', 'error', 'cancelled')
+        RETURNING id
+        "#,
+        new_session.workspace_id,
+        new_session.chat_id,
+        new_session.user_id,
+        new_session.agent_type as AgentType,
+        new_session.model,
+        new_session.mode,
+        node_id,
+        threshold,
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    let Some(session_id) = session_id else {
+        tracing::info!(
+            chat_id = %new_session.chat_id,
+            node_id = %node_id,
+            "[AgentSessions] Chat lease held by another instance"
+        );
+        return Ok(None);
+    };
+
+    tracing::debug!(
+        session_id = %session_id,
+        chat_id = %new_session.chat_id,
+        node_id = %node_id,
+        "[AgentSessions] Acquired chat lease"
+    );
+
+    get_session_by_id(conn, session_id).await
+}
+
+/// Renews the lease of a session held by `node_id`.
+///
+/// Returns false when the instance no longer holds the lease, e.g. because
+/// another instance took the chat over.
+pub async fn renew_chat_lease(conn: &mut DbConn, session_id: Uuid, node_id: Uuid) -> Result<bool> {
+    let rows_affected = sqlx::query!(
+        r#"
+        UPDATE agent_sessions
+        SET last_heartbeat = NOW(), updated_at = NOW()
+        WHERE id = $1 AND owner_node_id = $2
+        "#,
+        session_id,
+        node_id,
+    )
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected > 0)
+}
+
+/// Releases the lease of a session held by `node_id`, so any instance may claim it.
+pub async fn release_chat_lease(conn: &mut DbConn, session_id: Uuid, node_id: Uuid) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE agent_sessions
+        SET owner_node_id = NULL, updated_at = NOW()
+        WHERE id = $1 AND owner_node_id = $2
+        "#,
+        session_id,
+        node_id,
+    )
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(())
+}
+
+/// Releases the leases held by `node_id` on chats that are not running, e.g. when
+/// the instance shuts down.
+///
+/// Running chats stay leased, so another instance takes them over once the
+/// lease goes stale (see [`claim_orphaned_sessions`]).
+pub async fn release_node_leases(conn: &mut DbConn, node_id: Uuid) -> Result<u64> {
+    let rows_affected = sqlx::query!(
+        r#"
+        UPDATE agent_sessions
+        SET owner_node_id = NULL, updated_at = NOW()
+        WHERE owner_node_id = $1
+        AND status <> 'running'
+        "#,
+        node_id,
+    )
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
+
+/// Gets the instance holding a live lease on a chat, if any.
+pub async fn get_chat_lease_owner(conn: &mut DbConn, chat_id: Uuid) -> Result<Option<Uuid>> {
+    let threshold = Utc::now() - Duration::seconds(STALE_SESSION_THRESHOLD_SECONDS);
+
+    let owner = sqlx::query_scalar!(
+        r#"
+        SELECT owner_node_id as "owner_node_id!"
+        FROM agent_sessions
+        WHERE chat_id = $1
+        AND owner_node_id IS NOT NULL
+        AND last_heartbeat >= $2
+        AND status NOT IN ('completed', 'error', 'cancelled')
+        "#,
+        chat_id,
+        threshold,
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(owner)
+}
+
+/// Claims the running sessions of instances that stopped heartbeating.
+///
+/// Each orphaned session is claimed atomically, so when several instances look
+/// for orphans at once every session is taken over by exactly one of them.
+pub async fn claim_orphaned_sessions(conn: &mut DbConn, node_id: Uuid) -> Result<Vec<AgentSession>> {
+    let threshold = Utc::now() - Duration::seconds(STALE_SESSION_THRESHOLD_SECONDS);
+
+    let claimed = sqlx::query_scalar!(
+        r#"
+        UPDATE agent_sessions
+        SET owner_node_id = $1, last_heartbeat = NOW(), updated_at = NOW()
+        WHERE owner_node_id IS NOT NULL
+        AND owner_node_id <> $1
+        AND last_heartbeat < $2
+        AND status = 'running'
+        RETURNING id
+        "#,
+        node_id,
+        threshold,
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    let mut sessions = Vec::with_capacity(claimed.len());
+    for session_id in claimed {
+        if let Some(session) = get_session_by_id(conn, session_id).await? {
+            tracing::warn!(
+                session_id = %session.id,
+                chat_id = %session.chat_id,
+                node_id = %node_id,
+                "[AgentSessions] Claimed orphaned session"
+            );
+            sessions.push(session);
+        }
+    }
+
+    Ok(sessions)
+}
+
 /// Deletes a session by ID.
 pub async fn delete_session(conn: &mut DbConn, session_id: Uuid) -> Result<()> {
     tracing::debug!(
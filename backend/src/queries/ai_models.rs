@@ -20,8 +20,8 @@ pub async fn create_model(
 ) -> Result<AiModel> {
     let model = sqlx::query_as::<Postgres, AiModel>(
         r#"
-        INSERT INTO ai_models (provider, model_name, display_name, description, context_window, is_enabled, is_free)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        INSERT INTO ai_models (provider, model_name, display_name, description, context_window, is_enabled, is_free, supports_vision)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         RETURNING *
         "#
     )
@@ -32,6 +32,7 @@ pub async fn create_model(
     .bind(new_model.context_window)
     .bind(new_model.is_enabled)
     .bind(new_model.is_free)
+    .bind(new_model.supports_vision)
     .fetch_one(pool)
     .await?;
 
@@ -100,7 +101,7 @@ pub async fn get_model_by_provider_and_name_conn(
         r#"
         SELECT id, provider, model_name, display_name,
                description, context_window, is_enabled, is_free,
-               created_at, updated_at
+               supports_vision, created_at, updated_at
         FROM ai_models
         WHERE provider = $1 AND model_name = $2
         "#,
@@ -128,7 +129,7 @@ pub async fn get_workspace_models_by_provider(
         r#"
         SELECT m.id, m.provider, m.model_name, m.display_name,
                m.description, m.context_window, m.is_enabled, m.is_free,
-               m.created_at, m.updated_at
+               m.supports_vision, m.created_at, m.updated_at
         FROM ai_models m
         INNER JOIN workspace_ai_models wm ON m.id = wm.model_id
         WHERE wm.workspace_id = $1
@@ -150,7 +151,7 @@ pub async fn get_workspace_models_by_provider(
             r#"
             SELECT m.id, m.provider, m.model_name, m.display_name,
                    m.description, m.context_window, m.is_enabled, m.is_free,
-                   m.created_at, m.updated_at
+                   m.supports_vision, m.created_at, m.updated_at
             FROM ai_models m
             WHERE m.provider = $1
               AND m.is_enabled = true
@@ -194,8 +195,9 @@ pub async fn update_model(
             context_window = COALESCE($3, context_window),
             is_enabled = COALESCE($4, is_enabled),
             is_free = COALESCE($5, is_free),
+            supports_vision = COALESCE($6, supports_vision),
             updated_at = NOW()
-        WHERE id = $6
+        WHERE id = $7
         RETURNING *
         "#
     )
@@ -204,6 +206,7 @@ pub async fn update_model(
     .bind(updates.context_window)
     .bind(updates.is_enabled)
     .bind(updates.is_free)
+    .bind(updates.supports_vision)
     .bind(model_id)
     .fetch_one(pool)
     .await?;
@@ -293,7 +296,7 @@ pub async fn get_workspace_model_details(
         SELECT
             m.id as model_id, m.provider, m.model_name, m.display_name,
             m.description, m.context_window, m.is_enabled, m.is_free,
-            m.created_at as model_created_at, m.updated_at as model_updated_at,
+            m.supports_vision, m.created_at as model_created_at, m.updated_at as model_updated_at,
             wm.id as wm_id, wm.workspace_id, wm.model_id as wm_model_id,
             wm.status, wm.created_at as wm_created_at, wm.updated_at as wm_updated_at
         FROM workspace_ai_models wm
@@ -317,6 +320,7 @@ pub async fn get_workspace_model_details(
             context_window: row.get("context_window"),
             is_enabled: row.get("is_enabled"),
             is_free: row.get("is_free"),
+            supports_vision: row.get("supports_vision"),
             created_at: row.get("model_created_at"),
             updated_at: row.get("model_updated_at"),
         };
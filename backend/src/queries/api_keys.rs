@@ -0,0 +1,150 @@
+use crate::{
+    error::{Error, Result},
+    models::api_keys::{ApiKey, NewApiKey},
+};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::DbConn;
+
+/// Hash an API key using SHA-256 for secure storage
+pub fn hash_api_key(key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Creates a new API key in the database.
+pub async fn create_api_key(conn: &mut DbConn, new_key: NewApiKey) -> Result<ApiKey> {
+    let api_key = sqlx::query_as!(
+        ApiKey,
+        r#"
+        INSERT INTO api_keys (user_id, workspace_id, created_by, name, key_prefix, key_hash, permissions, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id, user_id, workspace_id, created_by, name, key_prefix, key_hash, permissions,
+                  expires_at, last_used_at, revoked_at, created_at, updated_at
+        "#,
+        new_key.user_id,
+        new_key.workspace_id,
+        new_key.created_by,
+        new_key.name,
+        new_key.key_prefix,
+        new_key.key_hash,
+        &new_key.permissions,
+        new_key.expires_at
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(api_key)
+}
+
+/// Gets an API key by its hash, including revoked and expired keys.
+pub async fn get_api_key_by_hash(conn: &mut DbConn, key_hash: &str) -> Result<Option<ApiKey>> {
+    let api_key = sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT id, user_id, workspace_id, created_by, name, key_prefix, key_hash, permissions,
+               expires_at, last_used_at, revoked_at, created_at, updated_at
+        FROM api_keys
+        WHERE key_hash = $1
+        "#,
+        key_hash
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(api_key)
+}
+
+/// Gets an API key by ID within a workspace.
+pub async fn get_api_key_in_workspace(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    id: Uuid,
+) -> Result<Option<ApiKey>> {
+    let api_key = sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT id, user_id, workspace_id, created_by, name, key_prefix, key_hash, permissions,
+               expires_at, last_used_at, revoked_at, created_at, updated_at
+        FROM api_keys
+        WHERE workspace_id = $1 AND id = $2
+        "#,
+        workspace_id,
+        id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(api_key)
+}
+
+/// Lists all keys (including revoked ones) that act as a user within a workspace.
+pub async fn list_api_keys_for_user(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    user_id: Uuid,
+) -> Result<Vec<ApiKey>> {
+    let api_keys = sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT id, user_id, workspace_id, created_by, name, key_prefix, key_hash, permissions,
+               expires_at, last_used_at, revoked_at, created_at, updated_at
+        FROM api_keys
+        WHERE workspace_id = $1 AND user_id = $2
+        ORDER BY created_at DESC
+        "#,
+        workspace_id,
+        user_id
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(api_keys)
+}
+
+/// Marks an API key as revoked. Returns `None` if the key was already revoked.
+pub async fn revoke_api_key(conn: &mut DbConn, id: Uuid) -> Result<Option<ApiKey>> {
+    let api_key = sqlx::query_as!(
+        ApiKey,
+        r#"
+        UPDATE api_keys
+        SET revoked_at = NOW(), updated_at = NOW()
+        WHERE id = $1 AND revoked_at IS NULL
+        RETURNING id, user_id, workspace_id, created_by, name, key_prefix, key_hash, permissions,
+                  expires_at, last_used_at, revoked_at, created_at, updated_at
+        "#,
+        id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(api_key)
+}
+
+/// Records that a key was used.
+///
+/// Writes are throttled to once per minute per key so that busy automation
+/// does not turn every request into an UPDATE.
+pub async fn touch_api_key_last_used(conn: &mut DbConn, id: Uuid) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE api_keys
+        SET last_used_at = NOW()
+        WHERE id = $1
+          AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')
+        "#,
+    )
+    .bind(id)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(())
+}
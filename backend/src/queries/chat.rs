@@ -3,6 +3,7 @@ use crate::{
     models::chat::{ChatMessage, ChatMessageRole, NewChatMessage, ChatMessageMetadata},
     DbConn,
 };
+use chrono::{DateTime, Utc};
 use sqlx::types::Json;
 use uuid::Uuid;
 
@@ -37,6 +38,45 @@ pub async fn insert_chat_message(conn: &mut DbConn, new_msg: NewChatMessage) ->
     Ok(msg)
 }
 
+/// Inserts a new chat message with an explicit creation time.
+///
+/// Used to place a message at a specific point of the history, which is
+/// ordered by `created_at`.
+pub async fn insert_chat_message_at(
+    conn: &mut DbConn,
+    new_msg: NewChatMessage,
+    created_at: DateTime<Utc>,
+) -> Result<ChatMessage> {
+    let msg = sqlx::query_as!(
+        ChatMessage,
+        r#"
+        INSERT INTO chat_messages (file_id, workspace_id, role, content, metadata, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING
+            id,
+            file_id,
+            workspace_id,
+            role as "role: ChatMessageRole",
+            content,
+            metadata as "metadata: Json<ChatMessageMetadata>",
+            created_at,
+            updated_at,
+            deleted_at
+        "#,
+        new_msg.file_id,
+        new_msg.workspace_id,
+        new_msg.role as ChatMessageRole,
+        new_msg.content,
+        Json(new_msg.metadata.0) as _,
+        created_at
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(msg)
+}
+
 /// Retrieves all non-deleted messages for a specific chat file.
 pub async fn get_messages_by_file_id(
     conn: &mut DbConn,
@@ -150,3 +190,74 @@ pub async fn soft_delete_all_messages_by_file_id(
 
     Ok(())
 }
+
+/// Appends events to a chat's stream event log, returning their ids in order.
+pub async fn insert_stream_events(
+    conn: &mut DbConn,
+    chat_id: Uuid,
+    events: &[serde_json::Value],
+) -> Result<Vec<i64>> {
+    let mut ids = sqlx::query_scalar!(
+        r#"
+        INSERT INTO chat_stream_events (chat_id, event)
+        SELECT $1, e.event
+        FROM UNNEST($2::jsonb[]) WITH ORDINALITY AS e(event, position)
+        ORDER BY e.position
+        RETURNING id
+        "#,
+        chat_id,
+        events as &[serde_json::Value],
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    // Ids are drawn in insertion order; RETURNING does not promise any order
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+/// Retrieves the logged events of a chat that come after `after_id`, oldest first.
+pub async fn get_stream_events_after(
+    conn: &mut DbConn,
+    chat_id: Uuid,
+    after_id: i64,
+    limit: i64,
+) -> Result<Vec<(i64, serde_json::Value)>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, event
+        FROM chat_stream_events
+        WHERE chat_id = $1 AND id > $2
+        ORDER BY id ASC
+        LIMIT $3
+        "#,
+        chat_id,
+        after_id,
+        limit,
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(rows.into_iter().map(|row| (row.id, row.event)).collect())
+}
+
+/// Deletes logged stream events older than `cutoff`, returning how many were deleted.
+pub async fn delete_stream_events_before(
+    conn: &mut DbConn,
+    cutoff: DateTime<Utc>,
+) -> Result<u64> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM chat_stream_events
+        WHERE created_at < $1
+        "#,
+        cutoff,
+    )
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(result.rows_affected())
+}
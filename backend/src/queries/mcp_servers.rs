@@ -0,0 +1,154 @@
+use crate::{
+    error::{Error, Result},
+    models::mcp::{McpServer, McpTransport, NewMcpServer, UpdateMcpServer},
+};
+use sqlx::types::Json;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::DbConn;
+
+/// Registers an MCP server in a workspace.
+pub async fn create_mcp_server(conn: &mut DbConn, new_server: NewMcpServer) -> Result<McpServer> {
+    let server = sqlx::query_as!(
+        McpServer,
+        r#"
+        INSERT INTO mcp_servers (workspace_id, name, transport, command, args, env, url, headers, enabled, timeout_seconds, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING id, workspace_id, name, transport as "transport: McpTransport", command, args,
+                  env as "env: Json<BTreeMap<String, String>>", url,
+                  headers as "headers: Json<BTreeMap<String, String>>",
+                  enabled, timeout_seconds, created_by, created_at, updated_at
+        "#,
+        new_server.workspace_id,
+        new_server.name,
+        new_server.transport as McpTransport,
+        new_server.command,
+        &new_server.args,
+        Json(&new_server.env) as _,
+        new_server.url,
+        Json(&new_server.headers) as _,
+        new_server.enabled,
+        new_server.timeout_seconds,
+        new_server.created_by
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|e| {
+        if e.to_string().contains("duplicate key") {
+            Error::Conflict(format!(
+                "An MCP server named '{}' already exists in this workspace",
+                new_server.name
+            ))
+        } else {
+            Error::Sqlx(e)
+        }
+    })?;
+
+    Ok(server)
+}
+
+/// Gets an MCP server of a workspace.
+pub async fn get_mcp_server(conn: &mut DbConn, workspace_id: Uuid, id: Uuid) -> Result<Option<McpServer>> {
+    let server = sqlx::query_as!(
+        McpServer,
+        r#"
+        SELECT id, workspace_id, name, transport as "transport: McpTransport", command, args,
+               env as "env: Json<BTreeMap<String, String>>", url,
+               headers as "headers: Json<BTreeMap<String, String>>",
+               enabled, timeout_seconds, created_by, created_at, updated_at
+        FROM mcp_servers
+        WHERE workspace_id = $1 AND id = $2
+        "#,
+        workspace_id,
+        id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(server)
+}
+
+/// Lists the MCP servers of a workspace, optionally only the enabled ones.
+pub async fn list_mcp_servers(conn: &mut DbConn, workspace_id: Uuid, enabled_only: bool) -> Result<Vec<McpServer>> {
+    let servers = sqlx::query_as!(
+        McpServer,
+        r#"
+        SELECT id, workspace_id, name, transport as "transport: McpTransport", command, args,
+               env as "env: Json<BTreeMap<String, String>>", url,
+               headers as "headers: Json<BTreeMap<String, String>>",
+               enabled, timeout_seconds, created_by, created_at, updated_at
+        FROM mcp_servers
+        WHERE workspace_id = $1 AND (enabled OR NOT $2)
+        ORDER BY name
+        "#,
+        workspace_id,
+        enabled_only
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(servers)
+}
+
+/// Updates an MCP server of a workspace, leaving `None` fields unchanged.
+pub async fn update_mcp_server(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    id: Uuid,
+    update: UpdateMcpServer,
+) -> Result<Option<McpServer>> {
+    let server = sqlx::query_as!(
+        McpServer,
+        r#"
+        UPDATE mcp_servers
+        SET command = COALESCE($3, command),
+            args = COALESCE($4, args),
+            env = COALESCE($5, env),
+            url = COALESCE($6, url),
+            headers = COALESCE($7, headers),
+            enabled = COALESCE($8, enabled),
+            timeout_seconds = COALESCE($9, timeout_seconds),
+            updated_at = NOW()
+        WHERE workspace_id = $1 AND id = $2
+        RETURNING id, workspace_id, name, transport as "transport: McpTransport", command, args,
+                  env as "env: Json<BTreeMap<String, String>>", url,
+                  headers as "headers: Json<BTreeMap<String, String>>",
+                  enabled, timeout_seconds, created_by, created_at, updated_at
+        "#,
+        workspace_id,
+        id,
+        update.command,
+        update.args.as_deref(),
+        update.env.map(Json) as _,
+        update.url,
+        update.headers.map(Json) as _,
+        update.enabled,
+        update.timeout_seconds
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(server)
+}
+
+/// Deletes an MCP server of a workspace.
+pub async fn delete_mcp_server(conn: &mut DbConn, workspace_id: Uuid, id: Uuid) -> Result<u64> {
+    let rows_affected = sqlx::query(
+        r#"
+        DELETE FROM mcp_servers
+        WHERE workspace_id = $1 AND id = $2
+        "#,
+    )
+    .bind(workspace_id)
+    .bind(id)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
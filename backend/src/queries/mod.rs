@@ -1,10 +1,17 @@
 pub mod agent_sessions;
 pub mod ai_models;
+pub mod api_keys;
 pub mod chat;
 pub mod files;
 pub mod invitations;
+pub mod mcp_servers;
+pub mod oidc;
 pub mod roles;
+pub mod service_accounts;
 pub mod sessions;
+pub mod tool_policies;
+pub mod two_factor;
 pub mod users;
+pub mod user_tokens;
 pub mod workspaces;
 pub mod workspace_members;
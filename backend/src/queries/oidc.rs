@@ -0,0 +1,299 @@
+use crate::{
+    error::{Error, Result},
+    models::oidc::{
+        NewOidcLoginState, NewUserIdentity, NewWorkspaceSsoDomain, OidcLoginState, UserIdentity,
+        WorkspaceSsoDomain, WorkspaceSsoDomainDetailed,
+    },
+};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::DbConn;
+
+/// Hash an OIDC `state` parameter using SHA-256 for secure storage
+pub fn hash_login_state(state: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(state.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+// ============================================================================
+// LOGIN STATES
+// ============================================================================
+
+/// Creates a new pending login state.
+pub async fn create_login_state(conn: &mut DbConn, new_state: NewOidcLoginState) -> Result<OidcLoginState> {
+    let state = sqlx::query_as!(
+        OidcLoginState,
+        r#"
+        INSERT INTO oidc_login_states (state_hash, code_verifier, nonce, redirect_to, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, state_hash, code_verifier, nonce, redirect_to, expires_at, created_at
+        "#,
+        new_state.state_hash,
+        new_state.code_verifier,
+        new_state.nonce,
+        new_state.redirect_to,
+        new_state.expires_at
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(state)
+}
+
+/// Atomically deletes and returns an unexpired login state.
+///
+/// Returns `None` when the state is unknown, expired or was already used, so a
+/// callback URL can never be replayed.
+pub async fn consume_login_state(conn: &mut DbConn, state_hash: &str) -> Result<Option<OidcLoginState>> {
+    let state = sqlx::query_as!(
+        OidcLoginState,
+        r#"
+        DELETE FROM oidc_login_states
+        WHERE state_hash = $1 AND expires_at > NOW()
+        RETURNING id, state_hash, code_verifier, nonce, redirect_to, expires_at, created_at
+        "#,
+        state_hash
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(state)
+}
+
+/// Deletes login states of abandoned login attempts.
+pub async fn delete_expired_login_states(conn: &mut DbConn) -> Result<u64> {
+    let rows_affected = sqlx::query(
+        r#"
+        DELETE FROM oidc_login_states
+        WHERE expires_at <= NOW()
+        "#,
+    )
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
+
+// ============================================================================
+// USER IDENTITIES
+// ============================================================================
+
+/// Links an external identity to a user.
+pub async fn create_user_identity(conn: &mut DbConn, new_identity: NewUserIdentity) -> Result<UserIdentity> {
+    let identity = sqlx::query_as!(
+        UserIdentity,
+        r#"
+        INSERT INTO user_identities (user_id, issuer, subject, email, last_login_at)
+        VALUES ($1, $2, $3, $4, NOW())
+        RETURNING id, user_id, issuer, subject, email, last_login_at, created_at
+        "#,
+        new_identity.user_id,
+        new_identity.issuer,
+        new_identity.subject,
+        new_identity.email
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|e| {
+        if e.to_string().contains("duplicate key") {
+            Error::Conflict("This identity is already linked to an account".to_string())
+        } else {
+            Error::Sqlx(e)
+        }
+    })?;
+
+    Ok(identity)
+}
+
+/// Gets the identity for an (issuer, subject) pair. The identity may not exist.
+pub async fn get_user_identity(conn: &mut DbConn, issuer: &str, subject: &str) -> Result<Option<UserIdentity>> {
+    let identity = sqlx::query_as!(
+        UserIdentity,
+        r#"
+        SELECT id, user_id, issuer, subject, email, last_login_at, created_at
+        FROM user_identities
+        WHERE issuer = $1 AND subject = $2
+        "#,
+        issuer,
+        subject
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(identity)
+}
+
+/// Lists the external identities linked to a user.
+pub async fn list_user_identities(conn: &mut DbConn, user_id: Uuid) -> Result<Vec<UserIdentity>> {
+    let identities = sqlx::query_as!(
+        UserIdentity,
+        r#"
+        SELECT id, user_id, issuer, subject, email, last_login_at, created_at
+        FROM user_identities
+        WHERE user_id = $1
+        ORDER BY created_at
+        "#,
+        user_id
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(identities)
+}
+
+/// Records a successful login and the email address the provider reported.
+pub async fn touch_user_identity(conn: &mut DbConn, id: Uuid, email: Option<&str>) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE user_identities
+        SET last_login_at = NOW(), email = COALESCE($2, email)
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(email)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(())
+}
+
+// ============================================================================
+// WORKSPACE SSO DOMAINS
+// ============================================================================
+
+/// Creates a new SSO domain for a workspace.
+pub async fn create_sso_domain(conn: &mut DbConn, new_domain: NewWorkspaceSsoDomain) -> Result<WorkspaceSsoDomain> {
+    let domain = sqlx::query_as!(
+        WorkspaceSsoDomain,
+        r#"
+        INSERT INTO workspace_sso_domains (workspace_id, domain, role_id, verification_token, created_by)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, workspace_id, domain, role_id, verification_token, verified_at, created_by, created_at
+        "#,
+        new_domain.workspace_id,
+        new_domain.domain,
+        new_domain.role_id,
+        new_domain.verification_token,
+        new_domain.created_by
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|e| {
+        if e.to_string().contains("duplicate key") {
+            Error::Conflict(format!(
+                "Domain '{}' is already configured for this workspace",
+                new_domain.domain
+            ))
+        } else {
+            Error::Sqlx(e)
+        }
+    })?;
+
+    Ok(domain)
+}
+
+/// Lists the SSO domains of a workspace with their role names.
+pub async fn list_sso_domains(conn: &mut DbConn, workspace_id: Uuid) -> Result<Vec<WorkspaceSsoDomainDetailed>> {
+    let domains = sqlx::query_as!(
+        WorkspaceSsoDomainDetailed,
+        r#"
+        SELECT d.id, d.workspace_id, d.domain, d.role_id, r.name as role_name,
+               d.verification_token, d.verified_at, d.created_by, d.created_at
+        FROM workspace_sso_domains d
+        JOIN roles r ON r.id = d.role_id
+        WHERE d.workspace_id = $1
+        ORDER BY d.domain
+        "#,
+        workspace_id
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(domains)
+}
+
+/// Gets an SSO domain of a workspace with its role name.
+pub async fn get_sso_domain(conn: &mut DbConn, workspace_id: Uuid, id: Uuid) -> Result<Option<WorkspaceSsoDomainDetailed>> {
+    let domain = sqlx::query_as!(
+        WorkspaceSsoDomainDetailed,
+        r#"
+        SELECT d.id, d.workspace_id, d.domain, d.role_id, r.name as role_name,
+               d.verification_token, d.verified_at, d.created_by, d.created_at
+        FROM workspace_sso_domains d
+        JOIN roles r ON r.id = d.role_id
+        WHERE d.workspace_id = $1 AND d.id = $2
+        "#,
+        workspace_id,
+        id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(domain)
+}
+
+/// Marks an SSO domain as verified.
+pub async fn mark_sso_domain_verified(conn: &mut DbConn, id: Uuid) -> Result<u64> {
+    let rows_affected = sqlx::query(
+        r#"
+        UPDATE workspace_sso_domains
+        SET verified_at = NOW()
+        WHERE id = $1 AND verified_at IS NULL
+        "#,
+    )
+    .bind(id)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
+
+/// Lists every verified workspace SSO entry for an email domain.
+pub async fn list_verified_sso_domains_by_domain(conn: &mut DbConn, domain: &str) -> Result<Vec<WorkspaceSsoDomain>> {
+    let domains = sqlx::query_as!(
+        WorkspaceSsoDomain,
+        r#"
+        SELECT id, workspace_id, domain, role_id, verification_token, verified_at, created_by, created_at
+        FROM workspace_sso_domains
+        WHERE domain = $1 AND verified_at IS NOT NULL
+        "#,
+        domain
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(domains)
+}
+
+/// Deletes an SSO domain of a workspace.
+pub async fn delete_sso_domain(conn: &mut DbConn, workspace_id: Uuid, id: Uuid) -> Result<u64> {
+    let rows_affected = sqlx::query(
+        r#"
+        DELETE FROM workspace_sso_domains
+        WHERE workspace_id = $1 AND id = $2
+        "#,
+    )
+    .bind(workspace_id)
+    .bind(id)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
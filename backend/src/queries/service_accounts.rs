@@ -0,0 +1,124 @@
+use crate::{
+    error::{Error, Result},
+    models::api_keys::{NewServiceAccount, ServiceAccount, ServiceAccountDetailed},
+};
+use uuid::Uuid;
+
+use crate::DbConn;
+
+/// Creates a new service account in the database.
+pub async fn create_service_account(
+    conn: &mut DbConn,
+    new_account: NewServiceAccount,
+) -> Result<ServiceAccount> {
+    let account = sqlx::query_as!(
+        ServiceAccount,
+        r#"
+        INSERT INTO service_accounts (workspace_id, user_id, name, description, created_by)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, workspace_id, user_id, name, description, created_by, created_at, updated_at
+        "#,
+        new_account.workspace_id,
+        new_account.user_id,
+        new_account.name,
+        new_account.description,
+        new_account.created_by
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(account)
+}
+
+/// Gets a service account by ID within a workspace.
+pub async fn get_service_account(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    id: Uuid,
+) -> Result<Option<ServiceAccount>> {
+    let account = sqlx::query_as!(
+        ServiceAccount,
+        r#"
+        SELECT id, workspace_id, user_id, name, description, created_by, created_at, updated_at
+        FROM service_accounts
+        WHERE workspace_id = $1 AND id = $2
+        "#,
+        workspace_id,
+        id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(account)
+}
+
+/// Gets the service account backed by a user, if any.
+pub async fn get_service_account_by_user_id(
+    conn: &mut DbConn,
+    user_id: Uuid,
+) -> Result<Option<ServiceAccount>> {
+    let account = sqlx::query_as!(
+        ServiceAccount,
+        r#"
+        SELECT id, workspace_id, user_id, name, description, created_by, created_at, updated_at
+        FROM service_accounts
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(account)
+}
+
+/// Gets a service account by name within a workspace.
+pub async fn get_service_account_by_name(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    name: &str,
+) -> Result<Option<ServiceAccount>> {
+    let account = sqlx::query_as!(
+        ServiceAccount,
+        r#"
+        SELECT id, workspace_id, user_id, name, description, created_by, created_at, updated_at
+        FROM service_accounts
+        WHERE workspace_id = $1 AND name = $2
+        "#,
+        workspace_id,
+        name
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(account)
+}
+
+/// Lists all service accounts in a workspace along with their current role.
+pub async fn list_service_accounts(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+) -> Result<Vec<ServiceAccountDetailed>> {
+    let accounts = sqlx::query_as!(
+        ServiceAccountDetailed,
+        r#"
+        SELECT sa.id, sa.workspace_id, sa.user_id, sa.name, sa.description,
+               r.name as role_name, sa.created_by, sa.created_at
+        FROM service_accounts sa
+        JOIN workspace_members wm ON wm.workspace_id = sa.workspace_id AND wm.user_id = sa.user_id
+        JOIN roles r ON r.id = wm.role_id
+        WHERE sa.workspace_id = $1
+        ORDER BY sa.created_at ASC
+        "#,
+        workspace_id
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(accounts)
+}
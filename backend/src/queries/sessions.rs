@@ -23,7 +23,7 @@ pub async fn create_session(conn: &mut DbConn, new_session: NewUserSession) -> R
         r#"
         INSERT INTO user_sessions (user_id, token_hash, expires_at)
         VALUES ($1, $2, $3)
-        RETURNING id, user_id, token_hash, expires_at, created_at, updated_at
+        RETURNING id, user_id, token_hash, expires_at, user_agent, ip_address, last_used_at, created_at, updated_at
         "#,
         new_session.user_id,
         new_session.token_hash,
@@ -41,7 +41,7 @@ pub async fn get_session_by_token_hash(conn: &mut DbConn, token_hash: &str) -> R
     let session = sqlx::query_as!(
         UserSession,
         r#"
-        SELECT id, user_id, token_hash, expires_at, created_at, updated_at
+        SELECT id, user_id, token_hash, expires_at, user_agent, ip_address, last_used_at, created_at, updated_at
         FROM user_sessions
         WHERE token_hash = $1
         "#,
@@ -59,7 +59,7 @@ pub async fn get_sessions_by_user(conn: &mut DbConn, user_id: Uuid) -> Result<Ve
     let sessions = sqlx::query_as!(
         UserSession,
         r#"
-        SELECT id, user_id, token_hash, expires_at, created_at, updated_at
+        SELECT id, user_id, token_hash, expires_at, user_agent, ip_address, last_used_at, created_at, updated_at
         FROM user_sessions
         WHERE user_id = $1
         ORDER BY created_at DESC
@@ -81,7 +81,7 @@ pub async fn update_session(conn: &mut DbConn, session_id: Uuid, update: UpdateU
         UPDATE user_sessions
         SET expires_at = COALESCE($1, expires_at), updated_at = now()
         WHERE id = $2
-        RETURNING id, user_id, token_hash, expires_at, created_at, updated_at
+        RETURNING id, user_id, token_hash, expires_at, user_agent, ip_address, last_used_at, created_at, updated_at
         "#,
         update.expires_at,
         session_id
@@ -182,7 +182,7 @@ pub async fn get_valid_session_by_token_hash(conn: &mut DbConn, token_hash: &str
     let session = sqlx::query_as!(
         UserSession,
         r#"
-        SELECT id, user_id, token_hash, expires_at, created_at, updated_at
+        SELECT id, user_id, token_hash, expires_at, user_agent, ip_address, last_used_at, created_at, updated_at
         FROM user_sessions
         WHERE token_hash = $1 AND expires_at > NOW()
         "#,
@@ -195,6 +195,24 @@ pub async fn get_valid_session_by_token_hash(conn: &mut DbConn, token_hash: &str
     Ok(session)
 }
 
+/// Gets a valid session (exists and not expired) by its ID.
+pub async fn get_valid_session_by_id(conn: &mut DbConn, session_id: Uuid) -> Result<Option<UserSession>> {
+    let session = sqlx::query_as!(
+        UserSession,
+        r#"
+        SELECT id, user_id, token_hash, expires_at, user_agent, ip_address, last_used_at, created_at, updated_at
+        FROM user_sessions
+        WHERE id = $1 AND expires_at > NOW()
+        "#,
+        session_id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(session)
+}
+
 /// Refreshes a session by extending its expiration time.
 pub async fn refresh_session(conn: &mut DbConn, session_id: Uuid, new_expires_at: chrono::DateTime<Utc>) -> Result<UserSession> {
     let updated_session = sqlx::query_as!(
@@ -203,7 +221,7 @@ pub async fn refresh_session(conn: &mut DbConn, session_id: Uuid, new_expires_at
         UPDATE user_sessions
         SET expires_at = $1, updated_at = now()
         WHERE id = $2
-        RETURNING id, user_id, token_hash, expires_at, created_at, updated_at
+        RETURNING id, user_id, token_hash, expires_at, user_agent, ip_address, last_used_at, created_at, updated_at
         "#,
         new_expires_at,
         session_id
@@ -241,7 +259,7 @@ pub async fn update_session_token_hash(
         UPDATE user_sessions
         SET token_hash = $1, updated_at = now()
         WHERE id = $2
-        RETURNING id, user_id, token_hash, expires_at, created_at, updated_at
+        RETURNING id, user_id, token_hash, expires_at, user_agent, ip_address, last_used_at, created_at, updated_at
         "#,
         new_token_hash,
         session_id
@@ -252,6 +270,87 @@ pub async fn update_session_token_hash(
 
     Ok(updated_session)
 }
+
+/// Gets the unexpired sessions of a user, most recently used first.
+pub async fn get_active_sessions_by_user(conn: &mut DbConn, user_id: Uuid) -> Result<Vec<UserSession>> {
+    let sessions = sqlx::query_as!(
+        UserSession,
+        r#"
+        SELECT id, user_id, token_hash, expires_at, user_agent, ip_address, last_used_at, created_at, updated_at
+        FROM user_sessions
+        WHERE user_id = $1 AND expires_at > NOW()
+        ORDER BY last_used_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(sessions)
+}
+
+/// Records the device that logged in or refreshed a session and marks it as used now.
+pub async fn touch_session(
+    conn: &mut DbConn,
+    session_id: Uuid,
+    user_agent: Option<&str>,
+    ip_address: Option<&str>,
+) -> Result<u64> {
+    let rows_affected = sqlx::query(
+        r#"
+        UPDATE user_sessions
+        SET user_agent = $2, ip_address = $3, last_used_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(session_id)
+    .bind(user_agent)
+    .bind(ip_address)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
+
+/// Deletes a session of a specific user by its ID.
+pub async fn delete_user_session(conn: &mut DbConn, user_id: Uuid, session_id: Uuid) -> Result<u64> {
+    let rows_affected = sqlx::query(
+        r#"
+        DELETE FROM user_sessions
+        WHERE id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
+
+/// Deletes all sessions of a user except one.
+pub async fn delete_other_user_sessions(conn: &mut DbConn, user_id: Uuid, keep_session_id: Uuid) -> Result<u64> {
+    let rows_affected = sqlx::query(
+        r#"
+        DELETE FROM user_sessions
+        WHERE user_id = $1 AND id <> $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(keep_session_id)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
+
 // ============================================================================
 // REVOKED TOKEN MANAGEMENT (Stolen Token Detection)
 // ============================================================================
@@ -260,16 +359,18 @@ pub async fn update_session_token_hash(
 pub async fn create_revoked_token(
     conn: &mut DbConn,
     user_id: Uuid,
+    session_id: Uuid,
     token_hash: &str,
 ) -> Result<RevokedRefreshToken> {
     let revoked_token = sqlx::query_as!(
         RevokedRefreshToken,
         r#"
-        INSERT INTO revoked_refresh_tokens (user_id, token_hash)
-        VALUES ($1, $2)
-        RETURNING id, user_id, token_hash, revoked_at, reason
+        INSERT INTO revoked_refresh_tokens (user_id, session_id, token_hash)
+        VALUES ($1, $2, $3)
+        RETURNING id, user_id, token_hash, revoked_at, reason, session_id
         "#,
         user_id,
+        session_id,
         token_hash
     )
     .fetch_one(conn)
@@ -287,7 +388,7 @@ pub async fn get_revoked_token(
     let revoked_token = sqlx::query_as!(
         RevokedRefreshToken,
         r#"
-        SELECT id, user_id, token_hash, revoked_at, reason
+        SELECT id, user_id, token_hash, revoked_at, reason, session_id
         FROM revoked_refresh_tokens
         WHERE token_hash = $1
         "#,
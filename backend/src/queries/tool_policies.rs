@@ -0,0 +1,61 @@
+use crate::{
+    error::{Error, Result},
+    models::tool_policy::{ToolPolicy, ToolPolicyRule},
+};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::DbConn;
+
+/// Gets the tool policy of a workspace, with no rules if none was set.
+pub async fn get_tool_policy(conn: &mut DbConn, workspace_id: Uuid) -> Result<ToolPolicy> {
+    let row = sqlx::query!(
+        r#"
+        SELECT rules as "rules: Json<Vec<ToolPolicyRule>>", updated_at
+        FROM workspace_tool_policies
+        WHERE workspace_id = $1
+        "#,
+        workspace_id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(row
+        .map(|row| ToolPolicy {
+            rules: row.rules.0,
+            updated_at: Some(row.updated_at),
+        })
+        .unwrap_or_default())
+}
+
+/// Replaces the tool policy of a workspace.
+pub async fn set_tool_policy(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    rules: &[ToolPolicyRule],
+    updated_by: Uuid,
+) -> Result<ToolPolicy> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO workspace_tool_policies (workspace_id, rules, updated_by)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (workspace_id) DO UPDATE
+        SET rules = EXCLUDED.rules,
+            updated_by = EXCLUDED.updated_by,
+            updated_at = NOW()
+        RETURNING rules as "rules: Json<Vec<ToolPolicyRule>>", updated_at
+        "#,
+        workspace_id,
+        Json(rules) as _,
+        updated_by
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(ToolPolicy {
+        rules: row.rules.0,
+        updated_at: Some(row.updated_at),
+    })
+}
@@ -0,0 +1,353 @@
+use crate::{
+    error::{Error, Result},
+    models::two_factor::{NewTwoFactorChallenge, TwoFactorChallenge, UserTotp},
+};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::DbConn;
+
+/// Hash a challenge token or recovery code using SHA-256 for secure storage
+pub fn hash_two_factor_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+// ============================================================================
+// TOTP AUTHENTICATORS
+// ============================================================================
+
+/// Stores a new pending TOTP secret for a user, replacing an earlier pending one.
+///
+/// Returns `None` if the user already has an enabled authenticator, which is never replaced.
+pub async fn upsert_pending_totp(conn: &mut DbConn, user_id: Uuid, secret: &str) -> Result<Option<UserTotp>> {
+    let totp = sqlx::query_as!(
+        UserTotp,
+        r#"
+        INSERT INTO user_totp (user_id, secret)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE
+        SET secret = EXCLUDED.secret,
+            last_used_step = NULL,
+            created_at = NOW(),
+            updated_at = NOW()
+        WHERE user_totp.enabled_at IS NULL
+        RETURNING user_id, secret, enabled_at, last_used_step, created_at, updated_at
+        "#,
+        user_id,
+        secret
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(totp)
+}
+
+/// Gets the TOTP authenticator of a user, pending or enabled. It may not exist.
+pub async fn get_user_totp(conn: &mut DbConn, user_id: Uuid) -> Result<Option<UserTotp>> {
+    let totp = sqlx::query_as!(
+        UserTotp,
+        r#"
+        SELECT user_id, secret, enabled_at, last_used_step, created_at, updated_at
+        FROM user_totp
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(totp)
+}
+
+/// Enables a pending authenticator, recording the time step of the confirming code.
+pub async fn enable_totp(conn: &mut DbConn, user_id: Uuid, step: i64) -> Result<Option<UserTotp>> {
+    let totp = sqlx::query_as!(
+        UserTotp,
+        r#"
+        UPDATE user_totp
+        SET enabled_at = NOW(), last_used_step = $2, updated_at = NOW()
+        WHERE user_id = $1 AND enabled_at IS NULL
+        RETURNING user_id, secret, enabled_at, last_used_step, created_at, updated_at
+        "#,
+        user_id,
+        step
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(totp)
+}
+
+/// Records an accepted code's time step.
+///
+/// Returns `false` if a code of the same or a later step was already accepted, so a
+/// code that was observed in transit cannot be used a second time.
+pub async fn record_totp_step(conn: &mut DbConn, user_id: Uuid, step: i64) -> Result<bool> {
+    let rows_affected = sqlx::query(
+        r#"
+        UPDATE user_totp
+        SET last_used_step = $2, updated_at = NOW()
+        WHERE user_id = $1
+          AND enabled_at IS NOT NULL
+          AND (last_used_step IS NULL OR last_used_step < $2)
+        "#,
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected == 1)
+}
+
+/// Deletes the authenticator of a user.
+pub async fn delete_user_totp(conn: &mut DbConn, user_id: Uuid) -> Result<u64> {
+    let rows_affected = sqlx::query(
+        r#"
+        DELETE FROM user_totp
+        WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
+
+/// Checks if a user has an enabled authenticator.
+pub async fn is_two_factor_enabled(conn: &mut DbConn, user_id: Uuid) -> Result<bool> {
+    let enabled = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM user_totp WHERE user_id = $1 AND enabled_at IS NOT NULL
+        ) as "enabled!"
+        "#,
+        user_id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(enabled)
+}
+
+/// Checks if a workspace requires 2FA and the user has not enabled it.
+pub async fn is_blocked_by_two_factor_policy(conn: &mut DbConn, workspace_id: Uuid, user_id: Uuid) -> Result<bool> {
+    let blocked = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM workspaces w
+            WHERE w.id = $1
+              AND w.require_two_factor
+              AND NOT EXISTS(
+                  SELECT 1 FROM user_totp t WHERE t.user_id = $2 AND t.enabled_at IS NOT NULL
+              )
+        ) as "blocked!"
+        "#,
+        workspace_id,
+        user_id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(blocked)
+}
+
+// ============================================================================
+// RECOVERY CODES
+// ============================================================================
+
+/// Replaces all recovery codes of a user with a new set of hashed codes.
+pub async fn replace_recovery_codes(conn: &mut DbConn, user_id: Uuid, code_hashes: &[String]) -> Result<()> {
+    delete_recovery_codes(conn, user_id).await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_recovery_codes (user_id, code_hash)
+        SELECT $1, UNNEST($2::TEXT[])
+        "#,
+    )
+    .bind(user_id)
+    .bind(code_hashes)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(())
+}
+
+/// Atomically marks an unused recovery code as used.
+///
+/// Returns `false` when the code does not exist or was already used.
+pub async fn consume_recovery_code(conn: &mut DbConn, user_id: Uuid, code_hash: &str) -> Result<bool> {
+    let rows_affected = sqlx::query(
+        r#"
+        UPDATE user_recovery_codes
+        SET used_at = NOW()
+        WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(code_hash)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected == 1)
+}
+
+/// Counts the unused recovery codes of a user.
+pub async fn count_unused_recovery_codes(conn: &mut DbConn, user_id: Uuid) -> Result<i64> {
+    let count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "count!"
+        FROM user_recovery_codes
+        WHERE user_id = $1 AND used_at IS NULL
+        "#,
+        user_id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(count)
+}
+
+/// Deletes all recovery codes of a user.
+pub async fn delete_recovery_codes(conn: &mut DbConn, user_id: Uuid) -> Result<u64> {
+    let rows_affected = sqlx::query(
+        r#"
+        DELETE FROM user_recovery_codes
+        WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
+
+// ============================================================================
+// LOGIN CHALLENGES
+// ============================================================================
+
+/// Creates a new login challenge.
+pub async fn create_challenge(conn: &mut DbConn, new_challenge: NewTwoFactorChallenge) -> Result<TwoFactorChallenge> {
+    let challenge = sqlx::query_as!(
+        TwoFactorChallenge,
+        r#"
+        INSERT INTO two_factor_challenges (user_id, token_hash, expires_at)
+        VALUES ($1, $2, $3)
+        RETURNING id, user_id, token_hash, failed_attempts, expires_at, created_at
+        "#,
+        new_challenge.user_id,
+        new_challenge.token_hash,
+        new_challenge.expires_at
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(challenge)
+}
+
+/// Gets an unexpired challenge by its token hash.
+pub async fn get_active_challenge(conn: &mut DbConn, token_hash: &str) -> Result<Option<TwoFactorChallenge>> {
+    let challenge = sqlx::query_as!(
+        TwoFactorChallenge,
+        r#"
+        SELECT id, user_id, token_hash, failed_attempts, expires_at, created_at
+        FROM two_factor_challenges
+        WHERE token_hash = $1 AND expires_at > NOW()
+        "#,
+        token_hash
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(challenge)
+}
+
+/// Increments the failed attempt counter of a challenge and returns the new count.
+pub async fn record_failed_challenge_attempt(conn: &mut DbConn, id: Uuid) -> Result<i32> {
+    let attempts = sqlx::query_scalar!(
+        r#"
+        UPDATE two_factor_challenges
+        SET failed_attempts = failed_attempts + 1
+        WHERE id = $1
+        RETURNING failed_attempts
+        "#,
+        id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(attempts.unwrap_or(i32::MAX))
+}
+
+/// Atomically deletes a challenge. Returns `false` if it was already consumed.
+pub async fn consume_challenge(conn: &mut DbConn, id: Uuid) -> Result<bool> {
+    let rows_affected = sqlx::query(
+        r#"
+        DELETE FROM two_factor_challenges
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected == 1)
+}
+
+/// Deletes all pending challenges of a user.
+pub async fn delete_user_challenges(conn: &mut DbConn, user_id: Uuid) -> Result<u64> {
+    let rows_affected = sqlx::query(
+        r#"
+        DELETE FROM two_factor_challenges
+        WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
+
+/// Deletes challenges of abandoned logins.
+pub async fn delete_expired_challenges(conn: &mut DbConn) -> Result<u64> {
+    let rows_affected = sqlx::query(
+        r#"
+        DELETE FROM two_factor_challenges
+        WHERE expires_at <= NOW()
+        "#,
+    )
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
@@ -0,0 +1,108 @@
+use crate::{
+    error::{Error, Result},
+    models::users::{NewUserToken, UserToken, UserTokenPurpose},
+};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::DbConn;
+
+/// Hash a user token using SHA-256 for secure storage
+pub fn hash_user_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Creates a new user token in the database.
+pub async fn create_user_token(conn: &mut DbConn, new_token: NewUserToken) -> Result<UserToken> {
+    let token = sqlx::query_as!(
+        UserToken,
+        r#"
+        INSERT INTO user_tokens (user_id, purpose, token_hash, expires_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, user_id, purpose as "purpose: UserTokenPurpose", token_hash, expires_at, used_at, created_at
+        "#,
+        new_token.user_id,
+        new_token.purpose as UserTokenPurpose,
+        new_token.token_hash,
+        new_token.expires_at
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(token)
+}
+
+/// Atomically marks a token as used and returns it.
+///
+/// Returns `None` when the token does not exist, belongs to a different purpose,
+/// has expired, or was already consumed. The single `UPDATE ... WHERE used_at IS NULL`
+/// guarantees that two concurrent requests cannot both consume the same token.
+pub async fn consume_user_token(
+    conn: &mut DbConn,
+    token_hash: &str,
+    purpose: UserTokenPurpose,
+) -> Result<Option<UserToken>> {
+    let token = sqlx::query_as!(
+        UserToken,
+        r#"
+        UPDATE user_tokens
+        SET used_at = NOW()
+        WHERE token_hash = $1
+          AND purpose = $2
+          AND used_at IS NULL
+          AND expires_at > NOW()
+        RETURNING id, user_id, purpose as "purpose: UserTokenPurpose", token_hash, expires_at, used_at, created_at
+        "#,
+        token_hash,
+        purpose as UserTokenPurpose
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(token)
+}
+
+/// Deletes all unused tokens of a purpose for a user.
+///
+/// Called before issuing a new token so only the most recent link stays valid.
+pub async fn delete_unused_user_tokens(
+    conn: &mut DbConn,
+    user_id: Uuid,
+    purpose: UserTokenPurpose,
+) -> Result<u64> {
+    let rows_affected = sqlx::query(
+        r#"
+        DELETE FROM user_tokens
+        WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(purpose)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
+
+/// Deletes expired and already-used tokens.
+/// Should be called periodically to maintain table size
+pub async fn delete_stale_user_tokens(conn: &mut DbConn) -> Result<u64> {
+    let rows_affected = sqlx::query(
+        r#"
+        DELETE FROM user_tokens
+        WHERE expires_at < NOW() OR used_at IS NOT NULL
+        "#,
+    )
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .rows_affected();
+
+    Ok(rows_affected)
+}
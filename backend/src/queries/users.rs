@@ -13,7 +13,7 @@ pub async fn create_user(conn: &mut DbConn, new_user: NewUser) -> Result<User> {
         r#"
         INSERT INTO users (email, password_hash, full_name)
         VALUES ($1, $2, $3)
-        RETURNING id, email, password_hash, full_name, created_at, updated_at
+        RETURNING id, email, password_hash, full_name, email_verified_at, created_at, updated_at
         "#,
         new_user.email,
         new_user.password_hash,
@@ -47,7 +47,7 @@ pub async fn get_user_by_id(conn: &mut DbConn, id: Uuid) -> Result<Option<User>>
     let user = sqlx::query_as!(
         User,
         r#"
-        SELECT id, email, password_hash, full_name, created_at, updated_at
+        SELECT id, email, password_hash, full_name, email_verified_at, created_at, updated_at
         FROM users
         WHERE id = $1
         "#,
@@ -65,7 +65,7 @@ pub async fn get_user_by_email(conn: &mut DbConn, email: &str) -> Result<Option<
     let user = sqlx::query_as!(
         User,
         r#"
-        SELECT id, email, password_hash, full_name, created_at, updated_at
+        SELECT id, email, password_hash, full_name, email_verified_at, created_at, updated_at
         FROM users
         WHERE email = $1
         "#,
@@ -83,7 +83,7 @@ pub async fn list_users(conn: &mut DbConn) -> Result<Vec<User>> {
     let users = sqlx::query_as!(
         User,
         r#"
-        SELECT id, email, password_hash, full_name, created_at, updated_at
+        SELECT id, email, password_hash, full_name, email_verified_at, created_at, updated_at
         FROM users
         ORDER BY created_at DESC
         "#,
@@ -103,7 +103,7 @@ pub async fn update_user(conn: &mut DbConn, user: &User) -> Result<User> {
         UPDATE users
         SET email = $1, password_hash = $2, full_name = $3, updated_at = now()
         WHERE id = $4
-        RETURNING id, email, password_hash, full_name, created_at, updated_at
+        RETURNING id, email, password_hash, full_name, email_verified_at, created_at, updated_at
         "#,
         &user.email,
         user.password_hash.as_deref(),
@@ -156,3 +156,40 @@ pub async fn delete_user(conn: &mut DbConn, id: Uuid) -> Result<u64> {
 
     Ok(rows_affected)
 }
+
+/// Marks a user's email address as verified (keeps the original timestamp if already verified).
+pub async fn mark_email_verified(conn: &mut DbConn, user_id: Uuid) -> Result<User> {
+    let user = sqlx::query_as!(
+        User,
+        r#"
+        UPDATE users
+        SET email_verified_at = COALESCE(email_verified_at, now()), updated_at = now()
+        WHERE id = $1
+        RETURNING id, email, password_hash, full_name, email_verified_at, created_at, updated_at
+        "#,
+        user_id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::Sqlx)?
+    .ok_or_else(|| Error::NotFound(format!("User with ID {} not found", user_id)))?;
+
+    Ok(user)
+}
+
+/// Removes a user's password so they can only sign in through an external identity.
+pub async fn clear_user_password(conn: &mut DbConn, user_id: Uuid) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET password_hash = NULL, updated_at = now()
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(())
+}
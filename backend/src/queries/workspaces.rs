@@ -13,7 +13,7 @@ pub async fn create_workspace(conn: &mut DbConn, new_workspace: NewWorkspace) ->
         r#"
         INSERT INTO workspaces (name, owner_id, ai_provider_override)
         VALUES ($1, $2, $3)
-        RETURNING id, name, owner_id, NULL as "role_name?", ai_provider_override, created_at, updated_at
+        RETURNING id, name, owner_id, NULL as "role_name?", ai_provider_override, require_two_factor, ai_fallback_models, created_at, updated_at
         "#,
         new_workspace.name,
         new_workspace.owner_id,
@@ -31,7 +31,7 @@ pub async fn get_workspace_by_id(conn: &mut DbConn, id: Uuid) -> Result<Workspac
     let workspace = sqlx::query_as!(
         Workspace,
         r#"
-        SELECT id, name, owner_id, NULL as "role_name?", ai_provider_override, created_at, updated_at
+        SELECT id, name, owner_id, NULL as "role_name?", ai_provider_override, require_two_factor, ai_fallback_models, created_at, updated_at
         FROM workspaces
         WHERE id = $1
         "#,
@@ -49,7 +49,7 @@ pub async fn get_workspace_by_id_optional(conn: &mut DbConn, id: Uuid) -> Result
     let workspace = sqlx::query_as!(
         Workspace,
         r#"
-        SELECT id, name, owner_id, NULL as "role_name?", ai_provider_override, created_at, updated_at
+        SELECT id, name, owner_id, NULL as "role_name?", ai_provider_override, require_two_factor, ai_fallback_models, created_at, updated_at
         FROM workspaces
         WHERE id = $1
         "#,
@@ -67,7 +67,7 @@ pub async fn get_workspaces_by_owner(conn: &mut DbConn, owner_id: Uuid) -> Resul
     let workspaces = sqlx::query_as!(
         Workspace,
         r#"
-        SELECT id, name, owner_id, NULL as "role_name?", ai_provider_override, created_at, updated_at
+        SELECT id, name, owner_id, NULL as "role_name?", ai_provider_override, require_two_factor, ai_fallback_models, created_at, updated_at
         FROM workspaces
         WHERE owner_id = $1
         ORDER BY created_at DESC
@@ -86,7 +86,7 @@ pub async fn list_workspaces(conn: &mut DbConn) -> Result<Vec<Workspace>> {
     let workspaces = sqlx::query_as!(
         Workspace,
         r#"
-        SELECT id, name, owner_id, NULL as "role_name?", ai_provider_override, created_at, updated_at
+        SELECT id, name, owner_id, NULL as "role_name?", ai_provider_override, require_two_factor, ai_fallback_models, created_at, updated_at
         FROM workspaces
         ORDER BY created_at DESC
         "#,
@@ -114,7 +114,7 @@ pub async fn update_workspace(conn: &mut DbConn, id: Uuid, update_workspace: Upd
                 ai_provider_override = $3,
                 updated_at = now()
             WHERE id = $4
-            RETURNING id, name, owner_id, NULL as "role_name?", ai_provider_override, created_at, updated_at
+            RETURNING id, name, owner_id, NULL as "role_name?", ai_provider_override, require_two_factor, ai_fallback_models, created_at, updated_at
             "#,
             update_workspace.name,
             update_workspace.owner_id,
@@ -133,7 +133,7 @@ pub async fn update_workspace(conn: &mut DbConn, id: Uuid, update_workspace: Upd
                 owner_id = COALESCE($2, owner_id),
                 updated_at = now()
             WHERE id = $3
-            RETURNING id, name, owner_id, NULL as "role_name?", ai_provider_override, created_at, updated_at
+            RETURNING id, name, owner_id, NULL as "role_name?", ai_provider_override, require_two_factor, ai_fallback_models, created_at, updated_at
             "#,
             update_workspace.name,
             update_workspace.owner_id,
@@ -164,6 +164,48 @@ pub async fn delete_workspace(conn: &mut DbConn, id: Uuid) -> Result<u64> {
     Ok(rows_affected)
 }
 
+/// Sets whether members need two-factor authentication to access a workspace.
+pub async fn set_require_two_factor(conn: &mut DbConn, id: Uuid, require_two_factor: bool) -> Result<Workspace> {
+    let workspace = sqlx::query_as!(
+        Workspace,
+        r#"
+        UPDATE workspaces
+        SET require_two_factor = $1,
+            updated_at = now()
+        WHERE id = $2
+        RETURNING id, name, owner_id, NULL as "role_name?", ai_provider_override, require_two_factor, ai_fallback_models, created_at, updated_at
+        "#,
+        require_two_factor,
+        id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(workspace)
+}
+
+/// Sets the models a workspace's chats fall back to, in order.
+pub async fn set_ai_fallback_models(conn: &mut DbConn, id: Uuid, fallback_models: &[String]) -> Result<Workspace> {
+    let workspace = sqlx::query_as!(
+        Workspace,
+        r#"
+        UPDATE workspaces
+        SET ai_fallback_models = $1,
+            updated_at = now()
+        WHERE id = $2
+        RETURNING id, name, owner_id, NULL as "role_name?", ai_provider_override, require_two_factor, ai_fallback_models, created_at, updated_at
+        "#,
+        fallback_models,
+        id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(workspace)
+}
+
 /// Checks if a user is the owner of a workspace.
 pub async fn is_workspace_owner(conn: &mut DbConn, workspace_id: Uuid, user_id: Uuid) -> Result<bool> {
     let count = sqlx::query_scalar!(
@@ -203,6 +245,8 @@ pub async fn get_workspaces_by_user_membership(
             w.owner_id,
             CASE WHEN r.id IS NOT NULL THEN r.name ELSE NULL END as "role_name?",
             w.ai_provider_override,
+            w.require_two_factor,
+            w.ai_fallback_models,
             w.created_at,
             w.updated_at
         FROM workspaces w
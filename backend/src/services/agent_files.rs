@@ -0,0 +1,238 @@
+//! Agent files: user-defined personas selectable when creating a chat.
+//!
+//! An agent file is a workspace file of type `agent` (conventionally `*.agent`)
+//! whose body is the system prompt and whose YAML frontmatter sets the default
+//! model, temperature, tool allowlist and memory scope. Chats created with an
+//! `agent_id` use it in place of the built-in planner/builder/assistant persona.
+
+use crate::DbConn;
+use crate::{
+    error::{Error, Result, ValidationErrors},
+    models::files::FileType,
+    providers::{AiProvider, ModelIdentifier},
+    queries::files as file_queries,
+    services::files,
+    services::storage::FileStorageService,
+    tools::ToolConfig,
+    utils::{agent_name_from_path, parse_agent_frontmatter, AgentMetadata},
+};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Highest sampling temperature accepted by the supported providers
+const MAX_TEMPERATURE: f32 = 2.0;
+
+/// An agent file resolved to its metadata and system prompt
+#[derive(Debug, Clone)]
+pub struct AgentDefinition {
+    pub id: Uuid,
+    pub path: String,
+    /// Frontmatter with `name` always filled in
+    pub metadata: AgentMetadata,
+    /// Agent body, used as the system prompt
+    pub system_prompt: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AgentDefinition {
+    /// Applies the agent's tool allowlist and memory scope to a tool config
+    pub fn restrict_tool_config(&self, tool_config: ToolConfig) -> ToolConfig {
+        ToolConfig {
+            allowed_tools: self.metadata.tools.clone(),
+            memory_scope: self.metadata.memory_scope.clone(),
+            ..tool_config
+        }
+    }
+}
+
+/// Validates agent file content, returning the parsed metadata and system prompt
+///
+/// Called whenever an agent file is created or gets a new version so a broken
+/// definition never reaches a chat.
+pub fn validate_agent_content(content: &Value) -> Result<(AgentMetadata, String)> {
+    let invalid = |message: String| {
+        Error::Validation(ValidationErrors::Single {
+            field: "content".to_string(),
+            message,
+        })
+    };
+
+    let Value::String(content) = content else {
+        return Err(invalid(
+            "Agent file content must be text: YAML frontmatter followed by the system prompt".to_string(),
+        ));
+    };
+
+    let (metadata, body) = parse_agent_frontmatter(content)
+        .map_err(|e| invalid(format!("Invalid agent frontmatter: {}", e)))?;
+    let metadata = metadata.unwrap_or_default();
+
+    let system_prompt = body.trim();
+    if system_prompt.is_empty() {
+        return Err(invalid("Agent file must contain a system prompt after the frontmatter".to_string()));
+    }
+
+    if let Some(model) = &metadata.model {
+        let model_id = ModelIdentifier::parse(model.trim(), AiProvider::OpenAi)
+            .map_err(|e| invalid(format!("Invalid agent model '{}': {}", model, e)))?;
+        if model_id.model.trim().is_empty() {
+            return Err(invalid(format!("Invalid agent model '{}': model name is empty", model)));
+        }
+    }
+
+    if let Some(temperature) = metadata.temperature
+        && !(0.0..=MAX_TEMPERATURE).contains(&temperature)
+    {
+        return Err(invalid(format!(
+            "Invalid agent temperature {}: must be between 0 and {}",
+            temperature, MAX_TEMPERATURE
+        )));
+    }
+
+    if let Some(tools) = &metadata.tools
+        && let Some(unknown) = tools.iter().find(|tool| crate::tools::get_tool_executor(tool).is_err())
+    {
+        return Err(invalid(format!("Unknown tool in agent tools list: {}", unknown)));
+    }
+
+    Ok((metadata, system_prompt.to_string()))
+}
+
+/// Gets an agent by file ID, scoped to the workspace
+pub async fn get_agent(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    workspace_id: Uuid,
+    agent_id: Uuid,
+) -> Result<AgentDefinition> {
+    let not_found = || Error::NotFound(format!("Agent not found: {}", agent_id));
+
+    let file = match file_queries::get_file_by_id(conn, agent_id).await {
+        Ok(file) => file,
+        Err(Error::NotFound(_)) => return Err(not_found()),
+        Err(e) => return Err(e),
+    };
+
+    if file.workspace_id != workspace_id
+        || file.file_type != FileType::Agent
+        || file.deleted_at.is_some()
+    {
+        return Err(not_found());
+    }
+
+    let file_with_content = files::get_file_with_content(conn, storage, file.id).await?;
+    let (mut metadata, system_prompt) = validate_agent_content(&file_with_content.content)?;
+    if metadata.name.trim().is_empty() {
+        metadata.name = agent_name_from_path(&file.path);
+    }
+
+    Ok(AgentDefinition {
+        id: file.id,
+        path: file.path,
+        metadata,
+        system_prompt,
+        updated_at: file.updated_at,
+    })
+}
+
+/// Loads the agent a chat was created with
+///
+/// A deleted or broken agent file must not block the chat, so errors are
+/// logged and the caller falls back to the built-in persona.
+pub async fn load_chat_agent(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    workspace_id: Uuid,
+    chat_id: Uuid,
+    agent_id: Uuid,
+) -> Option<AgentDefinition> {
+    match get_agent(conn, storage, workspace_id, agent_id).await {
+        Ok(agent) => Some(agent),
+        Err(e) => {
+            tracing::warn!(
+                chat_id = %chat_id,
+                agent_id = %agent_id,
+                error = %e,
+                "Chat agent is unavailable, falling back to the built-in persona"
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::MemoryScope;
+
+    fn content(text: &str) -> Value {
+        Value::String(text.to_string())
+    }
+
+    #[test]
+    fn test_validate_agent_content_valid() {
+        let (metadata, prompt) = validate_agent_content(&content(
+            "---\nmodel: openrouter:anthropic/claude-3.5-sonnet\ntemperature: 0.3\ntools: [read, grep]\nmemory_scope: user\n---\nYou review code.\n",
+        ))
+        .unwrap();
+
+        assert_eq!(metadata.temperature, Some(0.3));
+        assert_eq!(metadata.memory_scope, Some(MemoryScope::User));
+        assert_eq!(prompt, "You review code.");
+    }
+
+    #[test]
+    fn test_validate_agent_content_without_frontmatter() {
+        let (metadata, prompt) = validate_agent_content(&content("You are terse.")).unwrap();
+
+        assert_eq!(metadata, AgentMetadata::default());
+        assert_eq!(prompt, "You are terse.");
+    }
+
+    #[test]
+    fn test_validate_agent_content_rejects_invalid_definitions() {
+        let invalid = [
+            "---\nname: empty\n---\n",
+            "---\nmodel: acme:model-x\n---\nPrompt",
+            "---\nmodel: \"openai:\"\n---\nPrompt",
+            "---\ntemperature: 3.5\n---\nPrompt",
+            "---\ntools: [read, launch_rockets]\n---\nPrompt",
+            "---\nmemory_scope: team\n---\nPrompt",
+        ];
+        for text in invalid {
+            assert!(
+                matches!(validate_agent_content(&content(text)), Err(Error::Validation(_))),
+                "Should reject: {}",
+                text
+            );
+        }
+
+        assert!(validate_agent_content(&serde_json::json!({"prompt": "hi"})).is_err());
+    }
+
+    #[test]
+    fn test_restrict_tool_config() {
+        let agent = AgentDefinition {
+            id: Uuid::now_v7(),
+            path: "/agents/reviewer.agent".to_string(),
+            metadata: AgentMetadata {
+                tools: Some(vec!["read".to_string()]),
+                memory_scope: Some(MemoryScope::Global),
+                ..Default::default()
+            },
+            system_prompt: "Review.".to_string(),
+            updated_at: Utc::now(),
+        };
+        let config = agent.restrict_tool_config(ToolConfig {
+            plan_mode: true,
+            ..Default::default()
+        });
+
+        assert!(config.plan_mode);
+        assert!(config.is_tool_allowed("read"));
+        assert!(!config.is_tool_allowed("write"));
+        assert!(config.resolve_memory_scope(Some(MemoryScope::User)).is_err());
+        assert_eq!(config.resolve_memory_scope(None).unwrap(), Some(MemoryScope::Global));
+    }
+}
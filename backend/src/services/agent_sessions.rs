@@ -71,6 +71,48 @@ pub async fn create_session(
     agent_sessions::create_session(conn, new_session).await
 }
 
+/// Claims the lease on a chat for a backend instance, creating its session if needed.
+///
+/// # Returns
+/// The chat's session, reset to idle and owned by `node_id`
+///
+/// # Errors
+/// * `Validation` - If model or mode is invalid
+/// * `Conflict` - If another live instance holds the chat's lease
+#[allow(clippy::too_many_arguments)]
+pub async fn acquire_chat_lease(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    chat_id: Uuid,
+    user_id: Uuid,
+    agent_type: crate::models::agent_session::AgentType,
+    model: String,
+    mode: String,
+    node_id: Uuid,
+) -> Result<AgentSession> {
+    tracing::info!(
+        chat_id = %chat_id,
+        node_id = %node_id,
+        "[AgentSessions] Service: Acquiring chat lease"
+    );
+
+    validate_model_name(&model)?;
+    validate_mode(&mode)?;
+
+    let new_session = NewAgentSession {
+        workspace_id,
+        chat_id,
+        user_id,
+        agent_type,
+        model,
+        mode,
+    };
+
+    agent_sessions::acquire_chat_lease(conn, new_session, node_id)
+        .await?
+        .ok_or_else(|| Error::Conflict(format!("Chat {} is running on another instance", chat_id)))
+}
+
 // ============================================================================
 // SESSION RETRIEVAL
 // ============================================================================
@@ -0,0 +1,557 @@
+//! API keys and service accounts
+//!
+//! API keys let scripts and CI pipelines call the API without going through the
+//! login/refresh cookie flow. Every key:
+//! - acts as exactly one principal (the user who created it, or a service account)
+//! - is limited to one workspace and to a subset of the principal's permissions there
+//! - may expire, records when it was last used, and can be revoked at any time
+//!
+//! Keys look like `bsk_<8 hex chars>_<64 hex chars>`. The `bsk_<8 hex chars>` part is
+//! stored in clear as `key_prefix` so keys can be told apart in listings and logs;
+//! the full key is only ever stored as a SHA-256 hash.
+
+use crate::{DbConn, Result};
+use crate::{
+    error::{Error, ValidationErrors},
+    models::{
+        api_keys::{
+            ApiKey, ApiKeyScope, CreateApiKeyRequest, CreateServiceAccountRequest, CreatedApiKey,
+            NewApiKey, NewServiceAccount, ServiceAccountDetailed, API_KEY_PREFIX,
+        },
+        permissions::{member_permissions, workspace_permissions, PermissionValidator},
+        users::NewUser,
+        workspace_members::NewWorkspaceMember,
+    },
+    queries::{api_keys, roles, service_accounts, users, workspace_members},
+    services::workspace_members::{get_user_workspace_permissions, require_workspace_permission},
+    validation::validate_required_string,
+};
+use axum::http::Method;
+use chrono::Utc;
+use rand::Rng;
+use sqlx::Acquire;
+use uuid::Uuid;
+
+/// Maximum length for API key and service account names
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Domain used for the synthetic email addresses of service account users.
+/// `.invalid` is reserved (RFC 2606), so these addresses can never receive mail.
+const SERVICE_ACCOUNT_EMAIL_DOMAIN: &str = "service-accounts.invalid";
+
+// ============================================================================
+// KEY GENERATION & AUTHENTICATION
+// ============================================================================
+
+/// Returns true if a bearer token looks like an API key rather than a JWT
+pub fn is_api_key(token: &str) -> bool {
+    token.starts_with(API_KEY_PREFIX)
+}
+
+/// Generates a new API key, returning `(full_key, key_prefix)`
+fn generate_api_key() -> (String, String) {
+    let mut prefix_bytes = [0u8; 4];
+    let mut secret_bytes = [0u8; 32];
+    rand::rng().fill(&mut prefix_bytes);
+    rand::rng().fill(&mut secret_bytes);
+
+    let key_prefix = format!("{}{}", API_KEY_PREFIX, hex::encode(prefix_bytes));
+    let key = format!("{}_{}", key_prefix, hex::encode(secret_bytes));
+    (key, key_prefix)
+}
+
+/// Validates the format of an API key presented by a client
+fn validate_api_key_format(key: &str) -> Result<&str> {
+    let key = key.trim();
+    let valid = key
+        .strip_prefix(API_KEY_PREFIX)
+        .and_then(|rest| rest.split_once('_'))
+        .is_some_and(|(prefix, secret)| {
+            prefix.len() == 8
+                && secret.len() == 64
+                && prefix.chars().chain(secret.chars()).all(|c| c.is_ascii_hexdigit())
+        });
+
+    if !valid {
+        return Err(Error::InvalidToken("Invalid API key".to_string()));
+    }
+    Ok(key)
+}
+
+/// Authenticates a raw API key and records its use
+///
+/// # Returns
+/// * `Ok(ApiKey)` - The key is known, not revoked and not expired
+/// * `Err(Error::InvalidToken)` - Otherwise
+pub async fn authenticate_api_key(conn: &mut DbConn, key: &str) -> Result<ApiKey> {
+    let key = validate_api_key_format(key)?;
+
+    let api_key = api_keys::get_api_key_by_hash(conn, &api_keys::hash_api_key(key))
+        .await?
+        .ok_or_else(|| Error::InvalidToken("Invalid API key".to_string()))?;
+
+    if api_key.revoked_at.is_some() {
+        return Err(Error::InvalidToken("API key has been revoked".to_string()));
+    }
+    if !api_key.is_active() {
+        return Err(Error::InvalidToken("API key has expired".to_string()));
+    }
+
+    api_keys::touch_api_key_last_used(conn, api_key.id).await?;
+
+    Ok(api_key)
+}
+
+/// Determines which permission an API key needs for a workspace request
+///
+/// `route` is the request's matched route, such as `/api/v1/workspaces/{id}/members/{user_id}`.
+/// Deleting the workspace, changing its settings and managing its members need the
+/// same permission the services check for those actions. Other routes only need
+/// `workspace:read` for safe methods and `workspace:write` otherwise. The tool and MCP
+/// endpoints are always a POST, so their base requirement is `workspace:read` and the
+/// handlers check `workspace:write` for mutating tools.
+pub fn required_permission_for_request(method: &Method, route: &str) -> &'static str {
+    // The part of the route after the workspace, e.g. `/members/{user_id}`
+    let route = route
+        .split_once("{id}")
+        .map_or(route, |(_, rest)| rest)
+        .trim_end_matches('/');
+    let safe = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+
+    match route {
+        "" if *method == Method::DELETE => workspace_permissions::DELETE,
+        "" if !safe => workspace_permissions::MANAGE_SETTINGS,
+        "/members" if safe => member_permissions::VIEW_MEMBERS,
+        "/members" => member_permissions::ADD_MEMBERS,
+        "/members/{user_id}" if *method == Method::DELETE => member_permissions::REMOVE_MEMBERS,
+        "/members/{user_id}" => member_permissions::UPDATE_ROLES,
+        "/members/{user_id}/two-factor" => workspace_permissions::MANAGE_MEMBERS,
+        "/two-factor-policy" | "/ai-fallback-models" => workspace_permissions::MANAGE_SETTINGS,
+        "/tool-policy" if !safe => workspace_permissions::MANAGE_SETTINGS,
+        _ if route.starts_with("/mcp-servers") => workspace_permissions::MANAGE_SETTINGS,
+        _ if route.starts_with("/service-accounts") || route.starts_with("/sso-domains") => {
+            workspace_permissions::MANAGE_MEMBERS
+        }
+        "/tools" | "/mcp" if *method == Method::POST => workspace_permissions::READ,
+        _ if safe => workspace_permissions::READ,
+        _ => workspace_permissions::WRITE,
+    }
+}
+
+/// Checks that an API key may perform an action in a workspace
+///
+/// The key must belong to the workspace, must have been granted the permission, and
+/// the principal must still hold it through its current role (roles can be downgraded
+/// after the key was created).
+pub async fn authorize_api_key(
+    conn: &mut DbConn,
+    scope: &ApiKeyScope,
+    workspace_id: Uuid,
+    user_id: Uuid,
+    permission: &str,
+) -> Result<()> {
+    if scope.workspace_id != workspace_id {
+        return Err(Error::Forbidden(
+            "API key is not valid for this workspace".to_string(),
+        ));
+    }
+
+    if !scope.has_permission(permission) {
+        return Err(Error::Forbidden(format!(
+            "API key lacks required permission: {}",
+            permission
+        )));
+    }
+
+    require_workspace_permission(conn, workspace_id, user_id, permission).await
+}
+
+// ============================================================================
+// PERSONAL API KEYS
+// ============================================================================
+
+/// Validates a key or service account name
+fn validate_name(name: &str, field: &str) -> Result<String> {
+    let name = validate_required_string(name, field)?;
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(Error::Validation(ValidationErrors::Single {
+            field: field.to_string(),
+            message: format!("{} must be at most {} characters", field, MAX_NAME_LENGTH),
+        }));
+    }
+    Ok(name)
+}
+
+/// Validates that the requested permissions are a non-empty subset of what the
+/// principal currently holds in the workspace. Returns the de-duplicated list.
+async fn validate_permission_subset(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    principal_id: Uuid,
+    requested: &[String],
+) -> Result<Vec<String>> {
+    if requested.is_empty() {
+        return Err(Error::Validation(ValidationErrors::Single {
+            field: "permissions".to_string(),
+            message: "At least one permission is required".to_string(),
+        }));
+    }
+
+    let granted = get_user_workspace_permissions(conn, workspace_id, principal_id).await?;
+
+    let mut permissions: Vec<String> = Vec::with_capacity(requested.len());
+    for permission in requested {
+        let permission = permission.trim();
+        if !PermissionValidator::is_valid_permission(permission) {
+            return Err(Error::Validation(ValidationErrors::Single {
+                field: "permissions".to_string(),
+                message: format!("Invalid permission: {}", permission),
+            }));
+        }
+        if !granted.iter().any(|p| p == permission) {
+            return Err(Error::Forbidden(format!(
+                "Cannot grant permission not held in this workspace: {}",
+                permission
+            )));
+        }
+        if !permissions.iter().any(|p| p == permission) {
+            permissions.push(permission.to_string());
+        }
+    }
+
+    Ok(permissions)
+}
+
+/// Creates a key acting as `principal_id` in a workspace
+async fn issue_api_key(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    principal_id: Uuid,
+    created_by: Uuid,
+    request: CreateApiKeyRequest,
+) -> Result<CreatedApiKey> {
+    let name = validate_name(&request.name, "name")?;
+
+    if let Some(expires_at) = request.expires_at
+        && expires_at <= Utc::now()
+    {
+        return Err(Error::Validation(ValidationErrors::Single {
+            field: "expires_at".to_string(),
+            message: "Expiry must be in the future".to_string(),
+        }));
+    }
+
+    let permissions =
+        validate_permission_subset(conn, workspace_id, principal_id, &request.permissions).await?;
+
+    let (key, key_prefix) = generate_api_key();
+    let api_key = api_keys::create_api_key(conn, NewApiKey {
+        user_id: principal_id,
+        workspace_id,
+        created_by: Some(created_by),
+        name,
+        key_prefix,
+        key_hash: api_keys::hash_api_key(&key),
+        permissions,
+        expires_at: request.expires_at,
+    }).await?;
+
+    tracing::info!(
+        api_key_id = %api_key.id,
+        key_prefix = %api_key.key_prefix,
+        workspace_id = %workspace_id,
+        principal_id = %principal_id,
+        created_by = %created_by,
+        security_event = "api_key_created",
+        "API key created"
+    );
+
+    Ok(CreatedApiKey { api_key, key })
+}
+
+/// Creates a personal API key for the requester in a workspace
+///
+/// The secret is returned once in `CreatedApiKey::key` and cannot be recovered later.
+pub async fn create_api_key(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    requester_id: Uuid,
+    request: CreateApiKeyRequest,
+) -> Result<CreatedApiKey> {
+    issue_api_key(conn, workspace_id, requester_id, requester_id, request).await
+}
+
+/// Lists the requester's personal API keys in a workspace (including revoked ones)
+pub async fn list_api_keys(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    requester_id: Uuid,
+) -> Result<Vec<ApiKey>> {
+    api_keys::list_api_keys_for_user(conn, workspace_id, requester_id).await
+}
+
+/// Revokes an API key
+///
+/// Users can revoke their own keys; members with `workspace:manage_members` can revoke
+/// any key in the workspace, including service account keys.
+pub async fn revoke_api_key(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    key_id: Uuid,
+    requester_id: Uuid,
+) -> Result<ApiKey> {
+    let api_key = api_keys::get_api_key_in_workspace(conn, workspace_id, key_id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("API key {} not found", key_id)))?;
+
+    if api_key.user_id != requester_id {
+        require_workspace_permission(
+            conn,
+            workspace_id,
+            requester_id,
+            workspace_permissions::MANAGE_MEMBERS,
+        )
+        .await?;
+    }
+
+    let revoked = api_keys::revoke_api_key(conn, api_key.id)
+        .await?
+        .ok_or_else(|| Error::Conflict("API key is already revoked".to_string()))?;
+
+    tracing::info!(
+        api_key_id = %revoked.id,
+        key_prefix = %revoked.key_prefix,
+        workspace_id = %workspace_id,
+        revoked_by = %requester_id,
+        security_event = "api_key_revoked",
+        "API key revoked"
+    );
+
+    Ok(revoked)
+}
+
+// ============================================================================
+// SERVICE ACCOUNTS
+// ============================================================================
+
+/// Creates a service account in a workspace
+///
+/// A password-less user is created to back the account and is added to the workspace
+/// with the requested role. Requires `workspace:manage_members`.
+pub async fn create_service_account(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    requester_id: Uuid,
+    request: CreateServiceAccountRequest,
+) -> Result<ServiceAccountDetailed> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_MEMBERS).await?;
+
+    let name = validate_name(&request.name, "name")?;
+    let description = request
+        .description
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty());
+
+    let role = roles::get_role_by_workspace_and_name(conn, workspace_id, &request.role_name.to_lowercase())
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Role '{}' not found in workspace", request.role_name)))?;
+
+    if service_accounts::get_service_account_by_name(conn, workspace_id, &name).await?.is_some() {
+        return Err(Error::Conflict(format!(
+            "Service account '{}' already exists in this workspace",
+            name
+        )));
+    }
+
+    let mut tx = conn.begin().await.map_err(|e| {
+        Error::Internal(format!("Failed to begin transaction: {}", e))
+    })?;
+
+    let user = users::create_user(&mut tx, NewUser {
+        email: format!("sa-{}@{}", Uuid::now_v7().simple(), SERVICE_ACCOUNT_EMAIL_DOMAIN),
+        password_hash: None,
+        full_name: Some(name.clone()),
+    }).await?;
+
+    workspace_members::create_workspace_member(&mut tx, NewWorkspaceMember {
+        workspace_id,
+        user_id: user.id,
+        role_id: role.id,
+    }).await?;
+
+    let account = service_accounts::create_service_account(&mut tx, NewServiceAccount {
+        workspace_id,
+        user_id: user.id,
+        name,
+        description,
+        created_by: Some(requester_id),
+    }).await?;
+
+    tx.commit().await.map_err(|e| {
+        Error::Internal(format!("Failed to commit transaction: {}", e))
+    })?;
+
+    Ok(ServiceAccountDetailed {
+        id: account.id,
+        workspace_id: account.workspace_id,
+        user_id: account.user_id,
+        name: account.name,
+        description: account.description,
+        role_name: role.name,
+        created_by: account.created_by,
+        created_at: account.created_at,
+    })
+}
+
+/// Lists the service accounts of a workspace. Requires `workspace:manage_members`.
+pub async fn list_service_accounts(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    requester_id: Uuid,
+) -> Result<Vec<ServiceAccountDetailed>> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_MEMBERS).await?;
+    service_accounts::list_service_accounts(conn, workspace_id).await
+}
+
+/// Deletes a service account together with its backing user, membership and keys.
+/// Requires `workspace:manage_members`.
+pub async fn delete_service_account(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    service_account_id: Uuid,
+    requester_id: Uuid,
+) -> Result<()> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_MEMBERS).await?;
+
+    let account = service_accounts::get_service_account(conn, workspace_id, service_account_id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Service account {} not found", service_account_id)))?;
+
+    // Cascades to the membership, the service account row and every key it owns
+    users::delete_user(conn, account.user_id).await?;
+
+    tracing::info!(
+        service_account_id = %account.id,
+        workspace_id = %workspace_id,
+        deleted_by = %requester_id,
+        security_event = "service_account_deleted",
+        "Service account deleted"
+    );
+
+    Ok(())
+}
+
+/// Creates an API key acting as a service account. Requires `workspace:manage_members`.
+pub async fn create_service_account_api_key(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    service_account_id: Uuid,
+    requester_id: Uuid,
+    request: CreateApiKeyRequest,
+) -> Result<CreatedApiKey> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_MEMBERS).await?;
+
+    let account = service_accounts::get_service_account(conn, workspace_id, service_account_id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Service account {} not found", service_account_id)))?;
+
+    issue_api_key(conn, workspace_id, account.user_id, requester_id, request).await
+}
+
+/// Lists the API keys of a service account. Requires `workspace:manage_members`.
+pub async fn list_service_account_api_keys(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    service_account_id: Uuid,
+    requester_id: Uuid,
+) -> Result<Vec<ApiKey>> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_MEMBERS).await?;
+
+    let account = service_accounts::get_service_account(conn, workspace_id, service_account_id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Service account {} not found", service_account_id)))?;
+
+    api_keys::list_api_keys_for_user(conn, workspace_id, account.user_id).await
+}
+
+/// Returns true if the user backs a service account
+pub async fn is_service_account_user(conn: &mut DbConn, user_id: Uuid) -> Result<bool> {
+    Ok(service_accounts::get_service_account_by_user_id(conn, user_id).await?.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_api_key_format() {
+        let (key, key_prefix) = generate_api_key();
+        assert!(is_api_key(&key));
+        assert!(key.starts_with(&key_prefix));
+        assert_eq!(key_prefix.len(), API_KEY_PREFIX.len() + 8);
+        assert!(validate_api_key_format(&key).is_ok());
+    }
+
+    #[test]
+    fn test_validate_api_key_format_rejects_malformed_keys() {
+        assert!(validate_api_key_format("bsk_short").is_err());
+        assert!(validate_api_key_format("eyJhbGciOiJIUzI1NiJ9.payload.signature").is_err());
+        assert!(validate_api_key_format(&format!("bsk_zzzzzzzz_{}", "a".repeat(64))).is_err());
+    }
+
+    #[test]
+    fn test_required_permission_for_request() {
+        let route = "/api/v1/workspaces/{id}/files/{file_id}";
+        assert_eq!(required_permission_for_request(&Method::GET, route), workspace_permissions::READ);
+        assert_eq!(required_permission_for_request(&Method::PATCH, route), workspace_permissions::WRITE);
+        assert_eq!(required_permission_for_request(&Method::DELETE, route), workspace_permissions::WRITE);
+        assert_eq!(
+            required_permission_for_request(&Method::POST, "/api/v1/workspaces/{id}/tools"),
+            workspace_permissions::READ
+        );
+        assert_eq!(
+            required_permission_for_request(&Method::POST, "/api/v1/workspaces/{id}/mcp"),
+            workspace_permissions::READ
+        );
+    }
+
+    #[test]
+    fn test_required_permission_for_administrative_routes() {
+        let workspace = "/api/v1/workspaces/{id}";
+        assert_eq!(required_permission_for_request(&Method::GET, workspace), workspace_permissions::READ);
+        assert_eq!(required_permission_for_request(&Method::PATCH, workspace), workspace_permissions::MANAGE_SETTINGS);
+        assert_eq!(required_permission_for_request(&Method::DELETE, workspace), workspace_permissions::DELETE);
+
+        let members = "/api/v1/workspaces/{id}/members";
+        assert_eq!(required_permission_for_request(&Method::GET, members), member_permissions::VIEW_MEMBERS);
+        assert_eq!(required_permission_for_request(&Method::POST, members), member_permissions::ADD_MEMBERS);
+        assert_eq!(
+            required_permission_for_request(&Method::GET, "/api/v1/workspaces/{id}/members/me"),
+            workspace_permissions::READ
+        );
+
+        let member = "/api/v1/workspaces/{id}/members/{user_id}";
+        assert_eq!(required_permission_for_request(&Method::PATCH, member), member_permissions::UPDATE_ROLES);
+        assert_eq!(required_permission_for_request(&Method::DELETE, member), member_permissions::REMOVE_MEMBERS);
+        assert_eq!(
+            required_permission_for_request(&Method::DELETE, "/api/v1/workspaces/{id}/members/{user_id}/two-factor"),
+            workspace_permissions::MANAGE_MEMBERS
+        );
+
+        let tool_policy = "/api/v1/workspaces/{id}/tool-policy";
+        assert_eq!(required_permission_for_request(&Method::GET, tool_policy), workspace_permissions::READ);
+        assert_eq!(required_permission_for_request(&Method::PUT, tool_policy), workspace_permissions::MANAGE_SETTINGS);
+        assert_eq!(
+            required_permission_for_request(&Method::PATCH, "/api/v1/workspaces/{id}/two-factor-policy"),
+            workspace_permissions::MANAGE_SETTINGS
+        );
+        assert_eq!(
+            required_permission_for_request(&Method::GET, "/api/v1/workspaces/{id}/mcp-servers"),
+            workspace_permissions::MANAGE_SETTINGS
+        );
+        assert_eq!(
+            required_permission_for_request(&Method::DELETE, "/api/v1/workspaces/{id}/sso-domains/{domain_id}"),
+            workspace_permissions::MANAGE_MEMBERS
+        );
+    }
+}
@@ -3,24 +3,24 @@ use crate::models::chat::{ChatMessageMetadata, ChatMessageRole, NewChatMessage,
 use crate::models::sse::SseEvent;
 use crate::queries;
 use crate::services::agent_sessions;
+use crate::services::chat::compaction;
+use crate::services::chat::delegation::DelegationContext;
 use crate::services::chat::registry::{AgentCommand, AgentHandle, AgentRegistry};
-use crate::services::chat::rig_engine::RigService;
-use crate::services::chat::ChatService;
+use crate::services::chat::rig_engine::{self, RigService};
+use crate::services::chat::{AttachedImage, ChatService};
 use crate::services::storage::FileStorageService;
-use crate::providers::Agent;
+use crate::providers::retry::{self, ProviderFailure};
+use crate::providers::{Agent, ModelIdentifier};
+use crate::services::chat::rig_engine::ChatAgent;
+use crate::services::chat::tool_runner;
 use crate::DbPool;
 use futures::StreamExt;
-use rig::streaming::StreamingChat;
 use std::sync::Arc;
 use tokio::sync::{broadcast, mpsc, Mutex};
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
-/// Maximum number of retries for transient AI engine errors
-const MAX_AI_RETRIES: u32 = 3;
-/// Initial backoff duration in milliseconds for retries
-const RETRY_BACKOFF_MS: u64 = 1000;
 /// Stream read timeout in seconds - if no data received from API within this time, consider it stalled
 const STREAM_READ_TIMEOUT_SECS: u64 = 120;
 
@@ -45,23 +45,27 @@ struct AgentState {
     /// Cached agent with preserved chat_history
     /// Contains reasoning items for GPT-5 multi-turn conversations (for OpenAI)
     /// Wraps both OpenAI and OpenRouter agents in our unified Agent enum
-    cached_agent: Option<Agent>,
+    cached_agent: Option<ChatAgent>,
     /// Track model name to detect when to recreate agent
     current_model_name: Option<String>,
     /// Track user_id to detect when to recreate agent
     current_user_id: Option<Uuid>,
     /// Track mode to detect when to recreate agent (mode changes require new ToolConfig)
     current_mode: Option<String>,
+    /// Track MCP server generation to detect added, changed or removed servers
+    mcp_generation: Option<u64>,
 }
 
 impl AgentState {
     /// Check if the cached agent can be reused for the given session and user.
-    /// Agent can be reused only if all criteria match: model, user_id, and mode.
-    fn can_reuse(&self, session: &crate::models::chat::ChatSession, user_id: Uuid) -> bool {
+    /// Agent can be reused only if all criteria match: model, user_id, mode,
+    /// and the workspace's MCP server configuration.
+    fn can_reuse(&self, session: &crate::models::chat::ChatSession, user_id: Uuid, mcp_generation: u64) -> bool {
         self.cached_agent.is_some()
             && self.current_model_name.as_ref() == Some(&session.agent_config.model)
             && self.current_user_id.as_ref() == Some(&user_id)
             && self.current_mode.as_ref() == Some(&session.agent_config.mode)
+            && self.mcp_generation == Some(mcp_generation)
     }
 }
 
@@ -104,6 +108,7 @@ impl Default for ChatActorState {
                 current_model_name: None,
                 current_user_id: None,
                 current_mode: None,
+                mcp_generation: None,
             },
             tool_tracking: ToolTracking {
                 current_tool_name: None,
@@ -138,6 +143,10 @@ pub struct ChatActor {
     session_id: Option<Uuid>,
     /// Handle for the heartbeat task
     heartbeat_handle: Option<JoinHandle<()>>,
+    /// Cancelled when another instance takes the chat's lease over
+    lease_lost: CancellationToken,
+    /// Stops relaying events to the other instances of a cluster
+    event_relay: Option<CancellationToken>,
     /// Consolidated state - single lock for all actor state
     /// Reduces lock contention and eliminates deadlock risk
     state: Arc<Mutex<ChatActorState>>,
@@ -183,6 +192,8 @@ impl ChatActor {
             inactivity_timeout: args.inactivity_timeout,
             session_id: None,
             heartbeat_handle: None,
+            lease_lost: CancellationToken::new(),
+            event_relay: None,
             state: Arc::new(Mutex::new(ChatActorState::default())),
         };
 
@@ -236,6 +247,8 @@ impl ChatActor {
 
         self.session_id = Some(session_id);
         self.heartbeat_handle = Some(self.start_heartbeat_task(session_id));
+        self.event_relay = self.registry.relay_events(self.chat_id).await;
+        let lease_lost = self.lease_lost.clone();
 
         // Periodic heartbeat ping (every 10 seconds)
         let mut heartbeat_interval = tokio::time::interval(std::time::Duration::from_secs(10));
@@ -250,6 +263,14 @@ impl ChatActor {
                 _ = heartbeat_interval.tick() => {
                     let _ = self.event_tx.send(SseEvent::Ping);
                 }
+                _ = lease_lost.cancelled() => {
+                    tracing::warn!(
+                        chat_id = %self.chat_id,
+                        reason = "lease_lost",
+                        "[ChatActor] SHUTTING DOWN - Chat taken over by another instance"
+                    );
+                    break;
+                }
                 _ = &mut inactivity_timeout => {
                     // Only timeout if NOT actively processing
                     let is_actively_processing = self.state.lock().await.interaction.is_actively_processing;
@@ -482,6 +503,22 @@ impl ChatActor {
         if let Some(handle) = heartbeat_handle {
             handle.abort();
         }
+        if let Some(relay) = self.event_relay.take() {
+            relay.cancel();
+        }
+
+        // Let any instance pick the chat up right away
+        if let Some(session_id) = session_id
+            && let Ok(mut conn) = self.pool.acquire().await
+            && let Err(e) = queries::agent_sessions::release_chat_lease(&mut conn, session_id, self.registry.node_id).await
+        {
+            tracing::warn!(
+                chat_id = %self.chat_id,
+                session_id = %session_id,
+                error = %e,
+                "[ChatActor] Failed to release chat lease"
+            );
+        }
 
         // Note: We intentionally do NOT mark the session as completed here.
         // The session record should persist in its last known state (e.g., idle, running).
@@ -516,6 +553,40 @@ impl ChatActor {
 
         let mut conn = self.pool.acquire().await.map_err(crate::error::Error::Sqlx)?;
 
+        // Load AI config for compaction and reasoning settings
+        let ai_config = crate::config::Config::load()?.ai;
+
+        // 0. Summarize older history once it nears the context window. A failed
+        // summary only costs context space, so the response goes ahead regardless.
+        match compaction::compact_chat(
+            &mut conn,
+            &self.storage,
+            &self.rig_service,
+            &ai_config.compaction,
+            self.workspace_id,
+            self.chat_id,
+            self.default_context_token_limit,
+            false,
+        ).await {
+            Ok(result) if result.compacted => {
+                tracing::info!(
+                    chat_id = %self.chat_id,
+                    compacted_messages = result.compacted_message_count,
+                    tokens_before = result.tokens_before,
+                    tokens_after = result.tokens_after,
+                    "[ChatActor] Compacted conversation history"
+                );
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!(
+                    chat_id = %self.chat_id,
+                    error = %e,
+                    "[ChatActor] Failed to compact conversation history"
+                );
+            }
+        }
+
         // 1. Build structured context with persona, history, and attachments
         // Exclude last message (user's prompt) from history since we're responding to it
         let context = ChatService::build_context(
@@ -595,10 +666,13 @@ impl ChatActor {
                 agent_id: None,
                 model: DEFAULT_CHAT_MODEL.to_string(),
                 temperature: 0.7,
-                persona_override: Some(context.persona),
+                // The agent appends the skill catalog itself
+                persona_override: Some(context.base_persona),
                 previous_response_id: None,
                 mode: "plan".to_string(),
                 plan_file: None,
+                parent_chat_id: None,
+                allowed_tools: None,
             }
         };
 
@@ -611,9 +685,6 @@ impl ChatActor {
         // Store current model for potential cancellation
         self.state.lock().await.interaction.current_model = Some(session.agent_config.model.clone());
 
-        // 7. Load AI config for reasoning settings
-        let ai_config = crate::config::Config::load()?.ai;
-
         // 8. Get or create cached Rig Agent
         tracing::info!(
             chat_id = %self.chat_id,
@@ -641,7 +712,14 @@ impl ChatActor {
 
         let mut item_count = 0usize;
 
-        // Process stream with retry logic for transient errors
+        // Stream the response. Failed provider calls are retried as the retry policy
+        // allows, then the turn moves on to the workspace's fallback models in order.
+        // Only calls that failed before anything was streamed are: once text went out
+        // or a tool ran, starting over would send it again and rerun the tools.
+        let mut session = session;
+        let mut agent = agent;
+        let mut fallback_models: Option<std::vec::IntoIter<String>> = None;
+        let mut fallback_from: Option<String> = None;
         let mut retry_count = 0u32;
         let full_response = loop {
             // Check for cancellation before attempting
@@ -650,76 +728,91 @@ impl ChatActor {
                 return Err(crate::error::Error::Internal("Chat cancelled by user".to_string()));
             }
 
-            let result = match &agent {
-                Agent::OpenAI(openai_agent) => {
-                    tracing::info!(
-                        chat_id = %self.chat_id,
-                        retry = retry_count,
-                        "Calling OpenAI agent.stream_chat"
-                    );
-                    let stream = openai_agent.stream_chat(&prompt, history.clone()).await;
-                    tracing::info!(
-                        chat_id = %self.chat_id,
-                        "Stream created (OpenAI), entering response loop"
-                    );
-                    self.process_agent_stream(stream, &cancellation_token, &mut conn, &session, &mut item_count).await
-                }
-                Agent::OpenRouter(openrouter_agent) => {
-                    tracing::info!(
-                        chat_id = %self.chat_id,
-                        retry = retry_count,
-                        "Calling OpenRouter agent.stream_chat"
-                    );
-                    let stream = openrouter_agent.stream_chat(&prompt, history.clone()).await;
-                    tracing::info!(
-                        chat_id = %self.chat_id,
-                        "Stream created (OpenRouter), entering response loop"
-                    );
-                    self.process_agent_stream(stream, &cancellation_token, &mut conn, &session, &mut item_count).await
-                }
-            };
+            let result = self
+                .stream_agent(&agent, &prompt, &context.images, &history, &cancellation_token, &mut conn, &session, &mut item_count, retry_count)
+                .await;
 
-            match result {
+            let error = match result {
                 Ok(response) => break response,
-                Err(e) => {
-                    // Check if error is retryable (transient errors)
-                    let error_str = format!("{:?}", e);
-                    let is_retryable = error_str.contains("Failed to get tool definitions")
-                        || error_str.contains("RequestError")
-                        || error_str.contains("rate limit")
-                        || error_str.contains("timeout")
-                        || error_str.contains("connection")
-                        || error_str.contains("5")
-                        || error_str.contains("overloaded");
-
-                    if is_retryable && retry_count < MAX_AI_RETRIES {
-                        retry_count += 1;
-                        let backoff_ms = RETRY_BACKOFF_MS * (1 << (retry_count - 1)); // Exponential backoff
-                        tracing::warn!(
-                            chat_id = %self.chat_id,
-                            retry = retry_count,
-                            max_retries = MAX_AI_RETRIES,
-                            backoff_ms = backoff_ms,
-                            error = ?e,
-                            "[ChatActor] Transient AI error, retrying with backoff"
-                        );
+                Err(e) => e,
+            };
+            // Only failures of the provider itself are retried, not cancellations or tool errors
+            let crate::error::Error::AiProvider(message) = &error else {
+                return Err(error);
+            };
+            if item_count > 0 {
+                tracing::error!(
+                    chat_id = %self.chat_id,
+                    model = %session.agent_config.model,
+                    items = item_count,
+                    error = %message,
+                    "[ChatActor] AI provider error after the turn started streaming, not retrying"
+                );
+                return Err(error);
+            }
+            let failure = ProviderFailure::classify(message);
 
-                        // Clear agent cache to force fresh agent creation on retry
-                        self.state.lock().await.agent_state.cached_agent = None;
+            retry_count += 1;
+            if let Some(delay) = retry::retry_delay(&ai_config.retry, retry_count, &failure) {
+                tracing::warn!(
+                    chat_id = %self.chat_id,
+                    model = %session.agent_config.model,
+                    retry = retry_count,
+                    max_retries = ai_config.retry.max_retries,
+                    delay_ms = delay.as_millis() as u64,
+                    status = ?failure.status,
+                    error = %message,
+                    "[ChatActor] Transient AI provider error, retrying after delay"
+                );
 
-                        tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
-                    } else {
-                        // Non-retryable error or max retries exceeded
-                        tracing::error!(
-                            chat_id = %self.chat_id,
-                            retry = retry_count,
-                            error = ?e,
-                            "[ChatActor] AI error (not retrying)"
-                        );
-                        return Err(e);
-                    }
+                // Clear agent cache to force fresh agent creation on retry
+                self.state.lock().await.agent_state.cached_agent = None;
+
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => {}
+                    _ = tokio::time::sleep(delay) => {}
                 }
+                agent = self.get_or_create_agent(user_id, &session, &ai_config).await?;
+                continue;
             }
+
+            if fallback_models.is_none() {
+                fallback_models = Some(self.ai_fallback_models(&mut conn).await.into_iter());
+            }
+            let Some((fallback_session, fallback_agent)) = self
+                .next_fallback_agent(user_id, &session, fallback_from.as_deref(), fallback_models.as_mut().unwrap(), &ai_config)
+                .await
+            else {
+                tracing::error!(
+                    chat_id = %self.chat_id,
+                    model = %session.agent_config.model,
+                    retry = retry_count,
+                    error = %message,
+                    "[ChatActor] AI provider error (no retry or fallback left)"
+                );
+                return Err(error);
+            };
+
+            let reason = failure.reason();
+            tracing::warn!(
+                chat_id = %self.chat_id,
+                from_model = %session.agent_config.model,
+                to_model = %fallback_session.agent_config.model,
+                reason = %reason,
+                error = %message,
+                "[ChatActor] Falling back to the next model"
+            );
+            let _ = self.event_tx.send(SseEvent::ModelFallback {
+                from_model: session.agent_config.model.clone(),
+                to_model: fallback_session.agent_config.model.clone(),
+                reason,
+            });
+
+            fallback_from.get_or_insert_with(|| session.agent_config.model.clone());
+            self.state.lock().await.interaction.current_model = Some(fallback_session.agent_config.model.clone());
+            session = fallback_session;
+            agent = fallback_agent;
+            retry_count = 0;
         };
 
         // Check if stream completed without any items (possible API access issue)
@@ -768,6 +861,7 @@ impl ChatActor {
                     content: full_response,
                     metadata: sqlx::types::Json(crate::models::chat::ChatMessageMetadata {
                         model: Some(session.agent_config.model.clone()),
+                        fallback_from,
                         reasoning_id,
                         ..Default::default()
                     }),
@@ -1299,6 +1393,25 @@ impl ChatActor {
                                      }
                                  }
 
+                                 // Handle plan step transition (plan_update_step tool)
+                                 if result_json.get("previous_status").is_some()
+                                     && let Ok(update) = serde_json::from_value::<crate::models::requests::PlanUpdateStepResult>(result_json.clone())
+                                 {
+                                     tracing::info!(
+                                         "[ChatActor] Plan step {} of {} is now {} for chat {}",
+                                         update.step.index,
+                                         update.path,
+                                         update.step.status,
+                                         self.chat_id
+                                     );
+                                     let _ = self.event_tx.send(SseEvent::PlanStepUpdated {
+                                         path: update.path,
+                                         step: update.step,
+                                         previous_status: update.previous_status,
+                                         progress: update.progress,
+                                     });
+                                 }
+
                                  // Handle mode transition (exit_plan_mode tool)
                                  // Check if result has mode field = "build"
                                  if let Some(mode) = result_json.get("mode").and_then(|m| m.as_str()) {
@@ -1491,6 +1604,16 @@ impl ChatActor {
                 // FinalResponse is only used here for logging and usage statistics.
                 let response_text = final_response.response();
 
+                // Not every provider reports usage when streaming
+                let usage = final_response.usage();
+                if usage.total_tokens > 0 || usage.input_tokens > 0 || usage.output_tokens > 0 {
+                    let _ = self.event_tx.send(SseEvent::Usage {
+                        input_tokens: usage.input_tokens,
+                        output_tokens: usage.output_tokens,
+                        total_tokens: usage.total_tokens,
+                    });
+                }
+
                 // Debug logging to diagnose duplication issues
                 tracing::debug!(
                     chat_id = %self.chat_id,
@@ -1570,7 +1693,7 @@ impl ChatActor {
                                 timeout_secs = STREAM_READ_TIMEOUT_SECS,
                                 "[ChatActor] Stream read timeout - API stalled"
                             );
-                            return Err(crate::error::Error::Internal(
+                            return Err(crate::error::Error::AiProvider(
                                 format!("Stream read timeout after {} seconds - API stalled", STREAM_READ_TIMEOUT_SECS)
                             ));
                         }
@@ -1586,8 +1709,6 @@ impl ChatActor {
                 }
             };
 
-            *item_count += 1;
-
             match item {
                 Err(e) => {
                     let error_str = e.to_string();
@@ -1612,9 +1733,10 @@ impl ChatActor {
                         item_num = *item_count,
                         "Stream item error"
                     );
-                    return Err(crate::error::Error::Internal(format!("Streaming error: {}", e)));
+                    return Err(crate::error::Error::AiProvider(e.to_string()));
                 }
                 Ok(stream_item) => {
+                    *item_count += 1;
                     if let Err(e) = self.process_stream_item(
                         stream_item,
                         &mut full_response,
@@ -1633,6 +1755,120 @@ impl ChatActor {
         Ok(full_response)
     }
 
+    /// Streams one response of the agent, whichever provider it belongs to
+    #[allow(clippy::too_many_arguments)]
+    async fn stream_agent(
+        &self,
+        agent: &ChatAgent,
+        prompt: &str,
+        images: &[AttachedImage],
+        history: &[rig::message::Message],
+        cancellation_token: &CancellationToken,
+        conn: &mut sqlx::PgConnection,
+        session: &crate::models::chat::ChatSession,
+        item_count: &mut usize,
+        retry_count: u32,
+    ) -> crate::error::Result<String> {
+        tracing::info!(
+            chat_id = %self.chat_id,
+            agent = ?agent.agent,
+            model = %session.agent_config.model,
+            retry = retry_count,
+            "Calling agent.stream_chat"
+        );
+        let tools = agent.tools.clone();
+        // Read on every turn, so policy changes apply to running chats
+        let policy = queries::tool_policies::get_tool_policy(conn, self.workspace_id).await?;
+        // Checked per attempt, a fallback model may not accept images
+        let supports_vision = match ModelIdentifier::parse(&session.agent_config.model, self.rig_service.default_provider()) {
+            Ok(model) => ChatService::model_supports_vision(conn, &model).await,
+            Err(_) => false,
+        };
+        let prompt = rig_engine::prompt_message(prompt, images, supports_vision);
+        let turn = tool_runner::TurnContext {
+            chat_id: self.chat_id,
+            cancellation: cancellation_token.clone(),
+            events: self.event_tx.clone(),
+            policy: Arc::new(policy),
+            approvals: self.registry.approvals.clone(),
+            supports_vision,
+        };
+        match &agent.agent {
+            Agent::OpenAI(agent) => {
+                let stream = tool_runner::stream_multi_turn(agent, tools, turn, prompt, history.to_vec());
+                self.process_agent_stream(stream, cancellation_token, conn, session, item_count).await
+            }
+            Agent::OpenRouter(agent) => {
+                let stream = tool_runner::stream_multi_turn(agent, tools, turn, prompt, history.to_vec());
+                self.process_agent_stream(stream, cancellation_token, conn, session, item_count).await
+            }
+            Agent::Mock(agent) => {
+                let stream = tool_runner::stream_multi_turn(agent, tools, turn, prompt, history.to_vec());
+                self.process_agent_stream(stream, cancellation_token, conn, session, item_count).await
+            }
+            Agent::RecordingOpenAI(agent) => {
+                let stream = tool_runner::stream_multi_turn(agent, tools, turn, prompt, history.to_vec());
+                self.process_agent_stream(stream, cancellation_token, conn, session, item_count).await
+            }
+            Agent::RecordingOpenRouter(agent) => {
+                let stream = tool_runner::stream_multi_turn(agent, tools, turn, prompt, history.to_vec());
+                self.process_agent_stream(stream, cancellation_token, conn, session, item_count).await
+            }
+        }
+    }
+
+    /// Fallback chain of the chat's workspace, empty when it cannot be read
+    async fn ai_fallback_models(&self, conn: &mut sqlx::PgConnection) -> Vec<String> {
+        match queries::workspaces::get_workspace_by_id(conn, self.workspace_id).await {
+            Ok(workspace) => workspace.ai_fallback_models,
+            Err(e) => {
+                tracing::warn!(
+                    chat_id = %self.chat_id,
+                    error = %e,
+                    "[ChatActor] Failed to read the workspace's fallback models"
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    /// Session and agent of the next usable fallback model
+    ///
+    /// Models that already failed this turn are skipped, as are models whose agent
+    /// cannot be created, e.g. because their provider is not configured. The
+    /// fallback session starts a new response chain, since a response id of one
+    /// provider means nothing to another.
+    async fn next_fallback_agent(
+        &self,
+        user_id: Uuid,
+        session: &crate::models::chat::ChatSession,
+        fallback_from: Option<&str>,
+        fallback_models: &mut std::vec::IntoIter<String>,
+        ai_config: &crate::config::AiConfig,
+    ) -> Option<(crate::models::chat::ChatSession, ChatAgent)> {
+        for model in fallback_models.by_ref() {
+            if model == session.agent_config.model || Some(model.as_str()) == fallback_from {
+                continue;
+            }
+
+            let mut fallback_session = session.clone();
+            fallback_session.agent_config.model = model;
+            fallback_session.agent_config.previous_response_id = None;
+            match self.get_or_create_agent(user_id, &fallback_session, ai_config).await {
+                Ok(agent) => return Some((fallback_session, agent)),
+                Err(e) => {
+                    tracing::warn!(
+                        chat_id = %self.chat_id,
+                        model = %fallback_session.agent_config.model,
+                        error = %e,
+                        "[ChatActor] Skipping fallback model that cannot be used"
+                    );
+                }
+            }
+        }
+        None
+    }
+
     async fn flush_reasoning_buffer(
         &self,
         conn: &mut sqlx::PgConnection,
@@ -1685,11 +1921,15 @@ impl ChatActor {
         user_id: Uuid,
         session: &crate::models::chat::ChatSession,
         ai_config: &crate::config::AiConfig,
-    ) -> crate::error::Result<Agent> {
+    ) -> crate::error::Result<ChatAgent> {
+        // Read before creating the agent, so a server change made meanwhile
+        // still recreates it on the next message
+        let mcp_generation = self.rig_service.mcp_clients().generation(self.workspace_id).await;
+
         let mut state = self.state.lock().await;
 
         // Check if we can reuse the cached agent
-        if state.agent_state.can_reuse(session, user_id) {
+        if state.agent_state.can_reuse(session, user_id, mcp_generation) {
             Ok(state.agent_state.cached_agent.as_ref().unwrap().clone())
         } else {
             tracing::info!(
@@ -1697,6 +1937,15 @@ impl ChatActor {
                 self.chat_id, session.agent_config.model
             );
 
+            // Sub-agents get no delegation runtime, so they cannot delegate further
+            let delegation = session.agent_config.parent_chat_id.is_none().then(|| DelegationContext {
+                rig_service: self.rig_service.clone(),
+                registry: self.registry.clone(),
+                parent_event_tx: self.event_tx.clone(),
+                default_context_token_limit: self.default_context_token_limit,
+                inactivity_timeout: self.inactivity_timeout,
+            });
+
             // Create new agent
             let agent = self.rig_service.create_agent(
                 self.pool.clone(),
@@ -1706,6 +1955,7 @@ impl ChatActor {
                 user_id,
                 session,
                 ai_config,
+                delegation,
             ).await?;
 
             // Update cache - single atomic update
@@ -1713,6 +1963,7 @@ impl ChatActor {
             state.agent_state.current_model_name = Some(session.agent_config.model.clone());
             state.agent_state.current_user_id = Some(user_id);
             state.agent_state.current_mode = Some(session.agent_config.mode.clone());
+            state.agent_state.mcp_generation = Some(mcp_generation);
 
             // Update session metadata with the actual model being used
             // Drop the lock before doing async database operation
@@ -1767,7 +2018,8 @@ impl ChatActor {
     // SESSION TRACKING METHODS
     // ========================================================================
 
-    /// Creates a new agent session in the database.
+    /// Creates or takes over the chat's agent session in the database, claiming
+    /// the chat's lease for this instance.
     async fn create_session(&self) -> Result<Uuid, crate::error::Error> {
         tracing::info!(
             chat_id = %self.chat_id,
@@ -1828,7 +2080,7 @@ impl ChatActor {
             "[ChatActor] Session configuration determined from chat file"
         );
 
-        let session = agent_sessions::create_session(
+        let session = agent_sessions::acquire_chat_lease(
             &mut conn,
             self.workspace_id,
             self.chat_id,
@@ -1836,6 +2088,7 @@ impl ChatActor {
             agent_type,
             actual_model,
             actual_mode,
+            self.registry.node_id,
         )
         .await?;
 
@@ -1881,7 +2134,9 @@ impl ChatActor {
     }
 
     /// Starts a background task that sends periodic heartbeats to the database.
-    /// This keeps the session alive and indicates the agent is actively running.
+    /// This keeps the session alive and renews this instance's lease on the chat.
+    /// When the lease turns out to be lost, the current interaction is cancelled
+    /// and the actor stops.
     fn start_heartbeat_task(&self, session_id: Uuid) -> JoinHandle<()> {
         tracing::info!(
             chat_id = %self.chat_id,
@@ -1890,6 +2145,9 @@ impl ChatActor {
         );
 
         let pool = self.pool.clone();
+        let node_id = self.registry.node_id;
+        let lease_lost = self.lease_lost.clone();
+        let state = self.state.clone();
 
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
@@ -1915,17 +2173,32 @@ impl ChatActor {
                 };
 
                 // Update heartbeat timestamp
-                if let Err(e) = agent_sessions::update_heartbeat(&mut conn, session_id).await {
-                    tracing::warn!(
-                        session_id = %session_id,
-                        error = %e,
-                        "[ChatActor] Heartbeat: failed to update heartbeat"
-                    );
-                } else {
-                    tracing::trace!(
-                        session_id = %session_id,
-                        "[ChatActor] Heartbeat: successfully updated"
-                    );
+                match queries::agent_sessions::renew_chat_lease(&mut conn, session_id, node_id).await {
+                    Ok(true) => {
+                        tracing::trace!(
+                            session_id = %session_id,
+                            "[ChatActor] Heartbeat: successfully updated"
+                        );
+                    }
+                    Ok(false) => {
+                        tracing::warn!(
+                            session_id = %session_id,
+                            node_id = %node_id,
+                            "[ChatActor] Heartbeat: chat lease lost, stopping actor"
+                        );
+                        if let Some(token) = state.lock().await.interaction.current_cancellation_token.clone() {
+                            token.cancel();
+                        }
+                        lease_lost.cancel();
+                        break;
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            session_id = %session_id,
+                            error = %e,
+                            "[ChatActor] Heartbeat: failed to update heartbeat"
+                        );
+                    }
                 }
             }
         })
@@ -0,0 +1,147 @@
+//! Approval of the tool calls a workspace's tool policy asks about
+//!
+//! The tool loop registers a pending approval, sends the chat a `question_pending`
+//! event with Approve and Deny buttons and waits. The user answers it like an
+//! `ask_user` question, posting a message whose `question_answer` metadata carries
+//! the question id and an `approval` answer; the chat handler resolves the pending
+//! approval, on whichever instance runs the chat's actor.
+
+use std::time::Duration;
+
+use serde_json::{Value, json};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::models::sse::{Question, QuestionButton};
+use crate::models::tool_policy::ToolPolicyRule;
+
+/// Name of the question of an approval request, and key of its answer
+pub const APPROVAL_QUESTION: &str = "approval";
+
+/// Answer approving a call
+pub const APPROVE: &str = "approve";
+
+/// Answer denying a call
+pub const DENY: &str = "deny";
+
+/// How long a call waits for its approval before it is refused
+pub const APPROVAL_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Longest argument text shown in an approval request
+const MAX_ARGUMENTS_PREVIEW_CHARS: usize = 2000;
+
+/// Tool calls of this instance's chats waiting for the user's approval
+#[derive(Debug, Default)]
+pub struct ToolApprovals {
+    pending: scc::HashMap<Uuid, PendingApproval>,
+}
+
+#[derive(Debug)]
+struct PendingApproval {
+    chat_id: Uuid,
+    answer: oneshot::Sender<bool>,
+}
+
+impl ToolApprovals {
+    /// Registers a call of a chat waiting for approval, returning the id of its
+    /// question and the receiver of the user's answer
+    pub async fn request(&self, chat_id: Uuid) -> (Uuid, oneshot::Receiver<bool>) {
+        let question_id = Uuid::now_v7();
+        let (answer, receiver) = oneshot::channel();
+        let _ = self
+            .pending
+            .insert_async(question_id, PendingApproval { chat_id, answer })
+            .await;
+        (question_id, receiver)
+    }
+
+    /// Answers a pending approval of a chat
+    ///
+    /// Returns false if the chat has no pending approval with this id, because it
+    /// was answered already, timed out or waits on another instance.
+    pub async fn answer(&self, chat_id: Uuid, question_id: Uuid, approved: bool) -> bool {
+        match self
+            .pending
+            .remove_if_async(&question_id, |pending| pending.chat_id == chat_id)
+            .await
+        {
+            Some((_, pending)) => pending.answer.send(approved).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Drops a pending approval no longer waited for
+    pub async fn forget(&self, question_id: Uuid) {
+        let _ = self.pending.remove_async(&question_id).await;
+    }
+}
+
+/// Question asking the user to approve a call of `tool` with `arguments`
+pub fn approval_question(tool: &str, arguments: &Value, rule: &ToolPolicyRule) -> Question {
+    let mut arguments = serde_json::to_string_pretty(arguments).unwrap_or_default();
+    if let Some((end, _)) = arguments.char_indices().nth(MAX_ARGUMENTS_PREVIEW_CHARS) {
+        arguments.truncate(end);
+        arguments.push_str("\n...");
+    }
+    let mut question = format!("Allow the agent to call `{}`?\n\n```json\n{}\n```", tool, arguments);
+    if let Some(reason) = &rule.reason {
+        question.push_str(&format!("\n\n{}", reason));
+    }
+
+    Question {
+        name: APPROVAL_QUESTION.to_string(),
+        question,
+        schema: json!({ "type": "string", "enum": [APPROVE, DENY] }),
+        buttons: Some(vec![
+            QuestionButton {
+                label: "Approve".to_string(),
+                value: json!(APPROVE),
+                variant: Some("primary".to_string()),
+            },
+            QuestionButton {
+                label: "Deny".to_string(),
+                value: json!(DENY),
+                variant: Some("danger".to_string()),
+            },
+        ]),
+    }
+}
+
+/// Whether the answers of a question answer the approval question, and how
+pub fn approval_answer(answers: &Value) -> Option<bool> {
+    match answers.get(APPROVAL_QUESTION)?.as_str()? {
+        APPROVE => Some(true),
+        DENY => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_answers_reach_the_waiting_call() {
+        let approvals = ToolApprovals::default();
+        let chat_id = Uuid::now_v7();
+
+        let (question_id, answer) = approvals.request(chat_id).await;
+        assert!(!approvals.answer(Uuid::now_v7(), question_id, true).await, "other chats cannot answer");
+        assert!(approvals.answer(chat_id, question_id, true).await);
+        assert!(answer.await.unwrap());
+        assert!(!approvals.answer(chat_id, question_id, false).await, "answered already");
+
+        let (question_id, answer) = approvals.request(chat_id).await;
+        approvals.forget(question_id).await;
+        assert!(!approvals.answer(chat_id, question_id, true).await);
+        assert!(answer.await.is_err());
+    }
+
+    #[test]
+    fn test_approval_answer() {
+        assert_eq!(approval_answer(&json!({"approval": "approve"})), Some(true));
+        assert_eq!(approval_answer(&json!({"approval": "deny"})), Some(false));
+        assert_eq!(approval_answer(&json!({"approval": "maybe"})), None);
+        assert_eq!(approval_answer(&json!({"color": "blue"})), None);
+    }
+}
@@ -0,0 +1,344 @@
+//! Coordination of chat actors across backend instances
+//!
+//! When several instances serve the same database, each chat's actor runs on the
+//! instance holding the chat's lease (`agent_sessions.owner_node_id`, kept alive by
+//! the actor's heartbeat). The instances talk over a Postgres `LISTEN`/`NOTIFY`
+//! channel:
+//!
+//! - the owner publishes its actors' events, so SSE clients connected to any
+//!   instance see them
+//! - other instances publish commands (new message, stop, pause, cancel) addressed
+//!   to the owner
+//!
+//! Notification payloads are limited to 8000 bytes, so messages are split into
+//! base64 chunks and reassembled by the listeners. Postgres delivers the
+//! notifications of one connection in order, which keeps events ordered.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use tokio::sync::{broadcast, mpsc};
+use uuid::Uuid;
+
+use crate::database::DbPool;
+use crate::error::{Error, Result};
+use crate::models::sse::{SseEvent, StreamEvent};
+
+use super::registry::AgentRegistry;
+
+/// Notification channel shared by all instances
+pub const CLUSTER_CHANNEL: &str = "buildscale_agents";
+
+/// Raw bytes per chunk; base64 and the envelope keep the payload under 8000 bytes
+const MAX_CHUNK_BYTES: usize = 5000;
+
+/// How long chunks of an incomplete message are kept
+const PARTIAL_MESSAGE_TTL: Duration = Duration::from_secs(60);
+
+/// Delay before reconnecting a failed listener
+const LISTENER_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Message exchanged between instances
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClusterMessage {
+    /// Event emitted by the actor of a chat, with its id in the chat's event log
+    Event {
+        chat_id: Uuid,
+        id: Option<i64>,
+        event: SseEvent,
+    },
+    /// Command for the actor of a chat, addressed to the instance owning it
+    Command {
+        node_id: Uuid,
+        chat_id: Uuid,
+        command: RemoteCommand,
+    },
+}
+
+/// Command forwarded to the instance running a chat's actor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    /// Process the chat's new messages
+    ProcessInteraction { workspace_id: Uuid, user_id: Uuid },
+    /// Stop the current generation and the actor
+    Cancel { reason: String },
+    /// Stop the current generation, keeping the actor
+    Pause { reason: Option<String> },
+    /// Resume a tool call waiting for the user's approval
+    AnswerApproval { question_id: Uuid, approved: bool },
+}
+
+/// One notification payload
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    /// Instance that sent the message
+    origin: Uuid,
+    /// Message id, shared by its chunks
+    id: Uuid,
+    part: usize,
+    parts: usize,
+    /// Base64 chunk of the JSON message
+    data: String,
+}
+
+/// Publishes messages of one instance on the cluster channel
+#[derive(Debug, Clone)]
+pub struct ClusterBus {
+    pool: DbPool,
+    node_id: Uuid,
+}
+
+impl ClusterBus {
+    pub fn new(pool: DbPool, node_id: Uuid) -> Self {
+        Self { pool, node_id }
+    }
+
+    pub async fn publish(&self, message: &ClusterMessage) -> Result<()> {
+        let payloads = encode(self.node_id, message)?;
+
+        // Chunks go out on one connection so they arrive in order
+        let mut conn = self.pool.acquire().await.map_err(Error::Sqlx)?;
+        for payload in payloads {
+            sqlx::query("SELECT pg_notify($1, $2)")
+                .bind(CLUSTER_CHANNEL)
+                .bind(payload)
+                .execute(&mut *conn)
+                .await
+                .map_err(Error::Sqlx)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits a message into notification payloads
+fn encode(origin: Uuid, message: &ClusterMessage) -> Result<Vec<String>> {
+    let json = serde_json::to_vec(message).map_err(Error::Json)?;
+    let id = Uuid::now_v7();
+    let chunks: Vec<&[u8]> = json.chunks(MAX_CHUNK_BYTES).collect();
+    let parts = chunks.len();
+
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(part, chunk)| {
+            serde_json::to_string(&Envelope {
+                origin,
+                id,
+                part,
+                parts,
+                data: BASE64.encode(chunk),
+            })
+            .map_err(Error::Json)
+        })
+        .collect()
+}
+
+struct PartialMessage {
+    chunks: Vec<Option<Vec<u8>>>,
+    received: usize,
+    started: Instant,
+}
+
+/// Reassembles messages from notification payloads
+#[derive(Default)]
+struct Reassembler {
+    partials: HashMap<Uuid, PartialMessage>,
+}
+
+impl Reassembler {
+    /// Accepts a payload, returning the sender and message once it is complete
+    fn accept(&mut self, payload: &str) -> Option<(Uuid, ClusterMessage)> {
+        let envelope: Envelope = match serde_json::from_str(payload) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                tracing::warn!(error = %e, "[Cluster] Ignoring malformed notification");
+                return None;
+            }
+        };
+        let chunk = BASE64.decode(&envelope.data).ok()?;
+        if envelope.parts == 0 || envelope.part >= envelope.parts {
+            return None;
+        }
+
+        let json = if envelope.parts == 1 {
+            chunk
+        } else {
+            self.partials.retain(|_, partial| partial.started.elapsed() < PARTIAL_MESSAGE_TTL);
+
+            let partial = self.partials.entry(envelope.id).or_insert_with(|| PartialMessage {
+                chunks: vec![None; envelope.parts],
+                received: 0,
+                started: Instant::now(),
+            });
+            let slot = partial.chunks.get_mut(envelope.part)?;
+            if slot.is_none() {
+                *slot = Some(chunk);
+                partial.received += 1;
+            }
+            if partial.received < partial.chunks.len() {
+                return None;
+            }
+
+            let partial = self.partials.remove(&envelope.id)?;
+            partial.chunks.into_iter().flatten().flatten().collect()
+        };
+
+        match serde_json::from_slice(&json) {
+            Ok(message) => Some((envelope.origin, message)),
+            Err(e) => {
+                tracing::warn!(error = %e, "[Cluster] Ignoring undecodable message");
+                None
+            }
+        }
+    }
+}
+
+/// Listens for messages of other instances until `shutdown` fires
+///
+/// Events are delivered to the SSE clients of this instance; commands addressed
+/// to this instance are handed to `commands` as `(chat_id, command)`. The
+/// listener reconnects when the connection drops.
+pub async fn run_listener(
+    pool: DbPool,
+    registry: Arc<AgentRegistry>,
+    commands: mpsc::UnboundedSender<(Uuid, RemoteCommand)>,
+    mut shutdown: broadcast::Receiver<()>,
+) {
+    let mut reassembler = Reassembler::default();
+
+    loop {
+        let mut listener = match connect_listener(&pool).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!(error = %e, "[Cluster] Failed to listen for cluster messages, retrying");
+                tokio::select! {
+                    _ = shutdown.recv() => return,
+                    _ = tokio::time::sleep(LISTENER_RETRY_DELAY) => continue,
+                }
+            }
+        };
+        tracing::info!(node_id = %registry.node_id, channel = CLUSTER_CHANNEL, "[Cluster] Listening for cluster messages");
+
+        loop {
+            let notification = tokio::select! {
+                _ = shutdown.recv() => return,
+                notification = listener.recv() => notification,
+            };
+            let notification = match notification {
+                Ok(notification) => notification,
+                Err(e) => {
+                    tracing::warn!(error = %e, "[Cluster] Cluster listener failed, reconnecting");
+                    break;
+                }
+            };
+
+            let Some((origin, message)) = reassembler.accept(notification.payload()) else {
+                continue;
+            };
+            if origin == registry.node_id {
+                continue;
+            }
+
+            match message {
+                ClusterMessage::Event { chat_id, id, event } => {
+                    registry.deliver_remote_event(chat_id, StreamEvent { id, event }).await;
+                }
+                ClusterMessage::Command { node_id, chat_id, command } if node_id == registry.node_id => {
+                    tracing::info!(chat_id = %chat_id, origin = %origin, command = ?command, "[Cluster] Received remote command");
+                    let _ = commands.send((chat_id, command));
+                }
+                ClusterMessage::Command { .. } => {}
+            }
+        }
+    }
+}
+
+async fn connect_listener(pool: &DbPool) -> Result<PgListener> {
+    let mut listener = PgListener::connect_with(pool).await.map_err(Error::Sqlx)?;
+    listener.listen(CLUSTER_CHANNEL).await.map_err(Error::Sqlx)?;
+    Ok(listener)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_small_message() {
+        let origin = Uuid::now_v7();
+        let chat_id = Uuid::now_v7();
+        let message = ClusterMessage::Command {
+            node_id: Uuid::now_v7(),
+            chat_id,
+            command: RemoteCommand::Cancel { reason: "user_cancelled".to_string() },
+        };
+
+        let payloads = encode(origin, &message).unwrap();
+        assert_eq!(payloads.len(), 1);
+
+        let (sender, decoded) = Reassembler::default().accept(&payloads[0]).unwrap();
+        assert_eq!(sender, origin);
+        match decoded {
+            ClusterMessage::Command { chat_id: decoded_chat, command, .. } => {
+                assert_eq!(decoded_chat, chat_id);
+                assert_eq!(command, RemoteCommand::Cancel { reason: "user_cancelled".to_string() });
+            }
+            other => panic!("Unexpected message: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_round_trip_chunked_message() {
+        // Multibyte text split across chunks at arbitrary byte offsets
+        let text = "héllo wörld ✓ ".repeat(2000);
+        let message = ClusterMessage::Event {
+            chat_id: Uuid::now_v7(),
+            id: Some(42),
+            event: SseEvent::Chunk { text: text.clone() },
+        };
+
+        let payloads = encode(Uuid::now_v7(), &message).unwrap();
+        assert!(payloads.len() > 1);
+        assert!(payloads.iter().all(|payload| payload.len() < 8000));
+
+        // Chunks of another message in between are kept apart
+        let other = encode(Uuid::now_v7(), &ClusterMessage::Event {
+            chat_id: Uuid::now_v7(),
+            id: None,
+            event: SseEvent::Chunk { text: "x".repeat(12_000) },
+        })
+        .unwrap();
+
+        let mut reassembler = Reassembler::default();
+        assert!(reassembler.accept(&payloads[0]).is_none());
+        assert!(reassembler.accept(&other[0]).is_none());
+
+        let mut decoded = None;
+        for payload in payloads.iter().skip(1).rev() {
+            decoded = reassembler.accept(payload);
+        }
+        match decoded.unwrap().1 {
+            ClusterMessage::Event { id, event: SseEvent::Chunk { text: decoded }, .. } => {
+                assert_eq!(id, Some(42));
+                assert_eq!(decoded, text);
+            }
+            other => panic!("Unexpected message: {:?}", other),
+        }
+        assert_eq!(reassembler.partials.len(), 1);
+    }
+
+    #[test]
+    fn test_ignores_malformed_payloads() {
+        let mut reassembler = Reassembler::default();
+        assert!(reassembler.accept("not json").is_none());
+        assert!(reassembler.accept(r#"{"origin":"00000000-0000-0000-0000-000000000000","id":"00000000-0000-0000-0000-000000000000","part":0,"parts":1,"data":"e30="}"#).is_none());
+    }
+}
@@ -0,0 +1,327 @@
+//! Conversation compaction.
+//!
+//! Long chats eventually outgrow the model's context window. Compaction asks a
+//! (usually cheaper) model to summarize the older turns and persists the result
+//! as a `compaction_summary` message, placed in the history right after the last
+//! message it covers. The summarized messages stay in the database and the
+//! `.chat` file, but the AI context starts at the latest summary.
+//!
+//! Compaction runs automatically before a response once the history passes
+//! `threshold_ratio` of the context window, and on demand through
+//! `POST /workspaces/{id}/chats/{chat_id}/compact`.
+
+use crate::config::CompactionConfig;
+use crate::error::{Error, Result};
+use crate::models::chat::{
+    AgentConfig, ChatMessage, ChatMessageMetadata, ChatMessageRole, CompactChatResponse,
+    NewChatMessage, DEFAULT_CHAT_MODEL,
+};
+use crate::queries;
+use crate::services::chat::rig_engine::RigService;
+use crate::services::chat::{
+    filter_messages_for_context, truncate_tool_output, ChatService,
+};
+use crate::services::storage::FileStorageService;
+use crate::services::tokens::TokenCounter;
+use crate::DbConn;
+use uuid::Uuid;
+
+/// `message_type` of persisted summary messages
+pub const COMPACTION_SUMMARY_MESSAGE_TYPE: &str = "compaction_summary";
+
+/// Instructions given to the model writing the summary
+pub const SUMMARY_INSTRUCTIONS: &str = "You compact the history of a conversation between a user and an \
+AI assistant working in a file workspace, so the conversation can continue within a limited context \
+window. Summarize the transcript you are given. Preserve the user's goals and requests, decisions and \
+their reasons, files read, created or modified (with their paths), important tool results, open \
+questions, and the current state of the work with its next steps. If the transcript starts with an \
+earlier summary, fold it into yours. Write concise bullet points and do not invent details.";
+
+/// Whether a message is a persisted compaction summary
+pub fn is_compaction_summary(msg: &ChatMessage) -> bool {
+    msg.metadata.message_type.as_deref() == Some(COMPACTION_SUMMARY_MESSAGE_TYPE)
+}
+
+/// The history the AI sees: the latest summary and every message after it.
+///
+/// Messages before the latest summary are covered by it and dropped.
+pub fn compacted_history(mut messages: Vec<ChatMessage>) -> Vec<ChatMessage> {
+    match messages.iter().rposition(is_compaction_summary) {
+        Some(index) => messages.split_off(index),
+        None => messages,
+    }
+}
+
+/// Tokens of the messages sent to the AI, counted with the chat model's tokenizer.
+pub fn estimate_history_tokens(messages: &[ChatMessage], counter: TokenCounter) -> usize {
+    filter_messages_for_context(messages)
+        .iter()
+        .map(|msg| counter.count(&msg.content))
+        .sum()
+}
+
+/// Whether a history of `history_tokens` should be compacted.
+pub fn should_compact(history_tokens: usize, context_window: usize, config: &CompactionConfig) -> bool {
+    config.enabled && history_tokens as f64 >= context_window as f64 * config.threshold_ratio
+}
+
+/// Number of leading messages to summarize, 0 when there is nothing to compact.
+///
+/// Keeps at least `keep_recent` messages, and always the latest one, and moves
+/// the cut back to the start of a user turn, so tool calls are never separated
+/// from their results.
+pub fn compaction_split_point(history: &[ChatMessage], keep_recent: usize) -> usize {
+    let mut cut = history.len().saturating_sub(keep_recent.max(1));
+    while cut > 0 && !is_turn_start(&history[cut]) {
+        cut -= 1;
+    }
+
+    // Re-summarizing a lone summary gains nothing
+    if history[..cut].iter().all(is_compaction_summary) {
+        return 0;
+    }
+    cut
+}
+
+fn is_turn_start(msg: &ChatMessage) -> bool {
+    msg.role == ChatMessageRole::User && msg.metadata.message_type.is_none()
+}
+
+/// Renders messages as a plain-text transcript for the summarizer.
+pub fn render_transcript(messages: &[ChatMessage]) -> String {
+    filter_messages_for_context(messages)
+        .iter()
+        .map(|msg| {
+            let metadata = &msg.metadata.0;
+            match metadata.message_type.as_deref() {
+                Some(COMPACTION_SUMMARY_MESSAGE_TYPE) => {
+                    format!("Summary of the earlier conversation:\n{}", msg.content)
+                }
+                Some("tool_call") => format!(
+                    "Assistant called tool {}: {}",
+                    metadata.tool_name.as_deref().unwrap_or("unknown"),
+                    truncate_tool_output(
+                        &metadata.tool_arguments.as_ref().map(|args| args.to_string()).unwrap_or_default()
+                    )
+                ),
+                Some("tool_result") => format!(
+                    "Tool {} returned: {}",
+                    metadata.tool_name.as_deref().unwrap_or("unknown"),
+                    truncate_tool_output(metadata.tool_output.as_deref().unwrap_or(&msg.content))
+                ),
+                _ => {
+                    let role = match msg.role {
+                        ChatMessageRole::User => "User",
+                        ChatMessageRole::Assistant => "Assistant",
+                        ChatMessageRole::System => "System",
+                        ChatMessageRole::Tool => "Tool",
+                    };
+                    format!("{}: {}", role, msg.content)
+                }
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Compacts the history of a chat.
+///
+/// Without `force`, only compacts when enabled and the history is over the
+/// threshold. Returns `compacted: false` when there is nothing to summarize.
+#[allow(clippy::too_many_arguments)]
+pub async fn compact_chat(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    rig_service: &RigService,
+    config: &CompactionConfig,
+    workspace_id: Uuid,
+    chat_file_id: Uuid,
+    fallback_token_limit: usize,
+    force: bool,
+) -> Result<CompactChatResponse> {
+    let file = queries::files::get_file_by_id(conn, chat_file_id).await?;
+    if file.workspace_id != workspace_id || !matches!(file.file_type, crate::models::files::FileType::Chat) {
+        return Err(Error::NotFound(format!("Chat not found: {}", chat_file_id)));
+    }
+
+    let chat_model = queries::files::get_latest_version(conn, chat_file_id)
+        .await
+        .ok()
+        .and_then(|version| serde_json::from_value::<AgentConfig>(version.app_data).ok())
+        .map(|agent_config| agent_config.model)
+        .unwrap_or_else(|| DEFAULT_CHAT_MODEL.to_string());
+
+    let counter = TokenCounter::for_model(&chat_model);
+    let messages = queries::chat::get_messages_by_file_id(conn, workspace_id, chat_file_id).await?;
+    let history = compacted_history(messages);
+    let tokens_before = estimate_history_tokens(&history, counter);
+
+    let not_compacted = CompactChatResponse {
+        chat_id: chat_file_id,
+        compacted: false,
+        compacted_message_count: 0,
+        tokens_before,
+        tokens_after: tokens_before,
+        summary: None,
+    };
+
+    if !force {
+        let context_window = ChatService::get_model_context_window(conn, &chat_model)
+            .await
+            .unwrap_or(fallback_token_limit);
+        if !should_compact(tokens_before, context_window, config) {
+            return Ok(not_compacted);
+        }
+    }
+
+    let cut = compaction_split_point(&history, config.keep_recent_messages);
+    if cut == 0 {
+        return Ok(not_compacted);
+    }
+
+    let summary_model = config.summary_model.clone().unwrap_or(chat_model);
+    tracing::info!(
+        chat_id = %chat_file_id,
+        model = %summary_model,
+        messages = cut,
+        tokens_before = tokens_before,
+        forced = force,
+        "[Compaction] Summarizing older history"
+    );
+
+    let transcript = render_transcript(&history[..cut]);
+    let summary = rig_service.summarize(&summary_model, SUMMARY_INSTRUCTIONS, &transcript).await?;
+    let summary = summary.trim();
+    if summary.is_empty() {
+        return Err(Error::Internal("Summary model returned an empty summary".into()));
+    }
+
+    // Right after the last covered message, so the summary takes its place in the history
+    let created_at = history[cut - 1].created_at + chrono::Duration::microseconds(1);
+    let summary = ChatService::save_message_at(
+        conn,
+        storage,
+        workspace_id,
+        NewChatMessage {
+            file_id: chat_file_id,
+            workspace_id,
+            role: ChatMessageRole::System,
+            content: summary.to_string(),
+            metadata: sqlx::types::Json(ChatMessageMetadata {
+                message_type: Some(COMPACTION_SUMMARY_MESSAGE_TYPE.to_string()),
+                model: Some(summary_model),
+                ..Default::default()
+            }),
+        },
+        created_at,
+    )
+    .await?;
+
+    let tokens_after = estimate_history_tokens(std::slice::from_ref(&summary), counter)
+        + estimate_history_tokens(&history[cut..], counter);
+
+    Ok(CompactChatResponse {
+        chat_id: chat_file_id,
+        compacted: true,
+        compacted_message_count: cut,
+        tokens_before,
+        tokens_after,
+        summary: Some(summary),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn message(role: ChatMessageRole, message_type: Option<&str>, content: &str, minute: i64) -> ChatMessage {
+        ChatMessage {
+            id: Uuid::now_v7(),
+            file_id: Uuid::nil(),
+            workspace_id: Uuid::nil(),
+            role,
+            content: content.to_string(),
+            metadata: sqlx::types::Json(ChatMessageMetadata {
+                message_type: message_type.map(str::to_string),
+                tool_name: message_type.map(|_| "read".to_string()),
+                ..Default::default()
+            }),
+            created_at: Utc::now() + Duration::minutes(minute),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        }
+    }
+
+    fn turn(minute: i64) -> Vec<ChatMessage> {
+        vec![
+            message(ChatMessageRole::User, None, "Read the notes", minute),
+            message(ChatMessageRole::Assistant, Some("tool_call"), "", minute),
+            message(ChatMessageRole::Tool, Some("tool_result"), "notes", minute),
+            message(ChatMessageRole::Assistant, None, "Done", minute),
+        ]
+    }
+
+    #[test]
+    fn test_compacted_history_starts_at_latest_summary() {
+        let mut messages = turn(0);
+        messages.push(message(ChatMessageRole::System, Some(COMPACTION_SUMMARY_MESSAGE_TYPE), "first", 1));
+        messages.extend(turn(2));
+        messages.push(message(ChatMessageRole::System, Some(COMPACTION_SUMMARY_MESSAGE_TYPE), "second", 3));
+        messages.extend(turn(4));
+
+        let history = compacted_history(messages);
+        assert_eq!(history.len(), 5);
+        assert_eq!(history[0].content, "second");
+
+        assert_eq!(compacted_history(turn(0)).len(), 4);
+    }
+
+    #[test]
+    fn test_split_point_keeps_tool_calls_with_results() {
+        let mut history = turn(0);
+        history.extend(turn(1));
+        history.extend(turn(2));
+
+        // Keeping 6 would start inside the second turn, so the whole turn is kept
+        assert_eq!(compaction_split_point(&history, 6), 4);
+        assert_eq!(compaction_split_point(&history, 4), 8);
+        assert_eq!(compaction_split_point(&history, 12), 0);
+        assert_eq!(compaction_split_point(&history, 0), 8);
+    }
+
+    #[test]
+    fn test_split_point_skips_lone_summary() {
+        let mut history = vec![message(ChatMessageRole::System, Some(COMPACTION_SUMMARY_MESSAGE_TYPE), "summary", 0)];
+        history.extend(turn(1));
+        assert_eq!(compaction_split_point(&history, 4), 0);
+
+        history.extend(turn(2));
+        assert_eq!(compaction_split_point(&history, 4), 5);
+    }
+
+    #[test]
+    fn test_should_compact_uses_threshold_ratio() {
+        let config = CompactionConfig::default();
+        assert!(!should_compact(79_999, 100_000, &config));
+        assert!(should_compact(80_000, 100_000, &config));
+
+        let disabled = CompactionConfig { enabled: false, ..CompactionConfig::default() };
+        assert!(!should_compact(100_000, 100_000, &disabled));
+    }
+
+    #[test]
+    fn test_render_transcript() {
+        let mut messages = vec![message(ChatMessageRole::System, Some(COMPACTION_SUMMARY_MESSAGE_TYPE), "- earlier work", 0)];
+        messages.extend(turn(1));
+        messages.push(message(ChatMessageRole::Assistant, Some("reasoning_complete"), "thinking", 1));
+
+        let transcript = render_transcript(&messages);
+        assert!(transcript.starts_with("Summary of the earlier conversation:\n- earlier work"));
+        assert!(transcript.contains("User: Read the notes"));
+        assert!(transcript.contains("Assistant called tool read"));
+        assert!(transcript.contains("Tool read returned: notes"));
+        assert!(transcript.contains("Assistant: Done"));
+        assert!(!transcript.contains("thinking"));
+    }
+}
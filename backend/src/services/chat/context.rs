@@ -1,4 +1,5 @@
 use crate::models::chat::{ChatMessage, ChatMessageMetadata, ChatMessageRole};
+use crate::services::tokens::TokenCounter;
 use chrono::{DateTime, Utc};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
@@ -119,14 +120,16 @@ pub fn truncate_tool_output(output: &str) -> String {
 
 /// Attachment key types for identifying different attachment sources.
 ///
-/// Currently only `WorkspaceFile` is actively used. Other types are reserved
-/// for future enhancements (skills, environment context, etc.).
+/// `WorkspaceFile`, `WebPage` and `ActiveSkill` are actively used. Other types are
+/// reserved for future enhancements (environment context, etc.).
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(tag = "type", content = "id", rename_all = "lowercase")]
 pub enum AttachmentKey {
     SystemPersona,
     ActiveSkill(Uuid),
     WorkspaceFile(Uuid),
+    /// Web page attached by URL, keyed by a UUID derived from the URL
+    WebPage(Uuid),
     Environment,
     ChatHistory,
     UserRequest,
@@ -156,7 +159,7 @@ pub enum ContextItem {
         role: ChatMessageRole,
         content: String,
         created_at: DateTime<Utc>,
-        metadata: ChatMessageMetadata,
+        metadata: Box<ChatMessageMetadata>,
     },
     /// An attachment (file, skill, etc.) with rendered XML content
     Attachment {
@@ -195,6 +198,8 @@ pub const PRIORITY_MEDIUM: i32 = 5;
 pub const PRIORITY_LOW: i32 = 10;
 
 // Token Estimation
+/// Rough characters per token, for estimates where no tokenizer is at hand.
+/// Context budgeting counts real tokens with `TokenCounter`.
 pub const ESTIMATED_CHARS_PER_TOKEN: usize = 4;
 
 /// Manages file attachments with priority-based pruning and token estimation.
@@ -202,7 +207,7 @@ pub const ESTIMATED_CHARS_PER_TOKEN: usize = 4;
 /// # Purpose
 ///
 /// `AttachmentManager` handles workspace file attachments for AI chat sessions:
-/// - **Token Estimation**: Automatic counting using `ESTIMATED_CHARS_PER_TOKEN`
+/// - **Token Budgeting**: Each attachment carries its token count from the chat model's tokenizer
 /// - **Priority-Based Pruning**: Drops low-priority files when over token limits
 /// - **Keyed Addressability**: Access files by `WorkspaceFile(file_id)` key
 /// - **XML Rendering**: Formats attachments with `<file_context>` markers
@@ -223,13 +228,13 @@ pub const ESTIMATED_CHARS_PER_TOKEN: usize = 4;
 /// let mut manager = AttachmentManager::new();
 /// let file_id = Uuid::now_v7();
 ///
-/// // Add a file attachment with estimated tokens
+/// // Add a file attachment with its token count
 /// manager.add_fragment(
 ///     AttachmentKey::WorkspaceFile(file_id),
 ///     AttachmentValue {
 ///         content: "File content here".to_string(),
 ///         priority: PRIORITY_MEDIUM,
-///         tokens: 100,  // Estimated token count
+///         tokens: 100,  // TokenCounter::count of the content
 ///         is_essential: false,
 ///     },
 /// );
@@ -276,7 +281,7 @@ impl AttachmentManager {
         match key {
             AttachmentKey::SystemPersona => POS_SYSTEM_PERSONA,
             AttachmentKey::ActiveSkill(_) => POS_SKILLS,
-            AttachmentKey::WorkspaceFile(_) => POS_WORKSPACE_FILES,
+            AttachmentKey::WorkspaceFile(_) | AttachmentKey::WebPage(_) => POS_WORKSPACE_FILES,
             AttachmentKey::Environment => POS_ENVIRONMENT,
             AttachmentKey::ChatHistory => POS_CHAT_HISTORY,
             AttachmentKey::UserRequest => POS_USER_REQUEST,
@@ -342,6 +347,8 @@ impl AttachmentManager {
     /// File content here
     /// </file_context>
     /// ```
+    ///
+    /// and web pages in `<url_context>` markers.
     pub fn render(&self) -> String {
         let mut output = String::new();
 
@@ -353,6 +360,12 @@ impl AttachmentManager {
                     output.push_str(&value.content);
                     output.push_str("\n</file_context>\n\n");
                 }
+                AttachmentKey::WebPage(_) => {
+                    // Wrap web pages in their own markers, they are not workspace files
+                    output.push_str("<url_context>\n");
+                    output.push_str(&value.content);
+                    output.push_str("\n</url_context>\n\n");
+                }
                 AttachmentKey::SystemPersona => {
                     // System persona - just content
                     output.push_str(&value.content);
@@ -443,7 +456,7 @@ pub fn messages_to_context_items(messages: &[ChatMessage]) -> Vec<ContextItem> {
             role: msg.role,
             content: msg.content.clone(),
             created_at: msg.created_at,
-            metadata: msg.metadata.0.clone(),
+            metadata: Box::new(msg.metadata.0.clone()),
         })
         .collect()
 }
@@ -505,6 +518,9 @@ pub fn render_attachment_for_ai(key: &AttachmentKey, value: &AttachmentValue) ->
         AttachmentKey::WorkspaceFile(_) => {
             format!("<file_context>\n{}\n</file_context>", value.content)
         }
+        AttachmentKey::WebPage(_) => {
+            format!("<url_context>\n{}\n</url_context>", value.content)
+        }
         AttachmentKey::SystemPersona
         | AttachmentKey::ActiveSkill(_)
         | AttachmentKey::Environment
@@ -536,7 +552,7 @@ pub fn format_history_fragment(messages: &[ChatMessage]) -> String {
 /// # Purpose
 ///
 /// `HistoryManager` wraps a vector of chat messages and provides:
-/// - **Token Estimation**: Automatic counting using `ESTIMATED_CHARS_PER_TOKEN`
+/// - **Token Counting**: Counted with the chat model's tokenizer (`TokenCounter`)
 /// - **Pruning Strategies**: Future ability to truncate or summarize long conversations
 /// - **Convenience Methods**: Easy access to message history
 ///
@@ -553,15 +569,16 @@ pub fn format_history_fragment(messages: &[ChatMessage]) -> String {
 /// ```text,no_run
 /// use buildscale::services::chat::HistoryManager;
 ///
-/// // Create from existing message vector
-/// let manager = HistoryManager::new(vec![msg1, msg2, msg3]);
+/// // Create from existing message vector, counting with the chat model's tokenizer
+/// let manager = HistoryManager::new(vec![msg1, msg2, msg3])
+///     .with_token_counter(TokenCounter::for_model("openai:gpt-5-mini"));
 ///
 /// // Access as slice
 /// for msg in &manager.messages {
 ///     println!("{}: {}", msg.role, msg.content);
 /// }
 ///
-/// // Estimate total tokens
+/// // Count total tokens
 /// let total_tokens = manager.estimate_tokens();
 ///
 /// // Future: Prune to fit limit
@@ -571,21 +588,32 @@ pub fn format_history_fragment(messages: &[ChatMessage]) -> String {
 pub struct HistoryManager {
     /// Conversation history (excluding current message)
     pub messages: Vec<ChatMessage>,
+    /// Tokenizer of the chat model
+    pub token_counter: TokenCounter,
 }
 
 impl HistoryManager {
     /// Create a new HistoryManager from a message vector.
+    ///
+    /// Counts tokens with the default chat model's tokenizer.
     pub fn new(messages: Vec<ChatMessage>) -> Self {
-        Self { messages }
+        Self {
+            messages,
+            token_counter: TokenCounter::default(),
+        }
     }
 
-    /// Estimate total tokens for all messages in history.
-    ///
-    /// Uses `ESTIMATED_CHARS_PER_TOKEN` (4 chars per token) for approximation.
+    /// Count tokens with the given tokenizer instead.
+    pub fn with_token_counter(mut self, token_counter: TokenCounter) -> Self {
+        self.token_counter = token_counter;
+        self
+    }
+
+    /// Total tokens of all messages in history, counted with `token_counter`.
     pub fn estimate_tokens(&self) -> usize {
         self.messages
             .iter()
-            .map(|msg| msg.content.len() / ESTIMATED_CHARS_PER_TOKEN)
+            .map(|msg| self.token_counter.count(&msg.content))
             .sum()
     }
 
@@ -0,0 +1,456 @@
+//! Sub-agent delegation for the `delegate` tool.
+//!
+//! Each delegated task runs in its own child chat: a `.chat` file linked to the
+//! parent, driven by its own `ChatActor` (and therefore its own `agent_sessions`
+//! row). The child's events are nested into the parent's SSE stream as
+//! `SseEvent::Delegate`, and its final answer becomes the tool result.
+
+use crate::error::{Error, Result};
+use crate::models::chat::{
+    AgentConfig, ChatMessageMetadata, ChatMessageRole, NewChatMessage,
+};
+use crate::models::requests::{DelegateArgs, DelegateResult, DelegateTask, DelegateTaskResult};
+use crate::models::sse::SseEvent;
+use crate::services::chat::actor::{ChatActor, ChatActorArgs};
+use crate::services::chat::registry::{AgentCommand, AgentHandle, AgentRegistry};
+use crate::services::chat::rig_engine::RigService;
+use crate::services::chat::{truncate_at_char_boundary, ChatService};
+use crate::services::storage::FileStorageService;
+use crate::services::tokens::TokenCounter;
+use crate::tools::delegate::{resolve_delegate_tools, validate_delegate_args, DEFAULT_DELEGATE_TOKEN_BUDGET};
+use crate::tools::ToolConfig;
+use crate::DbPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Maximum time a single sub-agent may run before it is stopped
+const DELEGATE_TASK_TIMEOUT_SECS: u64 = 600;
+
+/// Maximum length in bytes of the task snippet used as the child chat name
+const DELEGATE_CHAT_NAME_SNIPPET_LENGTH: usize = 80;
+
+/// Runtime a chat actor hands to its `delegate` tool to start sub-agents
+#[derive(Clone)]
+pub struct DelegationContext {
+    pub rig_service: Arc<RigService>,
+    pub registry: Arc<AgentRegistry>,
+    /// Parent chat's event bus, sub-agent events are nested into it
+    pub parent_event_tx: broadcast::Sender<SseEvent>,
+    pub default_context_token_limit: usize,
+    pub inactivity_timeout: Duration,
+}
+
+/// A running sub-agent
+///
+/// Dropping it stops the sub-agent if it has not finished, so cancelling the
+/// parent's tool call also cancels its children.
+struct DelegateChild {
+    chat_id: Uuid,
+    task_index: usize,
+    task: String,
+    token_budget: usize,
+    usage: TokenUsage,
+    handle: AgentHandle,
+    registry: Arc<AgentRegistry>,
+    event_rx: broadcast::Receiver<SseEvent>,
+    finished: bool,
+}
+
+impl Drop for DelegateChild {
+    fn drop(&mut self) {
+        let chat_id = self.chat_id;
+        let finished = self.finished;
+        let handle = self.handle.clone();
+        let registry = self.registry.clone();
+
+        tokio::spawn(async move {
+            if !finished {
+                tracing::info!(chat_id = %chat_id, "[Delegate] Cancelling unfinished sub-agent");
+                registry.cancel_stream(&chat_id).await;
+            }
+            let _ = handle.command_tx.send(AgentCommand::Shutdown).await;
+            registry.remove(&chat_id).await;
+        });
+    }
+}
+
+/// Runs the delegated tasks concurrently and collects their final answers
+///
+/// All sub-agents are started before any of them is awaited, so a failure to
+/// start one (e.g. a database error) fails the whole call and stops the ones
+/// already started.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_delegates(
+    delegation: &DelegationContext,
+    pool: &DbPool,
+    storage: &Arc<FileStorageService>,
+    workspace_id: Uuid,
+    parent_chat_id: Uuid,
+    user_id: Uuid,
+    tool_config: &ToolConfig,
+    args: DelegateArgs,
+) -> Result<DelegateResult> {
+    validate_delegate_args(&args)?;
+
+    let mut conn = pool.acquire().await.map_err(Error::Sqlx)?;
+    let version = crate::queries::files::get_latest_version(&mut conn, parent_chat_id).await?;
+    let parent_config: AgentConfig = serde_json::from_value(version.app_data).map_err(Error::Json)?;
+    drop(conn);
+
+    let mut children = Vec::with_capacity(args.tasks.len());
+    for (task_index, task) in args.tasks.into_iter().enumerate() {
+        let child = start_child(
+            delegation,
+            pool,
+            storage,
+            workspace_id,
+            parent_chat_id,
+            user_id,
+            &parent_config,
+            tool_config,
+            task_index,
+            task,
+        )
+        .await?;
+        children.push(child);
+    }
+
+    tracing::info!(
+        chat_id = %parent_chat_id,
+        count = children.len(),
+        "[Delegate] Sub-agents started"
+    );
+
+    let results = futures::future::join_all(
+        children.iter_mut().map(|child| wait_for_child(delegation, child)),
+    )
+    .await;
+
+    Ok(DelegateResult { results })
+}
+
+/// Creates the child chat and starts its actor on the task
+#[allow(clippy::too_many_arguments)]
+async fn start_child(
+    delegation: &DelegationContext,
+    pool: &DbPool,
+    storage: &Arc<FileStorageService>,
+    workspace_id: Uuid,
+    parent_chat_id: Uuid,
+    user_id: Uuid,
+    parent_config: &AgentConfig,
+    tool_config: &ToolConfig,
+    task_index: usize,
+    task: DelegateTask,
+) -> Result<DelegateChild> {
+    let mut conn = pool.acquire().await.map_err(Error::Sqlx)?;
+
+    // 1. Create the child chat with the parent's model and mode, restricted to
+    //    the delegated tools
+    let child_config = AgentConfig {
+        agent_id: None,
+        model: parent_config.model.clone(),
+        temperature: parent_config.temperature,
+        persona_override: None,
+        previous_response_id: None,
+        mode: parent_config.mode.clone(),
+        plan_file: parent_config.plan_file.clone(),
+        parent_chat_id: Some(parent_chat_id),
+        allowed_tools: Some(resolve_delegate_tools(task.tools.as_deref(), tool_config)),
+    };
+    let name = {
+        let task_text = task.task.trim();
+        let snippet_end = truncate_at_char_boundary(task_text, DELEGATE_CHAT_NAME_SNIPPET_LENGTH);
+        format!("Delegate: {}", &task_text[..snippet_end])
+    };
+    let app_data = serde_json::to_value(&child_config).map_err(Error::Json)?;
+    let chat_file = ChatService::create_chat_file(&mut conn, workspace_id, user_id, name, app_data).await?;
+    crate::services::files::link_files(&mut conn, parent_chat_id, chat_file.id).await?;
+
+    // 2. The task is the child's first user message
+    ChatService::save_message(&mut conn, storage, workspace_id, NewChatMessage {
+        file_id: chat_file.id,
+        workspace_id,
+        role: ChatMessageRole::User,
+        content: task.task.clone(),
+        metadata: sqlx::types::Json(ChatMessageMetadata {
+            model: Some(child_config.model.clone()),
+            ..Default::default()
+        }),
+    }).await?;
+
+    // 3. Every model call of the child is sent its persona and task, counted
+    //    the way the child's actor builds them
+    let default_persona = crate::agents::get_persona(Some("delegate"), None, None);
+    let context = ChatService::build_context(
+        &mut conn,
+        storage,
+        workspace_id,
+        chat_file.id,
+        &default_persona,
+        delegation.default_context_token_limit,
+        false,
+    ).await?;
+    let usage = TokenUsage::new(
+        context.token_counter,
+        context.token_counter.count(&context.persona) + context.history.estimate_tokens(),
+    );
+    drop(conn);
+
+    // 4. Subscribe before the actor starts so no event is missed
+    let event_tx = delegation.registry.get_or_create_bus(chat_file.id).await;
+    let event_rx = event_tx.subscribe();
+
+    let handle = ChatActor::spawn(ChatActorArgs {
+        chat_id: chat_file.id,
+        workspace_id,
+        user_id,
+        pool: pool.clone(),
+        rig_service: delegation.rig_service.clone(),
+        storage: storage.clone(),
+        registry: delegation.registry.clone(),
+        default_persona,
+        default_context_token_limit: delegation.default_context_token_limit,
+        event_tx,
+        inactivity_timeout: delegation.inactivity_timeout,
+    });
+    delegation.registry.register(chat_file.id, handle.clone()).await;
+
+    let child = DelegateChild {
+        chat_id: chat_file.id,
+        task_index,
+        task: task.task,
+        token_budget: task.token_budget.unwrap_or(DEFAULT_DELEGATE_TOKEN_BUDGET),
+        usage,
+        handle,
+        registry: delegation.registry.clone(),
+        event_rx,
+        finished: false,
+    };
+
+    // Announce the child chat before any of its events
+    let _ = delegation.parent_event_tx.send(SseEvent::Delegate {
+        chat_id: child.chat_id,
+        task_index,
+        event: Box::new(SseEvent::SessionInit {
+            chat_id: child.chat_id,
+            plan_id: None,
+        }),
+    });
+
+    child
+        .handle
+        .command_tx
+        .send(AgentCommand::ProcessInteraction { user_id })
+        .await
+        .map_err(|e| Error::Internal(format!("Failed to start sub-agent: {}", e)))?;
+
+    Ok(child)
+}
+
+/// Forwards the child's events to the parent until its turn ends
+async fn wait_for_child(delegation: &DelegationContext, child: &mut DelegateChild) -> DelegateTaskResult {
+    let deadline = tokio::time::sleep(Duration::from_secs(DELEGATE_TASK_TIMEOUT_SECS));
+    tokio::pin!(deadline);
+
+    let mut answer = String::new();
+    let mut budget_exceeded = false;
+
+    let (status, error) = loop {
+        let event = tokio::select! {
+            _ = &mut deadline => {
+                delegation.registry.cancel_stream(&child.chat_id).await;
+                break ("timed_out", Some(format!(
+                    "Sub-agent did not finish within {} seconds",
+                    DELEGATE_TASK_TIMEOUT_SECS
+                )));
+            }
+            event = child.event_rx.recv() => event,
+        };
+
+        let event = match event {
+            Ok(SseEvent::Ping) => continue,
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    chat_id = %child.chat_id,
+                    skipped,
+                    "[Delegate] Parent lagged behind sub-agent events"
+                );
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                break ("failed", Some("Sub-agent event stream closed unexpectedly".to_string()));
+            }
+        };
+
+        child.usage.record(&event);
+        let _ = delegation.parent_event_tx.send(SseEvent::Delegate {
+            chat_id: child.chat_id,
+            task_index: child.task_index,
+            event: Box::new(event.clone()),
+        });
+
+        match event {
+            SseEvent::Chunk { text } => answer.push_str(&text),
+            SseEvent::Done { .. } => break ("completed", None),
+            SseEvent::Error { message } => break ("failed", Some(message)),
+            SseEvent::Stopped { reason, .. } => {
+                if budget_exceeded {
+                    break ("budget_exceeded", Some(format!(
+                        "Sub-agent exceeded its token budget of {}",
+                        child.token_budget
+                    )));
+                }
+                break ("cancelled", Some(format!("Sub-agent was stopped: {}", reason)));
+            }
+            _ => {}
+        }
+
+        if !budget_exceeded && child.usage.total() > child.token_budget {
+            tracing::info!(
+                chat_id = %child.chat_id,
+                tokens_used = child.usage.total(),
+                token_budget = child.token_budget,
+                "[Delegate] Sub-agent exceeded its token budget, stopping it"
+            );
+            budget_exceeded = true;
+
+            // Nothing to cancel means the turn is already wrapping up
+            if !delegation.registry.cancel_stream(&child.chat_id).await {
+                break ("budget_exceeded", Some(format!(
+                    "Sub-agent exceeded its token budget of {}",
+                    child.token_budget
+                )));
+            }
+        }
+    };
+
+    // Any running turn was cancelled above, so dropping the child only shuts it down
+    child.finished = true;
+    let tokens_used = child.usage.total();
+
+    tracing::info!(
+        chat_id = %child.chat_id,
+        status,
+        tokens_used,
+        reported = child.usage.reported.is_some(),
+        "[Delegate] Sub-agent finished"
+    );
+
+    DelegateTaskResult {
+        chat_id: child.chat_id,
+        task: child.task.clone(),
+        status: status.to_string(),
+        answer: if answer.trim().is_empty() { None } else { Some(answer.trim().to_string()) },
+        error,
+        tokens_used,
+    }
+}
+
+/// Tokens a sub-agent's model calls use, followed through its events
+///
+/// Each model call is sent the persona, the conversation and everything the turn
+/// added so far, so its prompt is counted again for every call. The counts are
+/// estimates of the child model's tokenizer until the provider reports the
+/// turn's usage, which then replaces them.
+struct TokenUsage {
+    token_counter: TokenCounter,
+    /// Persona and conversation before the turn
+    context_tokens: usize,
+    /// Model output and tool results added by the turn
+    turn_tokens: usize,
+    /// Whether a model call is streaming, it ends with the tool results it asked for
+    in_model_call: bool,
+    estimated: usize,
+    reported: Option<usize>,
+}
+
+impl TokenUsage {
+    fn new(token_counter: TokenCounter, context_tokens: usize) -> Self {
+        Self {
+            token_counter,
+            context_tokens,
+            turn_tokens: 0,
+            in_model_call: false,
+            estimated: 0,
+            reported: None,
+        }
+    }
+
+    fn record(&mut self, event: &SseEvent) {
+        match event {
+            SseEvent::Chunk { text } | SseEvent::Thought { text, .. } => self.record_output(text),
+            SseEvent::Call { args, .. } => self.record_output(&args.to_string()),
+            SseEvent::Observation { output, .. } => {
+                self.in_model_call = false;
+                self.turn_tokens += self.token_counter.count(output);
+            }
+            SseEvent::ModelFallback { to_model, .. } => {
+                self.token_counter = TokenCounter::for_model(to_model);
+            }
+            SseEvent::Usage { input_tokens, output_tokens, total_tokens } => {
+                let total = (*total_tokens).max(input_tokens + output_tokens);
+                self.reported = Some(usize::try_from(total).unwrap_or(usize::MAX));
+            }
+            _ => {}
+        }
+    }
+
+    fn record_output(&mut self, text: &str) {
+        if !self.in_model_call {
+            self.in_model_call = true;
+            self.estimated += self.context_tokens + self.turn_tokens;
+        }
+        let tokens = self.token_counter.count(text);
+        self.turn_tokens += tokens;
+        self.estimated += tokens;
+    }
+
+    /// Tokens used so far, as reported by the provider once it has
+    fn total(&self) -> usize {
+        self.reported.unwrap_or(self.estimated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_usage_counts_prompt_of_every_model_call() {
+        let counter = TokenCounter::for_model("gpt-5-mini");
+        let text = "The quick brown fox jumps over the lazy dog.";
+        let tokens = counter.count(text);
+        let mut usage = TokenUsage::new(counter, 100);
+
+        // First call: the context, then its output
+        usage.record(&SseEvent::Thought { agent_id: None, text: text.to_string() });
+        usage.record(&SseEvent::Call { tool: "read".to_string(), path: None, args: serde_json::json!({}) });
+        assert_eq!(usage.total(), 100 + tokens + counter.count("{}"));
+
+        // The tool result and the first call's output are sent with the second call
+        usage.record(&SseEvent::Observation { output: text.to_string(), success: true });
+        usage.record(&SseEvent::Chunk { text: text.to_string() });
+        let turn = tokens + counter.count("{}") + tokens;
+        assert_eq!(usage.total(), (100 + tokens + counter.count("{}")) + (100 + turn) + tokens);
+
+        usage.record(&SseEvent::Done { message: "Turn complete".to_string() });
+        assert_eq!(usage.total(), (100 + tokens + counter.count("{}")) + (100 + turn) + tokens);
+    }
+
+    #[test]
+    fn test_token_usage_prefers_reported_usage() {
+        let mut usage = TokenUsage::new(TokenCounter::for_model("gpt-5-mini"), 100);
+        usage.record(&SseEvent::Chunk { text: "a".repeat(400) });
+
+        usage.record(&SseEvent::Usage { input_tokens: 1200, output_tokens: 300, total_tokens: 1500 });
+        assert_eq!(usage.total(), 1500);
+
+        // Some providers report no total
+        usage.record(&SseEvent::Usage { input_tokens: 1200, output_tokens: 300, total_tokens: 0 });
+        assert_eq!(usage.total(), 1500);
+    }
+}
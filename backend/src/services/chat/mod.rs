@@ -94,11 +94,16 @@
 //! ```
 
 pub mod actor;
+pub mod approvals;
+pub mod cluster;
+pub mod compaction;
 pub mod context;
+pub mod delegation;
 pub mod registry;
 pub mod rig_engine;
 pub mod rig_tools;
 pub mod sync;
+pub mod tool_runner;
 
 pub use context::{
     build_sorted_context_items, filter_messages_for_context, get_indices_to_truncate,
@@ -116,14 +121,20 @@ mod tests;
 use crate::{
     error::{Error, Result},
     models::chat::{AgentConfig, ChatAttachment, ChatMessage, ChatMessageMetadata, ChatMessageRole, NewChatMessage, DEFAULT_CHAT_MODEL},
+    models::files::ImageContent,
     models::requests::{GrepResult, GlobResult, LsResult},
+    models::sse::{SseEvent, StreamEvent},
     queries, DbConn,
+    services::tokens::TokenCounter,
 };
 use uuid::Uuid;
 
 /// Default token limit for the context window (128k for modern models).
 pub const DEFAULT_CONTEXT_TOKEN_LIMIT: usize = 128000;
 
+/// Max number of logged stream events replayed per query
+const STREAM_EVENT_REPLAY_BATCH: i64 = 500;
+
 /// Max length for tool output before truncation (1MB)
 const MAX_TOOL_OUTPUT_LENGTH: usize = 1_048_576;
 /// Max length for 'write' tool content arg (10MB)
@@ -144,15 +155,23 @@ const CONTENT_PREVIEW_LINES: usize = 20;
 ///
 /// Uses AttachmentManager and HistoryManager for sophisticated management:
 /// - AttachmentManager: Priority-based pruning for file attachments
-/// - HistoryManager: Token estimation and future pruning for conversations
+/// - HistoryManager: Token counting and future pruning for conversations
 #[derive(Debug, Clone)]
 pub struct BuiltContext {
-    /// System persona/instructions for the AI
+    /// System persona/instructions for the AI, with the skill catalog appended
     pub persona: String,
+    /// Persona of the chat's agent, or the built-in one, without the skill catalog
+    pub base_persona: String,
     /// History manager for conversation messages
     pub history: HistoryManager,
     /// Attachment manager for file attachments with priority-based pruning
     pub attachment_manager: AttachmentManager,
+    /// Tokenizer of the chat's model
+    pub token_counter: TokenCounter,
+    /// Context window of the chat's model
+    pub token_limit: usize,
+    /// Images attached to the last message, sent along with the prompt
+    pub images: Vec<AttachedImage>,
 }
 
 /// A file attachment with its content for context.
@@ -163,9 +182,75 @@ pub struct FileAttachment {
     pub content: String,
 }
 
+/// An image attached to a message, sent as an image part to models that accept
+/// images and named in the prompt for those that do not.
+#[derive(Debug, Clone)]
+pub struct AttachedImage {
+    /// Path of the image file, or the name the image was pasted with
+    pub name: String,
+    pub image: ImageContent,
+}
+
 pub struct ChatService;
 
 impl ChatService {
+    /// Creates a virtual `.chat` file under `/chats` with its initial configuration.
+    ///
+    /// The file is created first to obtain its ID, which then becomes part of its
+    /// path (`/chats/chat-{id}.chat`). `app_data` is stored as the initial version.
+    pub async fn create_chat_file(
+        conn: &mut DbConn,
+        workspace_id: Uuid,
+        author_id: Uuid,
+        name: String,
+        app_data: serde_json::Value,
+    ) -> Result<crate::models::files::File> {
+        use crate::models::files::{FileStatus, FileType, NewFile, NewFileVersion};
+
+        // 1. Ensure the /chats folder exists and get its ID
+        let chats_folder_id = crate::services::files::ensure_path_exists(
+            conn,
+            workspace_id,
+            "chats",
+            author_id,
+        ).await?;
+
+        // 2. Create the .chat file identity with TEMPORARY path/slug
+        let chat_file = queries::files::create_file_identity(conn, NewFile {
+            workspace_id,
+            parent_id: chats_folder_id,
+            author_id,
+            file_type: FileType::Chat,
+            status: FileStatus::Ready,
+            name,
+            slug: "chat-temp".to_string(),  // Temporary slug
+            path: "/chats/chat-temp".to_string(),  // Temporary path
+            is_virtual: true,
+            is_remote: false,
+            permission: 600,
+        }).await?;
+
+        // 3. Update the file with its actual ID in the path/slug
+        let correct_path = format!("/chats/chat-{}.chat", chat_file.id);
+        let correct_slug = format!("chat-{}.chat", chat_file.id);
+        let chat_file = queries::files::update_file_path_and_slug(conn, chat_file.id, correct_path, correct_slug).await?;
+
+        // 4. Create initial version with config in app_data
+        let version = queries::files::create_version(conn, NewFileVersion {
+            id: None,
+            file_id: chat_file.id,
+            workspace_id,
+            branch: "main".to_string(),
+            app_data,
+            hash: "initial".to_string(),
+            author_id: Some(author_id),
+        }).await?;
+
+        queries::files::update_latest_version_id(conn, chat_file.id, version.id).await?;
+
+        Ok(chat_file)
+    }
+
     /// Saves a message and appends it to the disk file (Hybrid Persistence).
     /// - DB: Structured storage for O(1) query and context construction.
     /// - Disk: Markdown log for human readability and file system tools.
@@ -178,12 +263,39 @@ impl ChatService {
         workspace_id: Uuid,
         new_msg: NewChatMessage,
     ) -> Result<ChatMessage> {
-        let file_id = new_msg.file_id;
-
         // 1. Insert message into Source of Truth (chat_messages)
         let msg = queries::chat::insert_chat_message(conn, new_msg).await?;
 
         // 2. Append to Disk (File View)
+        Self::append_message_to_file(conn, storage, workspace_id, &msg).await?;
+
+        Ok(msg)
+    }
+
+    /// Saves a message placed at `created_at` in the history (Hybrid Persistence).
+    ///
+    /// The message is ordered by `created_at` in the DB, while the .chat file
+    /// log only ever grows, so it is appended at the end there.
+    pub async fn save_message_at(
+        conn: &mut DbConn,
+        storage: &crate::services::storage::FileStorageService,
+        workspace_id: Uuid,
+        new_msg: NewChatMessage,
+        created_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<ChatMessage> {
+        let msg = queries::chat::insert_chat_message_at(conn, new_msg, created_at).await?;
+        Self::append_message_to_file(conn, storage, workspace_id, &msg).await?;
+
+        Ok(msg)
+    }
+
+    /// Appends a saved message to its .chat file, skipping reasoning messages.
+    async fn append_message_to_file(
+        conn: &mut DbConn,
+        storage: &crate::services::storage::FileStorageService,
+        workspace_id: Uuid,
+        msg: &ChatMessage,
+    ) -> Result<()> {
         // Skip writing reasoning messages to .chat file - they're only for audit/debug in DB
         let is_reasoning = msg.metadata.message_type.as_ref()
             .map(|t| t == "reasoning_complete")
@@ -191,17 +303,17 @@ impl ChatService {
 
         if !is_reasoning {
             // Retrieve file path first
-            let file = queries::files::get_file_by_id(conn, file_id).await?;
+            let file = queries::files::get_file_by_id(conn, msg.file_id).await?;
 
-            let markdown_entry = format_message_as_markdown(&msg);
+            let markdown_entry = format_message_as_markdown(msg);
             // Use full hierarchical path for consistency with file storage
             storage.append_to_file(workspace_id, &file.path, &markdown_entry).await?;
 
-            // 3. Touch file to update timestamp
-            queries::files::touch_file(conn, file_id).await?;
+            // Touch file to update timestamp
+            queries::files::touch_file(conn, msg.file_id).await?;
         }
 
-        Ok(msg)
+        Ok(())
     }
 
     /// Saves a streaming event as a ChatMessage (for audit trail persistence).
@@ -242,6 +354,62 @@ impl ChatService {
         .await
     }
 
+    /// Appends events of a chat's live stream to its stream event log.
+    ///
+    /// Returns the id of each event, in order; pings are not logged and get `None`.
+    /// The ids resume an interrupted SSE stream through `Last-Event-ID`, see
+    /// [`Self::get_stream_events_after`].
+    pub async fn record_stream_events(
+        conn: &mut DbConn,
+        chat_id: Uuid,
+        events: &[SseEvent],
+    ) -> Result<Vec<Option<i64>>> {
+        let logged = events
+            .iter()
+            .filter(|event| !matches!(event, SseEvent::Ping))
+            .map(serde_json::to_value)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Error::Json)?;
+        if logged.is_empty() {
+            return Ok(vec![None; events.len()]);
+        }
+
+        let mut ids = queries::chat::insert_stream_events(conn, chat_id, &logged).await?.into_iter();
+        Ok(events
+            .iter()
+            .map(|event| match event {
+                SseEvent::Ping => None,
+                _ => ids.next(),
+            })
+            .collect())
+    }
+
+    /// Retrieves the logged events of a chat emitted after the event `after_id`.
+    ///
+    /// Events that no longer deserialize, e.g. logged by an older version, are skipped.
+    pub async fn get_stream_events_after(
+        conn: &mut DbConn,
+        chat_id: Uuid,
+        after_id: i64,
+    ) -> Result<Vec<StreamEvent>> {
+        let mut events = Vec::new();
+        let mut cursor = after_id;
+        loop {
+            let rows = queries::chat::get_stream_events_after(conn, chat_id, cursor, STREAM_EVENT_REPLAY_BATCH).await?;
+            let complete = (rows.len() as i64) < STREAM_EVENT_REPLAY_BATCH;
+            for (id, event) in rows {
+                cursor = id;
+                match serde_json::from_value(event) {
+                    Ok(event) => events.push(StreamEvent { id: Some(id), event }),
+                    Err(e) => tracing::warn!(chat_id = %chat_id, id, error = %e, "Skipping undecodable stream event"),
+                }
+            }
+            if complete {
+                return Ok(events);
+            }
+        }
+    }
+
     /// Summarizes tool inputs (arguments) to prevent database bloat.
     /// Truncates long string fields like 'content', 'old_string', 'new_string'.
     pub fn summarize_tool_inputs(
@@ -319,6 +487,9 @@ impl ChatService {
             "exit_plan_mode" => {
                 // No truncation needed - only path argument
             }
+            "plan_update_step" => {
+                // No truncation needed - only path, step number and status
+            }
             "glob" => {
                 // No truncation needed - pattern and path arguments are small
             }
@@ -335,6 +506,49 @@ impl ChatService {
             "cat" => {
                 // No truncation needed for paths array and boolean flags
             }
+            "skill_list" | "skill_activate" => {
+                // No truncation needed - only a query or skill name
+            }
+            "delegate" => {
+                // No truncation needed - at most 4 short task descriptions
+            }
+            "run" => {
+                // Truncate 'command' (the tool rejects commands over its limit, but the
+                // call is still recorded)
+                if let Some(command) = obj.get("command").and_then(|v| v.as_str())
+                    && command.chars().count() > crate::tools::run::MAX_COMMAND_LENGTH
+                {
+                    let preview = crate::utils::safe_preview(command, crate::tools::run::MAX_COMMAND_LENGTH);
+                    obj.insert(
+                        "command".to_string(),
+                        serde_json::json!(format!("{}\n... [truncated, {} characters total]", preview, command.chars().count())),
+                    );
+                }
+            }
+            name if name.starts_with("mcp__") => {
+                // MCP tools have arbitrary schemas, truncate any large string argument,
+                // nested ones included. The preview is capped by characters as well, a
+                // single line can be as large as the whole argument.
+                fn truncate_large_strings(val: &mut serde_json::Value) {
+                    match val {
+                        serde_json::Value::String(text) if text.len() > MAX_EDIT_DIFF_LENGTH => {
+                            let lines: Vec<&str> = text.lines().collect();
+                            let preview = lines.iter().take(CONTENT_PREVIEW_LINES).cloned().collect::<Vec<_>>().join("\n");
+                            let preview = crate::utils::safe_preview(&preview, MAX_EDIT_DIFF_LENGTH);
+                            *val = serde_json::json!(format!(
+                                "{}\n... [truncated, {} lines, {} characters total]",
+                                preview,
+                                lines.len(),
+                                text.chars().count()
+                            ));
+                        }
+                        serde_json::Value::Array(items) => items.iter_mut().for_each(truncate_large_strings),
+                        serde_json::Value::Object(fields) => fields.values_mut().for_each(truncate_large_strings),
+                        _ => {}
+                    }
+                }
+                obj.values_mut().for_each(truncate_large_strings);
+            }
             unknown_tool => {
                 // ERROR-level: Unknown tool can cause database bloat
                 // Tool was added to ToolExecutor but truncate logic not added here
@@ -613,6 +827,8 @@ impl ChatService {
                 previous_response_id: None,
                 mode: "plan".to_string(),
                 plan_file: None,
+                parent_chat_id: None,
+                allowed_tools: None,
             });
 
         // 2. Update the model field
@@ -661,6 +877,8 @@ impl ChatService {
                 previous_response_id: None,
                 mode: "plan".to_string(),
                 plan_file: None,
+                parent_chat_id: None,
+                allowed_tools: None,
             });
 
         // 2. Update the mode and plan_file fields
@@ -768,6 +986,36 @@ impl ChatService {
         Ok(Some(parsed.frontmatter))
     }
 
+    /// Gets the persona for a chat based on its mode.
+    ///
+    /// This extracts the mode from the chat's app_data and determines the appropriate
+    /// persona (role) for the agent.
+    pub async fn get_chat_persona(conn: &mut DbConn, chat_id: Uuid) -> Result<String> {
+        // Get the latest version to determine the mode from app_data
+        let latest_version = queries::files::get_latest_version(conn, chat_id).await?;
+
+        // Sub-agent chats started by the delegate tool keep the delegate persona
+        if latest_version.app_data.get("parent_chat_id").is_some_and(|v| !v.is_null()) {
+            return Ok(crate::agents::get_persona(Some("delegate"), None, None));
+        }
+
+        // Extract mode from app_data, default to "plan" if not set
+        let mode = latest_version
+            .app_data
+            .get("mode")
+            .and_then(|v| v.as_str())
+            .unwrap_or("plan");
+
+        // Determine role from mode
+        let role = match mode {
+            "build" => Some("builder"),
+            "plan" => Some("planner"),
+            _ => None,
+        };
+
+        Ok(crate::agents::get_persona(role, Some(mode), None))
+    }
+
     /// Retrieves the full chat session including configuration and message history.
     pub async fn get_chat_session(
         conn: &mut DbConn,
@@ -800,6 +1048,8 @@ impl ChatService {
                     previous_response_id: None,
                     mode: "plan".to_string(),
                     plan_file: None,
+                    parent_chat_id: None,
+                    allowed_tools: None,
                 })
             } else {
                  crate::models::chat::AgentConfig {
@@ -810,6 +1060,8 @@ impl ChatService {
                     previous_response_id: None,
                     mode: "plan".to_string(),
                     plan_file: None,
+                    parent_chat_id: None,
+                    allowed_tools: None,
                 }
             }
         } else {
@@ -821,6 +1073,8 @@ impl ChatService {
                 previous_response_id: None,
                 mode: "plan".to_string(),
                 plan_file: None,
+                parent_chat_id: None,
+                allowed_tools: None,
             }
         };
 
@@ -844,7 +1098,11 @@ impl ChatService {
 
     /// Builds the structured context for a chat session.
     ///
+    /// Tokens are counted with the tokenizer of the chat's model, and the token
+    /// limit is the model's context window when `ai_models` knows it.
+    ///
     /// # Arguments
+    /// * `default_context_token_limit` - Token limit for models without a known context window.
     /// * `exclude_last_message` - If true, excludes the last message (used for AI context where
     ///   last message is the user's prompt). If false, includes all messages (used for Context UI).
     pub async fn build_context(
@@ -858,26 +1116,138 @@ impl ChatService {
     ) -> Result<BuiltContext> {
         // 1. Load Session Identity & History
         let messages = queries::chat::get_messages_by_file_id(conn, workspace_id, chat_file_id).await?;
+        // Older turns covered by a compaction summary stay out of the context
+        let compacted = compaction::compacted_history(messages.clone());
 
-        // 2. Hydrate Persona
-        let persona = default_persona.to_string();
+        let app_data = queries::files::get_latest_version(conn, chat_file_id)
+            .await
+            .map(|version| version.app_data)
+            .unwrap_or_default();
+
+        // Budget with the chat model's tokenizer and context window
+        let model = app_data
+            .get("model")
+            .and_then(|model| model.as_str())
+            .unwrap_or(DEFAULT_CHAT_MODEL);
+        let token_counter = TokenCounter::for_model(model);
+        let token_limit = Self::get_model_context_window(conn, model)
+            .await
+            .unwrap_or(default_context_token_limit);
+
+        // 2. Hydrate Persona: the chat's agent file replaces the built-in persona,
+        // then the workspace skill catalog is appended
+        let agent_id = app_data
+            .get("agent_id")
+            .and_then(|agent_id| agent_id.as_str())
+            .and_then(|agent_id| Uuid::parse_str(agent_id).ok());
+        let chat_agent = match agent_id {
+            Some(agent_id) => {
+                crate::services::agent_files::load_chat_agent(conn, storage, workspace_id, chat_file_id, agent_id).await
+            }
+            None => None,
+        };
+        let base_persona = chat_agent
+            .as_ref()
+            .map_or(default_persona, |agent| agent.system_prompt.as_str())
+            .to_string();
+        let persona = crate::services::skills::persona_with_skill_catalog(
+            conn, storage, workspace_id, &base_persona,
+        ).await;
 
         // 3. Extract history (optionally exclude last message which is the prompt for AI context)
-        let history_messages = if exclude_last_message && messages.len() > 1 {
-            messages[..messages.len() - 1].to_vec()
+        let history_messages = if exclude_last_message && compacted.len() > 1 {
+            compacted[..compacted.len() - 1].to_vec()
         } else if exclude_last_message {
             Vec::new()
         } else {
-            messages.clone()
+            compacted
         };
-        let history = HistoryManager::new(history_messages);
+        let history = HistoryManager::new(history_messages).with_token_counter(token_counter);
 
         // 4. Hydrate Attachments using AttachmentManager
         let mut attachment_manager = AttachmentManager::new();
 
+        // Pinned skills stay active for the whole conversation, so collect them from
+        // every message. Keyed by skill ID, the first pin wins and its timestamp keeps
+        // the skill at a stable, cacheable position in the history.
+        for msg in &messages {
+            for attachment in &msg.metadata.0.attachments {
+                let ChatAttachment::Skill { skill_id, name } = attachment else {
+                    continue;
+                };
+                let key = AttachmentKey::ActiveSkill(*skill_id);
+                if attachment_manager.map.contains_key(&key) {
+                    continue;
+                }
+
+                match crate::services::skills::get_skill(conn, storage, workspace_id, *skill_id).await {
+                    Ok(skill) => {
+                        let content = crate::services::skills::render_pinned_skill(&skill);
+                        let tokens = token_counter.count(&content);
+                        attachment_manager.add_fragment(
+                            key,
+                            AttachmentValue {
+                                content,
+                                priority: PRIORITY_ESSENTIAL,
+                                tokens,
+                                is_essential: true,
+                                created_at: msg.created_at,
+                                updated_at: Some(skill.updated_at),
+                            },
+                        );
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            chat_id = %chat_file_id,
+                            skill_id = %skill_id,
+                            skill_name = %name,
+                            error = %e,
+                            "Pinned skill is no longer available"
+                        );
+                    }
+                }
+            }
+        }
+
+        let mut images = Vec::new();
         if let Some(last_msg) = messages.last() {
             let metadata = &last_msg.metadata.0;
             for attachment in &metadata.attachments {
+                if let ChatAttachment::Image { name, image } = attachment {
+                    images.push(AttachedImage {
+                        name: name.clone().unwrap_or_else(|| "pasted image".to_string()),
+                        image: image.clone(),
+                    });
+                    continue;
+                }
+
+                if let ChatAttachment::Url { url, title, snapshot_file_id } = attachment {
+                    let (content, updated_at) = Self::load_web_page(
+                        conn, storage, workspace_id, url, title.as_deref(), *snapshot_file_id,
+                    ).await;
+                    let tokens = token_counter.count(&content);
+                    attachment_manager.add_fragment(
+                        AttachmentKey::WebPage(crate::services::web_pages::page_id(url)),
+                        AttachmentValue {
+                            content,
+                            priority: PRIORITY_MEDIUM,
+                            tokens,
+                            is_essential: false,
+                            created_at: chrono::Utc::now(),
+                            updated_at,
+                        },
+                    );
+                    continue;
+                }
+
+                // Image files are sent as images rather than rendered as text
+                if let ChatAttachment::File { file_id, .. } = attachment
+                    && let Some(image) = Self::load_image_file(conn, storage, workspace_id, *file_id).await
+                {
+                    images.push(image);
+                    continue;
+                }
+
                 if let ChatAttachment::File { file_id, .. } = attachment
                     && let Ok(file_with_content) =
                         crate::services::files::get_file_with_content(conn, storage, *file_id).await
@@ -886,8 +1256,7 @@ impl ChatService {
                     if file_with_content.file.workspace_id == workspace_id {
                         let content = file_with_content.content.to_string();
 
-                        // Estimate tokens (rough approximation: 4 chars per token)
-                        let estimated_tokens = content.len() / ESTIMATED_CHARS_PER_TOKEN;
+                        let tokens = token_counter.count(&content);
 
                         // Get the file's updated_at timestamp for cache optimization
                         let source_modified_at = Some(file_with_content.file.updated_at);
@@ -899,7 +1268,7 @@ impl ChatService {
                             AttachmentValue {
                                 content,
                                 priority: PRIORITY_MEDIUM,
-                                tokens: estimated_tokens,
+                                tokens,
                                 is_essential: false,
                                 created_at: chrono::Utc::now(),
                                 updated_at: source_modified_at,
@@ -910,19 +1279,87 @@ impl ChatService {
             }
         }
 
-        // 5. Optimize attachments for token limit
-        attachment_manager.optimize_for_limit(default_context_token_limit);
+        // 5. Optimize attachments for what the persona and history leave of the token limit
+        let attachment_budget = token_limit
+            .saturating_sub(token_counter.count(&persona))
+            .saturating_sub(history.estimate_tokens());
+        attachment_manager.optimize_for_limit(attachment_budget);
 
         // 6. Sort by position for consistent rendering
         attachment_manager.sort_by_position();
 
         Ok(BuiltContext {
             persona,
+            base_persona,
             history,
             attachment_manager,
+            token_counter,
+            token_limit,
+            images,
         })
     }
 
+    /// Loads an attached web page for the context, from its snapshot if it has one
+    ///
+    /// A page that cannot be fetched is still listed, so the model can tell the
+    /// user instead of answering as if it had read it. Returns the content and
+    /// when the page was fetched or its snapshot last changed.
+    async fn load_web_page(
+        conn: &mut DbConn,
+        storage: &crate::services::storage::FileStorageService,
+        workspace_id: Uuid,
+        url: &str,
+        title: Option<&str>,
+        snapshot_file_id: Option<Uuid>,
+    ) -> (String, Option<chrono::DateTime<chrono::Utc>>) {
+        if let Some(file_id) = snapshot_file_id
+            && let Ok(snapshot) = crate::services::files::get_file_with_content(conn, storage, file_id).await
+            && snapshot.file.workspace_id == workspace_id
+        {
+            let mut content = format!("URL: {}\n", url);
+            if let Some(title) = title {
+                content.push_str(&format!("Title: {}\n", title));
+            }
+            let text = match &snapshot.content {
+                serde_json::Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            content.push_str(&format!("Snapshot: {}\n---\n{}", snapshot.file.path, text));
+            return (content, Some(snapshot.file.updated_at));
+        }
+
+        match crate::services::web_pages::fetch_page(workspace_id, url).await {
+            Ok(page) => (page.render(), Some(page.fetched_at)),
+            Err(e) => {
+                tracing::warn!(url = %url, error = %e, "Attached web page could not be fetched");
+                (format!("URL: {}\nThe page could not be fetched: {}", url, e), None)
+            }
+        }
+    }
+
+    /// Loads an attached workspace file as an image, `None` if it is not one
+    async fn load_image_file(
+        conn: &mut DbConn,
+        storage: &crate::services::storage::FileStorageService,
+        workspace_id: Uuid,
+        file_id: Uuid,
+    ) -> Option<AttachedImage> {
+        let file = queries::files::get_file_by_id(conn, file_id).await.ok()?;
+        if file.workspace_id != workspace_id || file.is_remote {
+            return None;
+        }
+        let bytes = storage.read_file(workspace_id, &file.path).await.ok()?;
+        crate::services::images::detect_media_type(&bytes)?;
+
+        match crate::services::images::prepare_image_blocking(bytes).await {
+            Ok(image) => Some(AttachedImage { name: file.path, image }),
+            Err(e) => {
+                tracing::warn!(file_id = %file_id, error = %e, "Attached image could not be prepared");
+                None
+            }
+        }
+    }
+
     // ========================================================================
     // Context API Methods
     // ========================================================================
@@ -930,6 +1367,50 @@ impl ChatService {
     /// Content preview length for context API responses
     const CONTENT_PREVIEW_LENGTH: usize = 200;
 
+    /// Returns the step progress of the chat's active plan.
+    ///
+    /// The active plan is the `plan_file` of the chat's AgentConfig, set when
+    /// the chat switches to Build Mode.
+    pub async fn get_plan_progress(
+        conn: &mut DbConn,
+        storage: &crate::services::storage::FileStorageService,
+        workspace_id: Uuid,
+        chat_file_id: Uuid,
+    ) -> Result<crate::models::chat::PlanProgressResponse> {
+        let file = queries::files::get_file_by_id(conn, chat_file_id).await?;
+        if file.workspace_id != workspace_id || !matches!(file.file_type, crate::models::files::FileType::Chat) {
+            return Err(Error::NotFound(format!("Chat not found: {}", chat_file_id)));
+        }
+
+        let version = queries::files::get_latest_version(conn, chat_file_id).await?;
+        let agent_config: AgentConfig = serde_json::from_value(version.app_data)
+            .map_err(|_| Error::NotFound("Chat has no configuration".into()))?;
+        let plan_file = agent_config
+            .plan_file
+            .ok_or_else(|| Error::NotFound("Chat has no active plan".into()))?;
+
+        let plan = queries::files::get_file_by_path(conn, workspace_id, &plan_file)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Plan not found: {}", plan_file)))?;
+        let plan = crate::services::files::get_file_with_content(conn, storage, plan.id).await?;
+        let content = match &plan.content {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+
+        let (metadata, _) = crate::utils::parse_frontmatter(&content);
+        let steps = crate::utils::parse_plan_steps(&content);
+        let progress = crate::utils::PlanProgress::from_steps(&steps);
+
+        Ok(crate::models::chat::PlanProgressResponse {
+            chat_id: chat_file_id,
+            plan_file,
+            metadata,
+            steps,
+            progress,
+        })
+    }
+
     /// Get detailed context information for debugging/inspection.
     ///
     /// Returns structured information about everything sent to the AI,
@@ -943,7 +1424,7 @@ impl ChatService {
         default_persona: &str,
         fallback_token_limit: usize,
     ) -> Result<crate::models::chat::ChatContextResponse> {
-        // 1. Get session for model/mode info first (needed to determine token limit)
+        // 1. Get session for model/mode info first
         let _file = queries::files::get_file_by_id(conn, chat_file_id).await?;
 
         let agent_config: crate::models::chat::AgentConfig = match queries::files::get_latest_version(conn, chat_file_id).await {
@@ -953,26 +1434,23 @@ impl ChatService {
             }
         };
 
-        // 2. Look up model's context window from database
-        let token_limit = Self::get_model_context_window(conn, &agent_config.model)
-            .await
-            .unwrap_or(fallback_token_limit);
-
-        // 3. Build context - include ALL messages for Context UI (no exclusion)
+        // 2. Build context - include ALL messages for Context UI (no exclusion).
+        // Tokens are counted with the model's tokenizer, the limit is its context window.
         let context = Self::build_context(
-            conn, storage, workspace_id, chat_file_id, default_persona, token_limit,
+            conn, storage, workspace_id, chat_file_id, default_persona, fallback_token_limit,
             false, // exclude_last_message=false for Context UI to show all messages
         ).await?;
+        let counter = context.token_counter;
 
-        // 4. Build each section
-        let system_prompt = Self::build_system_prompt_section(&context.persona, &agent_config.mode);
-        let history = Self::build_history_section(&context.history.messages, &context.attachment_manager);
-        let tools = Self::build_tools_section();
+        // 3. Build each section
+        let system_prompt = Self::build_system_prompt_section(&context.persona, &agent_config.mode, counter);
+        let history = Self::build_history_section(&context.history.messages, &context.attachment_manager, counter);
+        let tools = Self::build_tools_section(counter);
         let attachments = Self::build_attachments_section(&context.attachment_manager);
 
-        // 5. Build summary
+        // 4. Build summary
         let summary = Self::build_context_summary(
-            &system_prompt, &history, &tools, &attachments, &agent_config.model, token_limit
+            &system_prompt, &history, &tools, &attachments, &agent_config.model, context.token_limit, counter
         );
 
         Ok(crate::models::chat::ChatContextResponse {
@@ -986,7 +1464,7 @@ impl ChatService {
 
     /// Get the context window for a model from the database.
     /// Model string format: "provider:model_name" (e.g., "openai:gpt-4o")
-    async fn get_model_context_window(conn: &mut DbConn, model: &str) -> Option<usize> {
+    pub(crate) async fn get_model_context_window(conn: &mut DbConn, model: &str) -> Option<usize> {
         let (provider, model_name) = model.split_once(':')?;
         let ai_model = queries::ai_models::get_model_by_provider_and_name_conn(
             conn, provider, model_name
@@ -994,7 +1472,21 @@ impl ChatService {
         ai_model.context_window.map(|cw| cw as usize)
     }
 
-    fn build_system_prompt_section(persona: &str, mode: &str) -> crate::models::chat::SystemPromptSection {
+    /// Whether `ai_models` marks a model as accepting images.
+    /// Models it does not know are assumed not to.
+    pub(crate) async fn model_supports_vision(conn: &mut DbConn, model: &crate::providers::ModelIdentifier) -> bool {
+        queries::ai_models::get_model_by_provider_and_name_conn(conn, model.provider.as_str(), &model.model)
+            .await
+            .ok()
+            .flatten()
+            .is_some_and(|ai_model| ai_model.supports_vision)
+    }
+
+    fn build_system_prompt_section(
+        persona: &str,
+        mode: &str,
+        counter: TokenCounter,
+    ) -> crate::models::chat::SystemPromptSection {
         let persona_type = if persona.contains("Planner") { "planner" }
             else if persona.contains("Builder") { "builder" }
             else { "assistant" };
@@ -1002,7 +1494,7 @@ impl ChatService {
         crate::models::chat::SystemPromptSection {
             content: persona.to_string(),
             char_count: persona.len(),
-            token_count: persona.len() / ESTIMATED_CHARS_PER_TOKEN,
+            token_count: counter.count(persona),
             persona_type: persona_type.to_string(),
             mode: mode.to_string(),
         }
@@ -1011,6 +1503,7 @@ impl ChatService {
     fn build_history_section(
         messages: &[ChatMessage],
         attachment_manager: &AttachmentManager,
+        counter: TokenCounter,
     ) -> crate::models::chat::HistorySection {
         // Use centralized context building from context.rs (single source of truth)
         // Pass None for render_fn since Context UI doesn't need rendered attachments
@@ -1061,7 +1554,7 @@ impl ChatService {
                 }
 
                 // Calculate token count from truncated content (not original)
-                let truncated_length = content.len();
+                let token_count = counter.count(&content);
 
                 let preview = if content.len() > Self::CONTENT_PREVIEW_LENGTH {
                     let truncate_at = truncate_at_char_boundary(&content, Self::CONTENT_PREVIEW_LENGTH);
@@ -1074,7 +1567,7 @@ impl ChatService {
                     role: msg.role.to_string().to_lowercase(),
                     content_preview: preview,
                     content_length: original_length,
-                    token_count: truncated_length / ESTIMATED_CHARS_PER_TOKEN,
+                    token_count,
                     metadata: Some(crate::models::chat::HistoryMessageMetadata {
                         message_type: msg.metadata.message_type.clone(),
                         reasoning_id: msg.metadata.reasoning_id.clone(),
@@ -1093,7 +1586,7 @@ impl ChatService {
         }
     }
 
-    fn build_tools_section() -> crate::models::chat::ToolsSection {
+    fn build_tools_section(counter: TokenCounter) -> crate::models::chat::ToolsSection {
         // Get all tool definitions
         let tools = crate::tools::get_all_tool_definitions();
         let schema_json = serde_json::to_string(&tools).unwrap_or_default();
@@ -1102,7 +1595,7 @@ impl ChatService {
         crate::models::chat::ToolsSection {
             tools,
             tool_count,
-            estimated_schema_tokens: schema_json.len() / ESTIMATED_CHARS_PER_TOKEN,
+            estimated_schema_tokens: counter.count(&schema_json),
         }
     }
 
@@ -1110,6 +1603,7 @@ impl ChatService {
         let attachments: Vec<crate::models::chat::AttachmentInfo> = manager.map.iter().map(|(key, value)| {
             let (attachment_type, id) = match key {
                 AttachmentKey::WorkspaceFile(fid) => ("workspace_file", *fid),
+                AttachmentKey::WebPage(pid) => ("web_page", *pid),
                 AttachmentKey::ActiveSkill(sid) => ("skill", *sid),
                 AttachmentKey::SystemPersona => ("system_persona", Uuid::nil()),
                 AttachmentKey::Environment => ("environment", Uuid::nil()),
@@ -1150,6 +1644,7 @@ impl ChatService {
         attachments: &crate::models::chat::AttachmentsSection,
         model: &str,
         token_limit: usize,
+        counter: TokenCounter,
     ) -> crate::models::chat::ContextSummary {
         let breakdown = crate::models::chat::TokenBreakdown {
             system_prompt_tokens: system_prompt.token_count,
@@ -1170,6 +1665,7 @@ impl ChatService {
             utilization_percent: utilization,
             model: model.to_string(),
             token_limit,
+            tokenizer: counter.encoding().name().to_string(),
             breakdown,
         }
     }
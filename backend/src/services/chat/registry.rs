@@ -1,5 +1,10 @@
+use crate::database::DbPool;
 use crate::error::Result;
-use crate::models::sse::SseEvent;
+use crate::models::sse::{SseEvent, StreamEvent};
+use crate::DbConn;
+use super::approvals::ToolApprovals;
+use super::cluster::{ClusterBus, ClusterMessage, RemoteCommand};
+use super::ChatService;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
@@ -8,6 +13,12 @@ use uuid::Uuid;
 
 const EVENT_BUS_CAPACITY: usize = 1024;
 
+/// Max number of events logged in one write
+const EVENT_LOG_BATCH_SIZE: usize = 256;
+
+/// How long the relay of a stopped actor keeps forwarding events still being logged
+const RELAY_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
 #[derive(Debug, Clone)]
 pub enum AgentCommand {
     ProcessInteraction { user_id: Uuid },
@@ -30,44 +41,103 @@ pub struct AgentHandle {
 }
 
 pub struct AgentRegistry {
+    /// Identifies this backend instance in chat leases
+    pub node_id: Uuid,
     pub active_agents: scc::HashMap<Uuid, AgentHandle>,
     pub event_buses: scc::HashMap<Uuid, broadcast::Sender<SseEvent>>,
+    /// Events of each event bus once logged, with their ids
+    stream_buses: scc::HashMap<Uuid, broadcast::Sender<StreamEvent>>,
     /// Track active cancellation tokens for streams
     /// This allows STOP to cancel streams even after actor exits
     pub active_cancellations: Arc<Mutex<HashMap<Uuid, CancellationToken>>>,
+    /// Events of chats whose actors run on other instances
+    remote_event_buses: scc::HashMap<Uuid, broadcast::Sender<StreamEvent>>,
+    /// Stream event log; without it events carry no ids and cannot be replayed
+    event_log: Option<DbPool>,
+    /// Channel to the other instances, when running as a cluster
+    cluster: Option<ClusterBus>,
+    /// Tool calls of local actors waiting for the user's approval
+    pub approvals: Arc<ToolApprovals>,
 }
 
 impl AgentRegistry {
+    /// Registry of a single-instance deployment that keeps no event log
+    ///
+    /// The node id is fixed, so a restarted instance keeps the leases of its chats.
     pub fn new() -> Self {
+        Self::with_node(Uuid::nil(), None, None)
+    }
+
+    /// Registry of a single-instance deployment logging chat events for replay
+    pub fn with_event_log(pool: DbPool) -> Self {
+        Self::with_node(Uuid::nil(), Some(pool), None)
+    }
+
+    /// Registry of one instance in a multi-instance deployment
+    pub fn with_cluster(pool: DbPool) -> Self {
+        let node_id = Uuid::now_v7();
+        Self::with_node(node_id, Some(pool.clone()), Some(ClusterBus::new(pool, node_id)))
+    }
+
+    fn with_node(node_id: Uuid, event_log: Option<DbPool>, cluster: Option<ClusterBus>) -> Self {
         Self {
+            node_id,
             active_agents: scc::HashMap::new(),
             event_buses: scc::HashMap::new(),
+            stream_buses: scc::HashMap::new(),
             active_cancellations: Arc::new(Mutex::new(HashMap::new())),
+            remote_event_buses: scc::HashMap::new(),
+            event_log,
+            cluster,
+            approvals: Arc::new(ToolApprovals::default()),
         }
     }
 
+    pub fn is_clustered(&self) -> bool {
+        self.cluster.is_some()
+    }
+
     /// Gets or creates a persistent broadcast bus for a chat.
     /// This bus survives actor restarts to keep SSE connections stable.
+    ///
+    /// Events sent on the bus are logged and passed on to the chat's stream
+    /// subscribers, see [`Self::subscribe_stream`].
     pub async fn get_or_create_bus(&self, chat_id: Uuid) -> broadcast::Sender<SseEvent> {
-        if let Some(bus) = self.event_buses.read_async(&chat_id, |_, b| b.clone()).await {
-            tracing::debug!(
-                chat_id = %chat_id,
-                receivers = bus.receiver_count(),
-                "[AgentRegistry] Reusing existing event bus"
-            );
-            bus
-        } else {
-            tracing::info!(
-                chat_id = %chat_id,
-                capacity = EVENT_BUS_CAPACITY,
-                "[AgentRegistry] CREATING new persistent event bus"
-            );
-            let (tx, _) = broadcast::channel(EVENT_BUS_CAPACITY);
-            let _ = self.event_buses.insert_async(chat_id, tx.clone()).await;
-            tx
+        match self.event_buses.entry_async(chat_id).await {
+            scc::hash_map::Entry::Occupied(entry) => {
+                let bus = entry.get().clone();
+                tracing::debug!(
+                    chat_id = %chat_id,
+                    receivers = bus.receiver_count(),
+                    "[AgentRegistry] Reusing existing event bus"
+                );
+                bus
+            }
+            scc::hash_map::Entry::Vacant(entry) => {
+                tracing::info!(
+                    chat_id = %chat_id,
+                    capacity = EVENT_BUS_CAPACITY,
+                    "[AgentRegistry] CREATING new persistent event bus"
+                );
+                let (tx, rx) = broadcast::channel(EVENT_BUS_CAPACITY);
+                let (stream_tx, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+                let _ = self.stream_buses.insert_async(chat_id, stream_tx.clone()).await;
+                tokio::spawn(record_events(chat_id, rx, stream_tx, self.event_log.clone()));
+                entry.insert_entry(tx.clone());
+                tx
+            }
         }
     }
 
+    /// Subscribes to a chat's events as they are logged, with their ids
+    pub async fn subscribe_stream(&self, chat_id: Uuid) -> broadcast::Receiver<StreamEvent> {
+        self.get_or_create_bus(chat_id).await;
+        self.stream_buses
+            .read_async(&chat_id, |_, bus| bus.subscribe())
+            .await
+            .expect("stream bus is created with the event bus")
+    }
+
     pub async fn get_handle(&self, chat_id: &Uuid) -> Option<AgentHandle> {
         let handle = self.active_agents
             .read_async(chat_id, |_, h| h.clone())
@@ -133,6 +203,178 @@ impl AgentRegistry {
     }
 }
 
+impl AgentRegistry {
+    /// Gets the other instance running a chat's actor, if any
+    ///
+    /// Always `None` outside a cluster.
+    pub async fn remote_owner(&self, conn: &mut DbConn, chat_id: Uuid) -> Result<Option<Uuid>> {
+        if self.cluster.is_none() {
+            return Ok(None);
+        }
+        let owner = crate::queries::agent_sessions::get_chat_lease_owner(conn, chat_id).await?;
+        Ok(owner.filter(|owner| *owner != self.node_id))
+    }
+
+    /// Forwards a command to the instance running a chat's actor
+    pub async fn send_remote_command(&self, node_id: Uuid, chat_id: Uuid, command: RemoteCommand) -> Result<()> {
+        let Some(cluster) = &self.cluster else {
+            return Err(crate::error::Error::Internal("Not running as a cluster".into()));
+        };
+        tracing::info!(
+            chat_id = %chat_id,
+            owner = %node_id,
+            command = ?command,
+            "[AgentRegistry] Forwarding command to owning instance"
+        );
+        cluster
+            .publish(&ClusterMessage::Command { node_id, chat_id, command })
+            .await
+    }
+
+    /// Sends an event to the SSE clients of a chat on every instance
+    pub async fn send_event(&self, chat_id: Uuid, event: SseEvent) {
+        let _ = self.get_or_create_bus(chat_id).await.send(event.clone());
+
+        // A local actor's events are relayed already
+        if self.get_handle(&chat_id).await.is_none() {
+            self.publish_event(chat_id, event).await;
+        }
+    }
+
+    /// Publishes an event to the SSE clients of a chat on the other instances
+    pub async fn publish_event(&self, chat_id: Uuid, event: SseEvent) {
+        if let Some(cluster) = &self.cluster
+            && let Err(e) = cluster.publish(&ClusterMessage::Event { chat_id, id: None, event }).await
+        {
+            tracing::warn!(chat_id = %chat_id, error = %e, "[AgentRegistry] Failed to publish event");
+        }
+    }
+
+    /// Publishes the logged events of a local actor to the other instances until
+    /// the returned token is cancelled
+    ///
+    /// Returns `None` outside a cluster.
+    pub async fn relay_events(&self, chat_id: Uuid) -> Option<CancellationToken> {
+        let cluster = self.cluster.clone()?;
+        let mut events = self.subscribe_stream(chat_id).await;
+        let token = CancellationToken::new();
+        let stop = token.clone();
+
+        tokio::spawn(async move {
+            let mut stopping = false;
+            loop {
+                let received = if stopping {
+                    // The actor's last events may still be being logged
+                    match tokio::time::timeout(RELAY_DRAIN_TIMEOUT, events.recv()).await {
+                        Ok(received) => received,
+                        Err(_) => break,
+                    }
+                } else {
+                    tokio::select! {
+                        _ = stop.cancelled() => {
+                            stopping = true;
+                            continue;
+                        }
+                        received = events.recv() => received,
+                    }
+                };
+                let StreamEvent { id, event } = match received {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(chat_id = %chat_id, skipped, "[AgentRegistry] Relay lagged, events lost");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                // Every instance sends its own pings
+                if matches!(event, SseEvent::Ping) {
+                    continue;
+                }
+                if let Err(e) = cluster.publish(&ClusterMessage::Event { chat_id, id, event }).await {
+                    tracing::warn!(chat_id = %chat_id, error = %e, "[AgentRegistry] Failed to relay event");
+                }
+            }
+        });
+
+        Some(token)
+    }
+
+    /// Subscribes to the events of a chat published by other instances
+    ///
+    /// Returns `None` outside a cluster.
+    pub async fn subscribe_remote(&self, chat_id: Uuid) -> Option<broadcast::Receiver<StreamEvent>> {
+        self.cluster.as_ref()?;
+        let bus = self
+            .remote_event_buses
+            .entry_async(chat_id)
+            .await
+            .or_insert_with(|| broadcast::channel(EVENT_BUS_CAPACITY).0)
+            .get()
+            .clone();
+        Some(bus.subscribe())
+    }
+
+    /// Hands an event published by another instance to this instance's SSE clients
+    pub async fn deliver_remote_event(&self, chat_id: Uuid, event: StreamEvent) {
+        if let Some(bus) = self.remote_event_buses.read_async(&chat_id, |_, bus| bus.clone()).await {
+            let _ = bus.send(event);
+        }
+    }
+}
+
+/// Logs the events of a chat's event bus and passes them on with their ids
+///
+/// Events are written in batches of whatever arrived meanwhile, so logging keeps
+/// up with token streaming. When logging fails the events are passed on without
+/// ids rather than dropped.
+async fn record_events(
+    chat_id: Uuid,
+    mut events: broadcast::Receiver<SseEvent>,
+    stream_tx: broadcast::Sender<StreamEvent>,
+    event_log: Option<DbPool>,
+) {
+    loop {
+        let first = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!(chat_id = %chat_id, skipped, "[AgentRegistry] Event log lagged, events lost");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        let mut batch = vec![first];
+        while batch.len() < EVENT_LOG_BATCH_SIZE {
+            match events.try_recv() {
+                Ok(event) => batch.push(event),
+                Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+
+        let ids = match &event_log {
+            Some(pool) => match log_events(pool, chat_id, &batch).await {
+                Ok(ids) => ids,
+                Err(e) => {
+                    tracing::warn!(chat_id = %chat_id, error = %e, "[AgentRegistry] Failed to log events");
+                    vec![None; batch.len()]
+                }
+            },
+            None => vec![None; batch.len()],
+        };
+
+        for (event, id) in batch.into_iter().zip(ids) {
+            let _ = stream_tx.send(StreamEvent { id, event });
+        }
+    }
+}
+
+async fn log_events(pool: &DbPool, chat_id: Uuid, events: &[SseEvent]) -> Result<Vec<Option<i64>>> {
+    let mut conn = pool.acquire().await.map_err(crate::error::Error::Sqlx)?;
+    ChatService::record_stream_events(&mut conn, chat_id, events).await
+}
+
 impl Default for AgentRegistry {
     fn default() -> Self {
         Self::new()
@@ -1,23 +1,34 @@
 use crate::models::chat::{ChatMessage, ChatMessageRole, ChatSession};
+use crate::models::files::ImageContent;
 use crate::services::chat::rig_tools::{
     RigEditTool, RigGrepTool, RigGlobTool, RigFileInfoTool, RigLsTool, RigMkdirTool, RigMvTool, RigReadTool,
     RigRmTool, RigTouchTool, RigWriteTool, RigReadMultipleFilesTool, RigFindTool, RigCatTool,
     RigAskUserTool, RigExitPlanModeTool,
-    RigPlanWriteTool, RigPlanReadTool, RigPlanEditTool, RigPlanListTool,
+    RigPlanWriteTool, RigPlanReadTool, RigPlanEditTool, RigPlanListTool, RigPlanUpdateStepTool,
     RigMemorySetTool, RigMemoryGetTool, RigMemorySearchTool, RigMemoryDeleteTool, RigMemoryListTool,
     RigWebFetchTool, RigWebSearchTool,
+    RigSkillListTool, RigSkillActivateTool,
+    RigDelegateTool, RigRunTool, RigMcpTool,
 };
+use crate::services::chat::AttachedImage;
+use crate::services::chat::compaction::COMPACTION_SUMMARY_MESSAGE_TYPE;
+use crate::services::chat::delegation::DelegationContext;
+use crate::services::chat::tool_runner::AgentTools;
+use crate::services::mcp::McpClientPool;
 use crate::services::chat::context::{
     build_sorted_context_items, get_indices_to_truncate, render_attachment_for_ai,
     truncate_tool_output, AttachmentManager, ContextItem,
 };
 use crate::services::storage::FileStorageService;
-use crate::providers::{AiProvider, Agent, ModelIdentifier, OpenAiProvider, OpenRouterProvider};
+use crate::providers::mock::RecordingCompletionModel;
+use crate::providers::{AiProvider, Agent, MockProvider, ModelIdentifier, OpenAiProvider, OpenRouterProvider};
 use crate::config::AiConfig;
 use crate::DbPool;
 use crate::error::{Error, Result};
 use rig::client::CompletionClient;
 use rig::completion::Message;
+use rig::message::{ImageDetail, ImageMediaType, MimeType, UserContent};
+use rig::tool::ToolDyn;
 use std::sync::Arc;
 use std::str::FromStr;
 use uuid::Uuid;
@@ -26,237 +37,350 @@ use uuid::Uuid;
 /// This prevents infinite loops while allowing complex multi-step workflows.
 const DEFAULT_MAX_TOOL_ITERATIONS: usize = 100;
 
-/// Add all Rig tools to an agent builder
-fn add_tools_to_agent<M>(
-    builder: rig::agent::AgentBuilder<M>,
+/// Persona, tools and settings of a chat's agent, the same for every provider
+struct AgentSetup {
+    persona: String,
+    tools: AgentTools,
+    temperature: Option<f32>,
+}
+
+impl AgentSetup {
+    /// Builds the agent around a provider's model
+    fn build<M>(&self, model: M, additional_params: Option<serde_json::Value>) -> rig::agent::Agent<M>
+    where
+        M: rig::completion::CompletionModel + 'static,
+    {
+        let agent_builder = rig::agent::AgentBuilder::new(model)
+            .preamble(&self.persona)
+            .tools(self.tools.rig_tools())
+            .default_max_depth(DEFAULT_MAX_TOOL_ITERATIONS);
+        let agent_builder = match self.temperature {
+            Some(temperature) => agent_builder.temperature(temperature as f64),
+            None => agent_builder,
+        };
+        let agent_builder = match additional_params {
+            Some(params) => agent_builder.additional_params(params),
+            None => agent_builder,
+        };
+        agent_builder.build()
+    }
+}
+
+/// All Rig tools of a chat's agent
+#[allow(clippy::too_many_arguments)]
+fn build_agent_tools(
     pool: &DbPool,
     storage: &Arc<FileStorageService>,
     workspace_id: Uuid,
     chat_id: Uuid,
     user_id: Uuid,
     tool_config: &crate::tools::ToolConfig,
-) -> rig::agent::AgentBuilderSimple<M>
-where
-    M: rig::completion::CompletionModel + 'static,
-{
-    builder
-        .tool(RigLsTool {
+    delegation: Option<DelegationContext>,
+    mcp_tools: Vec<RigMcpTool>,
+    ai_config: &AiConfig,
+) -> AgentTools {
+    let mut tools: Vec<Box<dyn ToolDyn>> = vec![
+        Box::new(RigLsTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigReadTool {
+        }),
+        Box::new(RigReadTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigWriteTool {
+        }),
+        Box::new(RigWriteTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigEditTool {
+        }),
+        Box::new(RigEditTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigRmTool {
+        }),
+        Box::new(RigRmTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigMvTool {
+        }),
+        Box::new(RigMvTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigTouchTool {
+        }),
+        Box::new(RigTouchTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigMkdirTool {
+        }),
+        Box::new(RigMkdirTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigGrepTool {
+        }),
+        Box::new(RigGrepTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigAskUserTool {
+        }),
+        Box::new(RigAskUserTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigExitPlanModeTool {
+        }),
+        Box::new(RigExitPlanModeTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigGlobTool {
+        }),
+        Box::new(RigGlobTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigFileInfoTool {
+        }),
+        Box::new(RigFileInfoTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigReadMultipleFilesTool {
+        }),
+        Box::new(RigReadMultipleFilesTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigFindTool {
+        }),
+        Box::new(RigFindTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigCatTool {
+        }),
+        Box::new(RigCatTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigPlanWriteTool {
+        }),
+        Box::new(RigPlanWriteTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigPlanReadTool {
+        }),
+        Box::new(RigPlanReadTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigPlanEditTool {
+        }),
+        Box::new(RigPlanEditTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigPlanListTool {
+        }),
+        Box::new(RigPlanListTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigMemorySetTool {
+        }),
+        Box::new(RigPlanUpdateStepTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigMemoryGetTool {
+        }),
+        Box::new(RigMemorySetTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigMemorySearchTool {
+        }),
+        Box::new(RigMemoryGetTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigMemoryDeleteTool {
+        }),
+        Box::new(RigMemorySearchTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigMemoryListTool {
+        }),
+        Box::new(RigMemoryDeleteTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigWebFetchTool {
+        }),
+        Box::new(RigMemoryListTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .tool(RigWebSearchTool {
+        }),
+        Box::new(RigWebFetchTool {
             pool: pool.clone(),
             storage: storage.clone(),
             workspace_id,
             chat_id,
             user_id,
             tool_config: tool_config.clone(),
-        })
-        .default_max_depth(DEFAULT_MAX_TOOL_ITERATIONS)
+        }),
+        Box::new(RigWebSearchTool {
+            pool: pool.clone(),
+            storage: storage.clone(),
+            workspace_id,
+            chat_id,
+            user_id,
+            tool_config: tool_config.clone(),
+        }),
+        Box::new(RigSkillListTool {
+            pool: pool.clone(),
+            storage: storage.clone(),
+            workspace_id,
+            chat_id,
+            user_id,
+            tool_config: tool_config.clone(),
+        }),
+        Box::new(RigSkillActivateTool {
+            pool: pool.clone(),
+            storage: storage.clone(),
+            workspace_id,
+            chat_id,
+            user_id,
+            tool_config: tool_config.clone(),
+        }),
+    ];
+
+    // Commands need bubblewrap on the host, so the sandbox is opt-in
+    if ai_config.sandbox.enabled {
+        tools.push(Box::new(RigRunTool {
+            pool: pool.clone(),
+            storage: storage.clone(),
+            workspace_id,
+            chat_id,
+            user_id,
+            tool_config: tool_config.clone(),
+        }));
+    }
+
+    // Sub-agents cannot delegate further, so only top-level chats get the runtime
+    if let Some(delegation) = delegation {
+        tools.push(Box::new(RigDelegateTool {
+            pool: pool.clone(),
+            storage: storage.clone(),
+            workspace_id,
+            chat_id,
+            user_id,
+            tool_config: tool_config.clone(),
+            delegation,
+        }));
+    }
+
+    // Workspace MCP tools may change anything on their server, so Plan Mode only
+    // gets the ones the server declares read-only, and only those run concurrently
+    let read_only_mcp_tools = mcp_tools
+        .iter()
+        .filter(|tool| tool.tool.read_only)
+        .map(|tool| tool.name())
+        .collect();
+    tools.extend(
+        mcp_tools
+            .into_iter()
+            .filter(|tool| !tool_config.plan_mode || tool.tool.read_only)
+            .map(|tool| Box::new(tool) as Box<dyn ToolDyn>),
+    );
+
+    // Agent files and delegating parents may restrict the toolset, hide the rest
+    // from the model entirely
+    let tools = tools
+        .into_iter()
+        .filter(|tool| tool_config.is_tool_allowed(&tool.name()))
+        .collect();
+
+    AgentTools::new(
+        tools,
+        read_only_mcp_tools,
+        ai_config.max_parallel_tools,
+        ai_config.tool_timeouts.clone(),
+    )
+}
+
+/// A chat's agent, with the tools the tool loop calls directly
+#[derive(Debug, Clone)]
+pub struct ChatAgent {
+    pub agent: Agent,
+    pub tools: Arc<AgentTools>,
 }
 
 /// Multi-provider AI service supporting OpenAI and OpenRouter
@@ -264,7 +388,10 @@ where
 pub struct RigService {
     openai: Option<Arc<OpenAiProvider>>,
     openrouter: Option<Arc<OpenRouterProvider>>,
+    mock: Option<Arc<MockProvider>>,
     default_provider: AiProvider,
+    /// Connections to workspace MCP servers, shared by all agents
+    mcp_clients: Arc<McpClientPool>,
 }
 
 impl RigService {
@@ -293,8 +420,13 @@ impl RigService {
             None
         };
 
+        // Initialize mock provider if configured
+        let mock = ai_config.providers.mock.as_ref().map(|mock_config| {
+            Arc::new(MockProvider::new(&mock_config.fixtures_dir).with_recording(mock_config.record))
+        });
+
         // Validate at least one provider is configured
-        if openai.is_none() && openrouter.is_none() {
+        if openai.is_none() && openrouter.is_none() && mock.is_none() {
             return Err(crate::error::Error::Internal(
                 "No AI providers configured".to_string()
             ));
@@ -312,7 +444,12 @@ impl RigService {
                     "Default provider is OpenRouter, but OpenRouter is not configured".to_string()
                 ));
             }
-            AiProvider::OpenAi | AiProvider::OpenRouter => {
+            AiProvider::Mock if mock.is_none() => {
+                return Err(crate::error::Error::Internal(
+                    "Default provider is the mock provider, but it is not configured".to_string()
+                ));
+            }
+            AiProvider::OpenAi | AiProvider::OpenRouter | AiProvider::Mock => {
                 // Valid configuration, continue
             }
         }
@@ -320,7 +457,9 @@ impl RigService {
         Ok(RigService {
             openai,
             openrouter,
+            mock,
             default_provider,
+            mcp_clients: Arc::new(McpClientPool::new()),
         })
     }
 
@@ -338,7 +477,9 @@ impl RigService {
         RigService {
             openai,
             openrouter: None,
+            mock: None,
             default_provider: AiProvider::OpenAi,
+            mcp_clients: Arc::new(McpClientPool::new()),
         }
     }
 
@@ -360,7 +501,9 @@ impl RigService {
         RigService {
             openai,
             openrouter: None,
+            mock: None,
             default_provider: AiProvider::OpenAi,
+            mcp_clients: Arc::new(McpClientPool::new()),
         }
     }
 
@@ -375,10 +518,17 @@ impl RigService {
         RigService {
             openai,
             openrouter: None,
+            mock: None,
             default_provider: AiProvider::OpenAi,
+            mcp_clients: Arc::new(McpClientPool::new()),
         }
     }
 
+    /// Connections to workspace MCP servers
+    pub fn mcp_clients(&self) -> &Arc<McpClientPool> {
+        &self.mcp_clients
+    }
+
     /// Get the default provider
     pub fn default_provider(&self) -> AiProvider {
         self.default_provider
@@ -389,6 +539,7 @@ impl RigService {
         match provider {
             AiProvider::OpenAi => self.openai.is_some(),
             AiProvider::OpenRouter => self.openrouter.is_some(),
+            AiProvider::Mock => self.mock.is_some(),
         }
     }
 
@@ -401,11 +552,15 @@ impl RigService {
         if self.openrouter.is_some() {
             providers.push(AiProvider::OpenRouter);
         }
+        if self.mock.is_some() {
+            providers.push(AiProvider::Mock);
+        }
         providers
     }
 
     /// Creates a Rig agent configured for the given chat session.
-    /// Returns our unified Agent enum that wraps both OpenAI and OpenRouter agents.
+    /// Returns our unified Agent enum, wrapping the provider's agent, with its tools.
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_agent(
         &self,
         pool: DbPool,
@@ -414,8 +569,9 @@ impl RigService {
         chat_id: Uuid,
         user_id: Uuid,
         session: &ChatSession,
-        _ai_config: &AiConfig,
-    ) -> Result<Agent> {
+        ai_config: &AiConfig,
+        delegation: Option<DelegationContext>,
+    ) -> Result<ChatAgent> {
         // 1. Parse model identifier (supports both "provider:model" and legacy "model" formats)
         let model_id = ModelIdentifier::parse(
             &session.agent_config.model,
@@ -428,14 +584,28 @@ impl RigService {
             match model_id.provider {
                 AiProvider::OpenAi => "gpt-5-mini",
                 AiProvider::OpenRouter => "anthropic/claude-3.5-sonnet",
+                AiProvider::Mock => "default",
             }
         } else {
             model_id.model.as_str()
         };
 
-        // 3. Build persona (same for both providers)
+        // 3. Load the chat's agent file, which replaces the built-in persona
+        let chat_agent = if let Some(agent_id) = session.agent_config.agent_id {
+            let mut conn = pool.acquire().await.map_err(|e| Error::Internal(format!("Database error: {}", e)))?;
+            crate::services::agent_files::load_chat_agent(&mut conn, &storage, workspace_id, chat_id, agent_id).await
+        } else {
+            None
+        };
+
+        // Build persona (same for both providers)
         let persona = if let Some(ref override_persona) = session.agent_config.persona_override {
             override_persona.clone()
+        } else if let Some(ref agent) = chat_agent {
+            agent.system_prompt.clone()
+        } else if session.agent_config.parent_chat_id.is_some() {
+            // Sub-agent started by the delegate tool
+            crate::agents::get_persona(Some("delegate"), None, None)
         } else {
             // Auto-select persona based on chat mode
             let mode = session.agent_config.mode.as_str();
@@ -474,32 +644,76 @@ impl RigService {
             }
         };
 
+        // Advertise workspace skills so the agent knows what it can activate
+        let persona = {
+            let mut conn = pool.acquire().await.map_err(|e| Error::Internal(format!("Database error: {}", e)))?;
+            crate::services::skills::persona_with_skill_catalog(&mut conn, &storage, workspace_id, &persona).await
+        };
+
         // 4. Create ToolConfig (same for both providers)
         let tool_config = crate::tools::ToolConfig {
             plan_mode: session.agent_config.mode == "plan",
             active_plan_path: session.agent_config.plan_file.clone(),
+            sandbox: ai_config.sandbox.clone(),
+            web_search: ai_config.web_search.clone(),
+            ..Default::default()
+        };
+        let tool_config = match chat_agent {
+            Some(ref agent) => agent.restrict_tool_config(tool_config),
+            None => tool_config,
+        };
+        let tool_config = match session.agent_config.allowed_tools {
+            Some(ref tools) => tool_config.restrict_tools(tools),
+            None => tool_config,
+        };
+
+        // Tools of the workspace's MCP servers
+        let mcp_tools: Vec<RigMcpTool> = {
+            let mut conn = pool.acquire().await.map_err(|e| Error::Internal(format!("Database error: {}", e)))?;
+            crate::services::mcp::load_agent_tools(&mut conn, &self.mcp_clients, &ai_config.mcp, workspace_id)
+                .await
+                .into_iter()
+                .map(|(server, tool)| RigMcpTool {
+                    clients: self.mcp_clients.clone(),
+                    server,
+                    tool,
+                })
+                .collect()
+        };
+
+        // Only agent files set a temperature, reasoning models reject it otherwise
+        let temperature = chat_agent.as_ref().and_then(|agent| agent.metadata.temperature);
+
+        // Agents of the real providers record their completions when recording is enabled
+        let recorder = match self.mock.as_ref().filter(|mock| mock.is_recording()) {
+            Some(mock) if model_id.provider != AiProvider::Mock => Some(mock.recorder(chat_id)?),
+            _ => None,
+        };
+
+        let tools = build_agent_tools(
+            &pool,
+            &storage,
+            workspace_id,
+            chat_id,
+            user_id,
+            &tool_config,
+            delegation,
+            mcp_tools,
+            ai_config,
+        );
+        let setup = AgentSetup {
+            persona,
+            tools,
+            temperature,
         };
 
         // 5. Build agent based on provider type
-        match model_id.provider {
+        let agent = match model_id.provider {
             AiProvider::OpenAi => {
                 // Validate OpenAI provider is configured
                 let openai_provider = self.openai.as_ref()
                     .ok_or_else(|| Error::Internal("OpenAI provider not configured".to_string()))?;
 
-                // Build agent with OpenAI client
-                let agent_builder = openai_provider.client().agent(model_name)
-                    .preamble(&persona);
-                let agent_builder = add_tools_to_agent(
-                    agent_builder,
-                    &pool,
-                    &storage,
-                    workspace_id,
-                    chat_id,
-                    user_id,
-                    &tool_config,
-                );
-
                 // Build additional parameters for OpenAI Responses API
                 // CRITICAL: Set store: false to use stateless mode
                 // This prevents OpenAI from requiring reasoning items to be maintained across requests
@@ -526,47 +740,107 @@ impl RigService {
                     serde_json::to_string(&params).unwrap_or_else(|_| "INVALID".to_string())
                 );
 
-                let agent_builder = agent_builder.additional_params(params);
-
                 // Add previous_response_id if available (for conversation continuity with GPT-5)
-                let agent_builder = if let Some(ref response_id) = session.agent_config.previous_response_id {
-                    agent_builder.additional_params(serde_json::json!({
+                let params = if let Some(ref response_id) = session.agent_config.previous_response_id {
+                    serde_json::json!({
                         "previous_response_id": response_id
-                    }))
+                    })
                 } else {
-                    agent_builder
+                    params
                 };
 
-                let agent = agent_builder.build();
-                Ok(crate::providers::Agent::OpenAI(agent))
+                // Build agent with OpenAI client
+                let model = openai_provider.client().completion_model(model_name);
+                match recorder {
+                    Some(recorder) => Agent::RecordingOpenAI(
+                        setup.build(RecordingCompletionModel::new(model, recorder), Some(params)),
+                    ),
+                    None => Agent::OpenAI(setup.build(model, Some(params))),
+                }
             }
             AiProvider::OpenRouter => {
                 // Validate OpenRouter provider is configured
                 let openrouter_provider = self.openrouter.as_ref()
                     .ok_or_else(|| Error::Internal("OpenRouter provider not configured".to_string()))?;
 
-                // Build agent with OpenRouter client
-                let agent_builder = openrouter_provider.client().agent(model_name)
-                    .preamble(&persona);
-                let agent_builder = add_tools_to_agent(
-                    agent_builder,
-                    &pool,
-                    &storage,
-                    workspace_id,
-                    chat_id,
-                    user_id,
-                    &tool_config,
+                tracing::info!(
+                    "Built OpenRouter agent with model: {}",
+                    model_name
                 );
 
+                // Build agent with OpenRouter client
+                let model = openrouter_provider.client().completion_model(model_name);
+                match recorder {
+                    Some(recorder) => Agent::RecordingOpenRouter(
+                        setup.build(RecordingCompletionModel::new(model, recorder), None),
+                    ),
+                    None => Agent::OpenRouter(setup.build(model, None)),
+                }
+            }
+            AiProvider::Mock => {
+                // Validate mock provider is configured
+                let mock_provider = self.mock.as_ref()
+                    .ok_or_else(|| Error::Internal("Mock provider not configured".to_string()))?;
+
+                // The model name is the fixture to replay
+                let model = mock_provider.completion_model(model_name, chat_id)?;
                 tracing::info!(
-                    "Built OpenRouter agent with model: {}",
+                    "Built mock agent replaying fixture: {}",
                     model_name
                 );
 
-                let agent = agent_builder.build();
-                Ok(crate::providers::Agent::OpenRouter(agent))
+                Agent::Mock(setup.build(model, None))
             }
-        }
+        };
+
+        Ok(ChatAgent {
+            agent,
+            tools: Arc::new(setup.tools),
+        })
+    }
+
+    /// Summarizes a transcript with a tool-less agent of the given model.
+    ///
+    /// Used for conversation compaction, where `model` is usually a cheaper
+    /// model than the chat's.
+    pub async fn summarize(&self, model: &str, instructions: &str, transcript: &str) -> Result<String> {
+        use rig::completion::Prompt;
+
+        let model_id = ModelIdentifier::parse(model, self.default_provider)
+            .map_err(|e| Error::Internal(format!("Invalid model format: {}", e)))?;
+
+        let result = match model_id.provider {
+            AiProvider::OpenAi => {
+                let openai_provider = self.openai.as_ref()
+                    .ok_or_else(|| Error::Internal("OpenAI provider not configured".to_string()))?;
+                openai_provider.client().agent(&model_id.model)
+                    .preamble(instructions)
+                    .additional_params(serde_json::json!({ "store": false }))
+                    .build()
+                    .prompt(transcript)
+                    .await
+            }
+            AiProvider::OpenRouter => {
+                let openrouter_provider = self.openrouter.as_ref()
+                    .ok_or_else(|| Error::Internal("OpenRouter provider not configured".to_string()))?;
+                openrouter_provider.client().agent(&model_id.model)
+                    .preamble(instructions)
+                    .build()
+                    .prompt(transcript)
+                    .await
+            }
+            AiProvider::Mock => {
+                let mock_provider = self.mock.as_ref()
+                    .ok_or_else(|| Error::Internal("Mock provider not configured".to_string()))?;
+                rig::agent::AgentBuilder::new(mock_provider.completion_model(&model_id.model, Uuid::nil())?)
+                    .preamble(instructions)
+                    .build()
+                    .prompt(transcript)
+                    .await
+            }
+        };
+
+        result.map_err(|e| Error::Internal(format!("Summarization failed: {}", e)))
     }
 
     /// Reconstruct a ToolCall message from metadata.
@@ -617,7 +891,7 @@ impl RigService {
         metadata: &crate::models::chat::ChatMessageMetadata,
         include_note: bool,
     ) -> Option<Message> {
-        use rig::message::{UserContent, ToolResult, ToolResultContent};
+        use rig::message::{ToolResult, ToolResultContent};
         use rig::message::Text;
 
         let tool_id = metadata.reasoning_id.clone()
@@ -713,7 +987,7 @@ impl RigService {
                         }
                     }
                     // Reuse existing message conversion logic
-                    if let Some(msg) = Self::convert_single_message(role, content, metadata, self) {
+                    if let Some(msg) = Self::convert_single_message(role, content, *metadata, self) {
                         result.push(msg);
                     }
                 }
@@ -774,8 +1048,13 @@ impl RigService {
                 }
             }
             ChatMessageRole::System => {
-                // System messages typically not sent in chat history
-                None
+                // Compaction summaries stand in for the turns they cover,
+                // other system messages are not sent in chat history
+                if metadata.message_type.as_deref() == Some(COMPACTION_SUMMARY_MESSAGE_TYPE) {
+                    Some(Message::user(format!("[Summary of earlier conversation]\n{}", content)))
+                } else {
+                    None
+                }
             }
             ChatMessageRole::Tool => {
                 // Convert Tool role messages to Rig format
@@ -805,6 +1084,50 @@ impl RigService {
     }
 }
 
+/// Image part of a message sent to a model
+pub(crate) fn image_part(image: &ImageContent) -> UserContent {
+    UserContent::image_base64(
+        image.data.clone(),
+        ImageMediaType::from_mime_type(&image.media_type),
+        Some(ImageDetail::Auto),
+    )
+}
+
+/// Builds the prompt of a turn from the user's message and the images attached to it
+///
+/// Models that accept images receive each image as an image part after the text.
+/// Other models are told which images they cannot see, so they can say so rather
+/// than answer as if there were none.
+pub fn prompt_message(prompt: &str, images: &[AttachedImage], supports_vision: bool) -> Message {
+    if images.is_empty() {
+        return Message::user(prompt);
+    }
+
+    if !supports_vision {
+        let names: Vec<&str> = images.iter().map(|image| image.name.as_str()).collect();
+        return Message::user(format!(
+            "{}\n\n[Attached images not shown, this model does not accept images: {}]",
+            prompt,
+            names.join(", ")
+        ));
+    }
+
+    let mut content = Vec::with_capacity(1 + images.len() * 2);
+    if !prompt.trim().is_empty() {
+        content.push(UserContent::text(prompt));
+    }
+    for image in images {
+        content.push(UserContent::text(format!(
+            "[Attached image: {} ({}x{})]",
+            image.name, image.image.width, image.image.height
+        )));
+        content.push(image_part(&image.image));
+    }
+    Message::User {
+        content: rig::OneOrMany::many(content).expect("images are not empty"),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1117,4 +1440,42 @@ mod tests {
             _ => panic!("Expected User message"),
         }
     }
+
+    #[test]
+    fn test_prompt_message_with_images() {
+        let images = vec![AttachedImage {
+            name: "chart.png".to_string(),
+            image: ImageContent {
+                media_type: "image/png".to_string(),
+                width: 640,
+                height: 480,
+                data: "aGVsbG8=".to_string(),
+            },
+        }];
+
+        match prompt_message("What does this show?", &images, true) {
+            Message::User { content } => {
+                let parts: Vec<UserContent> = content.into_iter().collect();
+                assert_eq!(parts.len(), 3);
+                assert!(matches!(&parts[1], UserContent::Text(text) if text.text == "[Attached image: chart.png (640x480)]"));
+                match &parts[2] {
+                    UserContent::Image(image) => assert_eq!(image.media_type, Some(ImageMediaType::PNG)),
+                    other => panic!("Expected image content, got {:?}", other),
+                }
+            }
+            _ => panic!("Expected User message"),
+        }
+
+        match prompt_message("What does this show?", &images, false) {
+            Message::User { content } => {
+                let parts: Vec<UserContent> = content.into_iter().collect();
+                assert_eq!(parts.len(), 1);
+                match &parts[0] {
+                    UserContent::Text(text) => assert!(text.text.ends_with("this model does not accept images: chart.png]")),
+                    other => panic!("Expected text content, got {:?}", other),
+                }
+            }
+            _ => panic!("Expected User message"),
+        }
+    }
 }
@@ -3,16 +3,23 @@ use crate::models::requests::{
     EditArgs, GrepArgs, GlobArgs, LsArgs, FileInfoArgs, MkdirArgs, MvArgs, ReadArgs, ReadMultipleFilesArgs, RmArgs, TouchArgs, WriteArgs,
     FindArgs, CatArgs,
     AskUserArgs, ExitPlanModeArgs,
-    PlanWriteArgs, PlanReadArgs, PlanEditArgs, PlanListArgs,
+    PlanWriteArgs, PlanReadArgs, PlanEditArgs, PlanListArgs, PlanUpdateStepArgs,
     MemorySetArgs, MemoryGetArgs, MemorySearchArgs, MemoryDeleteArgs, MemoryListArgs,
     WebFetchArgs, WebSearchArgs,
+    SkillListArgs, SkillActivateArgs,
+    DelegateArgs,
+    RunArgs,
 };
+use crate::models::mcp::{McpServer, McpTool};
+use crate::services::chat::delegation::{self, DelegationContext};
+use crate::services::mcp::{self, McpClientPool};
 use crate::services::storage::FileStorageService;
 use crate::tools;
 
 use crate::DbPool;
 use rig::completion::ToolDefinition;
-use rig::tool::Tool as RigTool;
+use rig::tool::{Tool as RigTool, ToolDyn, ToolError};
+use rig::wasm_compat::WasmBoxedFuture;
 use std::future::Future;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -134,6 +141,8 @@ macro_rules! define_rig_tool {
                                     previous_response_id: None,
                                     mode: "plan".to_string(),
                                     plan_file: None,
+                                    parent_chat_id: None,
+                                    allowed_tools: None,
                                 }
                             });
 
@@ -145,9 +154,11 @@ macro_rules! define_rig_tool {
                             "Read current mode from database for ToolConfig"
                         );
 
+                        // Agent file restrictions are fixed for the chat, keep them
                         crate::tools::ToolConfig {
                             plan_mode: agent_config.mode == "plan",
                             active_plan_path: agent_config.plan_file,
+                            ..initial_tool_config
                         }
                     } else {
                         tracing::warn!(
@@ -157,6 +168,10 @@ macro_rules! define_rig_tool {
                         );
                         initial_tool_config
                     };
+                    let tool_config = tools::ToolConfig {
+                        call: tools::ToolCallContext::current(),
+                        ..tool_config
+                    };
 
                     tracing::debug!(
                         tool = $name,
@@ -351,6 +366,13 @@ define_rig_tool!(
     "plan_list"
 );
 
+define_rig_tool!(
+    RigPlanUpdateStepTool,
+    tools::plan_update_step::PlanUpdateStepTool,
+    PlanUpdateStepArgs,
+    "plan_update_step"
+);
+
 // Memory management tools
 define_rig_tool!(
     RigMemorySetTool,
@@ -402,3 +424,184 @@ define_rig_tool!(
     "web_search"
 );
 
+
+// Skill tools
+define_rig_tool!(
+    RigSkillListTool,
+    tools::skill_list::SkillListTool,
+    SkillListArgs,
+    "skill_list"
+);
+
+define_rig_tool!(
+    RigSkillActivateTool,
+    tools::skill_activate::SkillActivateTool,
+    SkillActivateArgs,
+    "skill_activate"
+);
+
+// Run tool
+define_rig_tool!(
+    RigRunTool,
+    tools::run::RunTool,
+    RunArgs,
+    "run"
+);
+
+// Delegate tool
+//
+// Written by hand instead of with define_rig_tool! since it needs the chat
+// runtime to start sub-agents rather than a database connection.
+pub struct RigDelegateTool {
+    pub pool: DbPool,
+    pub storage: Arc<FileStorageService>,
+    pub workspace_id: Uuid,
+    pub chat_id: Uuid,
+    pub user_id: Uuid,
+    pub tool_config: tools::ToolConfig,
+    pub delegation: DelegationContext,
+}
+
+impl RigTool for RigDelegateTool {
+    type Error = Error;
+    type Args = Option<DelegateArgs>;
+    type Output = serde_json::Value;
+
+    const NAME: &'static str = "delegate";
+
+    fn definition(
+        &self,
+        _prompt: String,
+    ) -> impl Future<Output = ToolDefinition> + Send + Sync {
+        let name = Self::NAME.to_string();
+        async move {
+            use crate::tools::Tool;
+            let core_tool = tools::delegate::DelegateTool;
+
+            ToolDefinition {
+                name,
+                description: core_tool.description().to_string(),
+                parameters: core_tool.definition(),
+            }
+        }
+    }
+
+    fn call(
+        &self,
+        args: Self::Args,
+    ) -> impl Future<Output = Result<Self::Output, Self::Error>> + Send {
+        let pool = self.pool.clone();
+        let storage = self.storage.clone();
+        let workspace_id = self.workspace_id;
+        let chat_id = self.chat_id;
+        let user_id = self.user_id;
+        let tool_config = self.tool_config.clone();
+        let delegation = self.delegation.clone();
+
+        async move {
+            let args = args.ok_or_else(|| {
+                Error::Validation(crate::error::ValidationErrors::Single {
+                    field: "arguments".to_string(),
+                    message: "Tool 'delegate' requires arguments, for example \
+                        {\"tasks\": [{\"task\": \"Summarize /notes/q3.md\", \"tools\": [\"read\"]}]}"
+                        .to_string(),
+                })
+            })?;
+
+            tracing::debug!(
+                tool = "delegate",
+                chat_id = %chat_id,
+                tasks = args.tasks.len(),
+                "Executing tool"
+            );
+
+            let result = delegation::run_delegates(
+                &delegation,
+                &pool,
+                &storage,
+                workspace_id,
+                chat_id,
+                user_id,
+                &tool_config,
+                args,
+            )
+            .await?;
+
+            serde_json::to_value(result).map_err(Error::Json)
+        }
+    }
+}
+
+// MCP tools
+//
+// Discovered at runtime, so they implement ToolDyn directly instead of the
+// statically named RigTool. The name and schema come from the MCP server.
+pub struct RigMcpTool {
+    pub clients: Arc<McpClientPool>,
+    pub server: Arc<McpServer>,
+    pub tool: McpTool,
+}
+
+impl ToolDyn for RigMcpTool {
+    fn name(&self) -> String {
+        self.tool.name.clone()
+    }
+
+    fn definition<'a>(&'a self, _prompt: String) -> WasmBoxedFuture<'a, ToolDefinition> {
+        Box::pin(async move {
+            let description = match &self.tool.description {
+                Some(description) => format!("[MCP server '{}'] {}", self.server.name, description),
+                None => format!("[MCP server '{}'] Tool '{}'", self.server.name, self.tool.remote_name),
+            };
+
+            ToolDefinition {
+                name: self.tool.name.clone(),
+                description,
+                parameters: self.tool.input_schema.clone(),
+            }
+        })
+    }
+
+    fn call<'a>(&'a self, args: String) -> WasmBoxedFuture<'a, Result<String, ToolError>> {
+        Box::pin(async move {
+            // Models send an empty string for tools without parameters
+            let args_val: serde_json::Value = if args.trim().is_empty() {
+                serde_json::json!({})
+            } else {
+                serde_json::from_str(&args).map_err(ToolError::JsonError)?
+            };
+
+            tracing::debug!(
+                tool = %self.tool.name,
+                server = %self.server.name,
+                args = %args_val,
+                "Executing MCP tool"
+            );
+
+            let response = tools::ToolCallContext::current()
+                .run(mcp::call_tool(&self.clients, &self.server, &self.tool, args_val.clone()))
+                .await
+                .map_err(|e| ToolError::ToolCallError(Box::new(e)))?;
+
+            if response.success {
+                serde_json::to_string(&response.result).map_err(ToolError::JsonError)
+            } else {
+                let error_msg = response
+                    .error
+                    .unwrap_or_else(|| "Unknown tool error".to_string());
+
+                tracing::warn!(
+                    tool = %self.tool.name,
+                    args = %args_val,
+                    error = %error_msg,
+                    "MCP tool execution failed"
+                );
+
+                Err(ToolError::ToolCallError(Box::new(Error::Internal(format!(
+                    "Tool '{}' failed with input {}: {}",
+                    self.tool.name, args_val, error_msg
+                )))))
+            }
+        })
+    }
+}
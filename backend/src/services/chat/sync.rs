@@ -57,6 +57,8 @@ impl ChatFrontmatter {
             previous_response_id: None,
             mode: self.mode.clone(),
             plan_file: self.plan_file.clone(),
+            parent_chat_id: None,
+            allowed_tools: None,
         }
     }
 
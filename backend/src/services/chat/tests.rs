@@ -2,7 +2,7 @@
 mod tests {
     use crate::services::chat::context::{
         AttachmentKey, AttachmentManager, AttachmentValue, PRIORITY_ESSENTIAL, PRIORITY_HIGH,
-        PRIORITY_LOW, PRIORITY_MEDIUM, ESTIMATED_CHARS_PER_TOKEN, truncate_tool_output,
+        PRIORITY_LOW, PRIORITY_MEDIUM, truncate_tool_output,
     };
     use chrono::Utc;
     use uuid::Uuid;
@@ -235,6 +235,47 @@ mod tests {
         assert_eq!(new_string, "short");
     }
 
+    #[test]
+    fn test_tool_input_summarization_run() {
+        use crate::services::chat::ChatService;
+
+        let args = serde_json::json!({ "command": "echo hi", "cwd": "/projects" });
+        assert_eq!(ChatService::summarize_tool_inputs("run", &args), args);
+
+        let args = serde_json::json!({ "command": "x".repeat(50_000) });
+        let summarized = ChatService::summarize_tool_inputs("run", &args);
+        let command = summarized.get("command").unwrap().as_str().unwrap();
+        assert!(command.ends_with("... [truncated, 50000 characters total]"));
+        assert!(command.len() < 11_000);
+    }
+
+    #[test]
+    fn test_tool_input_summarization_mcp() {
+        use crate::services::chat::ChatService;
+
+        let long_string = "payload line\n".repeat(100_000);
+        let args = serde_json::json!({
+            "query": "short",
+            "body": long_string,
+            "limit": 10
+        });
+
+        let summarized = ChatService::summarize_tool_inputs("mcp__github__create_issue", &args);
+        let body = summarized.get("body").unwrap().as_str().unwrap();
+
+        assert!(body.contains("... [truncated, 100000 lines, 1300000 characters total]"));
+        assert!(body.len() < 1000);
+        assert_eq!(summarized["query"], "short");
+        assert_eq!(summarized["limit"], 10);
+
+        // One huge line, nested in the arguments, is capped by characters
+        let args = serde_json::json!({ "files": [{ "content": "x".repeat(3_000_000) }] });
+        let summarized = ChatService::summarize_tool_inputs("mcp__github__push_files", &args);
+        let content = summarized["files"][0]["content"].as_str().unwrap();
+        assert!(content.ends_with("... [truncated, 1 lines, 3000000 characters total]"));
+        assert!(content.len() < 1_100_000);
+    }
+
     /// Regression test: Token count should be calculated from TRUNCATED content,
     /// not original content. This prevents showing inflated token counts in the
     /// context UI for old tool results that have been truncated.
@@ -288,7 +329,8 @@ mod tests {
 
         // 3. Call the function under test.
         let attachment_manager = AttachmentManager::new();
-        let history_section = ChatService::build_history_section(&messages, &attachment_manager);
+        let counter = crate::services::tokens::TokenCounter::default();
+        let history_section = ChatService::build_history_section(&messages, &attachment_manager, counter);
 
         // 4. Find our message in the result and assert on its token count.
         let info = history_section
@@ -298,7 +340,7 @@ mod tests {
             .expect("Long message not found in history section");
 
         let truncated_content = truncate_tool_output(&original_content);
-        let correct_token_count = truncated_content.len() / ESTIMATED_CHARS_PER_TOKEN;
+        let correct_token_count = counter.count(&truncated_content);
 
         assert_eq!(
             info.token_count, correct_token_count,
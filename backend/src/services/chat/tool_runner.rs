@@ -0,0 +1,677 @@
+//! Multi-turn tool loop of chat agents.
+//!
+//! rig's multi-turn stream runs the tool calls of a response one after the other,
+//! through a tool server that handles a single call at a time. This loop calls the
+//! agent's tools directly instead: consecutive read-only calls of a response run
+//! concurrently, at most `max_parallel_tools` at once, each tool acquiring its own
+//! database connection from the pool. A mutating call waits for the calls before it
+//! and runs alone, so changes happen in the order the model asked for them.
+//!
+//! The loop yields the items of rig's stream, each tool call followed by its result,
+//! so the actor processes them as before.
+//!
+//! Every call runs with a [`ToolCallContext`] whose cancellation token is a child of
+//! the chat's, so stopping the chat cancels the tools, and which is also cancelled
+//! when the call runs past its timeout. Output the tool streams while running is
+//! sent to the chat as `observation_chunk` events.
+//!
+//! Before a call runs it is matched against the workspace's tool policy. A call the
+//! policy denies is refused without running; one it asks about waits, alone, until
+//! the user approves or denies it (see [`super::approvals`]).
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use futures::{Stream, StreamExt};
+use rig::OneOrMany;
+use rig::agent::{MultiTurnStreamItem, StreamingError};
+use rig::completion::{CompletionModel, GetTokenUsage, PromptError, ToolDefinition, Usage};
+use rig::message::{AssistantContent, Message, ToolCall, ToolResult, ToolResultContent, UserContent};
+use rig::streaming::{StreamedAssistantContent, StreamedUserContent, StreamingCompletion};
+use rig::tool::{ToolDyn, ToolError, ToolSetError};
+use rig::wasm_compat::WasmBoxedFuture;
+use tokio::sync::{Semaphore, broadcast, mpsc};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::AbortOnDropHandle;
+
+use uuid::Uuid;
+
+use super::approvals::{self, ToolApprovals};
+use super::rig_engine;
+use crate::config::ToolTimeoutConfig;
+use crate::error::Error;
+use crate::models::files::ImageContent;
+use crate::models::requests::ToolResponse;
+use crate::models::sse::SseEvent;
+use crate::models::tool_policy::{TOOL_POLICY_DENIED, ToolPolicy, ToolPolicyAction, ToolPolicyRule};
+use crate::tools::{self, ToolCallContext};
+
+/// Tools of a chat's agent, called by the tool loop without going through rig
+#[derive(Clone)]
+pub struct AgentTools {
+    tools: HashMap<String, Arc<dyn ToolDyn>>,
+    read_only: HashSet<String>,
+    max_parallel_tools: usize,
+    timeouts: ToolTimeoutConfig,
+}
+
+impl fmt::Debug for AgentTools {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AgentTools")
+            .field("tools", &self.tools.keys().collect::<Vec<_>>())
+            .field("read_only", &self.read_only)
+            .field("max_parallel_tools", &self.max_parallel_tools)
+            .field("timeouts", &self.timeouts)
+            .finish()
+    }
+}
+
+impl AgentTools {
+    /// Built-in tools are read-only as their executor declares, other tools (MCP)
+    /// only when listed in `read_only`
+    pub fn new(
+        tools: Vec<Box<dyn ToolDyn>>,
+        read_only: HashSet<String>,
+        max_parallel_tools: usize,
+        timeouts: ToolTimeoutConfig,
+    ) -> Self {
+        let tools: HashMap<String, Arc<dyn ToolDyn>> =
+            tools.into_iter().map(|tool| (tool.name(), Arc::from(tool))).collect();
+        let read_only = tools
+            .keys()
+            .filter(|name| {
+                read_only.contains(*name)
+                    || tools::get_tool_executor(name).is_ok_and(|executor| executor.is_read_only())
+            })
+            .cloned()
+            .collect();
+
+        Self {
+            tools,
+            read_only,
+            max_parallel_tools: max_parallel_tools.max(1),
+            timeouts,
+        }
+    }
+
+    /// Tools to register on the agent, which only uses them for their definitions
+    pub fn rig_tools(&self) -> Vec<Box<dyn ToolDyn>> {
+        self.tools
+            .values()
+            .map(|tool| Box::new(SharedTool(tool.clone())) as Box<dyn ToolDyn>)
+            .collect()
+    }
+
+    pub fn is_read_only(&self, name: &str) -> bool {
+        self.read_only.contains(name)
+    }
+
+    /// Runs a tool call, returning the tool's output or error message
+    async fn call(&self, name: &str, args: String, context: ToolCallContext) -> String {
+        let result = match self.tools.get(name) {
+            Some(tool) => self.call_with_timeout(tool.as_ref(), name, args, context).await,
+            None => Err(ToolSetError::ToolNotFoundError(name.to_string())),
+        };
+        result.unwrap_or_else(|e| {
+            tracing::warn!(tool = %name, error = %e, "Error while calling tool");
+            e.to_string()
+        })
+    }
+
+    /// Runs a call with its context, cancelling it once past the tool's timeout
+    async fn call_with_timeout(
+        &self,
+        tool: &dyn ToolDyn,
+        name: &str,
+        args: String,
+        context: ToolCallContext,
+    ) -> Result<String, ToolSetError> {
+        let cancellation = context.cancellation.clone();
+        let call = context.scope(tool.call(args));
+        let Some(timeout) = self.timeouts.timeout(name) else {
+            return call.await.map_err(ToolSetError::ToolCallError);
+        };
+
+        match tokio::time::timeout(timeout, call).await {
+            Ok(result) => result.map_err(ToolSetError::ToolCallError),
+            Err(_) => {
+                cancellation.cancel();
+                Err(ToolSetError::ToolCallError(ToolError::ToolCallError(Box::new(Error::Internal(
+                    format!("Tool '{}' timed out after {} seconds and was cancelled", name, timeout.as_secs()),
+                )))))
+            }
+        }
+    }
+
+    /// Starts a tool call on its own task, once a permit is available
+    fn spawn_call(
+        self: &Arc<Self>,
+        call: &ToolCall,
+        permits: &Arc<Semaphore>,
+        cancellation: &CancellationToken,
+    ) -> RunningCall {
+        let tools = self.clone();
+        let permits = permits.clone();
+        let name = call.function.name.clone();
+        let args = match &call.function.arguments {
+            serde_json::Value::String(args) => args.clone(),
+            args => args.to_string(),
+        };
+        let (output_tx, output) = mpsc::unbounded_channel();
+        let context = ToolCallContext::new(cancellation.child_token(), Some(output_tx));
+
+        let handle = AbortOnDropHandle::new(tokio::spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            tools.call(&name, args, context).await
+        }));
+        RunningCall { handle, output }
+    }
+}
+
+/// Tool call started by [`AgentTools::spawn_call`]
+struct RunningCall {
+    handle: AbortOnDropHandle<String>,
+    output: mpsc::UnboundedReceiver<String>,
+}
+
+impl RunningCall {
+    /// Waits for the call's result, sending the output the tool streams meanwhile
+    /// as `observation_chunk` events
+    async fn finish(mut self, tool: &str, events: &broadcast::Sender<SseEvent>) -> String {
+        let send = |output: String| {
+            let _ = events.send(SseEvent::ObservationChunk { tool: tool.to_string(), output });
+        };
+
+        let result = loop {
+            tokio::select! {
+                biased;
+                Some(mut output) = self.output.recv() => {
+                    // One event for all the output sent since the last one
+                    while let Ok(more) = self.output.try_recv() {
+                        output.push_str(&more);
+                    }
+                    send(output);
+                }
+                result = &mut self.handle => break result,
+            }
+        };
+
+        let mut rest = String::new();
+        while let Ok(output) = self.output.try_recv() {
+            rest.push_str(&output);
+        }
+        if !rest.is_empty() {
+            send(rest);
+        }
+
+        result.unwrap_or_else(|e| format!("Tool call interrupted: {}", e))
+    }
+}
+
+/// Chat a turn's tool calls belong to
+#[derive(Debug, Clone)]
+pub struct TurnContext {
+    pub chat_id: Uuid,
+    /// The chat's cancellation token, cancelled when it is stopped
+    pub cancellation: CancellationToken,
+    /// The chat's events, receiving the output tools stream and approval requests
+    pub events: broadcast::Sender<SseEvent>,
+    /// Tool policy of the chat's workspace
+    pub policy: Arc<ToolPolicy>,
+    /// Where calls the policy asks about wait for the user's answer
+    pub approvals: Arc<ToolApprovals>,
+    /// Whether the chat's model accepts images, such as those `read` returns
+    pub supports_vision: bool,
+}
+
+impl TurnContext {
+    /// Rule of the workspace's policy requiring approval for, or refusing, a call
+    fn gate(&self, call: &ToolCall) -> Option<ToolPolicyRule> {
+        let arguments = tools::policy_arguments(&call.function.name, &call_arguments(call));
+        self.policy.gate(&call.function.name, &arguments).cloned()
+    }
+
+    /// Asks the user to approve a call, returning whether they did
+    ///
+    /// The call is refused when the chat is stopped or the user does not answer
+    /// in time.
+    async fn approve(&self, call: &ToolCall, rule: &ToolPolicyRule) -> bool {
+        let (question_id, answer) = self.approvals.request(self.chat_id).await;
+        tracing::info!(
+            chat_id = %self.chat_id,
+            tool = %call.function.name,
+            question_id = %question_id,
+            "Tool call waiting for approval"
+        );
+        let _ = self.events.send(SseEvent::QuestionPending {
+            question_id,
+            questions: vec![approvals::approval_question(&call.function.name, &call_arguments(call), rule)],
+            created_at: chrono::Utc::now(),
+        });
+
+        let approved = tokio::select! {
+            answer = tokio::time::timeout(approvals::APPROVAL_TIMEOUT, answer) => {
+                matches!(answer, Ok(Ok(true)))
+            }
+            _ = self.cancellation.cancelled() => false,
+        };
+        self.approvals.forget(question_id).await;
+        approved
+    }
+
+    /// Runs a call the workspace's policy asks about or denies
+    async fn call_gated(
+        &self,
+        tools: &Arc<AgentTools>,
+        call: &ToolCall,
+        rule: &ToolPolicyRule,
+        permits: &Arc<Semaphore>,
+    ) -> String {
+        let refusal = match rule.action {
+            ToolPolicyAction::Allow => None,
+            ToolPolicyAction::Deny => Some(TOOL_POLICY_DENIED),
+            ToolPolicyAction::Ask if self.approve(call, rule).await => None,
+            ToolPolicyAction::Ask => Some("The user did not approve this call"),
+        };
+        let Some(refusal) = refusal else {
+            return tools
+                .spawn_call(call, permits, &self.cancellation)
+                .finish(&call.function.name, &self.events)
+                .await;
+        };
+
+        tracing::info!(chat_id = %self.chat_id, tool = %call.function.name, "Tool call refused");
+        serde_json::to_string(&ToolResponse {
+            success: false,
+            result: serde_json::Value::Null,
+            error: Some(rule.refusal(refusal)),
+        })
+        .unwrap_or_default()
+    }
+}
+
+/// Arguments of a tool call, parsed when the model sent them as a JSON string
+///
+/// `Null` when they can't be parsed.
+fn call_arguments(call: &ToolCall) -> serde_json::Value {
+    match &call.function.arguments {
+        serde_json::Value::String(args) => serde_json::from_str(args).unwrap_or_default(),
+        args => args.clone(),
+    }
+}
+
+/// Takes the image out of a tool's output, with the path it was read from
+///
+/// Tools return images as base64 in `result.image` (see `ReadResult`), which a
+/// model would read as text. The output keeps the image's type and size, the
+/// image itself is sent as an image part when the chat's model accepts images
+/// and dropped otherwise.
+fn take_tool_image(output: String, supports_vision: bool) -> (String, Option<(String, ImageContent)>) {
+    let Ok(mut response) = serde_json::from_str::<serde_json::Value>(&output) else {
+        return (output, None);
+    };
+    let Some(image) = response.pointer_mut("/result/image") else {
+        return (output, None);
+    };
+    let Ok(content) = serde_json::from_value::<ImageContent>(image.take()) else {
+        return (output, None);
+    };
+
+    *image = serde_json::json!({
+        "media_type": content.media_type,
+        "width": content.width,
+        "height": content.height,
+        "note": if supports_vision {
+            "Attached after the tool results as an image"
+        } else {
+            "Not shown, this model does not accept images"
+        },
+    });
+    let label = response
+        .pointer("/result/path")
+        .and_then(|path| path.as_str())
+        .unwrap_or("image")
+        .to_string();
+    (response.to_string(), supports_vision.then_some((label, content)))
+}
+
+fn tool_result_item<R>(call: &ToolCall, output: &str) -> MultiTurnStreamItem<R> {
+    MultiTurnStreamItem::StreamUserItem(StreamedUserContent::ToolResult(ToolResult {
+        id: call.id.clone(),
+        call_id: call.call_id.clone(),
+        content: OneOrMany::one(ToolResultContent::text(output)),
+    }))
+}
+
+/// Tool registered on the rig agent, sharing its implementation with [`AgentTools`]
+struct SharedTool(Arc<dyn ToolDyn>);
+
+impl ToolDyn for SharedTool {
+    fn name(&self) -> String {
+        self.0.name()
+    }
+
+    fn definition<'a>(&'a self, prompt: String) -> WasmBoxedFuture<'a, ToolDefinition> {
+        self.0.definition(prompt)
+    }
+
+    fn call<'a>(&'a self, args: String) -> WasmBoxedFuture<'a, Result<String, ToolError>> {
+        self.0.call(args)
+    }
+}
+
+/// Splits a response's tool calls into the groups run together: each run of
+/// consecutive read-only calls, and each mutating call on its own
+fn call_batches(read_only: &[bool]) -> Vec<Range<usize>> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    while start < read_only.len() {
+        let end = if read_only[start] {
+            start + read_only[start..].iter().take_while(|read_only| **read_only).count()
+        } else {
+            start + 1
+        };
+        batches.push(start..end);
+        start = end;
+    }
+    batches
+}
+
+/// Streams the agent's answer to `prompt`, running the tools it calls until it
+/// answers without calling any, as rig's `stream_chat` does
+pub fn stream_multi_turn<M>(
+    agent: &rig::agent::Agent<M>,
+    tools: Arc<AgentTools>,
+    turn: TurnContext,
+    prompt: Message,
+    history: Vec<Message>,
+) -> impl Stream<Item = Result<MultiTurnStreamItem<M::StreamingResponse>, StreamingError>> + Unpin
+where
+    M: CompletionModel + 'static,
+    M::StreamingResponse: GetTokenUsage,
+{
+    let agent = agent.clone();
+    let max_depth = agent.default_max_depth.unwrap_or_default();
+    let permits = Arc::new(Semaphore::new(tools.max_parallel_tools));
+    let mut chat_history = history;
+    let mut current_prompt = prompt;
+
+    Box::pin(async_stream::stream! {
+        let mut depth = 0;
+        let mut last_text_response = String::new();
+        let mut is_text_response = false;
+        let mut aggregated_usage = Usage::new();
+
+        loop {
+            if depth > max_depth {
+                yield Err(StreamingError::Prompt(Box::new(PromptError::MaxDepthError {
+                    max_depth,
+                    chat_history: Box::new(chat_history.clone()),
+                    prompt: Box::new(current_prompt.clone()),
+                })));
+                break;
+            }
+            depth += 1;
+
+            let request = match agent.stream_completion(current_prompt.clone(), chat_history.clone()).await {
+                Ok(request) => request,
+                Err(e) => {
+                    yield Err(e.into());
+                    break;
+                }
+            };
+            let mut stream = match request.stream().await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    yield Err(e.into());
+                    break;
+                }
+            };
+            chat_history.push(current_prompt.clone());
+
+            let mut tool_calls = Vec::new();
+            let mut failed = false;
+            while let Some(content) = stream.next().await {
+                match content {
+                    Ok(StreamedAssistantContent::Text(text)) => {
+                        if !is_text_response {
+                            last_text_response.clear();
+                            is_text_response = true;
+                        }
+                        last_text_response.push_str(&text.text);
+                        yield Ok(MultiTurnStreamItem::StreamAssistantItem(StreamedAssistantContent::Text(text)));
+                    }
+                    // Run once the response is complete, with the other calls it makes
+                    Ok(StreamedAssistantContent::ToolCall(tool_call)) => tool_calls.push(tool_call),
+                    Ok(StreamedAssistantContent::ToolCallDelta { .. }) => {}
+                    Ok(StreamedAssistantContent::Final(response)) => {
+                        if let Some(usage) = response.token_usage() {
+                            aggregated_usage += usage;
+                        }
+                        if is_text_response {
+                            yield Ok(MultiTurnStreamItem::StreamAssistantItem(StreamedAssistantContent::Final(response)));
+                            is_text_response = false;
+                        }
+                    }
+                    Ok(content) => yield Ok(MultiTurnStreamItem::StreamAssistantItem(content)),
+                    Err(e) => {
+                        yield Err(e.into());
+                        failed = true;
+                        break;
+                    }
+                }
+            }
+            if failed {
+                break;
+            }
+
+            if tool_calls.is_empty() {
+                tracing::info!("Agent multi-turn stream finished");
+                yield Ok(MultiTurnStreamItem::final_response(&last_text_response, aggregated_usage));
+                break;
+            }
+
+            // Calls the policy asks about or denies run on their own
+            let gates: Vec<_> = tool_calls.iter().map(|call| turn.gate(call)).collect();
+            let read_only: Vec<bool> = tool_calls
+                .iter()
+                .zip(&gates)
+                .map(|(call, gate)| gate.is_none() && tools.is_read_only(&call.function.name))
+                .collect();
+            let mut tool_results = Vec::with_capacity(tool_calls.len());
+            let mut tool_images = Vec::new();
+            for batch in call_batches(&read_only) {
+                if let Some(rule) = &gates[batch.start] {
+                    let call = &tool_calls[batch.start];
+                    yield Ok(MultiTurnStreamItem::StreamAssistantItem(StreamedAssistantContent::ToolCall(call.clone())));
+                    let output = turn.call_gated(&tools, call, rule, &permits).await;
+                    let (output, image) = take_tool_image(output, turn.supports_vision);
+                    tool_images.extend(image);
+                    yield Ok(tool_result_item(call, &output));
+                    tool_results.push(output);
+                    continue;
+                }
+
+                let calls = &tool_calls[batch];
+                if calls.len() > 1 {
+                    tracing::debug!(tools = calls.len(), "Running read-only tool calls concurrently");
+                }
+                let running: Vec<_> = calls
+                    .iter()
+                    .map(|call| tools.spawn_call(call, &permits, &turn.cancellation))
+                    .collect();
+
+                // Each call is followed by its result, in the order the model made them
+                for (call, running) in calls.iter().zip(running) {
+                    yield Ok(MultiTurnStreamItem::StreamAssistantItem(StreamedAssistantContent::ToolCall(call.clone())));
+                    let output = running.finish(&call.function.name, &turn.events).await;
+                    let (output, image) = take_tool_image(output, turn.supports_vision);
+                    tool_images.extend(image);
+                    yield Ok(tool_result_item(call, &output));
+                    tool_results.push(output);
+                }
+            }
+
+            chat_history.push(Message::Assistant {
+                id: None,
+                content: OneOrMany::many(tool_calls.iter().cloned().map(AssistantContent::ToolCall))
+                    .expect("tool calls are not empty"),
+            });
+            for (call, output) in tool_calls.iter().zip(tool_results) {
+                let content = OneOrMany::one(ToolResultContent::text(&output));
+                let result = match &call.call_id {
+                    Some(call_id) => UserContent::tool_result_with_call_id(&call.id, call_id.clone(), content),
+                    None => UserContent::tool_result(&call.id, content),
+                };
+                chat_history.push(Message::User { content: OneOrMany::one(result) });
+            }
+            // Providers only take text in tool results, images follow in a message of their own
+            if !tool_images.is_empty() {
+                let mut content = Vec::with_capacity(tool_images.len() * 2);
+                for (label, image) in &tool_images {
+                    content.push(UserContent::text(format!(
+                        "[Image returned by a tool call: {} ({}x{})]",
+                        label, image.width, image.height
+                    )));
+                    content.push(rig_engine::image_part(image));
+                }
+                chat_history.push(Message::User {
+                    content: OneOrMany::many(content).expect("tool images are not empty"),
+                });
+            }
+
+            // The last tool result, or the images after it, is the next request's prompt
+            current_prompt = chat_history.pop().expect("chat history is not empty");
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tool that waits until its call is cancelled
+    struct HangingTool;
+
+    impl ToolDyn for HangingTool {
+        fn name(&self) -> String {
+            "hang".to_string()
+        }
+
+        fn definition<'a>(&'a self, _prompt: String) -> WasmBoxedFuture<'a, ToolDefinition> {
+            Box::pin(async {
+                ToolDefinition {
+                    name: "hang".to_string(),
+                    description: String::new(),
+                    parameters: serde_json::json!({}),
+                }
+            })
+        }
+
+        fn call<'a>(&'a self, _args: String) -> WasmBoxedFuture<'a, Result<String, ToolError>> {
+            Box::pin(async {
+                let call = ToolCallContext::current();
+                call.send_output("waiting\n");
+                call.run(std::future::pending::<crate::error::Result<()>>())
+                    .await
+                    .map(|_| "done".to_string())
+                    .map_err(|e| ToolError::ToolCallError(Box::new(e)))
+            })
+        }
+    }
+
+    fn hanging_tools(timeout_seconds: u64) -> Arc<AgentTools> {
+        let timeouts = ToolTimeoutConfig {
+            default_seconds: timeout_seconds,
+            tools: HashMap::new(),
+        };
+        Arc::new(AgentTools::new(vec![Box::new(HangingTool)], HashSet::new(), 4, timeouts))
+    }
+
+    fn hang_call() -> ToolCall {
+        ToolCall::new("call_1".to_string(), rig::message::ToolFunction::new("hang".to_string(), serde_json::json!({})))
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_times_out() {
+        let (events, mut received) = broadcast::channel(16);
+        let chat = CancellationToken::new();
+        let running = hanging_tools(1).spawn_call(&hang_call(), &Arc::new(Semaphore::new(1)), &chat);
+
+        let output = running.finish("hang", &events).await;
+        assert!(output.contains("Tool 'hang' timed out after 1 seconds"), "{}", output);
+        assert!(!chat.is_cancelled(), "Only the call is cancelled");
+        assert!(matches!(
+            received.try_recv(),
+            Ok(SseEvent::ObservationChunk { tool, output }) if tool == "hang" && output == "waiting\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stopping_the_chat_cancels_tool_calls() {
+        let (events, _received) = broadcast::channel(16);
+        let chat = CancellationToken::new();
+        let running = hanging_tools(0).spawn_call(&hang_call(), &Arc::new(Semaphore::new(1)), &chat);
+
+        chat.cancel();
+        let output = tokio::time::timeout(std::time::Duration::from_secs(5), running.finish("hang", &events))
+            .await
+            .expect("Cancelled call should finish");
+        assert!(output.contains("Tool call was cancelled"), "{}", output);
+    }
+
+    #[test]
+    fn test_tool_timeouts() {
+        let timeouts = ToolTimeoutConfig {
+            tools: HashMap::from([("delegate".to_string(), 0), ("web_fetch".to_string(), 60)]),
+            ..ToolTimeoutConfig::default()
+        };
+        assert_eq!(timeouts.timeout("grep"), Some(std::time::Duration::from_secs(300)));
+        assert_eq!(timeouts.timeout("web_fetch"), Some(std::time::Duration::from_secs(60)));
+        assert_eq!(timeouts.timeout("delegate"), None);
+    }
+
+    #[test]
+    fn test_call_batches() {
+        assert!(call_batches(&[]).is_empty());
+        assert_eq!(call_batches(&[true, true, true]), vec![0..3]);
+        assert_eq!(call_batches(&[false, false]), vec![0..1, 1..2]);
+        assert_eq!(
+            call_batches(&[true, true, false, true, false, false, true]),
+            vec![0..2, 2..3, 3..4, 4..5, 5..6, 6..7]
+        );
+    }
+
+    #[test]
+    fn test_take_tool_image() {
+        let output = serde_json::json!({
+            "success": true,
+            "result": {
+                "path": "/diagram.png",
+                "content": null,
+                "image": {"media_type": "image/png", "width": 40, "height": 30, "data": "aGVsbG8="}
+            },
+            "error": null
+        })
+        .to_string();
+
+        let (text, image) = take_tool_image(output.clone(), true);
+        let (label, image) = image.unwrap();
+        assert_eq!(label, "/diagram.png");
+        assert_eq!(image.data, "aGVsbG8=");
+        assert!(!text.contains("aGVsbG8="), "{}", text);
+        assert!(text.contains("\"width\":40"), "{}", text);
+
+        // Models without vision get the image's description only
+        let (text, image) = take_tool_image(output, false);
+        assert!(image.is_none());
+        assert!(!text.contains("aGVsbG8="));
+        assert!(text.contains("does not accept images"));
+
+        let (text, image) = take_tool_image("plain output".to_string(), true);
+        assert_eq!(text, "plain output");
+        assert!(image.is_none());
+    }
+}
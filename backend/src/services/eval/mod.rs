@@ -0,0 +1,321 @@
+//! Agent evaluation harness
+//!
+//! Runs [`Scenario`]s through the real agent loop and scores what the agent did,
+//! so prompt and model changes can be compared before they ship. Each scenario runs
+//! in a workspace of its own: the seed files are written, every turn is sent to a
+//! fresh chat actor, and the tool calls, responses and end state of the workspace
+//! are checked against the scenario's expectations.
+//!
+//! Runs against any configured provider. The `mock` model replays the fixture named
+//! after the scenario (`mock:<scenario name>`), which keeps the harness usable offline.
+
+pub mod report;
+pub mod scenario;
+
+pub use report::{EvalReport, ModelSummary, ScenarioResult};
+pub use scenario::{CheckResult, Expectations, Scenario, ToolCallRecord, TurnRecord};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::models::chat::{ChatMessageMetadata, ChatMessageRole, NewChatMessage, DEFAULT_CHAT_MODEL};
+use crate::models::requests::UserWorkspaceRegistrationRequest;
+use crate::models::sse::SseEvent;
+use crate::services::chat::actor::{ChatActor, ChatActorArgs};
+use crate::services::chat::registry::{AgentCommand, AgentRegistry};
+use crate::services::chat::rig_engine::RigService;
+use crate::services::chat::ChatService;
+use crate::services::storage::FileStorageService;
+use crate::services::{files, users, workspaces};
+use crate::tools::{Tool, ToolConfig};
+use crate::{queries, DbConn, DbPool};
+
+/// How long a stopping chat actor may take to clean up
+const ACTOR_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a scenario run produced, before scoring
+struct Transcript {
+    turns: Vec<TurnRecord>,
+    tool_calls: Vec<ToolCallRecord>,
+    files: HashMap<String, Option<String>>,
+}
+
+pub struct EvalRunner {
+    pool: DbPool,
+    storage: Arc<FileStorageService>,
+    rig_service: Arc<RigService>,
+    context_token_limit: usize,
+    keep_workspaces: bool,
+}
+
+impl EvalRunner {
+    pub fn new(pool: DbPool, storage: Arc<FileStorageService>, rig_service: Arc<RigService>, context_token_limit: usize) -> Self {
+        Self { pool, storage, rig_service, context_token_limit, keep_workspaces: false }
+    }
+
+    /// Keeps the workspaces of finished scenarios for inspection instead of deleting them
+    pub fn keep_workspaces(mut self, keep: bool) -> Self {
+        self.keep_workspaces = keep;
+        self
+    }
+
+    /// Runs every scenario against every model, or each scenario's own model when
+    /// `models` is empty
+    pub async fn run(&self, scenarios: &[Scenario], models: &[String], label: Option<String>) -> EvalReport {
+        let models: Vec<Option<&str>> = if models.is_empty() {
+            vec![None]
+        } else {
+            models.iter().map(|model| Some(model.as_str())).collect()
+        };
+
+        let mut results = Vec::new();
+        for model in models {
+            for scenario in scenarios {
+                let result = self.run_scenario(scenario, model).await;
+                tracing::info!(
+                    scenario = %result.scenario,
+                    model = %result.model,
+                    passed = result.passed,
+                    score = result.score,
+                    "[Eval] Scenario finished"
+                );
+                results.push(result);
+            }
+        }
+        EvalReport::new(label, results)
+    }
+
+    pub async fn run_scenario(&self, scenario: &Scenario, model: Option<&str>) -> ScenarioResult {
+        let model = scenario_model(scenario, model);
+        let started = Instant::now();
+
+        let mut result = match self.play_in_new_workspace(scenario, &model).await {
+            Ok(transcript) => {
+                let checks = scenario.expect.evaluate(&transcript.turns, &transcript.tool_calls, &transcript.files);
+                ScenarioResult {
+                    turns: transcript.turns,
+                    tool_calls: transcript.tool_calls,
+                    ..ScenarioResult::new(&scenario.name, &model, checks)
+                }
+            }
+            Err(e) => ScenarioResult::failed(&scenario.name, &model, e.to_string()),
+        };
+        result.duration_ms = started.elapsed().as_millis() as u64;
+        result
+    }
+
+    async fn play_in_new_workspace(&self, scenario: &Scenario, model: &str) -> Result<Transcript> {
+        let mut conn = self.pool.acquire().await.map_err(Error::Sqlx)?;
+
+        let run_id = Uuid::now_v7().simple().to_string();
+        let password = format!("Eval-{}-Pw1!", run_id);
+        let owner = users::register_user_with_workspace(&mut conn, UserWorkspaceRegistrationRequest {
+            email: format!("eval-{}@eval.buildscale.local", run_id),
+            password: password.clone(),
+            confirm_password: password,
+            full_name: Some("Eval Runner".to_string()),
+            workspace_name: format!("Eval {}", run_id),
+        })
+        .await?;
+        let workspace_id = owner.workspace.workspace.id;
+        let user_id = owner.user.id;
+
+        let transcript = self.play(&mut conn, scenario, model, workspace_id, user_id).await;
+
+        if self.keep_workspaces {
+            tracing::info!(scenario = %scenario.name, workspace_id = %workspace_id, "[Eval] Kept scenario workspace");
+        } else {
+            workspaces::delete_workspace(&mut conn, workspace_id).await?;
+            queries::users::delete_user(&mut conn, user_id).await?;
+        }
+
+        transcript
+    }
+
+    async fn play(
+        &self,
+        conn: &mut DbConn,
+        scenario: &Scenario,
+        model: &str,
+        workspace_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Transcript> {
+        for seed in &scenario.workspace {
+            let response = crate::tools::write::WriteTool
+                .execute(conn, &self.storage, workspace_id, user_id, ToolConfig::default(), json!({
+                    "path": seed.path,
+                    "content": seed.content,
+                }))
+                .await?;
+            if !response.success {
+                return Err(Error::Internal(format!(
+                    "Failed to seed {}: {}",
+                    seed.path,
+                    response.error.unwrap_or_default()
+                )));
+            }
+        }
+
+        // Same mode and persona as a chat created through the API
+        let role = scenario.role.as_deref();
+        let mode = match role {
+            Some("builder") => "build",
+            Some("planner") | None => "plan",
+            _ => "chat",
+        };
+        let persona = crate::agents::get_persona(role, Some(mode), None);
+        let chat = ChatService::create_chat_file(
+            conn,
+            workspace_id,
+            user_id,
+            format!("Eval: {}", scenario.name),
+            json!({
+                "model": chat_model(scenario, model),
+                "persona": persona,
+                "temperature": 0.7,
+                "mode": mode,
+                "plan_file": scenario.plan_file,
+            }),
+        )
+        .await?;
+
+        let registry = Arc::new(AgentRegistry::new());
+        let event_tx = registry.get_or_create_bus(chat.id).await;
+        let mut events = event_tx.subscribe();
+        let handle = ChatActor::spawn(ChatActorArgs {
+            chat_id: chat.id,
+            workspace_id,
+            user_id,
+            pool: self.pool.clone(),
+            rig_service: self.rig_service.clone(),
+            storage: self.storage.clone(),
+            registry,
+            default_persona: persona,
+            default_context_token_limit: self.context_token_limit,
+            event_tx,
+            inactivity_timeout: scenario.turn_timeout(),
+        });
+
+        let mut turns = Vec::new();
+        let mut tool_calls = Vec::new();
+        let played = async {
+            for message in &scenario.turns {
+                ChatService::save_message(conn, &self.storage, workspace_id, NewChatMessage {
+                    file_id: chat.id,
+                    workspace_id,
+                    role: ChatMessageRole::User,
+                    content: message.clone(),
+                    metadata: sqlx::types::Json(ChatMessageMetadata::default()),
+                })
+                .await?;
+                handle
+                    .command_tx
+                    .send(AgentCommand::ProcessInteraction { user_id })
+                    .await
+                    .map_err(|_| Error::Internal("Chat actor stopped".to_string()))?;
+
+                let turn = collect_turn(&mut events, &mut tool_calls, message, scenario.turn_timeout()).await;
+                let failed = turn.error.is_some();
+                turns.push(turn);
+                // Later turns build on this one
+                if failed {
+                    break;
+                }
+            }
+            Ok::<_, Error>(())
+        }
+        .await;
+
+        let _ = handle.command_tx.send(AgentCommand::Shutdown).await;
+        let _ = tokio::time::timeout(ACTOR_SHUTDOWN_TIMEOUT, handle.command_tx.closed()).await;
+        played?;
+
+        let mut end_state = HashMap::new();
+        for expected in &scenario.expect.files {
+            let content = match queries::files::get_file_by_path(conn, workspace_id, &expected.path).await? {
+                Some(file) => Some(match files::get_file_with_content(conn, &self.storage, file.id).await?.content {
+                    serde_json::Value::String(text) => text,
+                    content => serde_json::to_string_pretty(&content)?,
+                }),
+                None => None,
+            };
+            end_state.insert(expected.path.clone(), content);
+        }
+
+        Ok(Transcript { turns, tool_calls, files: end_state })
+    }
+}
+
+/// Model a scenario runs against: the requested one, else the scenario's own
+pub fn scenario_model(scenario: &Scenario, requested: Option<&str>) -> String {
+    requested
+        .or(scenario.model.as_deref())
+        .unwrap_or(DEFAULT_CHAT_MODEL)
+        .to_string()
+}
+
+/// Model of the scenario's chat, where `mock` replays the fixture named after the scenario
+fn chat_model(scenario: &Scenario, model: &str) -> String {
+    if model == "mock" {
+        format!("mock:{}", scenario.name)
+    } else {
+        model.to_string()
+    }
+}
+
+/// Reads the events of one turn until it is done, failed or timed out
+async fn collect_turn(
+    events: &mut broadcast::Receiver<SseEvent>,
+    tool_calls: &mut Vec<ToolCallRecord>,
+    message: &str,
+    turn_timeout: Duration,
+) -> TurnRecord {
+    let mut turn = TurnRecord { user: message.to_string(), response: String::new(), error: None };
+    let deadline = tokio::time::Instant::now() + turn_timeout;
+
+    loop {
+        let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+            Ok(Ok(event)) => event,
+            Ok(Err(broadcast::error::RecvError::Lagged(skipped))) => {
+                tracing::warn!(skipped, "[Eval] Missed chat events");
+                continue;
+            }
+            Ok(Err(broadcast::error::RecvError::Closed)) => {
+                turn.error = Some("Chat actor stopped".to_string());
+                break;
+            }
+            Err(_) => {
+                turn.error = Some(format!("Turn timed out after {}s", turn_timeout.as_secs()));
+                break;
+            }
+        };
+
+        match event {
+            SseEvent::Chunk { text } => turn.response.push_str(&text),
+            SseEvent::Call { tool, args, .. } => tool_calls.push(ToolCallRecord { tool, arguments: args, success: None }),
+            SseEvent::Observation { success, .. } => {
+                if let Some(call) = tool_calls.iter_mut().find(|call| call.success.is_none()) {
+                    call.success = Some(success);
+                }
+            }
+            SseEvent::Done { .. } => break,
+            SseEvent::Error { message } => {
+                turn.error = Some(message);
+                break;
+            }
+            SseEvent::Stopped { reason, .. } => {
+                turn.error = Some(format!("Stopped: {}", reason));
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    turn
+}
@@ -0,0 +1,258 @@
+//! Scored eval reports
+//!
+//! A report holds one result per scenario and model. It is written as JSON, so runs
+//! of different models or prompt revisions can be kept and compared, and rendered
+//! as a Markdown summary.
+
+use std::fmt::Write;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::scenario::{CheckResult, ToolCallRecord, TurnRecord};
+
+/// Result of running one scenario against one model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioResult {
+    pub scenario: String,
+    pub model: String,
+    /// Whether every check passed
+    pub passed: bool,
+    /// Fraction of checks passed, 0 when the scenario could not run
+    pub score: f64,
+    pub checks: Vec<CheckResult>,
+    pub turns: Vec<TurnRecord>,
+    pub tool_calls: Vec<ToolCallRecord>,
+    pub duration_ms: u64,
+    /// Why the scenario could not run, e.g. a seed file failed to write
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ScenarioResult {
+    pub fn new(scenario: &str, model: &str, checks: Vec<CheckResult>) -> Self {
+        let passed_checks = checks.iter().filter(|check| check.passed).count();
+        let score = if checks.is_empty() { 1.0 } else { passed_checks as f64 / checks.len() as f64 };
+        Self {
+            scenario: scenario.to_string(),
+            model: model.to_string(),
+            passed: passed_checks == checks.len(),
+            score,
+            checks,
+            turns: Vec::new(),
+            tool_calls: Vec::new(),
+            duration_ms: 0,
+            error: None,
+        }
+    }
+
+    /// Result of a scenario that could not run
+    pub fn failed(scenario: &str, model: &str, error: String) -> Self {
+        Self {
+            passed: false,
+            score: 0.0,
+            error: Some(error),
+            ..Self::new(scenario, model, Vec::new())
+        }
+    }
+}
+
+/// Totals of one model over all scenarios
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelSummary {
+    pub model: String,
+    pub scenarios: usize,
+    pub passed: usize,
+    /// Mean scenario score
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalReport {
+    /// Free-form name of the run, e.g. the prompt revision
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub results: Vec<ScenarioResult>,
+}
+
+impl EvalReport {
+    pub fn new(label: Option<String>, results: Vec<ScenarioResult>) -> Self {
+        Self { label, created_at: Utc::now(), results }
+    }
+
+    /// Models in the order they were run
+    fn models(&self) -> Vec<&str> {
+        let mut models: Vec<&str> = Vec::new();
+        for result in &self.results {
+            if !models.contains(&result.model.as_str()) {
+                models.push(&result.model);
+            }
+        }
+        models
+    }
+
+    pub fn summaries(&self) -> Vec<ModelSummary> {
+        self.models()
+            .into_iter()
+            .map(|model| {
+                let results: Vec<&ScenarioResult> = self.results.iter().filter(|r| r.model == model).collect();
+                ModelSummary {
+                    model: model.to_string(),
+                    scenarios: results.len(),
+                    passed: results.iter().filter(|r| r.passed).count(),
+                    score: results.iter().map(|r| r.score).sum::<f64>() / results.len() as f64,
+                }
+            })
+            .collect()
+    }
+
+    /// Whether every scenario passed on every model
+    pub fn all_passed(&self) -> bool {
+        self.results.iter().all(|result| result.passed)
+    }
+
+    /// Summary table per model, a scenario by model score matrix and the failed checks
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Eval report{}\n", self.label.as_ref().map(|l| format!(": {}", l)).unwrap_or_default());
+        let _ = writeln!(out, "Run at {}\n", self.created_at.to_rfc3339());
+
+        let _ = writeln!(out, "| Model | Passed | Score |\n|---|---|---|");
+        for summary in self.summaries() {
+            let _ = writeln!(out, "| {} | {}/{} | {:.0}% |", summary.model, summary.passed, summary.scenarios, summary.score * 100.0);
+        }
+
+        let models = self.models();
+        let mut scenarios: Vec<&str> = Vec::new();
+        for result in &self.results {
+            if !scenarios.contains(&result.scenario.as_str()) {
+                scenarios.push(&result.scenario);
+            }
+        }
+        let _ = writeln!(out, "\n| Scenario | {} |\n|---|{}", models.join(" | "), "---|".repeat(models.len()));
+        for scenario in &scenarios {
+            let cells: Vec<String> = models
+                .iter()
+                .map(|model| {
+                    self.results
+                        .iter()
+                        .find(|r| &r.scenario == scenario && &r.model == model)
+                        .map(|r| format!("{} {:.0}%", if r.passed { "✓" } else { "✗" }, r.score * 100.0))
+                        .unwrap_or_else(|| "-".to_string())
+                })
+                .collect();
+            let _ = writeln!(out, "| {} | {} |", scenario, cells.join(" | "));
+        }
+
+        let failures: Vec<&ScenarioResult> = self.results.iter().filter(|r| !r.passed).collect();
+        if !failures.is_empty() {
+            let _ = writeln!(out, "\n## Failures\n");
+            for result in failures {
+                let _ = writeln!(out, "**{}** on `{}`", result.scenario, result.model);
+                if let Some(error) = &result.error {
+                    let _ = writeln!(out, "- error: {}", error);
+                }
+                for check in result.checks.iter().filter(|check| !check.passed) {
+                    let _ = writeln!(out, "- {}: {}", check.name, check.detail.as_deref().unwrap_or("failed"));
+                }
+                let _ = writeln!(out);
+            }
+        }
+
+        out
+    }
+
+    /// Score changes since `baseline`, for the runs found in both reports
+    pub fn compare_markdown(&self, baseline: &EvalReport) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# Changes since {}\n",
+            baseline.label.clone().unwrap_or_else(|| baseline.created_at.to_rfc3339())
+        );
+        let _ = writeln!(out, "| Scenario | Model | Before | After |\n|---|---|---|---|");
+
+        let mut regressions = 0;
+        for result in &self.results {
+            let Some(before) = baseline
+                .results
+                .iter()
+                .find(|r| r.scenario == result.scenario && r.model == result.model)
+            else {
+                continue;
+            };
+            let marker = if result.score < before.score {
+                regressions += 1;
+                " ▼"
+            } else if result.score > before.score {
+                " ▲"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                out,
+                "| {} | {} | {:.0}% | {:.0}%{} |",
+                result.scenario,
+                result.model,
+                before.score * 100.0,
+                result.score * 100.0,
+                marker
+            );
+        }
+        let _ = writeln!(out, "\n{} regression(s)", regressions);
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(passed: bool) -> CheckResult {
+        CheckResult { name: "check".to_string(), passed, detail: None }
+    }
+
+    #[test]
+    fn test_scores_and_summaries() {
+        let report = EvalReport::new(Some("v2".to_string()), vec![
+            ScenarioResult::new("write_note", "mock", vec![check(true), check(true)]),
+            ScenarioResult::new("edit_todo", "mock", vec![check(true), check(false)]),
+            ScenarioResult::failed("write_note", "openai:gpt-5-mini", "No API key".to_string()),
+        ]);
+
+        assert_eq!(report.results[1].score, 0.5);
+        assert!(!report.all_passed());
+
+        let summaries = report.summaries();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!((summaries[0].model.as_str(), summaries[0].passed, summaries[0].scenarios), ("mock", 1, 2));
+        assert_eq!(summaries[0].score, 0.75);
+        assert_eq!(summaries[1].score, 0.0);
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("| mock | 1/2 | 75% |"));
+        assert!(markdown.contains("| write_note | ✓ 100% | ✗ 0% |"));
+        assert!(markdown.contains("- error: No API key"));
+    }
+
+    #[test]
+    fn test_compare_marks_regressions() {
+        let baseline = EvalReport::new(Some("v1".to_string()), vec![
+            ScenarioResult::new("write_note", "mock", vec![check(true)]),
+            ScenarioResult::new("edit_todo", "mock", vec![check(false)]),
+        ]);
+        let current = EvalReport::new(None, vec![
+            ScenarioResult::new("write_note", "mock", vec![check(false)]),
+            ScenarioResult::new("edit_todo", "mock", vec![check(true)]),
+            ScenarioResult::new("new_scenario", "mock", vec![check(true)]),
+        ]);
+
+        let comparison = current.compare_markdown(&baseline);
+        assert!(comparison.starts_with("# Changes since v1"));
+        assert!(comparison.contains("| write_note | mock | 100% | 0% ▼ |"));
+        assert!(comparison.contains("| edit_todo | mock | 0% | 100% ▲ |"));
+        assert!(!comparison.contains("new_scenario"));
+        assert!(comparison.contains("1 regression(s)"));
+    }
+}
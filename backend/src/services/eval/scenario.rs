@@ -0,0 +1,425 @@
+//! Eval scenario files and their checks
+//!
+//! A scenario seeds a fresh workspace, sends user turns to a new chat and states
+//! what the agent should have done. Scenarios are YAML files (JSON also works):
+//!
+//! ```yaml
+//! name: write_note
+//! description: Writes the note the user asks for
+//! role: assistant            # planner (default), builder or any other role for chat mode
+//! workspace:
+//!   - path: /notes/todo.md
+//!     content: "- buy milk"
+//! turns:
+//!   - Write a hello note to /notes/hello.md
+//! expect:
+//!   tool_calls:              # in this order, other calls may come in between
+//!     - tool: write
+//!       arguments: { path: /notes/hello.md }
+//!   forbidden_tools: [rm]
+//!   max_tool_calls: 5
+//!   files:
+//!     - path: /notes/hello.md
+//!       contains: [Hello]
+//!   response_contains: [/notes/hello.md]
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Error, Result, ValidationErrors};
+
+/// File extensions loaded as scenarios
+const SCENARIO_EXTENSIONS: [&str; 3] = ["yaml", "yml", "json"];
+
+/// Seconds a turn may take when the scenario does not say
+pub const DEFAULT_TURN_TIMEOUT_SECONDS: u64 = 300;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Identifies the scenario in reports; `mock` runs replay the fixture of this name
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Chat role, as in the create chat request (default: planner)
+    #[serde(default)]
+    pub role: Option<String>,
+    /// Model used unless the run names one
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Plan file of build mode chats
+    #[serde(default)]
+    pub plan_file: Option<String>,
+    /// Files written to the workspace before the first turn
+    #[serde(default)]
+    pub workspace: Vec<SeedFile>,
+    /// User messages, each sent once the previous turn is complete
+    pub turns: Vec<String>,
+    #[serde(default)]
+    pub expect: Expectations,
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeedFile {
+    pub path: String,
+    /// Text, or JSON for structured files
+    pub content: Value,
+}
+
+/// What the agent should have done by the end of the scenario
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Expectations {
+    /// Tool calls expected in this order
+    pub tool_calls: Vec<ExpectedToolCall>,
+    /// Tools that must not be called
+    pub forbidden_tools: Vec<String>,
+    pub max_tool_calls: Option<usize>,
+    /// Workspace files at the end of the scenario
+    pub files: Vec<ExpectedFile>,
+    /// Text the agent's responses must contain, case-insensitively
+    pub response_contains: Vec<String>,
+    /// Fail the scenario when a turn ends with an error (default: true)
+    pub no_errors: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExpectedToolCall {
+    pub tool: String,
+    /// Arguments the call must contain; objects match when every listed key matches
+    #[serde(default)]
+    pub arguments: Option<Value>,
+    /// Required outcome of the call
+    #[serde(default)]
+    pub success: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExpectedFile {
+    pub path: String,
+    /// Whether the file should exist (default: true)
+    #[serde(default)]
+    pub exists: Option<bool>,
+    /// Exact content
+    #[serde(default)]
+    pub equals: Option<String>,
+    /// Text the content must contain
+    #[serde(default)]
+    pub contains: Vec<String>,
+    /// Text the content must not contain
+    #[serde(default)]
+    pub not_contains: Vec<String>,
+}
+
+/// Tool call made by the agent, with its outcome once observed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    pub tool: String,
+    pub arguments: Value,
+    pub success: Option<bool>,
+}
+
+/// One user turn and how the agent answered it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnRecord {
+    pub user: String,
+    /// Text streamed by the agent during the turn
+    pub response: String,
+    pub error: Option<String>,
+}
+
+/// Outcome of one expectation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl CheckResult {
+    fn pass(name: String) -> Self {
+        Self { name, passed: true, detail: None }
+    }
+
+    fn fail(name: String, detail: String) -> Self {
+        Self { name, passed: false, detail: Some(detail) }
+    }
+
+    fn check(name: String, passed: bool, detail: impl FnOnce() -> String) -> Self {
+        if passed { Self::pass(name) } else { Self::fail(name, detail()) }
+    }
+}
+
+impl Scenario {
+    /// Loads a scenario file, as JSON for `.json` files and YAML otherwise
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let scenario: Self = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&text)?
+        } else {
+            serde_yaml::from_str(&text).map_err(|e| invalid(format!("{}: {}", path.display(), e)))?
+        };
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Loads a scenario file, or every scenario file of a directory sorted by name
+    pub fn load_all(path: &Path) -> Result<Vec<Self>> {
+        if !path.is_dir() {
+            return Ok(vec![Self::load(path)?]);
+        }
+
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(path)? {
+            let path = entry?.path();
+            let is_scenario = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| SCENARIO_EXTENSIONS.contains(&ext));
+            if path.is_file() && is_scenario {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        paths.iter().map(|path| Self::load(path)).collect()
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(invalid("Scenario name cannot be empty".to_string()));
+        }
+        if self.turns.is_empty() {
+            return Err(invalid(format!("Scenario '{}' has no turns", self.name)));
+        }
+        Ok(())
+    }
+
+    pub fn turn_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.timeout_seconds.unwrap_or(DEFAULT_TURN_TIMEOUT_SECONDS))
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::Validation(ValidationErrors::Single { field: "scenario".to_string(), message })
+}
+
+impl Expectations {
+    /// Checks the transcript and end state of a run
+    ///
+    /// `files` maps the paths of `self.files` to their content, `None` for missing files.
+    pub fn evaluate(
+        &self,
+        turns: &[TurnRecord],
+        tool_calls: &[ToolCallRecord],
+        files: &HashMap<String, Option<String>>,
+    ) -> Vec<CheckResult> {
+        let mut checks = Vec::new();
+
+        if self.no_errors.unwrap_or(true) {
+            let errors: Vec<&str> = turns.iter().filter_map(|turn| turn.error.as_deref()).collect();
+            checks.push(CheckResult::check("no_errors".to_string(), errors.is_empty(), || errors.join("; ")));
+        }
+
+        // Expected calls are matched in order, each after the previous match
+        let mut next = 0;
+        for (index, expected) in self.tool_calls.iter().enumerate() {
+            let name = format!("tool_call[{}]: {}", index, expected.tool);
+            match tool_calls[next..].iter().position(|call| expected.matches(call)) {
+                Some(position) => {
+                    next += position + 1;
+                    checks.push(CheckResult::pass(name));
+                }
+                None => {
+                    let called: Vec<&str> = tool_calls.iter().map(|call| call.tool.as_str()).collect();
+                    checks.push(CheckResult::fail(name, format!("No matching call in order, calls were [{}]", called.join(", "))));
+                }
+            }
+        }
+
+        for tool in &self.forbidden_tools {
+            let count = tool_calls.iter().filter(|call| &call.tool == tool).count();
+            checks.push(CheckResult::check(format!("forbidden_tool: {}", tool), count == 0, || {
+                format!("Called {} time(s)", count)
+            }));
+        }
+
+        if let Some(max) = self.max_tool_calls {
+            checks.push(CheckResult::check("max_tool_calls".to_string(), tool_calls.len() <= max, || {
+                format!("{} calls, at most {} expected", tool_calls.len(), max)
+            }));
+        }
+
+        for expected in &self.files {
+            checks.push(expected.check(files.get(&expected.path).cloned().flatten()));
+        }
+
+        let responses = turns.iter().map(|turn| turn.response.as_str()).collect::<Vec<_>>().join("\n").to_lowercase();
+        for text in &self.response_contains {
+            checks.push(CheckResult::check(format!("response_contains: {}", text), responses.contains(&text.to_lowercase()), || {
+                "Not found in the agent's responses".to_string()
+            }));
+        }
+
+        checks
+    }
+}
+
+impl ExpectedToolCall {
+    fn matches(&self, call: &ToolCallRecord) -> bool {
+        call.tool == self.tool
+            && self.success.is_none_or(|success| call.success == Some(success))
+            && self.arguments.as_ref().is_none_or(|arguments| json_contains(&call.arguments, arguments))
+    }
+}
+
+impl ExpectedFile {
+    fn check(&self, content: Option<String>) -> CheckResult {
+        let name = format!("file: {}", self.path);
+        let should_exist = self.exists.unwrap_or(true);
+
+        let Some(content) = content else {
+            return CheckResult::check(name, !should_exist, || "File does not exist".to_string());
+        };
+        if !should_exist {
+            return CheckResult::fail(name, "File exists".to_string());
+        }
+
+        if let Some(equals) = &self.equals
+            && content.trim_end() != equals.trim_end()
+        {
+            return CheckResult::fail(name, "Content differs from the expected content".to_string());
+        }
+        if let Some(missing) = self.contains.iter().find(|text| !content.contains(text.as_str())) {
+            return CheckResult::fail(name, format!("Content does not contain '{}'", missing));
+        }
+        if let Some(present) = self.not_contains.iter().find(|text| content.contains(text.as_str())) {
+            return CheckResult::fail(name, format!("Content contains '{}'", present));
+        }
+        CheckResult::pass(name)
+    }
+}
+
+/// Whether `actual` contains `expected`: objects match on the keys of `expected`,
+/// everything else must be equal
+fn json_contains(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => expected
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|actual| json_contains(actual, value))),
+        (Value::Array(actual), Value::Array(expected)) => {
+            actual.len() == expected.len() && actual.iter().zip(expected).all(|(a, e)| json_contains(a, e))
+        }
+        _ => actual == expected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(tool: &str, arguments: Value, success: bool) -> ToolCallRecord {
+        ToolCallRecord { tool: tool.to_string(), arguments, success: Some(success) }
+    }
+
+    fn turn(response: &str) -> TurnRecord {
+        TurnRecord { user: "Do it".to_string(), response: response.to_string(), error: None }
+    }
+
+    #[test]
+    fn test_parse_yaml_scenario() {
+        let scenario: Scenario = serde_yaml::from_str(
+            r#"
+name: edit_todo
+workspace:
+  - path: /todo.md
+    content: "- buy milk"
+turns: [Add eggs to my todo list]
+expect:
+  tool_calls:
+    - tool: edit
+      arguments: { path: /todo.md }
+  files:
+    - path: /todo.md
+      contains: [eggs]
+"#,
+        )
+        .unwrap();
+        assert_eq!(scenario.workspace[0].content, json!("- buy milk"));
+        assert_eq!(scenario.expect.tool_calls[0].arguments, Some(json!({"path": "/todo.md"})));
+        assert!(scenario.validate().is_ok());
+
+        let unknown = serde_yaml::from_str::<Scenario>("name: x\nturns: [hi]\nexpect:\n  tool_call: []\n");
+        assert!(unknown.is_err(), "Misspelled expectations should not be ignored");
+    }
+
+    #[test]
+    fn test_tool_calls_match_in_order() {
+        let expect = Expectations {
+            tool_calls: vec![
+                ExpectedToolCall { tool: "read".to_string(), arguments: Some(json!({"path": "/a.md"})), success: None },
+                ExpectedToolCall { tool: "write".to_string(), arguments: None, success: Some(true) },
+            ],
+            forbidden_tools: vec!["rm".to_string()],
+            max_tool_calls: Some(3),
+            ..Default::default()
+        };
+
+        let in_order = vec![
+            call("ls", json!({}), true),
+            call("read", json!({"path": "/a.md", "offset": 0}), true),
+            call("write", json!({"path": "/b.md"}), true),
+        ];
+        let checks = expect.evaluate(&[turn("done")], &in_order, &HashMap::new());
+        assert!(checks.iter().all(|check| check.passed), "{:?}", checks);
+
+        let out_of_order = vec![
+            call("write", json!({"path": "/b.md"}), true),
+            call("read", json!({"path": "/a.md"}), true),
+            call("rm", json!({"path": "/b.md"}), true),
+            call("write", json!({"path": "/b.md"}), false),
+        ];
+        let failed: Vec<String> = expect
+            .evaluate(&[turn("done")], &out_of_order, &HashMap::new())
+            .into_iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.name)
+            .collect();
+        assert_eq!(failed, vec!["tool_call[1]: write", "forbidden_tool: rm", "max_tool_calls"]);
+    }
+
+    #[test]
+    fn test_file_and_response_checks() {
+        let expect = Expectations {
+            files: vec![
+                ExpectedFile { path: "/hello.md".to_string(), exists: None, equals: Some("Hello".to_string()), contains: vec![], not_contains: vec![] },
+                ExpectedFile { path: "/gone.md".to_string(), exists: Some(false), equals: None, contains: vec![], not_contains: vec![] },
+                ExpectedFile { path: "/todo.md".to_string(), exists: None, equals: None, contains: vec!["eggs".to_string()], not_contains: vec!["milk".to_string()] },
+            ],
+            response_contains: vec!["HELLO.md".to_string()],
+            ..Default::default()
+        };
+        let files = HashMap::from([
+            ("/hello.md".to_string(), Some("Hello\n".to_string())),
+            ("/gone.md".to_string(), None),
+            ("/todo.md".to_string(), Some("- milk\n- eggs".to_string())),
+        ]);
+        let mut errored = turn("I wrote /hello.md");
+        errored.error = Some("AI Engine Error: timeout".to_string());
+
+        let checks = expect.evaluate(&[errored], &[], &files);
+        let failed: Vec<&str> = checks.iter().filter(|check| !check.passed).map(|check| check.name.as_str()).collect();
+        assert_eq!(failed, vec!["no_errors", "file: /todo.md"]);
+        assert_eq!(checks.len(), 5);
+    }
+}
@@ -131,6 +131,11 @@ pub async fn create_file_with_content(
     storage: &FileStorageService,
     request: CreateFileRequest,
 ) -> Result<FileWithContent> {
+    // Agent files are loaded as chat personas, reject broken definitions up front
+    if request.file_type == FileType::Agent {
+        crate::services::agent_files::validate_agent_content(&request.content)?;
+    }
+
     // 1. Start transaction
     let mut tx = conn.begin().await.map_err(|e| {
         Error::Internal(format!("Failed to begin transaction: {}", e))
@@ -306,6 +311,9 @@ pub async fn create_version(
     let file = files::get_file_by_id(conn, file_id).await?;
 
     let content = request.content;
+    if file.file_type == FileType::Agent {
+        crate::services::agent_files::validate_agent_content(&content)?;
+    }
 
     // PERSISTENCE: Write to disk to get hash
     // For strings: write as raw bytes (preserves newlines, special chars)
@@ -0,0 +1,239 @@
+//! Images for vision-capable models.
+//!
+//! Images reach the model from three places: image files attached to a chat
+//! message, images pasted into the message, and image files the `read` tool
+//! returns. All of them go through [`prepare_image`], which checks the data is a
+//! PNG, JPEG, GIF or WebP image and scales it down so that its longest side is at
+//! most [`MAX_IMAGE_DIMENSION`] pixels, what providers process without scaling it
+//! again themselves. Images already small enough are sent as they are.
+
+use crate::error::{Error, Result, ValidationErrors};
+use crate::models::chat::ChatAttachment;
+use crate::models::files::ImageContent;
+use crate::models::requests::ImageUpload;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageReader, Limits};
+use std::io::Cursor;
+
+/// Longest side of an image sent to a model, in pixels
+pub const MAX_IMAGE_DIMENSION: u32 = 1568;
+
+/// Largest image accepted, before resizing, in bytes
+pub const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+
+/// Most images pasted into a single chat message
+pub const MAX_IMAGES_PER_MESSAGE: usize = 10;
+
+/// Bytes read from the start of a file to tell whether it is an image
+pub const FORMAT_SNIFF_BYTES: usize = 32;
+
+/// Largest encoded image sent to a model, in bytes
+///
+/// Images within [`MAX_IMAGE_DIMENSION`] but over this size, such as large
+/// uncompressed PNGs, are re-encoded as JPEG.
+const MAX_SENT_IMAGE_BYTES: usize = 4 * 1024 * 1024;
+
+/// Largest width or height decoded, so a small file cannot claim a huge canvas
+const MAX_DECODED_DIMENSION: u32 = 16_384;
+
+/// Quality of JPEGs produced when resizing photos
+const JPEG_QUALITY: u8 = 85;
+
+/// MIME type of data in an image format models accept, `None` for anything else
+pub fn detect_media_type(bytes: &[u8]) -> Option<&'static str> {
+    match image::guess_format(bytes).ok()? {
+        ImageFormat::Png => Some("image/png"),
+        ImageFormat::Jpeg => Some("image/jpeg"),
+        ImageFormat::Gif => Some("image/gif"),
+        ImageFormat::WebP => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Decodes a pasted image, given as base64 or as a `data:` URL
+pub fn decode_base64_image(data: &str) -> Result<Vec<u8>> {
+    let encoded = match data.split_once(";base64,") {
+        Some((prefix, encoded)) if prefix.starts_with("data:") => encoded,
+        _ => data,
+    };
+    // Base64 takes 4 characters for every 3 bytes
+    if encoded.len() / 4 * 3 > MAX_IMAGE_BYTES {
+        return Err(invalid(format!("Images cannot be larger than {} MiB", MAX_IMAGE_BYTES / (1024 * 1024))));
+    }
+    let encoded: String = encoded.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    BASE64
+        .decode(encoded)
+        .map_err(|e| invalid(format!("Image data is not valid base64: {}", e)))
+}
+
+/// Checks an image and scales it down to fit [`MAX_IMAGE_DIMENSION`]
+///
+/// Decoding and resizing take a while for large images, async code runs this
+/// with [`prepare_image_blocking`].
+pub fn prepare_image(bytes: &[u8]) -> Result<ImageContent> {
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(invalid(format!("Images cannot be larger than {} MiB", MAX_IMAGE_BYTES / (1024 * 1024))));
+    }
+    let media_type = detect_media_type(bytes)
+        .ok_or_else(|| invalid("Only PNG, JPEG, GIF and WebP images are supported".to_string()))?;
+
+    let reader = || {
+        let mut reader = ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .map_err(|e| invalid(format!("Image could not be read: {}", e)))?;
+        let mut limits = Limits::default();
+        limits.max_image_width = Some(MAX_DECODED_DIMENSION);
+        limits.max_image_height = Some(MAX_DECODED_DIMENSION);
+        reader.limits(limits);
+        Ok::<_, Error>(reader)
+    };
+
+    let (width, height) = reader()?
+        .into_dimensions()
+        .map_err(|e| invalid(format!("Image could not be read: {}", e)))?;
+    if width.max(height) <= MAX_IMAGE_DIMENSION && bytes.len() <= MAX_SENT_IMAGE_BYTES {
+        return Ok(ImageContent {
+            media_type: media_type.to_string(),
+            width,
+            height,
+            data: BASE64.encode(bytes),
+        });
+    }
+
+    let decoded = reader()?
+        .decode()
+        .map_err(|e| invalid(format!("Image could not be decoded: {}", e)))?;
+    let resized = if width.max(height) > MAX_IMAGE_DIMENSION {
+        decoded.resize(MAX_IMAGE_DIMENSION, MAX_IMAGE_DIMENSION, FilterType::Triangle)
+    } else {
+        decoded
+    };
+    encode(&resized, media_type)
+}
+
+/// [`prepare_image`] on the blocking thread pool
+pub async fn prepare_image_blocking(bytes: Vec<u8>) -> Result<ImageContent> {
+    tokio::task::spawn_blocking(move || prepare_image(&bytes))
+        .await
+        .map_err(|e| Error::Internal(format!("Image processing failed: {}", e)))?
+}
+
+/// Resolves the images pasted into a chat request into `ChatAttachment::Image`
+/// entries, resized once here so the message stores what models are sent
+///
+/// Fails with a validation error if any of them is not a supported image.
+pub async fn resolve_image_attachments(uploads: &[ImageUpload]) -> Result<Vec<ChatAttachment>> {
+    if uploads.len() > MAX_IMAGES_PER_MESSAGE {
+        return Err(invalid(format!("A message can hold at most {} images", MAX_IMAGES_PER_MESSAGE)));
+    }
+
+    let mut attachments = Vec::with_capacity(uploads.len());
+    for upload in uploads {
+        let bytes = decode_base64_image(&upload.data)?;
+        attachments.push(ChatAttachment::Image {
+            name: upload.name.clone(),
+            image: prepare_image_blocking(bytes).await?,
+        });
+    }
+    Ok(attachments)
+}
+
+/// Encodes a resized image, keeping PNG for images with transparency or that were
+/// PNG or GIF already (screenshots, diagrams), JPEG for photos
+fn encode(image: &DynamicImage, original_media_type: &str) -> Result<ImageContent> {
+    let keep_png = image.color().has_alpha() || matches!(original_media_type, "image/png" | "image/gif");
+
+    let mut encoded = Vec::new();
+    if keep_png {
+        image
+            .write_to(&mut Cursor::new(&mut encoded), ImageFormat::Png)
+            .map_err(|e| Error::Internal(format!("Failed to encode image: {}", e)))?;
+    }
+    let media_type = if keep_png && encoded.len() <= MAX_SENT_IMAGE_BYTES {
+        "image/png"
+    } else {
+        encoded.clear();
+        let rgb = DynamicImage::ImageRgb8(image.to_rgb8());
+        rgb.write_with_encoder(JpegEncoder::new_with_quality(&mut encoded, JPEG_QUALITY))
+            .map_err(|e| Error::Internal(format!("Failed to encode image: {}", e)))?;
+        "image/jpeg"
+    };
+
+    Ok(ImageContent {
+        media_type: media_type.to_string(),
+        width: image.width(),
+        height: image.height(),
+        data: BASE64.encode(&encoded),
+    })
+}
+
+fn invalid(message: String) -> Error {
+    Error::Validation(ValidationErrors::Single {
+        field: "image".to_string(),
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbaImage::from_pixel(width, height, Rgba([10, 20, 30, 128]));
+        let mut bytes = Vec::new();
+        image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png).unwrap();
+        bytes
+    }
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, y| Rgb([(x % 256) as u8, (y % 256) as u8, 90]));
+        let mut bytes = Vec::new();
+        image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Jpeg).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_small_images_are_sent_unchanged() {
+        let bytes = png(40, 30);
+        let image = prepare_image(&bytes).unwrap();
+
+        assert_eq!(image.media_type, "image/png");
+        assert_eq!((image.width, image.height), (40, 30));
+        assert_eq!(BASE64.decode(&image.data).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_large_images_are_scaled_down() {
+        let image = prepare_image(&jpeg(3136, 1000)).unwrap();
+        assert_eq!(image.media_type, "image/jpeg");
+        assert_eq!(image.width, MAX_IMAGE_DIMENSION);
+        assert_eq!(image.height, 500);
+
+        let image = prepare_image(&png(1000, 2000)).unwrap();
+        assert_eq!(image.media_type, "image/png");
+        assert_eq!((image.width, image.height), (784, MAX_IMAGE_DIMENSION));
+        let decoded = image::load_from_memory(&BASE64.decode(&image.data).unwrap()).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (784, MAX_IMAGE_DIMENSION));
+    }
+
+    #[test]
+    fn test_non_images_are_rejected() {
+        assert!(detect_media_type(b"# Notes\n").is_none());
+        assert!(matches!(prepare_image(b"# Notes\n"), Err(Error::Validation(_))));
+        assert!(matches!(prepare_image(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"), Err(Error::Validation(_))));
+    }
+
+    #[test]
+    fn test_decode_base64_image() {
+        let bytes = png(2, 2);
+        let encoded = BASE64.encode(&bytes);
+
+        assert_eq!(decode_base64_image(&encoded).unwrap(), bytes);
+        assert_eq!(decode_base64_image(&format!("data:image/png;base64,{}", encoded)).unwrap(), bytes);
+        assert!(decode_base64_image("not base64!").is_err());
+    }
+}
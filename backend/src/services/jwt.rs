@@ -13,6 +13,9 @@ pub struct Claims {
     pub exp: i64,
     /// Issued at time as Unix timestamp
     pub iat: i64,
+    /// Session ID - refresh-token session the token was issued for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
 }
 
 /// Generates a JWT access token for a user
@@ -35,6 +38,24 @@ pub struct Claims {
 /// # Ok::<(), buildscale::error::Error>(())
 /// ```
 pub fn generate_jwt(user_id: Uuid, secret: &str, expiration_minutes: i64) -> Result<String> {
+    encode_claims(user_id, None, secret, expiration_minutes)
+}
+
+/// Generates a JWT access token bound to a login session
+///
+/// The `sid` claim lets session management endpoints tell which of the user's
+/// sessions made a request.
+///
+/// # Arguments
+/// * `user_id` - The user's UUID
+/// * `session_id` - ID of the refresh-token session
+/// * `secret` - The JWT secret key for signing
+/// * `expiration_minutes` - Token expiration time in minutes (from config)
+pub fn generate_session_jwt(user_id: Uuid, session_id: Uuid, secret: &str, expiration_minutes: i64) -> Result<String> {
+    encode_claims(user_id, Some(session_id), secret, expiration_minutes)
+}
+
+fn encode_claims(user_id: Uuid, session_id: Option<Uuid>, secret: &str, expiration_minutes: i64) -> Result<String> {
     let now = Utc::now();
     let expiration = now + Duration::minutes(expiration_minutes);
 
@@ -42,6 +63,7 @@ pub fn generate_jwt(user_id: Uuid, secret: &str, expiration_minutes: i64) -> Res
         sub: user_id.to_string(),
         exp: expiration.timestamp(),
         iat: now.timestamp(),
+        sid: session_id.map(|id| id.to_string()),
     };
 
     encode(
@@ -120,6 +142,28 @@ pub fn get_user_id_from_token(token: &str, secret: &str) -> Result<Uuid> {
         .map_err(|_| Error::Internal("Invalid user_id in token".to_string()))
 }
 
+/// Validates JWT from header OR cookie and returns the user_id and session ID
+///
+/// The session ID is `None` for tokens issued without a session.
+pub fn authenticate_jwt_session_from_anywhere(
+    auth_header: Option<&str>,
+    cookie_value: Option<&str>,
+    secret: &str,
+) -> Result<(Uuid, Option<Uuid>)> {
+    let token = crate::services::cookies::extract_jwt_token(auth_header, cookie_value)?;
+    let claims = verify_jwt(&token, secret)?;
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| Error::Internal("Invalid user_id in token".to_string()))?;
+    let session_id = claims
+        .sid
+        .map(|sid| {
+            Uuid::parse_str(&sid).map_err(|_| Error::Authentication("Invalid session in token".to_string()))
+        })
+        .transpose()?;
+
+    Ok((user_id, session_id))
+}
+
 /// Validates JWT from Authorization header and returns user_id
 /// Format: "Authorization: Bearer <token>"
 ///
@@ -248,6 +292,23 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_session_jwt_carries_session_id() {
+        let user_id = Uuid::now_v7();
+        let session_id = Uuid::now_v7();
+        let secret = "test-secret-key-for-testing";
+        let token = generate_session_jwt(user_id, session_id, secret, 15).unwrap();
+        let header = format!("Bearer {}", token);
+        let (extracted_user, extracted_session) =
+            authenticate_jwt_session_from_anywhere(Some(&header), None, secret).unwrap();
+        assert_eq!(extracted_user, user_id);
+        assert_eq!(extracted_session, Some(session_id));
+
+        let token = generate_jwt(user_id, secret, 15).unwrap();
+        let (_, extracted_session) = authenticate_jwt_session_from_anywhere(None, Some(&token), secret).unwrap();
+        assert_eq!(extracted_session, None);
+    }
+
     #[test]
     fn test_get_user_id_from_token() {
         let user_id = Uuid::now_v7();
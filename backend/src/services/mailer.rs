@@ -0,0 +1,135 @@
+//! Outgoing email
+//!
+//! Mail goes through a [`Mailer`]: an [`SmtpMailer`] when an SMTP server is
+//! configured, a [`LogMailer`] otherwise. Mail carries secrets such as password
+//! reset links, so the `LogMailer` only logs who a mail was for. Its body is
+//! logged at debug level when `log_links` is set, for local development.
+
+use crate::config::MailConfig;
+use crate::error::{Error, Result};
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use secrecy::ExposeSecret;
+use std::sync::Arc;
+
+/// Plain text email
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Sends email
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: &Email) -> Result<()>;
+}
+
+/// Creates the mailer for the configuration
+pub fn from_config(config: &MailConfig) -> Result<Arc<dyn Mailer>> {
+    let smtp_url = config.smtp_url.expose_secret();
+    if smtp_url.is_empty() {
+        Ok(Arc::new(LogMailer { log_links: config.log_links }))
+    } else {
+        Ok(Arc::new(SmtpMailer::new(smtp_url, &config.from)?))
+    }
+}
+
+/// Checks that a mailer can be created for the configuration
+pub fn validate_config(config: &MailConfig) -> Result<()> {
+    from_config(config).map(|_| ())
+}
+
+/// Sends mail through an SMTP server
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(smtp_url: &str, from: &str) -> Result<Self> {
+        let from = from
+            .parse()
+            .map_err(|e| Error::Internal(format!("Invalid BUILDSCALE__MAIL__FROM '{}': {}", from, e)))?;
+        // The URL holds the server's password, keep it out of the error
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(smtp_url)
+            .map_err(|e| Error::Internal(format!("Invalid BUILDSCALE__MAIL__SMTP_URL: {}", e)))?
+            .build();
+        Ok(Self { transport, from })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: &Email) -> Result<()> {
+        let to: Mailbox = email
+            .to
+            .parse()
+            .map_err(|e| Error::Internal(format!("Invalid recipient '{}': {}", email.to, e)))?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(&email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body.clone())
+            .map_err(|e| Error::Internal(format!("Failed to build mail: {}", e)))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| Error::Internal(format!("Failed to send mail: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Stands in when no SMTP server is configured, mail is not sent
+pub struct LogMailer {
+    /// Log mail bodies at debug level, for local development only
+    pub log_links: bool,
+}
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: &Email) -> Result<()> {
+        tracing::warn!(
+            to = %email.to,
+            subject = %email.subject,
+            "No SMTP server configured, mail not sent"
+        );
+        if self.log_links {
+            tracing::debug!(to = %email.to, body = %email.body, "Unsent mail");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_config_validates_settings() {
+        assert!(validate_config(&MailConfig::default()).is_ok());
+
+        let config = MailConfig {
+            smtp_url: "smtp://mail.example.com:587".to_string().into(),
+            ..Default::default()
+        };
+        assert!(validate_config(&config).is_ok());
+
+        let config = MailConfig {
+            smtp_url: "not a url".to_string().into(),
+            ..Default::default()
+        };
+        assert!(validate_config(&config).is_err());
+
+        let config = MailConfig {
+            smtp_url: "smtp://mail.example.com".to_string().into(),
+            from: "not an address".to_string(),
+            ..Default::default()
+        };
+        assert!(validate_config(&config).is_err());
+    }
+}
@@ -0,0 +1,559 @@
+//! Model Context Protocol client.
+//!
+//! Speaks JSON-RPC 2.0 to an MCP server over one of two transports:
+//! - stdio: the server is a child process, one JSON message per line
+//! - streamable HTTP: every message is POSTed to the server URL, the answer is a
+//!   JSON body or a `text/event-stream` carrying the response as one of its events
+//!
+//! Only what agents need is implemented: the `initialize` handshake,
+//! `tools/list` and `tools/call`.
+
+use crate::error::{Error, Result};
+use crate::models::mcp::{McpServer, McpTool, McpTransport};
+use crate::utils::net;
+use futures::StreamExt;
+use reqwest::header::{ACCEPT, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+/// Protocol revision sent in the handshake
+pub const MCP_PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Longest tool name the model providers accept
+const MAX_TOOL_NAME_LENGTH: usize = 64;
+
+/// Upper bound on `tools/list` pages, protects against servers that never stop paginating
+const MAX_TOOL_LIST_PAGES: usize = 20;
+
+/// Hex digits of the hash that tells apart tool names changed to fit providers
+const TOOL_NAME_HASH_LENGTH: usize = 8;
+
+/// Variables a stdio server inherits from this process, everything else comes from its settings
+const INHERITED_ENV_VARS: &[&str] = &["PATH", "HOME"];
+
+/// Name the agent calls a server's tool by: `mcp__{server}__{tool}`
+///
+/// Characters providers reject in tool names are replaced with `_` and long names
+/// are truncated. A name changed that way ends in `_` and a short hash of the
+/// original, so `a.b` and `a_b`, or two long names sharing a prefix, stay apart.
+pub fn namespaced_tool_name(server_name: &str, tool_name: &str) -> String {
+    let sanitized: String = tool_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect();
+    let mut name = format!("mcp__{}__{}", server_name, sanitized);
+    if sanitized == tool_name && name.len() <= MAX_TOOL_NAME_LENGTH {
+        return name;
+    }
+
+    let hash = hex::encode(Sha256::digest(tool_name.as_bytes()));
+    name.truncate(MAX_TOOL_NAME_LENGTH - TOOL_NAME_HASH_LENGTH - 1);
+    format!("{}_{}", name, &hash[..TOOL_NAME_HASH_LENGTH])
+}
+
+/// A connection to one MCP server
+pub struct McpClient {
+    server_name: String,
+    timeout: Duration,
+    transport: Transport,
+    next_id: AtomicU64,
+    closed: AtomicBool,
+}
+
+enum Transport {
+    Stdio(Box<Mutex<StdioConnection>>),
+    Http(HttpConnection),
+}
+
+struct StdioConnection {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+struct HttpConnection {
+    client: reqwest::Client,
+    url: String,
+    headers: HeaderMap,
+    /// Session assigned by the server in the handshake (`Mcp-Session-Id`)
+    session_id: std::sync::Mutex<Option<String>>,
+}
+
+impl McpClient {
+    /// Starts or connects to the server and performs the `initialize` handshake
+    pub async fn connect(server: &McpServer) -> Result<Self> {
+        let timeout = Duration::from_secs(server.timeout_seconds.max(1) as u64);
+        let mcp_error = |message: String| Error::Mcp(format!("'{}': {}", server.name, message));
+
+        let transport = match server.transport {
+            McpTransport::Stdio => {
+                let command = server
+                    .command
+                    .as_deref()
+                    .ok_or_else(|| mcp_error("stdio server has no command".to_string()))?;
+
+                // Secrets of this server, such as database or provider credentials,
+                // must not leak into a command the workspace chose
+                let inherited = INHERITED_ENV_VARS
+                    .iter()
+                    .filter_map(|key| std::env::var_os(key).map(|value| (*key, value)));
+                let mut child = Command::new(command)
+                    .args(&server.args)
+                    .env_clear()
+                    .envs(inherited)
+                    .envs(server.env.0.iter())
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .map_err(|e| mcp_error(format!("failed to start '{}': {}", command, e)))?;
+
+                let stdin = child.stdin.take().ok_or_else(|| mcp_error("no stdin".to_string()))?;
+                let stdout = child.stdout.take().ok_or_else(|| mcp_error("no stdout".to_string()))?;
+
+                Transport::Stdio(Box::new(Mutex::new(StdioConnection {
+                    child,
+                    stdin,
+                    stdout: BufReader::new(stdout).lines(),
+                })))
+            }
+            McpTransport::Http => {
+                let url = server
+                    .url
+                    .clone()
+                    .ok_or_else(|| mcp_error("http server has no url".to_string()))?;
+
+                let mut headers = HeaderMap::new();
+                for (name, value) in server.headers.0.iter() {
+                    let name = HeaderName::from_bytes(name.as_bytes())
+                        .map_err(|_| mcp_error(format!("invalid header name '{}'", name)))?;
+                    let value = HeaderValue::from_str(value)
+                        .map_err(|_| mcp_error(format!("invalid value of header '{}'", name)))?;
+                    headers.insert(name, value);
+                }
+
+                let client = net::public_client_builder()
+                    .timeout(timeout)
+                    .build()
+                    .map_err(|e| mcp_error(format!("failed to build HTTP client: {}", e)))?;
+
+                Transport::Http(HttpConnection {
+                    client,
+                    url,
+                    headers,
+                    session_id: std::sync::Mutex::new(None),
+                })
+            }
+        };
+
+        let client = Self {
+            server_name: server.name.clone(),
+            timeout,
+            transport,
+            next_id: AtomicU64::new(1),
+            closed: AtomicBool::new(false),
+        };
+        client.initialize().await?;
+
+        Ok(client)
+    }
+
+    pub fn server_name(&self) -> &str {
+        &self.server_name
+    }
+
+    /// True once the connection failed or was closed, the pool then reconnects
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    /// Closes the connection, a stdio server process is killed
+    ///
+    /// Requests in flight finish, later requests fail.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        if let Transport::Stdio(connection) = &self.transport
+            && let Ok(mut connection) = connection.try_lock()
+        {
+            let _ = connection.child.start_kill();
+        }
+    }
+
+    /// Lists the server's tools, following pagination
+    pub async fn list_tools(&self) -> Result<Vec<McpTool>> {
+        let mut tools = Vec::new();
+        let mut names = HashSet::new();
+        let mut cursor: Option<String> = None;
+
+        for _ in 0..MAX_TOOL_LIST_PAGES {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let result = self.request("tools/list", params).await?;
+
+            for tool in result.get("tools").and_then(Value::as_array).into_iter().flatten() {
+                let Some(remote_name) = tool.get("name").and_then(Value::as_str) else {
+                    continue;
+                };
+                let name = namespaced_tool_name(&self.server_name, remote_name);
+                if !names.insert(name.clone()) {
+                    tracing::warn!(
+                        server = %self.server_name,
+                        tool = %remote_name,
+                        name = %name,
+                        "[MCP] Skipping tool whose name is already taken"
+                    );
+                    continue;
+                }
+                tools.push(McpTool {
+                    name,
+                    remote_name: remote_name.to_string(),
+                    description: tool.get("description").and_then(Value::as_str).map(str::to_string),
+                    input_schema: tool
+                        .get("inputSchema")
+                        .cloned()
+                        .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+                    read_only: tool
+                        .pointer("/annotations/readOnlyHint")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                });
+            }
+
+            cursor = result.get("nextCursor").and_then(Value::as_str).map(str::to_string);
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok(tools)
+    }
+
+    /// Calls a tool and returns the raw `tools/call` result
+    /// (`{"content": [...], "isError": bool, "structuredContent": ...}`)
+    pub async fn call_tool(&self, remote_name: &str, arguments: Value) -> Result<Value> {
+        self.request("tools/call", json!({ "name": remote_name, "arguments": arguments }))
+            .await
+    }
+
+    async fn initialize(&self) -> Result<()> {
+        let result = self
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "buildscale", "version": env!("CARGO_PKG_VERSION") }
+                }),
+            )
+            .await?;
+
+        if result.pointer("/capabilities/tools").is_none() {
+            tracing::warn!(
+                server = %self.server_name,
+                "[MCP] Server does not declare the tools capability"
+            );
+        }
+
+        self.send(None, &json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await?;
+        Ok(())
+    }
+
+    /// Sends a request and waits for its response, within the server's timeout
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        if self.is_closed() {
+            return Err(self.error("connection is closed, the server was disabled or changed"));
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+
+        let response = tokio::time::timeout(self.timeout, self.send(Some(id), &message))
+            .await
+            .map_err(|_| {
+                self.error(&format!("{} timed out after {} seconds", method, self.timeout.as_secs()))
+            })??
+            .ok_or_else(|| self.error(&format!("no response to {}", method)))?;
+
+        if let Some(error) = response.get("error") {
+            let message = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+            return Err(self.error(&format!("{} failed: {}", method, message)));
+        }
+
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    /// Writes a message, and for requests (`id` set) reads until the matching response
+    async fn send(&self, id: Option<u64>, message: &Value) -> Result<Option<Value>> {
+        let result = match &self.transport {
+            Transport::Stdio(connection) => {
+                let mut connection = connection.lock().await;
+                self.send_stdio(&mut connection, id, message).await
+            }
+            Transport::Http(connection) => self.send_http(connection, id, message).await,
+        };
+
+        if let Err(ref e) = result {
+            tracing::warn!(server = %self.server_name, error = %e, "[MCP] Transport failed");
+        }
+        result
+    }
+
+    async fn send_stdio(
+        &self,
+        connection: &mut StdioConnection,
+        id: Option<u64>,
+        message: &Value,
+    ) -> Result<Option<Value>> {
+        let result = async {
+            write_line(&mut connection.stdin, message).await?;
+
+            let Some(id) = id else {
+                return Ok(None);
+            };
+
+            loop {
+                let line = connection
+                    .stdout
+                    .next_line()
+                    .await?
+                    .ok_or_else(|| self.error("server closed the connection"))?;
+
+                // Servers may log to stdout, skip anything that is not JSON-RPC
+                let Ok(incoming) = serde_json::from_str::<Value>(&line) else {
+                    continue;
+                };
+                if is_response_to(&incoming, id) {
+                    return Ok(Some(incoming));
+                }
+                if let Some(reply) = reply_to_server_request(&incoming) {
+                    write_line(&mut connection.stdin, &reply).await?;
+                }
+            }
+        }
+        .await;
+
+        // A broken pipe or an exited process cannot recover
+        if result.is_err() {
+            self.closed.store(true, Ordering::Relaxed);
+        }
+        result
+    }
+
+    async fn send_http(
+        &self,
+        connection: &HttpConnection,
+        id: Option<u64>,
+        message: &Value,
+    ) -> Result<Option<Value>> {
+        let session_id = connection.session_id.lock().ok().and_then(|session| session.clone());
+
+        let mut request = connection
+            .client
+            .post(&connection.url)
+            .headers(connection.headers.clone())
+            .header(ACCEPT, "application/json, text/event-stream")
+            .header("MCP-Protocol-Version", MCP_PROTOCOL_VERSION)
+            .json(message);
+        if let Some(ref session_id) = session_id {
+            request = request.header("Mcp-Session-Id", session_id);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| self.error(&format!("request failed: {}", net::request_error(&e))))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            // The server forgot our session, reconnect on the next use
+            if status == reqwest::StatusCode::NOT_FOUND && session_id.is_some() {
+                self.closed.store(true, Ordering::Relaxed);
+            }
+            return Err(self.error(&format!("server responded with HTTP {}", status)));
+        }
+
+        if let Some(session_id) = response.headers().get("mcp-session-id").and_then(|v| v.to_str().ok())
+            && let Ok(mut session) = connection.session_id.lock()
+        {
+            *session = Some(session_id.to_string());
+        }
+
+        let Some(id) = id else {
+            return Ok(None);
+        };
+
+        let is_event_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+
+        if !is_event_stream {
+            let body: Value = response
+                .json()
+                .await
+                .map_err(|e| self.error(&format!("invalid JSON response: {}", e)))?;
+            return Ok(Some(body));
+        }
+
+        // Read events until the response arrives, the server may send notifications first
+        let mut stream = response.bytes_stream();
+        let mut buffer: Vec<u8> = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| self.error(&format!("event stream failed: {}", e)))?;
+            buffer.extend(chunk.iter().filter(|&&b| b != b'\r'));
+
+            while let Some(event) = next_sse_event(&mut buffer) {
+                if let Some(incoming) = sse_event_data(&event).and_then(|data| serde_json::from_str::<Value>(&data).ok())
+                    && is_response_to(&incoming, id)
+                {
+                    return Ok(Some(incoming));
+                }
+            }
+        }
+
+        Err(self.error("event stream ended without a response"))
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::Mcp(format!("'{}': {}", self.server_name, message))
+    }
+}
+
+async fn write_line(stdin: &mut ChildStdin, message: &Value) -> Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stdin.write_all(line.as_bytes()).await?;
+    stdin.flush().await?;
+    Ok(())
+}
+
+fn is_response_to(message: &Value, id: u64) -> bool {
+    message.get("method").is_none() && message.get("id").and_then(Value::as_u64) == Some(id)
+}
+
+/// Answers requests the server sends to the client
+///
+/// Only `ping` is supported, everything else gets "method not found" so the
+/// server does not wait. Notifications need no answer.
+fn reply_to_server_request(message: &Value) -> Option<Value> {
+    let id = message.get("id")?;
+    let method = message.get("method").and_then(Value::as_str)?;
+
+    Some(if method == "ping" {
+        json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+    } else {
+        json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32601, "message": "Method not found" } })
+    })
+}
+
+/// Takes the next complete event (terminated by a blank line) off the buffer
+fn next_sse_event(buffer: &mut Vec<u8>) -> Option<String> {
+    let end = buffer.windows(2).position(|w| w == b"\n\n")?;
+    let event: Vec<u8> = buffer.drain(..end + 2).collect();
+    Some(String::from_utf8_lossy(&event[..end]).into_owned())
+}
+
+/// Joins the `data:` lines of an event
+fn sse_event_data(event: &str) -> Option<String> {
+    let data: Vec<&str> = event
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| data.strip_prefix(' ').unwrap_or(data))
+        .collect();
+    (!data.is_empty()).then(|| data.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespaced_tool_name() {
+        assert_eq!(namespaced_tool_name("github", "create_issue"), "mcp__github__create_issue");
+        assert_eq!(namespaced_tool_name("docs", "search-docs"), "mcp__docs__search-docs");
+
+        // Names changed to fit providers keep apart
+        let dotted = namespaced_tool_name("docs", "search.v2 pages");
+        assert!(dotted.starts_with("mcp__docs__search_v2_pages_"));
+        assert_ne!(dotted, namespaced_tool_name("docs", "search_v2 pages"));
+        assert_ne!(namespaced_tool_name("docs", "a.b"), namespaced_tool_name("docs", "a_b"));
+
+        let long = namespaced_tool_name("server", &"x".repeat(100));
+        assert_eq!(long.len(), MAX_TOOL_NAME_LENGTH);
+        assert!(long.starts_with("mcp__server__xxx"));
+        assert_ne!(long, namespaced_tool_name("server", &"x".repeat(101)));
+        assert_eq!(long, namespaced_tool_name("server", &"x".repeat(100)));
+    }
+
+    #[tokio::test]
+    async fn test_stdio_server_gets_only_its_own_environment() {
+        // Answers the handshake, then lists tools named after what it sees of its environment
+        let script = r#"
+            read -r line
+            echo '{"jsonrpc":"2.0","id":1,"result":{"capabilities":{"tools":{}}}}'
+            read -r line
+            read -r line
+            echo "{\"jsonrpc\":\"2.0\",\"id\":2,\"result\":{\"tools\":[{\"name\":\"${SERVER_VAR:-missing}\"},{\"name\":\"${CARGO_MANIFEST_DIR:+leaked-}path-${PATH:+set}\"}]}}"
+        "#;
+        let server = McpServer {
+            id: uuid::Uuid::now_v7(),
+            workspace_id: uuid::Uuid::now_v7(),
+            name: "env".to_string(),
+            transport: McpTransport::Stdio,
+            command: Some("sh".to_string()),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: sqlx::types::Json([("SERVER_VAR".to_string(), "configured".to_string())].into()),
+            url: None,
+            headers: sqlx::types::Json(Default::default()),
+            enabled: true,
+            timeout_seconds: 5,
+            created_by: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+
+        // Cargo sets CARGO_MANIFEST_DIR for tests, the server must not see it
+        assert!(std::env::var_os("CARGO_MANIFEST_DIR").is_some());
+        let client = McpClient::connect(&server).await.unwrap();
+        let names: Vec<String> = client.list_tools().await.unwrap().into_iter().map(|tool| tool.remote_name).collect();
+        assert_eq!(names, ["configured", "path-set"]);
+        client.close();
+    }
+
+    #[test]
+    fn test_sse_event_parsing() {
+        let mut buffer = b"event: message\ndata: {\"jsonrpc\":\"2.0\",\ndata: \"id\":1}\n\ndata: partial".to_vec();
+
+        let event = next_sse_event(&mut buffer).unwrap();
+        let message: Value = serde_json::from_str(&sse_event_data(&event).unwrap()).unwrap();
+        assert!(is_response_to(&message, 1));
+
+        // The incomplete event stays buffered
+        assert!(next_sse_event(&mut buffer).is_none());
+        assert_eq!(buffer, b"data: partial");
+    }
+
+    #[test]
+    fn test_reply_to_server_request() {
+        let ping = json!({ "jsonrpc": "2.0", "id": 7, "method": "ping" });
+        assert_eq!(reply_to_server_request(&ping).unwrap()["result"], json!({}));
+
+        let sampling = json!({ "jsonrpc": "2.0", "id": "a", "method": "sampling/createMessage" });
+        assert_eq!(reply_to_server_request(&sampling).unwrap()["error"]["code"], -32601);
+
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/progress" });
+        assert!(reply_to_server_request(&notification).is_none());
+    }
+}
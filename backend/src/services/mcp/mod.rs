@@ -0,0 +1,564 @@
+//! Model Context Protocol (MCP) servers
+//!
+//! Workspaces register external MCP servers whose tools are offered to chat
+//! agents next to the built-in tools, named `mcp__{server}__{tool}`.
+//!
+//! Connections live in the [`McpClientPool`] shared by all agents. A server is
+//! connected when the first agent of its workspace is created and reused until
+//! the server is changed, disabled or deleted, or the connection fails.
+
+pub mod client;
+pub mod server;
+
+pub use client::{namespaced_tool_name, McpClient};
+
+use crate::{DbConn, Result};
+use crate::{
+    config::McpConfig,
+    error::{Error, ValidationErrors},
+    models::{
+        mcp::{
+            CreateMcpServerRequest, McpServer, McpServerResponse, McpTool, McpTransport,
+            NewMcpServer, UpdateMcpServer, UpdateMcpServerRequest,
+        },
+        permissions::workspace_permissions,
+        requests::ToolResponse,
+    },
+    queries::mcp_servers,
+    services::workspace_members::require_workspace_permission,
+    utils::net,
+};
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderName, HeaderValue};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Longest timeout a server may set, in seconds
+pub const MAX_MCP_TIMEOUT_SECONDS: i32 = 300;
+
+/// Longest server name, keeps namespaced tool names readable
+const MAX_SERVER_NAME_LENGTH: usize = 32;
+
+/// An open connection together with the tools discovered on it
+#[derive(Clone)]
+pub struct McpConnection {
+    pub client: Arc<McpClient>,
+    pub tools: Arc<Vec<McpTool>>,
+    /// Version of the server configuration the connection was made with
+    server_updated_at: DateTime<Utc>,
+}
+
+/// Live MCP connections of all workspaces
+#[derive(Default)]
+pub struct McpClientPool {
+    connections: scc::HashMap<Uuid, McpConnection>,
+    /// Bumped whenever a workspace's servers change, chat actors rebuild their agent
+    generations: scc::HashMap<Uuid, u64>,
+}
+
+impl fmt::Debug for McpClientPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("McpClientPool")
+            .field("connections", &self.connections.len())
+            .finish()
+    }
+}
+
+impl McpClientPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the open connection to a server, connecting if needed
+    pub async fn connection(&self, server: &McpServer) -> Result<McpConnection> {
+        if let Some(connection) = self
+            .connections
+            .read_async(&server.id, |_, connection| connection.clone())
+            .await
+            && connection.server_updated_at == server.updated_at
+            && !connection.client.is_closed()
+        {
+            return Ok(connection);
+        }
+
+        let client = McpClient::connect(server).await?;
+        let tools = client.list_tools().await?;
+        tracing::info!(
+            server = %server.name,
+            workspace_id = %server.workspace_id,
+            tools = tools.len(),
+            "[MCP] Connected to server"
+        );
+        let connection = McpConnection {
+            client: Arc::new(client),
+            tools: Arc::new(tools),
+            server_updated_at: server.updated_at,
+        };
+
+        match self.connections.entry_async(server.id).await {
+            scc::hash_map::Entry::Occupied(mut entry) => {
+                let existing = entry.get();
+                // Another agent connected concurrently, or we hold a stale configuration
+                if !existing.client.is_closed() && existing.server_updated_at >= server.updated_at {
+                    connection.client.close();
+                    return Ok(existing.clone());
+                }
+                entry.insert(connection.clone()).client.close();
+            }
+            scc::hash_map::Entry::Vacant(entry) => {
+                entry.insert_entry(connection.clone());
+            }
+        }
+
+        Ok(connection)
+    }
+
+    /// Drops the connection to a server after it changed, and makes chat agents
+    /// of the workspace pick up the change
+    pub async fn invalidate(&self, workspace_id: Uuid, server_id: Uuid) {
+        if let Some((_, connection)) = self.connections.remove_async(&server_id).await {
+            connection.client.close();
+        }
+        self.generations
+            .entry_async(workspace_id)
+            .await
+            .and_modify(|generation| *generation += 1)
+            .or_insert(1);
+    }
+
+    /// Version of a workspace's server list, see [`Self::invalidate`]
+    pub async fn generation(&self, workspace_id: Uuid) -> u64 {
+        self.generations
+            .read_async(&workspace_id, |_, generation| *generation)
+            .await
+            .unwrap_or(0)
+    }
+}
+
+// ============================================================================
+// SERVER MANAGEMENT
+// ============================================================================
+
+/// Registers an MCP server. Requires `workspace:manage_settings`.
+pub async fn create_mcp_server(
+    conn: &mut DbConn,
+    clients: &McpClientPool,
+    config: &McpConfig,
+    workspace_id: Uuid,
+    requester_id: Uuid,
+    request: CreateMcpServerRequest,
+) -> Result<McpServerResponse> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_SETTINGS).await?;
+
+    let name = request.name.trim().to_lowercase();
+    validate_server_name(&name)?;
+    let timeout_seconds = request.timeout_seconds.unwrap_or(config.default_timeout_seconds);
+    let command = request.command.map(|c| c.trim().to_string());
+    let url = request.url.map(|u| u.trim().to_string());
+    validate_server_settings(
+        config,
+        request.transport,
+        command.as_deref(),
+        url.as_deref(),
+        &request.headers,
+        timeout_seconds,
+    )?;
+
+    let server = mcp_servers::create_mcp_server(conn, NewMcpServer {
+        workspace_id,
+        name,
+        transport: request.transport,
+        command,
+        args: request.args,
+        env: request.env,
+        url,
+        headers: request.headers,
+        enabled: request.enabled.unwrap_or(true),
+        timeout_seconds,
+        created_by: Some(requester_id),
+    }).await?;
+
+    clients.invalidate(workspace_id, server.id).await;
+
+    tracing::info!(
+        workspace_id = %workspace_id,
+        server = %server.name,
+        transport = %server.transport,
+        created_by = %requester_id,
+        "MCP server registered"
+    );
+
+    Ok(server.into())
+}
+
+/// Lists the MCP servers of a workspace. Requires `workspace:manage_settings`.
+pub async fn list_mcp_servers(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    requester_id: Uuid,
+) -> Result<Vec<McpServerResponse>> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_SETTINGS).await?;
+
+    let servers = mcp_servers::list_mcp_servers(conn, workspace_id, false).await?;
+    Ok(servers.into_iter().map(McpServerResponse::from).collect())
+}
+
+/// Changes an MCP server, e.g. to enable or disable it.
+/// Requires `workspace:manage_settings`.
+pub async fn update_mcp_server(
+    conn: &mut DbConn,
+    clients: &McpClientPool,
+    config: &McpConfig,
+    workspace_id: Uuid,
+    server_id: Uuid,
+    requester_id: Uuid,
+    request: UpdateMcpServerRequest,
+) -> Result<McpServerResponse> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_SETTINGS).await?;
+
+    let current = mcp_servers::get_mcp_server(conn, workspace_id, server_id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("MCP server {} not found", server_id)))?;
+
+    let command = request.command.map(|c| c.trim().to_string());
+    let url = request.url.map(|u| u.trim().to_string());
+    validate_server_settings(
+        config,
+        current.transport,
+        command.as_deref().or(current.command.as_deref()),
+        url.as_deref().or(current.url.as_deref()),
+        request.headers.as_ref().unwrap_or(&current.headers.0),
+        request.timeout_seconds.unwrap_or(current.timeout_seconds),
+    )?;
+
+    let server = mcp_servers::update_mcp_server(conn, workspace_id, server_id, UpdateMcpServer {
+        command,
+        args: request.args,
+        env: request.env,
+        url,
+        headers: request.headers,
+        enabled: request.enabled,
+        timeout_seconds: request.timeout_seconds,
+    })
+    .await?
+    .ok_or_else(|| Error::NotFound(format!("MCP server {} not found", server_id)))?;
+
+    clients.invalidate(workspace_id, server_id).await;
+
+    tracing::info!(
+        workspace_id = %workspace_id,
+        server = %server.name,
+        enabled = server.enabled,
+        updated_by = %requester_id,
+        "MCP server updated"
+    );
+
+    Ok(server.into())
+}
+
+/// Removes an MCP server. Requires `workspace:manage_settings`.
+pub async fn delete_mcp_server(
+    conn: &mut DbConn,
+    clients: &McpClientPool,
+    workspace_id: Uuid,
+    server_id: Uuid,
+    requester_id: Uuid,
+) -> Result<()> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_SETTINGS).await?;
+
+    if mcp_servers::delete_mcp_server(conn, workspace_id, server_id).await? == 0 {
+        return Err(Error::NotFound(format!("MCP server {} not found", server_id)));
+    }
+
+    clients.invalidate(workspace_id, server_id).await;
+    Ok(())
+}
+
+/// Connects to a server and lists its tools, e.g. to check a new registration.
+/// Requires `workspace:manage_settings`.
+///
+/// Disabled servers are connected just for this call.
+pub async fn list_mcp_server_tools(
+    conn: &mut DbConn,
+    clients: &McpClientPool,
+    config: &McpConfig,
+    workspace_id: Uuid,
+    server_id: Uuid,
+    requester_id: Uuid,
+) -> Result<Vec<McpTool>> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_SETTINGS).await?;
+
+    let server = mcp_servers::get_mcp_server(conn, workspace_id, server_id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("MCP server {} not found", server_id)))?;
+    ensure_transport_allowed(config, server.transport)?;
+
+    if server.enabled {
+        let connection = clients.connection(&server).await?;
+        Ok(connection.tools.as_ref().clone())
+    } else {
+        let client = McpClient::connect(&server).await?;
+        let tools = client.list_tools().await;
+        client.close();
+        tools
+    }
+}
+
+// ============================================================================
+// AGENT INTEGRATION
+// ============================================================================
+
+/// Tools of a workspace's enabled MCP servers, for a new chat agent
+///
+/// Servers are connected concurrently. A server that cannot be reached is
+/// logged and left out, so it never prevents the chat from running.
+pub async fn load_agent_tools(
+    conn: &mut DbConn,
+    clients: &McpClientPool,
+    config: &McpConfig,
+    workspace_id: Uuid,
+) -> Vec<(Arc<McpServer>, McpTool)> {
+    let servers = match mcp_servers::list_mcp_servers(conn, workspace_id, true).await {
+        Ok(servers) => servers,
+        Err(e) => {
+            tracing::error!(workspace_id = %workspace_id, error = %e, "[MCP] Failed to load servers");
+            return Vec::new();
+        }
+    };
+
+    let servers: Vec<Arc<McpServer>> = servers
+        .into_iter()
+        .filter(|server| ensure_transport_allowed(config, server.transport).is_ok())
+        .map(Arc::new)
+        .collect();
+
+    let connections =
+        futures::future::join_all(servers.iter().map(|server| clients.connection(server))).await;
+
+    let mut tools: Vec<(Arc<McpServer>, McpTool)> = Vec::new();
+    for (server, connection) in servers.into_iter().zip(connections) {
+        match connection {
+            Ok(connection) => {
+                for tool in connection.tools.iter() {
+                    if tools.iter().any(|(_, existing)| existing.name == tool.name) {
+                        tracing::warn!(tool = %tool.name, "[MCP] Skipping tool with duplicate name");
+                        continue;
+                    }
+                    tools.push((server.clone(), tool.clone()));
+                }
+            }
+            Err(e) => {
+                tracing::warn!(
+                    workspace_id = %workspace_id,
+                    server = %server.name,
+                    error = %e,
+                    "[MCP] Server unavailable, its tools are not offered"
+                );
+            }
+        }
+    }
+
+    tools
+}
+
+/// Calls an MCP tool on behalf of an agent
+///
+/// Reconnects when the pooled connection was closed. The result is shaped like
+/// the built-in tools' [`ToolResponse`], so chat events and persistence treat
+/// both the same way.
+pub async fn call_tool(
+    clients: &McpClientPool,
+    server: &McpServer,
+    tool: &McpTool,
+    arguments: Value,
+) -> Result<ToolResponse> {
+    let connection = clients.connection(server).await?;
+    let result = connection.client.call_tool(&tool.remote_name, arguments).await?;
+    Ok(tool_response(&result))
+}
+
+/// Converts a `tools/call` result into a [`ToolResponse`]
+fn tool_response(result: &Value) -> ToolResponse {
+    let text = result
+        .get("content")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|item| match item.get("type").and_then(Value::as_str) {
+            Some("text") => item.get("text").and_then(Value::as_str).unwrap_or_default().to_string(),
+            Some("resource") => item
+                .pointer("/resource/text")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("[resource: {}]", item.pointer("/resource/uri").and_then(Value::as_str).unwrap_or("unknown"))),
+            Some(kind) => format!("[{}: {}]", kind, item.get("mimeType").and_then(Value::as_str).unwrap_or("unknown")),
+            None => item.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if result.get("isError").and_then(Value::as_bool).unwrap_or(false) {
+        return ToolResponse {
+            success: false,
+            result: Value::Null,
+            error: Some(if text.is_empty() { "Tool call failed".to_string() } else { text }),
+        };
+    }
+
+    let mut output = json!({ "content": text });
+    if let Some(structured) = result.get("structuredContent") {
+        output["structured_content"] = structured.clone();
+    }
+    ToolResponse {
+        success: true,
+        result: output,
+        error: None,
+    }
+}
+
+// ============================================================================
+// VALIDATION
+// ============================================================================
+
+fn invalid(field: &str, message: impl Into<String>) -> Error {
+    Error::Validation(ValidationErrors::Single {
+        field: field.to_string(),
+        message: message.into(),
+    })
+}
+
+/// Stdio servers run a command on this host, so they are opt-in
+fn ensure_transport_allowed(config: &McpConfig, transport: McpTransport) -> Result<()> {
+    if transport == McpTransport::Stdio && !config.allow_stdio {
+        return Err(Error::Forbidden(
+            "stdio MCP servers are disabled on this server".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_server_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_SERVER_NAME_LENGTH
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid {
+        return Err(invalid(
+            "name",
+            format!(
+                "Name must be 1 to {} lowercase letters, digits or hyphens, starting with a letter or digit",
+                MAX_SERVER_NAME_LENGTH
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_server_settings(
+    config: &McpConfig,
+    transport: McpTransport,
+    command: Option<&str>,
+    url: Option<&str>,
+    headers: &BTreeMap<String, String>,
+    timeout_seconds: i32,
+) -> Result<()> {
+    ensure_transport_allowed(config, transport)?;
+
+    match transport {
+        McpTransport::Stdio => {
+            if command.is_none_or(str::is_empty) {
+                return Err(invalid("command", "command is required for stdio servers"));
+            }
+        }
+        McpTransport::Http => {
+            let url = url
+                .filter(|url| !url.is_empty())
+                .ok_or_else(|| invalid("url", "url is required for http servers"))?;
+            let parsed = url::Url::parse(url).map_err(|_| invalid("url", "url must be a valid URL"))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err(invalid("url", "url must use http or https"));
+            }
+            // Host names are checked when the client connects
+            if let Err(Error::Forbidden(message)) = net::check_url(&parsed) {
+                return Err(invalid("url", message));
+            }
+            for (name, value) in headers {
+                if HeaderName::from_bytes(name.as_bytes()).is_err() || HeaderValue::from_str(value).is_err() {
+                    return Err(invalid("headers", format!("Invalid header '{}'", name)));
+                }
+            }
+        }
+    }
+
+    if !(1..=MAX_MCP_TIMEOUT_SECONDS).contains(&timeout_seconds) {
+        return Err(invalid(
+            "timeout_seconds",
+            format!("timeout_seconds must be between 1 and {}", MAX_MCP_TIMEOUT_SECONDS),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_server_name() {
+        for name in ["github", "docs-search", "s3", "1password"] {
+            assert!(validate_server_name(name).is_ok(), "Should accept: {}", name);
+        }
+        for name in ["", "-docs", "my_server", "Docs", "a b", &"x".repeat(33)] {
+            assert!(validate_server_name(name).is_err(), "Should reject: {}", name);
+        }
+    }
+
+    #[test]
+    fn test_validate_server_settings() {
+        let config = McpConfig { allow_stdio: false, default_timeout_seconds: 30 };
+        let headers = BTreeMap::from([("Authorization".to_string(), "Bearer token".to_string())]);
+
+        assert!(validate_server_settings(&config, McpTransport::Http, None, Some("https://mcp.example.com/mcp"), &headers, 30).is_ok());
+        assert!(validate_server_settings(&config, McpTransport::Http, None, None, &headers, 30).is_err());
+        assert!(validate_server_settings(&config, McpTransport::Http, None, Some("file:///etc/passwd"), &headers, 30).is_err());
+        assert!(validate_server_settings(&config, McpTransport::Http, None, Some("https://mcp.example.com"), &headers, 0).is_err());
+        assert!(validate_server_settings(&config, McpTransport::Http, None, Some("http://169.254.169.254/mcp"), &headers, 30).is_err());
+        assert!(validate_server_settings(&config, McpTransport::Http, None, Some("http://[::1]:8080/mcp"), &headers, 30).is_err());
+
+        let bad_headers = BTreeMap::from([("Bad Header".to_string(), "x".to_string())]);
+        assert!(validate_server_settings(&config, McpTransport::Http, None, Some("https://mcp.example.com"), &bad_headers, 30).is_err());
+
+        // Stdio is opt-in
+        assert!(matches!(
+            validate_server_settings(&config, McpTransport::Stdio, Some("npx"), None, &BTreeMap::new(), 30),
+            Err(Error::Forbidden(_))
+        ));
+        let config = McpConfig { allow_stdio: true, ..config };
+        assert!(validate_server_settings(&config, McpTransport::Stdio, Some("npx"), None, &BTreeMap::new(), 30).is_ok());
+        assert!(validate_server_settings(&config, McpTransport::Stdio, Some(""), None, &BTreeMap::new(), 30).is_err());
+    }
+
+    #[test]
+    fn test_tool_response() {
+        let result = json!({
+            "content": [
+                { "type": "text", "text": "3 issues" },
+                { "type": "image", "data": "...", "mimeType": "image/png" }
+            ],
+            "structuredContent": { "count": 3 }
+        });
+        let response = tool_response(&result);
+        assert!(response.success);
+        assert_eq!(response.result["content"], "3 issues\n[image: image/png]");
+        assert_eq!(response.result["structured_content"]["count"], 3);
+
+        let result = json!({ "content": [{ "type": "text", "text": "Repository not found" }], "isError": true });
+        let response = tool_response(&result);
+        assert!(!response.success);
+        assert_eq!(response.error.as_deref(), Some("Repository not found"));
+    }
+}
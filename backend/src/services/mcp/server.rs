@@ -0,0 +1,291 @@
+//! MCP server publishing workspace tools
+//!
+//! Lets external agents and IDEs use a workspace through the Model Context
+//! Protocol. Requests arrive as JSON-RPC messages on the streamable HTTP
+//! transport and are answered with a single JSON response each; the server is
+//! stateless, so there are no sessions and no server-initiated messages.
+//!
+//! Published tools are the workspace file, plan and memory tools, executed
+//! through [`tools::ToolExecutor`] exactly like `POST /workspaces/:id/tools`, under
+//! the workspace's tool policy. Tools that only make sense inside a chat
+//! (`ask_user`, `exit_plan_mode`, `delegate`, skills) and tools reaching outside
+//! the workspace (`web_fetch`, `web_search`) are not published.
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::services::storage::FileStorageService;
+use crate::services::workspaces;
+use crate::tools::{self, ToolConfig};
+use crate::DbConn;
+
+use super::client::MCP_PROTOCOL_VERSION;
+
+/// Protocol versions this server speaks, newest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &[MCP_PROTOCOL_VERSION, "2025-03-26", "2024-11-05"];
+
+/// Tools published to MCP clients, in listing order
+pub const PUBLISHED_TOOLS: &[&str] = &[
+    "ls",
+    "read",
+    "read_multiple_files",
+    "cat",
+    "file_info",
+    "find",
+    "glob",
+    "grep",
+    "write",
+    "edit",
+    "mkdir",
+    "touch",
+    "mv",
+    "rm",
+    "plan_list",
+    "plan_read",
+    "plan_write",
+    "plan_edit",
+    "plan_update_step",
+    "memory_list",
+    "memory_get",
+    "memory_search",
+    "memory_set",
+    "memory_delete",
+];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Who is calling and under which restrictions
+#[derive(Debug, Clone)]
+pub struct McpServerContext {
+    pub workspace_id: Uuid,
+    pub user_id: Uuid,
+    /// Restrict modifying tools to plan files, like Plan Mode chats
+    pub plan_mode: bool,
+    /// Whether modifying tools may be called, false for read-only API keys
+    pub can_write: bool,
+}
+
+/// Handles one JSON-RPC message
+///
+/// Returns the response to send, or `None` for notifications and responses,
+/// which are only acknowledged.
+pub async fn handle_message(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    context: &McpServerContext,
+    message: Value,
+) -> Option<Value> {
+    if message.is_array() {
+        return Some(error_response(Value::Null, INVALID_REQUEST, "Batch requests are not supported"));
+    }
+    if message.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        return Some(error_response(Value::Null, INVALID_REQUEST, "Expected a JSON-RPC 2.0 message"));
+    }
+
+    let id = message.get("id").cloned();
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        // A response to a request we never send
+        return id.is_none().then(|| error_response(Value::Null, INVALID_REQUEST, "Missing method"));
+    };
+    let Some(id) = id else {
+        tracing::debug!(method = method, "[MCP] Notification received");
+        return None;
+    };
+
+    let params = message.get("params").cloned().unwrap_or_else(|| json!({}));
+    let result = match method {
+        "initialize" => Ok(initialize(&params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(list_tools(context)),
+        "tools/call" => call_tool(conn, storage, context, &params).await,
+        _ => Err((METHOD_NOT_FOUND, format!("Method '{}' not found", method))),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, &message),
+    })
+}
+
+/// Response to a body that is not JSON
+pub fn parse_error(message: &str) -> Value {
+    error_response(Value::Null, PARSE_ERROR, message)
+}
+
+/// Whether a client's `MCP-Protocol-Version` header names a version we speak
+pub fn is_supported_protocol_version(version: &str) -> bool {
+    SUPPORTED_PROTOCOL_VERSIONS.contains(&version)
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn initialize(params: &Value) -> Value {
+    // Answer with the client's version when we speak it, our newest otherwise
+    let protocol_version = params
+        .get("protocolVersion")
+        .and_then(Value::as_str)
+        .filter(|version| is_supported_protocol_version(version))
+        .unwrap_or(MCP_PROTOCOL_VERSION);
+
+    json!({
+        "protocolVersion": protocol_version,
+        "capabilities": { "tools": { "listChanged": false } },
+        "serverInfo": { "name": "buildscale", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "Tools operating on the files, plans and memories of one BuildScale workspace. \
+            Paths are absolute within the workspace, e.g. /notes/todo.md.",
+    })
+}
+
+fn list_tools(context: &McpServerContext) -> Value {
+    let definitions = tools::get_all_tool_definitions();
+    let tools: Vec<Value> = PUBLISHED_TOOLS
+        .iter()
+        .filter_map(|name| definitions.iter().find(|definition| definition.name == *name))
+        .filter_map(|definition| {
+            let read_only = tools::get_tool_executor(&definition.name).ok()?.is_read_only();
+            (read_only || context.can_write).then(|| {
+                json!({
+                    "name": definition.name,
+                    "description": definition.description,
+                    "inputSchema": definition.parameters,
+                    "annotations": {
+                        "readOnlyHint": read_only,
+                        "destructiveHint": matches!(definition.name.as_str(), "rm" | "mv" | "write" | "memory_delete"),
+                    },
+                })
+            })
+        })
+        .collect();
+
+    json!({ "tools": tools })
+}
+
+async fn call_tool(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    context: &McpServerContext,
+    params: &Value,
+) -> std::result::Result<Value, (i64, String)> {
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| (INVALID_PARAMS, "Missing tool name".to_string()))?;
+    if !PUBLISHED_TOOLS.contains(&name) {
+        return Err((INVALID_PARAMS, format!("Unknown tool: {}", name)));
+    }
+    let executor = tools::get_tool_executor(name).map_err(|e| (INVALID_PARAMS, e.to_string()))?;
+    let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+
+    // Failures of the call itself are reported in the result, so the model sees them
+    if !executor.is_read_only() && !context.can_write {
+        return Ok(tool_error(&format!(
+            "API key lacks required permission: {}",
+            crate::models::permissions::workspace_permissions::WRITE
+        )));
+    }
+    // The workspace's tool policy applies like on the tools API, there is no chat
+    // to approve `ask` calls in
+    if let Err(e) = workspaces::authorize_tool_call(conn, context.workspace_id, name, &arguments).await {
+        return Ok(tool_error(&e.to_string()));
+    }
+
+    tracing::info!(
+        workspace_id = %context.workspace_id,
+        user_id = %context.user_id,
+        tool = name,
+        plan_mode = context.plan_mode,
+        "[MCP] Executing tool for MCP client"
+    );
+
+    let config = ToolConfig {
+        plan_mode: context.plan_mode,
+        ..Default::default()
+    };
+    let response = executor
+        .execute(conn, storage, context.workspace_id, context.user_id, config, arguments)
+        .await;
+
+    Ok(match response {
+        Ok(mut response) if response.success => {
+            // Images `read` returns go to the client as image content, not as base64 text
+            let image = response.result.as_object_mut().and_then(|result| result.remove("image"));
+            let mut content = vec![json!({ "type": "text", "text": response.result.to_string() })];
+            if let Some(image) = image {
+                content.push(json!({ "type": "image", "data": image["data"], "mimeType": image["media_type"] }));
+            }
+            json!({
+                "content": content,
+                "structuredContent": response.result,
+                "isError": false,
+            })
+        }
+        Ok(response) => tool_error(response.error.as_deref().unwrap_or("Unknown tool error")),
+        Err(e) => tool_error(&e.to_string()),
+    })
+}
+
+fn tool_error(message: &str) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": message }],
+        "isError": true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(can_write: bool) -> McpServerContext {
+        McpServerContext {
+            workspace_id: Uuid::now_v7(),
+            user_id: Uuid::now_v7(),
+            plan_mode: false,
+            can_write,
+        }
+    }
+
+    #[test]
+    fn test_published_tools_exist() {
+        let definitions = tools::get_all_tool_definitions();
+        for name in PUBLISHED_TOOLS {
+            assert!(tools::get_tool_executor(name).is_ok(), "Unknown tool: {}", name);
+            assert!(definitions.iter().any(|d| d.name == *name), "No definition: {}", name);
+        }
+    }
+
+    #[test]
+    fn test_list_tools_hides_write_tools_from_read_only_callers() {
+        let all = list_tools(&context(true));
+        assert_eq!(all["tools"].as_array().unwrap().len(), PUBLISHED_TOOLS.len());
+
+        let read_only = list_tools(&context(false));
+        let names: Vec<&str> = read_only["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert!(names.contains(&"read"));
+        assert!(names.contains(&"memory_search"));
+        assert!(!names.contains(&"write"));
+        assert!(!names.contains(&"memory_set"));
+    }
+
+    #[test]
+    fn test_initialize_negotiates_version() {
+        let result = initialize(&json!({ "protocolVersion": "2025-03-26" }));
+        assert_eq!(result["protocolVersion"], "2025-03-26");
+
+        let result = initialize(&json!({ "protocolVersion": "1999-01-01" }));
+        assert_eq!(result["protocolVersion"], MCP_PROTOCOL_VERSION);
+    }
+}
@@ -1,13 +1,26 @@
+pub mod agent_files;
 pub mod agent_sessions;
+pub mod api_keys;
 pub mod chat;
 pub mod cookies;
+pub mod eval;
 pub mod files;
+pub mod images;
 pub mod invitations;
 pub mod jwt;
+pub mod mailer;
+pub mod mcp;
+pub mod oidc;
 pub mod refresh_tokens;
 pub mod users;
 pub mod roles;
+pub mod sandbox;
+pub mod web_pages;
+pub mod web_search;
 pub mod workspaces;
 pub mod workspace_members;
 pub mod sessions;
+pub mod skills;
 pub mod storage;
+pub mod tokens;
+pub mod two_factor;
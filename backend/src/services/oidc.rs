@@ -0,0 +1,726 @@
+//! OpenID Connect single sign-on
+//!
+//! Implements the authorization code flow with PKCE (S256) against one configured provider:
+//! 1. [`begin_login`] stores a random `state`, PKCE verifier and nonce, and returns the
+//!    provider's authorization URL for the browser to visit.
+//! 2. [`complete_login`] consumes the `state`, exchanges the code, verifies the ID token
+//!    against the provider's JWKS and issues a regular login session.
+//!
+//! Users are matched by linked identity first and by verified email address second; when
+//! neither matches, an account is provisioned just in time. Whenever a new identity is
+//! linked, the user joins every workspace that registered their email domain.
+
+use crate::{DbConn, Result};
+use crate::{
+    config::OidcConfig,
+    error::{Error, ValidationErrors},
+    models::{
+        oidc::{
+            CreateSsoDomainRequest, NewOidcLoginState, NewUserIdentity, NewWorkspaceSsoDomain,
+            OidcCallbackQuery, OidcIdTokenClaims, OidcLoginResult, OidcProviderMetadata,
+            OidcTokenResponse, WorkspaceSsoDomainDetailed,
+        },
+        permissions::workspace_permissions,
+        users::{NewUser, User},
+        workspace_members::NewWorkspaceMember,
+    },
+    queries::{oidc, roles, users, workspace_members},
+    services::{api_keys, sessions, users as user_service, workspace_members::require_workspace_permission},
+    validation::{validate_email, validate_full_name},
+};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header, jwk::JwkSet};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::Acquire;
+use uuid::Uuid;
+
+/// Timeout for every request made to the identity provider
+const PROVIDER_TIMEOUT_SECONDS: u64 = 10;
+
+/// Maximum length of a post-login redirect path
+const MAX_REDIRECT_PATH_LENGTH: usize = 2048;
+
+/// Name prefixed to an SSO domain for its verification TXT record
+const VERIFICATION_RECORD_PREFIX: &str = "_buildscale-verification";
+
+/// Domains of public email providers, where an address proves nothing about an organization
+const PUBLIC_EMAIL_DOMAINS: &[&str] = &[
+    "aol.com", "fastmail.com", "gmail.com", "gmx.com", "gmx.de", "gmx.net", "googlemail.com",
+    "hey.com", "hotmail.com", "icloud.com", "live.com", "mac.com", "mail.com", "mail.ru", "me.com",
+    "msn.com", "outlook.com", "pm.me", "proton.me", "protonmail.com", "qq.com", "tutanota.com",
+    "web.de", "yahoo.com", "yandex.com", "yandex.ru", "ymail.com", "zoho.com", "126.com", "163.com",
+];
+
+// ============================================================================
+// LOGIN FLOW
+// ============================================================================
+
+/// Starts an OIDC login and returns the provider URL to redirect the browser to
+///
+/// `redirect_to` is a frontend path to return to afterwards. Anything that is not a
+/// same-origin absolute path is ignored so the flow cannot be used as an open redirect.
+pub async fn begin_login(conn: &mut DbConn, config: &OidcConfig, redirect_to: Option<&str>) -> Result<String> {
+    ensure_enabled(config)?;
+
+    let client = provider_client()?;
+    let metadata = discover(&client, config).await?;
+
+    let state = generate_random_token();
+    let code_verifier = generate_random_token();
+    let nonce = generate_random_token();
+
+    oidc::create_login_state(conn, NewOidcLoginState {
+        state_hash: oidc::hash_login_state(&state),
+        code_verifier: code_verifier.clone(),
+        nonce: nonce.clone(),
+        redirect_to: sanitize_redirect_path(redirect_to),
+        expires_at: Utc::now() + Duration::minutes(config.login_state_expiration_minutes),
+    }).await?;
+
+    let mut url = url::Url::parse(&metadata.authorization_endpoint).map_err(|e| {
+        Error::Internal(format!("Invalid authorization endpoint from identity provider: {}", e))
+    })?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &config.client_id)
+        .append_pair("redirect_uri", &config.redirect_url)
+        .append_pair("scope", &config.scopes)
+        .append_pair("state", &state)
+        .append_pair("nonce", &nonce)
+        .append_pair("code_challenge", &pkce_challenge(&code_verifier))
+        .append_pair("code_challenge_method", "S256");
+
+    Ok(url.to_string())
+}
+
+/// Completes an OIDC login from the provider's callback parameters
+///
+/// The login state is consumed before anything else, so every callback URL works at most once.
+pub async fn complete_login(conn: &mut DbConn, config: &OidcConfig, query: OidcCallbackQuery) -> Result<OidcLoginResult> {
+    ensure_enabled(config)?;
+
+    if let Some(error) = query.error {
+        return Err(Error::Authentication(format!(
+            "Identity provider returned an error: {}{}",
+            error,
+            query.error_description.map(|d| format!(" ({})", d)).unwrap_or_default()
+        )));
+    }
+
+    let state = query
+        .state
+        .filter(|s| !s.trim().is_empty())
+        .ok_or_else(|| Error::InvalidToken("Missing login state".to_string()))?;
+    let code = query
+        .code
+        .filter(|c| !c.trim().is_empty())
+        .ok_or_else(|| Error::Validation(ValidationErrors::Single {
+            field: "code".to_string(),
+            message: "Authorization code is required".to_string(),
+        }))?;
+
+    let login_state = oidc::consume_login_state(conn, &oidc::hash_login_state(state.trim()))
+        .await?
+        .ok_or_else(|| Error::InvalidToken("Invalid or expired login state".to_string()))?;
+
+    let client = provider_client()?;
+    let metadata = discover(&client, config).await?;
+    let id_token = exchange_code(&client, config, &metadata, code.trim(), &login_state.code_verifier).await?;
+    let jwks = fetch_jwks(&client, &metadata).await?;
+    let claims = verify_id_token(&id_token, &jwks, &metadata.issuer, &config.client_id, &login_state.nonce)?;
+
+    let (user, provisioned, joined_workspace_ids) = resolve_user(conn, config, &metadata.issuer, &claims).await?;
+    let login = user_service::complete_primary_login(conn, user).await?;
+
+    Ok(OidcLoginResult {
+        login,
+        redirect_to: login_state.redirect_to,
+        provisioned,
+        joined_workspace_ids,
+    })
+}
+
+/// Verifies an ID token's signature and claims
+///
+/// Checks the signature against the provider's JWKS, the `iss`, `aud` and `exp`
+/// claims, and that the `nonce` matches the one stored for this login attempt.
+pub fn verify_id_token(
+    id_token: &str,
+    jwks: &JwkSet,
+    issuer: &str,
+    client_id: &str,
+    nonce: &str,
+) -> Result<OidcIdTokenClaims> {
+    let header = decode_header(id_token)
+        .map_err(|e| Error::InvalidToken(format!("Invalid ID token: {}", e)))?;
+
+    // Provider keys are public, so a symmetric algorithm can never be legitimate here
+    if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+        return Err(Error::InvalidToken("Unsupported ID token algorithm".to_string()));
+    }
+
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+    .ok_or_else(|| Error::InvalidToken("ID token signing key not found".to_string()))?;
+
+    let key = DecodingKey::from_jwk(jwk)
+        .map_err(|e| Error::InvalidToken(format!("Invalid ID token signing key: {}", e)))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_audience(&[client_id]);
+    validation.set_issuer(&[issuer]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+    let claims = decode::<OidcIdTokenClaims>(id_token, &key, &validation)
+        .map_err(|e| Error::InvalidToken(format!("Invalid ID token: {}", e)))?
+        .claims;
+
+    if claims.nonce.as_deref() != Some(nonce) {
+        return Err(Error::InvalidToken("ID token nonce mismatch".to_string()));
+    }
+
+    Ok(claims)
+}
+
+/// Finds, links or provisions the local user for verified ID token claims
+///
+/// Returns the user, whether it was created, and the workspaces it joined.
+async fn resolve_user(
+    conn: &mut DbConn,
+    config: &OidcConfig,
+    issuer: &str,
+    claims: &OidcIdTokenClaims,
+) -> Result<(User, bool, Vec<Uuid>)> {
+    let email = claims.email.as_deref().map(|e| e.trim().to_lowercase());
+
+    let mut tx = conn.begin().await.map_err(|e| {
+        Error::Internal(format!("Failed to begin transaction: {}", e))
+    })?;
+
+    // Returning user: the identity is already linked
+    if let Some(identity) = oidc::get_user_identity(&mut tx, issuer, &claims.sub).await? {
+        oidc::touch_user_identity(&mut tx, identity.id, email.as_deref()).await?;
+        let user = users::get_user_by_id(&mut tx, identity.user_id).await?
+            .ok_or_else(|| Error::NotFound(format!("User with ID {} not found", identity.user_id)))?;
+
+        tx.commit().await.map_err(|e| {
+            Error::Internal(format!("Failed to commit transaction: {}", e))
+        })?;
+
+        return Ok((user, false, Vec::new()));
+    }
+
+    // Linking and provisioning both rely on the provider vouching for the address
+    let email = match email {
+        Some(email) if claims.email_verified => email,
+        _ => {
+            return Err(Error::Authentication(
+                "The identity provider did not supply a verified email address".to_string(),
+            ));
+        }
+    };
+    validate_email(&email)?;
+
+    let (user, provisioned) = match users::get_user_by_email(&mut tx, &email).await? {
+        Some(user) => {
+            if api_keys::is_service_account_user(&mut tx, user.id).await? {
+                return Err(Error::Forbidden("Service accounts cannot sign in".to_string()));
+            }
+
+            // Whoever registered an unverified account may not own the mailbox. Drop their
+            // password and sessions so a pre-registered account cannot be taken over.
+            if user.email_verified_at.is_none() {
+                users::clear_user_password(&mut tx, user.id).await?;
+                sessions::revoke_all_user_sessions(&mut tx, user.id).await?;
+            }
+
+            (users::mark_email_verified(&mut tx, user.id).await?, false)
+        }
+        None => {
+            if !config.allow_signup {
+                return Err(Error::Forbidden(
+                    "No account exists for this email address".to_string(),
+                ));
+            }
+
+            let full_name = claims
+                .name
+                .as_deref()
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty());
+            // Provider names are best effort; fall back to no name rather than failing the login
+            let full_name = validate_full_name(&full_name).ok().and(full_name);
+
+            let user = users::create_user(&mut tx, NewUser {
+                email: email.clone(),
+                password_hash: None,
+                full_name,
+            }).await?;
+
+            (users::mark_email_verified(&mut tx, user.id).await?, true)
+        }
+    };
+
+    oidc::create_user_identity(&mut tx, NewUserIdentity {
+        user_id: user.id,
+        issuer: issuer.to_string(),
+        subject: claims.sub.clone(),
+        email: Some(email),
+    }).await?;
+
+    let joined_workspace_ids = join_workspaces_by_email_domain(&mut tx, &user).await?;
+
+    tx.commit().await.map_err(|e| {
+        Error::Internal(format!("Failed to commit transaction: {}", e))
+    })?;
+
+    tracing::info!(
+        user_id = %user.id,
+        issuer = %issuer,
+        joined_workspaces = joined_workspace_ids.len(),
+        security_event = if provisioned { "oidc_user_provisioned" } else { "oidc_identity_linked" },
+        "External identity linked"
+    );
+
+    Ok((user, provisioned, joined_workspace_ids))
+}
+
+/// Whether a workspace manages a user's account through single sign-on
+///
+/// That is the case when the user's verified email address is at one of the
+/// workspace's verified SSO domains.
+pub async fn is_sso_managed_user(conn: &mut DbConn, workspace_id: Uuid, user: &User) -> Result<bool> {
+    if user.email_verified_at.is_none() {
+        return Ok(false);
+    }
+    let Some(domain) = email_domain(&user.email) else {
+        return Ok(false);
+    };
+
+    let entries = oidc::list_verified_sso_domains_by_domain(conn, &domain).await?;
+    Ok(entries.iter().any(|entry| entry.workspace_id == workspace_id))
+}
+
+/// Adds a user to every workspace that verified their email domain
+async fn join_workspaces_by_email_domain(conn: &mut DbConn, user: &User) -> Result<Vec<Uuid>> {
+    let Some(domain) = email_domain(&user.email) else {
+        return Ok(Vec::new());
+    };
+
+    let mut joined = Vec::new();
+    for entry in oidc::list_verified_sso_domains_by_domain(conn, &domain).await? {
+        if workspace_members::is_workspace_member(conn, entry.workspace_id, user.id).await? {
+            continue;
+        }
+
+        workspace_members::create_workspace_member(conn, NewWorkspaceMember {
+            workspace_id: entry.workspace_id,
+            user_id: user.id,
+            role_id: entry.role_id,
+        }).await?;
+        joined.push(entry.workspace_id);
+    }
+
+    Ok(joined)
+}
+
+// ============================================================================
+// WORKSPACE SSO DOMAINS
+// ============================================================================
+
+/// Registers an email domain whose users auto-join the workspace on single sign-on
+///
+/// Requires `workspace:manage_members`. The domain takes effect once
+/// [`verify_sso_domain`] finds its verification token in DNS, so nobody can claim a
+/// domain they do not control. Public email providers cannot be registered.
+pub async fn create_sso_domain(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    requester_id: Uuid,
+    request: CreateSsoDomainRequest,
+) -> Result<WorkspaceSsoDomainDetailed> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_MEMBERS).await?;
+
+    let domain = normalize_domain(&request.domain)?;
+    if PUBLIC_EMAIL_DOMAINS.contains(&domain.as_str()) {
+        return Err(Error::Validation(ValidationErrors::Single {
+            field: "domain".to_string(),
+            message: format!("{} is a public email provider and cannot be an SSO domain", domain),
+        }));
+    }
+
+    let role = roles::get_role_by_workspace_and_name(conn, workspace_id, &request.role_name.to_lowercase())
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Role '{}' not found in workspace", request.role_name)))?;
+
+    let created = oidc::create_sso_domain(conn, NewWorkspaceSsoDomain {
+        workspace_id,
+        domain,
+        role_id: role.id,
+        verification_token: generate_random_token(),
+        created_by: Some(requester_id),
+    }).await?;
+
+    tracing::info!(
+        workspace_id = %workspace_id,
+        domain = %created.domain,
+        role = %role.name,
+        created_by = %requester_id,
+        security_event = "sso_domain_created",
+        "SSO domain registered, awaiting verification"
+    );
+
+    Ok(WorkspaceSsoDomainDetailed {
+        id: created.id,
+        workspace_id: created.workspace_id,
+        domain: created.domain,
+        role_id: created.role_id,
+        role_name: role.name,
+        verification_token: created.verification_token,
+        verified_at: created.verified_at,
+        created_by: created.created_by,
+        created_at: created.created_at,
+    })
+}
+
+/// Verifies an SSO domain by looking up its DNS TXT record
+///
+/// The record `_buildscale-verification.<domain>` must contain
+/// `buildscale-verification=<verification_token>`. Requires `workspace:manage_members`.
+/// Verifying a domain again is a no-op.
+pub async fn verify_sso_domain(
+    conn: &mut DbConn,
+    config: &OidcConfig,
+    workspace_id: Uuid,
+    domain_id: Uuid,
+    requester_id: Uuid,
+) -> Result<WorkspaceSsoDomainDetailed> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_MEMBERS).await?;
+
+    let entry = oidc::get_sso_domain(conn, workspace_id, domain_id).await?
+        .ok_or_else(|| Error::NotFound(format!("SSO domain {} not found", domain_id)))?;
+    if entry.verified_at.is_some() {
+        return Ok(entry);
+    }
+
+    let record_name = verification_record_name(&entry.domain);
+    let expected = verification_record_value(&entry.verification_token);
+    let records = lookup_txt_records(config, &record_name).await?;
+    if !records.iter().any(|record| record.trim() == expected) {
+        return Err(Error::Forbidden(format!(
+            "TXT record {} does not contain \"{}\"", record_name, expected
+        )));
+    }
+
+    oidc::mark_sso_domain_verified(conn, entry.id).await?;
+    tracing::info!(
+        workspace_id = %workspace_id,
+        domain = %entry.domain,
+        verified_by = %requester_id,
+        security_event = "sso_domain_verified",
+        "SSO domain verified"
+    );
+
+    oidc::get_sso_domain(conn, workspace_id, domain_id).await?
+        .ok_or_else(|| Error::NotFound(format!("SSO domain {} not found", domain_id)))
+}
+
+/// Lists the SSO domains of a workspace. Requires `workspace:manage_members`.
+pub async fn list_sso_domains(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    requester_id: Uuid,
+) -> Result<Vec<WorkspaceSsoDomainDetailed>> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_MEMBERS).await?;
+    oidc::list_sso_domains(conn, workspace_id).await
+}
+
+/// Removes an SSO domain. Existing members keep their membership.
+/// Requires `workspace:manage_members`.
+pub async fn delete_sso_domain(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    domain_id: Uuid,
+    requester_id: Uuid,
+) -> Result<()> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_MEMBERS).await?;
+
+    if oidc::delete_sso_domain(conn, workspace_id, domain_id).await? == 0 {
+        return Err(Error::NotFound(format!("SSO domain {} not found", domain_id)));
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// Returns `NotFound` unless single sign-on is enabled and configured
+fn ensure_enabled(config: &OidcConfig) -> Result<()> {
+    if !config.enabled || config.issuer_url.is_empty() || config.client_id.is_empty() || config.redirect_url.is_empty() {
+        return Err(Error::NotFound("Single sign-on is not configured".to_string()));
+    }
+    Ok(())
+}
+
+/// Generates a random URL-safe token (32 bytes, hex-encoded)
+///
+/// 64 hex characters also satisfy the PKCE verifier alphabet and length (43-128).
+fn generate_random_token() -> String {
+    let mut random_bytes = [0u8; 32];
+    rand::rng().fill(&mut random_bytes);
+    hex::encode(random_bytes)
+}
+
+/// Computes the S256 PKCE code challenge for a verifier (RFC 7636)
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// Accepts only same-origin absolute paths such as "/workspaces/123"
+pub fn sanitize_redirect_path(path: Option<&str>) -> Option<String> {
+    let path = path?.trim();
+    let is_safe = path.starts_with('/')
+        && !path.starts_with("//")
+        && !path.starts_with("/\\")
+        && path.len() <= MAX_REDIRECT_PATH_LENGTH
+        && !path.chars().any(|c| c.is_control());
+
+    is_safe.then(|| path.to_string())
+}
+
+/// Returns the lowercase domain part of an email address
+fn email_domain(email: &str) -> Option<String> {
+    email.rsplit_once('@')
+        .map(|(_, domain)| domain.to_lowercase())
+        .filter(|d| !d.is_empty())
+}
+
+/// DNS name of the TXT record that verifies an SSO domain
+pub fn verification_record_name(domain: &str) -> String {
+    format!("{}.{}", VERIFICATION_RECORD_PREFIX, domain)
+}
+
+/// Content of the TXT record that verifies an SSO domain
+pub fn verification_record_value(verification_token: &str) -> String {
+    format!("buildscale-verification={}", verification_token)
+}
+
+/// Looks up the TXT records of `name` through the configured DNS-over-HTTPS resolver
+async fn lookup_txt_records(config: &OidcConfig, name: &str) -> Result<Vec<String>> {
+    #[derive(serde::Deserialize)]
+    struct DnsResponse {
+        #[serde(rename = "Answer", default)]
+        answer: Vec<DnsAnswer>,
+    }
+    #[derive(serde::Deserialize)]
+    struct DnsAnswer {
+        #[serde(rename = "type")]
+        record_type: u16,
+        data: String,
+    }
+    const TXT: u16 = 16;
+
+    let url = url::Url::parse_with_params(&config.dns_query_url, &[("name", name), ("type", "TXT")])
+        .map_err(|e| Error::Internal(format!("Invalid DNS query URL: {}", e)))?;
+    let response: DnsResponse = provider_client()?
+        .get(url)
+        .header(reqwest::header::ACCEPT, "application/dns-json")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| Error::Internal(format!("DNS lookup of {} failed: {}", name, e)))?
+        .json()
+        .await
+        .map_err(|e| Error::Internal(format!("Invalid DNS response for {}: {}", name, e)))?;
+
+    Ok(response.answer.into_iter()
+        .filter(|answer| answer.record_type == TXT)
+        .map(|answer| parse_txt_data(&answer.data))
+        .collect())
+}
+
+/// Joins the quoted character strings of a TXT record, e.g. `"abc" "def"` into `abcdef`
+fn parse_txt_data(data: &str) -> String {
+    if !data.starts_with('"') {
+        return data.to_string();
+    }
+    data.split('"').skip(1).step_by(2).collect()
+}
+
+/// Normalizes and validates an email domain such as "example.com"
+fn normalize_domain(domain: &str) -> Result<String> {
+    let domain = domain.trim().trim_start_matches('@').to_lowercase();
+    let invalid = |message: &str| Error::Validation(ValidationErrors::Single {
+        field: "domain".to_string(),
+        message: message.to_string(),
+    });
+
+    if domain.is_empty() || domain.len() > 253 {
+        return Err(invalid("Domain must be between 1 and 253 characters"));
+    }
+
+    let labels: Vec<&str> = domain.split('.').collect();
+    let valid_labels = labels.iter().all(|label| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+    if labels.len() < 2 || !valid_labels {
+        return Err(invalid("Domain must be a valid domain name such as example.com"));
+    }
+
+    Ok(domain)
+}
+
+fn provider_client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(PROVIDER_TIMEOUT_SECONDS))
+        .build()
+        .map_err(|e| Error::Internal(format!("Failed to create HTTP client: {}", e)))
+}
+
+/// Fetches the provider's discovery document and checks it belongs to the configured issuer
+async fn discover(client: &reqwest::Client, config: &OidcConfig) -> Result<OidcProviderMetadata> {
+    let issuer_url = config.issuer_url.trim_end_matches('/');
+    let metadata: OidcProviderMetadata = client
+        .get(format!("{}/.well-known/openid-configuration", issuer_url))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| Error::Internal(format!("OIDC discovery failed: {}", e)))?
+        .json()
+        .await
+        .map_err(|e| Error::Internal(format!("Invalid OIDC discovery document: {}", e)))?;
+
+    if metadata.issuer.trim_end_matches('/') != issuer_url {
+        return Err(Error::Internal(format!(
+            "OIDC discovery issuer '{}' does not match configured issuer '{}'",
+            metadata.issuer, config.issuer_url
+        )));
+    }
+
+    Ok(metadata)
+}
+
+/// Exchanges an authorization code for an ID token
+async fn exchange_code(
+    client: &reqwest::Client,
+    config: &OidcConfig,
+    metadata: &OidcProviderMetadata,
+    code: &str,
+    code_verifier: &str,
+) -> Result<String> {
+    use secrecy::ExposeSecret;
+
+    let body = {
+        let mut form = url::form_urlencoded::Serializer::new(String::new());
+        form.append_pair("grant_type", "authorization_code")
+            .append_pair("code", code)
+            .append_pair("redirect_uri", &config.redirect_url)
+            .append_pair("client_id", &config.client_id)
+            .append_pair("code_verifier", code_verifier);
+        let client_secret = config.client_secret.expose_secret();
+        if !client_secret.is_empty() {
+            form.append_pair("client_secret", client_secret);
+        }
+        form.finish()
+    };
+
+    let response = client
+        .post(&metadata.token_endpoint)
+        .header(reqwest::header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .header(reqwest::header::ACCEPT, "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| Error::Internal(format!("OIDC token request failed: {}", e)))?;
+
+    if !response.status().is_success() {
+        tracing::warn!(
+            status = %response.status(),
+            security_event = "oidc_code_exchange_failed",
+            "Identity provider rejected the authorization code"
+        );
+        return Err(Error::Authentication(
+            "Identity provider rejected the authorization code".to_string(),
+        ));
+    }
+
+    let tokens: OidcTokenResponse = response
+        .json()
+        .await
+        .map_err(|e| Error::Internal(format!("Invalid OIDC token response: {}", e)))?;
+
+    Ok(tokens.id_token)
+}
+
+/// Fetches the provider's signing keys
+async fn fetch_jwks(client: &reqwest::Client, metadata: &OidcProviderMetadata) -> Result<JwkSet> {
+    client
+        .get(&metadata.jwks_uri)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| Error::Internal(format!("Failed to fetch OIDC signing keys: {}", e)))?
+        .json()
+        .await
+        .map_err(|e| Error::Internal(format!("Invalid OIDC signing keys: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pkce_challenge_matches_rfc_7636_example() {
+        // RFC 7636, Appendix B
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_redirect_path() {
+        assert_eq!(sanitize_redirect_path(Some("/workspaces/1?tab=files")).as_deref(), Some("/workspaces/1?tab=files"));
+        assert_eq!(sanitize_redirect_path(Some("//evil.example.com")), None);
+        assert_eq!(sanitize_redirect_path(Some("/\\evil.example.com")), None);
+        assert_eq!(sanitize_redirect_path(Some("https://evil.example.com")), None);
+        assert_eq!(sanitize_redirect_path(Some("/path\nSet-Cookie: x")), None);
+        assert_eq!(sanitize_redirect_path(None), None);
+    }
+
+    #[test]
+    fn test_normalize_domain() {
+        assert_eq!(normalize_domain(" @Example.COM ").unwrap(), "example.com");
+        assert_eq!(normalize_domain("sub.example.co.uk").unwrap(), "sub.example.co.uk");
+        assert!(normalize_domain("localhost").is_err());
+        assert!(normalize_domain("-bad.example.com").is_err());
+        assert!(normalize_domain("exa mple.com").is_err());
+        assert!(normalize_domain("example..com").is_err());
+    }
+
+    #[test]
+    fn test_email_domain() {
+        assert_eq!(email_domain("alice@example.com").as_deref(), Some("example.com"));
+        assert_eq!(email_domain("Alice@Example.COM").as_deref(), Some("example.com"));
+        assert_eq!(email_domain("alice"), None);
+        assert_eq!(email_domain("alice@"), None);
+    }
+
+    #[test]
+    fn test_parse_txt_data() {
+        assert_eq!(parse_txt_data("\"buildscale-verification=abc\""), "buildscale-verification=abc");
+        assert_eq!(parse_txt_data("\"buildscale-verification=\" \"abc\""), "buildscale-verification=abc");
+        assert_eq!(parse_txt_data("v=spf1 -all"), "v=spf1 -all");
+    }
+}
@@ -0,0 +1,402 @@
+//! Sandboxed command execution for the `run` tool.
+//!
+//! Commands run with bubblewrap (`bwrap`) in new user, mount, PID, IPC, UTS and
+//! cgroup namespaces, and a network namespace of their own unless the sandbox
+//! allows network access. All capabilities are dropped and the environment is
+//! cleared. The sandbox only sees the host's system directories, read-only, a
+//! private `/tmp` and the workspace's `latest/` tree, read-write at `/workspace`.
+//!
+//! CPU time, address space and file sizes are limited with rlimits set by the
+//! shell starting the command. Wall time is limited by killing bwrap, which
+//! takes every process of the sandbox's PID namespace with it. bwrap sets
+//! `no_new_privs`, but no seccomp filter is installed: the command can make any
+//! syscall the namespaces leave meaningful.
+//!
+//! Files the command changes are found by comparing snapshots of the tree taken
+//! before and after it ran, see [`snapshot_tree`] and [`diff_snapshots`].
+
+use crate::config::SandboxConfig;
+use crate::error::{Error, Result};
+use crate::models::requests::RunChangeKind;
+use crate::tools::ToolCallContext;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+
+/// Where the workspace's `latest/` tree is mounted in the sandbox
+pub const WORKSPACE_MOUNT: &str = "/workspace";
+
+/// Host directories mounted read-only in the sandbox, when they exist
+const SYSTEM_PATHS: &[&str] = &[
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/etc/alternatives",
+    "/etc/ld.so.cache",
+    "/etc/ssl",
+    "/etc/ca-certificates",
+];
+
+/// Host files name resolution needs, mounted only when the network is shared
+const NETWORK_PATHS: &[&str] = &["/etc/resolv.conf", "/etc/hosts", "/etc/nsswitch.conf"];
+
+/// How long output is still read once the command is gone
+///
+/// Processes left behind keep the pipes open, the sandbox's PID namespace
+/// normally takes them down with the command.
+const OUTPUT_GRACE: Duration = Duration::from_secs(1);
+
+/// Shell script applying the sandbox's rlimits before running the command in `$1`
+///
+/// File sizes are in 512-byte blocks and the address space in KiB, the units of
+/// `ulimit` in POSIX shells.
+pub fn limits_script(config: &SandboxConfig, timeout: Duration) -> String {
+    let cpu_seconds = match config.cpu_seconds {
+        0 => timeout.as_secs().max(1),
+        seconds => seconds,
+    };
+    let mut limits = vec![
+        format!("ulimit -t {}", cpu_seconds),
+        format!("ulimit -f {}", config.max_file_size_mb * 2048),
+    ];
+    if config.memory_mb > 0 {
+        limits.push(format!("ulimit -v {}", config.memory_mb * 1024));
+    }
+    format!("{} && exec /bin/sh -c \"$1\"", limits.join(" && "))
+}
+
+/// Arguments of the bwrap call running `command` in the workspace folder `cwd`
+///
+/// `workspace_dir` is the host path of the workspace's `latest/` tree and `cwd`
+/// a normalized workspace path.
+pub fn bwrap_args(
+    config: &SandboxConfig,
+    workspace_dir: &Path,
+    cwd: &str,
+    command: &str,
+    timeout: Duration,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = Vec::new();
+    let mut push = |items: &[&str]| args.extend(items.iter().map(OsString::from));
+
+    push(&["--die-with-parent", "--new-session", "--unshare-all"]);
+    if config.network {
+        push(&["--share-net"]);
+    }
+    push(&["--cap-drop", "ALL", "--clearenv"]);
+    push(&["--setenv", "PATH", "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin"]);
+    push(&["--setenv", "HOME", "/tmp", "--setenv", "TMPDIR", "/tmp", "--setenv", "LANG", "C.UTF-8"]);
+    for path in SYSTEM_PATHS {
+        push(&["--ro-bind-try", path, path]);
+    }
+    if config.network {
+        for path in NETWORK_PATHS {
+            push(&["--ro-bind-try", path, path]);
+        }
+    }
+    push(&["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp", "--hostname", "sandbox"]);
+
+    args.push("--bind".into());
+    args.push(workspace_dir.into());
+    args.push(WORKSPACE_MOUNT.into());
+    args.push("--chdir".into());
+    args.push(format!("{}{}", WORKSPACE_MOUNT, cwd.trim_end_matches('/')).into());
+
+    args.push("--".into());
+    args.push("/bin/sh".into());
+    args.push("-c".into());
+    args.push(limits_script(config, timeout).into());
+    args.push("sh".into());
+    args.push(command.into());
+    args
+}
+
+/// Output and exit status of a sandboxed command
+#[derive(Debug)]
+pub struct SandboxOutput {
+    /// Exit code, `None` if the command was killed
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    /// The tool call was cancelled while the command ran
+    pub cancelled: bool,
+    pub stdout: String,
+    pub stderr: String,
+    /// stdout or stderr was cut at `max_output_bytes`
+    pub truncated: bool,
+    pub duration: Duration,
+}
+
+/// Runs `command` in the sandbox until it exits, runs past `timeout` or the
+/// tool call is cancelled
+///
+/// stdout and stderr are streamed to the tool call as they are printed. A
+/// missing bwrap is an error, commands never run outside of the sandbox.
+pub async fn run_sandboxed(
+    config: &SandboxConfig,
+    workspace_dir: &Path,
+    cwd: &str,
+    command: &str,
+    timeout: Duration,
+    call: &ToolCallContext,
+) -> Result<SandboxOutput> {
+    let started = Instant::now();
+    let mut child = Command::new(&config.bwrap_path)
+        .args(bwrap_args(config, workspace_dir, cwd, command, timeout))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            Error::Internal(format!(
+                "Failed to start the sandbox ({}): {}. Is bubblewrap installed?",
+                config.bwrap_path, e
+            ))
+        })?;
+
+    let stdout = Arc::new(Mutex::new(CapturedOutput::default()));
+    let stderr = Arc::new(Mutex::new(CapturedOutput::default()));
+    let readers = [
+        tokio::spawn(capture(
+            child.stdout.take().expect("stdout is piped"),
+            stdout.clone(),
+            config.max_output_bytes,
+            call.clone(),
+        )),
+        tokio::spawn(capture(
+            child.stderr.take().expect("stderr is piped"),
+            stderr.clone(),
+            config.max_output_bytes,
+            call.clone(),
+        )),
+    ];
+
+    let (mut timed_out, mut cancelled) = (false, false);
+    let status = tokio::select! {
+        status = child.wait() => Some(status),
+        _ = tokio::time::sleep(timeout) => {
+            timed_out = true;
+            None
+        }
+        _ = call.cancellation.cancelled() => {
+            cancelled = true;
+            None
+        }
+    };
+    let status = match status {
+        Some(status) => status,
+        None => {
+            let _ = child.start_kill();
+            child.wait().await
+        }
+    }
+    .map_err(|e| Error::Internal(format!("Failed to wait for the sandbox: {}", e)))?;
+
+    for mut reader in readers {
+        if tokio::time::timeout(OUTPUT_GRACE, &mut reader).await.is_err() {
+            tracing::debug!("Sandbox output still open after the command exited");
+            reader.abort();
+        }
+    }
+
+    let stdout = std::mem::take(&mut *stdout.lock().expect("output lock"));
+    let stderr = std::mem::take(&mut *stderr.lock().expect("output lock"));
+    Ok(SandboxOutput {
+        exit_code: if timed_out || cancelled { None } else { status.code() },
+        timed_out,
+        cancelled,
+        truncated: stdout.truncated || stderr.truncated,
+        stdout: String::from_utf8_lossy(&stdout.bytes).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr.bytes).into_owned(),
+        duration: started.elapsed(),
+    })
+}
+
+#[derive(Debug, Default)]
+struct CapturedOutput {
+    bytes: Vec<u8>,
+    truncated: bool,
+}
+
+/// Reads a stream to its end, keeping its first `max_bytes` and streaming all of it
+async fn capture(
+    mut stream: impl AsyncRead + Unpin,
+    output: Arc<Mutex<CapturedOutput>>,
+    max_bytes: usize,
+    call: ToolCallContext,
+) {
+    let mut buf = [0u8; 8192];
+    loop {
+        let read = match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => read,
+        };
+        let chunk = &buf[..read];
+        call.send_output(String::from_utf8_lossy(chunk));
+
+        let mut output = output.lock().expect("output lock");
+        let room = max_bytes.saturating_sub(output.bytes.len());
+        if chunk.len() > room {
+            output.truncated = true;
+        }
+        output.bytes.extend_from_slice(&chunk[..chunk.len().min(room)]);
+    }
+}
+
+/// Entry of a workspace tree snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeEntry {
+    /// Regular file, by size and modification time
+    File { len: u64, modified: Option<SystemTime> },
+    /// Symbolic link, FIFO, socket or device
+    Special,
+}
+
+/// Files of a workspace tree by workspace path (`/src/main.rs`), folders excluded
+pub type TreeSnapshot = BTreeMap<String, TreeEntry>;
+
+/// Takes a snapshot of the tree at `root`, without following links
+///
+/// Blocking, run it with `spawn_blocking`. A missing root is an empty tree.
+pub fn snapshot_tree(root: &Path) -> std::io::Result<TreeSnapshot> {
+    let mut snapshot = TreeSnapshot::new();
+    if root.exists() {
+        snapshot_dir(root, "", &mut snapshot)?;
+    }
+    Ok(snapshot)
+}
+
+fn snapshot_dir(dir: &Path, prefix: &str, snapshot: &mut TreeSnapshot) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            snapshot_dir(&entry.path(), &path, snapshot)?;
+        } else if file_type.is_file() {
+            let metadata = entry.metadata()?;
+            snapshot.insert(
+                path,
+                TreeEntry::File {
+                    len: metadata.len(),
+                    modified: metadata.modified().ok(),
+                },
+            );
+        } else {
+            snapshot.insert(path, TreeEntry::Special);
+        }
+    }
+    Ok(())
+}
+
+/// Files created, modified or deleted between two snapshots, by path
+pub fn diff_snapshots(before: &TreeSnapshot, after: &TreeSnapshot) -> Vec<(String, RunChangeKind)> {
+    let mut changes: Vec<(String, RunChangeKind)> = after
+        .iter()
+        .filter_map(|(path, entry)| match before.get(path) {
+            None => Some((path.clone(), RunChangeKind::Created)),
+            Some(previous) if previous != entry => Some((path.clone(), RunChangeKind::Modified)),
+            Some(_) => None,
+        })
+        .collect();
+    changes.extend(
+        before
+            .keys()
+            .filter(|path| !after.contains_key(*path))
+            .map(|path| (path.clone(), RunChangeKind::Deleted)),
+    );
+    changes.sort_by(|a, b| a.0.cmp(&b.0));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args_text(config: &SandboxConfig) -> Vec<String> {
+        bwrap_args(config, Path::new("/data/ws/latest"), "/src", "cargo test", Duration::from_secs(30))
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_bwrap_args() {
+        let args = args_text(&SandboxConfig::default());
+        let joined = args.join(" ");
+
+        assert!(joined.starts_with("--die-with-parent --new-session --unshare-all --cap-drop ALL --clearenv"));
+        assert!(!args.contains(&"--share-net".to_string()));
+        assert!(!joined.contains("/etc/resolv.conf"));
+        assert!(joined.contains("--ro-bind-try /usr /usr"));
+        assert!(joined.contains("--bind /data/ws/latest /workspace --chdir /workspace/src --"));
+        assert_eq!(
+            &args[args.len() - 5..],
+            &[
+                "/bin/sh",
+                "-c",
+                "ulimit -t 30 && ulimit -f 204800 && ulimit -v 4194304 && exec /bin/sh -c \"$1\"",
+                "sh",
+                "cargo test",
+            ]
+        );
+
+        let config = SandboxConfig {
+            network: true,
+            memory_mb: 0,
+            cpu_seconds: 5,
+            ..SandboxConfig::default()
+        };
+        let args = args_text(&config);
+        assert!(args.contains(&"--share-net".to_string()));
+        assert!(args.join(" ").contains("--ro-bind-try /etc/resolv.conf /etc/resolv.conf"));
+        assert_eq!(args[args.len() - 3], "ulimit -t 5 && ulimit -f 204800 && exec /bin/sh -c \"$1\"");
+    }
+
+    #[test]
+    fn test_root_cwd_mounts_at_workspace() {
+        let args: Vec<OsString> =
+            bwrap_args(&SandboxConfig::default(), Path::new("/ws"), "/", "ls", Duration::from_secs(1));
+        let chdir = args.iter().position(|arg| arg == "--chdir").unwrap();
+        assert_eq!(args[chdir + 1], "/workspace");
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let dir = std::env::temp_dir().join(format!("sandbox-diff-{}", uuid::Uuid::now_v7()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.join("notes.md"), "notes").unwrap();
+        std::fs::write(dir.join("old.txt"), "old").unwrap();
+        let before = snapshot_tree(&dir).unwrap();
+        assert_eq!(before.len(), 3);
+
+        std::fs::write(dir.join("src/main.rs"), "fn main() { println!(); }").unwrap();
+        std::fs::remove_file(dir.join("old.txt")).unwrap();
+        std::fs::create_dir(dir.join("target")).unwrap();
+        std::fs::write(dir.join("target/out.txt"), "built").unwrap();
+        std::os::unix::fs::symlink("/etc/passwd", dir.join("passwd")).unwrap();
+        let after = snapshot_tree(&dir).unwrap();
+
+        assert_eq!(after.get("/passwd"), Some(&TreeEntry::Special));
+        assert_eq!(
+            diff_snapshots(&before, &after),
+            vec![
+                ("/old.txt".to_string(), RunChangeKind::Deleted),
+                ("/passwd".to_string(), RunChangeKind::Created),
+                ("/src/main.rs".to_string(), RunChangeKind::Modified),
+                ("/target/out.txt".to_string(), RunChangeKind::Created),
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
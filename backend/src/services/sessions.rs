@@ -1,8 +1,13 @@
 use crate::DbConn;
 use crate::{
     error::{Error, Result, ValidationErrors},
+    models::users::{SessionClient, SessionInfo},
     queries::sessions,
 };
+use uuid::Uuid;
+
+/// Longest user agent that is stored with a session
+const MAX_USER_AGENT_LENGTH: usize = 512;
 
 /// Cleans up all expired sessions from the database
 /// This should be called periodically to maintain database performance
@@ -75,4 +80,89 @@ pub async fn extend_all_user_sessions(conn: &mut DbConn, user_id: uuid::Uuid, ho
     }
 
     Ok(extended_count)
-}
\ No newline at end of file
+}
+
+/// Lists the active sessions of a user for the session management UI
+///
+/// `current_session_id` is the session of the requesting access token; that session
+/// is flagged as `current`.
+pub async fn list_user_sessions(
+    conn: &mut DbConn,
+    user_id: Uuid,
+    current_session_id: Option<Uuid>,
+) -> Result<Vec<SessionInfo>> {
+    let sessions = sessions::get_active_sessions_by_user(conn, user_id).await?;
+
+    Ok(sessions
+        .into_iter()
+        .map(|session| SessionInfo {
+            current: Some(session.id) == current_session_id,
+            id: session.id,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+            expires_at: session.expires_at,
+        })
+        .collect())
+}
+
+/// Revokes one of the user's own sessions by its ID
+///
+/// The refresh token of the session stops working immediately. Access tokens that were
+/// already issued for it stay valid until they expire.
+pub async fn revoke_user_session(conn: &mut DbConn, user_id: Uuid, session_id: Uuid) -> Result<()> {
+    let rows_affected = sessions::delete_user_session(conn, user_id, session_id).await?;
+
+    if rows_affected == 0 {
+        return Err(Error::NotFound(format!("Session {} not found", session_id)));
+    }
+
+    tracing::info!(
+        user_id = %user_id,
+        session_id = %session_id,
+        security_event = "session_revoked",
+        "User revoked a session"
+    );
+
+    Ok(())
+}
+
+/// Revokes every session of the user except the current one ("log out everywhere else")
+///
+/// Returns the number of revoked sessions.
+pub async fn revoke_other_sessions(
+    conn: &mut DbConn,
+    user_id: Uuid,
+    current_session_id: Option<Uuid>,
+) -> Result<u64> {
+    let current_session_id = current_session_id.ok_or_else(|| {
+        Error::InvalidToken("Access token is not bound to a session. Refresh it and try again.".to_string())
+    })?;
+
+    let rows_affected = sessions::delete_other_user_sessions(conn, user_id, current_session_id).await?;
+
+    tracing::info!(
+        user_id = %user_id,
+        session_id = %current_session_id,
+        revoked_sessions = rows_affected,
+        security_event = "other_sessions_revoked",
+        "User logged out all other sessions"
+    );
+
+    Ok(rows_affected)
+}
+
+/// Records the device of a login or refresh and marks the session as used
+pub async fn record_session_client(conn: &mut DbConn, session_id: Uuid, client: &SessionClient) -> Result<()> {
+    let user_agent = client.user_agent.as_deref().map(truncate_user_agent);
+    sessions::touch_session(conn, session_id, user_agent, client.ip_address.as_deref()).await?;
+    Ok(())
+}
+
+fn truncate_user_agent(user_agent: &str) -> &str {
+    match user_agent.char_indices().nth(MAX_USER_AGENT_LENGTH) {
+        Some((index, _)) => &user_agent[..index],
+        None => user_agent,
+    }
+}
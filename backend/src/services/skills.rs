@@ -0,0 +1,336 @@
+//! Skill files: reusable instruction packs the agent can activate.
+//!
+//! A skill is any workspace file of type `skill` (conventionally
+//! `/system/skills/{name}/SKILL.md`) whose YAML frontmatter carries a name,
+//! description, triggers and allowed tools. The frontmatter feeds the skill
+//! catalog in the system prompt; the body is only loaded when the agent calls
+//! `skill_activate` or a user pins the skill to a chat.
+
+use crate::DbConn;
+use crate::{
+    error::{Error, Result},
+    models::chat::ChatAttachment,
+    models::files::FileType,
+    models::requests::{SkillActivateResult, SkillListItem},
+    queries::files as file_queries,
+    services::files,
+    services::storage::FileStorageService,
+    utils::{parse_skill_frontmatter, skill_name_from_path, SkillMetadata},
+};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Most skills listed individually in the system prompt catalog
+const MAX_CATALOG_SKILLS: usize = 50;
+
+/// A skill file resolved to its metadata and instructions
+#[derive(Debug, Clone)]
+pub struct Skill {
+    pub id: Uuid,
+    pub path: String,
+    /// Frontmatter with `name` always filled in
+    pub metadata: SkillMetadata,
+    /// Skill body (everything after the frontmatter)
+    pub instructions: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Skill {
+    /// Returns true if the query matches the name, description or a trigger
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        query.is_empty()
+            || self.metadata.name.to_lowercase().contains(&query)
+            || self.metadata.description.to_lowercase().contains(&query)
+            || self.metadata.triggers.iter().any(|t| t.to_lowercase().contains(&query))
+    }
+
+    pub fn to_list_item(&self) -> SkillListItem {
+        SkillListItem {
+            id: self.id,
+            path: self.path.clone(),
+            name: self.metadata.name.clone(),
+            description: self.metadata.description.clone(),
+            triggers: self.metadata.triggers.clone(),
+            allowed_tools: self.metadata.allowed_tools.clone(),
+        }
+    }
+
+    pub fn to_activate_result(&self) -> SkillActivateResult {
+        SkillActivateResult {
+            id: self.id,
+            path: self.path.clone(),
+            name: self.metadata.name.clone(),
+            description: self.metadata.description.clone(),
+            allowed_tools: self.metadata.allowed_tools.clone(),
+            instructions: self.instructions.clone(),
+        }
+    }
+}
+
+/// Lists all skills in a workspace, sorted by name
+///
+/// Skill files whose content cannot be read are skipped with a warning so a
+/// single broken file does not hide the rest of the catalog.
+pub async fn list_skills(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    workspace_id: Uuid,
+) -> Result<Vec<Skill>> {
+    let skill_files = file_queries::get_files_by_type(conn, workspace_id, FileType::Skill).await?;
+
+    let mut skills = Vec::with_capacity(skill_files.len());
+    for file in skill_files {
+        match load_skill(conn, storage, file.id).await {
+            Ok(skill) => skills.push(skill),
+            Err(e) => {
+                tracing::warn!(
+                    workspace_id = %workspace_id,
+                    file_id = %file.id,
+                    path = %file.path,
+                    error = %e,
+                    "Skipping unreadable skill file"
+                );
+            }
+        }
+    }
+
+    skills.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name).then_with(|| a.path.cmp(&b.path)));
+    Ok(skills)
+}
+
+/// Gets a skill by file ID, scoped to the workspace
+pub async fn get_skill(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    workspace_id: Uuid,
+    skill_id: Uuid,
+) -> Result<Skill> {
+    let not_found = || Error::NotFound(format!("Skill not found: {}", skill_id));
+
+    let file = match file_queries::get_file_by_id(conn, skill_id).await {
+        Ok(file) => file,
+        Err(Error::NotFound(_)) => return Err(not_found()),
+        Err(e) => return Err(e),
+    };
+
+    if file.workspace_id != workspace_id
+        || file.file_type != FileType::Skill
+        || file.deleted_at.is_some()
+    {
+        return Err(not_found());
+    }
+
+    load_skill(conn, storage, file.id).await
+}
+
+/// Finds a skill by name (case-insensitive)
+pub async fn find_skill_by_name(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    workspace_id: Uuid,
+    name: &str,
+) -> Result<Skill> {
+    let name = name.trim();
+    list_skills(conn, storage, workspace_id)
+        .await?
+        .into_iter()
+        .find(|skill| skill.metadata.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| Error::NotFound(format!(
+            "Skill not found: {}. Use skill_list to see available skills.",
+            name
+        )))
+}
+
+/// Resolves skill IDs from a chat request into `ChatAttachment::Skill` entries
+///
+/// Fails with `NotFound` if any ID is not a skill in this workspace.
+pub async fn resolve_skill_attachments(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    workspace_id: Uuid,
+    skill_ids: &[Uuid],
+) -> Result<Vec<ChatAttachment>> {
+    let mut attachments = Vec::with_capacity(skill_ids.len());
+    for skill_id in skill_ids {
+        let skill = get_skill(conn, storage, workspace_id, *skill_id).await?;
+        attachments.push(ChatAttachment::Skill {
+            skill_id: skill.id,
+            name: skill.metadata.name,
+        });
+    }
+    Ok(attachments)
+}
+
+/// Builds the skill catalog section for the system prompt
+///
+/// Only names, descriptions and triggers are listed; the agent loads the
+/// instructions with `skill_activate`. Returns `None` if there are no skills.
+pub fn build_skill_catalog(skills: &[Skill]) -> Option<String> {
+    if skills.is_empty() {
+        return None;
+    }
+
+    let mut catalog = String::from(
+        "## Available Skills\n\n\
+         Skills are instruction packs stored in this workspace. When a request matches a skill, \
+         call `skill_activate` with its name to load the full instructions before starting the work.\n",
+    );
+
+    for skill in skills.iter().take(MAX_CATALOG_SKILLS) {
+        catalog.push_str(&format!("\n- {}", skill.metadata.name));
+        if !skill.metadata.description.is_empty() {
+            catalog.push_str(&format!(": {}", skill.metadata.description));
+        }
+        if !skill.metadata.triggers.is_empty() {
+            catalog.push_str(&format!(" (triggers: {})", skill.metadata.triggers.join(", ")));
+        }
+    }
+
+    if skills.len() > MAX_CATALOG_SKILLS {
+        catalog.push_str(&format!(
+            "\n- ... and {} more, use `skill_list` to search them",
+            skills.len() - MAX_CATALOG_SKILLS
+        ));
+    }
+
+    Some(catalog)
+}
+
+/// Appends the skill catalog to a persona, leaving it unchanged without skills
+pub fn append_skill_catalog(persona: &str, skills: &[Skill]) -> String {
+    match build_skill_catalog(skills) {
+        Some(catalog) => format!("{}\n\n{}", persona.trim_end(), catalog),
+        None => persona.to_string(),
+    }
+}
+
+/// Appends the workspace skill catalog to a persona
+///
+/// Failing to list skills must not block the chat, so errors are logged and
+/// the persona is returned unchanged.
+pub async fn persona_with_skill_catalog(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    workspace_id: Uuid,
+    persona: &str,
+) -> String {
+    match list_skills(conn, storage, workspace_id).await {
+        Ok(skills) => append_skill_catalog(persona, &skills),
+        Err(e) => {
+            tracing::warn!(workspace_id = %workspace_id, error = %e, "Failed to load skill catalog");
+            persona.to_string()
+        }
+    }
+}
+
+/// Renders a pinned skill for the AI context
+///
+/// The skill's allowed tools are listed for the agent to follow, they do not
+/// restrict the chat's toolset (agent files and the tool policy do that).
+pub fn render_pinned_skill(skill: &Skill) -> String {
+    let mut rendered = format!(
+        "<active_skill name=\"{}\" path=\"{}\">\n",
+        skill.metadata.name, skill.path
+    );
+    if !skill.metadata.allowed_tools.is_empty() {
+        rendered.push_str(&format!(
+            "Allowed tools: {}\n\n",
+            skill.metadata.allowed_tools.join(", ")
+        ));
+    }
+    rendered.push_str(skill.instructions.trim());
+    rendered.push_str("\n</active_skill>");
+    rendered
+}
+
+async fn load_skill(conn: &mut DbConn, storage: &FileStorageService, file_id: Uuid) -> Result<Skill> {
+    let file_with_content = files::get_file_with_content(conn, storage, file_id).await?;
+    let content = match &file_with_content.content {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    let (metadata, body) = parse_skill_frontmatter(&content);
+    let mut metadata = metadata.unwrap_or_default();
+    if metadata.name.trim().is_empty() {
+        metadata.name = skill_name_from_path(&file_with_content.file.path);
+    }
+
+    Ok(Skill {
+        id: file_with_content.file.id,
+        path: file_with_content.file.path.clone(),
+        instructions: body.trim().to_string(),
+        metadata,
+        updated_at: file_with_content.file.updated_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skill(name: &str, description: &str, triggers: &[&str]) -> Skill {
+        Skill {
+            id: Uuid::now_v7(),
+            path: format!("/system/skills/{}/SKILL.md", name),
+            metadata: SkillMetadata {
+                name: name.to_string(),
+                description: description.to_string(),
+                triggers: triggers.iter().map(|t| t.to_string()).collect(),
+                allowed_tools: vec!["read".to_string()],
+            },
+            instructions: "Do the thing.".to_string(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_catalog_lists_names_descriptions_and_triggers() {
+        let skills = vec![skill("github-pr", "Open pull requests", &["PR"]), skill("lint", "", &[])];
+        let catalog = build_skill_catalog(&skills).unwrap();
+
+        assert!(catalog.contains("skill_activate"));
+        assert!(catalog.contains("- github-pr: Open pull requests (triggers: PR)"));
+        assert!(catalog.contains("- lint"));
+        assert!(!catalog.contains("Do the thing."));
+    }
+
+    #[test]
+    fn test_append_skill_catalog_without_skills_keeps_persona() {
+        assert_eq!(append_skill_catalog("You are helpful.", &[]), "You are helpful.");
+    }
+
+    #[test]
+    fn test_catalog_caps_listed_skills() {
+        let skills: Vec<Skill> = (0..MAX_CATALOG_SKILLS + 3)
+            .map(|i| skill(&format!("skill-{:03}", i), "", &[]))
+            .collect();
+        let catalog = build_skill_catalog(&skills).unwrap();
+
+        assert!(catalog.contains("and 3 more"));
+        assert!(!catalog.contains(&format!("skill-{:03}", MAX_CATALOG_SKILLS)));
+    }
+
+    #[test]
+    fn test_skill_matches_query() {
+        let skill = skill("github-pr", "Open pull requests", &["merge request"]);
+
+        assert!(skill.matches("GitHub"));
+        assert!(skill.matches("pull"));
+        assert!(skill.matches("merge"));
+        assert!(skill.matches(""));
+        assert!(!skill.matches("stripe"));
+    }
+
+    #[test]
+    fn test_render_pinned_skill() {
+        let rendered = render_pinned_skill(&skill("lint", "", &[]));
+
+        assert!(rendered.starts_with("<active_skill name=\"lint\" path=\"/system/skills/lint/SKILL.md\">"));
+        assert!(rendered.contains("Allowed tools: read"));
+        assert!(rendered.contains("Do the thing."));
+        assert!(rendered.ends_with("</active_skill>"));
+    }
+}
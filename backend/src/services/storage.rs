@@ -97,6 +97,24 @@ impl FileStorageService {
         })
     }
 
+    /// Reads the first `len` bytes of a file from Latest directory, enough to tell
+    /// its format without reading all of it
+    pub async fn read_file_head(&self, workspace_id: Uuid, path: &str, len: usize) -> Result<Vec<u8>> {
+        use tokio::io::AsyncReadExt;
+
+        let full_path = self.get_file_path(workspace_id, path)?;
+        let file = fs::File::open(&full_path).await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => Error::NotFound(format!("File not found on disk: {}", path)),
+            _ => Error::Internal(format!("Failed to read file {:?}: {}", full_path, e)),
+        })?;
+
+        let mut head = Vec::with_capacity(len);
+        file.take(len as u64).read_to_end(&mut head).await.map_err(|e| {
+            Error::Internal(format!("Failed to read file {:?}: {}", full_path, e))
+        })?;
+        Ok(head)
+    }
+
     /// Reads a specific version from Archive
     pub async fn read_version(&self, workspace_id: Uuid, hash: &str) -> Result<Vec<u8>> {
         let full_path = self.get_archive_path(workspace_id, hash);
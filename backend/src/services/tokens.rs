@@ -0,0 +1,131 @@
+//! Token counting for context budgeting.
+//!
+//! Counts tokens with the BPE vocabulary of the model's family, using the
+//! tiktoken vocabularies bundled with `tiktoken-rs`, so nothing is downloaded
+//! at runtime. OpenAI models use their own encoding. Other families (Anthropic,
+//! Google, Meta, ...) publish no tiktoken-compatible vocabulary and are counted
+//! with `cl100k_base`, which is much closer than a per-character estimate.
+
+use tiktoken_rs::CoreBPE;
+
+/// BPE vocabulary used to count tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenEncoding {
+    /// GPT-4o, GPT-4.1, GPT-5, gpt-oss and the o-series
+    O200kBase,
+    /// GPT-4, GPT-3.5 and every non-OpenAI family
+    Cl100kBase,
+}
+
+impl TokenEncoding {
+    /// Selects the encoding for a model identifier.
+    ///
+    /// Accepts `provider:model` identifiers and OpenRouter `vendor/model` names,
+    /// e.g. `openai:gpt-5-mini` or `openrouter:openai/gpt-4o`.
+    pub fn for_model(model: &str) -> Self {
+        let name = model.split_once(':').map_or(model, |(_, name)| name);
+        let name = match name.split_once('/') {
+            Some(("openai", name)) => name,
+            // Another vendor's model
+            Some(_) => return TokenEncoding::Cl100kBase,
+            None => name,
+        };
+
+        if let Some(tokenizer) = tiktoken_rs::tokenizer::get_tokenizer(name) {
+            return match tokenizer {
+                tiktoken_rs::tokenizer::Tokenizer::O200kBase => TokenEncoding::O200kBase,
+                _ => TokenEncoding::Cl100kBase,
+            };
+        }
+
+        // Families newer than the tokenizer table
+        let o200k_families = ["gpt-5", "gpt-oss", "gpt-4.5", "o1", "o3", "o4"];
+        if o200k_families.iter().any(|family| name.starts_with(family)) {
+            TokenEncoding::O200kBase
+        } else {
+            TokenEncoding::Cl100kBase
+        }
+    }
+
+    /// Name of the vocabulary, e.g. `o200k_base`
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenEncoding::O200kBase => "o200k_base",
+            TokenEncoding::Cl100kBase => "cl100k_base",
+        }
+    }
+
+    /// Shared tokenizer, loaded on first use
+    fn bpe(&self) -> &'static CoreBPE {
+        match self {
+            TokenEncoding::O200kBase => tiktoken_rs::o200k_base_singleton(),
+            TokenEncoding::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+        }
+    }
+}
+
+/// Counts tokens the way a model's tokenizer does
+///
+/// Cheap to copy; the vocabularies are loaded once per process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenCounter {
+    encoding: TokenEncoding,
+}
+
+impl Default for TokenCounter {
+    /// Counter of the default chat model
+    fn default() -> Self {
+        Self::for_model(crate::models::chat::DEFAULT_CHAT_MODEL)
+    }
+}
+
+impl TokenCounter {
+    /// Counter for a model identifier, see [`TokenEncoding::for_model`]
+    pub fn for_model(model: &str) -> Self {
+        Self {
+            encoding: TokenEncoding::for_model(model),
+        }
+    }
+
+    pub fn encoding(&self) -> TokenEncoding {
+        self.encoding
+    }
+
+    /// Number of tokens in `text`, special tokens counted as plain text
+    pub fn count(&self, text: &str) -> usize {
+        if text.is_empty() {
+            return 0;
+        }
+        self.encoding.bpe().encode_ordinary(text).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding_for_model() {
+        assert_eq!(TokenEncoding::for_model("openai:gpt-5-mini"), TokenEncoding::O200kBase);
+        assert_eq!(TokenEncoding::for_model("openai:gpt-4o"), TokenEncoding::O200kBase);
+        assert_eq!(TokenEncoding::for_model("gpt-4.1-mini"), TokenEncoding::O200kBase);
+        assert_eq!(TokenEncoding::for_model("openai:o3-mini"), TokenEncoding::O200kBase);
+        assert_eq!(TokenEncoding::for_model("openai:gpt-4"), TokenEncoding::Cl100kBase);
+        assert_eq!(TokenEncoding::for_model("openrouter:openai/gpt-oss-120b"), TokenEncoding::O200kBase);
+        assert_eq!(TokenEncoding::for_model("openrouter:anthropic/claude-3.5-sonnet"), TokenEncoding::Cl100kBase);
+        assert_eq!(TokenEncoding::for_model("some-unknown-model"), TokenEncoding::Cl100kBase);
+    }
+
+    #[test]
+    fn test_count_tokens() {
+        let o200k = TokenCounter::for_model("openai:gpt-4o");
+        assert_eq!(o200k.count(""), 0);
+        assert_eq!(o200k.count("hello world"), 2);
+        // Special tokens in user content are plain text
+        assert!(o200k.count("<|endoftext|>") > 1);
+
+        let cl100k = TokenCounter::for_model("openai:gpt-4");
+        assert_eq!(cl100k.count("tiktoken is great!"), 6);
+        assert_eq!(cl100k.encoding().name(), "cl100k_base");
+    }
+}
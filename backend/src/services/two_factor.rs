@@ -0,0 +1,574 @@
+//! TOTP two-factor authentication
+//!
+//! Enrollment is a two-step process: `begin_enrollment` stores a pending secret and
+//! returns it as an `otpauth://` URI, and `confirm_enrollment` enables it once the user
+//! proves their authenticator produces valid codes. Confirmation returns a set of
+//! single-use recovery codes, which are only ever shown once.
+//!
+//! After the password step of a login, users with 2FA enabled receive a short-lived
+//! challenge token instead of a session (see `users::complete_primary_login`). The token
+//! is exchanged for a session in `complete_login` together with a TOTP or recovery code.
+
+use crate::{Config, DbConn, Result};
+use crate::{
+    error::{Error, ValidationErrors},
+    models::{
+        permissions::workspace_permissions,
+        two_factor::{
+            IssuedTwoFactorChallenge, NewTwoFactorChallenge, TotpEnrollment, TwoFactorCodeRequest,
+            TwoFactorStatus, VerifyTwoFactorLoginRequest,
+        },
+        users::LoginResult,
+        workspaces::Workspace,
+    },
+    queries::{two_factor, users, workspace_members, workspaces},
+    services::{oidc, sessions, users as user_service, workspace_members::require_workspace_permission},
+};
+use chrono::{Duration, Utc};
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha1::Sha1;
+use sqlx::Acquire;
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+/// Length of a TOTP time step in seconds (RFC 6238 default)
+const TOTP_STEP_SECONDS: i64 = 30;
+
+/// Number of digits of a TOTP code
+const TOTP_DIGITS: u32 = 6;
+
+/// Accepted clock drift between server and authenticator, in time steps
+const TOTP_ALLOWED_DRIFT: i64 = 1;
+
+/// Number of recovery codes issued at a time
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Wrong codes accepted for one login challenge before it is discarded
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+
+// ============================================================================
+// ENROLLMENT
+// ============================================================================
+
+/// Returns the two-factor state of a user
+pub async fn get_status(conn: &mut DbConn, user_id: Uuid) -> Result<TwoFactorStatus> {
+    let enabled_at = two_factor::get_user_totp(conn, user_id).await?.and_then(|totp| totp.enabled_at);
+    let recovery_codes_remaining = if enabled_at.is_some() {
+        two_factor::count_unused_recovery_codes(conn, user_id).await?
+    } else {
+        0
+    };
+
+    Ok(TwoFactorStatus {
+        enabled: enabled_at.is_some(),
+        enabled_at,
+        recovery_codes_remaining,
+    })
+}
+
+/// Starts TOTP enrollment by generating a new secret
+///
+/// Calling this again before confirming replaces the pending secret. Returns a
+/// `Conflict` error if 2FA is already enabled.
+pub async fn begin_enrollment(conn: &mut DbConn, user_id: Uuid) -> Result<TotpEnrollment> {
+    let user = users::get_user_by_id(conn, user_id).await?
+        .ok_or_else(|| Error::NotFound(format!("User with ID {} not found", user_id)))?;
+
+    let secret = generate_totp_secret();
+    two_factor::upsert_pending_totp(conn, user_id, &secret).await?
+        .ok_or_else(|| Error::Conflict("Two-factor authentication is already enabled".to_string()))?;
+
+    let config = Config::load()?;
+    let otpauth_url = otpauth_url(&config.auth.two_factor_issuer, &user.email, &secret);
+
+    Ok(TotpEnrollment { secret, otpauth_url })
+}
+
+/// Enables 2FA once the user enters a valid code for the pending secret
+///
+/// Returns the recovery codes in plain text. Only their hashes are stored.
+pub async fn confirm_enrollment(conn: &mut DbConn, user_id: Uuid, code: &str) -> Result<Vec<String>> {
+    let totp = two_factor::get_user_totp(conn, user_id).await?
+        .filter(|totp| totp.enabled_at.is_none())
+        .ok_or_else(|| Error::NotFound("No pending two-factor enrollment. Start enrollment first.".to_string()))?;
+
+    let step = verify_totp(&totp.secret, code, Utc::now().timestamp())
+        .ok_or_else(invalid_code_error)?;
+
+    let recovery_codes = generate_recovery_codes();
+
+    let mut tx = conn.begin().await.map_err(|e| {
+        Error::Internal(format!("Failed to begin transaction: {}", e))
+    })?;
+
+    two_factor::enable_totp(&mut tx, user_id, step).await?
+        .ok_or_else(|| Error::Conflict("Two-factor authentication is already enabled".to_string()))?;
+    two_factor::replace_recovery_codes(&mut tx, user_id, &hash_recovery_codes(&recovery_codes)).await?;
+
+    tx.commit().await.map_err(|e| {
+        Error::Internal(format!("Failed to commit transaction: {}", e))
+    })?;
+
+    tracing::info!(
+        user_id = %user_id,
+        security_event = "two_factor_enabled",
+        "Two-factor authentication enabled"
+    );
+
+    Ok(recovery_codes)
+}
+
+/// Replaces all recovery codes after verifying a current TOTP or recovery code
+pub async fn regenerate_recovery_codes(
+    conn: &mut DbConn,
+    user_id: Uuid,
+    proof: &TwoFactorCodeRequest,
+) -> Result<Vec<String>> {
+    ensure_enabled(conn, user_id).await?;
+    if !verify_proof(conn, user_id, proof).await? {
+        return Err(invalid_code_error());
+    }
+
+    let recovery_codes = generate_recovery_codes();
+    two_factor::replace_recovery_codes(conn, user_id, &hash_recovery_codes(&recovery_codes)).await?;
+
+    tracing::info!(
+        user_id = %user_id,
+        security_event = "two_factor_recovery_codes_regenerated",
+        "Two-factor recovery codes regenerated"
+    );
+
+    Ok(recovery_codes)
+}
+
+/// Disables 2FA after verifying a current TOTP or recovery code
+pub async fn disable(conn: &mut DbConn, user_id: Uuid, proof: &TwoFactorCodeRequest) -> Result<()> {
+    ensure_enabled(conn, user_id).await?;
+    if !verify_proof(conn, user_id, proof).await? {
+        return Err(invalid_code_error());
+    }
+
+    remove_two_factor(conn, user_id).await?;
+
+    tracing::info!(
+        user_id = %user_id,
+        security_event = "two_factor_disabled",
+        "Two-factor authentication disabled"
+    );
+
+    Ok(())
+}
+
+// ============================================================================
+// LOGIN
+// ============================================================================
+
+/// Issues a short-lived challenge for the second step of a login
+pub async fn issue_challenge(conn: &mut DbConn, user_id: Uuid) -> Result<IssuedTwoFactorChallenge> {
+    let config = Config::load()?;
+    let challenge_token = generate_challenge_token();
+    let expires_at = Utc::now() + Duration::minutes(config.auth.two_factor_challenge_expiration_minutes);
+
+    two_factor::create_challenge(conn, NewTwoFactorChallenge {
+        user_id,
+        token_hash: two_factor::hash_two_factor_token(&challenge_token),
+        expires_at,
+    }).await?;
+
+    Ok(IssuedTwoFactorChallenge { user_id, challenge_token, expires_at })
+}
+
+/// Completes a login by exchanging a challenge token and a second factor for a session
+///
+/// A challenge is single-use and is discarded after too many wrong codes, after which
+/// the user has to start over with their password.
+pub async fn complete_login(conn: &mut DbConn, request: VerifyTwoFactorLoginRequest) -> Result<LoginResult> {
+    let token_hash = two_factor::hash_two_factor_token(request.challenge_token.trim());
+    let challenge = two_factor::get_active_challenge(conn, &token_hash).await?
+        .filter(|challenge| challenge.failed_attempts < MAX_CHALLENGE_ATTEMPTS)
+        .ok_or_else(|| Error::InvalidToken("Invalid or expired challenge token".to_string()))?;
+
+    if !verify_proof(conn, challenge.user_id, &request.proof).await? {
+        let attempts = two_factor::record_failed_challenge_attempt(conn, challenge.id).await?;
+        if attempts >= MAX_CHALLENGE_ATTEMPTS {
+            two_factor::consume_challenge(conn, challenge.id).await?;
+        }
+
+        tracing::warn!(
+            user_id = %challenge.user_id,
+            failed_attempts = attempts,
+            security_event = "two_factor_login_failed",
+            "Invalid two-factor code during login"
+        );
+
+        return Err(Error::Authentication("Invalid two-factor code".to_string()));
+    }
+
+    // Two concurrent requests may both pass verification; only one may consume the challenge
+    if !two_factor::consume_challenge(conn, challenge.id).await? {
+        return Err(Error::InvalidToken("Invalid or expired challenge token".to_string()));
+    }
+
+    let user = users::get_user_by_id(conn, challenge.user_id).await?
+        .ok_or_else(|| Error::InvalidToken("Invalid or expired challenge token".to_string()))?;
+
+    user_service::issue_login_session(conn, user).await
+}
+
+// ============================================================================
+// WORKSPACE POLICY
+// ============================================================================
+
+/// Turns the workspace's "require 2FA" policy on or off
+///
+/// Only the workspace owner may change the policy, and they must have 2FA enabled
+/// themselves before requiring it so they cannot lock themselves out.
+pub async fn set_workspace_policy(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    requester_id: Uuid,
+    require_two_factor: bool,
+) -> Result<Workspace> {
+    if !workspaces::is_workspace_owner(conn, workspace_id, requester_id).await? {
+        return Err(Error::Forbidden(
+            "Only the workspace owner can change the two-factor policy".to_string(),
+        ));
+    }
+
+    if require_two_factor && !two_factor::is_two_factor_enabled(conn, requester_id).await? {
+        return Err(Error::Forbidden(
+            "Enable two-factor authentication on your own account before requiring it".to_string(),
+        ));
+    }
+
+    let workspace = workspaces::set_require_two_factor(conn, workspace_id, require_two_factor).await?;
+
+    tracing::info!(
+        workspace_id = %workspace_id,
+        require_two_factor,
+        changed_by = %requester_id,
+        security_event = "workspace_two_factor_policy_changed",
+        "Workspace two-factor policy changed"
+    );
+
+    Ok(workspace)
+}
+
+/// Rejects users without 2FA from workspaces that require it
+pub async fn ensure_workspace_policy(conn: &mut DbConn, workspace_id: Uuid, user_id: Uuid) -> Result<()> {
+    if two_factor::is_blocked_by_two_factor_policy(conn, workspace_id, user_id).await? {
+        return Err(Error::TwoFactorRequired(
+            "This workspace requires two-factor authentication. Enable it in your account settings to continue.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Removes 2FA from a member who lost their authenticator and recovery codes
+///
+/// Requires `workspace:manage_members`. 2FA belongs to the member's account rather than
+/// the workspace, so only members whose account the workspace manages can be reset:
+/// those with a verified email address at one of its SSO domains, which the workspace
+/// proved to control through DNS (see [`oidc::verify_sso_domain`]). The workspace owner
+/// can only be reset by themselves, and members reset their own 2FA through `disable`.
+/// All sessions of the member are revoked, so they have to log in again with their
+/// password and re-enroll.
+pub async fn reset_member_two_factor(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    requester_id: Uuid,
+    member_user_id: Uuid,
+) -> Result<()> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_MEMBERS).await?;
+
+    if member_user_id == requester_id {
+        return Err(Error::Validation(ValidationErrors::Single {
+            field: "user_id".to_string(),
+            message: "Use your own two-factor settings to disable two-factor authentication".to_string(),
+        }));
+    }
+
+    if workspaces::is_workspace_owner(conn, workspace_id, member_user_id).await? {
+        return Err(Error::Forbidden(
+            "Two-factor authentication of the workspace owner cannot be reset".to_string(),
+        ));
+    }
+
+    workspace_members::get_workspace_member_optional(conn, workspace_id, member_user_id).await?
+        .ok_or_else(|| Error::NotFound(format!("User {} is not a member of this workspace", member_user_id)))?;
+
+    let member = users::get_user_by_id(conn, member_user_id).await?
+        .ok_or_else(|| Error::NotFound(format!("User with ID {} not found", member_user_id)))?;
+    if !oidc::is_sso_managed_user(conn, workspace_id, &member).await? {
+        return Err(Error::Forbidden(
+            "Two-factor authentication can only be reset for members at one of the workspace's verified SSO domains".to_string(),
+        ));
+    }
+
+    if !two_factor::is_two_factor_enabled(conn, member_user_id).await? {
+        return Err(Error::NotFound(
+            "Member does not have two-factor authentication enabled".to_string(),
+        ));
+    }
+
+    let mut tx = conn.begin().await.map_err(|e| {
+        Error::Internal(format!("Failed to begin transaction: {}", e))
+    })?;
+
+    remove_two_factor(&mut tx, member_user_id).await?;
+    sessions::revoke_all_user_sessions(&mut tx, member_user_id).await?;
+
+    tx.commit().await.map_err(|e| {
+        Error::Internal(format!("Failed to commit transaction: {}", e))
+    })?;
+
+    tracing::warn!(
+        workspace_id = %workspace_id,
+        user_id = %member_user_id,
+        reset_by = %requester_id,
+        security_event = "two_factor_reset",
+        "Two-factor authentication reset by workspace admin, all sessions revoked"
+    );
+
+    Ok(())
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+async fn ensure_enabled(conn: &mut DbConn, user_id: Uuid) -> Result<()> {
+    if !two_factor::is_two_factor_enabled(conn, user_id).await? {
+        return Err(Error::NotFound("Two-factor authentication is not enabled".to_string()));
+    }
+    Ok(())
+}
+
+/// Deletes the authenticator, recovery codes and pending challenges of a user
+async fn remove_two_factor(conn: &mut DbConn, user_id: Uuid) -> Result<()> {
+    two_factor::delete_user_totp(conn, user_id).await?;
+    two_factor::delete_recovery_codes(conn, user_id).await?;
+    two_factor::delete_user_challenges(conn, user_id).await?;
+    Ok(())
+}
+
+fn invalid_code_error() -> Error {
+    Error::Validation(ValidationErrors::Single {
+        field: "code".to_string(),
+        message: "Invalid two-factor code".to_string(),
+    })
+}
+
+/// Checks a TOTP code or a recovery code, consuming it on success
+///
+/// Exactly one of the two must be provided.
+async fn verify_proof(conn: &mut DbConn, user_id: Uuid, proof: &TwoFactorCodeRequest) -> Result<bool> {
+    match (proof.code.as_deref(), proof.recovery_code.as_deref()) {
+        (Some(code), None) => {
+            let Some(totp) = two_factor::get_user_totp(conn, user_id).await? else {
+                return Ok(false);
+            };
+            if totp.enabled_at.is_none() {
+                return Ok(false);
+            }
+            match verify_totp(&totp.secret, code, Utc::now().timestamp()) {
+                Some(step) => two_factor::record_totp_step(conn, user_id, step).await,
+                None => Ok(false),
+            }
+        }
+        (None, Some(recovery_code)) => {
+            let code_hash = two_factor::hash_two_factor_token(&normalize_recovery_code(recovery_code));
+            let consumed = two_factor::consume_recovery_code(conn, user_id, &code_hash).await?;
+            if consumed {
+                tracing::info!(
+                    user_id = %user_id,
+                    security_event = "two_factor_recovery_code_used",
+                    "Two-factor recovery code used"
+                );
+            }
+            Ok(consumed)
+        }
+        _ => Err(Error::Validation(ValidationErrors::Single {
+            field: "code".to_string(),
+            message: "Provide either a code or a recovery_code".to_string(),
+        })),
+    }
+}
+
+/// Generates a random 160-bit TOTP secret (base32, as expected by authenticator apps)
+fn generate_totp_secret() -> String {
+    let mut random_bytes = [0u8; 20];
+    rand::rng().fill(&mut random_bytes);
+    BASE32_NOPAD.encode(&random_bytes)
+}
+
+/// Generates a random challenge token (32 bytes, hex-encoded)
+fn generate_challenge_token() -> String {
+    let mut random_bytes = [0u8; 32];
+    rand::rng().fill(&mut random_bytes);
+    hex::encode(random_bytes)
+}
+
+/// Generates recovery codes formatted as "XXXX-XXXX-XXXX-XXXX" (80 bits each)
+fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut random_bytes = [0u8; 10];
+            rand::rng().fill(&mut random_bytes);
+            let encoded = BASE32_NOPAD.encode(&random_bytes);
+            encoded
+                .as_bytes()
+                .chunks(4)
+                .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .collect()
+}
+
+fn hash_recovery_codes(codes: &[String]) -> Vec<String> {
+    codes
+        .iter()
+        .map(|code| two_factor::hash_two_factor_token(&normalize_recovery_code(code)))
+        .collect()
+}
+
+/// Ignores case, dashes and whitespace so codes can be typed loosely
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Builds the `otpauth://` URI understood by authenticator apps
+pub fn otpauth_url(issuer: &str, account: &str, secret: &str) -> String {
+    let mut url = url::Url::parse("otpauth://totp/").expect("static URL is valid");
+    url.set_path(&format!("{}:{}", issuer, account));
+    url.query_pairs_mut()
+        .append_pair("secret", secret)
+        .append_pair("issuer", issuer)
+        .append_pair("algorithm", "SHA1")
+        .append_pair("digits", &TOTP_DIGITS.to_string())
+        .append_pair("period", &TOTP_STEP_SECONDS.to_string());
+    url.to_string()
+}
+
+/// Computes the HOTP value of a counter (RFC 4226)
+pub fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    // Dynamic truncation
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    binary % 10u32.pow(TOTP_DIGITS)
+}
+
+fn format_code(value: u32) -> String {
+    format!("{:0width$}", value, width = TOTP_DIGITS as usize)
+}
+
+/// Computes the TOTP code of a base32 secret at a Unix timestamp
+///
+/// Returns `None` if the secret is not valid base32.
+pub fn generate_totp(secret: &str, timestamp: i64) -> Option<String> {
+    let secret = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let step = timestamp.div_euclid(TOTP_STEP_SECONDS);
+    Some(format_code(hotp(&secret, step as u64)))
+}
+
+/// Verifies a TOTP code at a Unix timestamp and returns the matching time step
+///
+/// Codes from the adjacent steps are accepted to tolerate clock drift.
+pub fn verify_totp(secret: &str, code: &str, timestamp: i64) -> Option<i64> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != TOTP_DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let secret = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+
+    let current_step = timestamp.div_euclid(TOTP_STEP_SECONDS);
+    (current_step - TOTP_ALLOWED_DRIFT..=current_step + TOTP_ALLOWED_DRIFT)
+        .filter(|step| *step >= 0)
+        .find(|step| {
+            let expected = format_code(hotp(&secret, *step as u64));
+            bool::from(expected.as_bytes().ct_eq(code.as_bytes()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 appendix B secret for SHA-1 ("12345678901234567890")
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_hotp_rfc4226_vectors() {
+        let expected = [755224, 287082, 359152, 969429, 338314, 254676, 287922, 162583, 399871, 520489];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(hotp(RFC_SECRET, counter as u64), *code);
+        }
+    }
+
+    #[test]
+    fn test_verify_totp_rfc6238_vectors() {
+        let secret = BASE32_NOPAD.encode(RFC_SECRET);
+        // The RFC lists 8-digit codes; the last 6 digits are the 6-digit codes
+        for (timestamp, code) in [(59, "287082"), (1111111109, "081804"), (1234567890, "005924"), (2000000000, "279037")] {
+            assert_eq!(verify_totp(&secret, code, timestamp), Some(timestamp / 30));
+        }
+    }
+
+    #[test]
+    fn test_generate_totp_round_trips() {
+        let secret = generate_totp_secret();
+        let code = generate_totp(&secret, 1_700_000_000).unwrap();
+        assert_eq!(verify_totp(&secret, &code, 1_700_000_000), Some(1_700_000_000 / 30));
+        assert_eq!(generate_totp("not base32!", 0), None);
+    }
+
+    #[test]
+    fn test_verify_totp_allows_one_step_of_drift() {
+        let secret = BASE32_NOPAD.encode(RFC_SECRET);
+        assert_eq!(verify_totp(&secret, "081804", 1111111109 + 30), Some(1111111109 / 30));
+        assert_eq!(verify_totp(&secret, "081804", 1111111109 + 60), None);
+    }
+
+    #[test]
+    fn test_verify_totp_rejects_malformed_codes() {
+        let secret = BASE32_NOPAD.encode(RFC_SECRET);
+        assert_eq!(verify_totp(&secret, "", 59), None);
+        assert_eq!(verify_totp(&secret, "28708", 59), None);
+        assert_eq!(verify_totp(&secret, "2870820", 59), None);
+        assert_eq!(verify_totp(&secret, "abcdef", 59), None);
+        assert_eq!(verify_totp(&secret, "287 082", 59), Some(1));
+    }
+
+    #[test]
+    fn test_recovery_codes_are_unique_and_normalize() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert_eq!(codes[0].len(), 19);
+        let unique: std::collections::HashSet<_> = codes.iter().collect();
+        assert_eq!(unique.len(), codes.len());
+
+        let lowercase = codes[0].to_lowercase().replace('-', " ");
+        assert_eq!(normalize_recovery_code(&lowercase), normalize_recovery_code(&codes[0]));
+    }
+
+    #[test]
+    fn test_otpauth_url() {
+        let url = otpauth_url("BuildScale", "user@example.com", "JBSWY3DPEHPK3PXP");
+        assert!(url.starts_with("otpauth://totp/BuildScale:user@example.com?secret=JBSWY3DPEHPK3PXP&issuer=BuildScale"));
+    }
+}
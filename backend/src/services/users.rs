@@ -1,12 +1,15 @@
-use crate::{Config, DbConn};
+use crate::{Cache, CacheConfig, Config, DbConn};
 use crate::{
     error::{Error, Result, ValidationErrors},
     models::{
-        users::{LoginUser, LoginResult, NewUser, NewUserSession, RefreshTokenResult, RegisterUser, User},
+        users::{
+            IssuedUserToken, LoginOutcome, LoginUser, LoginResult, NewUser, NewUserSession, NewUserToken,
+            RefreshTokenResult, RegisterUser, ResetPasswordRequest, User, UserTokenPurpose,
+        },
         requests::{UserWorkspaceRegistrationRequest, UserWorkspaceResult, CreateWorkspaceRequest}
     },
-    queries::{users, sessions},
-    services::{jwt, workspaces},
+    queries::{users, sessions, two_factor, user_tokens},
+    services::{jwt, mailer::{Email, Mailer}, two_factor as two_factor_service, workspaces},
     validation::{validate_email, validate_password, validate_full_name, validate_session_token, validate_required_string},
 };
 use argon2::{
@@ -14,34 +17,37 @@ use argon2::{
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
 };
 use chrono::{Duration, Utc};
+use rand::Rng;
 use secrecy::ExposeSecret;
 use sqlx::Acquire;
+use std::sync::LazyLock;
 use subtle::ConstantTimeEq;
 use uuid::Uuid;
 
+/// Window the password reset mails of an address are counted in
+const PASSWORD_RESET_WINDOW_SECONDS: u64 = 3600;
+
+static PASSWORD_RESET_REQUESTS: LazyLock<Cache<u32>> = LazyLock::new(|| {
+    Cache::new_local(CacheConfig {
+        cleanup_interval_seconds: 600,
+        default_ttl_seconds: Some(PASSWORD_RESET_WINDOW_SECONDS),
+    })
+});
+
+/// Password reset requests per address in the current window, keyed by lowercase address
+///
+/// The server runs `run_cache_cleanup` on it to drop expired counts.
+pub fn password_reset_requests() -> &'static Cache<u32> {
+    &PASSWORD_RESET_REQUESTS
+}
+
 /// Registers a new user with comprehensive validation and password hashing
 pub async fn register_user(conn: &mut DbConn, register_user: RegisterUser) -> Result<User> {
     // Validate email format
     validate_email(&register_user.email)?;
 
     // Use constant-time comparison for password confirmation to prevent timing attacks
-    if register_user.password.len() != register_user.confirm_password.len() {
-        return Err(Error::Validation(ValidationErrors::Single {
-            field: "confirm_password".to_string(),
-            message: "Passwords do not match".to_string(),
-        }));
-    }
-
-    let password_bytes = register_user.password.as_bytes();
-    let confirm_bytes = register_user.confirm_password.as_bytes();
-
-    // subtle's ct_eq returns Choice(1) if equal, Choice(0) if not equal
-    if password_bytes.ct_eq(confirm_bytes).unwrap_u8() == 0 {
-        return Err(Error::Validation(ValidationErrors::Single {
-            field: "confirm_password".to_string(),
-            message: "Passwords do not match".to_string(),
-        }));
-    }
+    ensure_passwords_match(&register_user.password, &register_user.confirm_password)?;
 
     // Validate password strength
     validate_password(&register_user.password)?;
@@ -78,23 +84,7 @@ pub async fn register_user_with_workspace(conn: &mut DbConn, request: UserWorksp
     validate_email(&request.email)?;
 
     // Use constant-time comparison for password confirmation to prevent timing attacks
-    if request.password.len() != request.confirm_password.len() {
-        return Err(Error::Validation(ValidationErrors::Single {
-            field: "confirm_password".to_string(),
-            message: "Passwords do not match".to_string(),
-        }));
-    }
-
-    let password_bytes = request.password.as_bytes();
-    let confirm_bytes = request.confirm_password.as_bytes();
-
-    // subtle's ct_eq returns Choice(1) if equal, Choice(0) if not equal
-    if password_bytes.ct_eq(confirm_bytes).unwrap_u8() == 0 {
-        return Err(Error::Validation(ValidationErrors::Single {
-            field: "confirm_password".to_string(),
-            message: "Passwords do not match".to_string(),
-        }));
-    }
+    ensure_passwords_match(&request.password, &request.confirm_password)?;
 
     validate_password(&request.password)?;
     validate_full_name(&request.full_name)?;
@@ -134,6 +124,25 @@ pub async fn register_user_with_workspace(conn: &mut DbConn, request: UserWorksp
     })
 }
 
+/// Checks that a password and its confirmation match using a constant-time comparison
+fn ensure_passwords_match(password: &str, confirm_password: &str) -> Result<()> {
+    let mismatch = || Error::Validation(ValidationErrors::Single {
+        field: "confirm_password".to_string(),
+        message: "Passwords do not match".to_string(),
+    });
+
+    if password.len() != confirm_password.len() {
+        return Err(mismatch());
+    }
+
+    // subtle's ct_eq returns Choice(1) if equal, Choice(0) if not equal
+    if password.as_bytes().ct_eq(confirm_password.as_bytes()).unwrap_u8() == 0 {
+        return Err(mismatch());
+    }
+
+    Ok(())
+}
+
 /// Generates a password hash using Argon2
 pub fn generate_password_hash(password: &str) -> Result<String> {
     // Hash the password using Argon2
@@ -171,7 +180,10 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
 }
 
 /// Logs in a user with comprehensive email and password validation
-pub async fn login_user(conn: &mut DbConn, login_user: LoginUser) -> Result<LoginResult> {
+///
+/// Users with two-factor authentication enabled receive a challenge instead of a
+/// session; see `two_factor::complete_login` for the second step.
+pub async fn login_user(conn: &mut DbConn, login_user: LoginUser) -> Result<LoginOutcome> {
     // Validate email format
     validate_email(&login_user.email)?;
 
@@ -200,13 +212,35 @@ pub async fn login_user(conn: &mut DbConn, login_user: LoginUser) -> Result<Logi
     // Load config
     let config = Config::load()?;
 
-    // Generate JWT access token (short-lived, 15 minutes by default)
-    let access_token = jwt::generate_jwt(
-        user.id,
-        config.jwt.secret.expose_secret(),
-        config.jwt.access_token_expiration_minutes,
-    )?;
-    let access_token_expires_at = Utc::now() + Duration::minutes(config.jwt.access_token_expiration_minutes);
+    // Optionally block login until the email address has been verified.
+    // Checked after the password so unverified status is not leaked to guessers.
+    if config.auth.require_email_verification && user.email_verified_at.is_none() {
+        return Err(Error::Forbidden(
+            "Email address has not been verified. Please check your inbox for the verification link.".to_string(),
+        ));
+    }
+
+    complete_primary_login(conn, user).await
+}
+
+/// Finishes the first login step once the user's primary credentials were accepted
+///
+/// Shared by every login method. Users with two-factor authentication enabled get a
+/// short-lived challenge, everyone else gets a session right away.
+pub async fn complete_primary_login(conn: &mut DbConn, user: User) -> Result<LoginOutcome> {
+    if two_factor::is_two_factor_enabled(conn, user.id).await? {
+        let challenge = two_factor_service::issue_challenge(conn, user.id).await?;
+        return Ok(LoginOutcome::TwoFactorRequired(challenge));
+    }
+
+    Ok(LoginOutcome::Authenticated(issue_login_session(conn, user).await?))
+}
+
+/// Issues an access token and a new refresh-token session for an authenticated user
+///
+/// Called once every required login factor has been verified.
+pub async fn issue_login_session(conn: &mut DbConn, user: User) -> Result<LoginResult> {
+    let config = Config::load()?;
 
     // Generate refresh token (session token, long-lived, 30 days by default)
     let refresh_token = generate_session_token()?;
@@ -224,12 +258,22 @@ pub async fn login_user(conn: &mut DbConn, login_user: LoginUser) -> Result<Logi
 
     let session = sessions::create_session(conn, new_session).await?;
 
+    // Generate JWT access token bound to the session (short-lived, 15 minutes by default)
+    let access_token = jwt::generate_session_jwt(
+        user.id,
+        session.id,
+        config.jwt.secret.expose_secret(),
+        config.jwt.access_token_expiration_minutes,
+    )?;
+    let access_token_expires_at = Utc::now() + Duration::minutes(config.jwt.access_token_expiration_minutes);
+
     Ok(LoginResult {
         user,
         access_token,
         refresh_token,  // Return unhashed token to client
         access_token_expires_at,
         refresh_token_expires_at: session.expires_at,
+        session_id: session.id,
     })
 }
 
@@ -379,13 +423,28 @@ pub async fn refresh_access_token(
             time_since_revocation.num_minutes()
         );
 
+        // A session that was logged out or revoked in the meantime gets no new tokens
+        if let Some(session_id) = revoked.session_id
+            && sessions::get_valid_session_by_id(conn, session_id).await?.is_none()
+        {
+            return Err(Error::InvalidToken("Invalid or expired refresh token".to_string()));
+        }
+
         // Load config and generate new access token
         let config = Config::load()?;
-        let access_token = jwt::generate_jwt(
-            revoked.user_id,
-            config.jwt.secret.expose_secret(),
-            config.jwt.access_token_expiration_minutes,
-        )?;
+        let access_token = match revoked.session_id {
+            Some(session_id) => jwt::generate_session_jwt(
+                revoked.user_id,
+                session_id,
+                config.jwt.secret.expose_secret(),
+                config.jwt.access_token_expiration_minutes,
+            )?,
+            None => jwt::generate_jwt(
+                revoked.user_id,
+                config.jwt.secret.expose_secret(),
+                config.jwt.access_token_expiration_minutes,
+            )?,
+        };
 
         let expires_at = Utc::now() + Duration::minutes(config.jwt.access_token_expiration_minutes);
 
@@ -394,6 +453,7 @@ pub async fn refresh_access_token(
             access_token,
             refresh_token: None,  // None - client should keep using their current token
             expires_at,
+            session_id: revoked.session_id,
         });
     }
 
@@ -417,7 +477,7 @@ pub async fn refresh_access_token(
 
     // STEP 5: Record old token as revoked BEFORE updating session
     // CRITICAL: If two requests race, the second one will detect theft
-    let _ = sessions::create_revoked_token(&mut tx, session.user_id, &old_token_hash).await?;
+    let _ = sessions::create_revoked_token(&mut tx, session.user_id, session.id, &old_token_hash).await?;
 
     // STEP 6: Update session with new token hash (invalidates old token)
     let _updated_session = sessions::update_session_token_hash(
@@ -438,9 +498,10 @@ pub async fn refresh_access_token(
         "Refresh token rotated successfully"
     );
 
-    // STEP 8: Generate new access token (JWT)
-    let access_token = jwt::generate_jwt(
+    // STEP 8: Generate new access token (JWT) for the same session
+    let access_token = jwt::generate_session_jwt(
         session.user_id,
+        session.id,
         config.jwt.secret.expose_secret(),
         config.jwt.access_token_expiration_minutes,
     )?;
@@ -451,6 +512,7 @@ pub async fn refresh_access_token(
         access_token,
         refresh_token: Some(new_refresh_token),  // Some during normal rotation
         expires_at,
+        session_id: Some(session.id),
     })
 }
 
@@ -509,3 +571,219 @@ pub async fn get_user_active_sessions(conn: &mut DbConn, user_id: Uuid) -> Resul
 pub async fn revoke_all_user_sessions(conn: &mut DbConn, user_id: Uuid) -> Result<u64> {
     crate::services::sessions::revoke_all_user_sessions(conn, user_id).await
 }
+
+// ============================================================================
+// PASSWORD RESET & EMAIL VERIFICATION
+// ============================================================================
+
+/// Generates a random single-use token (32 bytes, hex-encoded)
+fn generate_user_token() -> String {
+    let mut random_bytes = [0u8; 32];
+    rand::rng().fill(&mut random_bytes);
+    hex::encode(random_bytes)
+}
+
+/// Validates the format of a password reset or email verification token
+fn validate_user_token(token: &str) -> Result<&str> {
+    let token = token.trim();
+    if token.len() != 64 || !token.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::InvalidToken("Invalid or expired token".to_string()));
+    }
+    Ok(token)
+}
+
+/// Issues a new token for a user, invalidating any earlier unused token of the same purpose
+async fn issue_user_token(
+    conn: &mut DbConn,
+    user: User,
+    purpose: UserTokenPurpose,
+    expires_in: Duration,
+) -> Result<IssuedUserToken> {
+    let token = generate_user_token();
+    let expires_at = Utc::now() + expires_in;
+
+    let mut tx = conn.begin().await.map_err(|e| {
+        Error::Internal(format!("Failed to begin transaction: {}", e))
+    })?;
+
+    user_tokens::delete_unused_user_tokens(&mut tx, user.id, purpose).await?;
+    user_tokens::create_user_token(&mut tx, NewUserToken {
+        user_id: user.id,
+        purpose,
+        token_hash: user_tokens::hash_user_token(&token),
+        expires_at,
+    }).await?;
+
+    tx.commit().await.map_err(|e| {
+        Error::Internal(format!("Failed to commit transaction: {}", e))
+    })?;
+
+    Ok(IssuedUserToken { user, token, expires_at })
+}
+
+/// Starts the forgot-password flow for an email address
+///
+/// Returns `None` when no account uses the email, so callers can respond identically
+/// in both cases and avoid leaking which addresses are registered. Also returns `None`
+/// once the address had `auth.password_reset_mails_per_hour` requests within the hour,
+/// so the endpoint cannot be used to flood a mailbox.
+pub async fn request_password_reset(conn: &mut DbConn, email: &str) -> Result<Option<IssuedUserToken>> {
+    validate_email(email)?;
+
+    let sanitized_email = validate_required_string(email, "Email")?.to_lowercase();
+    let config = Config::load()?;
+
+    // Counted before the lookup, whether or not the address has an account
+    let requests = password_reset_requests();
+    let count = requests.get(&sanitized_email).await?.unwrap_or(0);
+    if count >= config.auth.password_reset_mails_per_hour {
+        tracing::warn!(
+            security_event = "password_reset_rate_limited",
+            "Password reset requests for one address exceeded the hourly limit"
+        );
+        return Ok(None);
+    }
+    let window = match requests.ttl(&sanitized_email).await? {
+        Some(remaining) if remaining > 0 => remaining as u64,
+        _ => PASSWORD_RESET_WINDOW_SECONDS,
+    };
+    requests.set_ex(&sanitized_email, count + 1, window).await?;
+
+    let Some(user) = users::get_user_by_email(conn, &sanitized_email).await? else {
+        return Ok(None);
+    };
+
+    // Service accounts authenticate with API keys only and must never gain a password
+    if crate::services::api_keys::is_service_account_user(conn, user.id).await? {
+        return Ok(None);
+    }
+
+    let expires_in = Duration::minutes(config.auth.password_reset_token_expiration_minutes);
+    let issued = issue_user_token(conn, user, UserTokenPurpose::PasswordReset, expires_in).await?;
+
+    Ok(Some(issued))
+}
+
+/// Completes the forgot-password flow
+///
+/// Consumes the reset token, sets the new password and revokes every session of the
+/// user so a stolen refresh token cannot outlive the reset. Because the token was
+/// delivered to the user's mailbox, the email address is also marked as verified.
+pub async fn reset_password(conn: &mut DbConn, request: ResetPasswordRequest) -> Result<User> {
+    let token = validate_user_token(&request.token)?;
+    ensure_passwords_match(&request.password, &request.confirm_password)?;
+    validate_password(&request.password)?;
+
+    let password_hash = generate_password_hash(&request.password)?;
+    let token_hash = user_tokens::hash_user_token(token);
+
+    let mut tx = conn.begin().await.map_err(|e| {
+        Error::Internal(format!("Failed to begin transaction: {}", e))
+    })?;
+
+    let consumed = user_tokens::consume_user_token(&mut tx, &token_hash, UserTokenPurpose::PasswordReset)
+        .await?
+        .ok_or_else(|| Error::InvalidToken("Invalid or expired token".to_string()))?;
+
+    users::update_user_password(&mut tx, consumed.user_id, &password_hash).await?;
+    let user = users::mark_email_verified(&mut tx, consumed.user_id).await?;
+    revoke_all_user_sessions(&mut tx, consumed.user_id).await?;
+    sessions::delete_revoked_tokens_by_user(&mut tx, consumed.user_id).await?;
+
+    tx.commit().await.map_err(|e| {
+        Error::Internal(format!("Failed to commit transaction: {}", e))
+    })?;
+
+    tracing::info!(
+        user_id = %user.id,
+        security_event = "password_reset",
+        "Password reset completed, all sessions revoked"
+    );
+
+    Ok(user)
+}
+
+/// Issues an email verification token for a user
+///
+/// Returns a `Conflict` error if the email address is already verified.
+pub async fn request_email_verification(conn: &mut DbConn, user_id: Uuid) -> Result<IssuedUserToken> {
+    let user = users::get_user_by_id(conn, user_id).await?
+        .ok_or_else(|| Error::NotFound(format!("User with ID {} not found", user_id)))?;
+
+    if user.email_verified_at.is_some() {
+        return Err(Error::Conflict("Email address is already verified".to_string()));
+    }
+
+    let config = Config::load()?;
+    let expires_in = Duration::hours(config.auth.email_verification_token_expiration_hours);
+    issue_user_token(conn, user, UserTokenPurpose::EmailVerification, expires_in).await
+}
+
+/// Consumes an email verification token and marks the user's email as verified
+pub async fn verify_email(conn: &mut DbConn, token: &str) -> Result<User> {
+    let token = validate_user_token(token)?;
+    let token_hash = user_tokens::hash_user_token(token);
+
+    let mut tx = conn.begin().await.map_err(|e| {
+        Error::Internal(format!("Failed to begin transaction: {}", e))
+    })?;
+
+    let consumed = user_tokens::consume_user_token(&mut tx, &token_hash, UserTokenPurpose::EmailVerification)
+        .await?
+        .ok_or_else(|| Error::InvalidToken("Invalid or expired token".to_string()))?;
+
+    let user = users::mark_email_verified(&mut tx, consumed.user_id).await?;
+
+    tx.commit().await.map_err(|e| {
+        Error::Internal(format!("Failed to commit transaction: {}", e))
+    })?;
+
+    Ok(user)
+}
+
+/// Sends a freshly issued token to its owner by email
+///
+/// The link holds the raw token and is never logged. A failed send is logged and
+/// not returned: the token was issued either way and the user can ask for a new one.
+pub async fn deliver_user_token(
+    mailer: &dyn Mailer,
+    config: &Config,
+    purpose: UserTokenPurpose,
+    issued: &IssuedUserToken,
+) {
+    let (path, subject, action) = match purpose {
+        UserTokenPurpose::PasswordReset => ("reset-password", "Reset your password", "reset your password"),
+        UserTokenPurpose::EmailVerification => ("verify-email", "Verify your email address", "verify your email address"),
+    };
+    let link = format!(
+        "{}/{}?token={}",
+        config.auth.public_base_url.trim_end_matches('/'),
+        path,
+        issued.token
+    );
+    let email = Email {
+        to: issued.user.email.clone(),
+        subject: subject.to_string(),
+        body: format!(
+            "Open this link to {}:\n\n{}\n\nThe link expires at {}. If you did not ask for it, ignore this mail.\n",
+            action,
+            link,
+            issued.expires_at.format("%Y-%m-%d %H:%M UTC")
+        ),
+    };
+
+    match mailer.send(&email).await {
+        Ok(()) => tracing::info!(
+            user_id = %issued.user.id,
+            purpose = %purpose,
+            expires_at = %issued.expires_at,
+            "User token sent"
+        ),
+        Err(e) => tracing::error!(
+            user_id = %issued.user.id,
+            purpose = %purpose,
+            error = %e,
+            "Failed to send user token"
+        ),
+    }
+}
@@ -0,0 +1,373 @@
+//! Web pages attached to chat messages by URL.
+//!
+//! Pages are fetched and converted to markdown with the `web_fetch` pipeline
+//! (Readability, then htmd) and cached in memory. A cached page is used as it is
+//! for [`PAGE_FRESH_SECONDS`]; after that it is revalidated with its `ETag` or
+//! `Last-Modified` date, so an unchanged page costs a `304 Not Modified`. If the
+//! site cannot be reached, the cached page is used until it is dropped after
+//! [`PAGE_RETENTION_SECONDS`].
+//!
+//! A page can also be saved as a snapshot document in the workspace's
+//! `/snapshots` folder when the message is posted. The chat then reads the
+//! snapshot instead of the live page.
+//!
+//! Only public addresses are fetched, see [`crate::utils::net`]. Cached pages
+//! belong to the workspace that fetched them.
+
+use crate::cache::{Cache, CacheConfig};
+use crate::error::{Error, Result, ValidationErrors};
+use crate::models::chat::ChatAttachment;
+use crate::models::files::FileType;
+use crate::models::requests::{CreateFileRequest, CreateVersionRequest, UrlAttachmentRequest};
+use crate::queries::files as file_queries;
+use crate::services::files;
+use crate::services::storage::FileStorageService;
+use crate::tools::web_fetch;
+use crate::utils::net;
+use crate::DbConn;
+use chrono::{DateTime, Utc};
+use reqwest::header::{ACCEPT, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How long a fetched page is used without asking the site again, in seconds
+pub const PAGE_FRESH_SECONDS: u64 = 15 * 60;
+
+/// How long a fetched page is kept for revalidation, in seconds
+pub const PAGE_RETENTION_SECONDS: u64 = 24 * 60 * 60;
+
+/// Most web pages attached to a single chat message
+pub const MAX_URLS_PER_MESSAGE: usize = 5;
+
+/// Largest page body downloaded, in bytes
+const MAX_PAGE_BYTES: usize = 2 * 1024 * 1024;
+
+/// Longest page content put into the context, in characters
+///
+/// Pages still over the context's token budget are dropped by the attachment
+/// manager like any other attachment.
+const MAX_PAGE_CHARS: usize = 100_000;
+
+/// Timeout for fetching a page
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Folder snapshots are saved to
+const SNAPSHOT_FOLDER: &str = "/snapshots";
+
+/// Longest snapshot file name, before the `.md` extension
+const MAX_SNAPSHOT_NAME_LENGTH: usize = 80;
+
+static PAGE_CACHE: LazyLock<Cache<WebPage>> = LazyLock::new(|| {
+    Cache::new_local(CacheConfig {
+        cleanup_interval_seconds: 600,
+        default_ttl_seconds: Some(PAGE_RETENTION_SECONDS),
+    })
+});
+
+/// Cache of fetched pages, keyed by workspace and URL
+///
+/// The server runs `run_cache_cleanup` on it to drop expired pages.
+pub fn page_cache() -> &'static Cache<WebPage> {
+    &PAGE_CACHE
+}
+
+/// Web page converted to markdown
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebPage {
+    /// URL the page was requested with
+    pub url: String,
+    /// URL the page was served from, after redirects
+    pub final_url: String,
+    pub title: Option<String>,
+    pub content: String,
+    /// True if the page was cut at the download or content limit
+    pub truncated: bool,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// When the page was last fetched or revalidated
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl WebPage {
+    /// Renders the page for the chat context
+    pub fn render(&self) -> String {
+        let mut output = format!("URL: {}\n", self.url);
+        if let Some(title) = &self.title {
+            output.push_str(&format!("Title: {}\n", title));
+        }
+        output.push_str(&format!("Fetched: {}\n---\n{}", self.fetched_at.to_rfc3339(), self.content));
+        if self.truncated {
+            output.push_str("\n[Page truncated]");
+        }
+        output
+    }
+}
+
+/// Stable attachment ID of a URL, so the same page is one attachment in the context
+pub fn page_id(url: &str) -> Uuid {
+    Uuid::new_v5(&Uuid::NAMESPACE_URL, url.as_bytes())
+}
+
+/// Fetches a page for a workspace, from its cache while it is fresh
+pub async fn fetch_page(workspace_id: Uuid, url: &str) -> Result<WebPage> {
+    fetch_page_with_max_age(workspace_id, url, Duration::from_secs(PAGE_FRESH_SECONDS)).await
+}
+
+/// Fetches a page for a workspace, using a cached copy younger than `max_age`
+/// and revalidating older ones
+pub async fn fetch_page_with_max_age(workspace_id: Uuid, url: &str, max_age: Duration) -> Result<WebPage> {
+    let parsed = parse_url(url)?;
+    net::check_url(&parsed)?;
+    let url = parsed.to_string();
+    let cache_key = format!("{}:{}", workspace_id, url);
+    let cached = page_cache().get(&cache_key).await.ok().flatten();
+    if let Some(page) = &cached
+        && Utc::now().signed_duration_since(page.fetched_at).to_std().unwrap_or_default() < max_age
+    {
+        return Ok(page.clone());
+    }
+
+    let client = net::public_client_builder()
+        .timeout(FETCH_TIMEOUT)
+        .user_agent("BuildScale-AI/1.0")
+        .build()
+        .map_err(|e| Error::Internal(format!("Failed to create HTTP client: {}", e)))?;
+    let mut request = client
+        .get(&url)
+        .header(ACCEPT, "text/html,application/xhtml+xml,text/plain;q=0.9,*/*;q=0.8");
+    if let Some(page) = &cached {
+        if let Some(etag) = &page.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &page.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            // A stale page beats no page while the site is unreachable
+            if let Some(page) = cached {
+                tracing::warn!(url = %url, error = %e, "Web page could not be revalidated, using the cached copy");
+                return Ok(page);
+            }
+            return Err(Error::Internal(format!("Failed to fetch {}: {}", url, net::request_error(&e))));
+        }
+    };
+
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED
+        && let Some(mut page) = cached
+    {
+        page.fetched_at = Utc::now();
+        let _ = page_cache().set(&cache_key, page.clone()).await;
+        return Ok(page);
+    }
+    if !response.status().is_success() {
+        return Err(Error::Internal(format!("Failed to fetch {}: HTTP {}", url, response.status())));
+    }
+
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    let content_type = header(CONTENT_TYPE).unwrap_or_default().to_ascii_lowercase();
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+    let final_url = response.url().to_string();
+
+    let is_html = content_type.is_empty() || content_type.contains("html");
+    let is_text = content_type.starts_with("text/") || content_type.contains("json") || content_type.contains("xml");
+    if !is_html && !is_text {
+        return Err(Error::Internal(format!("{} is not a web page ({})", url, content_type)));
+    }
+
+    let (body, body_truncated) = web_fetch::read_body(response, MAX_PAGE_BYTES).await?;
+    let body = String::from_utf8_lossy(&body);
+    let (content, title) = if is_html {
+        web_fetch::html_to_markdown(&body, &final_url)
+    } else {
+        (body.to_string(), None)
+    };
+    let (content, content_truncated) = match content.char_indices().nth(MAX_PAGE_CHARS) {
+        Some((end, _)) => (content[..end].to_string(), true),
+        None => (content, false),
+    };
+
+    let page = WebPage {
+        url: url.clone(),
+        final_url,
+        title: title.filter(|title| !title.trim().is_empty()),
+        content,
+        truncated: body_truncated || content_truncated,
+        etag,
+        last_modified,
+        fetched_at: Utc::now(),
+    };
+    let _ = page_cache().set(&cache_key, page.clone()).await;
+    Ok(page)
+}
+
+/// Resolves the web pages attached to a chat request into `ChatAttachment::Url`
+/// entries
+///
+/// Pages are fetched when the chat needs them, except those to snapshot, which
+/// are fetched and saved to the workspace now. Fails with a validation error if
+/// a URL is invalid or not public, or a page to snapshot cannot be fetched.
+pub async fn resolve_url_attachments(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    workspace_id: Uuid,
+    user_id: Uuid,
+    requests: &[UrlAttachmentRequest],
+) -> Result<Vec<ChatAttachment>> {
+    if requests.len() > MAX_URLS_PER_MESSAGE {
+        return Err(invalid(format!("A message can hold at most {} URLs", MAX_URLS_PER_MESSAGE)));
+    }
+
+    let mut attachments = Vec::with_capacity(requests.len());
+    for request in requests {
+        let parsed = parse_url(&request.url)?;
+        net::check_url_resolves(&parsed)
+            .await
+            .map_err(|e| invalid(format!("Cannot attach {}: {}", request.url, e)))?;
+        let url = parsed.to_string();
+        if !request.snapshot {
+            attachments.push(ChatAttachment::Url {
+                url,
+                title: None,
+                snapshot_file_id: None,
+            });
+            continue;
+        }
+
+        let page = fetch_page(workspace_id, &url)
+            .await
+            .map_err(|e| invalid(format!("Could not fetch {} for a snapshot: {}", url, e)))?;
+        let snapshot_file_id = save_snapshot(conn, storage, workspace_id, user_id, &page).await?;
+        attachments.push(ChatAttachment::Url {
+            url,
+            title: page.title,
+            snapshot_file_id: Some(snapshot_file_id),
+        });
+    }
+    Ok(attachments)
+}
+
+/// Workspace path of a page's snapshot, e.g. `/snapshots/docs.rs-serde-latest.md`
+pub fn snapshot_path(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let slug = files::slugify(without_scheme.trim_end_matches('/'));
+    let name: String = slug.chars().take(MAX_SNAPSHOT_NAME_LENGTH).collect();
+    let name = name.trim_matches('-');
+    let name = if name.is_empty() { "page" } else { name };
+    format!("{}/{}.md", SNAPSHOT_FOLDER, name)
+}
+
+/// Saves a page as a workspace document, as a new version if it was saved before
+async fn save_snapshot(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    workspace_id: Uuid,
+    user_id: Uuid,
+    page: &WebPage,
+) -> Result<Uuid> {
+    let path = snapshot_path(&page.url);
+    let content = serde_json::Value::String(format!(
+        "> Snapshot of <{}>, fetched {}\n\n{}",
+        page.url,
+        page.fetched_at.to_rfc3339(),
+        page.content
+    ));
+
+    if let Some(file) = file_queries::get_file_by_path(conn, workspace_id, &path).await? {
+        files::create_version(
+            conn,
+            storage,
+            file.id,
+            CreateVersionRequest {
+                author_id: Some(user_id),
+                branch: Some("main".to_string()),
+                content,
+                app_data: None,
+            },
+        )
+        .await?;
+        return Ok(file.id);
+    }
+
+    let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+    let created = files::create_file_with_content(
+        conn,
+        storage,
+        CreateFileRequest {
+            workspace_id,
+            parent_id: None,
+            author_id: user_id,
+            name,
+            slug: None,
+            path: Some(path),
+            is_virtual: None,
+            is_remote: None,
+            permission: None,
+            file_type: FileType::Document,
+            content,
+            app_data: None,
+        },
+    )
+    .await?;
+    Ok(created.file.id)
+}
+
+fn parse_url(url: &str) -> Result<url::Url> {
+    let parsed = url::Url::parse(url.trim()).map_err(|e| invalid(format!("Invalid URL '{}': {}", url, e)))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(invalid(format!(
+            "URL scheme '{}' not allowed. Only http and https are supported.",
+            parsed.scheme()
+        )));
+    }
+    Ok(parsed)
+}
+
+fn invalid(message: String) -> Error {
+    Error::Validation(ValidationErrors::Single {
+        field: "urls".to_string(),
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_path() {
+        assert_eq!(snapshot_path("https://docs.rs/serde/latest/"), "/snapshots/docs.rs-serde-latest.md");
+        assert_eq!(
+            snapshot_path("http://example.com/a?b=C&d=%20e#top"),
+            "/snapshots/example.com-a-b-c-d-20e-top.md"
+        );
+        assert_eq!(snapshot_path("https://"), "/snapshots/page.md");
+        let long = format!("https://example.com/{}", "x".repeat(200));
+        assert_eq!(snapshot_path(&long).len(), "/snapshots/".len() + MAX_SNAPSHOT_NAME_LENGTH + ".md".len());
+    }
+
+    #[test]
+    fn test_page_id_is_stable() {
+        assert_eq!(page_id("https://example.com/"), page_id("https://example.com/"));
+        assert_ne!(page_id("https://example.com/"), page_id("https://example.com/other"));
+    }
+
+    #[test]
+    fn test_parse_url_rejects_other_schemes() {
+        assert!(parse_url("https://example.com/docs").is_ok());
+        assert!(matches!(parse_url("file:///etc/passwd"), Err(Error::Validation(_))));
+        assert!(matches!(parse_url("not a url"), Err(Error::Validation(_))));
+    }
+}
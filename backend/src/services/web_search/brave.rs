@@ -0,0 +1,118 @@
+//! Brave Search API
+//!
+//! Needs a subscription token from <https://brave.com/search/api/>.
+
+use super::{api_base_url, api_url, send_json, SearchBackend, SearchResults};
+use crate::error::Result;
+use crate::models::requests::SearchResultItem;
+use crate::tools::web_fetch;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Name of the backend in the configuration
+pub const NAME: &str = "brave";
+
+/// Public API of Brave Search
+const DEFAULT_BASE_URL: &str = "https://api.search.brave.com";
+
+/// Most results Brave returns for a request
+const MAX_COUNT: usize = 20;
+
+pub struct BraveBackend {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl BraveBackend {
+    pub fn new(base_url: Option<&str>, api_key: String, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            client: web_fetch::http_client(timeout, true)?,
+            base_url: api_base_url(base_url, DEFAULT_BASE_URL),
+            api_key,
+        })
+    }
+}
+
+#[async_trait]
+impl SearchBackend for BraveBackend {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    async fn search(&self, query: &str, count: usize) -> Result<SearchResults> {
+        let count = count.min(MAX_COUNT).to_string();
+        let url = api_url(
+            &format!("{}/res/v1/web/search", self.base_url),
+            &[("q", query), ("count", count.as_str())],
+        )?;
+        let request = self.client.get(url).header("X-Subscription-Token", &self.api_key);
+        let response: BraveResponse = send_json("Brave Search", request).await?;
+
+        Ok(SearchResults {
+            provider: NAME.to_string(),
+            results: response
+                .web
+                .map(|web| web.results)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|result| SearchResultItem {
+                    title: strip_tags(&result.title),
+                    url: result.url,
+                    snippet: strip_tags(&result.description),
+                    published_date: result.page_age,
+                })
+                .collect(),
+            answer: None,
+        })
+    }
+}
+
+/// Removes the `<strong>` tags Brave highlights query terms with
+fn strip_tags(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => stripped.push(c),
+            _ => {}
+        }
+    }
+    stripped
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveResponse {
+    #[serde(default)]
+    web: Option<BraveWebResults>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveWebResults {
+    #[serde(default)]
+    results: Vec<BraveResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveResult {
+    title: String,
+    url: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    page_age: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_tags() {
+        assert_eq!(strip_tags("The <strong>Rust</strong> book"), "The Rust book");
+        assert_eq!(strip_tags("a > b"), "a > b");
+    }
+}
@@ -0,0 +1,87 @@
+//! Exa, a neural search API
+//!
+//! Needs an API key from <https://exa.ai>. Snippets are the highlights Exa picks
+//! from each page for the query.
+
+use super::{api_base_url, send_json, SearchBackend, SearchResults};
+use crate::error::Result;
+use crate::models::requests::SearchResultItem;
+use crate::tools::web_fetch;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Name of the backend in the configuration
+pub const NAME: &str = "exa";
+
+/// Public API of Exa
+const DEFAULT_BASE_URL: &str = "https://api.exa.ai";
+
+pub struct ExaBackend {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl ExaBackend {
+    pub fn new(base_url: Option<&str>, api_key: String, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            client: web_fetch::http_client(timeout, true)?,
+            base_url: api_base_url(base_url, DEFAULT_BASE_URL),
+            api_key,
+        })
+    }
+}
+
+#[async_trait]
+impl SearchBackend for ExaBackend {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    async fn search(&self, query: &str, count: usize) -> Result<SearchResults> {
+        let request = self
+            .client
+            .post(format!("{}/search", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .json(&serde_json::json!({
+                "query": query,
+                "numResults": count,
+                "contents": { "highlights": true },
+            }));
+        let response: ExaResponse = send_json("Exa", request).await?;
+
+        Ok(SearchResults {
+            provider: NAME.to_string(),
+            results: response
+                .results
+                .into_iter()
+                .map(|result| SearchResultItem {
+                    title: result.title.unwrap_or_else(|| result.url.clone()),
+                    snippet: result.highlights.join(" … "),
+                    url: result.url,
+                    published_date: result.published_date,
+                })
+                .collect(),
+            answer: None,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ExaResponse {
+    #[serde(default)]
+    results: Vec<ExaResult>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExaResult {
+    #[serde(default)]
+    title: Option<String>,
+    url: String,
+    #[serde(default)]
+    published_date: Option<String>,
+    #[serde(default)]
+    highlights: Vec<String>,
+}
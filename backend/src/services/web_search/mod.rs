@@ -0,0 +1,253 @@
+//! Web search backends of the `web_search` tool
+//!
+//! A [`SearchBackend`] queries one search service, chosen with the
+//! [`WebSearchConfig`]. SearxNG, Brave Search, Tavily and Exa are queried through
+//! their JSON APIs. DuckDuckGo is scraped from its HTML pages, which needs no
+//! account but breaks when DuckDuckGo changes them, so it is the default and the
+//! fallback of the other backends rather than the recommended one.
+//!
+//! Results are cached in memory by backend, query and result count for the
+//! configured TTL. Results of the fallback and empty results are not cached, so
+//! the next search asks the configured backend again.
+
+pub mod brave;
+pub mod duckduckgo;
+pub mod exa;
+pub mod searxng;
+pub mod tavily;
+
+pub use brave::BraveBackend;
+pub use duckduckgo::DuckDuckGoBackend;
+pub use exa::ExaBackend;
+pub use searxng::SearxngBackend;
+pub use tavily::TavilyBackend;
+
+use crate::cache::{Cache, CacheConfig};
+use crate::config::WebSearchConfig;
+use crate::error::{Error, Result};
+use crate::models::requests::SearchResultItem;
+use crate::utils::safe_preview;
+use async_trait::async_trait;
+use secrecy::ExposeSecret;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Most results requested from a backend for a single search
+pub const MAX_SEARCH_RESULTS: usize = 50;
+
+static SEARCH_CACHE: LazyLock<Cache<SearchResults>> = LazyLock::new(|| {
+    Cache::new_local(CacheConfig {
+        cleanup_interval_seconds: 600,
+        default_ttl_seconds: None,
+    })
+});
+
+/// Cache of search results, keyed by backend, result count and query
+///
+/// The server runs `run_cache_cleanup` on it to drop expired results.
+pub fn search_cache() -> &'static Cache<SearchResults> {
+    &SEARCH_CACHE
+}
+
+/// Results of a search
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchResults {
+    /// Name of the backend that answered
+    pub provider: String,
+    pub results: Vec<SearchResultItem>,
+    /// Direct answer to the query, from backends that write one
+    pub answer: Option<String>,
+}
+
+/// A web search service
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Name of the backend, reported as the provider of its results
+    fn name(&self) -> &'static str;
+
+    /// Searches the web for `query`, returning at most `count` results
+    async fn search(&self, query: &str, count: usize) -> Result<SearchResults>;
+}
+
+/// Creates the backend selected by the configuration
+pub fn backend(config: &WebSearchConfig) -> Result<Box<dyn SearchBackend>> {
+    let timeout = Duration::from_secs(config.timeout_seconds);
+    let base_url = config.base_url.as_deref();
+    let api_key = || {
+        let key = config.api_key.expose_secret();
+        if key.is_empty() {
+            Err(Error::Internal(format!(
+                "Web search backend '{}' needs BUILDSCALE__AI__WEB_SEARCH__API_KEY",
+                config.backend
+            )))
+        } else {
+            Ok(key.to_string())
+        }
+    };
+
+    Ok(match config.backend.as_str() {
+        duckduckgo::NAME => Box::new(DuckDuckGoBackend::new(timeout)?),
+        searxng::NAME => {
+            let base_url = base_url.ok_or_else(|| {
+                Error::Internal(
+                    "Web search backend 'searxng' needs BUILDSCALE__AI__WEB_SEARCH__BASE_URL".to_string(),
+                )
+            })?;
+            Box::new(SearxngBackend::new(base_url, timeout)?)
+        }
+        brave::NAME => Box::new(BraveBackend::new(base_url, api_key()?, timeout)?),
+        tavily::NAME => Box::new(TavilyBackend::new(base_url, api_key()?, timeout)?),
+        exa::NAME => Box::new(ExaBackend::new(base_url, api_key()?, timeout)?),
+        other => {
+            return Err(Error::Internal(format!(
+                "Unknown web search backend '{}', expected duckduckgo, searxng, brave, tavily or exa",
+                other
+            )));
+        }
+    })
+}
+
+/// Searches with the configured backend, falling back to DuckDuckGo when it fails
+pub async fn search(config: &WebSearchConfig, query: &str, count: usize) -> Result<SearchResults> {
+    let backend = backend(config)?;
+    let fallback = if config.fallback && backend.name() != duckduckgo::NAME {
+        Some(DuckDuckGoBackend::new(Duration::from_secs(config.timeout_seconds))?)
+    } else {
+        None
+    };
+
+    search_with(
+        backend.as_ref(),
+        fallback.as_ref().map(|b| b as &dyn SearchBackend),
+        config.cache_ttl_seconds,
+        query,
+        count,
+    )
+    .await
+}
+
+/// Searches with `backend`, caching its results for `cache_ttl_seconds`
+///
+/// When `backend` fails, `fallback` is searched instead if there is one.
+pub async fn search_with(
+    backend: &dyn SearchBackend,
+    fallback: Option<&dyn SearchBackend>,
+    cache_ttl_seconds: u64,
+    query: &str,
+    count: usize,
+) -> Result<SearchResults> {
+    let query = query.trim();
+    let count = count.clamp(1, MAX_SEARCH_RESULTS);
+    let key = format!("{}:{}:{}", backend.name(), count, query);
+
+    if cache_ttl_seconds > 0
+        && let Some(results) = SEARCH_CACHE.get(&key).await?
+    {
+        tracing::debug!(query = %query, backend = backend.name(), "Web search cache hit");
+        return Ok(results);
+    }
+
+    let mut results = match backend.search(query, count).await {
+        Ok(results) => results,
+        Err(e) => match fallback {
+            Some(fallback) => {
+                tracing::warn!(
+                    query = %query,
+                    backend = backend.name(),
+                    fallback = fallback.name(),
+                    error = %e,
+                    "Web search failed, searching the fallback"
+                );
+                let mut results = fallback.search(query, count).await?;
+                results.results.truncate(count);
+                return Ok(results);
+            }
+            None => return Err(e),
+        },
+    };
+    results.results.truncate(count);
+
+    if cache_ttl_seconds > 0 && !results.results.is_empty() {
+        SEARCH_CACHE.set_ex(&key, results.clone(), cache_ttl_seconds).await?;
+    }
+
+    tracing::info!(
+        query = %query,
+        backend = backend.name(),
+        results_count = results.results.len(),
+        "Web search completed"
+    );
+    Ok(results)
+}
+
+/// Base URL of an API, without its trailing slash
+fn api_base_url(base_url: Option<&str>, default: &str) -> String {
+    base_url.unwrap_or(default).trim_end_matches('/').to_string()
+}
+
+/// URL of an API endpoint with its query parameters
+fn api_url(endpoint: &str, params: &[(&str, &str)]) -> Result<reqwest::Url> {
+    reqwest::Url::parse_with_params(endpoint, params)
+        .map_err(|e| Error::Internal(format!("Invalid web search URL '{}': {}", endpoint, e)))
+}
+
+/// Sends a request to a backend's API and parses its JSON response
+async fn send_json<T: DeserializeOwned>(backend: &str, request: reqwest::RequestBuilder) -> Result<T> {
+    let response = request
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .map_err(|e| Error::Internal(format!("{} request failed: {}", backend, e)))?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| Error::Internal(format!("Failed to read {} response: {}", backend, e)))?;
+
+    if !status.is_success() {
+        return Err(Error::Internal(format!(
+            "{} returned status {}: {}",
+            backend,
+            status,
+            safe_preview(&body, 200)
+        )));
+    }
+
+    serde_json::from_str(&body)
+        .map_err(|e| Error::Internal(format!("Failed to parse {} response: {}", backend, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_selection() {
+        let config = |backend: &str| WebSearchConfig {
+            backend: backend.to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(backend(&config("duckduckgo")).unwrap().name(), "duckduckgo");
+        // SearxNG is self-hosted, it has no default URL
+        assert!(backend(&config("searxng")).is_err());
+        // API backends need a key
+        assert!(backend(&config("brave")).is_err());
+        assert!(backend(&config("bing")).is_err());
+
+        let config = WebSearchConfig {
+            api_key: "key".to_string().into(),
+            ..config("tavily")
+        };
+        assert_eq!(backend(&config).unwrap().name(), "tavily");
+    }
+
+    #[test]
+    fn test_api_base_url() {
+        assert_eq!(api_base_url(None, "https://api.exa.ai"), "https://api.exa.ai");
+        assert_eq!(api_base_url(Some("http://localhost:8080/"), "https://api.exa.ai"), "http://localhost:8080");
+    }
+}
@@ -0,0 +1,82 @@
+//! SearxNG, a self-hosted metasearch engine
+//!
+//! Queried through its JSON API, which the instance must enable by listing
+//! `json` in `search.formats` of its `settings.yml`.
+
+use super::{api_url, send_json, SearchBackend, SearchResults};
+use crate::error::Result;
+use crate::models::requests::SearchResultItem;
+use crate::tools::web_fetch;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Name of the backend in the configuration
+pub const NAME: &str = "searxng";
+
+pub struct SearxngBackend {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl SearxngBackend {
+    pub fn new(base_url: &str, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            client: web_fetch::http_client(timeout, true)?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl SearchBackend for SearxngBackend {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    async fn search(&self, query: &str, count: usize) -> Result<SearchResults> {
+        let url = api_url(&format!("{}/search", self.base_url), &[("q", query), ("format", "json")])?;
+        let request = self.client.get(url);
+        let response: SearxngResponse = send_json("SearxNG", request).await?;
+
+        Ok(SearchResults {
+            provider: NAME.to_string(),
+            results: response
+                .results
+                .into_iter()
+                .take(count)
+                .map(|result| SearchResultItem {
+                    title: result.title,
+                    url: result.url,
+                    snippet: result.content.unwrap_or_default(),
+                    published_date: result.published_date,
+                })
+                .collect(),
+            // Answers are plain strings in older versions, objects in newer ones
+            answer: response.answers.into_iter().find_map(|answer| match answer {
+                Value::String(text) => Some(text),
+                Value::Object(object) => object.get("answer").and_then(|a| a.as_str()).map(String::from),
+                _ => None,
+            }),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SearxngResponse {
+    #[serde(default)]
+    results: Vec<SearxngResult>,
+    #[serde(default)]
+    answers: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearxngResult {
+    title: String,
+    url: String,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(rename = "publishedDate", default)]
+    published_date: Option<String>,
+}
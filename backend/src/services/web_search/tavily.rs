@@ -0,0 +1,90 @@
+//! Tavily, a search API built for LLM agents
+//!
+//! Needs an API key from <https://tavily.com>. Tavily also writes a short
+//! answer to the query, returned as the search's answer.
+
+use super::{api_base_url, send_json, SearchBackend, SearchResults};
+use crate::error::Result;
+use crate::models::requests::SearchResultItem;
+use crate::tools::web_fetch;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Name of the backend in the configuration
+pub const NAME: &str = "tavily";
+
+/// Public API of Tavily
+const DEFAULT_BASE_URL: &str = "https://api.tavily.com";
+
+/// Most results Tavily returns for a request
+const MAX_COUNT: usize = 20;
+
+pub struct TavilyBackend {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl TavilyBackend {
+    pub fn new(base_url: Option<&str>, api_key: String, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            client: web_fetch::http_client(timeout, true)?,
+            base_url: api_base_url(base_url, DEFAULT_BASE_URL),
+            api_key,
+        })
+    }
+}
+
+#[async_trait]
+impl SearchBackend for TavilyBackend {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    async fn search(&self, query: &str, count: usize) -> Result<SearchResults> {
+        let request = self
+            .client
+            .post(format!("{}/search", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&serde_json::json!({
+                "query": query,
+                "max_results": count.min(MAX_COUNT),
+                "include_answer": true,
+            }));
+        let response: TavilyResponse = send_json("Tavily", request).await?;
+
+        Ok(SearchResults {
+            provider: NAME.to_string(),
+            results: response
+                .results
+                .into_iter()
+                .map(|result| SearchResultItem {
+                    title: result.title,
+                    url: result.url,
+                    snippet: result.content,
+                    published_date: result.published_date,
+                })
+                .collect(),
+            answer: response.answer.filter(|answer| !answer.is_empty()),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TavilyResponse {
+    #[serde(default)]
+    answer: Option<String>,
+    #[serde(default)]
+    results: Vec<TavilyResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TavilyResult {
+    title: String,
+    url: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    published_date: Option<String>,
+}
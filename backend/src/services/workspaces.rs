@@ -6,17 +6,36 @@ use crate::{
             CreateWorkspaceRequest, CreateWorkspaceWithMembersRequest,
             CompleteWorkspaceResult
         },
-        workspaces::{NewWorkspace, Workspace},
+        tool_policy::{
+            glob_regex, ToolPolicy, ToolPolicyAction, UpdateToolPolicyRequest, TOOL_POLICY_DENIED,
+            TOOL_POLICY_NEEDS_APPROVAL,
+        },
+        workspaces::{NewWorkspace, UpdateAiFallbackModelsRequest, Workspace},
         workspace_members::NewWorkspaceMember,
+        permissions::workspace_permissions,
         roles::ADMIN_ROLE,
     },
-    queries::{workspaces, workspace_members},
-    services::roles,
+    providers::{AiProvider, ModelIdentifier},
+    queries::{tool_policies, workspaces, workspace_members},
+    services::{roles, workspace_members::require_workspace_permission},
     validation::{validate_workspace_name, validate_required_string},
 };
 use sqlx::Acquire;
+use std::str::FromStr;
 use uuid::Uuid;
 
+/// Longest fallback chain a workspace can set
+pub const MAX_AI_FALLBACK_MODELS: usize = 5;
+
+/// Most rules a workspace's tool policy can have
+pub const MAX_TOOL_POLICY_RULES: usize = 100;
+
+/// Longest tool or argument pattern of a tool policy rule
+const MAX_TOOL_POLICY_PATTERN_LENGTH: usize = 500;
+
+/// Longest reason of a tool policy rule
+const MAX_TOOL_POLICY_REASON_LENGTH: usize = 500;
+
 /// Creates a workspace with default roles and owner as admin
 ///
 /// This operation ensures atomicity - workspace creation, role creation, and
@@ -253,6 +272,150 @@ pub async fn update_workspace(
     Ok(updated)
 }
 
+/// Sets the models the workspace's chats fall back to when their model's provider
+/// keeps failing. Requires `workspace:manage_settings`.
+///
+/// Each model is a `provider:model` identifier of a known provider, listed once.
+/// Whether the provider is configured is checked when a chat falls back, so the
+/// chain can be set up before a provider's key is.
+pub async fn set_ai_fallback_models(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    requester_id: Uuid,
+    request: UpdateAiFallbackModelsRequest,
+) -> Result<Workspace> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_SETTINGS).await?;
+
+    let invalid = |message: String| {
+        Error::Validation(ValidationErrors::Single {
+            field: "fallback_models".to_string(),
+            message,
+        })
+    };
+    if request.fallback_models.len() > MAX_AI_FALLBACK_MODELS {
+        return Err(invalid(format!("At most {} fallback models can be set", MAX_AI_FALLBACK_MODELS)));
+    }
+
+    let mut fallback_models: Vec<String> = Vec::with_capacity(request.fallback_models.len());
+    for model in &request.fallback_models {
+        let (provider, name) = model
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| invalid(format!("'{}' must be in provider:model format", model)))?;
+        let provider = AiProvider::from_str(provider).map_err(invalid)?;
+        if name.is_empty() {
+            return Err(invalid(format!("'{}' has no model name", model)));
+        }
+        let model = ModelIdentifier { provider, model: name.to_string() }.to_string();
+        if fallback_models.contains(&model) {
+            return Err(invalid(format!("'{}' is listed twice", model)));
+        }
+        fallback_models.push(model);
+    }
+
+    let workspace = workspaces::set_ai_fallback_models(conn, workspace_id, &fallback_models).await?;
+
+    tracing::info!(
+        workspace_id = %workspace_id,
+        fallback_models = ?fallback_models,
+        changed_by = %requester_id,
+        "Workspace AI fallback models changed"
+    );
+
+    Ok(workspace)
+}
+
+/// Gets the tool policy of a workspace. Requires `workspace:read`.
+pub async fn get_tool_policy(conn: &mut DbConn, workspace_id: Uuid, requester_id: Uuid) -> Result<ToolPolicy> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::READ).await?;
+    tool_policies::get_tool_policy(conn, workspace_id).await
+}
+
+/// Replaces the rules deciding which tool calls of the workspace's chats run,
+/// wait for the user's approval or are refused. Requires `workspace:manage_settings`.
+pub async fn set_tool_policy(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    requester_id: Uuid,
+    request: UpdateToolPolicyRequest,
+) -> Result<ToolPolicy> {
+    require_workspace_permission(conn, workspace_id, requester_id, workspace_permissions::MANAGE_SETTINGS).await?;
+
+    let invalid = |message: String| {
+        Error::Validation(ValidationErrors::Single {
+            field: "rules".to_string(),
+            message,
+        })
+    };
+    if request.rules.len() > MAX_TOOL_POLICY_RULES {
+        return Err(invalid(format!("At most {} rules can be set", MAX_TOOL_POLICY_RULES)));
+    }
+
+    let mut rules = Vec::with_capacity(request.rules.len());
+    for (index, mut rule) in request.rules.into_iter().enumerate() {
+        rule.tool = rule.tool.trim().to_string();
+        if rule.tool.is_empty() {
+            return Err(invalid(format!("Rule {} has no tool pattern", index)));
+        }
+        let patterns = std::iter::once(&rule.tool).chain(rule.arguments.values());
+        for pattern in patterns {
+            if pattern.len() > MAX_TOOL_POLICY_PATTERN_LENGTH || glob_regex(pattern).is_none() {
+                return Err(invalid(format!("Rule {} has an invalid pattern '{}'", index, pattern)));
+            }
+        }
+        rule.reason = rule.reason.map(|reason| reason.trim().to_string()).filter(|reason| !reason.is_empty());
+        if rule.reason.as_ref().is_some_and(|reason| reason.len() > MAX_TOOL_POLICY_REASON_LENGTH) {
+            return Err(invalid(format!(
+                "Rule {} has a reason longer than {} characters",
+                index, MAX_TOOL_POLICY_REASON_LENGTH
+            )));
+        }
+        rules.push(rule);
+    }
+
+    let policy = tool_policies::set_tool_policy(conn, workspace_id, &rules, requester_id).await?;
+
+    tracing::info!(
+        workspace_id = %workspace_id,
+        rules = policy.rules.len(),
+        changed_by = %requester_id,
+        "Workspace tool policy changed"
+    );
+
+    Ok(policy)
+}
+
+/// Checks a tool call made outside of a chat against the workspace's tool policy
+///
+/// The tools API and the MCP endpoint call this before running a tool, chats gate
+/// their calls with the same `ToolPolicy::gate` on the same normalized arguments. Calls a `deny` rule matches are
+/// refused. Calls an `ask` rule matches are refused too, as there is no chat to ask
+/// the user in.
+pub async fn authorize_tool_call(
+    conn: &mut DbConn,
+    workspace_id: Uuid,
+    tool: &str,
+    arguments: &serde_json::Value,
+) -> Result<()> {
+    let policy = tool_policies::get_tool_policy(conn, workspace_id).await?;
+    let arguments = crate::tools::policy_arguments(tool, arguments);
+    let Some(rule) = policy.gate(tool, &arguments) else {
+        return Ok(());
+    };
+
+    tracing::info!(
+        workspace_id = %workspace_id,
+        tool = %tool,
+        action = ?rule.action,
+        "Tool call refused by the workspace's tool policy"
+    );
+    let refusal = match rule.action {
+        ToolPolicyAction::Ask => TOOL_POLICY_NEEDS_APPROVAL,
+        _ => TOOL_POLICY_DENIED,
+    };
+    Err(Error::Forbidden(rule.refusal(refusal)))
+}
+
 // Essential read methods (kept from original)
 /// Gets a workspace by ID
 pub async fn get_workspace(conn: &mut DbConn, id: Uuid) -> Result<Workspace> {
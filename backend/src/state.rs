@@ -1,10 +1,13 @@
 use crate::{
     cache::Cache, config::Config, database::DbPool, models::users::User,
     services::chat::registry::AgentRegistry, services::chat::rig_engine::RigService,
-    services::storage::FileStorageService,
+    services::mailer::{self, Mailer}, services::storage::FileStorageService,
 };
+use crate::services::chat::actor::{ChatActor, ChatActorArgs};
+use crate::services::chat::registry::AgentHandle;
 use std::sync::Arc;
 use tokio::sync::mpsc;
+use uuid::Uuid;
 
 /// Message sent to the archive cleanup worker
 #[derive(Debug, Clone)]
@@ -33,6 +36,8 @@ pub struct AppState {
     pub storage: Arc<FileStorageService>,
     /// Application configuration
     pub config: Config,
+    /// Outgoing email (password reset and email verification links)
+    pub mailer: Arc<dyn Mailer>,
     /// Channel to notify archive cleanup worker
     pub archive_cleanup_tx: mpsc::UnboundedSender<ArchiveCleanupMessage>,
 }
@@ -56,6 +61,8 @@ impl AppState {
         archive_cleanup_tx: mpsc::UnboundedSender<ArchiveCleanupMessage>,
     ) -> Self {
         let storage = Arc::new(FileStorageService::new(&config.storage.base_path));
+        let mailer = mailer::from_config(&config.mail)
+            .expect("Mail settings are validated when the config is loaded");
         // Note: Storage init is async, so we might want to call it from main before creating AppState,
         // or just let it create directories lazily/on-startup.
         // For this implementation, we assume main.rs might call init, or we lazily handle it.
@@ -63,12 +70,45 @@ impl AppState {
         Self {
             cache,
             user_cache,
+            agents: Arc::new(if config.ai.cluster.enabled {
+                AgentRegistry::with_cluster(pool.clone())
+            } else {
+                AgentRegistry::with_event_log(pool.clone())
+            }),
             pool,
-            agents: Arc::new(AgentRegistry::new()),
             rig_service,
             storage,
             config,
+            mailer,
             archive_cleanup_tx,
         }
     }
+
+    /// Spawns a chat actor on this instance and registers it
+    ///
+    /// The actor publishes on the chat's persistent event bus.
+    pub async fn spawn_chat_actor(
+        &self,
+        chat_id: Uuid,
+        workspace_id: Uuid,
+        user_id: Uuid,
+        default_persona: String,
+    ) -> AgentHandle {
+        let event_tx = self.agents.get_or_create_bus(chat_id).await;
+        let handle = ChatActor::spawn(ChatActorArgs {
+            chat_id,
+            workspace_id,
+            user_id,
+            pool: self.pool.clone(),
+            rig_service: self.rig_service.clone(),
+            storage: self.storage.clone(),
+            registry: self.agents.clone(),
+            default_persona,
+            default_context_token_limit: self.config.ai.default_context_token_limit,
+            event_tx,
+            inactivity_timeout: std::time::Duration::from_secs(self.config.ai.actor_inactivity_timeout_seconds),
+        });
+        self.agents.register(chat_id, handle.clone()).await;
+        handle
+    }
 }
@@ -0,0 +1,237 @@
+//! Delegate tool - runs scoped tasks in concurrent sub-agents.
+//!
+//! The tool itself needs the chat runtime (actor registry and event bus), so it
+//! only runs inside a chat, see `services::chat::delegation`. This module holds
+//! the schema and the argument validation shared by both entry points.
+
+use crate::error::{Error, Result, ValidationErrors};
+use crate::models::requests::{DelegateArgs, ToolResponse};
+use crate::services::storage::FileStorageService;
+use crate::tools::{Tool, ToolConfig};
+use crate::DbConn;
+use async_trait::async_trait;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Maximum number of tasks in a single delegate call
+pub const MAX_DELEGATE_TASKS: usize = 4;
+
+/// Token budget of a task that does not set one
+pub const DEFAULT_DELEGATE_TOKEN_BUDGET: usize = 50_000;
+
+/// Highest token budget a task may ask for
+pub const MAX_DELEGATE_TOKEN_BUDGET: usize = 200_000;
+
+/// Tools a sub-agent never gets: it cannot talk to the user, change the
+/// parent's mode or start sub-agents of its own
+pub const NON_DELEGABLE_TOOLS: &[&str] = &["delegate", "ask_user", "exit_plan_mode"];
+
+pub struct DelegateTool;
+
+/// Validates delegate arguments before any sub-agent is started
+pub fn validate_delegate_args(args: &DelegateArgs) -> Result<()> {
+    let invalid = |field: &str, message: String| {
+        Error::Validation(ValidationErrors::Single {
+            field: field.to_string(),
+            message,
+        })
+    };
+
+    if args.tasks.is_empty() {
+        return Err(invalid("tasks", "tasks array cannot be empty".to_string()));
+    }
+    if args.tasks.len() > MAX_DELEGATE_TASKS {
+        return Err(invalid(
+            "tasks",
+            format!("At most {} tasks can be delegated at once", MAX_DELEGATE_TASKS),
+        ));
+    }
+
+    for (index, task) in args.tasks.iter().enumerate() {
+        if task.task.trim().is_empty() {
+            return Err(invalid(&format!("tasks[{}].task", index), "task cannot be empty".to_string()));
+        }
+
+        if let Some(budget) = task.token_budget
+            && !(1..=MAX_DELEGATE_TOKEN_BUDGET).contains(&budget)
+        {
+            return Err(invalid(
+                &format!("tasks[{}].token_budget", index),
+                format!("token_budget must be between 1 and {}", MAX_DELEGATE_TOKEN_BUDGET),
+            ));
+        }
+
+        for tool in task.tools.iter().flatten() {
+            if NON_DELEGABLE_TOOLS.contains(&tool.as_str()) {
+                return Err(invalid(
+                    &format!("tasks[{}].tools", index),
+                    format!("Tool '{}' cannot be given to a sub-agent", tool),
+                ));
+            }
+            if crate::tools::get_tool_executor(tool).is_err() {
+                return Err(invalid(&format!("tasks[{}].tools", index), format!("Unknown tool: {}", tool)));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves the tools a sub-agent may call
+///
+/// Starts from the requested tools (every tool when omitted), keeps only those
+/// the parent may call itself and drops the non-delegable ones.
+pub fn resolve_delegate_tools(requested: Option<&[String]>, parent_config: &ToolConfig) -> Vec<String> {
+    let candidates: Vec<String> = match requested {
+        Some(tools) => tools.to_vec(),
+        None => crate::tools::get_all_tool_definitions()
+            .into_iter()
+            .map(|definition| definition.name)
+            .collect(),
+    };
+
+    let mut tools: Vec<String> = Vec::new();
+    for tool in candidates {
+        if !NON_DELEGABLE_TOOLS.contains(&tool.as_str())
+            && parent_config.is_tool_allowed(&tool)
+            && !tools.contains(&tool)
+        {
+            tools.push(tool);
+        }
+    }
+    tools
+}
+
+#[async_trait]
+impl Tool for DelegateTool {
+    fn name(&self) -> &'static str {
+        "delegate"
+    }
+
+    fn description(&self) -> &'static str {
+        r#"Runs up to 4 scoped tasks in parallel sub-agents and returns their final answers.
+
+Each sub-agent gets a fresh conversation with only the task text, so write
+self-contained instructions (paths, goal, expected answer format). Sub-agents
+cannot ask the user questions or delegate further. Restrict "tools" to what the
+task needs; read-only tasks should not get write tools. Each task stops when it
+exceeds its token_budget (default 50000) and returns its partial answer.
+
+Use it for independent work that can run side by side, e.g. researching several
+topics or reviewing several folders. Do not delegate trivial single-tool steps.
+
+Example: {"tasks": [{"task": "Summarize the open questions in /notes/q3.md", "tools": ["read", "grep"]}, {"task": "List all .plan files and their status", "tools": ["glob", "read"], "token_budget": 20000}]}"#
+    }
+
+    fn definition(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "tasks": {
+                    "type": "array",
+                    "minItems": 1,
+                    "maxItems": MAX_DELEGATE_TASKS,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "task": {
+                                "type": "string",
+                                "description": "Self-contained instructions for the sub-agent"
+                            },
+                            "tools": {
+                                "type": "array",
+                                "items": {"type": "string"},
+                                "description": "Tools the sub-agent may call. Defaults to your own tools"
+                            },
+                            "token_budget": {
+                                "type": ["integer", "string"],
+                                "description": "Maximum estimated tokens the sub-agent may produce (default 50000)"
+                            }
+                        },
+                        "required": ["task"],
+                        "additionalProperties": false
+                    }
+                }
+            },
+            "required": ["tasks"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(
+        &self,
+        _conn: &mut DbConn,
+        _storage: &FileStorageService,
+        _workspace_id: Uuid,
+        _user_id: Uuid,
+        _config: ToolConfig,
+        args: Value,
+    ) -> Result<ToolResponse> {
+        let delegate_args: DelegateArgs = serde_json::from_value(args)?;
+        validate_delegate_args(&delegate_args)?;
+
+        // Sub-agents run as chat actors and stream into the parent's chat
+        Err(Error::Validation(ValidationErrors::Single {
+            field: "tool".to_string(),
+            message: "delegate is only available to agents inside a chat".to_string(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::requests::DelegateTask;
+
+    fn task(text: &str, tools: Option<&[&str]>) -> DelegateTask {
+        DelegateTask {
+            task: text.to_string(),
+            tools: tools.map(|tools| tools.iter().map(|t| t.to_string()).collect()),
+            token_budget: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_delegate_args() {
+        let valid = DelegateArgs {
+            tasks: vec![task("Summarize /notes", Some(&["read", "ls"])), task("Search the web", None)],
+        };
+        assert!(validate_delegate_args(&valid).is_ok());
+
+        let invalid = [
+            DelegateArgs { tasks: vec![] },
+            DelegateArgs { tasks: vec![task("   ", None)] },
+            DelegateArgs { tasks: vec![task("Ask around", Some(&["ask_user"]))] },
+            DelegateArgs { tasks: vec![task("Recurse", Some(&["delegate"]))] },
+            DelegateArgs { tasks: vec![task("Teleport", Some(&["teleport"]))] },
+            DelegateArgs { tasks: (0..=MAX_DELEGATE_TASKS).map(|i| task(&format!("Task {}", i), None)).collect() },
+            DelegateArgs {
+                tasks: vec![DelegateTask { token_budget: Some(0), ..task("Nothing", None) }],
+            },
+        ];
+        for args in invalid {
+            assert!(
+                matches!(validate_delegate_args(&args), Err(Error::Validation(_))),
+                "Should reject: {:?}",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_delegate_tools() {
+        let parent = ToolConfig::default();
+        let tools = resolve_delegate_tools(None, &parent);
+        assert!(tools.contains(&"read".to_string()));
+        assert!(!tools.iter().any(|t| NON_DELEGABLE_TOOLS.contains(&t.as_str())));
+
+        // A sub-agent never gets more tools than its parent
+        let parent = ToolConfig {
+            allowed_tools: Some(vec!["read".to_string(), "grep".to_string(), "delegate".to_string()]),
+            ..Default::default()
+        };
+        let requested = vec!["read".to_string(), "write".to_string()];
+        assert_eq!(resolve_delegate_tools(Some(&requested), &parent), vec!["read"]);
+        assert_eq!(resolve_delegate_tools(None, &parent), vec!["read", "grep"]);
+    }
+}
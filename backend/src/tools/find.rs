@@ -83,7 +83,7 @@ EXAMPLES: {"name":"*.txt"} or {"file_type":"folder"} or {"min_size":1048576}"#
         storage: &FileStorageService,
         workspace_id: Uuid,
         _user_id: Uuid,
-        _config: ToolConfig,
+        config: ToolConfig,
         args: Value,
     ) -> Result<ToolResponse> {
         let args: FindArgs = serde_json::from_value(args)?;
@@ -150,9 +150,7 @@ EXAMPLES: {"name":"*.txt"} or {"file_type":"folder"} or {"min_size":1048576}"#
         tracing::debug!("Executing find command in directory: {:?}", workspace_path);
 
         // Execute command
-        let output = cmd.output().await.map_err(|e| {
-            Error::Internal(format!("Failed to execute find command: {}", e))
-        })?;
+        let output = super::helpers::run_command(&mut cmd, &config.call).await?;
 
         // Handle exit codes
         // 0 = matches found
@@ -61,7 +61,7 @@ PARAMETERS: pattern (required), path (default '/')"#
         storage: &FileStorageService,
         workspace_id: Uuid,
         _user_id: Uuid,
-        _config: ToolConfig,
+        config: ToolConfig,
         args: Value,
     ) -> Result<ToolResponse> {
         let glob_args: GlobArgs = serde_json::from_value(args)?;
@@ -109,9 +109,7 @@ PARAMETERS: pattern (required), path (default '/')"#
         tracing::debug!("Executing glob command: rg --files --glob {}", normalized_pattern);
 
         // Execute command
-        let output = cmd.output().await.map_err(|e| {
-            Error::Internal(format!("Failed to execute ripgrep command: {}", e))
-        })?;
+        let output = super::helpers::run_command(&mut cmd, &config.call).await?;
 
         // Handle exit codes
         // 0 = matches found
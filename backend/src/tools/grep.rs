@@ -8,6 +8,7 @@ use std::process::Command as StdCommand;
 use tokio::process::Command as TokioCommand;
 use std::path::Path;
 use std::collections::HashMap;
+use std::ops::ControlFlow;
 use super::{Tool, ToolConfig};
 
 /// State for tracking context lines across ripgrep JSON events
@@ -228,7 +229,7 @@ EXAMPLE: {"pattern":"fn main","path_pattern":"*.rs","context":3}"#
         storage: &FileStorageService,
         workspace_id: Uuid,
         _user_id: Uuid,
-        _config: ToolConfig,
+        config: ToolConfig,
         args: Value,
     ) -> Result<ToolResponse> {
         let grep_args: GrepArgs = serde_json::from_value(args)?;
@@ -279,16 +280,55 @@ EXAMPLE: {"pattern":"fn main","path_pattern":"*.rs","context":3}"#
 
         tracing::debug!("Using grep command: {}, is_ripgrep: {}", program, is_ripgrep);
 
-        // Execute command
-        let output = cmd.output().await.map_err(|e| {
-            Error::Internal(format!("Failed to execute grep command: {}", e))
-        })?;
+        // Get limit from args (default: 50, 0 means unlimited)
+        let limit = grep_args.limit.unwrap_or(50);
+        let mut matches = Vec::new();
+
+        // Get normalized path_pattern for filtering (needed for grep fallback)
+        let path_pattern_filter = grep_args.path_pattern.as_deref();
 
-        // Handle exit codes
+        // Matches are parsed as the command prints them, and streamed to the chat
+        // when the call's output is
+        let call = &config.call;
+        let push_match = |matches: &mut Vec<GrepMatch>, grep_match: GrepMatch| {
+            if call.streams_output() {
+                call.send_output(format!(
+                    "{}:{}: {}\n",
+                    grep_match.path, grep_match.line_number, grep_match.line_text
+                ));
+            }
+            matches.push(grep_match);
+        };
+
+        let mut file_path_cache = HashMap::new();
+        let mut context_tracker = ContextTracker::new();
+        let output = super::helpers::stream_command_lines(&mut cmd, call, |line| {
+            // limit: 0 means unlimited, otherwise stop at limit
+            if limit > 0 && matches.len() >= limit {
+                return ControlFlow::Break(());
+            }
+
+            if is_ripgrep {
+                // Use JSON parser for ripgrep
+                if let Some(grep_match) = parse_json_grep_output(line, &search_path, &mut file_path_cache, &mut context_tracker) {
+                    push_match(&mut matches, grep_match);
+                }
+            } else if let Some(grep_match) = parse_grep_output(line, &search_path) {
+                // Use plain text parser for grep
+                // Filter by path_pattern if provided (needed for grep fallback)
+                if path_pattern_filter.is_none_or(|pattern| path_matches_glob(&grep_match.path, pattern)) {
+                    push_match(&mut matches, grep_match);
+                }
+            }
+            ControlFlow::Continue(())
+        })
+        .await?;
+
+        // Handle exit codes, unless the search was stopped at the limit
         // 0 = matches found
         // 1 = no matches found (successful search)
         // >1 = error
-        if !output.status.success() {
+        if !output.stopped && !output.status.success() {
             let code = output.status.code().unwrap_or(2);
             if code == 1 {
                 // No matches - return success with empty list
@@ -299,65 +339,17 @@ EXAMPLE: {"pattern":"fn main","path_pattern":"*.rs","context":3}"#
                 });
             }
 
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            tracing::error!("Grep command failed (code {}): {}", code, stderr);
+            tracing::error!("Grep command failed (code {}): {}", code, output.stderr);
             return Ok(ToolResponse {
                 success: false,
                 result: Value::Null,
-                error: Some(format!("Grep command failed: {}", stderr)),
+                error: Some(format!("Grep command failed: {}", output.stderr)),
             });
         }
 
-        // Parse output
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        tracing::debug!("Grep stdout length: {} bytes", stdout.len());
-
-        // Get limit from args (default: 50, 0 means unlimited)
-        let limit = grep_args.limit.unwrap_or(50);
-        let mut matches = Vec::new();
-
-        // Get normalized path_pattern for filtering (needed for grep fallback)
-        let path_pattern_filter = grep_args.path_pattern.as_deref();
-
-        if is_ripgrep {
-            // Use JSON parser for ripgrep
-            tracing::debug!("Parsing ripgrep JSON output");
-            let mut file_path_cache = HashMap::new();
-            let mut context_tracker = ContextTracker::new();
-            for line in stdout.lines() {
-                // limit: 0 means unlimited, otherwise stop at limit
-                if limit > 0 && matches.len() >= limit {
-                    break;
-                }
-
-                if let Some(grep_match) = parse_json_grep_output(line, &search_path, &mut file_path_cache, &mut context_tracker) {
-                    matches.push(grep_match);
-                }
-            }
-            // Don't forget to finalize the last match if there is one
-            if let Some(final_match) = context_tracker.finalize_match() {
-                matches.push(final_match);
-            }
-        } else {
-            // Use plain text parser for grep
-            tracing::debug!("Parsing grep plain text output");
-            for line in stdout.lines() {
-                // limit: 0 means unlimited, otherwise stop at limit
-                if limit > 0 && matches.len() >= limit {
-                    break;
-                }
-
-                if let Some(grep_match) = parse_grep_output(line, &search_path) {
-                    // Filter by path_pattern if provided (needed for grep fallback)
-                    if let Some(pattern) = path_pattern_filter {
-                        if path_matches_glob(&grep_match.path, pattern) {
-                            matches.push(grep_match);
-                        }
-                    } else {
-                        matches.push(grep_match);
-                    }
-                }
-            }
+        // Don't forget to finalize the last match if there is one
+        if let Some(final_match) = context_tracker.finalize_match() {
+            push_match(&mut matches, final_match);
         }
 
         tracing::debug!("Parsed {} matches", matches.len());
@@ -2,14 +2,20 @@
 //!
 //! This module provides helper functions for working with files on disk,
 //! particularly for files that may not be in the database (e.g., files created
-//! via SSH, migration scripts, or external tools).
+//! via SSH, migration scripts, or external tools), and for running the search
+//! commands (`rg`, `grep`, `find`) some tools are built on.
 
-use crate::{DbConn, error::Result, models::files::FileType};
+use crate::{DbConn, error::{Error, Result}, models::files::FileType};
 use crate::models::requests::CreateFileRequest;
 use crate::services::storage::FileStorageService;
+use super::ToolCallContext;
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
+use std::ops::ControlFlow;
 use std::path::Path;
+use std::process::{ExitStatus, Output, Stdio};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command as TokioCommand;
 
 /// Check if a file exists on disk
 ///
@@ -302,3 +308,82 @@ pub async fn delete_file_from_disk(
     }
     Ok(())
 }
+
+/// Runs a command to completion and collects its output
+///
+/// The command is killed if the tool call is cancelled before it exits.
+pub async fn run_command(cmd: &mut TokioCommand, call: &ToolCallContext) -> Result<Output> {
+    let program = cmd.as_std().get_program().to_string_lossy().into_owned();
+    cmd.kill_on_drop(true);
+
+    call.run(async {
+        cmd.output()
+            .await
+            .map_err(|e| Error::Internal(format!("Failed to execute {}: {}", program, e)))
+    })
+    .await
+}
+
+/// Exit status and error output of a command run by [`stream_command_lines`]
+#[derive(Debug)]
+pub struct StreamedCommand {
+    pub status: ExitStatus,
+    pub stderr: String,
+    /// Whether `on_line` stopped reading before the command finished, which kills it
+    pub stopped: bool,
+}
+
+/// Runs a command, passing each line of its standard output to `on_line` as soon
+/// as it is printed, until the output ends or `on_line` breaks
+///
+/// The command is killed when reading stops early or the tool call is cancelled.
+/// Invalid UTF-8 in the output is replaced, as with `String::from_utf8_lossy`.
+pub async fn stream_command_lines(
+    cmd: &mut TokioCommand,
+    call: &ToolCallContext,
+    mut on_line: impl FnMut(&str) -> ControlFlow<()>,
+) -> Result<StreamedCommand> {
+    let program = cmd.as_std().get_program().to_string_lossy().into_owned();
+    let failed = |e: std::io::Error| Error::Internal(format!("Failed to execute {}: {}", program, e));
+
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(failed)?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+
+    call.run(async {
+        let read_stdout = async {
+            let mut stdout = BufReader::new(stdout);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                if stdout.read_until(b'\n', &mut line).await? == 0 {
+                    return Ok::<_, std::io::Error>(false);
+                }
+                let text = String::from_utf8_lossy(&line);
+                if on_line(text.trim_end_matches(['\n', '\r'])).is_break() {
+                    child.start_kill()?;
+                    return Ok(true);
+                }
+            }
+        };
+        // Read alongside stdout, so a command filling the stderr pipe does not block
+        let read_stderr = async {
+            let mut buf = Vec::new();
+            stderr.read_to_end(&mut buf).await?;
+            Ok::<_, std::io::Error>(String::from_utf8_lossy(&buf).into_owned())
+        };
+
+        let (stopped, stderr) = tokio::join!(read_stdout, read_stderr);
+        let stopped = stopped.map_err(failed)?;
+        let stderr = stderr.map_err(failed)?;
+        let status = child.wait().await.map_err(failed)?;
+
+        Ok(StreamedCommand { status, stderr, stopped })
+    })
+    .await
+}
@@ -59,11 +59,14 @@ Example: {"scope": "user", "category": "preferences", "key": "coding-style"}"#
         storage: &FileStorageService,
         workspace_id: Uuid,
         user_id: Uuid,
-        _config: ToolConfig,
+        config: ToolConfig,
         args: Value,
     ) -> Result<ToolResponse> {
         let memory_args: MemoryGetArgs = serde_json::from_value(args)?;
 
+        // Agents restricted to one memory scope cannot touch the other
+        config.resolve_memory_scope(Some(memory_args.scope.clone()))?;
+
         // Generate path based on scope
         let user_id_for_path = if matches!(memory_args.scope, MemoryScope::User) {
             Some(user_id)
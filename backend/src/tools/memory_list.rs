@@ -92,10 +92,13 @@ Examples:
         storage: &FileStorageService,
         workspace_id: Uuid,
         user_id: Uuid,
-        _config: ToolConfig,
+        config: ToolConfig,
         args: Value,
     ) -> Result<ToolResponse> {
-        let list_args: MemoryListArgs = serde_json::from_value(args)?;
+        let mut list_args: MemoryListArgs = serde_json::from_value(args)?;
+
+        // Agents restricted to one memory scope only see that scope
+        list_args.scope = config.resolve_memory_scope(list_args.scope)?;
 
         let workspace_path = storage.get_workspace_path(workspace_id);
 
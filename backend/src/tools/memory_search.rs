@@ -8,7 +8,8 @@ use crate::models::requests::{
     ToolResponse, MemorySearchArgs, MemorySearchResult, MemoryMatch,
 };
 use crate::services::storage::FileStorageService;
-use crate::tools::{Tool, ToolConfig};
+use crate::tools::{Tool, ToolCallContext, ToolConfig};
+use crate::tools::helpers::run_command;
 use crate::utils::{parse_memory_frontmatter, parse_memory_path, MemoryScope};
 use crate::DbConn;
 use async_trait::async_trait;
@@ -89,10 +90,13 @@ Examples:
         storage: &FileStorageService,
         workspace_id: Uuid,
         user_id: Uuid,
-        _config: ToolConfig,
+        config: ToolConfig,
         args: Value,
     ) -> Result<ToolResponse> {
-        let search_args: MemorySearchArgs = serde_json::from_value(args)?;
+        let mut search_args: MemorySearchArgs = serde_json::from_value(args)?;
+
+        // Agents restricted to one memory scope only see that scope
+        search_args.scope = config.resolve_memory_scope(search_args.scope)?;
 
         let limit = search_args.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
         let case_sensitive = search_args.case_sensitive.unwrap_or(false);
@@ -112,6 +116,7 @@ Examples:
             &grep_patterns,
             case_sensitive,
             &workspace_path,
+            &config.call,
         ).await?;
 
         // If no matches from grep, return early
@@ -291,15 +296,16 @@ async fn run_grep_for_memories(
     glob_patterns: &[String],
     case_sensitive: bool,
     workspace_path: &Path,
+    call: &ToolCallContext,
 ) -> Result<Vec<String>> {
     let mut all_files: HashSet<String> = HashSet::new();
 
     for glob_pattern in glob_patterns {
         // Try ripgrep first, then fall back to standard grep
-        let files = run_ripgrep_glob(pattern, glob_pattern, case_sensitive, workspace_path).await;
+        let files = run_ripgrep_glob(pattern, glob_pattern, case_sensitive, workspace_path, call).await;
         let files = match files {
             Ok(f) => f,
-            Err(_) => run_standard_grep_glob(pattern, glob_pattern, case_sensitive, workspace_path).await?,
+            Err(_) => run_standard_grep_glob(pattern, glob_pattern, case_sensitive, workspace_path, call).await?,
         };
 
         // Merge file paths into result
@@ -349,6 +355,7 @@ async fn run_ripgrep_glob(
     glob_pattern: &str,
     case_sensitive: bool,
     workspace_path: &Path,
+    call: &ToolCallContext,
 ) -> Result<Vec<String>> {
     // Extract directory from glob pattern for the search path
     // e.g., "users/123/memories/**/*.md" -> search in "users/123/memories"
@@ -385,9 +392,8 @@ async fn run_ripgrep_glob(
 
     tracing::debug!("Running ripgrep with pattern: {}, search_dir: {}, search_path: {:?}", pattern, search_dir, search_path);
 
-    let output = cmd.output().await.map_err(|e| {
+    let output = run_command(&mut cmd, call).await.inspect_err(|e| {
         tracing::error!("Failed to execute ripgrep: {}", e);
-        Error::Internal(format!("Failed to execute ripgrep: {}", e))
     })?;
 
     tracing::debug!("ripgrep exit code: {:?}", output.status.code());
@@ -434,6 +440,7 @@ async fn run_standard_grep_glob(
     glob_pattern: &str,
     case_sensitive: bool,
     workspace_path: &Path,
+    call: &ToolCallContext,
 ) -> Result<Vec<String>> {
     // Extract directory from glob pattern
     // e.g., "users/123/memories/**/*.md" -> "users/123/memories"
@@ -464,9 +471,7 @@ async fn run_standard_grep_glob(
     cmd.arg(pattern);
     cmd.arg(".");
 
-    let output = cmd.output().await.map_err(|e| {
-        Error::Internal(format!("Failed to execute grep: {}", e))
-    })?;
+    let output = run_command(&mut cmd, call).await?;
 
     // Exit code 1 means no matches, which is fine
     if !output.status.success() && output.status.code() != Some(1) {
@@ -88,11 +88,14 @@ Example: {"scope": "user", "category": "preferences", "key": "coding-style", "ti
         storage: &FileStorageService,
         workspace_id: Uuid,
         user_id: Uuid,
-        _config: ToolConfig,
+        config: ToolConfig,
         args: Value,
     ) -> Result<ToolResponse> {
         let memory_args: MemorySetArgs = serde_json::from_value(args)?;
 
+        // Agents restricted to one memory scope cannot touch the other
+        config.resolve_memory_scope(Some(memory_args.scope.clone()))?;
+
         // Validate required fields
         if memory_args.category.trim().is_empty() {
             return Err(Error::Validation(ValidationErrors::Single {
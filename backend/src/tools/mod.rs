@@ -24,6 +24,7 @@ pub mod plan_write;
 pub mod plan_read;
 pub mod plan_edit;
 pub mod plan_list;
+pub mod plan_update_step;
 pub mod memory_set;
 pub mod memory_get;
 pub mod memory_search;
@@ -31,13 +32,20 @@ pub mod memory_delete;
 pub mod memory_list;
 pub mod web_fetch;
 pub mod web_search;
+pub mod skill_list;
+pub mod skill_activate;
+pub mod delegate;
+pub mod run;
 
 pub mod helpers;
 
-use crate::{DbConn, error::{Error, Result}, models::requests::ToolResponse, models::chat::ToolDefinition, services::storage::FileStorageService};
+use crate::{DbConn, config::{SandboxConfig, WebSearchConfig}, error::{Error, Result}, models::requests::ToolResponse, models::chat::ToolDefinition, services::storage::FileStorageService, utils::MemoryScope};
 use uuid::Uuid;
 use serde_json::Value;
 use async_trait::async_trait;
+use std::future::Future;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
 /// Error message shown when tools are restricted in Plan Mode
 ///
@@ -58,6 +66,7 @@ pub const PLAN_MODE_ERROR: &str = "System is in Plan Mode. To switch to Build Mo
 /// let config = ToolConfig {
 ///     plan_mode: true,
 ///     active_plan_path: Some("/plans/project-roadmap.plan".to_string()),
+///     ..Default::default()
 /// };
 /// ```
 #[derive(Debug, Clone)]
@@ -75,9 +84,22 @@ pub struct ToolConfig {
     /// agent's context.
     pub active_plan_path: Option<String>,
 
+    /// Tools the chat's agent file or delegating parent allows (all tools when `None`)
+    pub allowed_tools: Option<Vec<String>>,
+
+    /// Memory scope the chat's agent file is restricted to (both scopes when `None`)
+    pub memory_scope: Option<MemoryScope>,
+
+    /// Cancellation and output of the call running the tool
+    pub call: ToolCallContext,
+
+    /// Sandbox the `run` tool executes commands in
+    pub sandbox: SandboxConfig,
+
+    /// Search service the `web_search` tool queries
+    pub web_search: WebSearchConfig,
+
     // Future extensibility:
-    // pub skills: Vec<String>,
-    // pub agent_id: Uuid,
     // pub session_id: Uuid,
 }
 
@@ -86,6 +108,112 @@ impl Default for ToolConfig {
         Self {
             plan_mode: false, // Default to Build Mode for normal operation
             active_plan_path: None,
+            allowed_tools: None,
+            memory_scope: None,
+            call: ToolCallContext::default(),
+            sandbox: SandboxConfig::default(),
+            web_search: WebSearchConfig::default(),
+        }
+    }
+}
+
+impl ToolConfig {
+    /// Returns true if the tool may be called under this configuration
+    pub fn is_tool_allowed(&self, tool_name: &str) -> bool {
+        self.allowed_tools
+            .as_ref()
+            .is_none_or(|tools| tools.iter().any(|t| t == tool_name))
+    }
+
+    /// Narrows the allowed tools to those also in `tools`
+    pub fn restrict_tools(self, tools: &[String]) -> Self {
+        let allowed_tools = tools
+            .iter()
+            .filter(|tool| self.is_tool_allowed(tool))
+            .cloned()
+            .collect();
+        Self {
+            allowed_tools: Some(allowed_tools),
+            ..self
+        }
+    }
+
+    /// Resolves the memory scope a memory tool should use
+    ///
+    /// Fails if the requested scope is outside the agent's memory scope. An
+    /// omitted scope defaults to the agent's scope.
+    pub fn resolve_memory_scope(&self, requested: Option<MemoryScope>) -> Result<Option<MemoryScope>> {
+        match (&self.memory_scope, requested) {
+            (Some(allowed), Some(requested)) if *allowed != requested => {
+                Err(Error::Validation(crate::error::ValidationErrors::Single {
+                    field: "scope".to_string(),
+                    message: format!(
+                        "This agent can only access {} memories, not {} memories",
+                        allowed, requested
+                    ),
+                }))
+            }
+            (Some(allowed), _) => Ok(Some(allowed.clone())),
+            (None, requested) => Ok(requested),
+        }
+    }
+}
+
+tokio::task_local! {
+    static CURRENT_CALL: ToolCallContext;
+}
+
+/// Context of a single tool call
+///
+/// The chat's tool loop cancels it when the chat is stopped or the call runs past
+/// its timeout. Tools waiting on child processes or the network do so through
+/// [`ToolCallContext::run`], which gives up as soon as that happens. Tools with
+/// long outputs can also stream them while running with [`ToolCallContext::send_output`].
+/// Outside of chats (REST API, MCP server) calls are never cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct ToolCallContext {
+    /// Cancelled when the chat is stopped or the call times out
+    pub cancellation: CancellationToken,
+    output: Option<mpsc::UnboundedSender<String>>,
+}
+
+impl ToolCallContext {
+    pub fn new(cancellation: CancellationToken, output: Option<mpsc::UnboundedSender<String>>) -> Self {
+        Self { cancellation, output }
+    }
+
+    /// Context of the tool call running on the current task
+    pub fn current() -> Self {
+        CURRENT_CALL.try_with(Clone::clone).unwrap_or_default()
+    }
+
+    /// Runs `future` with this context as the current call's
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT_CALL.scope(self, future).await
+    }
+
+    /// Runs `future` until it completes or the call is cancelled
+    ///
+    /// A cancelled future is dropped, so child processes spawned with
+    /// `kill_on_drop` are killed and requests in flight are aborted.
+    pub async fn run<T>(&self, future: impl Future<Output = Result<T>>) -> Result<T> {
+        tokio::select! {
+            result = future => result,
+            _ = self.cancellation.cancelled() => {
+                Err(Error::Internal("Tool call was cancelled".to_string()))
+            }
+        }
+    }
+
+    /// Whether output sent with [`ToolCallContext::send_output`] reaches anyone
+    pub fn streams_output(&self) -> bool {
+        self.output.is_some()
+    }
+
+    /// Streams part of the tool's output before it completes
+    pub fn send_output(&self, output: impl Into<String>) {
+        if let Some(tx) = &self.output {
+            let _ = tx.send(output.into());
         }
     }
 }
@@ -165,6 +293,7 @@ pub fn get_tool_executor(tool_name: &str) -> Result<ToolExecutor> {
         "plan_read" => Ok(ToolExecutor::PlanRead),
         "plan_edit" => Ok(ToolExecutor::PlanEdit),
         "plan_list" => Ok(ToolExecutor::PlanList),
+        "plan_update_step" => Ok(ToolExecutor::PlanUpdateStep),
         "memory_set" => Ok(ToolExecutor::MemorySet),
         "memory_get" => Ok(ToolExecutor::MemoryGet),
         "memory_search" => Ok(ToolExecutor::MemorySearch),
@@ -172,6 +301,10 @@ pub fn get_tool_executor(tool_name: &str) -> Result<ToolExecutor> {
         "memory_list" => Ok(ToolExecutor::MemoryList),
         "web_fetch" => Ok(ToolExecutor::WebFetch),
         "web_search" => Ok(ToolExecutor::WebSearch),
+        "skill_list" => Ok(ToolExecutor::SkillList),
+        "skill_activate" => Ok(ToolExecutor::SkillActivate),
+        "delegate" => Ok(ToolExecutor::Delegate),
+        "run" => Ok(ToolExecutor::Run),
         _ => Err(Error::NotFound(format!("Tool '{}' not found", tool_name))),
     }
 }
@@ -203,6 +336,40 @@ pub fn normalize_path(path: &str) -> String {
     format!("/{}", components.join("/"))
 }
 
+/// Arguments of the built-in tools that name workspace paths
+const PATH_ARGUMENTS: &[&str] = &["path", "paths", "source", "destination", "cwd", "plan_file_path"];
+
+/// Arguments of a tool call with its paths normalized, as the tool will use them
+///
+/// Built-in tools normalize their paths before using them, so `projects/site`
+/// and `/notes/../projects/site` both name `/projects/site`. The tool policy is
+/// matched against these arguments, so a path rule holds however the path is
+/// spelled. Other tools' arguments are returned as they are.
+pub fn policy_arguments(tool: &str, arguments: &Value) -> Value {
+    let mut arguments = arguments.clone();
+    let Some(object) = arguments.as_object_mut() else {
+        return arguments;
+    };
+    if get_tool_executor(tool).is_err() {
+        return arguments;
+    }
+
+    for name in PATH_ARGUMENTS {
+        match object.get_mut(*name) {
+            Some(Value::String(path)) => *path = normalize_path(path),
+            Some(Value::Array(paths)) => {
+                for path in paths {
+                    if let Value::String(path) = path {
+                        *path = normalize_path(path);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    arguments
+}
+
 /// Tool executor enum for dispatching tool execution
 pub enum ToolExecutor {
     Ls,
@@ -225,6 +392,7 @@ pub enum ToolExecutor {
     PlanRead,
     PlanEdit,
     PlanList,
+    PlanUpdateStep,
     MemorySet,
     MemoryGet,
     MemorySearch,
@@ -232,9 +400,40 @@ pub enum ToolExecutor {
     MemoryList,
     WebFetch,
     WebSearch,
+    SkillList,
+    SkillActivate,
+    Delegate,
+    Run,
 }
 
 impl ToolExecutor {
+    /// Returns true if the tool never modifies workspace state
+    ///
+    /// `ask_user` and `exit_plan_mode` only affect the chat flow, but they are treated
+    /// as mutating since they change what the agent is allowed to do next.
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            ToolExecutor::Ls
+                | ToolExecutor::Read
+                | ToolExecutor::Grep
+                | ToolExecutor::Glob
+                | ToolExecutor::FileInfo
+                | ToolExecutor::ReadMultipleFiles
+                | ToolExecutor::Find
+                | ToolExecutor::Cat
+                | ToolExecutor::PlanRead
+                | ToolExecutor::PlanList
+                | ToolExecutor::MemoryGet
+                | ToolExecutor::MemorySearch
+                | ToolExecutor::MemoryList
+                | ToolExecutor::WebFetch
+                | ToolExecutor::WebSearch
+                | ToolExecutor::SkillList
+                | ToolExecutor::SkillActivate
+        )
+    }
+
     pub async fn execute(
         &self,
         conn: &mut DbConn,
@@ -265,6 +464,7 @@ impl ToolExecutor {
             ToolExecutor::PlanRead => "plan_read",
             ToolExecutor::PlanEdit => "plan_edit",
             ToolExecutor::PlanList => "plan_list",
+            ToolExecutor::PlanUpdateStep => "plan_update_step",
             ToolExecutor::MemorySet => "memory_set",
             ToolExecutor::MemoryGet => "memory_get",
             ToolExecutor::MemorySearch => "memory_search",
@@ -272,6 +472,10 @@ impl ToolExecutor {
             ToolExecutor::MemoryList => "memory_list",
             ToolExecutor::WebFetch => "web_fetch",
             ToolExecutor::WebSearch => "web_search",
+            ToolExecutor::SkillList => "skill_list",
+            ToolExecutor::SkillActivate => "skill_activate",
+            ToolExecutor::Delegate => "delegate",
+            ToolExecutor::Run => "run",
         };
 
         let span = tracing::info_span!("tool_execute", tool = name, workspace_id = %workspace_id, user_id = %user_id);
@@ -300,6 +504,7 @@ impl ToolExecutor {
             ToolExecutor::PlanRead => plan_read::PlanReadTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
             ToolExecutor::PlanEdit => plan_edit::PlanEditTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
             ToolExecutor::PlanList => plan_list::PlanListTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
+            ToolExecutor::PlanUpdateStep => plan_update_step::PlanUpdateStepTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
             ToolExecutor::MemorySet => memory_set::MemorySetTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
             ToolExecutor::MemoryGet => memory_get::MemoryGetTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
             ToolExecutor::MemorySearch => memory_search::MemorySearchTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
@@ -307,6 +512,10 @@ impl ToolExecutor {
             ToolExecutor::MemoryList => memory_list::MemoryListTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
             ToolExecutor::WebFetch => web_fetch::WebFetchTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
             ToolExecutor::WebSearch => web_search::WebSearchTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
+            ToolExecutor::SkillList => skill_list::SkillListTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
+            ToolExecutor::SkillActivate => skill_activate::SkillActivateTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
+            ToolExecutor::Delegate => delegate::DelegateTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
+            ToolExecutor::Run => run::RunTool.execute(conn, storage, workspace_id, user_id, config.clone(), args).await,
         };
 
         match &result {
@@ -437,6 +646,11 @@ pub fn get_all_tool_definitions() -> Vec<ToolDefinition> {
             description: plan_list::PlanListTool.description().into(),
             parameters: plan_list::PlanListTool.definition(),
         },
+        ToolDefinition {
+            name: "plan_update_step".into(),
+            description: plan_update_step::PlanUpdateStepTool.description().into(),
+            parameters: plan_update_step::PlanUpdateStepTool.definition(),
+        },
         ToolDefinition {
             name: "memory_set".into(),
             description: memory_set::MemorySetTool.description().into(),
@@ -472,5 +686,25 @@ pub fn get_all_tool_definitions() -> Vec<ToolDefinition> {
             description: web_search::WebSearchTool.description().into(),
             parameters: web_search::WebSearchTool.definition(),
         },
+        ToolDefinition {
+            name: "skill_list".into(),
+            description: skill_list::SkillListTool.description().into(),
+            parameters: skill_list::SkillListTool.definition(),
+        },
+        ToolDefinition {
+            name: "skill_activate".into(),
+            description: skill_activate::SkillActivateTool.description().into(),
+            parameters: skill_activate::SkillActivateTool.definition(),
+        },
+        ToolDefinition {
+            name: "delegate".into(),
+            description: delegate::DelegateTool.description().into(),
+            parameters: delegate::DelegateTool.definition(),
+        },
+        ToolDefinition {
+            name: "run".into(),
+            description: run::RunTool.description().into(),
+            parameters: run::RunTool.definition(),
+        },
     ]
 }
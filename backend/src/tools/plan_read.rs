@@ -6,7 +6,7 @@ use crate::queries::files as file_queries;
 use crate::services::files;
 use crate::services::storage::FileStorageService;
 use crate::tools::{Tool, ToolConfig};
-use crate::utils::parse_frontmatter;
+use crate::utils::{parse_frontmatter, parse_plan_steps};
 use crate::DbConn;
 use async_trait::async_trait;
 use serde_json::Value;
@@ -27,7 +27,8 @@ impl Tool for PlanReadTool {
         r#"Reads a plan file with parsed frontmatter.
 
 Supports lookup by path or name (searches /plans/ directory).
-Returns metadata (title, status, created_at) and content."#
+Returns metadata (title, status, created_at), content and the step checklist
+(task items like "- [ ] step", numbered from 1)."#
     }
 
     fn definition(&self) -> Value {
@@ -157,6 +158,7 @@ Returns metadata (title, status, created_at) and content."#
             content: sliced_content,
             hash: file_with_content.latest_version.hash,
             total_lines: Some(total_lines),
+            steps: parse_plan_steps(&content_text),
         };
 
         Ok(ToolResponse {
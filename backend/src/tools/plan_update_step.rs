@@ -0,0 +1,157 @@
+//! Plan update step tool - tracks progress on a plan's step checklist.
+
+use crate::error::{Error, Result, ValidationErrors};
+use crate::models::files::FileType;
+use crate::models::requests::{CreateVersionRequest, PlanUpdateStepArgs, PlanUpdateStepResult, ToolResponse};
+use crate::queries::files as file_queries;
+use crate::services::files;
+use crate::services::storage::FileStorageService;
+use crate::tools::{Tool, ToolConfig};
+use crate::utils::{parse_plan_steps, update_plan_step, PlanProgress};
+use crate::DbConn;
+use async_trait::async_trait;
+use serde_json::Value;
+use uuid::Uuid;
+
+pub struct PlanUpdateStepTool;
+
+#[async_trait]
+impl Tool for PlanUpdateStepTool {
+    fn name(&self) -> &'static str {
+        "plan_update_step"
+    }
+
+    fn description(&self) -> &'static str {
+        r#"Marks a step of a plan as pending, in_progress, done or blocked.
+
+Steps are the plan's task items ("- [ ] step"), numbered from 1 in order (see
+plan_read "steps"). Only the checkbox changes: [ ] pending, [~] in_progress,
+[x] done, [!] blocked. Path defaults to the active plan in Build Mode.
+
+Mark a step in_progress when you start it and done when it is finished. Use
+blocked when you cannot continue, and explain why in your reply.
+
+Example: {"step": 2, "status": "done"}"#
+    }
+
+    fn definition(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": ["string", "null"],
+                    "description": "Plan file path. Defaults to the active plan"
+                },
+                "step": {
+                    "type": ["integer", "string"],
+                    "description": "1-based step number. Accepts integer or string."
+                },
+                "status": {
+                    "type": "string",
+                    "enum": ["pending", "in_progress", "done", "blocked"]
+                }
+            },
+            "required": ["step", "status"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(
+        &self,
+        conn: &mut DbConn,
+        storage: &FileStorageService,
+        workspace_id: Uuid,
+        user_id: Uuid,
+        config: ToolConfig,
+        args: Value,
+    ) -> Result<ToolResponse> {
+        let step_args: PlanUpdateStepArgs = serde_json::from_value(args)?;
+
+        // Determine path, defaulting to the active plan
+        let path = step_args
+            .path
+            .or(config.active_plan_path.clone())
+            .ok_or_else(|| {
+                Error::Validation(ValidationErrors::Single {
+                    field: "path".to_string(),
+                    message: "path is required when there is no active plan".to_string(),
+                })
+            })?;
+        let path = super::normalize_path(&path);
+
+        // Ensure it's a .plan file
+        if !path.ends_with(".plan") {
+            return Err(Error::Validation(ValidationErrors::Single {
+                field: "path".to_string(),
+                message: "plan_update_step only works on .plan files".to_string(),
+            }));
+        }
+
+        let file = file_queries::get_file_by_path(conn, workspace_id, &path).await?
+            .ok_or_else(|| Error::NotFound(format!("Plan not found: {}", path)))?;
+
+        // Plan Mode Guard
+        if config.plan_mode && !matches!(file.file_type, FileType::Plan) {
+            return Err(Error::Validation(ValidationErrors::Single {
+                field: "path".to_string(),
+                message: super::PLAN_MODE_ERROR.to_string(),
+            }));
+        }
+
+        let file_with_content = files::get_file_with_content(conn, storage, file.id).await?;
+        let content_text = match &file_with_content.content {
+            Value::String(s) => s.clone(),
+            other => other.get("text").and_then(|t| t.as_str()).map(str::to_string).unwrap_or_else(|| other.to_string()),
+        };
+
+        let steps = parse_plan_steps(&content_text);
+        let previous = steps
+            .iter()
+            .find(|s| s.index == step_args.step)
+            .ok_or_else(|| {
+                Error::Validation(ValidationErrors::Single {
+                    field: "step".to_string(),
+                    message: format!(
+                        "Step {} does not exist, the plan has {} steps (task items like \"- [ ] step\")",
+                        step_args.step,
+                        steps.len()
+                    ),
+                })
+            })?
+            .clone();
+
+        // Unchanged status keeps the current version
+        let (version_id, hash, steps) = if previous.status == step_args.status {
+            (file_with_content.latest_version.id, file_with_content.latest_version.hash, steps)
+        } else {
+            let updated_content = update_plan_step(&content_text, step_args.step, step_args.status)
+                .ok_or_else(|| Error::Internal(format!("Failed to update step {} of {}", step_args.step, path)))?;
+
+            let version = files::create_version(conn, storage, file.id, CreateVersionRequest {
+                author_id: Some(user_id),
+                branch: Some("main".to_string()),
+                content: serde_json::json!(updated_content),
+                app_data: None,
+            }).await?;
+
+            (version.id, version.hash, parse_plan_steps(&updated_content))
+        };
+
+        let step = steps[step_args.step - 1].clone();
+        let result = PlanUpdateStepResult {
+            path,
+            file_id: file.id,
+            version_id,
+            hash,
+            step,
+            previous_status: previous.status,
+            progress: PlanProgress::from_steps(&steps),
+        };
+
+        Ok(ToolResponse {
+            success: true,
+            result: serde_json::to_value(result)?,
+            error: None,
+        })
+    }
+}
@@ -1,11 +1,14 @@
 use crate::{DbConn, error::{Result, Error}};
+use crate::models::files::ImageContent;
 use crate::models::requests::{ToolResponse, ReadArgs, ReadResult};
-use crate::services::files;
+use crate::services::{files, images};
+use crate::services::storage::FileStorageService;
 use crate::queries::files as file_queries;
 use crate::tools::helpers;
 use uuid::Uuid;
 use serde_json::Value;
 use async_trait::async_trait;
+use sha2::{Digest, Sha256};
 use super::{Tool, ToolConfig};
 
 /// Default maximum number of lines to read from a file.
@@ -60,6 +63,49 @@ fn slice_content_from_end(
     (sliced_lines, total_lines, was_truncated)
 }
 
+/// Reads a file as an image with the hash of its content, `None` if it is not one
+///
+/// Only the start of the file is read to tell, text files are not read twice.
+async fn read_image(
+    storage: &FileStorageService,
+    workspace_id: Uuid,
+    path: &str,
+) -> Result<Option<(ImageContent, String)>> {
+    let Ok(head) = storage.read_file_head(workspace_id, path, images::FORMAT_SNIFF_BYTES).await else {
+        return Ok(None);
+    };
+    if images::detect_media_type(&head).is_none() {
+        return Ok(None);
+    }
+
+    let bytes = storage.read_file(workspace_id, path).await?;
+    let hash = hex::encode(Sha256::digest(&bytes));
+    let image = images::prepare_image_blocking(bytes).await?;
+    Ok(Some((image, hash)))
+}
+
+/// Result of reading an image file, which has no text content or lines
+fn image_response(path: String, image: ImageContent, hash: String, synced: bool) -> Result<ToolResponse> {
+    let result = ReadResult {
+        path,
+        content: Value::Null,
+        hash,
+        synced,
+        total_lines: None,
+        truncated: None,
+        offset: None,
+        limit: None,
+        cursor: None,
+        image: Some(image),
+    };
+
+    Ok(ToolResponse {
+        success: true,
+        result: serde_json::to_value(result)?,
+        error: None,
+    })
+}
+
 /// Read file contents tool
 ///
 /// Reads the latest version of a file within a workspace.
@@ -76,6 +122,7 @@ impl Tool for ReadTool {
 
 OFFSET: Positive=from start (100=line 100+), Negative=from end (-100=last 100 lines).
 SCROLL MODE: Set cursor for relative navigation.
+IMAGES: PNG, JPEG, GIF and WebP files are returned as images you can see, if your model accepts images.
 
 EXAMPLES: {"path":"/f"} or {"path":"/f","offset":-100,"limit":100}"#
     }
@@ -134,6 +181,10 @@ EXAMPLES: {"path":"/f"} or {"path":"/f","offset":-100,"limit":100}"#
                 match helpers::file_exists_on_disk(storage, workspace_id, &path).await {
                     Ok(true) => {
                         tracing::debug!(workspace_id = %workspace_id, path = %path, "File exists on disk, reading from filesystem");
+                        if let Some((image, hash)) = read_image(storage, workspace_id, &path).await? {
+                            return image_response(path, image, hash, false);
+                        }
+
                         // File exists on disk but not in database - read from disk
                         let (content, hash) = helpers::read_file_from_disk(
                             storage,
@@ -155,6 +206,7 @@ EXAMPLES: {"path":"/f"} or {"path":"/f","offset":-100,"limit":100}"#
                             offset: Some(0),
                             limit: Some(limit),
                             cursor: None,  // Scroll mode not supported for unsynced files
+                            image: None,
                         };
 
                         return Ok(ToolResponse {
@@ -184,6 +236,13 @@ EXAMPLES: {"path":"/f"} or {"path":"/f","offset":-100,"limit":100}"#
             }));
         }
 
+        if !file.is_remote
+            && let Some((image, _)) = read_image(storage, workspace_id, &file.path).await?
+        {
+            let hash = file_queries::get_latest_version(conn, file.id).await?.hash;
+            return image_response(path, image, hash, true);
+        }
+
         let file_with_content = files::get_file_with_content(conn, storage, file.id).await?;
 
         // Calculate offset based on mode (cursor vs absolute)
@@ -258,6 +317,7 @@ EXAMPLES: {"path":"/f"} or {"path":"/f","offset":-100,"limit":100}"#
             offset: Some(actual_offset),
             limit: Some(limit),
             cursor: Some(new_cursor),
+            image: None,
         };
 
         Ok(ToolResponse {
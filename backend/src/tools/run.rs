@@ -0,0 +1,342 @@
+//! Run tool - executes shell commands in a sandbox over the workspace's files.
+//!
+//! The sandbox itself lives in `services::sandbox`. Once the command is done,
+//! the files it created, modified or deleted in the workspace's `latest/` tree
+//! are recorded as new versions, like the changes of any other tool.
+
+use crate::error::{Error, Result, ValidationErrors};
+use crate::models::files::FileType;
+use crate::models::requests::{
+    CreateFileRequest, CreateVersionRequest, RunArgs, RunChangeKind, RunFileChange, RunResult,
+    RunUnrecordedChange, ToolResponse,
+};
+use crate::queries::files as file_queries;
+use crate::services::files;
+use crate::services::sandbox::{self, TreeEntry, TreeSnapshot};
+use crate::services::storage::FileStorageService;
+use crate::tools::{Tool, ToolConfig};
+use crate::DbConn;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Longest command accepted, in characters
+pub const MAX_COMMAND_LENGTH: usize = 10_000;
+
+/// Most file changes of a single run recorded as versions
+pub const MAX_RECORDED_CHANGES: usize = 100;
+
+/// Largest file a run's changes are recorded for, in bytes
+pub const MAX_RECORDED_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Most unrecorded changes listed in the result
+const MAX_LISTED_UNRECORDED: usize = 100;
+
+pub struct RunTool;
+
+#[async_trait]
+impl Tool for RunTool {
+    fn name(&self) -> &'static str {
+        "run"
+    }
+
+    fn description(&self) -> &'static str {
+        r#"Runs a shell command (/bin/sh -c) in a sandbox over the workspace's files and returns its exit code, stdout and stderr.
+
+The workspace is mounted at /workspace and the command starts in "cwd" (default "/", the workspace root). Use it to validate your work: run tests, linters, compilers or scripts. The host's installed tools are available read-only; there is no network access unless the server enables it, so installing packages usually fails. Only /workspace and /tmp are writable, and /tmp is discarded afterwards.
+
+Files the command creates, modifies or deletes in the workspace are recorded as new versions and listed in "changes". Binary files, files over 1 MiB and names with uppercase letters or spaces stay on disk only and are listed in "unrecorded". Symbolic links the command creates are removed.
+
+The command is killed after "timeout" seconds (default and maximum set by the server). Not available in Plan Mode.
+
+Example: {"command": "python3 -m pytest -q", "cwd": "/projects/parser", "timeout": 120}"#
+    }
+
+    fn definition(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": "Shell command to run"
+                },
+                "cwd": {
+                    "type": ["string", "null"],
+                    "description": "Workspace folder to run the command in (default: /)"
+                },
+                "timeout": {
+                    "type": ["integer", "string", "null"],
+                    "description": "Seconds before the command is killed (default and maximum set by the server)"
+                }
+            },
+            "required": ["command"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(
+        &self,
+        conn: &mut DbConn,
+        storage: &FileStorageService,
+        workspace_id: Uuid,
+        user_id: Uuid,
+        config: ToolConfig,
+        args: Value,
+    ) -> Result<ToolResponse> {
+        let run_args: RunArgs = serde_json::from_value(args)?;
+        let invalid = |field: &str, message: String| {
+            Error::Validation(ValidationErrors::Single {
+                field: field.to_string(),
+                message,
+            })
+        };
+
+        if config.plan_mode {
+            return Err(invalid("command", super::PLAN_MODE_ERROR.to_string()));
+        }
+        if !config.sandbox.enabled {
+            return Err(invalid("command", "The run tool is not enabled on this server".to_string()));
+        }
+        if run_args.command.trim().is_empty() {
+            return Err(invalid("command", "command cannot be empty".to_string()));
+        }
+        if run_args.command.chars().count() > MAX_COMMAND_LENGTH {
+            return Err(invalid(
+                "command",
+                format!("command cannot be longer than {} characters", MAX_COMMAND_LENGTH),
+            ));
+        }
+
+        let cwd = super::normalize_path(run_args.cwd.as_deref().unwrap_or("/"));
+        let workspace_dir = storage.get_workspace_path(workspace_id);
+        tokio::fs::create_dir_all(&workspace_dir).await?;
+        if !workspace_dir.join(cwd.trim_start_matches('/')).is_dir() {
+            return Err(invalid("cwd", format!("Folder not found: {}", cwd)));
+        }
+
+        let timeout_seconds = run_args
+            .timeout
+            .map(|seconds| seconds as u64)
+            .unwrap_or(config.sandbox.timeout_seconds)
+            .clamp(1, config.sandbox.max_timeout_seconds.max(1));
+
+        let before = snapshot(workspace_dir.clone()).await?;
+        let output = sandbox::run_sandboxed(
+            &config.sandbox,
+            &workspace_dir,
+            &cwd,
+            &run_args.command,
+            Duration::from_secs(timeout_seconds),
+            &config.call,
+        )
+        .await?;
+        let after = snapshot(workspace_dir.clone()).await?;
+
+        // Changes are recorded even when the command was killed, they are on disk
+        let (changes, unrecorded) = record_changes(
+            conn,
+            storage,
+            workspace_id,
+            user_id,
+            &workspace_dir,
+            sandbox::diff_snapshots(&before, &after),
+            &after,
+        )
+        .await;
+
+        tracing::info!(
+            exit_code = ?output.exit_code,
+            timed_out = output.timed_out,
+            changes = changes.len(),
+            unrecorded = unrecorded.len(),
+            duration_ms = output.duration.as_millis() as u64,
+            "Sandboxed command finished"
+        );
+
+        if output.cancelled {
+            return Err(Error::Internal("Tool call was cancelled".to_string()));
+        }
+
+        // A failing command is still a successful call, the model reads its exit code
+        Ok(ToolResponse {
+            success: true,
+            result: serde_json::to_value(RunResult {
+                command: run_args.command,
+                cwd,
+                exit_code: output.exit_code,
+                timed_out: output.timed_out,
+                stdout: output.stdout,
+                stderr: output.stderr,
+                output_truncated: output.truncated,
+                duration_ms: output.duration.as_millis() as u64,
+                changes,
+                unrecorded,
+            })?,
+            error: None,
+        })
+    }
+}
+
+async fn snapshot(workspace_dir: PathBuf) -> Result<TreeSnapshot> {
+    tokio::task::spawn_blocking(move || sandbox::snapshot_tree(&workspace_dir))
+        .await
+        .map_err(|e| Error::Internal(format!("Workspace snapshot failed: {}", e)))?
+        .map_err(|e| Error::Internal(format!("Failed to read the workspace tree: {}", e)))
+}
+
+/// Records the changes of a run, returning those recorded and those left on disk
+async fn record_changes(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    workspace_id: Uuid,
+    user_id: Uuid,
+    workspace_dir: &Path,
+    changes: Vec<(String, RunChangeKind)>,
+    after: &TreeSnapshot,
+) -> (Vec<RunFileChange>, Vec<RunUnrecordedChange>) {
+    let mut recorded = Vec::new();
+    let mut unrecorded = Vec::new();
+
+    for (path, change) in changes {
+        let disk_path = workspace_dir.join(path.trim_start_matches('/'));
+        let outcome = if after.get(&path) == Some(&TreeEntry::Special) {
+            // Links could point the server's own file tools outside of the workspace
+            let _ = tokio::fs::remove_file(&disk_path).await;
+            Err("Links and special files are removed, only regular files are recorded".to_string())
+        } else if recorded.len() >= MAX_RECORDED_CHANGES {
+            Err(format!("Only the first {} changes of a run are recorded", MAX_RECORDED_CHANGES))
+        } else {
+            record_change(conn, storage, workspace_id, user_id, &disk_path, &path, change).await
+        };
+
+        match outcome {
+            Ok(Some(recorded_change)) => recorded.push(recorded_change),
+            Ok(None) => {}
+            Err(reason) if unrecorded.len() < MAX_LISTED_UNRECORDED => {
+                unrecorded.push(RunUnrecordedChange { path, change, reason })
+            }
+            Err(_) => {}
+        }
+    }
+
+    (recorded, unrecorded)
+}
+
+/// Records a single changed file, `None` if there is nothing to record
+///
+/// Deleting a file the workspace does not track, or rewriting a file with its
+/// current content, changes nothing.
+async fn record_change(
+    conn: &mut DbConn,
+    storage: &FileStorageService,
+    workspace_id: Uuid,
+    user_id: Uuid,
+    disk_path: &Path,
+    path: &str,
+    change: RunChangeKind,
+) -> std::result::Result<Option<RunFileChange>, String> {
+    let existing = file_queries::get_file_by_path(conn, workspace_id, path)
+        .await
+        .map_err(|e| e.to_string())?;
+    if existing.as_ref().is_some_and(|file| file.is_virtual || file.file_type == FileType::Folder) {
+        return Err("The workspace manages this path itself".to_string());
+    }
+
+    if change == RunChangeKind::Deleted {
+        let Some(file) = existing else {
+            return Ok(None);
+        };
+        files::soft_delete_file(conn, storage, file.id).await.map_err(|e| e.to_string())?;
+        return Ok(Some(RunFileChange {
+            path: path.to_string(),
+            change,
+            version_id: None,
+        }));
+    }
+
+    let len = tokio::fs::metadata(disk_path).await.map_err(|e| e.to_string())?.len();
+    if len > MAX_RECORDED_FILE_BYTES {
+        return Err(format!("Files over {} KiB are not recorded", MAX_RECORDED_FILE_BYTES / 1024));
+    }
+    let bytes = tokio::fs::read(disk_path).await.map_err(|e| e.to_string())?;
+    let content = String::from_utf8(bytes).map_err(|_| "Binary files are not recorded".to_string())?;
+
+    match existing {
+        Some(file) => {
+            if let Ok(version) = file_queries::get_latest_version(conn, file.id).await
+                && storage
+                    .read_version(workspace_id, &version.hash)
+                    .await
+                    .is_ok_and(|previous| previous == content.as_bytes())
+            {
+                return Ok(None);
+            }
+
+            let version = files::create_version(
+                conn,
+                storage,
+                file.id,
+                CreateVersionRequest {
+                    author_id: Some(user_id),
+                    branch: Some("main".to_string()),
+                    content: Value::String(content),
+                    app_data: None,
+                },
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+            Ok(Some(RunFileChange {
+                path: path.to_string(),
+                change: RunChangeKind::Modified,
+                version_id: Some(version.id),
+            }))
+        }
+        None => {
+            // Workspace paths are slugs, other names would be recorded under a different path
+            if !path.split('/').skip(1).all(|segment| files::slugify(segment) == segment) {
+                return Err(
+                    "Only names of lowercase letters, digits, '.', '_' and '-' are recorded".to_string(),
+                );
+            }
+
+            let file_type = if crate::utils::is_skill_manifest_path(path) {
+                FileType::Skill
+            } else if crate::utils::is_agent_file_path(path) {
+                FileType::Agent
+            } else {
+                FileType::Document
+            };
+            let name = path.rsplit('/').next().unwrap_or(path).to_string();
+
+            let created = files::create_file_with_content(
+                conn,
+                storage,
+                CreateFileRequest {
+                    workspace_id,
+                    parent_id: None,
+                    author_id: user_id,
+                    name,
+                    slug: None,
+                    path: Some(path.to_string()),
+                    is_virtual: None,
+                    is_remote: None,
+                    permission: None,
+                    file_type,
+                    content: Value::String(content),
+                    app_data: None,
+                },
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+            Ok(Some(RunFileChange {
+                path: path.to_string(),
+                change: RunChangeKind::Created,
+                version_id: Some(created.latest_version.id),
+            }))
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Skill activate tool - loads a skill's instructions into the conversation.
+
+use crate::error::{Error, Result, ValidationErrors};
+use crate::models::requests::{SkillActivateArgs, ToolResponse};
+use crate::services::skills;
+use crate::services::storage::FileStorageService;
+use crate::tools::{Tool, ToolConfig};
+use crate::DbConn;
+use async_trait::async_trait;
+use serde_json::Value;
+use uuid::Uuid;
+
+pub struct SkillActivateTool;
+
+#[async_trait]
+impl Tool for SkillActivateTool {
+    fn name(&self) -> &'static str {
+        "skill_activate"
+    }
+
+    fn description(&self) -> &'static str {
+        r#"Loads the full instructions of a skill by name.
+
+Call this when a request matches a skill from the Available Skills catalog
+(or from skill_list), then follow the returned instructions. If the skill
+lists allowed_tools, restrict yourself to those tools while following it.
+
+Example: {"name": "github-pr"}"#
+    }
+
+    fn definition(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "Name of the skill to activate (case-insensitive)"
+                }
+            },
+            "required": ["name"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(
+        &self,
+        conn: &mut DbConn,
+        storage: &FileStorageService,
+        workspace_id: Uuid,
+        _user_id: Uuid,
+        _config: ToolConfig,
+        args: Value,
+    ) -> Result<ToolResponse> {
+        let activate_args: SkillActivateArgs = serde_json::from_value(args)?;
+
+        if activate_args.name.trim().is_empty() {
+            return Err(Error::Validation(ValidationErrors::Single {
+                field: "name".to_string(),
+                message: "name cannot be empty".to_string(),
+            }));
+        }
+
+        // Skills only add instructions, so they are available in plan mode too
+        let skill = skills::find_skill_by_name(conn, storage, workspace_id, &activate_args.name).await?;
+
+        Ok(ToolResponse {
+            success: true,
+            result: serde_json::to_value(skill.to_activate_result())?,
+            error: None,
+        })
+    }
+}
@@ -0,0 +1,82 @@
+//! Skill list tool - lists skill files with their frontmatter.
+
+use crate::error::Result;
+use crate::models::requests::{SkillListArgs, SkillListResult, ToolResponse};
+use crate::services::skills;
+use crate::services::storage::FileStorageService;
+use crate::tools::{Tool, ToolConfig};
+use crate::DbConn;
+use async_trait::async_trait;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Default maximum number of skills returned
+const DEFAULT_LIST_LIMIT: usize = 50;
+
+pub struct SkillListTool;
+
+#[async_trait]
+impl Tool for SkillListTool {
+    fn name(&self) -> &'static str {
+        "skill_list"
+    }
+
+    fn description(&self) -> &'static str {
+        r#"Lists skills available in this workspace without loading their instructions.
+
+Skills are reusable instruction packs (usually /system/skills/{name}/SKILL.md).
+Returns name, description, triggers and allowed_tools for each skill.
+Use skill_activate to load a skill's full instructions.
+
+Example: {"query": "pull request"}"#
+    }
+
+    fn definition(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": ["string", "null"],
+                    "description": "Case-insensitive filter on name, description and triggers"
+                },
+                "limit": {
+                    "type": ["integer", "string", "null"],
+                    "description": "Maximum number of skills to return (default: 50). Accepts integer or string."
+                }
+            },
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(
+        &self,
+        conn: &mut DbConn,
+        storage: &FileStorageService,
+        workspace_id: Uuid,
+        _user_id: Uuid,
+        _config: ToolConfig,
+        args: Value,
+    ) -> Result<ToolResponse> {
+        let list_args: SkillListArgs = serde_json::from_value(args)?;
+        let query = list_args.query.unwrap_or_default();
+        let limit = list_args.limit.unwrap_or(DEFAULT_LIST_LIMIT);
+
+        let mut matching: Vec<_> = skills::list_skills(conn, storage, workspace_id)
+            .await?
+            .iter()
+            .filter(|skill| skill.matches(&query))
+            .map(|skill| skill.to_list_item())
+            .collect();
+
+        let total = matching.len();
+        matching.truncate(limit);
+
+        let result = SkillListResult { skills: matching, total };
+
+        Ok(ToolResponse {
+            success: true,
+            result: serde_json::to_value(result)?,
+            error: None,
+        })
+    }
+}
@@ -114,7 +114,7 @@ EXAMPLE: {"url":"https://docs.rs/serde","format":"markdown","max_content_size":5
         _storage: &FileStorageService,
         _workspace_id: Uuid,
         _user_id: Uuid,
-        _config: ToolConfig,
+        config: ToolConfig,
         args: Value,
     ) -> Result<ToolResponse> {
         let fetch_args: WebFetchArgs = serde_json::from_value(args)?;
@@ -140,16 +140,7 @@ EXAMPLE: {"url":"https://docs.rs/serde","format":"markdown","max_content_size":5
         let timeout_secs = fetch_args.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS as usize) as u64;
         let follow_redirects = fetch_args.follow_redirects.unwrap_or(true);
 
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(timeout_secs))
-            .redirect(if follow_redirects {
-                reqwest::redirect::Policy::limited(10)
-            } else {
-                reqwest::redirect::Policy::none()
-            })
-            .user_agent("BuildScale-AI/1.0")
-            .build()
-            .map_err(|e| Error::Internal(format!("Failed to create HTTP client: {}", e)))?;
+        let client = http_client(std::time::Duration::from_secs(timeout_secs), follow_redirects)?;
 
         // Calculate max content size with ceiling
         let max_content_size = fetch_args.max_content_size
@@ -175,11 +166,13 @@ EXAMPLE: {"url":"https://docs.rs/serde","format":"markdown","max_content_size":5
             request_builder = request_builder.body(body);
         }
 
-        // Execute request
+        // Execute request, abandoned if the tool call is cancelled
         let start_time = Instant::now();
-        let response = request_builder.send().await.map_err(|e| {
-            Error::Internal(format!("HTTP request failed: {}", e))
-        })?;
+        let response = config.call.run(async {
+            request_builder.send().await.map_err(|e| {
+                Error::Internal(format!("HTTP request failed: {}", e))
+            })
+        }).await?;
 
         let elapsed_ms = start_time.elapsed().as_millis() as u64;
         let status_code = response.status().as_u16();
@@ -190,23 +183,7 @@ EXAMPLE: {"url":"https://docs.rs/serde","format":"markdown","max_content_size":5
             .map(|s| s.to_string());
 
         // Get response body with streaming to prevent OOM
-        let mut body_bytes = Vec::new();
-        let mut stream = response.bytes_stream();
-        let mut truncated = false;
-
-        while let Some(item) = stream.next().await {
-            let chunk = item.map_err(|e| Error::Internal(format!("Failed to read response chunk: {}", e)))?;
-            if body_bytes.len() + chunk.len() > max_content_size {
-                // Only take what fits within the limit
-                let remaining = max_content_size.saturating_sub(body_bytes.len());
-                if remaining > 0 {
-                    body_bytes.extend_from_slice(&chunk[..remaining]);
-                }
-                truncated = true;
-                break;
-            }
-            body_bytes.extend_from_slice(&chunk);
-        }
+        let (body_bytes, truncated) = config.call.run(read_body(response, max_content_size)).await?;
 
         // Convert to string (handle binary content gracefully)
         let body_str = String::from_utf8_lossy(&body_bytes);
@@ -252,16 +229,7 @@ EXAMPLE: {"url":"https://docs.rs/serde","format":"markdown","max_content_size":5
                     html_to_text(&body_str)
                 }
             }
-            WebFetchFormat::Markdown => {
-                // Try clean extraction first
-                if let Some((clean_html, _, title)) = extract_article_content(&body_str, &final_url) {
-                    let md = htmd::convert(&clean_html).unwrap_or_default();
-                    format!("# {}\n\n{}", title, md)
-                } else {
-                    // Fallback: convert raw HTML
-                    htmd::convert(&body_str).unwrap_or_default()
-                }
-            }
+            WebFetchFormat::Markdown => html_to_markdown(&body_str, &final_url).0,
         };
 
         // Extract links if requested
@@ -290,6 +258,54 @@ EXAMPLE: {"url":"https://docs.rs/serde","format":"markdown","max_content_size":5
     }
 }
 
+/// HTTP client used to fetch web content
+pub(crate) fn http_client(timeout: std::time::Duration, follow_redirects: bool) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .redirect(if follow_redirects {
+            reqwest::redirect::Policy::limited(10)
+        } else {
+            reqwest::redirect::Policy::none()
+        })
+        .user_agent("BuildScale-AI/1.0")
+        .build()
+        .map_err(|e| Error::Internal(format!("Failed to create HTTP client: {}", e)))
+}
+
+/// Reads a response body up to `max_size` bytes
+/// Returns the bytes read and whether the body was cut at the limit
+pub(crate) async fn read_body(response: reqwest::Response, max_size: usize) -> Result<(Vec<u8>, bool)> {
+    let mut body_bytes = Vec::new();
+    let mut stream = response.bytes_stream();
+
+    while let Some(item) = stream.next().await {
+        let chunk = item.map_err(|e| Error::Internal(format!("Failed to read response chunk: {}", e)))?;
+        if body_bytes.len() + chunk.len() > max_size {
+            // Only take what fits within the limit
+            let remaining = max_size.saturating_sub(body_bytes.len());
+            if remaining > 0 {
+                body_bytes.extend_from_slice(&chunk[..remaining]);
+            }
+            return Ok((body_bytes, true));
+        }
+        body_bytes.extend_from_slice(&chunk);
+    }
+    Ok((body_bytes, false))
+}
+
+/// Convert HTML to markdown, keeping only the article content when Readability finds one
+/// Returns (markdown, title), the title only if the article was extracted
+pub(crate) fn html_to_markdown(html: &str, url: &str) -> (String, Option<String>) {
+    // Try clean extraction first
+    if let Some((clean_html, _, title)) = extract_article_content(html, url) {
+        let md = htmd::convert(&clean_html).unwrap_or_default();
+        (format!("# {}\n\n{}", title, md), Some(title))
+    } else {
+        // Fallback: convert raw HTML
+        (htmd::convert(html).unwrap_or_default(), None)
+    }
+}
+
 /// Convert HTML to plain text by stripping tags
 fn html_to_text(html: &str) -> String {
     use scraper::{Html, Selector};
@@ -129,6 +129,12 @@ impl Tool for WriteTool {
                         message: format!("Invalid file type: {}", ft_str),
                     })
                 })?
+            } else if crate::utils::is_skill_manifest_path(&path) {
+                // Skill manifests are picked up by the skill catalog without an explicit type
+                FileType::Skill
+            } else if crate::utils::is_agent_file_path(&path) {
+                // Agent files are validated and selectable as chat personas
+                FileType::Agent
             } else {
                 FileType::Document
             };
@@ -0,0 +1,154 @@
+//! Agent metadata parsing for agent files.
+//!
+//! Agent files (`*.agent`) define a custom persona. The YAML frontmatter holds
+//! the defaults for chats using the agent, the body is the system prompt:
+//!
+//! ```text
+//! ---
+//! name: reviewer
+//! description: Reviews pull requests for security issues
+//! model: openai:gpt-5
+//! temperature: 0.2
+//! tools: [read, grep, glob, memory_get]
+//! memory_scope: user
+//! ---
+//! You are a meticulous security reviewer...
+//! ```
+
+use super::MemoryScope;
+use serde::{Deserialize, Serialize};
+
+/// File extension of agent files
+pub const AGENT_FILE_EXTENSION: &str = ".agent";
+
+/// Agent metadata extracted from YAML frontmatter
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AgentMetadata {
+    /// Display name (falls back to the file name when empty)
+    #[serde(default)]
+    pub name: String,
+    /// One-line summary shown when picking an agent
+    #[serde(default)]
+    pub description: String,
+    /// Default model for new chats ("provider:model")
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Default sampling temperature for new chats
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Tools the agent may call (all tools when absent)
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+    /// Restricts memory tools to a single scope (both scopes when absent)
+    #[serde(default)]
+    pub memory_scope: Option<MemoryScope>,
+}
+
+/// Parse agent frontmatter from content, returns (metadata, remaining_content)
+///
+/// Unlike other frontmatter parsers this one reports YAML errors, since agent
+/// files are validated on write.
+pub fn parse_agent_frontmatter(content: &str) -> Result<(Option<AgentMetadata>, &str), serde_yaml::Error> {
+    let content = content.trim_start();
+
+    // Check for YAML frontmatter delimiter
+    if !content.starts_with("---\n") {
+        return Ok((None, content));
+    }
+
+    // Find closing delimiter
+    let rest = &content[4..]; // Skip opening "---\n"
+    let (yaml_str, remaining) = if let Some(end_idx) = rest.find("\n---\n") {
+        (&rest[..end_idx], &rest[end_idx + 5..]) // Skip "\n---\n"
+    } else if let Some(end_idx) = rest.find("\n---") {
+        // Handle case where content ends with ---
+        (&rest[..end_idx], &rest[end_idx + 4..])
+    } else {
+        return Ok((None, content));
+    };
+
+    let metadata = serde_yaml::from_str::<Option<AgentMetadata>>(yaml_str)?.unwrap_or_default();
+    Ok((Some(metadata), remaining))
+}
+
+/// Returns true if the path has the agent file extension
+pub fn is_agent_file_path(path: &str) -> bool {
+    path.to_lowercase().ends_with(AGENT_FILE_EXTENSION)
+}
+
+/// Derive an agent name from its file path (file name without extension)
+pub fn agent_name_from_path(path: &str) -> String {
+    let file_name = path.rsplit('/').find(|p| !p.is_empty()).unwrap_or_default();
+    file_name
+        .rsplit_once('.')
+        .map(|(stem, _)| stem)
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or(file_name)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_agent_frontmatter_valid() {
+        let content = r#"---
+name: reviewer
+description: Reviews code
+model: openai:gpt-5
+temperature: 0.2
+tools: [read, grep]
+memory_scope: global
+---
+You are a reviewer."#;
+
+        let (metadata, prompt) = parse_agent_frontmatter(content).unwrap();
+        let metadata = metadata.expect("Should parse metadata");
+
+        assert_eq!(metadata.name, "reviewer");
+        assert_eq!(metadata.model.as_deref(), Some("openai:gpt-5"));
+        assert_eq!(metadata.temperature, Some(0.2));
+        assert_eq!(metadata.tools, Some(vec!["read".to_string(), "grep".to_string()]));
+        assert_eq!(metadata.memory_scope, Some(MemoryScope::Global));
+        assert_eq!(prompt, "You are a reviewer.");
+    }
+
+    #[test]
+    fn test_parse_agent_frontmatter_empty_block() {
+        let (metadata, prompt) = parse_agent_frontmatter("---\n\n---\nBe brief.").unwrap();
+
+        assert_eq!(metadata, Some(AgentMetadata::default()));
+        assert_eq!(prompt, "Be brief.");
+    }
+
+    #[test]
+    fn test_parse_agent_frontmatter_rejects_unknown_fields() {
+        let content = "---\nname: x\ntemprature: 0.5\n---\nPrompt";
+        assert!(parse_agent_frontmatter(content).is_err());
+    }
+
+    #[test]
+    fn test_parse_agent_frontmatter_rejects_invalid_scope() {
+        let content = "---\nmemory_scope: team\n---\nPrompt";
+        assert!(parse_agent_frontmatter(content).is_err());
+    }
+
+    #[test]
+    fn test_parse_agent_frontmatter_none() {
+        let (metadata, prompt) = parse_agent_frontmatter("Just a prompt.").unwrap();
+
+        assert!(metadata.is_none());
+        assert_eq!(prompt, "Just a prompt.");
+    }
+
+    #[test]
+    fn test_agent_paths() {
+        assert!(is_agent_file_path("/agents/reviewer.agent"));
+        assert!(is_agent_file_path("/agents/Reviewer.AGENT"));
+        assert!(!is_agent_file_path("/agents/reviewer.md"));
+        assert_eq!(agent_name_from_path("/agents/reviewer.agent"), "reviewer");
+        assert_eq!(agent_name_from_path("reviewer"), "reviewer");
+    }
+}
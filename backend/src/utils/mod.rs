@@ -1,15 +1,28 @@
 //! Utility modules for BuildScale
 
+pub mod agent_metadata;
 pub mod plan_namer;
+pub mod plan_steps;
 pub mod frontmatter;
 pub mod memory_metadata;
+pub mod net;
+pub mod skill_metadata;
 pub mod string;
 
+pub use agent_metadata::{
+    agent_name_from_path, is_agent_file_path, parse_agent_frontmatter, AgentMetadata,
+    AGENT_FILE_EXTENSION,
+};
 pub use plan_namer::generate_plan_name;
+pub use plan_steps::{parse_plan_steps, update_plan_step, PlanProgress, PlanStep, PlanStepStatus};
 pub use frontmatter::{parse_frontmatter, prepend_frontmatter, PlanMetadata, PlanStatus};
 pub use memory_metadata::{
     parse_memory_frontmatter, prepend_memory_frontmatter, generate_memory_path,
     parse_memory_path,
     MemoryMetadata, MemoryScope,
 };
+pub use skill_metadata::{
+    is_skill_manifest_path, parse_skill_frontmatter, skill_name_from_path, SkillMetadata,
+    SKILLS_ROOT, SKILL_FILE_NAME,
+};
 pub use string::{safe_preview, truncate_safe, MAX_PREVIEW_LEN};
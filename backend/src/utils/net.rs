@@ -0,0 +1,244 @@
+//! Guards for requests the server makes to URLs its users supply
+//!
+//! Web pages attached to chat messages and workspace MCP servers are fetched by
+//! the server itself, so their URLs must not reach into the server's own network:
+//! loopback, private and link-local addresses (such as the cloud metadata service
+//! at 169.254.169.254) and other addresses that are not publicly routable are
+//! refused. Hosts listed in `BUILDSCALE__SERVER__ALLOWED_PRIVATE_HOSTS` are exempt,
+//! for intranet services the server is meant to reach.
+//!
+//! Host names are checked each time a connection is made by [`PublicResolver`],
+//! so a name that resolves to a private address later, or a redirect to one, is
+//! refused as well. Addresses written in a URL are never resolved; [`check_url`]
+//! and the redirect policy of [`public_client_builder`] check those.
+
+use crate::error::{Error, Result, ValidationErrors};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{LazyLock, RwLock};
+use url::{Host, Url};
+
+/// Most redirects followed for a request
+const MAX_REDIRECTS: usize = 10;
+
+static ALLOWED_PRIVATE_HOSTS: LazyLock<RwLock<HashSet<String>>> = LazyLock::new(Default::default);
+
+/// Lets requests reach `hosts` even though they resolve to private addresses
+///
+/// Hosts are names or addresses as written in URLs, e.g. `wiki.intranet` or
+/// `10.0.0.5`. The server adds those of its configuration at startup.
+pub fn allow_private_hosts<S: AsRef<str>>(hosts: impl IntoIterator<Item = S>) {
+    let mut allowed = ALLOWED_PRIVATE_HOSTS.write().unwrap_or_else(|e| e.into_inner());
+    for host in hosts {
+        let host = host.as_ref().trim().trim_start_matches('[').trim_end_matches(']');
+        if !host.is_empty() {
+            allowed.insert(host.to_ascii_lowercase());
+        }
+    }
+}
+
+fn is_allowed_private_host(host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase();
+    ALLOWED_PRIVATE_HOSTS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(&host)
+}
+
+/// Returns true if `ip` is a publicly routable address
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => is_public_ipv6(ip),
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "This network"
+        || a == 0
+        // Shared address space of carrier-grade NAT
+        || (a == 100 && (b & 0xc0) == 64)
+        // IETF protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking
+        || (a == 198 && (b & 0xfe) == 18)
+        // Reserved
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    if let Some(ipv4) = ip.to_ipv4_mapped() {
+        return is_public_ipv4(ipv4);
+    }
+    let segments = ip.segments();
+    // NAT64 embeds the IPv4 address it translates to
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        return is_public_ipv4(Ipv4Addr::from(((segments[6] as u32) << 16) | segments[7] as u32));
+    }
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // IPv4-compatible addresses, deprecated
+        || segments[..6] == [0; 6]
+        // Unique local
+        || (segments[0] & 0xfe00) == 0xfc00
+        // Link-local
+        || (segments[0] & 0xffc0) == 0xfe80
+        // Documentation
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+}
+
+/// Checks that a URL does not name a private address
+///
+/// Only addresses written in the URL are checked, host names are checked when
+/// they are resolved. See [`check_url_resolves`] to resolve them now.
+pub fn check_url(url: &Url) -> Result<()> {
+    let ip = match url.host() {
+        Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+        Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+        Some(Host::Domain(_)) => return Ok(()),
+        None => return Err(Error::Forbidden(format!("{} has no host", url))),
+    };
+    if is_public_ip(ip) || is_allowed_private_host(&ip.to_string()) {
+        Ok(())
+    } else {
+        Err(not_public(&ip.to_string()))
+    }
+}
+
+/// Checks that a URL names or resolves to public addresses only
+///
+/// Used to reject a URL when it is submitted. The connection is checked again
+/// when it is made, as the name may resolve differently by then.
+pub async fn check_url_resolves(url: &Url) -> Result<()> {
+    check_url(url)?;
+    let Some(Host::Domain(host)) = url.host() else {
+        return Ok(());
+    };
+    if is_allowed_private_host(host) {
+        return Ok(());
+    }
+
+    let port = url.port_or_known_default().unwrap_or(0);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| Error::Validation(ValidationErrors::Single {
+            field: "url".to_string(),
+            message: format!("Could not resolve {}: {}", host, e),
+        }))?
+        .collect();
+    if addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+        return Err(not_public(host));
+    }
+    Ok(())
+}
+
+fn not_public(host: &str) -> Error {
+    Error::Forbidden(format!("{} is not a public address", host))
+}
+
+/// Resolves host names to their public addresses only
+///
+/// A name with no public address fails to resolve, so the request fails.
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let allowed = is_allowed_private_host(&host);
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| allowed || is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(not_public(&host).to_string().into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// HTTP client builder whose requests and redirects only reach public addresses
+pub fn public_client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .dns_resolver(PublicResolver)
+        .redirect(reqwest::redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            match check_url(attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e.to_string()),
+            }
+        }))
+}
+
+/// Describes a failed request with its causes, which tell why an address was refused
+pub fn request_error(error: &reqwest::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public(ip: &str) -> bool {
+        is_public_ip(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        assert!(public("93.184.216.34"));
+        assert!(public("2606:4700:4700::1111"));
+
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+            "64:ff9b::a00:1",
+        ] {
+            assert!(!public(ip), "{} should not be public", ip);
+        }
+    }
+
+    #[test]
+    fn test_check_url() {
+        let check = |url: &str| check_url(&Url::parse(url).unwrap());
+
+        assert!(check("https://example.com/page").is_ok());
+        assert!(check("http://93.184.216.34/").is_ok());
+        assert!(matches!(check("http://169.254.169.254/latest/meta-data/"), Err(Error::Forbidden(_))));
+        assert!(matches!(check("http://[::1]:8080/"), Err(Error::Forbidden(_))));
+        assert!(matches!(check("http://0x7f000001/"), Err(Error::Forbidden(_))));
+
+        allow_private_hosts(["10.9.8.7"]);
+        assert!(check("http://10.9.8.7/wiki").is_ok());
+    }
+}
@@ -0,0 +1,294 @@
+//! Step checklist parsing for plan files.
+//!
+//! The steps of a plan are the markdown task items in its body, numbered from 1
+//! in document order. The checkbox marker holds the step's progress:
+//!
+//! ```text
+//! - [ ] Pending step
+//! - [~] Step in progress
+//! - [x] Done step
+//! - [!] Blocked step
+//! ```
+//!
+//! Task items inside fenced code blocks are ignored.
+
+use super::frontmatter::parse_frontmatter;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Progress state of a plan step
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanStepStatus {
+    Pending,
+    InProgress,
+    Done,
+    Blocked,
+}
+
+impl PlanStepStatus {
+    /// Checkbox marker written for this status
+    pub fn marker(self) -> char {
+        match self {
+            Self::Pending => ' ',
+            Self::InProgress => '~',
+            Self::Done => 'x',
+            Self::Blocked => '!',
+        }
+    }
+
+    /// Parses a checkbox marker, `None` if the marker is not a known status
+    pub fn from_marker(marker: char) -> Option<Self> {
+        match marker {
+            ' ' => Some(Self::Pending),
+            '~' => Some(Self::InProgress),
+            'x' | 'X' => Some(Self::Done),
+            '!' => Some(Self::Blocked),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PlanStepStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending => write!(f, "pending"),
+            Self::InProgress => write!(f, "in_progress"),
+            Self::Done => write!(f, "done"),
+            Self::Blocked => write!(f, "blocked"),
+        }
+    }
+}
+
+impl FromStr for PlanStepStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pending" => Ok(Self::Pending),
+            "in_progress" => Ok(Self::InProgress),
+            "done" => Ok(Self::Done),
+            "blocked" => Ok(Self::Blocked),
+            _ => Err(format!("Invalid plan step status: {}", s)),
+        }
+    }
+}
+
+/// A task item of a plan
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PlanStep {
+    /// 1-based position among the plan's task items
+    pub index: usize,
+    pub text: String,
+    pub status: PlanStepStatus,
+}
+
+/// Step counts per status
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PlanProgress {
+    pub total: usize,
+    pub pending: usize,
+    pub in_progress: usize,
+    pub done: usize,
+    pub blocked: usize,
+}
+
+impl PlanProgress {
+    pub fn from_steps(steps: &[PlanStep]) -> Self {
+        let mut progress = Self {
+            total: steps.len(),
+            ..Default::default()
+        };
+        for step in steps {
+            match step.status {
+                PlanStepStatus::Pending => progress.pending += 1,
+                PlanStepStatus::InProgress => progress.in_progress += 1,
+                PlanStepStatus::Done => progress.done += 1,
+                PlanStepStatus::Blocked => progress.blocked += 1,
+            }
+        }
+        progress
+    }
+
+    /// True when the plan has steps and all of them are done
+    pub fn is_complete(&self) -> bool {
+        self.total > 0 && self.done == self.total
+    }
+}
+
+/// Location of a task item's checkbox within a line
+struct TaskItem<'a> {
+    /// Byte offset of the marker character in the line
+    marker_offset: usize,
+    status: PlanStepStatus,
+    text: &'a str,
+}
+
+/// Parses a line as a task item (`- [ ] text`, `* [x] text`, `+ [~] text`)
+fn parse_task_item(line: &str) -> Option<TaskItem<'_>> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+
+    let mut chars = trimmed.chars();
+    if !matches!(chars.next(), Some('-' | '*' | '+')) || chars.next() != Some(' ') {
+        return None;
+    }
+
+    let rest = &trimmed[2..];
+    let rest_trimmed = rest.trim_start_matches(' ');
+    let mut chars = rest_trimmed.chars();
+    if chars.next() != Some('[') {
+        return None;
+    }
+    let marker = chars.next()?;
+    if chars.next() != Some(']') {
+        return None;
+    }
+    let status = PlanStepStatus::from_marker(marker)?;
+
+    let marker_offset = indent + 2 + (rest.len() - rest_trimmed.len()) + 1;
+    let text = rest_trimmed[1 + marker.len_utf8() + 1..].trim();
+    Some(TaskItem { marker_offset, status, text })
+}
+
+/// Iterates the task items of a plan body as (line index, item)
+fn task_items(body: &str) -> impl Iterator<Item = (usize, TaskItem<'_>)> {
+    let mut in_code_block = false;
+    body.lines().enumerate().filter_map(move |(line_index, line)| {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            return None;
+        }
+        if in_code_block {
+            return None;
+        }
+        parse_task_item(line).map(|item| (line_index, item))
+    })
+}
+
+/// Parses the step checklist of a plan (content with or without frontmatter)
+pub fn parse_plan_steps(content: &str) -> Vec<PlanStep> {
+    let (_, body) = parse_frontmatter(content);
+    task_items(body)
+        .enumerate()
+        .map(|(i, (_, item))| PlanStep {
+            index: i + 1,
+            text: item.text.to_string(),
+            status: item.status,
+        })
+        .collect()
+}
+
+/// Sets the status of the step at `index` (1-based)
+///
+/// Only the checkbox marker changes, the frontmatter and every other byte of
+/// the plan are kept as they are. Returns `None` if there is no such step.
+pub fn update_plan_step(content: &str, index: usize, status: PlanStepStatus) -> Option<String> {
+    let (_, body) = parse_frontmatter(content);
+    let body_start = content.len() - body.len();
+
+    let (line_index, marker_offset) = task_items(body)
+        .nth(index.checked_sub(1)?)
+        .map(|(line_index, item)| (line_index, item.marker_offset))?;
+
+    // Byte offset of the line in the body, counting the original line endings
+    let line_start: usize = body
+        .split_inclusive('\n')
+        .take(line_index)
+        .map(str::len)
+        .sum();
+    let marker_start = body_start + line_start + marker_offset;
+    let marker_len = content[marker_start..].chars().next()?.len_utf8();
+
+    let mut updated = String::with_capacity(content.len());
+    updated.push_str(&content[..marker_start]);
+    updated.push(status.marker());
+    updated.push_str(&content[marker_start + marker_len..]);
+    Some(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAN: &str = "---
+title: Launch
+status: approved
+created_at: 2025-01-15T10:30:00Z
+---
+# Steps
+
+- [x] Write the announcement
+- [~] Update the docs
+  - [ ] Nested: fix screenshots
+* [!] Get legal sign-off
+- Not a step
+- [?] Unknown marker
+
+```markdown
+- [ ] Example inside a code block
+```
+
++ [ ] Publish
+";
+
+    #[test]
+    fn test_parse_plan_steps() {
+        let steps = parse_plan_steps(PLAN);
+
+        let summary: Vec<(usize, &str, PlanStepStatus)> =
+            steps.iter().map(|s| (s.index, s.text.as_str(), s.status)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, "Write the announcement", PlanStepStatus::Done),
+                (2, "Update the docs", PlanStepStatus::InProgress),
+                (3, "Nested: fix screenshots", PlanStepStatus::Pending),
+                (4, "Get legal sign-off", PlanStepStatus::Blocked),
+                (5, "Publish", PlanStepStatus::Pending),
+            ]
+        );
+
+        let progress = PlanProgress::from_steps(&steps);
+        assert_eq!(progress.total, 5);
+        assert_eq!(progress.pending, 2);
+        assert_eq!(progress.in_progress, 1);
+        assert_eq!(progress.done, 1);
+        assert_eq!(progress.blocked, 1);
+        assert!(!progress.is_complete());
+    }
+
+    #[test]
+    fn test_update_plan_step_only_changes_marker() {
+        let updated = update_plan_step(PLAN, 5, PlanStepStatus::Done).unwrap();
+        assert_eq!(updated, PLAN.replace("+ [ ] Publish", "+ [x] Publish"));
+
+        let updated = update_plan_step(PLAN, 3, PlanStepStatus::Blocked).unwrap();
+        assert!(updated.contains("  - [!] Nested: fix screenshots"));
+        assert!(updated.contains("- [ ] Example inside a code block"));
+        assert!(updated.starts_with("---\ntitle: Launch\n"));
+    }
+
+    #[test]
+    fn test_update_plan_step_out_of_range() {
+        assert!(update_plan_step(PLAN, 0, PlanStepStatus::Done).is_none());
+        assert!(update_plan_step(PLAN, 6, PlanStepStatus::Done).is_none());
+        assert!(update_plan_step("No steps here", 1, PlanStepStatus::Done).is_none());
+    }
+
+    #[test]
+    fn test_update_plan_step_crlf_without_frontmatter() {
+        let content = "- [ ] One\r\n- [ ] Two\r\n";
+        let updated = update_plan_step(content, 2, PlanStepStatus::InProgress).unwrap();
+        assert_eq!(updated, "- [ ] One\r\n- [~] Two\r\n");
+        assert_eq!(parse_plan_steps(&updated)[1].text, "Two");
+    }
+
+    #[test]
+    fn test_plan_step_status_from_str() {
+        assert_eq!("in_progress".parse::<PlanStepStatus>(), Ok(PlanStepStatus::InProgress));
+        assert_eq!("DONE".parse::<PlanStepStatus>(), Ok(PlanStepStatus::Done));
+        assert!("finished".parse::<PlanStepStatus>().is_err());
+    }
+}
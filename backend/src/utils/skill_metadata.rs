@@ -0,0 +1,171 @@
+//! Skill metadata parsing for skill files.
+//!
+//! Skill files are reusable instruction packs with YAML frontmatter,
+//! conventionally stored at `/system/skills/{name}/SKILL.md`:
+//!
+//! ```text
+//! ---
+//! name: github-pr
+//! description: Open and describe pull requests
+//! triggers: [pull request, PR]
+//! allowed_tools: [read, grep, web_fetch]
+//! ---
+//! # Instructions
+//! ...
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// Conventional folder for workspace skills
+pub const SKILLS_ROOT: &str = "/system/skills";
+
+/// Conventional file name of a skill manifest inside its folder
+pub const SKILL_FILE_NAME: &str = "SKILL.md";
+
+/// Skill metadata extracted from YAML frontmatter
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SkillMetadata {
+    /// Unique skill name (falls back to the folder or file name when empty)
+    #[serde(default)]
+    pub name: String,
+    /// One-line summary shown in the skill catalog
+    #[serde(default)]
+    pub description: String,
+    /// Phrases that suggest the skill is relevant to a request
+    #[serde(default)]
+    pub triggers: Vec<String>,
+    /// Tools the skill instructions rely on
+    #[serde(default, alias = "allowed-tools")]
+    pub allowed_tools: Vec<String>,
+}
+
+/// Parse skill frontmatter from content, returns (metadata, remaining_content)
+pub fn parse_skill_frontmatter(content: &str) -> (Option<SkillMetadata>, &str) {
+    let content = content.trim_start();
+
+    // Check for YAML frontmatter delimiter
+    if !content.starts_with("---\n") {
+        return (None, content);
+    }
+
+    // Find closing delimiter
+    let rest = &content[4..]; // Skip opening "---\n"
+    let (yaml_str, remaining) = if let Some(end_idx) = rest.find("\n---\n") {
+        (&rest[..end_idx], &rest[end_idx + 5..]) // Skip "\n---\n"
+    } else if let Some(end_idx) = rest.find("\n---") {
+        // Handle case where content ends with ---
+        (&rest[..end_idx], &rest[end_idx + 4..])
+    } else {
+        return (None, content);
+    };
+
+    match serde_yaml::from_str::<SkillMetadata>(yaml_str) {
+        Ok(metadata) => (Some(metadata), remaining),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to parse skill frontmatter");
+            (None, content)
+        }
+    }
+}
+
+/// Returns true for the conventional manifest path `/system/skills/{name}/SKILL.md`
+///
+/// The file name is matched case-insensitively since stored paths are lowercased.
+pub fn is_skill_manifest_path(path: &str) -> bool {
+    let path = path.to_lowercase();
+    path.strip_prefix(SKILLS_ROOT)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .and_then(|rest| rest.strip_suffix(&SKILL_FILE_NAME.to_lowercase()))
+        .and_then(|folder| folder.strip_suffix('/'))
+        .is_some_and(|folder| !folder.is_empty() && !folder.contains('/'))
+}
+
+/// Derive a skill name from its file path
+///
+/// `/system/skills/{name}/SKILL.md` yields the folder name, any other path
+/// yields the file name without its extension.
+pub fn skill_name_from_path(path: &str) -> String {
+    let mut parts = path.rsplit('/').filter(|p| !p.is_empty());
+    let file_name = parts.next().unwrap_or_default();
+
+    if file_name.eq_ignore_ascii_case(SKILL_FILE_NAME)
+        && let Some(folder) = parts.next()
+    {
+        return folder.to_string();
+    }
+
+    file_name
+        .rsplit_once('.')
+        .map(|(stem, _)| stem)
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or(file_name)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_skill_frontmatter_valid() {
+        let content = r#"---
+name: github-pr
+description: Open and describe pull requests
+triggers:
+  - pull request
+  - PR
+allowed_tools: [read, grep]
+---
+# Steps
+
+1. Read the diff."#;
+
+        let (metadata, remaining) = parse_skill_frontmatter(content);
+        let metadata = metadata.expect("Should parse metadata");
+
+        assert_eq!(metadata.name, "github-pr");
+        assert_eq!(metadata.description, "Open and describe pull requests");
+        assert_eq!(metadata.triggers, vec!["pull request", "PR"]);
+        assert_eq!(metadata.allowed_tools, vec!["read", "grep"]);
+        assert!(remaining.starts_with("# Steps"));
+    }
+
+    #[test]
+    fn test_parse_skill_frontmatter_hyphenated_allowed_tools() {
+        let content = "---\nname: lint\nallowed-tools: [grep]\n---\nRun the linter.";
+
+        let (metadata, remaining) = parse_skill_frontmatter(content);
+        let metadata = metadata.expect("Should parse metadata");
+
+        assert_eq!(metadata.allowed_tools, vec!["grep"]);
+        assert!(metadata.triggers.is_empty());
+        assert_eq!(remaining, "Run the linter.");
+    }
+
+    #[test]
+    fn test_parse_skill_frontmatter_none() {
+        let content = "# Just instructions\n\nNo frontmatter here.";
+        let (metadata, remaining) = parse_skill_frontmatter(content);
+
+        assert!(metadata.is_none());
+        assert!(remaining.contains("Just instructions"));
+    }
+
+    #[test]
+    fn test_is_skill_manifest_path() {
+        assert!(is_skill_manifest_path("/system/skills/github-pr/SKILL.md"));
+        assert!(is_skill_manifest_path("/system/skills/github-pr/skill.md"));
+        assert!(!is_skill_manifest_path("/system/skills/SKILL.md"));
+        assert!(!is_skill_manifest_path("/system/skills/a/b/SKILL.md"));
+        assert!(!is_skill_manifest_path("/system/skillset/a/SKILL.md"));
+        assert!(!is_skill_manifest_path("/system/skills/a/README.md"));
+    }
+
+    #[test]
+    fn test_skill_name_from_path() {
+        assert_eq!(skill_name_from_path("/system/skills/github-pr/SKILL.md"), "github-pr");
+        assert_eq!(skill_name_from_path("/system/skills/github-pr/skill.md"), "github-pr");
+        assert_eq!(skill_name_from_path("/notes/release.md"), "release");
+        assert_eq!(skill_name_from_path("/notes/checklist"), "checklist");
+    }
+}
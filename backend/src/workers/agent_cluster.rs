@@ -0,0 +1,175 @@
+use crate::models::chat::ChatMessageRole;
+use crate::models::agent_session::SessionStatus;
+use crate::models::sse::SseEvent;
+use crate::queries;
+use crate::services::chat::ChatService;
+use crate::services::chat::cluster::{self, RemoteCommand};
+use crate::services::chat::registry::AgentCommand;
+use crate::state::AppState;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::time::interval;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Background worker coordinating this instance with the rest of a cluster
+///
+/// Runs the chat commands other instances forward to this one, and takes over
+/// the running chats of instances that stopped heartbeating. On shutdown the
+/// leases of idle chats are released so other instances pick them up at once.
+pub async fn agent_cluster_worker(state: AppState, mut shutdown_rx: broadcast::Receiver<()>) {
+    let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+    tokio::spawn(cluster::run_listener(
+        state.pool.clone(),
+        state.agents.clone(),
+        command_tx,
+        shutdown_rx.resubscribe(),
+    ));
+
+    let takeover_interval_seconds = state.config.ai.cluster.takeover_interval_seconds;
+    let mut takeover_interval = interval(Duration::from_secs(takeover_interval_seconds));
+
+    info!(
+        "[ClusterWorker] Started as node {} (takeover check every {}s)",
+        state.agents.node_id, takeover_interval_seconds
+    );
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => {
+                info!("[ClusterWorker] Shutting down");
+                break;
+            }
+            command = command_rx.recv() => {
+                if let Some((chat_id, command)) = command {
+                    run_remote_command(&state, chat_id, command).await;
+                }
+            }
+            _ = takeover_interval.tick() => {
+                take_over_orphaned_chats(&state).await;
+            }
+        }
+    }
+
+    match state.pool.acquire().await {
+        Ok(mut conn) => match queries::agent_sessions::release_node_leases(&mut conn, state.agents.node_id).await {
+            Ok(count) => info!("[ClusterWorker] Released {} chat leases", count),
+            Err(e) => warn!("[ClusterWorker] Failed to release chat leases: {}", e),
+        },
+        Err(e) => warn!("[ClusterWorker] Failed to acquire connection: {}", e),
+    }
+
+    info!("[ClusterWorker] Stopped");
+}
+
+/// Runs a command forwarded by another instance on the local actor of a chat
+async fn run_remote_command(state: &AppState, chat_id: Uuid, command: RemoteCommand) {
+    match command {
+        RemoteCommand::ProcessInteraction { workspace_id, user_id } => {
+            let handle = match state.agents.get_handle(&chat_id).await {
+                Some(handle) => handle,
+                None => {
+                    // The actor stopped since the command was sent
+                    let persona = match state.pool.acquire().await {
+                        Ok(mut conn) => ChatService::get_chat_persona(&mut conn, chat_id).await,
+                        Err(e) => Err(crate::error::Error::Sqlx(e)),
+                    };
+                    match persona {
+                        Ok(persona) => state.spawn_chat_actor(chat_id, workspace_id, user_id, persona).await,
+                        Err(e) => {
+                            error!("[ClusterWorker] Failed to rehydrate actor for chat {}: {}", chat_id, e);
+                            return;
+                        }
+                    }
+                }
+            };
+            let _ = handle.command_tx.send(AgentCommand::ProcessInteraction { user_id }).await;
+        }
+        RemoteCommand::Pause { reason } => {
+            state.agents.cancel_stream(&chat_id).await;
+            if let Some(handle) = state.agents.get_handle(&chat_id).await {
+                let _ = handle.command_tx.send(AgentCommand::Pause {
+                    reason,
+                    responder: Arc::new(Mutex::new(None)),
+                }).await;
+            }
+        }
+        RemoteCommand::Cancel { reason } => {
+            state.agents.cancel_stream(&chat_id).await;
+            if let Some(handle) = state.agents.get_handle(&chat_id).await {
+                let _ = handle.command_tx.send(AgentCommand::Cancel {
+                    reason,
+                    responder: Arc::new(Mutex::new(None)),
+                }).await;
+                state.agents.remove(&chat_id).await;
+            }
+        }
+        RemoteCommand::AnswerApproval { question_id, approved } => {
+            if !state.agents.approvals.answer(chat_id, question_id, approved).await {
+                warn!("[ClusterWorker] No pending approval {} for chat {}", question_id, chat_id);
+            }
+        }
+    }
+}
+
+/// Claims the running chats of dead instances and resumes them here
+///
+/// A chat whose last message is the user's never got its answer, so the
+/// interaction starts over. Otherwise the interaction stopped between steps;
+/// clients are told it was interrupted and the chat is left idle.
+async fn take_over_orphaned_chats(state: &AppState) {
+    let mut conn = match state.pool.acquire().await {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("[ClusterWorker] Failed to acquire connection: {}", e);
+            return;
+        }
+    };
+
+    let sessions = match queries::agent_sessions::claim_orphaned_sessions(&mut conn, state.agents.node_id).await {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            warn!("[ClusterWorker] Failed to claim orphaned sessions: {}", e);
+            return;
+        }
+    };
+
+    for session in sessions {
+        let messages = match queries::chat::get_messages_by_file_id(&mut conn, session.workspace_id, session.chat_id).await {
+            Ok(messages) => messages,
+            Err(e) => {
+                warn!("[ClusterWorker] Failed to load messages of chat {}: {}", session.chat_id, e);
+                continue;
+            }
+        };
+        let awaiting_answer = messages.last().is_some_and(|message| message.role == ChatMessageRole::User);
+
+        if awaiting_answer {
+            info!("[ClusterWorker] Resuming chat {} taken over from a dead instance", session.chat_id);
+            let persona = match ChatService::get_chat_persona(&mut conn, session.chat_id).await {
+                Ok(persona) => persona,
+                Err(e) => {
+                    warn!("[ClusterWorker] Failed to resolve persona of chat {}: {}", session.chat_id, e);
+                    continue;
+                }
+            };
+            let handle = state
+                .spawn_chat_actor(session.chat_id, session.workspace_id, session.user_id, persona)
+                .await;
+            let _ = handle.command_tx.send(AgentCommand::ProcessInteraction {
+                user_id: session.user_id,
+            }).await;
+        } else {
+            info!("[ClusterWorker] Chat {} was interrupted by a dead instance", session.chat_id);
+            if let Err(e) = queries::agent_sessions::update_session_status(&mut conn, session.id, SessionStatus::Idle, None).await {
+                warn!("[ClusterWorker] Failed to reset session {}: {}", session.id, e);
+            }
+            let _ = queries::agent_sessions::release_chat_lease(&mut conn, session.id, state.agents.node_id).await;
+            state.agents.send_event(session.chat_id, SseEvent::Stopped {
+                reason: "interrupted".to_string(),
+                partial_response: None,
+            }).await;
+        }
+    }
+}
@@ -1,5 +1,9 @@
 pub mod revoked_token_cleanup;
 pub mod archive_cleanup;
+pub mod agent_cluster;
+pub mod stream_event_cleanup;
 
 pub use revoked_token_cleanup::revoked_token_cleanup_worker;
 pub use archive_cleanup::archive_cleanup_worker;
+pub use agent_cluster::agent_cluster_worker;
+pub use stream_event_cleanup::stream_event_cleanup_worker;
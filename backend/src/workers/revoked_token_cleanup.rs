@@ -1,4 +1,4 @@
-use crate::queries::sessions;
+use crate::queries::{oidc, sessions, two_factor, user_tokens};
 use crate::Config;
 use std::time::Duration;
 use tokio::time::interval;
@@ -8,6 +8,9 @@ use tracing::{info, warn, error};
 ///
 /// Runs every 5 minutes to remove revoked tokens based on retention period
 /// This keeps the revoked_refresh_tokens table size manageable
+///
+/// Also removes used or expired password reset and email verification tokens,
+/// and abandoned single sign-on and two-factor login attempts
 pub async fn revoked_token_cleanup_worker(
     pool: sqlx::PgPool,
     mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
@@ -52,6 +55,39 @@ pub async fn revoked_token_cleanup_worker(
                         warn!("[AuthWorker] Failed to cleanup expired revoked tokens: {}", e);
                     }
                 }
+
+                match user_tokens::delete_stale_user_tokens(&mut conn).await {
+                    Ok(count) => {
+                        if count > 0 {
+                            info!("[AuthWorker] Cleaned up {} used or expired user tokens", count);
+                        }
+                    }
+                    Err(e) => {
+                        warn!("[AuthWorker] Failed to cleanup user tokens: {}", e);
+                    }
+                }
+
+                match oidc::delete_expired_login_states(&mut conn).await {
+                    Ok(count) => {
+                        if count > 0 {
+                            info!("[AuthWorker] Cleaned up {} expired SSO login states", count);
+                        }
+                    }
+                    Err(e) => {
+                        warn!("[AuthWorker] Failed to cleanup SSO login states: {}", e);
+                    }
+                }
+
+                match two_factor::delete_expired_challenges(&mut conn).await {
+                    Ok(count) => {
+                        if count > 0 {
+                            info!("[AuthWorker] Cleaned up {} expired two-factor challenges", count);
+                        }
+                    }
+                    Err(e) => {
+                        warn!("[AuthWorker] Failed to cleanup two-factor challenges: {}", e);
+                    }
+                }
             }
         }
     }
@@ -0,0 +1,50 @@
+use crate::queries::chat;
+use chrono::Utc;
+use std::time::Duration;
+use tokio::time::interval;
+use tracing::{info, warn, error};
+
+/// Background worker that periodically deletes old chat stream events
+///
+/// Runs every hour. Events are only needed to resume interrupted SSE streams,
+/// so they are kept for `retention_hours`
+pub async fn stream_event_cleanup_worker(
+    pool: sqlx::PgPool,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+    retention_hours: i64,
+) {
+    let mut cleanup_interval = interval(Duration::from_secs(3600)); // Every hour
+    info!("[StreamEventWorker] Started (runs every hour, keeps {}h of events)", retention_hours);
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => {
+                info!("[StreamEventWorker] Shutting down");
+                break;
+            }
+            _ = cleanup_interval.tick() => {
+                let mut conn = match pool.acquire().await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        error!("[StreamEventWorker] Failed to acquire database connection for cleanup: {}", e);
+                        continue;
+                    }
+                };
+
+                let cutoff = Utc::now() - chrono::Duration::hours(retention_hours);
+                match chat::delete_stream_events_before(&mut conn, cutoff).await {
+                    Ok(count) => {
+                        if count > 0 {
+                            info!("[StreamEventWorker] Cleaned up {} old chat stream events", count);
+                        }
+                    }
+                    Err(e) => {
+                        warn!("[StreamEventWorker] Failed to cleanup chat stream events: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    info!("[StreamEventWorker] Stopped");
+}
@@ -0,0 +1,159 @@
+use buildscale::{
+    models::agent_session::{AgentType, NewAgentSession, SessionStatus},
+    queries::agent_sessions::{
+        acquire_chat_lease, claim_orphaned_sessions, get_chat_lease_owner, release_chat_lease,
+        release_node_leases, renew_chat_lease, update_session_status,
+    },
+};
+use crate::common::database::TestDb;
+use uuid::Uuid;
+
+/// Creates a user, workspace and chat file, returning the session to lease
+async fn setup_chat(conn: &mut sqlx::PgConnection, prefix: &str) -> NewAgentSession {
+    let user_id = Uuid::now_v7();
+    let workspace_id = Uuid::now_v7();
+    let chat_id = Uuid::now_v7();
+
+    sqlx::query(
+        r#"
+        INSERT INTO users (id, email, password_hash, created_at, updated_at)
+        VALUES ($1, $2, $3, NOW(), NOW())
+        "#,
+    )
+    .bind(user_id)
+    .bind(format!("{}_{}@example.com", prefix, user_id))
+    .bind("hash")
+    .execute(&mut *conn)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"
+        INSERT INTO workspaces (id, name, owner_id, created_at, updated_at)
+        VALUES ($1, $2, $3, NOW(), NOW())
+        "#,
+    )
+    .bind(workspace_id)
+    .bind("Test Workspace")
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await
+    .unwrap();
+
+    let file_name = format!("test_chat_{}.md", chat_id);
+    sqlx::query(
+        r#"
+        INSERT INTO files (id, workspace_id, name, slug, path, file_type, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, NOW(), NOW())
+        "#,
+    )
+    .bind(chat_id)
+    .bind(workspace_id)
+    .bind(&file_name)
+    .bind(&file_name)
+    .bind(format!("/{}", file_name))
+    .bind("chat")
+    .execute(&mut *conn)
+    .await
+    .unwrap();
+
+    NewAgentSession {
+        workspace_id,
+        chat_id,
+        user_id,
+        agent_type: AgentType::Assistant,
+        model: "gpt-4o".to_string(),
+        mode: "chat".to_string(),
+    }
+}
+
+/// Makes the lease of a session look abandoned
+async fn expire_heartbeat(conn: &mut sqlx::PgConnection, session_id: Uuid) {
+    sqlx::query("UPDATE agent_sessions SET last_heartbeat = NOW() - INTERVAL '10 minutes' WHERE id = $1")
+        .bind(session_id)
+        .execute(conn)
+        .await
+        .unwrap();
+}
+
+/// A live lease keeps other instances out until it is released
+#[tokio::test]
+async fn test_chat_lease_is_exclusive() {
+    let test_db = TestDb::new("test_chat_lease_is_exclusive").await;
+    let mut conn = test_db.get_connection().await;
+    let new_session = setup_chat(conn.as_mut(), "test_chat_lease_exclusive").await;
+    let (node_a, node_b) = (Uuid::now_v7(), Uuid::now_v7());
+
+    let session = acquire_chat_lease(&mut conn, new_session.clone(), node_a)
+        .await
+        .unwrap()
+        .expect("Free chat should be leased");
+    assert_eq!(session.status, SessionStatus::Idle);
+    assert_eq!(get_chat_lease_owner(&mut conn, new_session.chat_id).await.unwrap(), Some(node_a));
+
+    // The holder may acquire again, e.g. after an actor restart
+    let again = acquire_chat_lease(&mut conn, new_session.clone(), node_a).await.unwrap();
+    assert_eq!(again.map(|s| s.id), Some(session.id));
+
+    assert!(acquire_chat_lease(&mut conn, new_session.clone(), node_b).await.unwrap().is_none());
+    assert!(!renew_chat_lease(&mut conn, session.id, node_b).await.unwrap());
+    assert!(renew_chat_lease(&mut conn, session.id, node_a).await.unwrap());
+
+    release_chat_lease(&mut conn, session.id, node_a).await.unwrap();
+    assert_eq!(get_chat_lease_owner(&mut conn, new_session.chat_id).await.unwrap(), None);
+
+    let taken = acquire_chat_lease(&mut conn, new_session.clone(), node_b)
+        .await
+        .unwrap()
+        .expect("Released chat should be leased");
+    assert_eq!(taken.id, session.id);
+    assert!(!renew_chat_lease(&mut conn, session.id, node_a).await.unwrap());
+}
+
+/// Running chats of instances that stopped heartbeating are claimed exactly once
+#[tokio::test]
+async fn test_claim_orphaned_sessions() {
+    let test_db = TestDb::new("test_claim_orphaned_sessions").await;
+    let mut conn = test_db.get_connection().await;
+    let new_session = setup_chat(conn.as_mut(), "test_claim_orphaned").await;
+    let (dead_node, node_b, node_c) = (Uuid::now_v7(), Uuid::now_v7(), Uuid::now_v7());
+
+    let session = acquire_chat_lease(&mut conn, new_session.clone(), dead_node)
+        .await
+        .unwrap()
+        .unwrap();
+    update_session_status(&mut conn, session.id, SessionStatus::Running, None).await.unwrap();
+
+    // Live leases are left alone
+    let claimed = claim_orphaned_sessions(&mut conn, node_b).await.unwrap();
+    assert!(!claimed.iter().any(|s| s.id == session.id));
+
+    expire_heartbeat(conn.as_mut(), session.id).await;
+    assert_eq!(get_chat_lease_owner(&mut conn, new_session.chat_id).await.unwrap(), None);
+
+    let claimed = claim_orphaned_sessions(&mut conn, node_b).await.unwrap();
+    assert!(claimed.iter().any(|s| s.id == session.id));
+    assert_eq!(get_chat_lease_owner(&mut conn, new_session.chat_id).await.unwrap(), Some(node_b));
+
+    let claimed = claim_orphaned_sessions(&mut conn, node_c).await.unwrap();
+    assert!(!claimed.iter().any(|s| s.id == session.id));
+    assert!(!renew_chat_lease(&mut conn, session.id, dead_node).await.unwrap());
+}
+
+/// A stopping instance frees its idle chats and keeps its running ones for takeover
+#[tokio::test]
+async fn test_release_node_leases_keeps_running_chats() {
+    let test_db = TestDb::new("test_release_node_leases_keeps_running_chats").await;
+    let mut conn = test_db.get_connection().await;
+    let idle_chat = setup_chat(conn.as_mut(), "test_release_node_idle").await;
+    let running_chat = setup_chat(conn.as_mut(), "test_release_node_running").await;
+    let node = Uuid::now_v7();
+
+    acquire_chat_lease(&mut conn, idle_chat.clone(), node).await.unwrap().unwrap();
+    let running = acquire_chat_lease(&mut conn, running_chat.clone(), node).await.unwrap().unwrap();
+    update_session_status(&mut conn, running.id, SessionStatus::Running, None).await.unwrap();
+
+    assert_eq!(release_node_leases(&mut conn, node).await.unwrap(), 1);
+    assert_eq!(get_chat_lease_owner(&mut conn, idle_chat.chat_id).await.unwrap(), None);
+    assert_eq!(get_chat_lease_owner(&mut conn, running_chat.chat_id).await.unwrap(), Some(node));
+}
@@ -1,3 +1,4 @@
+pub mod chat_leases;
 pub mod create_agent_session;
 pub mod get_agent_session;
 pub mod update_agent_session;
@@ -0,0 +1,193 @@
+use buildscale::{
+    error::Error,
+    models::{
+        api_keys::{ApiKeyScope, CreateApiKeyRequest},
+        permissions::workspace_permissions,
+        requests::CreateWorkspaceRequest,
+        workspaces::Workspace,
+    },
+    services::{
+        api_keys::{authenticate_api_key, authorize_api_key, create_api_key, list_api_keys, revoke_api_key},
+        users::register_user,
+        workspaces::create_workspace,
+    },
+};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use crate::common::database::TestApp;
+
+async fn create_owned_workspace(test_app: &TestApp) -> (Uuid, Workspace) {
+    let mut conn = test_app.get_connection().await;
+    let user = register_user(&mut conn, test_app.generate_test_user()).await.unwrap();
+    let result = create_workspace(&mut conn, CreateWorkspaceRequest {
+        name: format!("{}_workspace", test_app.test_prefix()),
+        owner_id: user.id,
+    }).await.unwrap();
+    (user.id, result.workspace)
+}
+
+fn key_request(permissions: &[&str]) -> CreateApiKeyRequest {
+    CreateApiKeyRequest {
+        name: "ci".to_string(),
+        permissions: permissions.iter().map(|p| p.to_string()).collect(),
+        expires_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_api_key_create_and_authenticate() {
+    let test_app = TestApp::new("test_api_key_create_and_authenticate").await;
+    let mut conn = test_app.get_connection().await;
+    let (user_id, workspace) = create_owned_workspace(&test_app).await;
+
+    let created = create_api_key(&mut conn, workspace.id, user_id, key_request(&[
+        workspace_permissions::READ,
+        workspace_permissions::READ,
+    ])).await.unwrap();
+
+    assert!(created.key.starts_with(&created.api_key.key_prefix));
+    assert_ne!(created.api_key.key_hash, created.key, "Only the hash is stored");
+    assert_eq!(created.api_key.permissions, vec![workspace_permissions::READ.to_string()], "Duplicates are dropped");
+    assert!(created.api_key.last_used_at.is_none());
+
+    let authenticated = authenticate_api_key(&mut conn, &created.key).await.unwrap();
+    assert_eq!(authenticated.id, created.api_key.id);
+    assert_eq!(authenticated.user_id, user_id);
+
+    let keys = list_api_keys(&mut conn, workspace.id, user_id).await.unwrap();
+    assert_eq!(keys.len(), 1);
+    assert!(keys[0].last_used_at.is_some(), "Authentication records last use");
+
+    // Serialized keys never expose the hash
+    let json = serde_json::to_value(&keys[0]).unwrap();
+    assert!(json.get("key_hash").is_none());
+}
+
+#[tokio::test]
+async fn test_api_key_rejects_unknown_and_malformed_keys() {
+    let test_app = TestApp::new("test_api_key_rejects_unknown_and_malformed_keys").await;
+    let mut conn = test_app.get_connection().await;
+
+    let unknown = format!("bsk_deadbeef_{}", "0".repeat(64));
+    assert!(matches!(authenticate_api_key(&mut conn, &unknown).await, Err(Error::InvalidToken(_))));
+    assert!(matches!(authenticate_api_key(&mut conn, "bsk_nope").await, Err(Error::InvalidToken(_))));
+}
+
+#[tokio::test]
+async fn test_api_key_revocation() {
+    let test_app = TestApp::new("test_api_key_revocation").await;
+    let mut conn = test_app.get_connection().await;
+    let (user_id, workspace) = create_owned_workspace(&test_app).await;
+
+    let created = create_api_key(&mut conn, workspace.id, user_id, key_request(&[workspace_permissions::READ]))
+        .await
+        .unwrap();
+
+    let revoked = revoke_api_key(&mut conn, workspace.id, created.api_key.id, user_id).await.unwrap();
+    assert!(revoked.revoked_at.is_some());
+
+    assert!(matches!(authenticate_api_key(&mut conn, &created.key).await, Err(Error::InvalidToken(_))));
+    assert!(matches!(
+        revoke_api_key(&mut conn, workspace.id, created.api_key.id, user_id).await,
+        Err(Error::Conflict(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_api_key_expiry() {
+    let test_app = TestApp::new("test_api_key_expiry").await;
+    let mut conn = test_app.get_connection().await;
+    let (user_id, workspace) = create_owned_workspace(&test_app).await;
+
+    let mut request = key_request(&[workspace_permissions::READ]);
+    request.expires_at = Some(Utc::now() - Duration::minutes(1));
+    assert!(matches!(
+        create_api_key(&mut conn, workspace.id, user_id, request).await,
+        Err(Error::Validation(_))
+    ));
+
+    let mut request = key_request(&[workspace_permissions::READ]);
+    request.expires_at = Some(Utc::now() + Duration::days(1));
+    let created = create_api_key(&mut conn, workspace.id, user_id, request).await.unwrap();
+    authenticate_api_key(&mut conn, &created.key).await.unwrap();
+
+    sqlx::query("UPDATE api_keys SET expires_at = NOW() - INTERVAL '1 minute' WHERE id = $1")
+        .bind(created.api_key.id)
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+    assert!(matches!(authenticate_api_key(&mut conn, &created.key).await, Err(Error::InvalidToken(_))));
+}
+
+#[tokio::test]
+async fn test_api_key_permission_validation() {
+    let test_app = TestApp::new("test_api_key_permission_validation").await;
+    let mut conn = test_app.get_connection().await;
+    let (user_id, workspace) = create_owned_workspace(&test_app).await;
+
+    assert!(matches!(
+        create_api_key(&mut conn, workspace.id, user_id, key_request(&[])).await,
+        Err(Error::Validation(_))
+    ));
+    assert!(matches!(
+        create_api_key(&mut conn, workspace.id, user_id, key_request(&["workspace:fly"])).await,
+        Err(Error::Validation(_))
+    ));
+
+    // A non-member holds no permissions in the workspace and cannot create keys for it
+    let outsider = register_user(&mut conn, test_app.generate_test_user()).await.unwrap();
+    assert!(matches!(
+        create_api_key(&mut conn, workspace.id, outsider.id, key_request(&[workspace_permissions::READ])).await,
+        Err(Error::Forbidden(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_api_key_scope_authorization() {
+    let test_app = TestApp::new("test_api_key_scope_authorization").await;
+    let mut conn = test_app.get_connection().await;
+    let (user_id, workspace) = create_owned_workspace(&test_app).await;
+
+    let created = create_api_key(&mut conn, workspace.id, user_id, key_request(&[workspace_permissions::READ]))
+        .await
+        .unwrap();
+    let scope = ApiKeyScope::from(&created.api_key);
+
+    authorize_api_key(&mut conn, &scope, workspace.id, user_id, workspace_permissions::READ)
+        .await
+        .unwrap();
+
+    // Permission outside the key's subset
+    assert!(matches!(
+        authorize_api_key(&mut conn, &scope, workspace.id, user_id, workspace_permissions::WRITE).await,
+        Err(Error::Forbidden(_))
+    ));
+
+    // Different workspace
+    assert!(matches!(
+        authorize_api_key(&mut conn, &scope, Uuid::now_v7(), user_id, workspace_permissions::READ).await,
+        Err(Error::Forbidden(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_api_key_cannot_be_revoked_by_other_member_without_permission() {
+    let test_app = TestApp::new("test_api_key_cannot_be_revoked_by_other_member_without_permission").await;
+    let mut conn = test_app.get_connection().await;
+    let (user_id, workspace) = create_owned_workspace(&test_app).await;
+
+    let created = create_api_key(&mut conn, workspace.id, user_id, key_request(&[workspace_permissions::READ]))
+        .await
+        .unwrap();
+
+    let outsider = register_user(&mut conn, test_app.generate_test_user()).await.unwrap();
+    assert!(matches!(
+        revoke_api_key(&mut conn, workspace.id, created.api_key.id, outsider.id).await,
+        Err(Error::Forbidden(_))
+    ));
+    assert!(matches!(
+        revoke_api_key(&mut conn, Uuid::now_v7(), created.api_key.id, user_id).await,
+        Err(Error::NotFound(_))
+    ));
+}
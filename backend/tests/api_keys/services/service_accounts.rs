@@ -0,0 +1,146 @@
+use buildscale::{
+    error::Error,
+    models::{
+        api_keys::{CreateApiKeyRequest, CreateServiceAccountRequest},
+        permissions::workspace_permissions,
+        requests::CreateWorkspaceRequest,
+        roles::VIEWER_ROLE,
+        workspaces::Workspace,
+    },
+    services::{
+        api_keys::{
+            authenticate_api_key, create_service_account, create_service_account_api_key,
+            delete_service_account, list_service_account_api_keys, list_service_accounts,
+        },
+        users::{register_user, request_password_reset},
+        workspaces::create_workspace,
+    },
+};
+use uuid::Uuid;
+use crate::common::database::TestApp;
+
+async fn create_owned_workspace(test_app: &TestApp) -> (Uuid, Workspace) {
+    let mut conn = test_app.get_connection().await;
+    let user = register_user(&mut conn, test_app.generate_test_user()).await.unwrap();
+    let result = create_workspace(&mut conn, CreateWorkspaceRequest {
+        name: format!("{}_workspace", test_app.test_prefix()),
+        owner_id: user.id,
+    }).await.unwrap();
+    (user.id, result.workspace)
+}
+
+fn service_account_request(name: &str, role_name: &str) -> CreateServiceAccountRequest {
+    CreateServiceAccountRequest {
+        name: name.to_string(),
+        description: Some("CI pipeline".to_string()),
+        role_name: role_name.to_string(),
+    }
+}
+
+fn key_request(permissions: &[&str]) -> CreateApiKeyRequest {
+    CreateApiKeyRequest {
+        name: "deploy".to_string(),
+        permissions: permissions.iter().map(|p| p.to_string()).collect(),
+        expires_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_service_account_lifecycle() {
+    let test_app = TestApp::new("test_service_account_lifecycle").await;
+    let mut conn = test_app.get_connection().await;
+    let (owner_id, workspace) = create_owned_workspace(&test_app).await;
+
+    let account = create_service_account(&mut conn, workspace.id, owner_id, service_account_request("github-actions", VIEWER_ROLE))
+        .await
+        .unwrap();
+    assert_eq!(account.role_name, VIEWER_ROLE);
+    assert!(test_app.is_workspace_member(workspace.id, account.user_id).await.unwrap());
+
+    let accounts = list_service_accounts(&mut conn, workspace.id, owner_id).await.unwrap();
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].name, "github-actions");
+
+    let created = create_service_account_api_key(&mut conn, workspace.id, account.id, owner_id, key_request(&[workspace_permissions::READ]))
+        .await
+        .unwrap();
+    assert_eq!(created.api_key.user_id, account.user_id);
+    assert_eq!(created.api_key.created_by, Some(owner_id));
+    assert_eq!(authenticate_api_key(&mut conn, &created.key).await.unwrap().user_id, account.user_id);
+
+    let keys = list_service_account_api_keys(&mut conn, workspace.id, account.id, owner_id).await.unwrap();
+    assert_eq!(keys.len(), 1);
+
+    // Deleting the account removes its membership and invalidates its keys
+    delete_service_account(&mut conn, workspace.id, account.id, owner_id).await.unwrap();
+    assert!(!test_app.is_workspace_member(workspace.id, account.user_id).await.unwrap());
+    assert!(matches!(authenticate_api_key(&mut conn, &created.key).await, Err(Error::InvalidToken(_))));
+    assert!(list_service_accounts(&mut conn, workspace.id, owner_id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_service_account_key_limited_to_role_permissions() {
+    let test_app = TestApp::new("test_service_account_key_limited_to_role_permissions").await;
+    let mut conn = test_app.get_connection().await;
+    let (owner_id, workspace) = create_owned_workspace(&test_app).await;
+
+    let account = create_service_account(&mut conn, workspace.id, owner_id, service_account_request("reader", VIEWER_ROLE))
+        .await
+        .unwrap();
+
+    // Viewers cannot write, so neither can their keys
+    let result = create_service_account_api_key(
+        &mut conn,
+        workspace.id,
+        account.id,
+        owner_id,
+        key_request(&[workspace_permissions::READ, workspace_permissions::WRITE]),
+    ).await;
+    assert!(matches!(result, Err(Error::Forbidden(_))));
+}
+
+#[tokio::test]
+async fn test_service_account_requires_manage_members() {
+    let test_app = TestApp::new("test_service_account_requires_manage_members").await;
+    let mut conn = test_app.get_connection().await;
+    let (_owner_id, workspace) = create_owned_workspace(&test_app).await;
+
+    let outsider = register_user(&mut conn, test_app.generate_test_user()).await.unwrap();
+    let result = create_service_account(&mut conn, workspace.id, outsider.id, service_account_request("bot", VIEWER_ROLE)).await;
+    assert!(matches!(result, Err(Error::Forbidden(_))));
+    assert!(matches!(list_service_accounts(&mut conn, workspace.id, outsider.id).await, Err(Error::Forbidden(_))));
+}
+
+#[tokio::test]
+async fn test_service_account_name_must_be_unique() {
+    let test_app = TestApp::new("test_service_account_name_must_be_unique").await;
+    let mut conn = test_app.get_connection().await;
+    let (owner_id, workspace) = create_owned_workspace(&test_app).await;
+
+    create_service_account(&mut conn, workspace.id, owner_id, service_account_request("bot", VIEWER_ROLE))
+        .await
+        .unwrap();
+    let duplicate = create_service_account(&mut conn, workspace.id, owner_id, service_account_request("bot", VIEWER_ROLE)).await;
+    assert!(matches!(duplicate, Err(Error::Conflict(_))));
+
+    let unknown_role = create_service_account(&mut conn, workspace.id, owner_id, service_account_request("other", "superuser")).await;
+    assert!(matches!(unknown_role, Err(Error::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_service_account_cannot_request_password_reset() {
+    let test_app = TestApp::new("test_service_account_cannot_request_password_reset").await;
+    let mut conn = test_app.get_connection().await;
+    let (owner_id, workspace) = create_owned_workspace(&test_app).await;
+
+    let account = create_service_account(&mut conn, workspace.id, owner_id, service_account_request("bot", VIEWER_ROLE))
+        .await
+        .unwrap();
+    let email: String = sqlx::query_scalar("SELECT email FROM users WHERE id = $1")
+        .bind(account.user_id)
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+
+    assert!(request_password_reset(&mut conn, &email).await.unwrap().is_none());
+}
@@ -0,0 +1,154 @@
+//! Tests for user-defined agent files
+//!
+//! These tests verify that:
+//! - `.agent` files are validated whenever they are written
+//! - Chats created with an agent file take its model, temperature and persona
+//! - Only agent files can be selected as a chat's agent
+
+use crate::common::{TestApp, TestAppOptions, register_and_login, create_workspace};
+use crate::chat::get_chat;
+use crate::tools::common::execute_tool;
+
+const REVIEWER_AGENT: &str = "---
+name: reviewer
+description: Reviews changes for security issues
+model: openai:gpt-4o
+temperature: 0.2
+tools: [read, grep, memory_get]
+memory_scope: user
+---
+You are Sentinel, a meticulous security reviewer. Never modify files.";
+
+/// Helper to write an agent file, returning the raw tool response
+async fn write_agent(app: &TestApp, workspace_id: &str, token: &str, content: &str, overwrite: bool) -> reqwest::Response {
+    execute_tool(
+        app,
+        workspace_id,
+        token,
+        "write",
+        serde_json::json!({
+            "path": "/agents/reviewer.agent",
+            "content": content,
+            "overwrite": overwrite
+        }),
+    )
+    .await
+}
+
+/// Helper to create a chat with the given agent, returning the raw response
+async fn create_agent_chat(app: &TestApp, workspace_id: &str, token: &str, agent_id: &str) -> reqwest::Response {
+    app.client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/chats", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({
+            "goal": "Review the login handler",
+            "agent_id": agent_id
+        }))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_agent_file_is_validated_on_write() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Agent Validation Test").await;
+
+    let response = write_agent(&app, &workspace_id, &token, "---\ntemperature: 7\n---\nToo hot.", false).await;
+    assert_eq!(response.status(), 400);
+
+    let response = write_agent(&app, &workspace_id, &token, "---\ntools: [teleport]\n---\nPrompt", false).await;
+    assert_eq!(response.status(), 400);
+
+    let response = write_agent(&app, &workspace_id, &token, REVIEWER_AGENT, false).await;
+    assert_eq!(response.status(), 200);
+
+    // The .agent extension gives the file the agent type
+    let response = execute_tool(
+        &app,
+        &workspace_id,
+        &token,
+        "file_info",
+        serde_json::json!({"path": "/agents/reviewer.agent"}),
+    )
+    .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["result"]["file_type"], "agent");
+
+    // New versions are validated too, and a rejected write keeps the old version
+    let response = write_agent(&app, &workspace_id, &token, "---\nname: reviewer\n---\n", true).await;
+    assert_eq!(response.status(), 400);
+
+    let response = execute_tool(
+        &app,
+        &workspace_id,
+        &token,
+        "read",
+        serde_json::json!({"path": "/agents/reviewer.agent"}),
+    )
+    .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["result"]["content"].as_str().unwrap().contains("Sentinel"));
+}
+
+#[tokio::test]
+async fn test_chat_created_with_agent_file() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Agent Chat Test").await;
+
+    let response = write_agent(&app, &workspace_id, &token, REVIEWER_AGENT, false).await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    let agent_id = body["result"]["file_id"].as_str().unwrap().to_string();
+
+    let response = create_agent_chat(&app, &workspace_id, &token, &agent_id).await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let chat_id = body["chat_id"].as_str().unwrap().to_string();
+
+    // Model and temperature default to the agent file's values
+    let chat = get_chat(&app, &workspace_id, &chat_id, &token).await;
+    assert_eq!(chat["agent_config"]["agent_id"], agent_id.as_str());
+    assert_eq!(chat["agent_config"]["model"], "openai:gpt-4o");
+    assert!((chat["agent_config"]["temperature"].as_f64().unwrap() - 0.2).abs() < 1e-6);
+
+    let first_message = &chat["messages"][0];
+    let attachments = first_message["metadata"]["attachments"].as_array().unwrap();
+    assert!(attachments.iter().any(|a| a["type"] == "agent" && a["name"] == "reviewer"));
+
+    // The agent's system prompt replaces the built-in persona
+    let response = app
+        .client
+        .get(&app.url(&format!("/api/v1/workspaces/{}/chats/{}/context", workspace_id, chat_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let context: serde_json::Value = response.json().await.unwrap();
+    let system_prompt = context["system_prompt"]["content"].as_str().unwrap();
+    assert!(system_prompt.starts_with("You are Sentinel"));
+    assert!(!system_prompt.contains("Planner"));
+}
+
+#[tokio::test]
+async fn test_chat_rejects_non_agent_file() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Agent Reject Test").await;
+
+    let response = execute_tool(
+        &app,
+        &workspace_id,
+        &token,
+        "write",
+        serde_json::json!({"path": "/notes.md", "content": "You are a pirate."}),
+    )
+    .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    let document_id = body["result"]["file_id"].as_str().unwrap().to_string();
+
+    let response = create_agent_chat(&app, &workspace_id, &token, &document_id).await;
+    assert_eq!(response.status(), 404);
+}
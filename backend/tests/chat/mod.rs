@@ -2,6 +2,7 @@
 //!
 //! Tests for chat model management and persistence.
 
+pub mod agent_file_tests;
 pub mod model_update_tests;
 pub mod yaml_sync_tests;
 
@@ -15,6 +15,8 @@ fn test_chat_frontmatter_from_agent_config() {
         previous_response_id: None,
         mode: "plan".to_string(),
         plan_file: None,
+        parent_chat_id: None,
+        allowed_tools: None,
     };
 
     let frontmatter = ChatFrontmatter::from_agent_config(&config);
@@ -32,6 +34,8 @@ fn test_chat_frontmatter_with_plan_file() {
         previous_response_id: None,
         mode: "build".to_string(),
         plan_file: Some("/plans/my-plan.plan".to_string()),
+        parent_chat_id: None,
+        allowed_tools: None,
     };
 
     let frontmatter = ChatFrontmatter::from_agent_config(&config);
@@ -101,6 +105,8 @@ fn test_chat_frontmatter_merge_into_agent_config() {
         previous_response_id: Some("response-123".to_string()),
         mode: "plan".to_string(), // This should be overridden
         plan_file: None,          // This should be overridden
+        parent_chat_id: None,
+        allowed_tools: None,
     };
 
     let merged = frontmatter.merge_into_agent_config(config);
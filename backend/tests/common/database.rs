@@ -108,6 +108,13 @@ impl TestDb {
             .execute(pool)
             .await;
 
+        // Service account users do not share the test email prefix
+        let cleanup_service_accounts = "DELETE FROM users WHERE id IN (SELECT user_id FROM service_accounts WHERE workspace_id IN (SELECT id FROM workspaces WHERE name LIKE $1))";
+        let _ = sqlx::query(cleanup_service_accounts)
+            .bind(&pattern)
+            .execute(pool)
+            .await;
+
         let cleanup_members = "DELETE FROM workspace_members WHERE user_id IN (SELECT id FROM users WHERE email LIKE $1) OR workspace_id IN (SELECT id FROM workspaces WHERE name LIKE $1)";
         let _ = sqlx::query(cleanup_members)
             .bind(&pattern)
@@ -189,6 +196,13 @@ impl Drop for TestDb {
                 .execute(&pool)
                 .await;
 
+            // Service account users do not share the test email prefix
+            let cleanup_service_accounts = "DELETE FROM users WHERE id IN (SELECT user_id FROM service_accounts WHERE workspace_id IN (SELECT id FROM workspaces WHERE name LIKE $1))";
+            let _ = sqlx::query(cleanup_service_accounts)
+                .bind(&pattern)
+                .execute(&pool)
+                .await;
+
             let cleanup_members = "DELETE FROM workspace_members WHERE user_id IN (SELECT id FROM users WHERE email LIKE $1) OR workspace_id IN (SELECT id FROM workspaces WHERE name LIKE $1)";
             let _ = sqlx::query(cleanup_members)
                 .bind(&pattern)
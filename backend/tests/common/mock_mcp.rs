@@ -0,0 +1,158 @@
+//! In-process mock MCP server for tool server tests
+//!
+//! Speaks the streamable HTTP transport on a random port. It offers a read-only
+//! `search_docs` tool, answered as an event stream, and a mutating `create_issue`
+//! tool, answered as plain JSON. Every request must carry [`MOCK_MCP_TOKEN`].
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+
+pub const MOCK_MCP_TOKEN: &str = "Bearer mock-mcp-token";
+const SESSION_ID: &str = "mock-mcp-session";
+
+#[derive(Clone, Default)]
+struct MockState {
+    calls: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+pub struct MockMcpServer {
+    pub url: String,
+    calls: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+impl MockMcpServer {
+    /// Starts the server on a random port
+    ///
+    /// Requests only reach public addresses, so 127.0.0.1 is allowed for the
+    /// tests' servers.
+    pub async fn start() -> Self {
+        buildscale::utils::net::allow_private_hosts(["127.0.0.1"]);
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind random port");
+        let url = format!("http://127.0.0.1:{}/mcp", listener.local_addr().unwrap().port());
+        let state = MockState::default();
+        let calls = state.calls.clone();
+
+        let app = Router::new().route("/mcp", post(handle)).with_state(state);
+
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        Self { url, calls }
+    }
+
+    /// `(tool, arguments)` of every `tools/call` received so far
+    pub fn calls(&self) -> Vec<(String, Value)> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+async fn handle(State(state): State<MockState>, headers: HeaderMap, Json(message): Json<Value>) -> Response {
+    if headers.get("authorization").and_then(|v| v.to_str().ok()) != Some(MOCK_MCP_TOKEN) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let method = message["method"].as_str().unwrap_or_default();
+    let Some(id) = message.get("id").cloned() else {
+        // Notifications are accepted without a body
+        return StatusCode::ACCEPTED.into_response();
+    };
+
+    if method != "initialize" && headers.get("mcp-session-id").and_then(|v| v.to_str().ok()) != Some(SESSION_ID) {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+
+    match method {
+        "initialize" => (
+            [("mcp-session-id", SESSION_ID)],
+            Json(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": "mock", "version": "1.0.0" },
+                },
+            })),
+        )
+            .into_response(),
+        "tools/list" => Json(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": {
+                "tools": [
+                    {
+                        "name": "search_docs",
+                        "description": "Search the documentation",
+                        "inputSchema": {
+                            "type": "object",
+                            "properties": { "query": { "type": "string" } },
+                            "required": ["query"],
+                        },
+                        "annotations": { "readOnlyHint": true },
+                    },
+                    {
+                        "name": "create_issue",
+                        "description": "Create an issue",
+                        "inputSchema": {
+                            "type": "object",
+                            "properties": { "title": { "type": "string" } },
+                        },
+                    },
+                ],
+            },
+        }))
+        .into_response(),
+        "tools/call" => {
+            let name = message["params"]["name"].as_str().unwrap_or_default().to_string();
+            let arguments = message["params"]["arguments"].clone();
+            state.calls.lock().unwrap().push((name.clone(), arguments.clone()));
+
+            match name.as_str() {
+                "search_docs" => {
+                    let response = json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "result": {
+                            "content": [{ "type": "text", "text": format!("Found 1 page for '{}'", arguments["query"].as_str().unwrap_or_default()) }],
+                            "structuredContent": { "pages": ["/docs/getting-started"] },
+                        },
+                    });
+                    let progress = json!({
+                        "jsonrpc": "2.0",
+                        "method": "notifications/progress",
+                        "params": { "progress": 1 },
+                    });
+                    (
+                        [("content-type", "text/event-stream")],
+                        format!("event: message\ndata: {}\n\nevent: message\ndata: {}\n\n", progress, response),
+                    )
+                        .into_response()
+                }
+                _ => {
+                    let result = match arguments["title"].as_str() {
+                        Some(title) => json!({ "content": [{ "type": "text", "text": format!("Created issue '{}'", title) }] }),
+                        None => json!({ "content": [{ "type": "text", "text": "title is required" }], "isError": true }),
+                    };
+                    Json(json!({ "jsonrpc": "2.0", "id": id, "result": result })).into_response()
+                }
+            }
+        }
+        _ => Json(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32601, "message": "Method not found" },
+        }))
+        .into_response(),
+    }
+}
@@ -0,0 +1,161 @@
+//! In-process search APIs for tests of the web search backends
+//!
+//! Serves SearxNG, Brave Search, Tavily and Exa lookalikes under `/searxng`,
+//! `/brave`, `/tavily` and `/exa` on a random port, plus `/broken`, a SearxNG
+//! that always fails. Each API returns three results titled after the query and
+//! counts the requests it gets so tests can tell cached results from fresh ones.
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+
+pub const BRAVE_KEY: &str = "brave-test-key";
+pub const TAVILY_KEY: &str = "tavily-test-key";
+pub const EXA_KEY: &str = "exa-test-key";
+
+/// Number of results every API returns
+pub const RESULT_COUNT: usize = 3;
+
+type Requests = Arc<Mutex<HashMap<&'static str, usize>>>;
+
+pub struct MockSearchServer {
+    pub base_url: String,
+    requests: Requests,
+}
+
+impl MockSearchServer {
+    /// Starts the server on a random port
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind random port");
+        let base_url = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let requests = Requests::default();
+
+        let app = Router::new()
+            .route("/searxng/search", get(searxng))
+            .route("/broken/search", get(broken))
+            .route("/brave/res/v1/web/search", get(brave))
+            .route("/tavily/search", post(tavily))
+            .route("/exa/search", post(exa))
+            .with_state(requests.clone());
+
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        Self { base_url, requests }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Requests `api` received
+    pub fn requests(&self, api: &str) -> usize {
+        self.requests.lock().unwrap().get(api).copied().unwrap_or(0)
+    }
+}
+
+fn count(requests: &Requests, api: &'static str) {
+    *requests.lock().unwrap().entry(api).or_default() += 1;
+}
+
+fn result_url(query: &str, i: usize) -> String {
+    format!("https://example.com/{}/{}", query.replace(' ', "-"), i)
+}
+
+async fn searxng(State(requests): State<Requests>, Query(params): Query<HashMap<String, String>>) -> impl IntoResponse {
+    count(&requests, "searxng");
+    if params.get("format").map(String::as_str) != Some("json") {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let query = params.get("q").cloned().unwrap_or_default();
+    let results: Vec<Value> = (1..=RESULT_COUNT)
+        .map(|i| json!({
+            "title": format!("{} result {}", query, i),
+            "url": result_url(&query, i),
+            "content": format!("About {}", query),
+            "publishedDate": null,
+            "engine": "duckduckgo",
+        }))
+        .collect();
+    Json(json!({ "query": query, "results": results, "answers": [{ "answer": format!("{} is a test", query) }] }))
+        .into_response()
+}
+
+async fn broken(State(requests): State<Requests>) -> impl IntoResponse {
+    count(&requests, "broken");
+    (StatusCode::INTERNAL_SERVER_ERROR, "engine unavailable")
+}
+
+async fn brave(
+    State(requests): State<Requests>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    count(&requests, "brave");
+    if headers.get("x-subscription-token").is_none_or(|key| key != BRAVE_KEY) {
+        return (StatusCode::UNAUTHORIZED, Json(json!({ "error": "invalid token" }))).into_response();
+    }
+    let query = params.get("q").cloned().unwrap_or_default();
+    let count: usize = params.get("count").and_then(|c| c.parse().ok()).unwrap_or(RESULT_COUNT);
+    let results: Vec<Value> = (1..=RESULT_COUNT.min(count))
+        .map(|i| json!({
+            "title": format!("<strong>{}</strong> result {}", query, i),
+            "url": result_url(&query, i),
+            "description": format!("All about <strong>{}</strong>", query),
+            "page_age": "2026-01-15T10:00:00",
+        }))
+        .collect();
+    Json(json!({ "type": "search", "web": { "type": "search", "results": results } })).into_response()
+}
+
+async fn tavily(State(requests): State<Requests>, headers: HeaderMap, Json(body): Json<Value>) -> impl IntoResponse {
+    count(&requests, "tavily");
+    let authorized = headers
+        .get("authorization")
+        .is_some_and(|auth| auth.to_str().ok() == Some(format!("Bearer {}", TAVILY_KEY).as_str()));
+    if !authorized {
+        return (StatusCode::UNAUTHORIZED, Json(json!({ "detail": { "error": "Unauthorized" } }))).into_response();
+    }
+    let query = body["query"].as_str().unwrap_or_default().to_string();
+    let max_results = body["max_results"].as_u64().unwrap_or(5) as usize;
+    let results: Vec<Value> = (1..=RESULT_COUNT.min(max_results))
+        .map(|i| json!({
+            "title": format!("{} result {}", query, i),
+            "url": result_url(&query, i),
+            "content": format!("About {}", query),
+            "score": 0.9,
+        }))
+        .collect();
+    let answer = body["include_answer"].as_bool().unwrap_or(false).then(|| format!("{} is a test", query));
+    Json(json!({ "query": query, "answer": answer, "results": results })).into_response()
+}
+
+async fn exa(State(requests): State<Requests>, headers: HeaderMap, Json(body): Json<Value>) -> impl IntoResponse {
+    count(&requests, "exa");
+    if headers.get("x-api-key").is_none_or(|key| key != EXA_KEY) {
+        return (StatusCode::UNAUTHORIZED, Json(json!({ "error": "Invalid API key" }))).into_response();
+    }
+    let query = body["query"].as_str().unwrap_or_default().to_string();
+    let num_results = body["numResults"].as_u64().unwrap_or(10) as usize;
+    let results: Vec<Value> = (1..=RESULT_COUNT.min(num_results))
+        .map(|i| json!({
+            // Exa has no title for some pages
+            "title": if i == 1 { Value::Null } else { json!(format!("{} result {}", query, i)) },
+            "url": result_url(&query, i),
+            "publishedDate": "2026-01-15T10:00:00.000Z",
+            "highlights": [format!("First part about {}", query), "second part"],
+        }))
+        .collect();
+    Json(json!({ "requestId": "test", "results": results })).into_response()
+}
@@ -0,0 +1,97 @@
+//! In-process web server for tests that fetch pages
+//!
+//! Serves an HTML article with an `ETag`, answering `304 Not Modified` when it is
+//! revalidated, a plain text file, an image, a redirect to the cloud metadata
+//! service and a missing page on a random port. Counts requests so tests can
+//! tell cached pages from fetched ones.
+
+use axum::{extract::State, http::{HeaderMap, StatusCode, header}, response::{IntoResponse, Redirect}, routing::get, Router};
+use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+use tokio::net::TcpListener;
+
+pub const ARTICLE_ETAG: &str = "\"article-v1\"";
+
+pub const ARTICLE_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Release notes</title></head>
+<body>
+  <nav><a href="/">Home</a> <a href="/blog">Blog</a></nav>
+  <article>
+    <h1>Release notes</h1>
+    <p>Version 2.4 adds streaming uploads to the storage API. Uploads larger than the
+    configured part size are now split and sent in parallel, which makes large files
+    much faster to store on slow connections.</p>
+    <p>The retry policy of the client was reworked as well. Failed parts are retried
+    with exponential backoff instead of restarting the whole upload from the start.</p>
+    <p>Deprecated endpoints from version 1 are removed in this release, clients still
+    using them should move to the version 2 endpoints described in the migration guide.</p>
+  </article>
+  <footer>Copyright Example Corp</footer>
+</body>
+</html>"#;
+
+#[derive(Clone, Default)]
+struct Counters {
+    article_requests: Arc<AtomicUsize>,
+    not_modified: Arc<AtomicUsize>,
+}
+
+pub struct MockWebServer {
+    pub base_url: String,
+    counters: Counters,
+}
+
+impl MockWebServer {
+    /// Starts the server on a random port
+    ///
+    /// Requests only reach public addresses, so 127.0.0.1 is allowed for the
+    /// tests' servers.
+    pub async fn start() -> Self {
+        buildscale::utils::net::allow_private_hosts(["127.0.0.1"]);
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind random port");
+        let base_url = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let counters = Counters::default();
+
+        let app = Router::new()
+            .route("/article", get(article))
+            .route("/notes.txt", get(|| async { ([(header::CONTENT_TYPE, "text/plain")], "Plain notes") }))
+            .route("/logo.png", get(|| async { ([(header::CONTENT_TYPE, "image/png")], vec![0x89u8, b'P', b'N', b'G']) }))
+            .route("/metadata", get(|| async { Redirect::temporary("http://169.254.169.254/latest/meta-data/") }))
+            .with_state(counters.clone());
+
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        Self { base_url, counters }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Requests for the article, revalidations included
+    pub fn article_requests(&self) -> usize {
+        self.counters.article_requests.load(Ordering::SeqCst)
+    }
+
+    /// Revalidations answered with `304 Not Modified`
+    pub fn not_modified(&self) -> usize {
+        self.counters.not_modified.load(Ordering::SeqCst)
+    }
+}
+
+async fn article(State(counters): State<Counters>, headers: HeaderMap) -> impl IntoResponse {
+    counters.article_requests.fetch_add(1, Ordering::SeqCst);
+    if headers.get(header::IF_NONE_MATCH).is_some_and(|etag| etag == ARTICLE_ETAG) {
+        counters.not_modified.fetch_add(1, Ordering::SeqCst);
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, ARTICLE_ETAG)]).into_response();
+    }
+    (
+        [(header::CONTENT_TYPE, "text/html; charset=utf-8"), (header::ETAG, ARTICLE_ETAG)],
+        ARTICLE_HTML,
+    )
+        .into_response()
+}
@@ -1,5 +1,9 @@
 pub mod database;
 pub mod helpers;
+pub mod mock_mcp;
+pub mod mock_oidc;
+pub mod mock_search;
+pub mod mock_web;
 pub mod test_app;
 
 pub use database::{TestDb, TestApp as DbTestApp};
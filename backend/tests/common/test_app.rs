@@ -101,9 +101,22 @@ impl TestApp {
     /// let app = TestApp::new_with_options(TestAppOptions { cookie_store: false }).await;
     /// ```
     pub async fn new_with_options(options: TestAppOptions) -> Self {
-        // Load config
-        let mut config = load_config().expect("Failed to load config");
+        let config = load_config().expect("Failed to load config");
+        Self::new_with_config(options, config).await
+    }
 
+    /// Create a new HTTP test app with custom options and configuration
+    ///
+    /// Use this when a test needs settings that differ from the environment,
+    /// e.g. pointing single sign-on at a mock provider.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut config = load_config().unwrap();
+    /// config.oidc = provider.config();
+    /// let app = TestApp::new_with_config(TestAppOptions::api(), config).await;
+    /// ```
+    pub async fn new_with_config(options: TestAppOptions, mut config: buildscale::Config) -> Self {
         // Disable storage worker background processing in tests to avoid race conditions
         // during manual logic verification.
         config.storage_worker.cleanup_batch_size = 0;
@@ -0,0 +1,36 @@
+#!/bin/sh
+# Stands in for bubblewrap in tests: understands the options the run tool
+# passes, maps the /workspace bind mount back to its host folder and runs the
+# command there, without any isolation.
+set -e
+
+workspace=""
+chdir=""
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --)
+            shift
+            break
+            ;;
+        --bind)
+            [ "$3" = "/workspace" ] && workspace="$2"
+            shift 3
+            ;;
+        --ro-bind | --ro-bind-try | --setenv)
+            shift 3
+            ;;
+        --chdir)
+            chdir="$2"
+            shift 2
+            ;;
+        --proc | --dev | --tmpfs | --hostname | --cap-drop)
+            shift 2
+            ;;
+        *)
+            shift
+            ;;
+    esac
+done
+
+cd "$workspace${chdir#/workspace}"
+exec "$@"
@@ -0,0 +1,324 @@
+use crate::common::{TestApp, TestAppOptions, create_workspace, register_and_login};
+
+/// Creates a personal API key through the API and returns `(key_id, secret)`
+async fn create_key(app: &TestApp, token: &str, workspace_id: &str, permissions: &[&str]) -> (String, String) {
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/api-keys", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({
+            "name": "ci",
+            "permissions": permissions,
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    (
+        body["api_key"]["id"].as_str().unwrap().to_string(),
+        body["key"].as_str().unwrap().to_string(),
+    )
+}
+
+#[tokio::test]
+async fn test_api_key_authenticates_workspace_requests() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "API Key Auth Test").await;
+    let (_, key) = create_key(&app, &token, &workspace_id, &["workspace:read"]).await;
+
+    let response = app
+        .client
+        .get(&app.url(&format!("/api/v1/workspaces/{}", workspace_id)))
+        .header("Authorization", format!("Bearer {}", key))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // Read-only tools are allowed with workspace:read
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/tools", workspace_id)))
+        .header("Authorization", format!("Bearer {}", key))
+        .json(&serde_json::json!({ "tool": "ls", "args": { "path": "/" } }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn test_read_only_api_key_cannot_write() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "API Key Scope Test").await;
+    let (_, key) = create_key(&app, &token, &workspace_id, &["workspace:read"]).await;
+
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/tools", workspace_id)))
+        .header("Authorization", format!("Bearer {}", key))
+        .json(&serde_json::json!({
+            "tool": "write",
+            "args": { "path": "/notes.md", "content": "hello" }
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/files", workspace_id)))
+        .header("Authorization", format!("Bearer {}", key))
+        .json(&serde_json::json!({ "name": "notes.md", "file_type": "document" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+}
+
+#[tokio::test]
+async fn test_api_key_is_limited_to_its_workspace() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "API Key Home").await;
+    let other_workspace_id = create_workspace(&app, &token, "API Key Elsewhere").await;
+    let (_, key) = create_key(&app, &token, &workspace_id, &["workspace:read"]).await;
+
+    let response = app
+        .client
+        .get(&app.url(&format!("/api/v1/workspaces/{}", other_workspace_id)))
+        .header("Authorization", format!("Bearer {}", key))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+}
+
+#[tokio::test]
+async fn test_api_key_cannot_manage_api_keys() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "API Key Escalation").await;
+    let (_, key) = create_key(&app, &token, &workspace_id, &["workspace:read", "workspace:write"]).await;
+
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/api-keys", workspace_id)))
+        .header("Authorization", format!("Bearer {}", key))
+        .json(&serde_json::json!({ "name": "minted", "permissions": ["workspace:read"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+}
+
+#[tokio::test]
+async fn test_revoked_api_key_returns_401() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "API Key Revoke").await;
+    let (key_id, key) = create_key(&app, &token, &workspace_id, &["workspace:read"]).await;
+
+    let response = app
+        .client
+        .delete(&app.url(&format!("/api/v1/workspaces/{}/api-keys/{}", workspace_id, key_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let response = app
+        .client
+        .get(&app.url(&format!("/api/v1/workspaces/{}", workspace_id)))
+        .header("Authorization", format!("Bearer {}", key))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+
+    // Listing never exposes the secret
+    let response = app
+        .client
+        .get(&app.url(&format!("/api/v1/workspaces/{}/api-keys", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["count"], 1);
+    assert!(body["api_keys"][0]["revoked_at"].is_string());
+    assert!(body["api_keys"][0].get("key_hash").is_none());
+    assert!(body["api_keys"][0].get("key").is_none());
+}
+
+#[tokio::test]
+async fn test_service_account_key_via_api() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Service Account API").await;
+
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/service-accounts", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({ "name": "github-actions", "role_name": "editor" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let service_account_id = body["service_account"]["id"].as_str().unwrap().to_string();
+
+    let response = app
+        .client
+        .post(&app.url(&format!(
+            "/api/v1/workspaces/{}/service-accounts/{}/api-keys",
+            workspace_id, service_account_id
+        )))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({ "name": "deploy", "permissions": ["workspace:read", "workspace:write"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let key = body["key"].as_str().unwrap().to_string();
+
+    let response = app
+        .client
+        .get(&app.url(&format!("/api/v1/workspaces/{}/members/me", workspace_id)))
+        .header("Authorization", format!("Bearer {}", key))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["member"]["full_name"], "github-actions");
+}
+
+#[tokio::test]
+async fn test_api_key_rejected_outside_workspace_routes() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "API Key Boundaries").await;
+    let (_, key) = create_key(&app, &token, &workspace_id, &["workspace:read", "workspace:write"]).await;
+
+    let response = app
+        .client
+        .post(&app.url("/api/v1/workspaces"))
+        .header("Authorization", format!("Bearer {}", key))
+        .json(&serde_json::json!({ "name": "Created With A Key" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    for path in ["/api/v1/workspaces", "/api/v1/auth/me"] {
+        let response = app
+            .client
+            .get(&app.url(path))
+            .header("Authorization", format!("Bearer {}", key))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403, "{} accepted an API key", path);
+    }
+
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/agent-sessions/{}/pause", uuid::Uuid::now_v7())))
+        .header("Authorization", format!("Bearer {}", key))
+        .json(&serde_json::json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    // Opted-in routes still accept the key (no AI providers are configured in tests)
+    let response = app
+        .client
+        .get(&app.url("/api/v1/providers"))
+        .header("Authorization", format!("Bearer {}", key))
+        .send()
+        .await
+        .unwrap();
+    assert_ne!(response.status(), 403);
+    assert_ne!(response.status(), 401);
+}
+
+#[tokio::test]
+async fn test_write_only_api_key_cannot_administer_workspace() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Write Only Key").await;
+    let (_, key) = create_key(&app, &token, &workspace_id, &["workspace:write"]).await;
+    let workspace_url = |path: &str| app.url(&format!("/api/v1/workspaces/{}{}", workspace_id, path));
+
+    let response = app
+        .client
+        .get(&workspace_url("/members/me"))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    let owner_id = body["member"]["user_id"].as_str().unwrap().to_string();
+
+    let requests = [
+        app.client
+            .post(&workspace_url("/members"))
+            .json(&serde_json::json!({ "email": "someone@example.com", "role_name": "admin" })),
+        app.client
+            .patch(&workspace_url(&format!("/members/{}", owner_id)))
+            .json(&serde_json::json!({ "role_name": "viewer" })),
+        app.client.delete(&workspace_url(&format!("/members/{}", owner_id))),
+        app.client.delete(&workspace_url(&format!("/members/{}/two-factor", owner_id))),
+        app.client
+            .patch(&workspace_url("/two-factor-policy"))
+            .json(&serde_json::json!({ "require_two_factor": false })),
+        app.client
+            .put(&workspace_url("/tool-policy"))
+            .json(&serde_json::json!({ "rules": [] })),
+        app.client
+            .patch(&workspace_url(""))
+            .json(&serde_json::json!({ "name": "Renamed With A Key" })),
+        app.client.delete(&workspace_url("")),
+    ];
+    for request in requests {
+        let response = request
+            .header("Authorization", format!("Bearer {}", key))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403, "{:?}", response.url());
+    }
+
+    // The workspace is untouched
+    let response = app
+        .client
+        .get(&workspace_url(""))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["workspace"]["name"], "Write Only Key");
+
+    // A key granted workspace:delete may delete it
+    let (_, key) = create_key(&app, &token, &workspace_id, &["workspace:delete"]).await;
+    let response = app
+        .client
+        .delete(&workspace_url(""))
+        .header("Authorization", format!("Bearer {}", key))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
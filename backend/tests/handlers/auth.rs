@@ -1773,3 +1773,96 @@ async fn test_me_endpoint_cache_hit_works_correctly() {
     assert_eq!(body1, body2);
     assert_eq!(body2, body3);
 }
+
+#[tokio::test]
+async fn test_forgot_password_endpoint_does_not_reveal_unknown_email() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .client
+        .post(app.url("/api/v1/auth/password/forgot"))
+        .json(&serde_json::json!({
+            "email": generate_test_email()
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["message"].as_str().unwrap().contains("If an account exists"));
+}
+
+#[tokio::test]
+async fn test_reset_password_endpoint_rejects_invalid_token() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .client
+        .post(app.url("/api/v1/auth/password/reset"))
+        .json(&serde_json::json!({
+            "token": "0".repeat(64),
+            "password": "BrandNewSecurePass456!",
+            "confirm_password": "BrandNewSecurePass456!"
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 401);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "INVALID_TOKEN");
+}
+
+#[tokio::test]
+async fn test_verify_email_endpoint_marks_user_verified() {
+    let app = TestApp::new().await;
+    let email = generate_test_email();
+
+    let register_response = app
+        .client
+        .post(app.url("/api/v1/auth/register"))
+        .json(&serde_json::json!({
+            "email": email,
+            "password": "SecurePass123!",
+            "confirm_password": "SecurePass123!"
+        }))
+        .send()
+        .await
+        .unwrap();
+    let register_body: serde_json::Value = register_response.json().await.unwrap();
+    assert!(register_body["user"]["email_verified_at"].is_null());
+    let user_id: uuid::Uuid = register_body["user"]["id"].as_str().unwrap().parse().unwrap();
+
+    // The link is delivered out of band; issue a fresh token through the service layer
+    let mut conn = app.get_connection().await;
+    let issued = buildscale::services::users::request_email_verification(&mut conn, user_id)
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .post(app.url("/api/v1/auth/email/verify"))
+        .json(&serde_json::json!({ "token": issued.token }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["user"]["email_verified_at"].is_string());
+}
+
+#[tokio::test]
+async fn test_resend_email_verification_requires_authentication() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .client
+        .post(app.url("/api/v1/auth/email/verification"))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 401);
+}
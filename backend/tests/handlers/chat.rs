@@ -356,3 +356,234 @@ async fn test_get_chat_endpoint() {
     let found = messages.iter().any(|m| m["content"].as_str() == Some(msg_content));
     assert!(found, "Posted message not found in history");
 }
+
+#[tokio::test]
+async fn test_compact_chat_endpoint() {
+    let app = TestApp::new().await;
+    let (_email, access_token, workspace_id, chat_id) = setup_test_chat(&app).await;
+
+    // A fresh chat has nothing to summarize, so no model is called
+    let response = app
+        .client
+        .post(&app.url(&format!(
+            "/api/v1/workspaces/{}/chats/{}/compact",
+            workspace_id, chat_id
+        )))
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["chat_id"], chat_id.to_string());
+    assert_eq!(body["compacted"], false);
+    assert_eq!(body["compacted_message_count"], 0);
+    assert!(body.get("summary").is_none());
+
+    // Unknown chats are not found
+    let response = app
+        .client
+        .post(&app.url(&format!(
+            "/api/v1/workspaces/{}/chats/{}/compact",
+            workspace_id,
+            Uuid::now_v7()
+        )))
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_approval_answer_without_pending_call_returns_404() {
+    let app = TestApp::new().await;
+    let (_email, access_token, workspace_id, chat_id) = setup_test_chat(&app).await;
+    let chat_url = app.url(&format!("/api/v1/workspaces/{}/chats/{}", workspace_id, chat_id));
+
+    let response = app
+        .client
+        .post(&chat_url)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .json(&json!({
+            "content": "approve",
+            "metadata": {
+                "question_answer": {
+                    "question_id": Uuid::now_v7(),
+                    "answers": { "approval": "approve" }
+                }
+            }
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // The answer is not saved as a message
+    let response = app
+        .client
+        .get(&chat_url)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    let messages = body["messages"].as_array().unwrap();
+    assert!(!messages.iter().any(|m| m["content"].as_str() == Some("approve")));
+}
+
+#[tokio::test]
+async fn test_post_message_with_pasted_images() {
+    use base64::Engine;
+    use image::{ImageFormat, Rgb, RgbImage};
+
+    let app = TestApp::new().await;
+    let (_email, access_token, workspace_id, chat_id) = setup_test_chat(&app).await;
+    let chat_url = app.url(&format!("/api/v1/workspaces/{}/chats/{}", workspace_id, chat_id));
+
+    // Pasted data that is not an image is refused
+    let response = app
+        .client
+        .post(&chat_url)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .json(&json!({
+            "content": "What is in this picture?",
+            "images": [{"data": "data:image/png;base64,bm90IGFuIGltYWdl", "name": "notes.png"}]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    // Images are stored with the message, scaled down to what models are sent
+    let mut png = Vec::new();
+    RgbImage::from_pixel(3000, 1500, Rgb([200, 40, 40]))
+        .write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+        .unwrap();
+    let data = base64::engine::general_purpose::STANDARD.encode(&png);
+
+    let response = app
+        .client
+        .post(&chat_url)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .json(&json!({
+            "content": "What is in this picture?",
+            "images": [{"data": data, "name": "red.png"}]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 202);
+
+    let body: serde_json::Value = app
+        .client
+        .get(&chat_url)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let message = body["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["content"] == "What is in this picture?")
+        .expect("Posted message not found in history");
+    let attachment = &message["metadata"]["attachments"][0];
+    assert_eq!(attachment["type"], "image");
+    assert_eq!(attachment["name"], "red.png");
+    assert_eq!(attachment["image"]["width"], 1568);
+    assert_eq!(attachment["image"]["height"], 784);
+}
+
+#[tokio::test]
+async fn test_post_message_with_url_snapshot() {
+    let app = TestApp::new().await;
+    let (_email, access_token, workspace_id, chat_id) = setup_test_chat(&app).await;
+    let server = crate::common::mock_web::MockWebServer::start().await;
+    let chat_url = app.url(&format!("/api/v1/workspaces/{}/chats/{}", workspace_id, chat_id));
+
+    // Only http and https URLs can be attached
+    let response = app
+        .client
+        .post(&chat_url)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .json(&json!({
+            "content": "Read this",
+            "urls": [{"url": "file:///etc/passwd"}]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    // nor private addresses, such as the cloud metadata service
+    let response = app
+        .client
+        .post(&chat_url)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .json(&json!({
+            "content": "Read this",
+            "urls": [{"url": "http://169.254.169.254/latest/meta-data/"}]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let article_url = server.url("/article");
+    let response = app
+        .client
+        .post(&chat_url)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .json(&json!({
+            "content": "Summarize the release notes",
+            "urls": [{"url": article_url, "snapshot": true}]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 202);
+
+    let body: serde_json::Value = app
+        .client
+        .get(&chat_url)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let message = body["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["content"] == "Summarize the release notes")
+        .expect("Posted message not found in history");
+    let attachment = &message["metadata"]["attachments"][0];
+    assert_eq!(attachment["type"], "url");
+    assert_eq!(attachment["url"], article_url);
+    assert_eq!(attachment["title"], "Release notes");
+    assert!(attachment["snapshot_file_id"].is_string());
+
+    // The snapshot is a document in the workspace
+    let snapshot_path = buildscale::services::web_pages::snapshot_path(&article_url);
+    assert!(snapshot_path.starts_with("/snapshots/127.0.0.1-"), "{}", snapshot_path);
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/tools", workspace_id)))
+        .header("Authorization", format!("Bearer {}", access_token))
+        .json(&json!({"tool": "read", "args": {"path": snapshot_path}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let read: serde_json::Value = response.json().await.unwrap();
+    let content = read["result"]["content"].as_str().unwrap();
+    assert!(content.starts_with(&format!("> Snapshot of <{}>", article_url)), "{}", content);
+    assert!(content.contains("streaming uploads"));
+}
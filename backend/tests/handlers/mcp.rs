@@ -0,0 +1,235 @@
+use crate::common::{TestApp, TestAppOptions, create_workspace, register_and_login};
+
+/// Sends one JSON-RPC message to the workspace MCP endpoint
+async fn rpc(app: &TestApp, token: &str, path: &str, message: serde_json::Value) -> reqwest::Response {
+    app.client
+        .post(&app.url(path))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Accept", "application/json, text/event-stream")
+        .json(&message)
+        .send()
+        .await
+        .unwrap()
+}
+
+/// Calls a tool and returns the `tools/call` result
+async fn call_tool(app: &TestApp, token: &str, path: &str, name: &str, arguments: serde_json::Value) -> serde_json::Value {
+    let response = rpc(app, token, path, serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": { "name": name, "arguments": arguments },
+    }))
+    .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    body["result"].clone()
+}
+
+fn tool_names(body: &serde_json::Value) -> Vec<String> {
+    body["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_mcp_endpoint_publishes_workspace_tools() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "MCP Endpoint").await;
+    let path = format!("/api/v1/workspaces/{}/mcp", workspace_id);
+
+    let response = rpc(&app, &token, &path, serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-06-18",
+            "capabilities": {},
+            "clientInfo": { "name": "test", "version": "1.0.0" },
+        },
+    }))
+    .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["id"], 1);
+    assert_eq!(body["result"]["protocolVersion"], "2025-06-18");
+    assert_eq!(body["result"]["serverInfo"]["name"], "buildscale");
+    assert!(body["result"]["capabilities"]["tools"].is_object());
+
+    let response = rpc(&app, &token, &path, serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/initialized",
+    }))
+    .await;
+    assert_eq!(response.status(), 202);
+
+    let response = rpc(&app, &token, &path, serde_json::json!({ "jsonrpc": "2.0", "id": "list", "method": "tools/list" })).await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["id"], "list");
+    let names = tool_names(&body);
+    for name in ["ls", "read", "write", "edit", "grep", "memory_set", "memory_search"] {
+        assert!(names.contains(&name.to_string()), "Missing tool: {}", name);
+    }
+    for name in ["ask_user", "exit_plan_mode", "delegate", "web_fetch"] {
+        assert!(!names.contains(&name.to_string()), "Unexpected tool: {}", name);
+    }
+    let read = body["result"]["tools"].as_array().unwrap().iter().find(|tool| tool["name"] == "read").unwrap();
+    assert_eq!(read["annotations"]["readOnlyHint"], true);
+    assert!(read["inputSchema"]["properties"]["path"].is_object());
+
+    // Tools operate on the workspace
+    let result = call_tool(&app, &token, &path, "write", serde_json::json!({ "path": "/notes/mcp.md", "content": "hello from mcp" })).await;
+    assert_eq!(result["isError"], false, "{}", result);
+
+    let result = call_tool(&app, &token, &path, "read", serde_json::json!({ "path": "/notes/mcp.md" })).await;
+    assert_eq!(result["isError"], false, "{}", result);
+    assert_eq!(result["structuredContent"]["path"], "/notes/mcp.md");
+    assert!(result["content"][0]["text"].as_str().unwrap().contains("hello from mcp"));
+
+    // Tool failures are reported in the result
+    let result = call_tool(&app, &token, &path, "read", serde_json::json!({ "path": "/missing.md" })).await;
+    assert_eq!(result["isError"], true);
+
+    // Unknown tools and methods are protocol errors
+    let response = rpc(&app, &token, &path, serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "tools/call",
+        "params": { "name": "ask_user", "arguments": {} },
+    }))
+    .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["code"], -32602);
+
+    let response = rpc(&app, &token, &path, serde_json::json!({ "jsonrpc": "2.0", "id": 4, "method": "resources/list" })).await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["code"], -32601);
+}
+
+#[tokio::test]
+async fn test_mcp_endpoint_honors_plan_mode() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "MCP Plan Mode").await;
+    let path = format!("/api/v1/workspaces/{}/mcp?plan_mode=true", workspace_id);
+
+    let result = call_tool(&app, &token, &path, "write", serde_json::json!({ "path": "/src/main.rs", "content": "fn main() {}" })).await;
+    assert_eq!(result["isError"], true);
+    assert!(result["content"][0]["text"].as_str().unwrap().contains("Plan Mode"));
+
+    let result = call_tool(&app, &token, &path, "ls", serde_json::json!({ "path": "/" })).await;
+    assert_eq!(result["isError"], false, "{}", result);
+}
+
+#[tokio::test]
+async fn test_mcp_endpoint_respects_api_key_permissions() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "MCP API Key").await;
+    let path = format!("/api/v1/workspaces/{}/mcp", workspace_id);
+
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/api-keys", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({ "name": "ide", "permissions": ["workspace:read"] }))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    let key = body["key"].as_str().unwrap().to_string();
+
+    let response = rpc(&app, &key, &path, serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" })).await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    let names = tool_names(&body);
+    assert!(names.contains(&"read".to_string()));
+    assert!(!names.contains(&"write".to_string()));
+
+    let result = call_tool(&app, &key, &path, "write", serde_json::json!({ "path": "/a.md", "content": "x" })).await;
+    assert_eq!(result["isError"], true);
+    assert!(result["content"][0]["text"].as_str().unwrap().contains("workspace:write"));
+
+    // Non-members are rejected like on every workspace route
+    let other_token = register_and_login(&app).await;
+    let response = rpc(&app, &other_token, &path, serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" })).await;
+    assert_eq!(response.status(), 403);
+}
+
+#[tokio::test]
+async fn test_mcp_endpoint_enforces_tool_policy() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "MCP Tool Policy").await;
+    let path = format!("/api/v1/workspaces/{}/mcp", workspace_id);
+
+    let response = app
+        .client
+        .put(&app.url(&format!("/api/v1/workspaces/{}/tool-policy", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({ "rules": [
+            { "tool": "write", "action": "deny", "reason": "Writes go through review" },
+            { "tool": "rm", "action": "ask" }
+        ] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let result = call_tool(&app, &token, &path, "write", serde_json::json!({ "path": "/a.md", "content": "x" })).await;
+    assert_eq!(result["isError"], true);
+    assert!(result["content"][0]["text"].as_str().unwrap().contains("Writes go through review"));
+
+    // Nobody can approve a call outside of a chat, so `ask` refuses it
+    let result = call_tool(&app, &token, &path, "rm", serde_json::json!({ "path": "/a.md" })).await;
+    assert_eq!(result["isError"], true);
+    assert!(result["content"][0]["text"].as_str().unwrap().contains("requires approval"));
+
+    let result = call_tool(&app, &token, &path, "ls", serde_json::json!({ "path": "/" })).await;
+    assert_eq!(result["isError"], false, "{}", result);
+}
+
+#[tokio::test]
+async fn test_mcp_endpoint_transport_errors() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "MCP Transport").await;
+    let url = app.url(&format!("/api/v1/workspaces/{}/mcp", workspace_id));
+
+    let response = app
+        .client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/json")
+        .body("{not json")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["code"], -32700);
+
+    let response = app
+        .client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .header("MCP-Protocol-Version", "1999-01-01")
+        .json(&serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    // No server-initiated stream
+    let response = app
+        .client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 405);
+}
@@ -0,0 +1,251 @@
+use crate::common::{
+    TestApp, TestAppOptions, create_workspace, generate_test_email, register_and_login,
+    mock_mcp::{MockMcpServer, MOCK_MCP_TOKEN},
+};
+use buildscale::config::McpConfig;
+use buildscale::services::chat::rig_tools::RigMcpTool;
+use buildscale::services::mcp::{self, McpClientPool};
+use rig::tool::ToolDyn;
+use std::sync::Arc;
+
+/// Registers the mock server in a workspace and returns the response body
+async fn register_server(app: &TestApp, token: &str, workspace_id: &str, server: &MockMcpServer, name: &str) -> serde_json::Value {
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/mcp-servers", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({
+            "name": name,
+            "transport": "http",
+            "url": server.url,
+            "headers": { "Authorization": MOCK_MCP_TOKEN },
+            "timeout_seconds": 5,
+        }))
+        .send()
+        .await
+        .unwrap();
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(status, 200, "{}", body);
+    body
+}
+
+#[tokio::test]
+async fn test_mcp_server_lifecycle() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "MCP Workspace").await;
+    let server = MockMcpServer::start().await;
+
+    let body = register_server(&app, &token, &workspace_id, &server, "docs").await;
+    let server_id = body["mcp_server"]["id"].as_str().unwrap().to_string();
+    assert_eq!(body["mcp_server"]["name"], "docs");
+    assert_eq!(body["mcp_server"]["enabled"], true);
+    assert_eq!(body["mcp_server"]["timeout_seconds"], 5);
+    // Header values carry credentials and are never returned
+    assert_eq!(body["mcp_server"]["header_names"], serde_json::json!(["Authorization"]));
+    assert!(!body.to_string().contains("mock-mcp-token"));
+
+    // Tools are discovered and namespaced by server
+    let response = app
+        .client
+        .get(&app.url(&format!("/api/v1/workspaces/{}/mcp-servers/{}/tools", workspace_id, server_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["count"], 2);
+    assert_eq!(body["tools"][0]["name"], "mcp__docs__search_docs");
+    assert_eq!(body["tools"][0]["remote_name"], "search_docs");
+    assert_eq!(body["tools"][0]["read_only"], true);
+    assert_eq!(body["tools"][1]["name"], "mcp__docs__create_issue");
+    assert_eq!(body["tools"][1]["read_only"], false);
+
+    // A second server with the same name is rejected
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/mcp-servers", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({ "name": "docs", "transport": "http", "url": server.url }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 409);
+
+    // Disable it
+    let response = app
+        .client
+        .patch(&app.url(&format!("/api/v1/workspaces/{}/mcp-servers/{}", workspace_id, server_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({ "enabled": false }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["mcp_server"]["enabled"], false);
+    assert_eq!(body["mcp_server"]["url"], server.url.as_str());
+
+    let response = app
+        .client
+        .get(&app.url(&format!("/api/v1/workspaces/{}/mcp-servers", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["count"], 1);
+    assert_eq!(body["mcp_servers"][0]["enabled"], false);
+
+    // Remove it
+    let response = app
+        .client
+        .delete(&app.url(&format!("/api/v1/workspaces/{}/mcp-servers/{}", workspace_id, server_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let response = app
+        .client
+        .delete(&app.url(&format!("/api/v1/workspaces/{}/mcp-servers/{}", workspace_id, server_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_create_mcp_server_validation() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "MCP Validation").await;
+
+    let cases = [
+        (serde_json::json!({ "name": "Bad Name", "transport": "http", "url": "https://mcp.example.com" }), 400),
+        (serde_json::json!({ "name": "docs", "transport": "http" }), 400),
+        (serde_json::json!({ "name": "docs", "transport": "http", "url": "ftp://mcp.example.com" }), 400),
+        (serde_json::json!({ "name": "docs", "transport": "http", "url": "https://mcp.example.com", "timeout_seconds": 0 }), 400),
+        // The server's own network is off limits
+        (serde_json::json!({ "name": "docs", "transport": "http", "url": "http://169.254.169.254/mcp" }), 400),
+        // stdio servers run commands on the backend host and are disabled by default
+        (serde_json::json!({ "name": "local", "transport": "stdio", "command": "npx" }), 403),
+    ];
+
+    for (request, status) in cases {
+        let response = app
+            .client
+            .post(&app.url(&format!("/api/v1/workspaces/{}/mcp-servers", workspace_id)))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&request)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status, "Unexpected status for {}", request);
+    }
+}
+
+#[tokio::test]
+async fn test_mcp_servers_require_manage_settings() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let admin_token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &admin_token, "MCP Permissions").await;
+
+    let member_email = generate_test_email();
+    app.client
+        .post(&app.url("/api/v1/auth/register"))
+        .json(&serde_json::json!({
+            "email": member_email,
+            "password": "SecurePass123!",
+            "confirm_password": "SecurePass123!"
+        }))
+        .send()
+        .await
+        .unwrap();
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/members", workspace_id)))
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .json(&serde_json::json!({ "email": member_email, "role_name": "member" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let response = app
+        .client
+        .post(&app.url("/api/v1/auth/login"))
+        .json(&serde_json::json!({ "email": member_email, "password": "SecurePass123!" }))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    let member_token = body["access_token"].as_str().unwrap().to_string();
+
+    let response = app
+        .client
+        .get(&app.url(&format!("/api/v1/workspaces/{}/mcp-servers", workspace_id)))
+        .header("Authorization", format!("Bearer {}", member_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/mcp-servers", workspace_id)))
+        .header("Authorization", format!("Bearer {}", member_token))
+        .json(&serde_json::json!({ "name": "docs", "transport": "http", "url": "https://mcp.example.com" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+}
+
+#[tokio::test]
+async fn test_mcp_tools_are_offered_to_agents() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "MCP Agent Tools").await;
+    let server = MockMcpServer::start().await;
+    register_server(&app, &token, &workspace_id, &server, "docs").await;
+
+    let clients = Arc::new(McpClientPool::new());
+    let mut conn = app.get_connection().await;
+    let tools = mcp::load_agent_tools(&mut conn, &clients, &McpConfig::default(), workspace_id.parse().unwrap()).await;
+    assert_eq!(tools.len(), 2);
+
+    let tools: Vec<RigMcpTool> = tools
+        .into_iter()
+        .map(|(server, tool)| RigMcpTool { clients: clients.clone(), server, tool })
+        .collect();
+
+    let search = &tools[0];
+    assert_eq!(search.name(), "mcp__docs__search_docs");
+    let definition = search.definition(String::new()).await;
+    assert!(definition.description.contains("Search the documentation"));
+    assert_eq!(definition.parameters["required"], serde_json::json!(["query"]));
+
+    // Streamed response, the progress notification before it is skipped
+    let output = search.call(r#"{"query": "install"}"#.to_string()).await.unwrap();
+    let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(output["content"], "Found 1 page for 'install'");
+    assert_eq!(output["structured_content"]["pages"][0], "/docs/getting-started");
+
+    // Tool errors reported by the server fail the call
+    let create_issue = &tools[1];
+    let error = create_issue.call(String::new()).await.unwrap_err();
+    assert!(error.to_string().contains("title is required"));
+
+    let output = create_issue.call(r#"{"title": "Broken link"}"#.to_string()).await.unwrap();
+    let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(output["content"], "Created issue 'Broken link'");
+
+    assert_eq!(server.calls().len(), 3);
+    assert_eq!(server.calls()[1], ("create_issue".to_string(), serde_json::json!({})));
+}
@@ -1,4 +1,5 @@
 pub mod agent_sessions;
+pub mod api_keys;
 pub mod auth;
 pub mod health;
 pub mod workspaces;
@@ -6,3 +7,8 @@ pub mod members;
 pub mod files;
 pub mod files_virtual_test;
 pub mod chat;
+pub mod oidc;
+pub mod two_factor;
+pub mod sessions;
+pub mod mcp;
+pub mod mcp_servers;
@@ -0,0 +1,324 @@
+use crate::common::{
+    TestApp, TestAppOptions, create_workspace, generate_test_email, register_and_login,
+    mock_oidc::{MockIdentity, MockOidcProvider},
+};
+use buildscale::load_config;
+use reqwest::header::{LOCATION, SET_COOKIE};
+
+/// Starts a mock provider and a test app configured to use it
+async fn setup() -> (TestApp, MockOidcProvider) {
+    setup_with(|_| {}).await
+}
+
+async fn setup_with(customize: impl FnOnce(&mut buildscale::config::OidcConfig)) -> (TestApp, MockOidcProvider) {
+    let provider = MockOidcProvider::start().await;
+    let mut config = load_config().expect("Failed to load config");
+    config.oidc = provider.config();
+    customize(&mut config.oidc);
+    let app = TestApp::new_with_config(TestAppOptions::api(), config).await;
+    (app, provider)
+}
+
+/// Runs the whole browser flow and returns the final callback response
+async fn sign_in(app: &TestApp, provider: &MockOidcProvider, identity: &MockIdentity, redirect_to: &str) -> reqwest::Response {
+    let response = app
+        .client
+        .get(url::Url::parse_with_params(&app.url("/api/v1/auth/oidc/authorize"), &[("redirect_to", redirect_to)]).unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 303);
+    let authorization_url = response.headers()[LOCATION].to_str().unwrap().to_string();
+
+    let (code, state) = provider.authorize(&authorization_url, identity);
+    callback(app, &code, &state).await
+}
+
+async fn callback(app: &TestApp, code: &str, state: &str) -> reqwest::Response {
+    app.client
+        .get(url::Url::parse_with_params(&app.url("/api/v1/auth/oidc/callback"), &[("code", code), ("state", state)]).unwrap())
+        .send()
+        .await
+        .unwrap()
+}
+
+fn location(response: &reqwest::Response) -> String {
+    response.headers()[LOCATION].to_str().unwrap().to_string()
+}
+
+fn access_token_cookie(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find_map(|cookie| cookie.strip_prefix("access_token="))
+        .map(|rest| rest.split(';').next().unwrap().to_string())
+}
+
+async fn me(app: &TestApp, token: &str) -> serde_json::Value {
+    let response = app
+        .client
+        .get(&app.url("/api/v1/auth/me"))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    response.json().await.unwrap()
+}
+
+fn unique_domain() -> String {
+    format!("sso{}.example.com", rand::random::<u32>())
+}
+
+#[tokio::test]
+async fn test_oidc_authorize_returns_404_when_disabled() {
+    let mut config = load_config().expect("Failed to load config");
+    config.oidc.enabled = false;
+    let app = TestApp::new_with_config(TestAppOptions::api(), config).await;
+
+    let response = app
+        .client
+        .get(&app.url("/api/v1/auth/oidc/authorize"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_oidc_login_provisions_new_user() {
+    let (app, provider) = setup().await;
+    let email = generate_test_email();
+
+    let response = sign_in(&app, &provider, &MockIdentity::verified(&email), "/workspaces").await;
+    assert_eq!(response.status(), 303);
+    assert_eq!(location(&response), format!("{}/workspaces", app.config.auth.public_base_url.trim_end_matches('/')));
+
+    let token = access_token_cookie(&response).expect("access_token cookie must be set");
+    let body = me(&app, &token).await;
+    assert_eq!(body["user"]["email"], email);
+    assert_eq!(body["user"]["full_name"], "SSO User");
+
+    // Provisioned accounts are passwordless and trust the provider's verification
+    let (password_hash, email_verified_at): (Option<String>, Option<chrono::DateTime<chrono::Utc>>) =
+        sqlx::query_as("SELECT password_hash, email_verified_at FROM users WHERE email = $1")
+            .bind(&email)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert!(password_hash.is_none());
+    assert!(email_verified_at.is_some());
+}
+
+#[tokio::test]
+async fn test_oidc_returning_user_is_matched_by_subject() {
+    let (app, provider) = setup().await;
+    let mut identity = MockIdentity::verified(&generate_test_email());
+
+    let first = sign_in(&app, &provider, &identity, "/").await;
+    let first_user = me(&app, &access_token_cookie(&first).unwrap()).await;
+
+    // The provider may report a changed address; the linked subject still wins
+    identity.email = generate_test_email();
+    let second = sign_in(&app, &provider, &identity, "/").await;
+    let second_user = me(&app, &access_token_cookie(&second).unwrap()).await;
+
+    assert_eq!(first_user["user"]["id"], second_user["user"]["id"]);
+}
+
+#[tokio::test]
+async fn test_oidc_links_existing_account_by_verified_email() {
+    let (app, provider) = setup().await;
+    let email = generate_test_email();
+
+    app.client
+        .post(&app.url("/api/v1/auth/register"))
+        .json(&serde_json::json!({
+            "email": email,
+            "password": "SecurePass123!",
+            "confirm_password": "SecurePass123!"
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let response = sign_in(&app, &provider, &MockIdentity::verified(&email), "/").await;
+    let body = me(&app, &access_token_cookie(&response).unwrap()).await;
+    assert_eq!(body["user"]["email"], email);
+
+    // The local address was never verified, so the pre-existing password is dropped
+    let login = app
+        .client
+        .post(&app.url("/api/v1/auth/login"))
+        .json(&serde_json::json!({ "email": email, "password": "SecurePass123!" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(login.status(), 401);
+}
+
+#[tokio::test]
+async fn test_oidc_rejects_unverified_email() {
+    let (app, provider) = setup().await;
+    let mut identity = MockIdentity::verified(&generate_test_email());
+    identity.email_verified = false;
+
+    let response = sign_in(&app, &provider, &identity, "/").await;
+    assert_eq!(response.status(), 303);
+    assert!(location(&response).ends_with("/login?error=sso_failed"));
+    assert!(access_token_cookie(&response).is_none());
+}
+
+#[tokio::test]
+async fn test_oidc_signup_can_be_disabled() {
+    let (app, provider) = setup_with(|oidc| oidc.allow_signup = false).await;
+
+    let response = sign_in(&app, &provider, &MockIdentity::verified(&generate_test_email()), "/").await;
+    assert!(location(&response).ends_with("/login?error=sso_failed"));
+}
+
+#[tokio::test]
+async fn test_oidc_callback_cannot_be_replayed() {
+    let (app, provider) = setup().await;
+    let identity = MockIdentity::verified(&generate_test_email());
+
+    let response = app
+        .client
+        .get(&app.url("/api/v1/auth/oidc/authorize"))
+        .send()
+        .await
+        .unwrap();
+    let (code, state) = provider.authorize(&location(&response), &identity);
+
+    let first = callback(&app, &code, &state).await;
+    assert!(access_token_cookie(&first).is_some());
+
+    let replay = callback(&app, &code, &state).await;
+    assert!(location(&replay).ends_with("/login?error=sso_failed"));
+    assert!(access_token_cookie(&replay).is_none());
+
+    let unknown_state = callback(&app, &code, "not-a-real-state").await;
+    assert!(location(&unknown_state).ends_with("/login?error=sso_failed"));
+}
+
+#[tokio::test]
+async fn test_oidc_ignores_external_redirect_targets() {
+    let (app, provider) = setup().await;
+
+    let response = sign_in(&app, &provider, &MockIdentity::verified(&generate_test_email()), "//evil.example.com").await;
+    assert_eq!(location(&response), format!("{}/", app.config.auth.public_base_url.trim_end_matches('/')));
+}
+
+/// Adds an SSO domain and returns the response
+async fn add_sso_domain(app: &TestApp, token: &str, workspace_id: &str, domain: &str) -> reqwest::Response {
+    app.client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/sso-domains", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({ "domain": domain, "role_name": "member" }))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn verify_sso_domain(app: &TestApp, token: &str, workspace_id: &str, domain_id: &str) -> reqwest::Response {
+    app.client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/sso-domains/{}/verify", workspace_id, domain_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn member_status(app: &TestApp, token: &str, workspace_id: &str) -> u16 {
+    app.client
+        .get(&app.url(&format!("/api/v1/workspaces/{}/members/me", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap()
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn test_oidc_auto_joins_workspace_by_email_domain() {
+    let (app, provider) = setup().await;
+    let domain = unique_domain();
+    let owner_token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &owner_token, "SSO Auto Join").await;
+
+    let response = add_sso_domain(&app, &owner_token, &workspace_id, &domain.to_uppercase()).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["sso_domain"]["domain"], domain);
+    assert_eq!(body["sso_domain"]["role_name"], "member");
+    assert!(body["sso_domain"]["verified_at"].is_null());
+    let domain_id = body["sso_domain"]["id"].as_str().unwrap().to_string();
+    let record_name = body["verification_record"]["name"].as_str().unwrap().to_string();
+    let record_value = body["verification_record"]["value"].as_str().unwrap().to_string();
+    assert_eq!(record_name, format!("_buildscale-verification.{}", domain));
+
+    // Nobody joins through a domain that is not verified yet
+    let response = sign_in(&app, &provider, &MockIdentity::verified(&format!("early@{}", domain)), "/").await;
+    let token = access_token_cookie(&response).unwrap();
+    assert_eq!(member_status(&app, &token, &workspace_id).await, 403);
+
+    provider.publish_txt(&record_name, &record_value);
+    let response = verify_sso_domain(&app, &owner_token, &workspace_id, &domain_id).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["sso_domain"]["verified_at"].is_string());
+
+    // A colleague signs in with SSO for the first time and lands in the workspace,
+    // whatever the case of their address
+    let email = format!("Colleague@{}", domain.to_uppercase());
+    let response = sign_in(&app, &provider, &MockIdentity::verified(&email), "/").await;
+    let token = access_token_cookie(&response).unwrap();
+
+    let response = app
+        .client
+        .get(&app.url(&format!("/api/v1/workspaces/{}/members/me", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["member"]["role_name"], "member");
+}
+
+#[tokio::test]
+async fn test_sso_domain_requires_dns_verification() {
+    let (app, provider) = setup().await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "SSO Domain Claim").await;
+
+    // Public email providers and invalid domains are refused
+    for domain in ["gmail.com", "Outlook.com", "not a domain"] {
+        assert_eq!(add_sso_domain(&app, &token, &workspace_id, domain).await.status(), 400);
+    }
+
+    let domain = unique_domain();
+    let response = add_sso_domain(&app, &token, &workspace_id, &domain).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let domain_id = body["sso_domain"]["id"].as_str().unwrap().to_string();
+    let record_name = body["verification_record"]["name"].as_str().unwrap().to_string();
+    let record_value = body["verification_record"]["value"].as_str().unwrap().to_string();
+
+    // Neither a missing record nor another workspace's token verifies the domain
+    assert_eq!(verify_sso_domain(&app, &token, &workspace_id, &domain_id).await.status(), 403);
+    provider.publish_txt(&record_name, "buildscale-verification=someone-else");
+    assert_eq!(verify_sso_domain(&app, &token, &workspace_id, &domain_id).await.status(), 403);
+
+    provider.publish_txt(&record_name, &record_value);
+    assert_eq!(verify_sso_domain(&app, &token, &workspace_id, &domain_id).await.status(), 200);
+    assert_eq!(verify_sso_domain(&app, &token, &workspace_id, &domain_id).await.status(), 200);
+
+    // Other users cannot verify the workspace's domains
+    let member_token = register_and_login(&app).await;
+    assert_eq!(verify_sso_domain(&app, &member_token, &workspace_id, &domain_id).await.status(), 403);
+    assert_eq!(verify_sso_domain(&app, &token, &workspace_id, &uuid::Uuid::now_v7().to_string()).await.status(), 404);
+}
@@ -0,0 +1,206 @@
+use crate::common::{TestApp, TestAppOptions, generate_test_email};
+
+const PASSWORD: &str = "SecurePass123!";
+
+struct Device {
+    access_token: String,
+    refresh_token: String,
+}
+
+async fn register(app: &TestApp) -> String {
+    let email = generate_test_email();
+    app.client
+        .post(&app.url("/api/v1/auth/register"))
+        .json(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "confirm_password": PASSWORD
+        }))
+        .send()
+        .await
+        .unwrap();
+    email
+}
+
+async fn login_from(app: &TestApp, email: &str, user_agent: &str) -> Device {
+    let response = app
+        .client
+        .post(&app.url("/api/v1/auth/login"))
+        .header("User-Agent", user_agent)
+        .json(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    Device {
+        access_token: body["access_token"].as_str().unwrap().to_string(),
+        refresh_token: body["refresh_token"].as_str().unwrap().to_string(),
+    }
+}
+
+async fn list_sessions(app: &TestApp, access_token: &str) -> Vec<serde_json::Value> {
+    let response = app
+        .client
+        .get(&app.url("/api/v1/auth/sessions"))
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    body["sessions"].as_array().unwrap().clone()
+}
+
+async fn refresh_from(app: &TestApp, refresh_token: &str, user_agent: &str) -> reqwest::Response {
+    app.client
+        .post(&app.url("/api/v1/auth/refresh"))
+        .header("Authorization", format!("Bearer {}", refresh_token))
+        .header("User-Agent", user_agent)
+        .send()
+        .await
+        .unwrap()
+}
+
+fn session_with_agent<'a>(sessions: &'a [serde_json::Value], user_agent: &str) -> &'a serde_json::Value {
+    sessions
+        .iter()
+        .find(|session| session["user_agent"] == user_agent)
+        .expect("session for user agent")
+}
+
+#[tokio::test]
+async fn test_list_sessions_shows_devices_and_current_flag() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let email = register(&app).await;
+    login_from(&app, &email, "Laptop/1.0").await;
+    let phone = login_from(&app, &email, "Phone/2.0").await;
+
+    let sessions = list_sessions(&app, &phone.access_token).await;
+    assert_eq!(sessions.len(), 2);
+
+    let phone_session = session_with_agent(&sessions, "Phone/2.0");
+    assert_eq!(phone_session["current"], true);
+    assert_eq!(phone_session["ip_address"], "127.0.0.1");
+    assert!(phone_session["created_at"].is_string());
+    assert!(phone_session["last_used_at"].is_string());
+    assert!(phone_session.get("token_hash").is_none());
+
+    assert_eq!(session_with_agent(&sessions, "Laptop/1.0")["current"], false);
+}
+
+#[tokio::test]
+async fn test_refresh_keeps_session_and_records_device() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let email = register(&app).await;
+    let device = login_from(&app, &email, "Browser/1.0").await;
+    let session_id = list_sessions(&app, &device.access_token).await[0]["id"].clone();
+
+    let response = refresh_from(&app, &device.refresh_token, "Browser/1.1").await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let access_token = body["access_token"].as_str().unwrap();
+
+    // The refreshed access token belongs to the same session, now showing the new user agent
+    let sessions = list_sessions(&app, access_token).await;
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0]["id"], session_id);
+    assert_eq!(sessions[0]["current"], true);
+    assert_eq!(sessions[0]["user_agent"], "Browser/1.1");
+}
+
+#[tokio::test]
+async fn test_revoke_session_by_id() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let email = register(&app).await;
+    let laptop = login_from(&app, &email, "Laptop/1.0").await;
+    let lost_phone = login_from(&app, &email, "Phone/2.0").await;
+
+    let sessions = list_sessions(&app, &laptop.access_token).await;
+    let phone_session_id = session_with_agent(&sessions, "Phone/2.0")["id"].as_str().unwrap().to_string();
+    let revoke_url = app.url(&format!("/api/v1/auth/sessions/{}", phone_session_id));
+
+    // Sessions of other users cannot be revoked
+    let other_email = register(&app).await;
+    let other = login_from(&app, &other_email, "Other/1.0").await;
+    let response = app
+        .client
+        .delete(&revoke_url)
+        .header("Authorization", format!("Bearer {}", other.access_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let response = app
+        .client
+        .delete(&revoke_url)
+        .header("Authorization", format!("Bearer {}", laptop.access_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // The revoked device can no longer refresh, the other one still can
+    assert_eq!(refresh_from(&app, &lost_phone.refresh_token, "Phone/2.0").await.status(), 401);
+    assert_eq!(refresh_from(&app, &laptop.refresh_token, "Laptop/1.0").await.status(), 200);
+
+    let response = app
+        .client
+        .delete(&revoke_url)
+        .header("Authorization", format!("Bearer {}", laptop.access_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_revoke_other_sessions_keeps_current() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let email = register(&app).await;
+    let current = login_from(&app, &email, "Laptop/1.0").await;
+    let phone = login_from(&app, &email, "Phone/2.0").await;
+    let tablet = login_from(&app, &email, "Tablet/3.0").await;
+
+    let response = app
+        .client
+        .post(&app.url("/api/v1/auth/sessions/revoke-others"))
+        .header("Authorization", format!("Bearer {}", current.access_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["revoked_sessions"], 2);
+
+    let sessions = list_sessions(&app, &current.access_token).await;
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0]["current"], true);
+
+    assert_eq!(refresh_from(&app, &phone.refresh_token, "Phone/2.0").await.status(), 401);
+    assert_eq!(refresh_from(&app, &tablet.refresh_token, "Tablet/3.0").await.status(), 401);
+    assert_eq!(refresh_from(&app, &current.refresh_token, "Laptop/1.0").await.status(), 200);
+}
+
+#[tokio::test]
+async fn test_rotated_token_of_revoked_session_gets_no_access_token() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let email = register(&app).await;
+    let laptop = login_from(&app, &email, "Laptop/1.0").await;
+    let phone = login_from(&app, &email, "Phone/2.0").await;
+
+    // Rotate the phone's refresh token, then revoke the phone
+    assert_eq!(refresh_from(&app, &phone.refresh_token, "Phone/2.0").await.status(), 200);
+    let response = app
+        .client
+        .post(&app.url("/api/v1/auth/sessions/revoke-others"))
+        .header("Authorization", format!("Bearer {}", laptop.access_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // The old token is within the rotation grace period but its session is gone
+    assert_eq!(refresh_from(&app, &phone.refresh_token, "Phone/2.0").await.status(), 401);
+}
@@ -0,0 +1,465 @@
+use crate::common::{TestApp, TestAppOptions, create_workspace, generate_test_email};
+use buildscale::services::two_factor::generate_totp;
+
+const PASSWORD: &str = "SecurePass123!";
+
+/// TOTP code for the current time shifted by whole 30-second steps
+///
+/// Each accepted code must be from a later step than the previous one, and the server
+/// accepts one step of drift, so a test can use the steps -1, 0 and +1 in that order.
+fn code_at(secret: &str, step_offset: i64) -> String {
+    generate_totp(secret, chrono::Utc::now().timestamp() + step_offset * 30).unwrap()
+}
+
+async fn register(app: &TestApp) -> String {
+    register_email(app, &generate_test_email()).await
+}
+
+/// Registers a user with a verified email address at `domain`
+async fn register_verified_at(app: &TestApp, domain: &str) -> String {
+    let email = format!("user{}@{}", rand::random::<u32>(), domain);
+    register_email(app, &email).await;
+    sqlx::query("UPDATE users SET email_verified_at = NOW() WHERE email = $1")
+        .bind(&email)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    email
+}
+
+async fn register_email(app: &TestApp, email: &str) -> String {
+    app.client
+        .post(&app.url("/api/v1/auth/register"))
+        .json(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "confirm_password": PASSWORD
+        }))
+        .send()
+        .await
+        .unwrap();
+    email.to_string()
+}
+
+async fn login(app: &TestApp, email: &str) -> reqwest::Response {
+    app.client
+        .post(&app.url("/api/v1/auth/login"))
+        .json(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn login_token(app: &TestApp, email: &str) -> String {
+    let body: serde_json::Value = login(app, email).await.json().await.unwrap();
+    body["access_token"].as_str().expect("login should not require 2FA").to_string()
+}
+
+async fn post(app: &TestApp, path: &str, token: &str, body: serde_json::Value) -> reqwest::Response {
+    app.client
+        .post(&app.url(path))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+/// Enrolls the user with a code from the previous time step and returns the secret
+/// and recovery codes
+async fn enable_two_factor(app: &TestApp, token: &str) -> (String, Vec<String>) {
+    let response = post(app, "/api/v1/auth/two-factor/enroll", token, serde_json::json!({})).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let secret = body["secret"].as_str().unwrap().to_string();
+    assert!(body["otpauth_url"].as_str().unwrap().starts_with("otpauth://totp/"));
+
+    let response = post(
+        app,
+        "/api/v1/auth/two-factor/confirm",
+        token,
+        serde_json::json!({ "code": code_at(&secret, -1) }),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let recovery_codes = body["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|code| code.as_str().unwrap().to_string())
+        .collect();
+
+    (secret, recovery_codes)
+}
+
+/// Logs in with a password and returns the challenge token
+async fn start_challenge(app: &TestApp, email: &str) -> String {
+    let response = login(app, email).await;
+    assert_eq!(response.status(), 200);
+    assert!(response.headers().get("set-cookie").is_none());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["two_factor_required"], true);
+    assert!(body.get("access_token").is_none());
+    body["challenge_token"].as_str().unwrap().to_string()
+}
+
+async fn verify(app: &TestApp, body: serde_json::Value) -> reqwest::Response {
+    app.client
+        .post(&app.url("/api/v1/auth/login/two-factor"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_two_factor_login_flow() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let email = register(&app).await;
+    let token = login_token(&app, &email).await;
+
+    let (secret, recovery_codes) = enable_two_factor(&app, &token).await;
+    assert_eq!(recovery_codes.len(), 10);
+
+    let response = app
+        .client
+        .get(&app.url("/api/v1/auth/two-factor"))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["two_factor"]["enabled"], true);
+    assert_eq!(body["two_factor"]["recovery_codes_remaining"], 10);
+
+    let challenge_token = start_challenge(&app, &email).await;
+    let response = verify(&app, serde_json::json!({
+        "challenge_token": challenge_token,
+        "code": code_at(&secret, 0)
+    }))
+    .await;
+    assert_eq!(response.status(), 200);
+    assert!(response.headers().get("set-cookie").is_some());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["user"]["email"], email);
+    assert!(body["access_token"].is_string());
+
+    // The challenge is single-use
+    let response = verify(&app, serde_json::json!({
+        "challenge_token": challenge_token,
+        "code": code_at(&secret, 1)
+    }))
+    .await;
+    assert_eq!(response.status(), 401);
+}
+
+#[tokio::test]
+async fn test_two_factor_code_cannot_be_replayed() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let email = register(&app).await;
+    let token = login_token(&app, &email).await;
+    let (secret, _) = enable_two_factor(&app, &token).await;
+
+    // The confirmation code's step was consumed at enrollment
+    let challenge_token = start_challenge(&app, &email).await;
+    let response = verify(&app, serde_json::json!({
+        "challenge_token": challenge_token,
+        "code": code_at(&secret, -1)
+    }))
+    .await;
+    assert_eq!(response.status(), 401);
+
+    let response = verify(&app, serde_json::json!({
+        "challenge_token": challenge_token,
+        "code": code_at(&secret, 0)
+    }))
+    .await;
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn test_two_factor_challenge_is_discarded_after_too_many_failures() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let email = register(&app).await;
+    let token = login_token(&app, &email).await;
+    let (secret, _) = enable_two_factor(&app, &token).await;
+
+    let challenge_token = start_challenge(&app, &email).await;
+    for _ in 0..5 {
+        let response = verify(&app, serde_json::json!({
+            "challenge_token": challenge_token,
+            "code": "000000"
+        }))
+        .await;
+        assert_eq!(response.status(), 401);
+    }
+
+    let response = verify(&app, serde_json::json!({
+        "challenge_token": challenge_token,
+        "code": code_at(&secret, 0)
+    }))
+    .await;
+    assert_eq!(response.status(), 401);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "INVALID_TOKEN");
+}
+
+#[tokio::test]
+async fn test_two_factor_recovery_code_is_single_use() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let email = register(&app).await;
+    let token = login_token(&app, &email).await;
+    let (_, recovery_codes) = enable_two_factor(&app, &token).await;
+
+    // Recovery codes are accepted regardless of case and dashes
+    let loosely_typed = recovery_codes[0].to_lowercase().replace('-', "");
+    let challenge_token = start_challenge(&app, &email).await;
+    let response = verify(&app, serde_json::json!({
+        "challenge_token": challenge_token,
+        "recovery_code": loosely_typed
+    }))
+    .await;
+    assert_eq!(response.status(), 200);
+
+    let challenge_token = start_challenge(&app, &email).await;
+    let response = verify(&app, serde_json::json!({
+        "challenge_token": challenge_token,
+        "recovery_code": recovery_codes[0]
+    }))
+    .await;
+    assert_eq!(response.status(), 401);
+
+    // Exactly one kind of proof is required
+    let response = verify(&app, serde_json::json!({ "challenge_token": challenge_token })).await;
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn test_disable_two_factor_requires_valid_code() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let email = register(&app).await;
+    let token = login_token(&app, &email).await;
+    let (secret, _) = enable_two_factor(&app, &token).await;
+
+    let response = post(&app, "/api/v1/auth/two-factor/disable", &token, serde_json::json!({ "code": "000000" })).await;
+    assert_eq!(response.status(), 400);
+
+    let response = post(
+        &app,
+        "/api/v1/auth/two-factor/disable",
+        &token,
+        serde_json::json!({ "code": code_at(&secret, 0) }),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+
+    // Password logins issue a session directly again
+    login_token(&app, &email).await;
+}
+
+#[tokio::test]
+async fn test_workspace_two_factor_policy() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let owner_email = register(&app).await;
+    let owner_token = login_token(&app, &owner_email).await;
+    let workspace_id = create_workspace(&app, &owner_token, "2FA Policy").await;
+    let policy_url = app.url(&format!("/api/v1/workspaces/{}/two-factor-policy", workspace_id));
+
+    let member_email = register(&app).await;
+    let member_token = login_token(&app, &member_email).await;
+    let response = post(
+        &app,
+        &format!("/api/v1/workspaces/{}/members", workspace_id),
+        &owner_token,
+        serde_json::json!({ "email": member_email, "role_name": "member" }),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+
+    // The owner must protect their own account first
+    let response = app
+        .client
+        .patch(&policy_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&serde_json::json!({ "require_two_factor": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    enable_two_factor(&app, &owner_token).await;
+    let response = app
+        .client
+        .patch(&policy_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .json(&serde_json::json!({ "require_two_factor": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["workspace"]["require_two_factor"], true);
+
+    let workspace_url = app.url(&format!("/api/v1/workspaces/{}", workspace_id));
+    let response = app
+        .client
+        .get(&workspace_url)
+        .header("Authorization", format!("Bearer {}", member_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "TWO_FACTOR_REQUIRED");
+
+    enable_two_factor(&app, &member_token).await;
+    let response = app
+        .client
+        .get(&workspace_url)
+        .header("Authorization", format!("Bearer {}", member_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn test_admin_can_reset_member_two_factor() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let domain = format!("reset{}.example.com", rand::random::<u32>());
+    let owner_email = register_verified_at(&app, &domain).await;
+    let owner_token = login_token(&app, &owner_email).await;
+    let workspace_id = create_workspace(&app, &owner_token, "2FA Reset").await;
+    let response = post(
+        &app,
+        &format!("/api/v1/workspaces/{}/sso-domains", workspace_id),
+        &owner_token,
+        serde_json::json!({ "domain": domain, "role_name": "member" }),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+
+    let member_email = register_verified_at(&app, &domain).await;
+    let member_token = login_token(&app, &member_email).await;
+    let response = post(
+        &app,
+        &format!("/api/v1/workspaces/{}/members", workspace_id),
+        &owner_token,
+        serde_json::json!({ "email": member_email, "role_name": "member" }),
+    )
+    .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    let member_id = body["member"]["user_id"].as_str().unwrap().to_string();
+    enable_two_factor(&app, &member_token).await;
+
+    let owner_id: serde_json::Value = app
+        .client
+        .get(&app.url("/api/v1/auth/me"))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let owner_id = owner_id["user"]["id"].as_str().unwrap().to_string();
+
+    // Members cannot reset anyone, and nobody can reset the owner through this endpoint
+    let response = app
+        .client
+        .delete(&app.url(&format!("/api/v1/workspaces/{}/members/{}/two-factor", workspace_id, owner_id)))
+        .header("Authorization", format!("Bearer {}", member_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    // The domain only counts once verified through DNS, which the SSO tests cover
+    let reset_url = app.url(&format!("/api/v1/workspaces/{}/members/{}/two-factor", workspace_id, member_id));
+    let response = app
+        .client
+        .delete(&reset_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    sqlx::query("UPDATE workspace_sso_domains SET verified_at = NOW() WHERE domain = $1")
+        .bind(&domain)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let response = app
+        .client
+        .delete(&reset_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // Nothing left to reset, and the member logs in with the password alone again
+    let response = app
+        .client
+        .delete(&reset_url)
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+    login_token(&app, &member_email).await;
+}
+
+#[tokio::test]
+async fn test_workspace_cannot_reset_foreign_member_two_factor() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let owner_email = register(&app).await;
+    let owner_token = login_token(&app, &owner_email).await;
+    let workspace_id = create_workspace(&app, &owner_token, "2FA Takeover").await;
+
+    // Members can be added without their consent, so membership alone grants nothing,
+    // and neither does claiming their domain without proving it through DNS
+    let domain = format!("takeover{}.example.com", rand::random::<u32>());
+    let member_email = register_verified_at(&app, &domain).await;
+    let member_token = login_token(&app, &member_email).await;
+    enable_two_factor(&app, &member_token).await;
+    let response = post(
+        &app,
+        &format!("/api/v1/workspaces/{}/members", workspace_id),
+        &owner_token,
+        serde_json::json!({ "email": member_email, "role_name": "member" }),
+    )
+    .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    let member_id = body["member"]["user_id"].as_str().unwrap().to_string();
+    let response = post(
+        &app,
+        &format!("/api/v1/workspaces/{}/sso-domains", workspace_id),
+        &owner_token,
+        serde_json::json!({ "domain": domain, "role_name": "member" }),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+
+    let response = app
+        .client
+        .delete(&app.url(&format!("/api/v1/workspaces/{}/members/{}/two-factor", workspace_id, member_id)))
+        .header("Authorization", format!("Bearer {}", owner_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    // The member keeps 2FA and their session
+    start_challenge(&app, &member_email).await;
+    let response = app
+        .client
+        .get(&app.url("/api/v1/auth/me"))
+        .header("Authorization", format!("Bearer {}", member_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
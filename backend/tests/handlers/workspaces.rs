@@ -307,6 +307,266 @@ async fn test_update_workspace_validates_empty_name() {
     assert_eq!(response.status(), 400);
 }
 
+// ============================================================================
+// UPDATE AI FALLBACK MODELS TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_update_ai_fallback_models_returns_200_for_owner() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Fallback Workspace").await;
+    let url = app.url(&format!("/api/v1/workspaces/{}/ai-fallback-models", workspace_id));
+
+    let response = app
+        .client
+        .patch(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({
+            "fallback_models": ["OpenRouter:anthropic/claude-3.5-sonnet", "openai:gpt-5-nano"]
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(
+        body["workspace"]["ai_fallback_models"],
+        serde_json::json!(["openrouter:anthropic/claude-3.5-sonnet", "openai:gpt-5-nano"])
+    );
+
+    // An empty list turns fallback off
+    let response = app
+        .client
+        .patch(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({ "fallback_models": [] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["workspace"]["ai_fallback_models"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn test_update_ai_fallback_models_validates_models() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Fallback Workspace").await;
+    let url = app.url(&format!("/api/v1/workspaces/{}/ai-fallback-models", workspace_id));
+
+    for fallback_models in [
+        serde_json::json!(["gpt-5-nano"]),
+        serde_json::json!(["anthropic:claude-3.5-sonnet"]),
+        serde_json::json!(["openai:"]),
+        serde_json::json!(["openai:gpt-5-nano", "OPENAI:gpt-5-nano"]),
+        serde_json::json!(["openai:a", "openai:b", "openai:c", "openai:d", "openai:e", "openai:f"]),
+    ] {
+        let response = app
+            .client
+            .patch(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&serde_json::json!({ "fallback_models": fallback_models }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400, "{} should be rejected", fallback_models);
+    }
+}
+
+#[tokio::test]
+async fn test_update_ai_fallback_models_returns_403_for_non_member() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token1 = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token1, "Owner Workspace").await;
+    let token2 = register_and_login(&app).await;
+
+    let response = app
+        .client
+        .patch(&app.url(&format!("/api/v1/workspaces/{}/ai-fallback-models", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token2))
+        .json(&serde_json::json!({ "fallback_models": ["openai:gpt-5-nano"] }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 403);
+}
+
+// ============================================================================
+// TOOL POLICY TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn test_tool_policy_round_trip() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Policy Workspace").await;
+    let url = app.url(&format!("/api/v1/workspaces/{}/tool-policy", workspace_id));
+
+    // No rules until a policy is set
+    let response = app
+        .client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["tool_policy"]["rules"], serde_json::json!([]));
+    assert!(body["tool_policy"]["updated_at"].is_null());
+
+    let rules = serde_json::json!([
+        { "tool": "rm", "arguments": { "path": "/projects/**" }, "action": "ask", "reason": "Projects are shared" },
+        { "tool": "web_fetch", "arguments": { "url": "https://docs.rs/**" }, "action": "allow" },
+        { "tool": "web_fetch", "action": "ask" },
+        { "tool": "mcp__*", "action": "deny" }
+    ]);
+    let response = app
+        .client
+        .put(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({ "rules": rules }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["tool_policy"]["rules"], rules);
+
+    let response = app
+        .client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["tool_policy"]["rules"], rules);
+    assert!(body["tool_policy"]["updated_at"].is_string());
+}
+
+#[tokio::test]
+async fn test_update_tool_policy_validates_rules() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Policy Workspace").await;
+    let url = app.url(&format!("/api/v1/workspaces/{}/tool-policy", workspace_id));
+
+    let too_many: Vec<_> = (0..101).map(|_| serde_json::json!({ "tool": "rm", "action": "ask" })).collect();
+    for rules in [
+        serde_json::json!([{ "tool": " ", "action": "ask" }]),
+        serde_json::json!([{ "tool": "rm", "action": "ask", "reason": "x".repeat(501) }]),
+        serde_json::json!([{ "tool": "rm", "arguments": { "path": "/".repeat(501) }, "action": "deny" }]),
+        serde_json::json!(too_many),
+    ] {
+        let response = app
+            .client
+            .put(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&serde_json::json!({ "rules": rules }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400, "{} should be rejected", rules);
+    }
+
+    // Unknown actions do not deserialize
+    let response = app
+        .client
+        .put(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({ "rules": [{ "tool": "rm", "action": "maybe" }] }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_client_error());
+}
+
+#[tokio::test]
+async fn test_tool_policy_returns_403_for_non_member() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token1 = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token1, "Owner Workspace").await;
+    let token2 = register_and_login(&app).await;
+    let url = app.url(&format!("/api/v1/workspaces/{}/tool-policy", workspace_id));
+
+    let response = app
+        .client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", token2))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    let response = app
+        .client
+        .put(&url)
+        .header("Authorization", format!("Bearer {}", token2))
+        .json(&serde_json::json!({ "rules": [] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+}
+
+/// Sets a workspace's tool policy as its owner
+async fn set_tool_policy(app: &TestApp, token: &str, workspace_id: &str, rules: serde_json::Value) {
+    let response = app
+        .client
+        .put(&app.url(&format!("/api/v1/workspaces/{}/tool-policy", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({ "rules": rules }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn test_tools_api_enforces_tool_policy() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Policy Enforcement").await;
+    set_tool_policy(&app, &token, &workspace_id, serde_json::json!([
+        { "tool": "write", "arguments": { "path": "/projects/**" }, "action": "deny", "reason": "Projects are shared" },
+        { "tool": "rm", "action": "ask" }
+    ]))
+    .await;
+
+    let execute = |tool: &str, args: serde_json::Value| {
+        app.client
+            .post(&app.url(&format!("/api/v1/workspaces/{}/tools", workspace_id)))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&serde_json::json!({ "tool": tool, "args": args }))
+            .send()
+    };
+
+    let response = execute("write", serde_json::json!({ "path": "/projects/a.md", "content": "x" })).await.unwrap();
+    assert_eq!(response.status(), 403);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("Projects are shared"), "{}", body);
+
+    // Paths are matched as the tool normalizes them
+    for path in ["projects/a.md", "//projects/a.md", "/notes/../projects/a.md"] {
+        let response = execute("write", serde_json::json!({ "path": path, "content": "x" })).await.unwrap();
+        assert_eq!(response.status(), 403, "{}", path);
+    }
+
+    // Nobody can approve a call outside of a chat, so `ask` refuses it
+    let response = execute("write", serde_json::json!({ "path": "/notes.md", "content": "x" })).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let response = execute("rm", serde_json::json!({ "path": "/notes.md" })).await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    let response = execute("read", serde_json::json!({ "path": "/notes.md" })).await.unwrap();
+    assert_eq!(response.status(), 200);
+}
+
 // ============================================================================
 // DELETE WORKSPACE TESTS
 // ============================================================================
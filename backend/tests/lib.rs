@@ -8,4 +8,5 @@ pub mod handlers;
 pub mod tools;
 pub mod services;
 pub mod chat;
-pub mod agent_sessions;
\ No newline at end of file
+pub mod agent_sessions;
+pub mod api_keys;
@@ -8,12 +8,14 @@
 //! - Enforces security (workspace isolation)
 
 use buildscale::models::chat::{ChatAttachment, ChatMessageMetadata, ChatMessageRole};
-use buildscale::models::files::FileType;
+use buildscale::models::files::{FileType, ImageContent};
 use buildscale::models::requests::CreateFileRequest;
 use buildscale::queries::chat;
-use buildscale::services::chat::{AttachmentKey, ChatService, DEFAULT_CONTEXT_TOKEN_LIMIT};
+use buildscale::services::chat::rig_engine::RigService;
+use buildscale::services::chat::{compaction, AttachmentKey, ChatService, DEFAULT_CONTEXT_TOKEN_LIMIT};
 use buildscale::services::files::create_file_with_content;
 use buildscale::services::storage::FileStorageService;
+use buildscale::services::tokens::TokenCounter;
 use buildscale::load_config;
 use crate::common::database::TestApp;
 
@@ -51,6 +53,8 @@ async fn test_build_context_with_persona() {
     // Should contain system persona
     assert!(context.persona.contains("BuildScale AI"));
     assert!(context.persona.contains("professional software engineering assistant"));
+    // The base persona is kept apart for agents that append the skill catalog themselves
+    assert_eq!(context.base_persona, "You are BuildScale AI, a professional software engineering assistant.");
 }
 
 #[tokio::test]
@@ -545,3 +549,333 @@ async fn test_build_context_fragment_ordering() {
         _ => panic!("Expected WorkspaceFile key"),
     }
 }
+
+#[tokio::test]
+async fn test_build_context_starts_at_compaction_summary() {
+    let test_app = TestApp::new("test_build_context_starts_at_compaction_summary").await;
+    let mut conn = test_app.get_connection().await;
+    let storage = FileStorageService::new(&load_config().unwrap().storage.base_path);
+
+    let (user, workspace) = test_app.create_test_workspace_with_user().await.unwrap();
+
+    let chat_request = CreateFileRequest {
+        workspace_id: workspace.id,
+        parent_id: None,
+        author_id: user.id,
+        name: "test_chat".to_string(),
+        slug: None,
+        path: None,
+        is_virtual: Some(true),
+        is_remote: None,
+        permission: None,
+        file_type: FileType::Chat,
+        content: serde_json::json!({}),
+        app_data: None,
+    };
+    let chat = create_file_with_content(&mut conn, &storage, chat_request)
+        .await
+        .expect("Failed to create chat file");
+
+    let mut messages = Vec::new();
+    for (role, content) in [
+        (ChatMessageRole::User, "Old question"),
+        (ChatMessageRole::Assistant, "Old answer"),
+        (ChatMessageRole::User, "Recent question"),
+        (ChatMessageRole::Assistant, "Recent answer"),
+    ] {
+        let message = chat::insert_chat_message(
+            &mut conn,
+            buildscale::models::chat::NewChatMessage {
+                file_id: chat.file.id,
+                workspace_id: workspace.id,
+                role,
+                content: content.to_string(),
+                metadata: sqlx::types::Json(ChatMessageMetadata::default()),
+            },
+        )
+        .await
+        .expect("Failed to insert message");
+        messages.push(message);
+    }
+
+    // Summary of the first turn, placed right after it
+    let summary = ChatService::save_message_at(
+        &mut conn,
+        &storage,
+        workspace.id,
+        buildscale::models::chat::NewChatMessage {
+            file_id: chat.file.id,
+            workspace_id: workspace.id,
+            role: ChatMessageRole::System,
+            content: "- The user asked an old question".to_string(),
+            metadata: sqlx::types::Json(ChatMessageMetadata {
+                message_type: Some(compaction::COMPACTION_SUMMARY_MESSAGE_TYPE.to_string()),
+                ..Default::default()
+            }),
+        },
+        messages[1].created_at + chrono::Duration::microseconds(1),
+    )
+    .await
+    .expect("Failed to save summary");
+
+    let context = ChatService::build_context(&mut conn, &storage, workspace.id, chat.file.id, "You are BuildScale AI.", 4000, true)
+        .await
+        .expect("Failed to build context");
+
+    // The summary replaces the first turn, the last message is the prompt
+    let contents: Vec<&str> = context.history.iter().map(|m| m.content.as_str()).collect();
+    assert_eq!(contents, vec!["- The user asked an old question", "Recent question"]);
+    assert_eq!(context.history[0].id, summary.id);
+
+    // The summarized messages stay in the chat
+    let all = chat::get_messages_by_file_id(&mut conn, workspace.id, chat.file.id).await.unwrap();
+    assert_eq!(all.len(), 5);
+    assert_eq!(all[2].id, summary.id);
+
+    // Too little history after the summary to compact it again
+    let config = buildscale::config::CompactionConfig { keep_recent_messages: 2, ..Default::default() };
+    let result = compaction::compact_chat(
+        &mut conn,
+        &storage,
+        &RigService::dummy(),
+        &config,
+        workspace.id,
+        chat.file.id,
+        DEFAULT_CONTEXT_TOKEN_LIMIT,
+        true,
+    )
+    .await
+    .expect("Failed to compact chat");
+    assert!(!result.compacted);
+    assert_eq!(result.tokens_before, result.tokens_after);
+}
+
+#[tokio::test]
+async fn test_build_context_budgets_with_model_tokenizer() {
+    let test_app = TestApp::new("test_build_context_budgets_with_model_tokenizer").await;
+    let mut conn = test_app.get_connection().await;
+    let storage = FileStorageService::new(&load_config().unwrap().storage.base_path);
+
+    let (user, workspace) = test_app.create_test_workspace_with_user().await.unwrap();
+
+    let chat_request = CreateFileRequest {
+        workspace_id: workspace.id,
+        parent_id: None,
+        author_id: user.id,
+        name: "gemini_chat".to_string(),
+        slug: None,
+        path: None,
+        is_virtual: Some(true),
+        is_remote: None,
+        permission: None,
+        file_type: FileType::Chat,
+        content: serde_json::json!({}),
+        app_data: Some(serde_json::json!({ "model": "openrouter:google/gemini-2.5-flash-lite" })),
+    };
+    let chat = create_file_with_content(&mut conn, &storage, chat_request)
+        .await
+        .expect("Failed to create chat file");
+
+    for content in ["Summarize the release notes", "The notes list three fixes."] {
+        chat::insert_chat_message(
+            &mut conn,
+            buildscale::models::chat::NewChatMessage {
+                file_id: chat.file.id,
+                workspace_id: workspace.id,
+                role: ChatMessageRole::User,
+                content: content.to_string(),
+                metadata: sqlx::types::Json(ChatMessageMetadata::default()),
+            },
+        )
+        .await
+        .expect("Failed to insert message");
+    }
+
+    let context = ChatService::build_context(&mut conn, &storage, workspace.id, chat.file.id, "You are BuildScale AI.", 4000, false)
+        .await
+        .expect("Failed to build context");
+
+    // The limit is the model's context window, not the fallback
+    assert_eq!(context.token_limit, 1_048_576);
+    assert_eq!(context.token_counter, TokenCounter::for_model("openrouter:google/gemini-2.5-flash-lite"));
+
+    let expected: usize = context.history.iter().map(|msg| context.token_counter.count(&msg.content)).sum();
+    assert_eq!(context.history.estimate_tokens(), expected);
+    assert!(expected > 0);
+}
+
+#[tokio::test]
+async fn test_build_context_sends_attached_images_as_images() {
+    let test_app = TestApp::new("test_build_context_sends_attached_images_as_images").await;
+    let mut conn = test_app.get_connection().await;
+    let storage = FileStorageService::new(&load_config().unwrap().storage.base_path);
+
+    let (user, workspace) = test_app.create_test_workspace_with_user().await.unwrap();
+
+    let chat_request = CreateFileRequest {
+        workspace_id: workspace.id,
+        parent_id: None,
+        author_id: user.id,
+        name: "image_chat".to_string(),
+        slug: None,
+        path: None,
+        is_virtual: Some(true),
+        is_remote: None,
+        permission: None,
+        file_type: FileType::Chat,
+        content: serde_json::json!({}),
+        app_data: None,
+    };
+    let chat = create_file_with_content(&mut conn, &storage, chat_request)
+        .await
+        .expect("Failed to create chat file");
+
+    // An image file in the workspace, larger than models are sent
+    let file_request = CreateFileRequest {
+        workspace_id: workspace.id,
+        parent_id: None,
+        author_id: user.id,
+        name: "diagram.png".to_string(),
+        slug: None,
+        path: Some("/diagram.png".to_string()),
+        is_virtual: None,
+        is_remote: None,
+        permission: None,
+        file_type: FileType::Document,
+        content: serde_json::json!("placeholder"),
+        app_data: None,
+    };
+    let file = create_file_with_content(&mut conn, &storage, file_request)
+        .await
+        .expect("Failed to create file");
+    let mut png = Vec::new();
+    image::RgbImage::new(3200, 1600)
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    std::fs::write(storage.get_workspace_path(workspace.id).join("diagram.png"), &png).unwrap();
+
+    let pasted = ImageContent {
+        media_type: "image/jpeg".to_string(),
+        width: 4,
+        height: 3,
+        data: "AAAA".to_string(),
+    };
+    chat::insert_chat_message(
+        &mut conn,
+        buildscale::models::chat::NewChatMessage {
+            file_id: chat.file.id,
+            workspace_id: workspace.id,
+            role: ChatMessageRole::User,
+            content: "What do these show?".to_string(),
+            metadata: sqlx::types::Json(ChatMessageMetadata {
+                attachments: vec![
+                    ChatAttachment::File {
+                        file_id: file.file.id,
+                        version_id: None,
+                    },
+                    ChatAttachment::Image {
+                        name: Some("screenshot.jpg".to_string()),
+                        image: pasted.clone(),
+                    },
+                ],
+                ..Default::default()
+            }),
+        },
+    )
+    .await
+    .expect("Failed to insert message with images");
+
+    let context = ChatService::build_context(&mut conn, &storage, workspace.id, chat.file.id, "You are BuildScale AI.", 4000, true)
+        .await
+        .expect("Failed to build context");
+
+    // Images are not rendered as text attachments
+    assert!(context.attachment_manager.map.is_empty());
+    assert_eq!(context.images.len(), 2);
+
+    assert_eq!(context.images[0].name, "/diagram.png");
+    assert_eq!(context.images[0].image.media_type, "image/png");
+    assert_eq!((context.images[0].image.width, context.images[0].image.height), (1568, 784));
+
+    assert_eq!(context.images[1].name, "screenshot.jpg");
+    assert_eq!(context.images[1].image, pasted);
+}
+
+#[tokio::test]
+async fn test_build_context_fetches_attached_web_pages() {
+    let test_app = TestApp::new("test_build_context_fetches_attached_web_pages").await;
+    let mut conn = test_app.get_connection().await;
+    let storage = FileStorageService::new(&load_config().unwrap().storage.base_path);
+    let server = crate::common::mock_web::MockWebServer::start().await;
+
+    let (user, workspace) = test_app.create_test_workspace_with_user().await.unwrap();
+
+    let chat_request = CreateFileRequest {
+        workspace_id: workspace.id,
+        parent_id: None,
+        author_id: user.id,
+        name: "url_chat".to_string(),
+        slug: None,
+        path: None,
+        is_virtual: Some(true),
+        is_remote: None,
+        permission: None,
+        file_type: FileType::Chat,
+        content: serde_json::json!({}),
+        app_data: None,
+    };
+    let chat = create_file_with_content(&mut conn, &storage, chat_request)
+        .await
+        .expect("Failed to create chat file");
+
+    let article_url = server.url("/article");
+    let missing_url = server.url("/missing");
+    chat::insert_chat_message(
+        &mut conn,
+        buildscale::models::chat::NewChatMessage {
+            file_id: chat.file.id,
+            workspace_id: workspace.id,
+            role: ChatMessageRole::User,
+            content: "Summarize these".to_string(),
+            metadata: sqlx::types::Json(ChatMessageMetadata {
+                attachments: vec![
+                    ChatAttachment::Url {
+                        url: article_url.clone(),
+                        title: None,
+                        snapshot_file_id: None,
+                    },
+                    ChatAttachment::Url {
+                        url: missing_url.clone(),
+                        title: None,
+                        snapshot_file_id: None,
+                    },
+                ],
+                ..Default::default()
+            }),
+        },
+    )
+    .await
+    .expect("Failed to insert message with URLs");
+
+    let context = ChatService::build_context(&mut conn, &storage, workspace.id, chat.file.id, "You are BuildScale AI.", 4000, true)
+        .await
+        .expect("Failed to build context");
+
+    let article_key = AttachmentKey::WebPage(buildscale::services::web_pages::page_id(&article_url));
+    let article = context.attachment_manager.map.get(&article_key).expect("Article should be attached");
+    assert!(article.content.starts_with(&format!("URL: {}\nTitle: Release notes\n", article_url)));
+    assert!(article.content.contains("streaming uploads"));
+    assert!(article.tokens > 0);
+    assert!(context.attachment_manager.render().contains("<url_context>\nURL: "));
+
+    // Pages that cannot be fetched are still listed for the model
+    let missing_key = AttachmentKey::WebPage(buildscale::services::web_pages::page_id(&missing_url));
+    let missing = context.attachment_manager.map.get(&missing_key).expect("Missing page should be listed");
+    assert!(missing.content.contains("could not be fetched"), "{}", missing.content);
+
+    // Building the context again uses the cached page
+    ChatService::build_context(&mut conn, &storage, workspace.id, chat.file.id, "You are BuildScale AI.", 4000, true)
+        .await
+        .expect("Failed to build context");
+    assert_eq!(server.article_requests(), 1);
+}
@@ -0,0 +1,70 @@
+use crate::common::TestApp;
+use buildscale::models::sse::SseEvent;
+use buildscale::services::chat::cluster::{run_listener, RemoteCommand};
+use buildscale::services::chat::registry::AgentRegistry;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{timeout, Duration};
+use uuid::Uuid;
+
+/// Events and commands cross instances over LISTEN/NOTIFY
+#[tokio::test]
+async fn test_cluster_relays_events_and_commands() {
+    let app = TestApp::new().await;
+    let node_a = Arc::new(AgentRegistry::with_cluster(app.pool.clone()));
+    let node_b = Arc::new(AgentRegistry::with_cluster(app.pool.clone()));
+    assert_ne!(node_a.node_id, node_b.node_id);
+
+    let (shutdown_tx, _) = broadcast::channel(1);
+    let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+    tokio::spawn(run_listener(app.pool.clone(), node_b.clone(), command_tx, shutdown_tx.subscribe()));
+
+    let chat_id = Uuid::now_v7();
+    let mut remote_events = node_b.subscribe_remote(chat_id).await.expect("Clustered registry");
+
+    // Events of a local actor on node A reach node B's SSE clients
+    let event_tx = node_a.get_or_create_bus(chat_id).await;
+    let relay = node_a.relay_events(chat_id).await.expect("Clustered registry");
+
+    // The listener needs a moment to subscribe
+    let text = "x".repeat(20_000);
+    let mut received = None;
+    for _ in 0..50 {
+        let _ = event_tx.send(SseEvent::Chunk { text: text.clone() });
+        if let Ok(Ok(event)) = timeout(Duration::from_millis(100), remote_events.recv()).await {
+            received = Some(event);
+            break;
+        }
+    }
+    let received = received.expect("Event should be relayed");
+    match received.event {
+        SseEvent::Chunk { text: relayed } => assert_eq!(relayed, text),
+        other => panic!("Unexpected event: {:?}", other),
+    }
+    relay.cancel();
+
+    // Commands reach the addressed node only
+    let command = RemoteCommand::Cancel { reason: "user_cancelled".to_string() };
+    node_a.send_remote_command(Uuid::now_v7(), chat_id, command.clone()).await.unwrap();
+    node_a.send_remote_command(node_b.node_id, chat_id, command.clone()).await.unwrap();
+
+    let (received_chat, received_command) = timeout(Duration::from_secs(5), command_rx.recv())
+        .await
+        .expect("Command should arrive")
+        .unwrap();
+    assert_eq!(received_chat, chat_id);
+    assert_eq!(received_command, command);
+    assert!(command_rx.try_recv().is_err());
+
+    let _ = shutdown_tx.send(());
+}
+
+/// Single-instance registries keep everything local
+#[tokio::test]
+async fn test_single_instance_registry_is_not_clustered() {
+    let registry = AgentRegistry::new();
+    assert!(registry.node_id.is_nil());
+    assert!(!registry.is_clustered());
+    assert!(registry.subscribe_remote(Uuid::now_v7()).await.is_none());
+    assert!(registry.relay_events(Uuid::now_v7()).await.is_none());
+}
@@ -0,0 +1,385 @@
+//! Tests running the whole agent loop of a chat offline, against recorded completions.
+
+use crate::common::database::TestApp;
+use buildscale::{
+    config::{AiConfig, MockProviderConfig},
+    load_config,
+    models::chat::{ChatMessage, ChatMessageMetadata, ChatMessageRole, NewChatMessage},
+    models::sse::SseEvent,
+    models::tool_policy::{ToolPolicyAction, ToolPolicyRule},
+    queries,
+    services::chat::actor::{ChatActor, ChatActorArgs},
+    services::chat::registry::{AgentCommand, AgentRegistry},
+    services::chat::rig_engine::RigService,
+    services::chat::ChatService,
+    services::storage::FileStorageService,
+};
+use std::sync::Arc;
+use tokio::time::{timeout, Duration};
+use uuid::Uuid;
+
+/// Fixtures of the mock provider
+pub const FIXTURES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/llm");
+
+pub fn mock_rig_service() -> RigService {
+    let mut config = AiConfig::default();
+    config.providers.mock = Some(MockProviderConfig {
+        fixtures_dir: FIXTURES_DIR.to_string(),
+        record: false,
+    });
+    config.providers.default_provider = "mock".to_string();
+    RigService::from_config(&config).expect("Mock provider should be enough")
+}
+
+/// Creates a chat on `model` with one user message, runs its turn and returns the
+/// events sent until the turn is done
+async fn run_mock_turn(
+    test_app: &TestApp,
+    workspace_id: Uuid,
+    user_id: Uuid,
+    model: &str,
+    prompt: &str,
+) -> (Uuid, Vec<SseEvent>) {
+    let registry = Arc::new(AgentRegistry::new());
+    run_mock_turn_answering(test_app, &registry, workspace_id, user_id, model, prompt, |_, _| {}).await
+}
+
+/// Runs a turn like [`run_mock_turn`], passing the chat id and each event to
+/// `on_event` as it is sent
+async fn run_mock_turn_answering(
+    test_app: &TestApp,
+    registry: &Arc<AgentRegistry>,
+    workspace_id: Uuid,
+    user_id: Uuid,
+    model: &str,
+    prompt: &str,
+    on_event: impl FnMut(Uuid, &SseEvent),
+) -> (Uuid, Vec<SseEvent>) {
+    let (chat_id, received) =
+        run_mock_turn_events(test_app, registry, workspace_id, user_id, model, prompt, on_event).await;
+    if let Some(SseEvent::Error { message }) = received.last() {
+        panic!("Turn failed: {}", message);
+    }
+    (chat_id, received)
+}
+
+/// Runs a turn like [`run_mock_turn_answering`], returning the events sent until
+/// the turn is done or failed, the `Error` event last if it failed
+async fn run_mock_turn_events(
+    test_app: &TestApp,
+    registry: &Arc<AgentRegistry>,
+    workspace_id: Uuid,
+    user_id: Uuid,
+    model: &str,
+    prompt: &str,
+    mut on_event: impl FnMut(Uuid, &SseEvent),
+) -> (Uuid, Vec<SseEvent>) {
+    let mut conn = test_app.get_connection().await;
+    let storage = Arc::new(FileStorageService::new(&load_config().unwrap().storage.base_path));
+
+    let chat = ChatService::create_chat_file(
+        &mut conn,
+        workspace_id,
+        user_id,
+        format!("Chat: {}", prompt),
+        serde_json::json!({
+            "model": model,
+            "mode": "chat",
+            "temperature": 0.7,
+            "persona": "test persona",
+        }),
+    )
+    .await
+    .unwrap();
+    ChatService::save_message(&mut conn, &storage, workspace_id, NewChatMessage {
+        file_id: chat.id,
+        workspace_id,
+        role: ChatMessageRole::User,
+        content: prompt.to_string(),
+        metadata: sqlx::types::Json(ChatMessageMetadata::default()),
+    })
+    .await
+    .unwrap();
+
+    let event_tx = registry.get_or_create_bus(chat.id).await;
+    let mut events = event_tx.subscribe();
+    let handle = ChatActor::spawn(ChatActorArgs {
+        chat_id: chat.id,
+        workspace_id,
+        user_id,
+        pool: test_app.test_db.pool.clone(),
+        rig_service: Arc::new(mock_rig_service()),
+        storage,
+        registry: registry.clone(),
+        default_persona: "test persona".to_string(),
+        default_context_token_limit: 128_000,
+        event_tx,
+        inactivity_timeout: Duration::from_secs(30),
+    });
+    handle.command_tx.send(AgentCommand::ProcessInteraction { user_id }).await.unwrap();
+
+    let mut received = Vec::new();
+    loop {
+        let event = timeout(Duration::from_secs(30), events.recv())
+            .await
+            .expect("Turn should complete")
+            .unwrap();
+        on_event(chat.id, &event);
+        match event {
+            SseEvent::Done { .. } => break,
+            SseEvent::Error { .. } => {
+                received.push(event);
+                break;
+            }
+            event => received.push(event),
+        }
+    }
+    (chat.id, received)
+}
+
+/// Final answer of a chat
+async fn final_answer(test_app: &TestApp, workspace_id: Uuid, chat_id: Uuid) -> Option<ChatMessage> {
+    let mut conn = test_app.get_connection().await;
+    let messages = queries::chat::get_messages_by_file_id(&mut conn, workspace_id, chat_id).await.unwrap();
+    messages
+        .into_iter()
+        .rev()
+        .find(|message| message.role == ChatMessageRole::Assistant && message.metadata.message_type.is_none())
+}
+
+#[tokio::test]
+async fn test_mock_provider_runs_tool_loop() {
+    let test_app = TestApp::new("test_mock_provider_runs_tool_loop").await;
+    let mut conn = test_app.get_connection().await;
+    let (user, workspace) = test_app.create_test_workspace_with_user().await.unwrap();
+
+    let (chat_id, received) = run_mock_turn(&test_app, workspace.id, user.id, "mock:write_note", "Write a hello note").await;
+
+    // The recorded tool call ran against the workspace
+    assert!(received.iter().any(|event| matches!(event, SseEvent::Call { tool, .. } if tool == "write")));
+    assert!(received.iter().any(|event| matches!(event, SseEvent::Observation { success: true, .. })));
+    let note = queries::files::get_file_by_path(&mut conn, workspace.id, "/notes/hello.md").await.unwrap();
+    assert!(note.is_some(), "The write tool should have created the note");
+
+    // and the final answer was saved
+    let answer = final_answer(&test_app, workspace.id, chat_id).await;
+    assert_eq!(answer.map(|message| message.content), Some("I wrote the note to /notes/hello.md.".to_string()));
+}
+
+#[tokio::test]
+async fn test_rate_limited_call_is_retried() {
+    let test_app = TestApp::new("test_rate_limited_call_is_retried").await;
+    let (user, workspace) = test_app.create_test_workspace_with_user().await.unwrap();
+
+    let (chat_id, received) = run_mock_turn(&test_app, workspace.id, user.id, "mock:rate_limited", "Hello").await;
+
+    // The 429 was retried on the same model
+    assert!(!received.iter().any(|event| matches!(event, SseEvent::ModelFallback { .. })));
+    let answer = final_answer(&test_app, workspace.id, chat_id).await.unwrap();
+    assert_eq!(answer.content, "Answered once the rate limit cleared.");
+    assert_eq!(answer.metadata.model.as_deref(), Some("mock:rate_limited"));
+    assert_eq!(answer.metadata.fallback_from, None);
+}
+
+#[tokio::test]
+async fn test_failing_model_falls_back_to_workspace_chain() {
+    let test_app = TestApp::new("test_failing_model_falls_back_to_workspace_chain").await;
+    let mut conn = test_app.get_connection().await;
+    let (user, workspace) = test_app.create_test_workspace_with_user().await.unwrap();
+    // A fixture that does not exist cannot be used, so the chain moves past it
+    queries::workspaces::set_ai_fallback_models(
+        &mut conn,
+        workspace.id,
+        &["mock:missing_fixture".to_string(), "mock:write_note".to_string()],
+    )
+    .await
+    .unwrap();
+
+    let (chat_id, received) = run_mock_turn(&test_app, workspace.id, user.id, "mock:unavailable", "Write a hello note").await;
+
+    let fallbacks: Vec<(&str, &str)> = received
+        .iter()
+        .filter_map(|event| match event {
+            SseEvent::ModelFallback { from_model, to_model, .. } => Some((from_model.as_str(), to_model.as_str())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(fallbacks, vec![("mock:unavailable", "mock:write_note")]);
+
+    // The fallback model finished the turn, and the answer records the switch
+    let note = queries::files::get_file_by_path(&mut conn, workspace.id, "/notes/hello.md").await.unwrap();
+    assert!(note.is_some(), "The fallback model should have written the note");
+    let answer = final_answer(&test_app, workspace.id, chat_id).await.unwrap();
+    assert_eq!(answer.content, "I wrote the note to /notes/hello.md.");
+    assert_eq!(answer.metadata.model.as_deref(), Some("mock:write_note"));
+    assert_eq!(answer.metadata.fallback_from.as_deref(), Some("mock:unavailable"));
+}
+
+#[tokio::test]
+async fn test_failure_after_tool_call_is_not_retried() {
+    let test_app = TestApp::new("test_failure_after_tool_call_is_not_retried").await;
+    let mut conn = test_app.get_connection().await;
+    let (user, workspace) = test_app.create_test_workspace_with_user().await.unwrap();
+    queries::workspaces::set_ai_fallback_models(&mut conn, workspace.id, &["mock:write_note".to_string()])
+        .await
+        .unwrap();
+
+    let registry = Arc::new(AgentRegistry::new());
+    let (_, received) = run_mock_turn_events(
+        &test_app,
+        &registry,
+        workspace.id,
+        user.id,
+        "mock:unavailable_after_tool_call",
+        "Write a hello note",
+        |_, _| {},
+    )
+    .await;
+
+    // The 503 came after the write ran, so the turn fails rather than starting over
+    assert!(matches!(received.last(), Some(SseEvent::Error { message }) if message.contains("503")));
+    assert!(!received.iter().any(|event| matches!(event, SseEvent::ModelFallback { .. })));
+    let writes = received
+        .iter()
+        .filter(|event| matches!(event, SseEvent::Call { tool, .. } if tool == "write"))
+        .count();
+    assert_eq!(writes, 1, "The write should have run once");
+}
+
+#[tokio::test]
+async fn test_tool_calls_of_a_response_keep_their_order() {
+    let test_app = TestApp::new("test_tool_calls_of_a_response_keep_their_order").await;
+    let mut conn = test_app.get_connection().await;
+    let (user, workspace) = test_app.create_test_workspace_with_user().await.unwrap();
+
+    let (chat_id, received) = run_mock_turn(&test_app, workspace.id, user.id, "mock:parallel_reads", "What is on my todo list?").await;
+
+    // Each call is followed by its result, in the order the model made the calls,
+    // though the reads between the writes ran concurrently
+    let steps: Vec<String> = received
+        .iter()
+        .filter_map(|event| match event {
+            SseEvent::Call { tool, .. } => Some(tool.clone()),
+            SseEvent::Observation { success: true, .. } => Some("ok".to_string()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(steps, ["write", "ok", "read", "ok", "ls", "ok", "grep", "ok", "write", "ok"]);
+
+    // The reads ran after the write before them
+    let observations: Vec<&String> = received
+        .iter()
+        .filter_map(|event| match event {
+            SseEvent::Observation { output, .. } => Some(output),
+            _ => None,
+        })
+        .collect();
+    assert!(observations[1].contains("review the backlog"), "read should see the note: {}", observations[1]);
+    assert!(observations[2].contains("todo.md"), "ls should list the note: {}", observations[2]);
+    assert!(!observations[2].contains("done.md"), "ls should run before the last write: {}", observations[2]);
+
+    let done = queries::files::get_file_by_path(&mut conn, workspace.id, "/notes/done.md").await.unwrap();
+    assert!(done.is_some(), "The last write should have run");
+    let answer = final_answer(&test_app, workspace.id, chat_id).await;
+    assert_eq!(answer.map(|message| message.content), Some("The todo note lists one item.".to_string()));
+}
+
+/// Sets a workspace's tool policy to a single rule for calls of `tool` with `path`
+async fn set_path_rule(test_app: &TestApp, workspace_id: Uuid, user_id: Uuid, tool: &str, path: &str, action: ToolPolicyAction) {
+    let mut conn = test_app.get_connection().await;
+    let rule = ToolPolicyRule {
+        tool: tool.to_string(),
+        arguments: [("path".to_string(), path.to_string())].into(),
+        action,
+        reason: Some("Notes are reviewed".to_string()),
+    };
+    queries::tool_policies::set_tool_policy(&mut conn, workspace_id, &[rule], user_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_tool_call_waits_for_approval() {
+    let test_app = TestApp::new("test_tool_call_waits_for_approval").await;
+    let mut conn = test_app.get_connection().await;
+    let (user, workspace) = test_app.create_test_workspace_with_user().await.unwrap();
+    set_path_rule(&test_app, workspace.id, user.id, "write", "/notes/**", ToolPolicyAction::Ask).await;
+
+    let registry = Arc::new(AgentRegistry::new());
+    let approvals = registry.approvals.clone();
+    let (_, received) = run_mock_turn_answering(
+        &test_app,
+        &registry,
+        workspace.id,
+        user.id,
+        "mock:write_note",
+        "Write a hello note",
+        |chat_id, event| {
+            if let SseEvent::QuestionPending { question_id, .. } = event {
+                let (approvals, question_id) = (approvals.clone(), *question_id);
+                tokio::spawn(async move { approvals.answer(chat_id, question_id, true).await });
+            }
+        },
+    )
+    .await;
+
+    // The call was announced, waited for its approval, then ran
+    let steps: Vec<&str> = received
+        .iter()
+        .filter_map(|event| match event {
+            SseEvent::Call { .. } => Some("call"),
+            SseEvent::QuestionPending { questions, .. } => {
+                assert_eq!(questions[0].name, "approval");
+                assert!(questions[0].question.contains("/notes/hello.md"));
+                assert!(questions[0].question.contains("Notes are reviewed"));
+                Some("approval")
+            }
+            SseEvent::Observation { success: true, .. } => Some("ok"),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(steps, ["call", "approval", "ok"]);
+    let note = queries::files::get_file_by_path(&mut conn, workspace.id, "/notes/hello.md").await.unwrap();
+    assert!(note.is_some(), "The approved write should have run");
+}
+
+#[tokio::test]
+async fn test_refused_tool_calls_do_not_run() {
+    let test_app = TestApp::new("test_refused_tool_calls_do_not_run").await;
+    let mut conn = test_app.get_connection().await;
+    let (user, workspace) = test_app.create_test_workspace_with_user().await.unwrap();
+
+    // Denied by the user
+    set_path_rule(&test_app, workspace.id, user.id, "write", "/notes/*.md", ToolPolicyAction::Ask).await;
+    let registry = Arc::new(AgentRegistry::new());
+    let approvals = registry.approvals.clone();
+    let (_, received) = run_mock_turn_answering(
+        &test_app,
+        &registry,
+        workspace.id,
+        user.id,
+        "mock:write_note",
+        "Write a hello note",
+        |chat_id, event| {
+            if let SseEvent::QuestionPending { question_id, .. } = event {
+                let (approvals, question_id) = (approvals.clone(), *question_id);
+                tokio::spawn(async move { approvals.answer(chat_id, question_id, false).await });
+            }
+        },
+    )
+    .await;
+    assert!(received.iter().any(|event| matches!(
+        event,
+        SseEvent::Observation { success: false, output, .. } if output.contains("did not approve")
+    )));
+
+    // Denied by the policy, without asking
+    set_path_rule(&test_app, workspace.id, user.id, "write", "/notes/**", ToolPolicyAction::Deny).await;
+    let (_, received) = run_mock_turn(&test_app, workspace.id, user.id, "mock:write_note", "Write a hello note").await;
+    assert!(!received.iter().any(|event| matches!(event, SseEvent::QuestionPending { .. })));
+    assert!(received.iter().any(|event| matches!(
+        event,
+        SseEvent::Observation { success: false, output, .. } if output.contains("Notes are reviewed")
+    )));
+
+    let note = queries::files::get_file_by_path(&mut conn, workspace.id, "/notes/hello.md").await.unwrap();
+    assert!(note.is_none(), "Refused writes should not have run");
+}
@@ -2,4 +2,7 @@ mod build_context_tests;
 mod persistence_tests;
 mod actor_tests;
 mod cancellation_tests;
+mod cluster_tests;
+mod stream_events_tests;
+pub mod mock_provider_tests;
 mod rig_engine_tests;
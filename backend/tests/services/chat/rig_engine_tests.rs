@@ -20,6 +20,7 @@ fn test_rig_service_from_config_openai_only() {
             reasoning_effort: "low".to_string(),
         }),
         openrouter: None,
+        mock: None,
         default_provider: "openai".to_string(),
         default_model: "openai:gpt-5-mini".to_string(),
     };
@@ -48,6 +49,7 @@ fn test_rig_service_from_config_openrouter_only() {
             api_key,
             base_url: None,
         }),
+        mock: None,
         default_provider: "openrouter".to_string(),
         default_model: "openrouter:anthropic/claude-3.5-sonnet".to_string(),
     };
@@ -82,6 +84,7 @@ fn test_rig_service_from_config_both_providers() {
             api_key: openrouter_key,
             base_url: None,
         }),
+        mock: None,
         default_provider: "openai".to_string(),
         default_model: "openai:gpt-5-mini".to_string(),
     };
@@ -109,6 +112,7 @@ fn test_rig_service_from_config_no_providers() {
     config.providers = ProviderConfig {
         openai: None,
         openrouter: None,
+        mock: None,
         default_provider: "openai".to_string(),
         default_model: "openai:gpt-5-mini".to_string(),
     };
@@ -141,6 +145,7 @@ fn test_rig_service_from_config_invalid_default_provider() {
             reasoning_effort: "low".to_string(),
         }),
         openrouter: None,
+        mock: None,
         default_provider: "invalid-provider".to_string(),
         default_model: "openai:gpt-5-mini".to_string(),
     };
@@ -171,6 +176,7 @@ fn test_rig_service_from_config_default_provider_not_configured() {
             api_key,
             base_url: None,
         }),
+        mock: None,
         default_provider: "openai".to_string(), // Default is OpenAI but only OpenRouter is configured
         default_model: "openai:gpt-5-mini".to_string(),
     };
@@ -0,0 +1,110 @@
+//! Tests for the chat stream event log that lets SSE clients resume with `Last-Event-ID`.
+
+use crate::common::database::TestApp;
+use buildscale::{
+    load_config,
+    models::files::FileType,
+    models::requests::CreateFileRequest,
+    models::sse::SseEvent,
+    services::chat::ChatService,
+    services::chat::registry::AgentRegistry,
+    services::files::create_file_with_content,
+    services::storage::FileStorageService,
+};
+use tokio::time::{timeout, Duration};
+use uuid::Uuid;
+
+/// Helper to create a chat file within the given test app.
+async fn setup_chat(test_app: &TestApp) -> Uuid {
+    let mut conn = test_app.get_connection().await;
+    let storage = FileStorageService::new(&load_config().unwrap().storage.base_path);
+
+    let (user, workspace) = test_app.create_test_workspace_with_user().await.unwrap();
+
+    let chat_request = CreateFileRequest {
+        workspace_id: workspace.id,
+        parent_id: None,
+        author_id: user.id,
+        name: "test_chat".to_string(),
+        slug: None,
+        path: None,
+        is_virtual: Some(true),
+        is_remote: None,
+        permission: None,
+        file_type: FileType::Chat,
+        content: serde_json::json!({}),
+        app_data: None,
+    };
+    let chat = create_file_with_content(&mut conn, &storage, chat_request)
+        .await
+        .expect("Failed to create chat file");
+
+    chat.file.id
+}
+
+#[tokio::test]
+async fn test_stream_events_are_logged_and_replayed() {
+    let test_app = TestApp::new("test_stream_events_are_logged_and_replayed").await;
+    let chat_id = setup_chat(&test_app).await;
+    let registry = AgentRegistry::with_event_log(test_app.test_db.pool.clone());
+
+    let mut stream = registry.subscribe_stream(chat_id).await;
+    let event_tx = registry.get_or_create_bus(chat_id).await;
+    for event in [
+        SseEvent::Chunk { text: "first".to_string() },
+        SseEvent::Ping,
+        SseEvent::Chunk { text: "second".to_string() },
+        SseEvent::Done { message: "done".to_string() },
+    ] {
+        event_tx.send(event).unwrap();
+    }
+
+    let mut received = Vec::new();
+    for _ in 0..4 {
+        let event = timeout(Duration::from_secs(5), stream.recv())
+            .await
+            .expect("Event should be logged")
+            .unwrap();
+        received.push(event);
+    }
+
+    // Pings are not logged; other events get increasing ids
+    assert!(received[1].id.is_none());
+    let ids: Vec<i64> = received.iter().filter_map(|event| event.id).collect();
+    assert_eq!(ids.len(), 3);
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+    // A client that saw the first event gets the rest back
+    let mut conn = test_app.get_connection().await;
+    let replayed = ChatService::get_stream_events_after(&mut conn, chat_id, ids[0]).await.unwrap();
+    assert_eq!(replayed.iter().map(|event| event.id.unwrap()).collect::<Vec<_>>(), ids[1..]);
+    match &replayed[0].event {
+        SseEvent::Chunk { text } => assert_eq!(text, "second"),
+        other => panic!("Unexpected event: {:?}", other),
+    }
+    assert!(matches!(replayed[1].event, SseEvent::Done { .. }));
+
+    // Events of other chats are not replayed
+    let other = ChatService::get_stream_events_after(&mut conn, Uuid::now_v7(), 0).await.unwrap();
+    assert!(other.is_empty());
+}
+
+#[tokio::test]
+async fn test_stream_events_without_log_have_no_ids() {
+    let registry = AgentRegistry::new();
+    let chat_id = Uuid::now_v7();
+
+    let mut stream = registry.subscribe_stream(chat_id).await;
+    registry
+        .get_or_create_bus(chat_id)
+        .await
+        .send(SseEvent::Chunk { text: "hello".to_string() })
+        .unwrap();
+
+    let event = timeout(Duration::from_secs(5), stream.recv())
+        .await
+        .expect("Event should be forwarded")
+        .unwrap();
+    assert!(event.id.is_none());
+    assert!(matches!(event.event, SseEvent::Chunk { .. }));
+}
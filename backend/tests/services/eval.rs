@@ -0,0 +1,65 @@
+//! Tests running the eval scenarios of tests/fixtures/evals against the mock provider.
+
+use crate::common::database::TestApp;
+use crate::services::chat::mock_provider_tests::mock_rig_service;
+use buildscale::{
+    load_config,
+    services::eval::{EvalRunner, Scenario},
+    services::storage::FileStorageService,
+};
+use std::path::Path;
+use std::sync::Arc;
+
+const SCENARIOS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/evals");
+
+fn mock_runner(test_app: &TestApp) -> EvalRunner {
+    let storage = Arc::new(FileStorageService::new(&load_config().unwrap().storage.base_path));
+    EvalRunner::new(test_app.test_db.pool.clone(), storage, Arc::new(mock_rig_service()), 128_000)
+}
+
+#[tokio::test]
+async fn test_eval_scenarios_pass_on_mock() {
+    let test_app = TestApp::new("test_eval_scenarios_pass_on_mock").await;
+    let scenarios = Scenario::load_all(Path::new(SCENARIOS_DIR)).unwrap();
+    assert_eq!(
+        scenarios.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+        vec!["edit_todo", "write_note"]
+    );
+
+    let report = mock_runner(&test_app).run(&scenarios, &["mock".to_string()], Some("fixtures".to_string())).await;
+    assert!(report.all_passed(), "{}", report.to_markdown());
+
+    let summaries = report.summaries();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!((summaries[0].model.as_str(), summaries[0].passed, summaries[0].score), ("mock", 2, 1.0));
+
+    // Both turns of the multi-turn scenario ran, with their tool calls observed
+    let edit_todo = &report.results[0];
+    assert_eq!(edit_todo.turns.len(), 2);
+    assert_eq!(edit_todo.turns[0].response, "Your todo list has one item: buy milk.");
+    assert_eq!(
+        edit_todo.tool_calls.iter().map(|call| (call.tool.as_str(), call.success)).collect::<Vec<_>>(),
+        vec![("read", Some(true)), ("edit", Some(true))]
+    );
+}
+
+#[tokio::test]
+async fn test_eval_scores_unmet_expectations() {
+    let test_app = TestApp::new("test_eval_scores_unmet_expectations").await;
+    let mut scenario = Scenario::load(&Path::new(SCENARIOS_DIR).join("write_note.yaml")).unwrap();
+    scenario.expect.forbidden_tools.push("write".to_string());
+    scenario.expect.files[0].contains = vec!["Goodbye".to_string()];
+
+    let result = mock_runner(&test_app).run_scenario(&scenario, Some("mock")).await;
+    assert!(!result.passed);
+    let failed: Vec<&str> = result.checks.iter().filter(|c| !c.passed).map(|c| c.name.as_str()).collect();
+    assert_eq!(failed, vec!["forbidden_tool: write", "file: /notes/hello.md"]);
+    assert_eq!(result.score, 5.0 / 7.0);
+
+    // A scenario without a fixture fails its first turn
+    scenario.name = "missing_fixture".to_string();
+    let result = mock_runner(&test_app).run_scenario(&scenario, Some("mock")).await;
+    assert!(!result.passed);
+    assert!(result.turns[0].error.is_some());
+    assert!(result.checks.iter().any(|c| c.name == "no_errors" && !c.passed));
+}
@@ -1,3 +1,6 @@
 pub mod chat;
 pub mod storage;
 pub mod storage_cleanup;
+pub mod eval;
+pub mod web_pages;
+pub mod web_search;
@@ -0,0 +1,76 @@
+//! Tests for web pages attached to chat messages, fetched from a local server
+
+use buildscale::error::Error;
+use buildscale::services::web_pages::{fetch_page, fetch_page_with_max_age};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::common::mock_web::MockWebServer;
+
+#[tokio::test]
+async fn test_fetch_page_converts_html_to_markdown() {
+    let server = MockWebServer::start().await;
+    let workspace_id = Uuid::now_v7();
+
+    let page = fetch_page(workspace_id, &server.url("/article")).await.unwrap();
+    assert_eq!(page.title.as_deref(), Some("Release notes"));
+    assert!(page.content.starts_with("# Release notes"), "{}", page.content);
+    assert!(page.content.contains("streaming uploads"));
+    // Readability keeps the article and drops the page chrome
+    assert!(!page.content.contains("Copyright Example Corp"), "{}", page.content);
+    assert_eq!(page.etag.as_deref(), Some("\"article-v1\""));
+    assert!(!page.truncated);
+
+    let page = fetch_page(workspace_id, &server.url("/notes.txt")).await.unwrap();
+    assert_eq!(page.content, "Plain notes");
+    assert!(page.title.is_none());
+}
+
+#[tokio::test]
+async fn test_fetch_page_caches_and_revalidates() {
+    let server = MockWebServer::start().await;
+    let url = server.url("/article");
+    let workspace_id = Uuid::now_v7();
+
+    let first = fetch_page(workspace_id, &url).await.unwrap();
+    let cached = fetch_page(workspace_id, &url).await.unwrap();
+    assert_eq!(server.article_requests(), 1, "Fresh pages come from the cache");
+    assert_eq!(cached.fetched_at, first.fetched_at);
+
+    // Stale pages are revalidated with their ETag
+    let revalidated = fetch_page_with_max_age(workspace_id, &url, Duration::ZERO).await.unwrap();
+    assert_eq!(server.article_requests(), 2);
+    assert_eq!(server.not_modified(), 1);
+    assert_eq!(revalidated.content, first.content);
+    assert!(revalidated.fetched_at > first.fetched_at);
+
+    // Other workspaces do not share the cache
+    fetch_page(Uuid::now_v7(), &url).await.unwrap();
+    assert_eq!(server.article_requests(), 3);
+}
+
+#[tokio::test]
+async fn test_fetch_page_rejects_what_is_not_a_page() {
+    let server = MockWebServer::start().await;
+    let workspace_id = Uuid::now_v7();
+
+    assert!(fetch_page(workspace_id, &server.url("/logo.png")).await.is_err());
+    assert!(fetch_page(workspace_id, &server.url("/missing")).await.is_err());
+    assert!(matches!(fetch_page(workspace_id, "ftp://example.com/file").await, Err(Error::Validation(_))));
+}
+
+#[tokio::test]
+async fn test_fetch_page_only_reaches_public_addresses() {
+    let server = MockWebServer::start().await;
+    let workspace_id = Uuid::now_v7();
+
+    for url in ["http://169.254.169.254/latest/meta-data/", "http://10.0.0.1/", "http://[::1]/"] {
+        assert!(matches!(fetch_page(workspace_id, url).await, Err(Error::Forbidden(_))), "{}", url);
+    }
+    // Names are checked as they resolve, redirects as they are followed
+    let port = server.base_url.rsplit(':').next().unwrap();
+    let localhost = fetch_page(workspace_id, &format!("http://localhost:{}/article", port)).await;
+    assert!(matches!(localhost, Err(Error::Internal(message)) if message.contains("not a public address")));
+    let redirected = fetch_page(workspace_id, &server.url("/metadata")).await;
+    assert!(matches!(redirected, Err(Error::Internal(message)) if message.contains("not a public address")));
+}
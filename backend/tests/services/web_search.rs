@@ -0,0 +1,141 @@
+//! Tests for the web search backends, run against local stubs of their APIs
+
+use buildscale::config::WebSearchConfig;
+use buildscale::services::web_search::{
+    self, BraveBackend, ExaBackend, SearchBackend, SearxngBackend, TavilyBackend,
+};
+use std::time::Duration;
+
+use crate::common::mock_search::{MockSearchServer, BRAVE_KEY, EXA_KEY, RESULT_COUNT, TAVILY_KEY};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[tokio::test]
+async fn test_searxng_backend() {
+    let server = MockSearchServer::start().await;
+    let backend = SearxngBackend::new(&server.url("/searxng/"), TIMEOUT).unwrap();
+
+    let results = backend.search("rust lang", 10).await.unwrap();
+    assert_eq!(results.provider, "searxng");
+    assert_eq!(results.results.len(), RESULT_COUNT);
+    assert_eq!(results.results[0].title, "rust lang result 1");
+    assert_eq!(results.results[0].url, "https://example.com/rust-lang/1");
+    assert_eq!(results.results[0].snippet, "About rust lang");
+    assert_eq!(results.answer.as_deref(), Some("rust lang is a test"));
+
+    // SearxNG returns a page of results, only the requested count is kept
+    let results = backend.search("rust lang", 2).await.unwrap();
+    assert_eq!(results.results.len(), 2);
+}
+
+#[tokio::test]
+async fn test_brave_backend() {
+    let server = MockSearchServer::start().await;
+    let backend = BraveBackend::new(Some(&server.url("/brave")), BRAVE_KEY.to_string(), TIMEOUT).unwrap();
+
+    let results = backend.search("tokio", 2).await.unwrap();
+    assert_eq!(results.provider, "brave");
+    assert_eq!(results.results.len(), 2);
+    // Highlighting tags are removed
+    assert_eq!(results.results[0].title, "tokio result 1");
+    assert_eq!(results.results[0].snippet, "All about tokio");
+    assert_eq!(results.results[0].published_date.as_deref(), Some("2026-01-15T10:00:00"));
+
+    let backend = BraveBackend::new(Some(&server.url("/brave")), "wrong".to_string(), TIMEOUT).unwrap();
+    let error = backend.search("tokio", 2).await.unwrap_err();
+    assert!(error.to_string().contains("401"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_tavily_backend() {
+    let server = MockSearchServer::start().await;
+    let backend = TavilyBackend::new(Some(&server.url("/tavily")), TAVILY_KEY.to_string(), TIMEOUT).unwrap();
+
+    let results = backend.search("axum", 10).await.unwrap();
+    assert_eq!(results.provider, "tavily");
+    assert_eq!(results.results.len(), RESULT_COUNT);
+    assert_eq!(results.results[1].title, "axum result 2");
+    assert_eq!(results.answer.as_deref(), Some("axum is a test"));
+}
+
+#[tokio::test]
+async fn test_exa_backend() {
+    let server = MockSearchServer::start().await;
+    let backend = ExaBackend::new(Some(&server.url("/exa")), EXA_KEY.to_string(), TIMEOUT).unwrap();
+
+    let results = backend.search("sqlx", 10).await.unwrap();
+    assert_eq!(results.provider, "exa");
+    assert_eq!(results.results.len(), RESULT_COUNT);
+    // Pages without a title are named by their URL
+    assert_eq!(results.results[0].title, "https://example.com/sqlx/1");
+    assert_eq!(results.results[1].title, "sqlx result 2");
+    assert_eq!(results.results[1].snippet, "First part about sqlx … second part");
+}
+
+#[tokio::test]
+async fn test_search_caches_results() {
+    let server = MockSearchServer::start().await;
+    let backend = SearxngBackend::new(&server.url("/searxng"), TIMEOUT).unwrap();
+
+    let first = web_search::search_with(&backend, None, 60, "cached query", 5).await.unwrap();
+    let second = web_search::search_with(&backend, None, 60, "cached query", 5).await.unwrap();
+    assert_eq!(server.requests("searxng"), 1, "Repeated searches come from the cache");
+    assert_eq!(second.results.len(), first.results.len());
+
+    // Another result count is another search
+    web_search::search_with(&backend, None, 60, "cached query", 2).await.unwrap();
+    assert_eq!(server.requests("searxng"), 2);
+
+    // A TTL of 0 disables the cache
+    web_search::search_with(&backend, None, 0, "uncached query", 5).await.unwrap();
+    web_search::search_with(&backend, None, 0, "uncached query", 5).await.unwrap();
+    assert_eq!(server.requests("searxng"), 4);
+}
+
+#[tokio::test]
+async fn test_search_falls_back_when_the_backend_fails() {
+    let server = MockSearchServer::start().await;
+    let broken = SearxngBackend::new(&server.url("/broken"), TIMEOUT).unwrap();
+    let fallback = BraveBackend::new(Some(&server.url("/brave")), BRAVE_KEY.to_string(), TIMEOUT).unwrap();
+
+    let results = web_search::search_with(&broken, Some(&fallback), 60, "fallback query", 5)
+        .await
+        .unwrap();
+    assert_eq!(results.provider, "brave");
+    assert_eq!(results.results.len(), RESULT_COUNT);
+
+    // Results of the fallback are not cached, the backend is asked again
+    web_search::search_with(&broken, Some(&fallback), 60, "fallback query", 5)
+        .await
+        .unwrap();
+    assert_eq!(server.requests("broken"), 2);
+
+    // Without a fallback the error is returned
+    let error = web_search::search_with(&broken, None, 60, "fallback query", 5)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("500"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_search_uses_the_configured_backend() {
+    let server = MockSearchServer::start().await;
+    let config = WebSearchConfig {
+        backend: "tavily".to_string(),
+        base_url: Some(server.url("/tavily")),
+        api_key: TAVILY_KEY.to_string().into(),
+        fallback: false,
+        cache_ttl_seconds: 0,
+        ..Default::default()
+    };
+
+    let results = web_search::search(&config, "configured query", 5).await.unwrap();
+    assert_eq!(results.provider, "tavily");
+    assert_eq!(server.requests("tavily"), 1);
+
+    let config = WebSearchConfig {
+        api_key: "wrong".to_string().into(),
+        ..config
+    };
+    assert!(web_search::search(&config, "configured query", 5).await.is_err());
+}
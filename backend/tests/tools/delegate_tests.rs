@@ -0,0 +1,49 @@
+//! Tests for the delegate tool
+//!
+//! Sub-agents need the chat runtime, so through the tools endpoint the tool
+//! only validates its arguments and then reports that it needs a chat.
+
+use crate::common::{TestApp, TestAppOptions, register_and_login, create_workspace};
+use crate::tools::common::execute_tool;
+
+#[tokio::test]
+async fn test_delegate_rejects_invalid_tasks() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Delegate Validation Test").await;
+
+    let invalid = [
+        serde_json::json!({"tasks": []}),
+        serde_json::json!({"tasks": [{"task": ""}]}),
+        serde_json::json!({"tasks": [{"task": "Ask the user", "tools": ["ask_user"]}]}),
+        serde_json::json!({"tasks": [{"task": "Delegate again", "tools": ["delegate"]}]}),
+        serde_json::json!({"tasks": [{"task": "Read", "tools": ["teleport"]}]}),
+        serde_json::json!({"tasks": [{"task": "Read", "token_budget": 0}]}),
+        serde_json::json!({"tasks": [
+            {"task": "1"}, {"task": "2"}, {"task": "3"}, {"task": "4"}, {"task": "5"}
+        ]}),
+    ];
+    for args in invalid {
+        let response = execute_tool(&app, &workspace_id, &token, "delegate", args.clone()).await;
+        assert_eq!(response.status(), 400, "Should reject: {}", args);
+    }
+}
+
+#[tokio::test]
+async fn test_delegate_requires_chat() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Delegate Chat Only Test").await;
+
+    let response = execute_tool(
+        &app,
+        &workspace_id,
+        &token,
+        "delegate",
+        serde_json::json!({"tasks": [{"task": "Summarize /notes", "tools": ["read", "ls"], "token_budget": "20000"}]}),
+    )
+    .await;
+    assert_eq!(response.status(), 400);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body.to_string().contains("only available to agents inside a chat"));
+}
@@ -23,4 +23,10 @@ pub mod plan_mode_tests;
 pub mod find_vs_glob_fallback_analysis;
 pub mod mv_integration_test;
 pub mod memory_tools_tests;
+pub mod skill_tools_tests;
+pub mod delegate_tests;
+pub mod run_tests;
+pub mod web_search_tests;
+pub mod plan_update_step_tests;
+pub mod tool_call_tests;
 pub mod common;
@@ -0,0 +1,151 @@
+//! Tests for plan step tracking
+//!
+//! Covers the plan_update_step tool, the steps returned by plan_read and the
+//! chat plan progress endpoint.
+
+use crate::chat::create_chat;
+use crate::common::{TestApp, TestAppOptions, register_and_login, create_workspace};
+use crate::tools::common::execute_tool;
+
+const PLAN_CONTENT: &str = "## Step-by-Step Plan\n- [ ] Create the schema\n- [ ] Add the endpoint\n- [ ] Write the docs\n";
+
+async fn write_plan(app: &TestApp, workspace_id: &str, token: &str, path: &str) {
+    let response = execute_tool(
+        app,
+        workspace_id,
+        token,
+        "plan_write",
+        serde_json::json!({"title": "Step Tracking", "content": PLAN_CONTENT, "path": path, "status": "approved"}),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn test_plan_update_step_marks_steps() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Plan Step Test").await;
+    write_plan(&app, &workspace_id, &token, "/plans/steps.plan").await;
+
+    let response = execute_tool(
+        &app,
+        &workspace_id,
+        &token,
+        "plan_update_step",
+        serde_json::json!({"path": "/plans/steps.plan", "step": "2", "status": "in_progress"}),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let result = &body["result"];
+    assert_eq!(result["step"]["index"], 2);
+    assert_eq!(result["step"]["text"], "Add the endpoint");
+    assert_eq!(result["step"]["status"], "in_progress");
+    assert_eq!(result["previous_status"], "pending");
+    assert_eq!(result["progress"]["total"], 3);
+    assert_eq!(result["progress"]["in_progress"], 1);
+
+    let response = execute_tool(
+        &app,
+        &workspace_id,
+        &token,
+        "plan_update_step",
+        serde_json::json!({"path": "/plans/steps.plan", "step": 1, "status": "done"}),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+
+    // plan_read returns the checklist and the frontmatter is kept
+    let response = execute_tool(&app, &workspace_id, &token, "plan_read", serde_json::json!({"path": "/plans/steps.plan"})).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let result = &body["result"];
+    assert_eq!(result["metadata"]["title"], "Step Tracking");
+    assert_eq!(result["steps"][0]["status"], "done");
+    assert_eq!(result["steps"][1]["status"], "in_progress");
+    assert_eq!(result["steps"][2]["status"], "pending");
+    assert!(result["content"].as_str().unwrap().contains("- [x] Create the schema\n- [~] Add the endpoint"));
+}
+
+#[tokio::test]
+async fn test_plan_update_step_rejects_invalid_args() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Plan Step Validation Test").await;
+    write_plan(&app, &workspace_id, &token, "/plans/steps.plan").await;
+
+    let invalid = [
+        serde_json::json!({"path": "/plans/steps.plan", "step": 4, "status": "done"}),
+        serde_json::json!({"path": "/plans/steps.plan", "step": 0, "status": "done"}),
+        serde_json::json!({"path": "/plans/steps.plan", "step": 1, "status": "finished"}),
+        serde_json::json!({"path": "/notes/steps.md", "step": 1, "status": "done"}),
+        serde_json::json!({"step": 1, "status": "done"}),
+    ];
+    for args in invalid {
+        let response = execute_tool(&app, &workspace_id, &token, "plan_update_step", args.clone()).await;
+        assert_eq!(response.status(), 400, "Should reject: {}", args);
+    }
+}
+
+#[tokio::test]
+async fn test_chat_plan_progress_endpoint() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Plan Progress Test").await;
+    write_plan(&app, &workspace_id, &token, "/plans/progress.plan").await;
+    let chat_id = create_chat(&app, &workspace_id, &token, "Track the plan").await;
+
+    let progress_url = format!(
+        "{}/api/v1/workspaces/{}/chats/{}/plan",
+        app.address, workspace_id, chat_id
+    );
+
+    // No active plan yet
+    let response = app
+        .client
+        .get(&progress_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let response = app
+        .client
+        .patch(&format!("{}/api/v1/workspaces/{}/chats/{}", app.address, workspace_id, chat_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({"app_data": {"mode": "build", "plan_file": "/plans/progress.plan"}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    for step in 1..=3 {
+        let response = execute_tool(
+            &app,
+            &workspace_id,
+            &token,
+            "plan_update_step",
+            serde_json::json!({"path": "/plans/progress.plan", "step": step, "status": "done"}),
+        )
+        .await;
+        assert_eq!(response.status(), 200);
+    }
+
+    let response = app
+        .client
+        .get(&progress_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["chat_id"], chat_id.as_str());
+    assert_eq!(body["plan_file"], "/plans/progress.plan");
+    assert_eq!(body["metadata"]["status"], "approved");
+    assert_eq!(body["steps"].as_array().unwrap().len(), 3);
+    assert_eq!(body["progress"]["done"], 3);
+    assert_eq!(body["progress"]["total"], 3);
+}
@@ -487,3 +487,48 @@ async fn test_read_cursor_field_always_returned() {
     // Cursor field should always be returned (at end of read)
     assert_eq!(body["result"]["cursor"], 3);
 }
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image::RgbaImage::new(width, height)
+        .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .unwrap();
+    bytes
+}
+
+#[tokio::test]
+async fn test_read_image_file_returns_image() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Read Image Test").await;
+
+    let workspace_dir = std::path::PathBuf::from(&app.config.storage.base_path)
+        .join("workspaces")
+        .join(&workspace_id)
+        .join("latest");
+    std::fs::create_dir_all(&workspace_dir).unwrap();
+    std::fs::write(workspace_dir.join("small.png"), png(40, 30)).unwrap();
+    std::fs::write(workspace_dir.join("large.png"), png(2000, 4000)).unwrap();
+
+    let response = execute_tool(&app, &workspace_id, &token, "read", serde_json::json!({"path": "/small.png"})).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let result = &body["result"];
+    assert!(result["content"].is_null(), "{}", result);
+    assert_eq!(result["synced"], false);
+    assert_eq!(result["image"]["media_type"], "image/png");
+    assert_eq!(result["image"]["width"], 40);
+    assert_eq!(result["image"]["height"], 30);
+
+    use base64::Engine;
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(result["image"]["data"].as_str().unwrap())
+        .unwrap();
+    assert_eq!(data, png(40, 30));
+
+    // Large images are scaled down for models
+    let response = execute_tool(&app, &workspace_id, &token, "read", serde_json::json!({"path": "/large.png"})).await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["result"]["image"]["width"], 784);
+    assert_eq!(body["result"]["image"]["height"], 1568);
+}
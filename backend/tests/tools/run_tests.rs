@@ -0,0 +1,180 @@
+//! Tests for the run tool
+//!
+//! bubblewrap is not available everywhere the tests run, so the sandbox is
+//! replaced by a script that runs the command in the workspace folder without
+//! isolating it. These tests cover how commands run and how their changes are
+//! recorded, not the isolation itself.
+
+use buildscale::load_config;
+use std::path::PathBuf;
+
+use crate::common::{TestApp, TestAppOptions, create_workspace, register_and_login};
+use crate::tools::common::{execute_tool, read_file, write_file};
+
+const FAKE_BWRAP: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/sandbox/fake_bwrap.sh");
+
+async fn sandbox_app(bwrap_path: &str) -> TestApp {
+    let mut config = load_config().expect("Failed to load config");
+    config.ai.sandbox.enabled = true;
+    config.ai.sandbox.bwrap_path = bwrap_path.to_string();
+    TestApp::new_with_config(TestAppOptions::api(), config).await
+}
+
+fn workspace_dir(app: &TestApp, workspace_id: &str) -> PathBuf {
+    PathBuf::from(&app.config.storage.base_path)
+        .join("workspaces")
+        .join(workspace_id)
+        .join("latest")
+}
+
+async fn run(app: &TestApp, workspace_id: &str, token: &str, args: serde_json::Value) -> serde_json::Value {
+    let response = execute_tool(app, workspace_id, token, "run", args).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["success"].as_bool().unwrap(), "{}", body);
+    body["result"].clone()
+}
+
+fn change_of<'a>(result: &'a serde_json::Value, path: &str) -> &'a serde_json::Value {
+    result["changes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|change| change["path"] == path)
+        .unwrap_or_else(|| panic!("no change of {} in {}", path, result))
+}
+
+#[tokio::test]
+async fn test_run_captures_output_and_records_changes() {
+    let app = sandbox_app(FAKE_BWRAP).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Run Test").await;
+
+    write_file(&app, &workspace_id, &token, "/src/main.txt", serde_json::json!("one")).await;
+    write_file(&app, &workspace_id, &token, "/src/same.txt", serde_json::json!("same")).await;
+    write_file(&app, &workspace_id, &token, "/old.md", serde_json::json!("old")).await;
+
+    let result = run(
+        &app,
+        &workspace_id,
+        &token,
+        serde_json::json!({
+            "command": "printf two > main.txt && printf same > same.txt && rm ../old.md && mkdir -p ../out && printf built > ../out/result.txt && pwd && echo oops >&2 && exit 3",
+            "cwd": "/src"
+        }),
+    )
+    .await;
+
+    assert_eq!(result["exit_code"], 3);
+    assert_eq!(result["timed_out"], false);
+    assert!(result["stdout"].as_str().unwrap().ends_with("/latest/src\n"), "{}", result);
+    assert_eq!(result["stderr"], "oops\n");
+    assert_eq!(result["cwd"], "/src");
+
+    assert_eq!(result["changes"].as_array().unwrap().len(), 3, "{}", result);
+    assert_eq!(change_of(&result, "/src/main.txt")["change"], "modified");
+    assert_eq!(change_of(&result, "/out/result.txt")["change"], "created");
+    assert_eq!(change_of(&result, "/old.md")["change"], "deleted");
+    assert!(change_of(&result, "/old.md").get("version_id").is_none());
+
+    assert_eq!(read_file(&app, &workspace_id, &token, "/src/main.txt").await, "two");
+    assert_eq!(read_file(&app, &workspace_id, &token, "/out/result.txt").await, "built");
+    let response = execute_tool(&app, &workspace_id, &token, "read", serde_json::json!({"path": "/old.md"})).await;
+    assert_ne!(response.status(), 200, "Deleted file should be gone");
+}
+
+#[tokio::test]
+async fn test_run_reports_unrecorded_changes() {
+    let app = sandbox_app(FAKE_BWRAP).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Run Unrecorded Test").await;
+
+    let result = run(
+        &app,
+        &workspace_id,
+        &token,
+        serde_json::json!({
+            "command": "echo notes > Notes.md && printf '\\377\\376' > data.bin && ln -s /etc/passwd passwd"
+        }),
+    )
+    .await;
+
+    assert_eq!(result["exit_code"], 0);
+    assert!(result["changes"].as_array().unwrap().is_empty(), "{}", result);
+    let unrecorded: Vec<&str> = result["unrecorded"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|change| change["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(unrecorded, vec!["/Notes.md", "/data.bin", "/passwd"]);
+
+    // Links could lead the other file tools out of the workspace
+    let dir = workspace_dir(&app, &workspace_id);
+    assert!(std::fs::symlink_metadata(dir.join("passwd")).is_err(), "Link should be removed");
+    assert!(dir.join("Notes.md").exists(), "Unrecorded files stay on disk");
+}
+
+#[tokio::test]
+async fn test_run_kills_commands_at_their_timeout() {
+    let app = sandbox_app(FAKE_BWRAP).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Run Timeout Test").await;
+
+    let result = run(
+        &app,
+        &workspace_id,
+        &token,
+        serde_json::json!({"command": "echo started > partial.txt; echo started; exec sleep 30", "timeout": 1}),
+    )
+    .await;
+
+    assert_eq!(result["timed_out"], true);
+    assert!(result["exit_code"].is_null());
+    assert_eq!(result["stdout"], "started\n");
+    assert!(result["duration_ms"].as_u64().unwrap() < 10_000);
+    // Changes made before the command was killed are still recorded
+    assert_eq!(change_of(&result, "/partial.txt")["change"], "created");
+}
+
+#[tokio::test]
+async fn test_run_is_refused_when_disabled_or_in_plan_mode() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Run Disabled Test").await;
+
+    let response = execute_tool(&app, &workspace_id, &token, "run", serde_json::json!({"command": "true"})).await;
+    assert_eq!(response.status(), 400);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body.to_string().contains("not enabled"), "{}", body);
+
+    let app = sandbox_app(FAKE_BWRAP).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Run Plan Mode Test").await;
+
+    let response = app
+        .client
+        .post(format!("{}/api/v1/workspaces/{}/tools", app.address, workspace_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({"tool": "run", "args": {"command": "touch x"}, "plan_mode": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    assert!(!workspace_dir(&app, &workspace_id).join("x").exists());
+
+    let response = execute_tool(&app, &workspace_id, &token, "run", serde_json::json!({"command": "true", "cwd": "/missing"})).await;
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn test_run_fails_without_bubblewrap() {
+    let app = sandbox_app("/nonexistent/bwrap").await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Run Missing Sandbox Test").await;
+
+    // Commands never run outside of the sandbox
+    let response = execute_tool(&app, &workspace_id, &token, "run", serde_json::json!({"command": "touch x"})).await;
+    assert_eq!(response.status(), 500);
+    assert!(!workspace_dir(&app, &workspace_id).join("x").exists());
+}
@@ -0,0 +1,240 @@
+//! Tests for skill tools (skill_list, skill_activate) and pinned skill attachments
+//!
+//! Tests cover:
+//! - Skill manifests written under /system/skills get the skill file type
+//! - Listing skills with frontmatter and query filtering
+//! - Activating a skill by name returns its instructions
+//! - Skills pinned to a chat appear in the context and the catalog in the system prompt
+
+use crate::common::{TestApp, TestAppOptions, register_and_login, create_workspace};
+use crate::tools::common::execute_tool;
+
+const PR_SKILL: &str = "---
+name: github-pr
+description: Open and describe pull requests
+triggers: [pull request, PR]
+allowed_tools: [read, grep]
+---
+# Opening a PR
+
+1. Summarize the diff.
+2. Link the issue.";
+
+const LINT_SKILL: &str = "---
+description: Run the linter before committing
+---
+Run `cargo clippy` and fix every warning.";
+
+/// Helper to write a skill manifest, returning its file ID
+async fn write_skill(app: &TestApp, workspace_id: &str, token: &str, folder: &str, content: &str) -> String {
+    let response = execute_tool(
+        app,
+        workspace_id,
+        token,
+        "write",
+        serde_json::json!({
+            "path": format!("/system/skills/{}/SKILL.md", folder),
+            "content": content
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    body["result"]["file_id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_skill_list_returns_frontmatter() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Skill List Test").await;
+
+    let pr_id = write_skill(&app, &workspace_id, &token, "github-pr", PR_SKILL).await;
+    write_skill(&app, &workspace_id, &token, "lint", LINT_SKILL).await;
+
+    let response = execute_tool(&app, &workspace_id, &token, "skill_list", serde_json::json!({})).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let result = &body["result"];
+
+    assert_eq!(result["total"], 2);
+    let skills = result["skills"].as_array().unwrap();
+    assert_eq!(skills[0]["name"], "github-pr");
+    assert_eq!(skills[0]["id"], pr_id.as_str());
+    assert_eq!(skills[0]["triggers"], serde_json::json!(["pull request", "PR"]));
+    assert_eq!(skills[0]["allowed_tools"], serde_json::json!(["read", "grep"]));
+    assert!(skills[0].get("instructions").is_none());
+
+    // Without a name in the frontmatter the folder name is used
+    assert_eq!(skills[1]["name"], "lint");
+    assert_eq!(skills[1]["description"], "Run the linter before committing");
+}
+
+#[tokio::test]
+async fn test_skill_list_filters_by_query() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Skill Query Test").await;
+
+    write_skill(&app, &workspace_id, &token, "github-pr", PR_SKILL).await;
+    write_skill(&app, &workspace_id, &token, "lint", LINT_SKILL).await;
+
+    let response = execute_tool(
+        &app,
+        &workspace_id,
+        &token,
+        "skill_list",
+        serde_json::json!({"query": "pull request"}),
+    )
+    .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+
+    assert_eq!(body["result"]["total"], 1);
+    assert_eq!(body["result"]["skills"][0]["name"], "github-pr");
+}
+
+#[tokio::test]
+async fn test_skill_activate_returns_instructions() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Skill Activate Test").await;
+
+    write_skill(&app, &workspace_id, &token, "github-pr", PR_SKILL).await;
+
+    let response = execute_tool(
+        &app,
+        &workspace_id,
+        &token,
+        "skill_activate",
+        serde_json::json!({"name": "GitHub-PR"}),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let result = &body["result"];
+
+    assert_eq!(result["name"], "github-pr");
+    // Stored paths are lowercased
+    assert_eq!(result["path"], "/system/skills/github-pr/skill.md");
+    assert_eq!(result["allowed_tools"], serde_json::json!(["read", "grep"]));
+    let instructions = result["instructions"].as_str().unwrap();
+    assert!(instructions.starts_with("# Opening a PR"));
+    assert!(!instructions.contains("triggers:"));
+}
+
+#[tokio::test]
+async fn test_skill_activate_unknown_skill() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Skill Missing Test").await;
+
+    let response = execute_tool(
+        &app,
+        &workspace_id,
+        &token,
+        "skill_activate",
+        serde_json::json!({"name": "does-not-exist"}),
+    )
+    .await;
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_pinned_skill_in_chat_context() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Skill Pin Test").await;
+
+    let pr_id = write_skill(&app, &workspace_id, &token, "github-pr", PR_SKILL).await;
+    write_skill(&app, &workspace_id, &token, "lint", LINT_SKILL).await;
+
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/chats", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({
+            "goal": "Open a PR for the login fix",
+            "skills": [pr_id]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let chat_id = body["chat_id"].as_str().unwrap().to_string();
+
+    // A follow-up message without skills keeps the pin
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/chats/{}", workspace_id, chat_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({"content": "Also mention the tests"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 202);
+
+    let response = app
+        .client
+        .get(&app.url(&format!("/api/v1/workspaces/{}/chats/{}/context", workspace_id, chat_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let context: serde_json::Value = response.json().await.unwrap();
+
+    let system_prompt = context["system_prompt"]["content"].as_str().unwrap();
+    assert!(system_prompt.contains("## Available Skills"));
+    assert!(system_prompt.contains("- lint: Run the linter before committing"));
+    assert!(!system_prompt.contains("Summarize the diff"));
+
+    let attachments = context["attachments"]["attachments"].as_array().unwrap();
+    let skill = attachments
+        .iter()
+        .find(|a| a["attachment_type"] == "skill")
+        .expect("pinned skill attachment");
+    assert_eq!(skill["id"], pr_id.as_str());
+    assert_eq!(skill["is_essential"], true);
+
+    let tool_names: Vec<&str> = context["tools"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|t| t["name"].as_str())
+        .collect();
+    assert!(tool_names.contains(&"skill_list"));
+    assert!(tool_names.contains(&"skill_activate"));
+}
+
+#[tokio::test]
+async fn test_pinning_unknown_skill_is_rejected() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Skill Pin Reject Test").await;
+
+    // A regular document is not a skill
+    let response = execute_tool(
+        &app,
+        &workspace_id,
+        &token,
+        "write",
+        serde_json::json!({"path": "/notes.md", "content": "not a skill"}),
+    )
+    .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    let document_id = body["result"]["file_id"].as_str().unwrap().to_string();
+
+    let response = app
+        .client
+        .post(&app.url(&format!("/api/v1/workspaces/{}/chats", workspace_id)))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({
+            "goal": "Do something",
+            "skills": [document_id]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
@@ -0,0 +1,84 @@
+//! Tests for the context of a tool call
+//!
+//! Chats run tools with a context whose cancellation stops them and which
+//! receives the output they stream, so these tests call the tools directly
+//! rather than through the tools endpoint.
+
+use buildscale::services::storage::FileStorageService;
+use buildscale::tools::{Tool, ToolCallContext, ToolConfig, grep::GrepTool};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::common::{TestApp, TestAppOptions, register_and_login, create_workspace};
+use crate::tools::common::write_file;
+
+#[tokio::test]
+async fn test_grep_streams_matches_as_call_output() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Grep Streaming Test").await;
+
+    write_file(&app, &workspace_id, &token, "/notes/a.md", serde_json::json!("first needle")).await;
+    write_file(&app, &workspace_id, &token, "/notes/b.md", serde_json::json!("no match\nsecond needle")).await;
+
+    let storage = FileStorageService::new(&app.config.storage.base_path);
+    let mut conn = app.pool.acquire().await.unwrap();
+    let (output_tx, mut output) = mpsc::unbounded_channel();
+    let config = ToolConfig {
+        call: ToolCallContext::new(CancellationToken::new(), Some(output_tx)),
+        ..Default::default()
+    };
+
+    let response = GrepTool
+        .execute(
+            &mut conn,
+            &storage,
+            workspace_id.parse().unwrap(),
+            Uuid::now_v7(),
+            config,
+            serde_json::json!({"pattern": "needle"}),
+        )
+        .await
+        .unwrap();
+    assert!(response.success);
+    assert_eq!(response.result["matches"].as_array().unwrap().len(), 2);
+
+    let mut streamed = Vec::new();
+    while let Ok(chunk) = output.try_recv() {
+        streamed.push(chunk);
+    }
+    streamed.sort();
+    assert_eq!(streamed, vec!["/notes/a.md:1: first needle\n", "/notes/b.md:2: second needle\n"]);
+}
+
+#[tokio::test]
+async fn test_cancelled_call_stops_grep() {
+    let app = TestApp::new_with_options(TestAppOptions::api()).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Grep Cancellation Test").await;
+
+    write_file(&app, &workspace_id, &token, "/notes/a.md", serde_json::json!("needle")).await;
+
+    let storage = FileStorageService::new(&app.config.storage.base_path);
+    let mut conn = app.pool.acquire().await.unwrap();
+    let cancellation = CancellationToken::new();
+    cancellation.cancel();
+    let config = ToolConfig {
+        call: ToolCallContext::new(cancellation, None),
+        ..Default::default()
+    };
+
+    let result = GrepTool
+        .execute(
+            &mut conn,
+            &storage,
+            workspace_id.parse().unwrap(),
+            Uuid::now_v7(),
+            config,
+            serde_json::json!({"pattern": "needle"}),
+        )
+        .await;
+    let error = result.expect_err("Cancelled grep should fail");
+    assert!(error.to_string().contains("cancelled"), "{}", error);
+}
@@ -0,0 +1,43 @@
+//! Tests for the web_search tool, searching a local SearxNG stub
+
+use buildscale::load_config;
+
+use crate::common::mock_search::{MockSearchServer, RESULT_COUNT};
+use crate::common::{TestApp, TestAppOptions, create_workspace, register_and_login};
+use crate::tools::common::execute_tool;
+
+async fn searxng_app(server: &MockSearchServer) -> TestApp {
+    let mut config = load_config().expect("Failed to load config");
+    config.ai.web_search.backend = "searxng".to_string();
+    config.ai.web_search.base_url = Some(server.url("/searxng"));
+    config.ai.web_search.fallback = false;
+    TestApp::new_with_config(TestAppOptions::api(), config).await
+}
+
+#[tokio::test]
+async fn test_web_search_uses_configured_backend() {
+    let server = MockSearchServer::start().await;
+    let app = searxng_app(&server).await;
+    let token = register_and_login(&app).await;
+    let workspace_id = create_workspace(&app, &token, "Web Search Test").await;
+
+    let response = execute_tool(
+        &app,
+        &workspace_id,
+        &token,
+        "web_search",
+        serde_json::json!({ "query": "web search tool", "max_results": 2, "offset": 1 }),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["success"].as_bool().unwrap(), "{}", body);
+
+    let result = &body["result"];
+    assert_eq!(result["provider"], "searxng");
+    assert_eq!(result["total"], RESULT_COUNT);
+    assert_eq!(result["answer"], "web search tool is a test");
+    let results = result["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["title"], "web search tool result 2");
+}
@@ -23,10 +23,7 @@ async fn test_list_users_empty() {
             "User ID should not be empty"
         );
         assert!(!user.email.is_empty(), "User email should not be empty");
-        assert!(
-            user.password_hash.is_some(),
-            "Password hash should exist"
-        );
+        // Single sign-on and service account users have no password
         if let Some(hash) = &user.password_hash {
             assert!(!hash.is_empty(), "Password hash should not be empty");
         }
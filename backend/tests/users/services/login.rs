@@ -24,7 +24,7 @@ async fn test_login_success() {
         password: password.clone(),
     };
 
-    let login_result = login_user(&mut conn, login_user_data).await.unwrap();
+    let login_result = login_user(&mut conn, login_user_data).await.unwrap().into_login_result().unwrap();
 
     // Verify login result
     assert_eq!(login_result.user.id, registered_user.id);
@@ -134,7 +134,7 @@ async fn test_session_validation() {
         password: password.clone(),
     };
 
-    let login_result = login_user(&mut conn, login_user_data).await.unwrap();
+    let login_result = login_user(&mut conn, login_user_data).await.unwrap().into_login_result().unwrap();
 
     // Test valid session token
     let validated_user = validate_session(&mut conn, &login_result.refresh_token).await.unwrap();
@@ -187,7 +187,7 @@ async fn test_logout() {
         password: password.clone(),
     };
 
-    let login_result = login_user(&mut conn, login_user_data).await.unwrap();
+    let login_result = login_user(&mut conn, login_user_data).await.unwrap().into_login_result().unwrap();
 
     // Logout the user
     logout_user(&mut conn, &login_result.refresh_token).await.unwrap();
@@ -227,7 +227,7 @@ async fn test_session_refresh() {
         password: password.clone(),
     };
 
-    let login_result = login_user(&mut conn, login_user_data).await.unwrap();
+    let login_result = login_user(&mut conn, login_user_data).await.unwrap().into_login_result().unwrap();
 
     // Refresh the session for 24 more hours
     let refreshed_token = refresh_session(&mut conn, &login_result.refresh_token, 24).await.unwrap();
@@ -281,7 +281,7 @@ async fn test_login_case_insensitive_email() {
         password: password.clone(),
     };
 
-    let login_result = login_user(&mut conn, login_user_data).await.unwrap();
+    let login_result = login_user(&mut conn, login_user_data).await.unwrap().into_login_result().unwrap();
     assert_eq!(login_result.user.email, email); // Should return the original case
 }
 
@@ -302,7 +302,7 @@ async fn test_cleanup_expired_sessions() {
         password: password.clone(),
     };
 
-    let login_result = login_user(&mut conn, login_user_data).await.unwrap();
+    let login_result = login_user(&mut conn, login_user_data).await.unwrap().into_login_result().unwrap();
 
     // Manually expire the session by updating expires_at to past time
     let expired_time = Utc::now() - Duration::hours(1);
@@ -3,4 +3,6 @@ pub mod authentication;
 pub mod user_management;
 pub mod password_hashing;
 pub mod user_workspace_registration;
-pub mod login;
\ No newline at end of file
+pub mod login;
+pub mod password_reset;
+
@@ -0,0 +1,252 @@
+use async_trait::async_trait;
+use buildscale::{
+    Config,
+    error::{Error, Result},
+    models::users::{LoginUser, ResetPasswordRequest, UserTokenPurpose},
+    services::mailer::{Email, Mailer},
+    services::users::{
+        deliver_user_token, get_user_active_sessions, login_user, register_user,
+        request_email_verification, request_password_reset, reset_password, verify_email,
+    },
+};
+use std::sync::Mutex;
+use crate::common::database::TestApp;
+
+/// Keeps the mail it is given instead of sending it
+#[derive(Default)]
+struct RecordingMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+#[async_trait]
+impl Mailer for RecordingMailer {
+    async fn send(&self, email: &Email) -> Result<()> {
+        self.sent.lock().unwrap().push(email.clone());
+        Ok(())
+    }
+}
+
+const NEW_PASSWORD: &str = "BrandNewSecurePass456!";
+
+fn reset_request(token: &str) -> ResetPasswordRequest {
+    ResetPasswordRequest {
+        token: token.to_string(),
+        password: NEW_PASSWORD.to_string(),
+        confirm_password: NEW_PASSWORD.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_password_reset_flow() {
+    let test_app = TestApp::new("test_password_reset_flow").await;
+    let mut conn = test_app.get_connection().await;
+
+    let register_user_data = test_app.generate_test_user();
+    let email = register_user_data.email.clone();
+    let old_password = register_user_data.password.clone();
+    let user = register_user(&mut conn, register_user_data).await.unwrap();
+
+    // Existing session that must not survive the reset
+    login_user(&mut conn, LoginUser { email: email.clone(), password: old_password.clone() })
+        .await
+        .unwrap();
+    assert_eq!(get_user_active_sessions(&mut conn, user.id).await.unwrap().len(), 1);
+
+    let issued = request_password_reset(&mut conn, &email).await.unwrap().unwrap();
+    assert_eq!(issued.user.id, user.id);
+    assert_eq!(issued.token.len(), 64);
+
+    let reset_user = reset_password(&mut conn, reset_request(&issued.token)).await.unwrap();
+    assert_eq!(reset_user.id, user.id);
+    assert!(reset_user.email_verified_at.is_some(), "Reset proves mailbox ownership");
+
+    // All sessions revoked
+    assert!(get_user_active_sessions(&mut conn, user.id).await.unwrap().is_empty());
+
+    // Old password rejected, new password accepted
+    let old_login = login_user(&mut conn, LoginUser { email: email.clone(), password: old_password }).await;
+    assert!(matches!(old_login, Err(Error::Authentication(_))));
+    login_user(&mut conn, LoginUser { email, password: NEW_PASSWORD.to_string() })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_password_reset_token_is_single_use() {
+    let test_app = TestApp::new("test_password_reset_token_is_single_use").await;
+    let mut conn = test_app.get_connection().await;
+
+    let register_user_data = test_app.generate_test_user();
+    let email = register_user_data.email.clone();
+    register_user(&mut conn, register_user_data).await.unwrap();
+
+    let issued = request_password_reset(&mut conn, &email).await.unwrap().unwrap();
+    reset_password(&mut conn, reset_request(&issued.token)).await.unwrap();
+
+    let second = reset_password(&mut conn, reset_request(&issued.token)).await;
+    assert!(matches!(second, Err(Error::InvalidToken(_))));
+}
+
+#[tokio::test]
+async fn test_password_reset_unknown_email_returns_none() {
+    let test_app = TestApp::new("test_password_reset_unknown_email_returns_none").await;
+    let mut conn = test_app.get_connection().await;
+
+    let result = request_password_reset(&mut conn, &test_app.generate_test_email()).await.unwrap();
+    assert!(result.is_none());
+}
+
+#[tokio::test]
+async fn test_password_reset_new_request_invalidates_previous_token() {
+    let test_app = TestApp::new("test_password_reset_new_request_invalidates_previous_token").await;
+    let mut conn = test_app.get_connection().await;
+
+    let register_user_data = test_app.generate_test_user();
+    let email = register_user_data.email.clone();
+    register_user(&mut conn, register_user_data).await.unwrap();
+
+    let first = request_password_reset(&mut conn, &email).await.unwrap().unwrap();
+    let second = request_password_reset(&mut conn, &email).await.unwrap().unwrap();
+    assert_ne!(first.token, second.token);
+
+    let stale = reset_password(&mut conn, reset_request(&first.token)).await;
+    assert!(matches!(stale, Err(Error::InvalidToken(_))));
+    reset_password(&mut conn, reset_request(&second.token)).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_password_reset_mails_are_limited_per_address() {
+    let test_app = TestApp::new("test_password_reset_mails_are_limited_per_address").await;
+    let mut conn = test_app.get_connection().await;
+
+    let register_user_data = test_app.generate_test_user();
+    let email = register_user_data.email.clone();
+    register_user(&mut conn, register_user_data).await.unwrap();
+
+    let limit = Config::load().unwrap().auth.password_reset_mails_per_hour;
+    for _ in 0..limit {
+        assert!(request_password_reset(&mut conn, &email).await.unwrap().is_some());
+    }
+    assert!(request_password_reset(&mut conn, &email).await.unwrap().is_none());
+    assert!(request_password_reset(&mut conn, &email.to_uppercase()).await.unwrap().is_none());
+
+    // Other addresses are not affected
+    let other = test_app.generate_test_user();
+    let other_email = other.email.clone();
+    register_user(&mut conn, other).await.unwrap();
+    assert!(request_password_reset(&mut conn, &other_email).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_password_reset_expired_token_rejected() {
+    let test_app = TestApp::new("test_password_reset_expired_token_rejected").await;
+    let mut conn = test_app.get_connection().await;
+
+    let register_user_data = test_app.generate_test_user();
+    let email = register_user_data.email.clone();
+    let user = register_user(&mut conn, register_user_data).await.unwrap();
+
+    let issued = request_password_reset(&mut conn, &email).await.unwrap().unwrap();
+
+    sqlx::query("UPDATE user_tokens SET expires_at = NOW() - INTERVAL '1 minute' WHERE user_id = $1")
+        .bind(user.id)
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+    let result = reset_password(&mut conn, reset_request(&issued.token)).await;
+    assert!(matches!(result, Err(Error::InvalidToken(_))));
+}
+
+#[tokio::test]
+async fn test_password_reset_rejects_weak_or_mismatched_password() {
+    let test_app = TestApp::new("test_password_reset_rejects_weak_or_mismatched_password").await;
+    let mut conn = test_app.get_connection().await;
+
+    let register_user_data = test_app.generate_test_user();
+    let email = register_user_data.email.clone();
+    register_user(&mut conn, register_user_data).await.unwrap();
+
+    let issued = request_password_reset(&mut conn, &email).await.unwrap().unwrap();
+
+    let mismatch = reset_password(&mut conn, ResetPasswordRequest {
+        token: issued.token.clone(),
+        password: NEW_PASSWORD.to_string(),
+        confirm_password: "SomethingElse123!".to_string(),
+    }).await;
+    assert!(matches!(mismatch, Err(Error::Validation(_))));
+
+    let weak = reset_password(&mut conn, ResetPasswordRequest {
+        token: issued.token.clone(),
+        password: "short".to_string(),
+        confirm_password: "short".to_string(),
+    }).await;
+    assert!(matches!(weak, Err(Error::Validation(_))));
+
+    // Validation failures must not burn the token
+    reset_password(&mut conn, reset_request(&issued.token)).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_email_verification_flow() {
+    let test_app = TestApp::new("test_email_verification_flow").await;
+    let mut conn = test_app.get_connection().await;
+
+    let user = register_user(&mut conn, test_app.generate_test_user()).await.unwrap();
+    assert!(user.email_verified_at.is_none());
+
+    let issued = request_email_verification(&mut conn, user.id).await.unwrap();
+    let verified = verify_email(&mut conn, &issued.token).await.unwrap();
+    assert_eq!(verified.id, user.id);
+    assert!(verified.email_verified_at.is_some());
+
+    // Token is single-use and already-verified users cannot request another one
+    assert!(matches!(verify_email(&mut conn, &issued.token).await, Err(Error::InvalidToken(_))));
+    assert!(matches!(request_email_verification(&mut conn, user.id).await, Err(Error::Conflict(_))));
+}
+
+#[tokio::test]
+async fn test_verification_token_cannot_reset_password() {
+    let test_app = TestApp::new("test_verification_token_cannot_reset_password").await;
+    let mut conn = test_app.get_connection().await;
+
+    let user = register_user(&mut conn, test_app.generate_test_user()).await.unwrap();
+    let issued = request_email_verification(&mut conn, user.id).await.unwrap();
+
+    let result = reset_password(&mut conn, reset_request(&issued.token)).await;
+    assert!(matches!(result, Err(Error::InvalidToken(_))));
+}
+
+#[tokio::test]
+async fn test_verify_email_malformed_token() {
+    let test_app = TestApp::new("test_verify_email_malformed_token").await;
+    let mut conn = test_app.get_connection().await;
+
+    let result = verify_email(&mut conn, "not-a-token").await;
+    assert!(matches!(result, Err(Error::InvalidToken(_))));
+}
+
+#[tokio::test]
+async fn test_password_reset_link_is_mailed_to_the_user() {
+    let test_app = TestApp::new("test_password_reset_link_is_mailed_to_the_user").await;
+    let mut conn = test_app.get_connection().await;
+
+    let register_user_data = test_app.generate_test_user();
+    let email = register_user_data.email.clone();
+    register_user(&mut conn, register_user_data).await.unwrap();
+    let issued = request_password_reset(&mut conn, &email).await.unwrap().unwrap();
+
+    let mut config = Config::default();
+    config.auth.public_base_url = "https://app.example.com/".to_string();
+    let mailer = RecordingMailer::default();
+    deliver_user_token(&mailer, &config, UserTokenPurpose::PasswordReset, &issued).await;
+
+    let sent = mailer.sent.lock().unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to, email);
+    assert!(
+        sent[0].body.contains(&format!("https://app.example.com/reset-password?token={}", issued.token)),
+        "{}",
+        sent[0].body
+    );
+}
@@ -150,7 +150,7 @@ async fn test_update_password_success() {
         password: new_password.to_string(),
     };
 
-    let login_result = buildscale::services::users::login_user(&mut conn, login_data).await.unwrap();
+    let login_result = buildscale::services::users::login_user(&mut conn, login_data).await.unwrap().into_login_result().unwrap();
     assert_eq!(login_result.user.id, registered_user.id);
 
     // Verify the old password no longer works
@@ -208,7 +208,7 @@ async fn test_get_session_info_success() {
         password,
     };
 
-    let login_result = buildscale::services::users::login_user(&mut conn, login_data).await.unwrap();
+    let login_result = buildscale::services::users::login_user(&mut conn, login_data).await.unwrap().into_login_result().unwrap();
 
     // Get session info
     let session_info = get_session_info(&mut conn, &login_result.refresh_token).await.unwrap();
@@ -317,7 +317,7 @@ async fn test_get_user_active_sessions() {
             password: password.clone(),
         };
 
-        let login_result = buildscale::services::users::login_user(&mut conn, login_data).await.unwrap();
+        let login_result = buildscale::services::users::login_user(&mut conn, login_data).await.unwrap().into_login_result().unwrap();
         session_tokens.push(login_result.refresh_token);
     }
 
@@ -352,7 +352,7 @@ async fn test_revoke_all_user_sessions() {
             password: password.clone(),
         };
 
-        let login_result = buildscale::services::users::login_user(&mut conn, login_data).await.unwrap();
+        let login_result = buildscale::services::users::login_user(&mut conn, login_data).await.unwrap().into_login_result().unwrap();
         session_tokens.push(login_result.refresh_token);
     }
 
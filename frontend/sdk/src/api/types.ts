@@ -518,6 +518,7 @@ export interface ContextSummary {
   utilization_percent: number
   model: string
   token_limit: number
+  tokenizer: 'o200k_base' | 'cl100k_base'
   breakdown: TokenBreakdown
 }
 
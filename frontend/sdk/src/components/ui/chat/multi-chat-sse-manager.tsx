@@ -90,6 +90,7 @@ export function MultiChatSSEManagerProvider({
   const connectionStatesRef = React.useRef<Map<string, SSEConnectionState>>(new Map())
   const accessOrderRef = React.useRef<string[]>([]) // For LRU eviction
   const connectionIdCounterRef = React.useRef(0)
+  const lastEventIdsRef = React.useRef<Map<string, string>>(new Map()) // For resuming after a dropped connection
   const apiClientRef = React.useRef<any>(null)
 
   // Set up health check interval
@@ -193,10 +194,15 @@ export function MultiChatSSEManagerProvider({
           throw new Error('API client not set. Make sure ChatProvider is initialized.')
         }
 
+        // Resume after the last event received, so events emitted while disconnected are replayed
+        const lastEventId = lastEventIdsRef.current.get(chatId)
         const response = await apiClientRef.current.requestRaw(
           `/workspaces/${workspaceId}/chats/${chatId}/events`,
           {
-            headers: { Accept: 'text/event-stream' },
+            headers: {
+              Accept: 'text/event-stream',
+              ...(lastEventId ? { 'Last-Event-ID': lastEventId } : {}),
+            },
             signal: controller.signal,
             timeout: false,
           }
@@ -276,15 +282,21 @@ export function MultiChatSSEManagerProvider({
                 const parts = line.split('\n')
                 let eventType = 'chunk'
                 let dataStr = ''
+                let eventId = ''
 
                 for (const p of parts) {
                   if (p.startsWith('event: ')) {
                     eventType = p.slice(7).trim()
                   } else if (p.startsWith('data: ')) {
                     dataStr = p.slice(6).trim()
+                  } else if (p.startsWith('id: ')) {
+                    eventId = p.slice(4).trim()
                   }
                 }
 
+                if (eventId) {
+                  lastEventIdsRef.current.set(chatId, eventId)
+                }
                 if (!dataStr) continue
 
                 try {
@@ -378,6 +390,7 @@ export function MultiChatSSEManagerProvider({
   const disconnectChat = React.useCallback((chatId: string) => {
     disconnectChatInternal(chatId)
     subscribersRef.current.delete(chatId)
+    lastEventIdsRef.current.delete(chatId)
     accessOrderRef.current = accessOrderRef.current.filter((id) => id !== chatId)
   }, [disconnectChatInternal])
 
@@ -387,6 +400,7 @@ export function MultiChatSSEManagerProvider({
     }
     connectionsRef.current.clear()
     subscribersRef.current.clear()
+    lastEventIdsRef.current.clear()
     accessOrderRef.current = []
   }, [disconnectChatInternal])
 